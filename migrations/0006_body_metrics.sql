@@ -0,0 +1,10 @@
+-- Body metrics (smart scale / manual weight log)
+-- One reading per date; importers and manual entry upsert by date.
+
+CREATE TABLE IF NOT EXISTS body_metrics (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    date            DATE NOT NULL UNIQUE,
+    weight_kg       REAL CHECK (weight_kg IS NULL OR weight_kg > 0),
+    body_fat_pct    REAL CHECK (body_fat_pct IS NULL OR (body_fat_pct > 0 AND body_fat_pct < 100)),
+    source          TEXT NOT NULL DEFAULT 'manual'
+);
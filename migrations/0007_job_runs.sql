@@ -0,0 +1,14 @@
+-- Background job run history
+-- One row per job execution; finished_at/status are filled in when the run completes.
+
+CREATE TABLE IF NOT EXISTS job_runs (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    job          TEXT NOT NULL,
+    started_at   DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at  DATETIME,
+    status       TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'ok', 'error')),
+    detail       TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_job_started_at
+    ON job_runs(job, started_at);
@@ -0,0 +1,21 @@
+-- Yearly archive tables for append-only telemetry
+-- The telemetry_archive job moves rows older than the retention window into
+-- <table>_archive_<year> tables (created on demand). While a move is in progress the job
+-- holds a row in archive_guard, which is the only way the append-only delete trigger
+-- lets rows leave the hot table.
+
+CREATE TABLE IF NOT EXISTS archive_guard (
+    table_name  TEXT PRIMARY KEY
+);
+
+DROP TRIGGER IF EXISTS personalization_friction_events_no_delete;
+
+CREATE TRIGGER IF NOT EXISTS personalization_friction_events_no_delete
+BEFORE DELETE ON personalization_friction_events
+FOR EACH ROW
+WHEN NOT EXISTS (
+    SELECT 1 FROM archive_guard WHERE table_name = 'personalization_friction_events'
+)
+BEGIN
+    SELECT RAISE(ABORT, 'personalization_friction_events is append-only');
+END;
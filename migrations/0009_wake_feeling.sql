@@ -0,0 +1,40 @@
+-- Morning grogginess: how the user felt on waking and how long it took to feel alert.
+-- Both are optional per session; the daily view averages the sessions that report them.
+
+ALTER TABLE sleep_metrics ADD COLUMN wake_feeling INTEGER CHECK (wake_feeling BETWEEN 1 AND 5);
+ALTER TABLE sleep_metrics ADD COLUMN sleep_inertia_min INTEGER CHECK (sleep_inertia_min >= 0);
+
+DROP VIEW IF EXISTS v_daily_sleep;
+CREATE VIEW v_daily_sleep AS
+SELECT
+    MIN(base.id) AS id,
+    base.wake_date,
+    time(MIN(base.bed_dt)) AS bed_time,
+    time(MAX(base.wake_dt)) AS wake_time,
+    CAST(AVG(base.latency_min) AS INTEGER) AS latency_min,
+    SUM(base.awakenings) AS awakenings,
+    CAST(AVG(base.quality) AS INTEGER) AS quality,
+    SUM(base.duration_min) AS duration_min,
+    COUNT(*) AS session_count,
+    CAST(AVG(base.wake_feeling) AS INTEGER) AS wake_feeling,
+    CAST(AVG(base.sleep_inertia_min) AS INTEGER) AS sleep_inertia_min
+FROM (
+    SELECT
+        s.id,
+        COALESCE(s.session_date, s.date) AS wake_date,
+        CASE
+            WHEN s.bed_time > s.wake_time
+                THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+            ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+        END AS bed_dt,
+        datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+        m.latency_min,
+        m.awakenings,
+        m.quality,
+        m.duration_min,
+        m.wake_feeling,
+        m.sleep_inertia_min
+    FROM sleep_sessions s
+    JOIN sleep_metrics m ON m.session_id = s.id
+) base
+GROUP BY base.wake_date;
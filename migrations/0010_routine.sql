@@ -0,0 +1,11 @@
+-- Pre-sleep routine tracking
+-- The checklist itself lives in app_settings under 'routine_checklist' (JSON).
+-- Each recorded evening stores one row per checklist item; `date` is the evening the
+-- routine applies to, so outcomes come from the sleep that wakes on date + 1 day.
+
+CREATE TABLE IF NOT EXISTS routine_entries (
+    date     DATE NOT NULL,
+    item_id  TEXT NOT NULL,
+    done     INTEGER NOT NULL CHECK (done IN (0, 1)),
+    PRIMARY KEY (date, item_id)
+);
@@ -0,0 +1,11 @@
+-- Sleep aids used per session (earplugs, mask, white noise, melatonin, ...)
+-- Aid names are normalized to lowercase by the API.
+
+CREATE TABLE IF NOT EXISTS sleep_aids (
+    session_id  INTEGER NOT NULL REFERENCES sleep_sessions(id) ON DELETE CASCADE,
+    aid         TEXT NOT NULL,
+    PRIMARY KEY (session_id, aid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sleep_aids_aid
+    ON sleep_aids(aid);
@@ -0,0 +1,13 @@
+-- External sleep disturbances (noise, partner, pet, child), logged separately from the
+-- user's own sleep metrics. `date` is the wake date of the affected night, matching
+-- v_daily_sleep.wake_date; `time` is local clock time of the event.
+
+CREATE TABLE IF NOT EXISTS disturbances (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    date            DATE NOT NULL,
+    time            TEXT NOT NULL,
+    kind            TEXT NOT NULL CHECK (kind IN ('noise', 'partner', 'pet', 'child')),
+    duration_min    INTEGER NOT NULL CHECK (duration_min BETWEEN 0 AND 720)
+);
+
+CREATE INDEX IF NOT EXISTS idx_disturbances_date ON disturbances(date);
@@ -0,0 +1,10 @@
+-- Self-experiments: named date ranges (e.g. "no caffeine month") whose nights are compared
+-- against a baseline. end_date NULL means the experiment is still running.
+
+CREATE TABLE IF NOT EXISTS experiments (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    name        TEXT NOT NULL,
+    start_date  DATE NOT NULL,
+    end_date    DATE CHECK (end_date IS NULL OR end_date >= start_date),
+    description TEXT
+);
@@ -0,0 +1,32 @@
+-- Audit log for destructive operations (append-only)
+-- One row per deleted entity, with the optional reason code and note supplied by the client.
+-- Rows only leave the table through the telemetry_archive job (see archive_guard).
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    recorded_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    action      TEXT NOT NULL,
+    entity      TEXT NOT NULL,
+    entity_id   INTEGER,
+    reason      TEXT,
+    note        TEXT
+);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+BEFORE UPDATE ON audit_log
+FOR EACH ROW
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+BEFORE DELETE ON audit_log
+FOR EACH ROW
+WHEN NOT EXISTS (
+    SELECT 1 FROM archive_guard WHERE table_name = 'audit_log'
+)
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_recorded_at ON audit_log(recorded_at);
@@ -0,0 +1,6 @@
+-- Indexes for filtered, cursor-paginated audit log reads (GET /api/admin/audit)
+-- Pages are ordered by id DESC, so each filter column is paired with id to let SQLite
+-- walk the index in order and stop at the page limit instead of scanning and sorting.
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity_action_id ON audit_log(entity, action, id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action_id ON audit_log(action, id);
@@ -0,0 +1,20 @@
+-- Allow instance-defined exercise intensity levels (validated by the application against the
+-- `intensity_levels` setting). SQLite cannot drop a CHECK constraint, so rebuild the table.
+CREATE TABLE exercise_events_new (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    date            DATE NOT NULL,
+    intensity       TEXT NOT NULL CHECK (length(intensity) BETWEEN 1 AND 32),
+    start_time      TIME,
+    duration_min    INTEGER
+);
+
+INSERT INTO exercise_events_new(id, date, intensity, start_time, duration_min)
+SELECT id, date, intensity, start_time, duration_min FROM exercise_events;
+
+DROP TABLE exercise_events;
+ALTER TABLE exercise_events_new RENAME TO exercise_events;
+
+-- Recreate the "daily intensity" sentinel index from 0002
+CREATE UNIQUE INDEX IF NOT EXISTS daily_exercise_unique
+  ON exercise_events(date)
+  WHERE start_time IS NULL AND duration_min IS NULL;
@@ -0,0 +1,14 @@
+-- History of fired alert rules (rules themselves live in app_settings under 'alert_rules').
+-- One row per rule and evaluated wake date, so re-running the nightly evaluation never
+-- delivers the same alert twice. delivered_via is NULL until delivery has been attempted.
+
+CREATE TABLE IF NOT EXISTS alert_events (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id       TEXT NOT NULL,
+    as_of         DATE NOT NULL,
+    value         REAL NOT NULL,
+    message       TEXT NOT NULL,
+    delivered_via TEXT,
+    fired_at      DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (rule_id, as_of)
+);
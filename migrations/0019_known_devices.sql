@@ -0,0 +1,12 @@
+-- Devices that have logged in, identified by a SHA-256 of user agent and network prefix
+-- (see security::device). Raw user agents and addresses are never stored; label is a coarse
+-- "browser on OS" description for telling devices apart.
+
+CREATE TABLE IF NOT EXISTS known_devices (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    fingerprint   TEXT NOT NULL UNIQUE,
+    label         TEXT NOT NULL,
+    first_seen_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    login_count   INTEGER NOT NULL DEFAULT 1
+);
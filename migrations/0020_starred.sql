@@ -0,0 +1,8 @@
+-- Starred sleep sessions and notes (POST/DELETE /api/sleep/{id}/star, /api/note/{id}/star),
+-- listed together by GET /api/starred. Stars are few, so only starred rows are indexed.
+
+ALTER TABLE sleep_sessions ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE notes ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_sleep_sessions_starred ON sleep_sessions(id) WHERE starred = 1;
+CREATE INDEX IF NOT EXISTS idx_notes_starred ON notes(id) WHERE starred = 1;
@@ -0,0 +1,19 @@
+-- Links from recorded sessions and exercise to their ids in external services (a Health Auto
+-- Export workout id, or whatever id a Tasker relay forwards: Fitbit log, Strava activity, Oura
+-- period). (source, external_id) is unique so re-synced entries are recognized; url is an
+-- optional deep link back to the source. Exactly one of the target columns is set.
+
+CREATE TABLE IF NOT EXISTS external_refs (
+    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+    source           TEXT NOT NULL,
+    external_id      TEXT NOT NULL,
+    url              TEXT,
+    sleep_session_id INTEGER REFERENCES sleep_sessions(id) ON DELETE CASCADE,
+    exercise_id      INTEGER REFERENCES exercise_events(id) ON DELETE CASCADE,
+    created_at       DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (source, external_id),
+    CHECK ((sleep_session_id IS NULL) <> (exercise_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_external_refs_sleep ON external_refs(sleep_session_id);
+CREATE INDEX IF NOT EXISTS idx_external_refs_exercise ON external_refs(exercise_id);
@@ -0,0 +1,13 @@
+-- Bearer tokens for scripts and widgets (GET/POST /api/tokens). Only the SHA-256 of the secret
+-- is stored; scope is "read", "write:sleep", or "admin" (see models::api_token). Every token
+-- expires; last_used_at is updated on each authenticated request.
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    name         TEXT NOT NULL,
+    token_hash   TEXT NOT NULL UNIQUE,
+    scope        TEXT NOT NULL,
+    created_at   DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at   DATETIME NOT NULL,
+    last_used_at DATETIME
+);
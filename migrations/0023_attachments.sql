@@ -0,0 +1,19 @@
+-- Files attached to a day (photos, audio memos), uploaded with POST /api/attachments. The
+-- bytes live under ATTACHMENT_DIR as <id> (and <id>.thumb.jpg when a thumbnail was made);
+-- this table holds the metadata extracted at upload time.
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    date         DATE NOT NULL,
+    filename     TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes   INTEGER NOT NULL,
+    sha256       TEXT NOT NULL,
+    width        INTEGER,
+    height       INTEGER,
+    duration_ms  INTEGER,
+    has_thumb    INTEGER NOT NULL DEFAULT 0,
+    created_at   DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_date ON attachments(date);
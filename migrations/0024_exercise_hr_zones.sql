@@ -0,0 +1,8 @@
+-- Minutes an exercise session spent in each heart-rate zone (Z1 easiest .. Z5 maximal), as
+-- reported by Strava or Garmin. All five are NULL when the session has no heart-rate data.
+
+ALTER TABLE exercise_events ADD COLUMN hr_z1_min INTEGER CHECK (hr_z1_min >= 0);
+ALTER TABLE exercise_events ADD COLUMN hr_z2_min INTEGER CHECK (hr_z2_min >= 0);
+ALTER TABLE exercise_events ADD COLUMN hr_z3_min INTEGER CHECK (hr_z3_min >= 0);
+ALTER TABLE exercise_events ADD COLUMN hr_z4_min INTEGER CHECK (hr_z4_min >= 0);
+ALTER TABLE exercise_events ADD COLUMN hr_z5_min INTEGER CHECK (hr_z5_min >= 0);
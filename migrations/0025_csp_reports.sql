@@ -0,0 +1,17 @@
+-- Content-Security-Policy violation reports sent by browsers to POST /api/csp-report
+-- (enabled by CSP_REPORT_URI). Query strings and fragments are stripped from URIs before
+-- storing; only the newest reports are kept (see sleep_api::csp_reports).
+
+CREATE TABLE IF NOT EXISTS csp_reports (
+    id                 INTEGER PRIMARY KEY AUTOINCREMENT,
+    received_at        DATETIME NOT NULL,
+    document_uri       TEXT NOT NULL,
+    violated_directive TEXT NOT NULL,
+    blocked_uri        TEXT NOT NULL,
+    source_file        TEXT,
+    line_number        INTEGER,
+    disposition        TEXT NOT NULL,
+    sample             TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_csp_reports_received_at ON csp_reports(received_at);
@@ -0,0 +1,13 @@
+-- Read-only links to a date range (POST /api/share-links, GET /api/shared/{secret}). Only the
+-- SHA-256 of the secret is stored. Every link expires; last_used_at is updated on each view.
+-- What a link reveals is governed by the "share" redaction policy (app_settings 'redaction').
+
+CREATE TABLE IF NOT EXISTS share_links (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_hash   TEXT NOT NULL UNIQUE,
+    from_date    DATE NOT NULL,
+    to_date      DATE NOT NULL,
+    created_at   DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at   DATETIME NOT NULL,
+    last_used_at DATETIME
+);
@@ -0,0 +1,11 @@
+-- Failed login attempts for the lockout in security::lockout. One row per key: "account:<email>"
+-- (lowercased) or "ip:<address>". failures counts towards the next lock; lockouts counts the
+-- locks so far, which doubles each lock's duration. Rows are deleted on a successful login.
+
+CREATE TABLE IF NOT EXISTS login_failures (
+    key             TEXT PRIMARY KEY,
+    failures        INTEGER NOT NULL DEFAULT 0,
+    lockouts        INTEGER NOT NULL DEFAULT 0,
+    locked_until    DATETIME,
+    last_failure_at DATETIME NOT NULL
+);
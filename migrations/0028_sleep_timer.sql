@@ -0,0 +1,8 @@
+-- The running sleep timer (POST /api/sleep/start .. POST /api/sleep/stop). At most one row:
+-- started_at is the UTC instant the timer was started. Stopping it creates a sleep session and
+-- deletes the row.
+
+CREATE TABLE IF NOT EXISTS sleep_timer (
+    id         INTEGER PRIMARY KEY CHECK (id = 1),
+    started_at DATETIME NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Sampled friction telemetry: with TELEMETRY_SUCCESS_SAMPLE_RATE=N only 1 in N successful
+-- submissions is stored, with sample_weight = N (errors are always stored with weight 1).
+-- Aggregates weight every row by sample_weight so counts and rates stay unbiased.
+
+ALTER TABLE personalization_friction_events
+    ADD COLUMN sample_weight INTEGER NOT NULL DEFAULT 1 CHECK (sample_weight >= 1);
@@ -0,0 +1,19 @@
+-- The sleep goal (GET/PUT /api/goals), moved out of the `sleep_goal` app_settings key. At most
+-- one row; without it the default goal (23:00, 8 hours) applies.
+
+CREATE TABLE IF NOT EXISTS goals (
+    id                  INTEGER PRIMARY KEY CHECK (id = 1),
+    target_bedtime      TEXT NOT NULL,
+    target_duration_min INTEGER NOT NULL CHECK (target_duration_min BETWEEN 180 AND 720),
+    updated_at          DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO goals (id, target_bedtime, target_duration_min)
+SELECT 1, json_extract(value, '$.target_bedtime'), json_extract(value, '$.target_duration_min')
+FROM app_settings
+WHERE key = 'sleep_goal'
+  AND json_valid(value)
+  AND json_extract(value, '$.target_bedtime') IS NOT NULL
+  AND json_extract(value, '$.target_duration_min') BETWEEN 180 AND 720;
+
+DELETE FROM app_settings WHERE key = 'sleep_goal';
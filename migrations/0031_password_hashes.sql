@@ -0,0 +1,10 @@
+-- Password hashes upgraded to the current hashing policy on login (see
+-- sleep_api::security::password). source_hash is the configured ADMIN_PASSWORD_HASH the row
+-- replaced; the row is only used while that configured hash is unchanged.
+
+CREATE TABLE IF NOT EXISTS password_hashes (
+    account     TEXT PRIMARY KEY,
+    hash        TEXT NOT NULL,
+    source_hash TEXT NOT NULL,
+    updated_at  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,26 @@
+-- Login email changes (POST /api/account/email, confirmed via a link sent through the
+-- notification channels).
+--
+-- email_changes holds one row per request; only the SHA-256 of the confirmation secret is
+-- stored. source_email is the login email when the change was requested, so a request made
+-- before another change can no longer be confirmed.
+--
+-- account_email overrides ADMIN_EMAIL once a change is confirmed. source_email is the
+-- ADMIN_EMAIL it replaced; the override is ignored when ADMIN_EMAIL is changed.
+
+CREATE TABLE IF NOT EXISTS email_changes (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_hash   TEXT NOT NULL UNIQUE,
+    new_email    TEXT NOT NULL,
+    source_email TEXT NOT NULL,
+    created_at   DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at   DATETIME NOT NULL,
+    confirmed_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS account_email (
+    id           INTEGER PRIMARY KEY CHECK (id = 1),
+    email        TEXT NOT NULL,
+    source_email TEXT NOT NULL,
+    updated_at   DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,17 @@
+-- Share link use limits and access analytics (GET /api/share-links/{id}/stats).
+--
+-- use_count counts successful views; a link with max_uses stops working once use_count reaches
+-- it. share_link_visitors keeps one row per link and client address, so a link opened from
+-- unexpected places (a leak) shows up and can be revoked.
+
+ALTER TABLE share_links ADD COLUMN use_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE share_links ADD COLUMN max_uses INTEGER CHECK (max_uses IS NULL OR max_uses >= 1);
+
+CREATE TABLE IF NOT EXISTS share_link_visitors (
+    link_id       INTEGER NOT NULL REFERENCES share_links(id) ON DELETE CASCADE,
+    ip            TEXT NOT NULL,
+    use_count     INTEGER NOT NULL DEFAULT 1,
+    first_used_at DATETIME NOT NULL,
+    last_used_at  DATETIME NOT NULL,
+    PRIMARY KEY (link_id, ip)
+);
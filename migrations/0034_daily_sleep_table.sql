@@ -0,0 +1,338 @@
+-- Materialize the per-wake-date aggregate behind v_daily_sleep.
+--
+-- Recent, range and trends queries read v_daily_sleep on every request; as a plain view it
+-- re-joined sleep_sessions with sleep_metrics and grouped every night each time. daily_sleep
+-- now holds one row per wake date and v_daily_sleep selects from it, so readers are unchanged.
+--
+-- Triggers keep the table in step with every write to sleep_sessions and sleep_metrics
+-- (repository writes, imports, schema-change backfills, salvage), recomputing only the wake
+-- dates a row moved from or to. The aggregate is the one of 0013_daily_longest_segment.sql;
+-- change both the triggers and repository::rebuild_daily_sleep when it changes.
+
+CREATE INDEX IF NOT EXISTS idx_sleep_sessions_wake_date
+    ON sleep_sessions(COALESCE(session_date, date));
+
+CREATE TABLE IF NOT EXISTS daily_sleep (
+    wake_date           DATE PRIMARY KEY NOT NULL,
+    id                  INTEGER NOT NULL,
+    bed_time            TIME NOT NULL,
+    wake_time           TIME NOT NULL,
+    latency_min         INTEGER,
+    awakenings          INTEGER,
+    quality             INTEGER,
+    duration_min        INTEGER,
+    session_count       INTEGER NOT NULL,
+    wake_feeling        INTEGER,
+    sleep_inertia_min   INTEGER,
+    longest_segment_min INTEGER
+);
+
+-- Backfill from the view before it is replaced.
+INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+    session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+SELECT id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+    session_count, wake_feeling, sleep_inertia_min, longest_segment_min
+FROM v_daily_sleep;
+
+DROP VIEW IF EXISTS v_daily_sleep;
+CREATE VIEW v_daily_sleep AS
+SELECT id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+    session_count, wake_feeling, sleep_inertia_min, longest_segment_min
+FROM daily_sleep;
+
+CREATE TRIGGER daily_sleep_metrics_insert
+AFTER INSERT ON sleep_metrics
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+CREATE TRIGGER daily_sleep_metrics_update
+AFTER UPDATE ON sleep_metrics
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+-- Once the session is gone (cascade) the lookup is NULL; the session trigger recomputes.
+CREATE TRIGGER daily_sleep_metrics_delete
+AFTER DELETE ON sleep_metrics
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = OLD.session_id);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = OLD.session_id)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+-- Metrics usually follow their session; this covers rows copied in the other order.
+CREATE TRIGGER daily_sleep_sessions_insert
+AFTER INSERT ON sleep_sessions
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(NEW.session_date, NEW.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(NEW.session_date, NEW.date)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+CREATE TRIGGER daily_sleep_sessions_update
+AFTER UPDATE OF date, session_date, bed_time, wake_time ON sleep_sessions
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(OLD.session_date, OLD.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(OLD.session_date, OLD.date)
+    ) base
+    GROUP BY base.wake_date;
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(NEW.session_date, NEW.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(NEW.session_date, NEW.date)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+CREATE TRIGGER daily_sleep_sessions_delete
+AFTER DELETE ON sleep_sessions
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(OLD.session_date, OLD.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(OLD.session_date, OLD.date)
+    ) base
+    GROUP BY base.wake_date;
+END;
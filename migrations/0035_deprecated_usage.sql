@@ -0,0 +1,9 @@
+-- Calls to legacy or aliased endpoints, one row per endpoint (see sleep_api::deprecation).
+-- GET /api/admin/deprecations reports them so compatibility shims can be removed once unused.
+
+CREATE TABLE IF NOT EXISTS deprecated_usage (
+    endpoint   TEXT PRIMARY KEY,
+    calls      INTEGER NOT NULL DEFAULT 0,
+    first_seen DATETIME NOT NULL,
+    last_seen  DATETIME NOT NULL
+);
@@ -0,0 +1,28 @@
+-- Free-form tags on sleep sessions and notes (caffeine, travel, sick, ...).
+--
+-- Tag names are normalized to lowercase by the API and stored once in `tags`; the link
+-- tables attach them to sessions and notes. GET /api/sleep/range?tag= filters by them and
+-- GET /api/trends/tags compares nights with and without each tag.
+
+CREATE TABLE IF NOT EXISTS tags (
+    id    INTEGER PRIMARY KEY AUTOINCREMENT,
+    name  TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS sleep_session_tags (
+    session_id  INTEGER NOT NULL REFERENCES sleep_sessions(id) ON DELETE CASCADE,
+    tag_id      INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (session_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sleep_session_tags_tag
+    ON sleep_session_tags(tag_id);
+
+CREATE TABLE IF NOT EXISTS note_tags (
+    note_id  INTEGER NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    tag_id   INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (note_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_tags_tag
+    ON note_tags(tag_id);
@@ -0,0 +1,23 @@
+-- Medications (melatonin, prescription sleep aids, ...) and their per-night intake.
+--
+-- `medications` is the user's list; `medication_events` logs each intake against the wake
+-- date of the night it was taken for. GET /api/trends/summary overlays the number of nights
+-- each medication was taken per bucket.
+
+CREATE TABLE IF NOT EXISTS medications (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    name          TEXT NOT NULL UNIQUE,
+    dose          TEXT NULL,
+    prescription  INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS medication_events (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    medication_id  INTEGER NOT NULL REFERENCES medications(id) ON DELETE CASCADE,
+    date           TEXT NOT NULL,
+    time           TEXT NULL,
+    dose           TEXT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_medication_events_date
+    ON medication_events(date);
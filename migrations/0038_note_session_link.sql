@@ -0,0 +1,7 @@
+-- Notes linked to a specific sleep session (NoteInput.session_id), listed with the session by
+-- GET /api/sleep/{id}. A linked note follows the session's wake date when the session is
+-- edited; deleting the session keeps the note on its date and clears the link.
+
+ALTER TABLE notes ADD COLUMN session_id INTEGER NULL REFERENCES sleep_sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_notes_session_id ON notes(session_id) WHERE session_id IS NOT NULL;
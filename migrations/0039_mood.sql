@@ -0,0 +1,9 @@
+-- Daytime mood / energy rating, one per day (POST /api/mood).
+--
+-- `date` is the day rated, i.e. the wake date of the night before it. GET /api/trends/summary
+-- averages it per bucket next to the sleep metrics.
+
+CREATE TABLE IF NOT EXISTS mood_entries (
+    date  TEXT PRIMARY KEY,
+    mood  INTEGER NOT NULL CHECK (mood BETWEEN 1 AND 5)
+);
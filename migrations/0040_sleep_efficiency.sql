@@ -0,0 +1,409 @@
+-- Wake after sleep onset (WASO) and sleep efficiency.
+--
+-- waso_min is reported minutes awake between falling asleep and the final wake. efficiency is
+-- the share of time in bed spent asleep, (duration_min - latency_min - waso_min) / duration_min
+-- rounded to 3 decimals, computed on every write (sleep_core::time::sleep_efficiency); a
+-- missing waso_min counts as 0. Rows without a positive duration have no efficiency.
+--
+-- daily_sleep sums waso_min over a wake date's sessions and weights efficiency by time in bed.
+-- The triggers of 0034_daily_sleep_table.sql are replaced with ones carrying both columns;
+-- change them together with repository::DAILY_SLEEP_AGGREGATE.
+
+ALTER TABLE sleep_metrics ADD COLUMN waso_min INTEGER CHECK (waso_min >= 0);
+ALTER TABLE sleep_metrics ADD COLUMN efficiency REAL CHECK (efficiency BETWEEN 0 AND 1);
+
+ALTER TABLE daily_sleep ADD COLUMN waso_min INTEGER;
+ALTER TABLE daily_sleep ADD COLUMN efficiency REAL;
+
+DROP VIEW IF EXISTS v_daily_sleep;
+CREATE VIEW v_daily_sleep AS
+SELECT id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+    session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency
+FROM daily_sleep;
+
+DROP TRIGGER IF EXISTS daily_sleep_metrics_insert;
+DROP TRIGGER IF EXISTS daily_sleep_metrics_update;
+DROP TRIGGER IF EXISTS daily_sleep_metrics_delete;
+DROP TRIGGER IF EXISTS daily_sleep_sessions_insert;
+DROP TRIGGER IF EXISTS daily_sleep_sessions_update;
+DROP TRIGGER IF EXISTS daily_sleep_sessions_delete;
+
+-- Backfilled before the triggers are back; daily_sleep is rebuilt at the end.
+UPDATE sleep_metrics
+SET efficiency = ROUND(MAX(duration_min - latency_min, 0) * 1.0 / duration_min, 3)
+WHERE duration_min > 0;
+
+CREATE TRIGGER daily_sleep_metrics_insert
+AFTER INSERT ON sleep_metrics
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+CREATE TRIGGER daily_sleep_metrics_update
+AFTER UPDATE ON sleep_metrics
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = NEW.session_id)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+-- Once the session is gone (cascade) the lookup is NULL; the session trigger recomputes.
+CREATE TRIGGER daily_sleep_metrics_delete
+AFTER DELETE ON sleep_metrics
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = OLD.session_id);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = (SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = OLD.session_id)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+-- Metrics usually follow their session; this covers rows copied in the other order.
+CREATE TRIGGER daily_sleep_sessions_insert
+AFTER INSERT ON sleep_sessions
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(NEW.session_date, NEW.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(NEW.session_date, NEW.date)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+CREATE TRIGGER daily_sleep_sessions_update
+AFTER UPDATE OF date, session_date, bed_time, wake_time ON sleep_sessions
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(OLD.session_date, OLD.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(OLD.session_date, OLD.date)
+    ) base
+    GROUP BY base.wake_date;
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(NEW.session_date, NEW.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(NEW.session_date, NEW.date)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+CREATE TRIGGER daily_sleep_sessions_delete
+AFTER DELETE ON sleep_sessions
+FOR EACH ROW
+BEGIN
+    DELETE FROM daily_sleep WHERE wake_date = COALESCE(OLD.session_date, OLD.date);
+    INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+        session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+    SELECT
+        MIN(base.id),
+        base.wake_date,
+        time(MIN(base.bed_dt)),
+        time(MAX(base.wake_dt)),
+        CAST(AVG(base.latency_min) AS INTEGER),
+        SUM(base.awakenings),
+        CAST(AVG(base.quality) AS INTEGER),
+        SUM(base.duration_min),
+        COUNT(*),
+        CAST(AVG(base.wake_feeling) AS INTEGER),
+        CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+        MAX(base.duration_min),
+        SUM(base.waso_min),
+        ROUND(SUM(base.efficiency * base.duration_min)
+            / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+    FROM (
+        SELECT
+            s.id,
+            COALESCE(s.session_date, s.date) AS wake_date,
+            CASE
+                WHEN s.bed_time > s.wake_time
+                    THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+                ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+            END AS bed_dt,
+            datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+            m.latency_min,
+            m.awakenings,
+            m.quality,
+            m.duration_min,
+            m.wake_feeling,
+            m.sleep_inertia_min,
+            m.waso_min,
+            m.efficiency
+        FROM sleep_sessions s
+        JOIN sleep_metrics m ON m.session_id = s.id
+        WHERE COALESCE(s.session_date, s.date) = COALESCE(OLD.session_date, OLD.date)
+    ) base
+    GROUP BY base.wake_date;
+END;
+
+-- Recompute every wake date with the new columns.
+DELETE FROM daily_sleep;
+INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings, quality, duration_min,
+    session_count, wake_feeling, sleep_inertia_min, longest_segment_min, waso_min, efficiency)
+SELECT
+    MIN(base.id),
+    base.wake_date,
+    time(MIN(base.bed_dt)),
+    time(MAX(base.wake_dt)),
+    CAST(AVG(base.latency_min) AS INTEGER),
+    SUM(base.awakenings),
+    CAST(AVG(base.quality) AS INTEGER),
+    SUM(base.duration_min),
+    COUNT(*),
+    CAST(AVG(base.wake_feeling) AS INTEGER),
+    CAST(AVG(base.sleep_inertia_min) AS INTEGER),
+    MAX(base.duration_min),
+    SUM(base.waso_min),
+    ROUND(SUM(base.efficiency * base.duration_min)
+        / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3)
+FROM (
+    SELECT
+        s.id,
+        COALESCE(s.session_date, s.date) AS wake_date,
+        CASE
+            WHEN s.bed_time > s.wake_time
+                THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+            ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+        END AS bed_dt,
+        datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+        m.latency_min,
+        m.awakenings,
+        m.quality,
+        m.duration_min,
+        m.wake_feeling,
+        m.sleep_inertia_min,
+        m.waso_min,
+        m.efficiency
+    FROM sleep_sessions s
+    JOIN sleep_metrics m ON m.session_id = s.id
+) base
+GROUP BY base.wake_date;
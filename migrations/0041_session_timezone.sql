@@ -0,0 +1,7 @@
+-- Per-session timezone (SleepInput.timezone).
+--
+-- IANA name of the timezone the night was spent in, e.g. `America/New_York`. When set, the
+-- session's duration is computed in it instead of the user timezone; NULL keeps the old
+-- behaviour.
+
+ALTER TABLE sleep_sessions ADD COLUMN timezone TEXT;
@@ -0,0 +1,35 @@
+# English strings for report endpoints and insights.
+# Numbers arrive preformatted; keep placeables as-is.
+
+duration-hm = { $hours }h { $minutes }m
+duration-m = { $minutes }m
+duration-total-m = { $minutes } min
+unit-hours = h
+unit-minutes = min
+
+now-past-bedtime = { $past } past your target bedtime; head to bed now.
+now-debt-bed-now = Sleep debt is { $debt }; head to bed now to catch up.
+now-debt-go-early = Sleep debt is { $debt }; aim for bed { $early } early, in { $until }.
+now-wind-down = Bedtime in { $until }; start winding down.
+now-on-track = On track: bedtime in { $until }.
+
+context-duration = Your { $avg } { $unit } average is { $position ->
+        [below] below
+        [above] above
+       *[within] within
+    } the typical { $low }–{ $high } { $unit } range for ages { $bracket }.
+context-latency = Your { $avg } min average time to fall asleep is { $position ->
+        [below] below
+        [above] above
+       *[within] within
+    } the typical { $low }–{ $high } min range.
+
+compare-few-nights = { $label } has only { $nights } logged nights; deltas may not be meaningful
+compare-periods-overlap = The periods overlap; nights in both count on each side
+
+feed-title = SleepTracker weekly reports
+feed-report-title = Week { $week } ({ $from } – { $to })
+feed-report-nights = Nights logged: { $nights }
+feed-report-duration = Average sleep: { $avg }
+feed-report-quality = Average quality: { $avg } / 5
+feed-report-delta = { $sign }{ $delta } average sleep vs. the previous week
@@ -0,0 +1,35 @@
+# 日本語: レポート・インサイト用の文言。
+# 数値は整形済みで渡されるため、プレースホルダーはそのまま使う。
+
+duration-hm = { $hours }時間{ $minutes }分
+duration-m = { $minutes }分
+duration-total-m = { $minutes }分
+unit-hours = 時間
+unit-minutes = 分
+
+now-past-bedtime = 目標の就寝時刻を{ $past }過ぎています。今すぐ寝ましょう。
+now-debt-bed-now = 睡眠負債は{ $debt }です。今すぐ寝て取り戻しましょう。
+now-debt-go-early = 睡眠負債は{ $debt }です。いつもより{ $early }早く、{ $until }後の就寝を目指しましょう。
+now-wind-down = 就寝まであと{ $until }です。そろそろ寝る準備を始めましょう。
+now-on-track = 順調です。就寝まであと{ $until }。
+
+context-duration = 平均睡眠時間{ $avg }{ $unit }は、{ $bracket }歳の一般的な範囲（{ $low }〜{ $high }{ $unit }）{ $position ->
+        [below] を下回っています
+        [above] を上回っています
+       *[within] の範囲内です
+    }。
+context-latency = 平均入眠時間{ $avg }分は、一般的な範囲（{ $low }〜{ $high }分）{ $position ->
+        [below] を下回っています
+        [above] を上回っています
+       *[within] の範囲内です
+    }。
+
+compare-few-nights = { $label }の記録は{ $nights }夜分のみのため、差分は参考程度です
+compare-periods-overlap = 2つの期間が重なっています。重複する夜は両方に含まれます
+
+feed-title = SleepTracker 週間レポート
+feed-report-title = { $week }（{ $from }〜{ $to }）
+feed-report-nights = 記録した夜: { $nights }
+feed-report-duration = 平均睡眠時間: { $avg }
+feed-report-quality = 平均睡眠の質: { $avg } / 5
+feed-report-delta = 平均睡眠時間は前週比 { $sign }{ $delta }
@@ -0,0 +1,195 @@
+#![doc = r#"Read-only analytics queries
+
+Backs `POST /api/admin/query`, which runs ad-hoc `SELECT` statements against the live
+database and returns the rows as JSON. The feature is off unless `ADMIN_QUERY_ENABLED`
+is set (see [`config::admin_query_enabled`]).
+
+Statements are sandboxed in layers:
+- [`validate_select`] accepts a single statement that starts with `SELECT` or `WITH`.
+- [`run_read_only`] wraps the statement in `SELECT * FROM (...)` and executes it on a pooled
+  connection switched to `PRAGMA query_only`, so SQLite itself rejects any write that slips
+  past validation.
+- A progress handler interrupts the statement once the time limit elapses, and at most
+  `max_rows` rows are returned.
+
+[`config::admin_query_enabled`]: crate::config::admin_query_enabled
+"#]
+
+use crate::{db::Db, domain::DomainError};
+use base64::{Engine as _, engine::general_purpose};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{Column, Executor, Row, TypeInfo, ValueRef, sqlite::SqliteRow};
+use std::time::{Duration, Instant};
+
+/// Longest statement accepted by [`validate_select`], in bytes.
+pub const MAX_SQL_LEN: usize = 10_000;
+
+#[doc = r#"Request body for `POST /api/admin/query`."#]
+#[derive(Deserialize, Debug, JsonSchema)]
+pub struct QueryRequest {
+    pub sql: String,
+}
+
+#[doc = r#"Result of a read-only query.
+
+- `columns`: column names in select order.
+- `rows`: one array per row; INTEGER/REAL map to numbers, TEXT to strings, BLOB to base64
+  strings, and NULL to `null`.
+- `truncated`: `true` when more rows were available than the row limit.
+"#]
+#[derive(Serialize, Debug, JsonSchema)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub truncated: bool,
+}
+
+#[doc = r#"Validate that `sql` is a single read-only statement and return it without
+surrounding whitespace or trailing semicolons.
+
+Semicolons are rejected anywhere but at the end, including inside string literals; use
+`char(59)` when a literal semicolon is needed.
+
+# Example
+
+```rust
+# use sleep_api::admin_query::validate_select;
+assert_eq!(validate_select("  select 1; ").unwrap(), "select 1");
+assert!(validate_select("DELETE FROM sleep_sessions").is_err());
+assert!(validate_select("SELECT 1; DROP TABLE sleep_sessions").is_err());
+```
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when the statement is empty, too long, contains more
+than one statement, or does not start with `SELECT`/`WITH`.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+pub fn validate_select(sql: &str) -> Result<&str, DomainError> {
+    if sql.len() > MAX_SQL_LEN {
+        return Err(DomainError::InvalidInput(format!(
+            "sql must be at most {MAX_SQL_LEN} bytes"
+        )));
+    }
+    let stmt = sql
+        .trim()
+        .trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+    if stmt.is_empty() {
+        return Err(DomainError::InvalidInput("sql is required".into()));
+    }
+    if stmt.contains(';') {
+        return Err(DomainError::InvalidInput(
+            "only a single statement is allowed".into(),
+        ));
+    }
+    let keyword: String = stmt
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if keyword != "SELECT" && keyword != "WITH" {
+        return Err(DomainError::InvalidInput(
+            "only SELECT statements are allowed".into(),
+        ));
+    }
+    Ok(stmt)
+}
+
+#[doc = r#"Run a validated statement on a read-only connection.
+
+At most `max_rows` rows are collected; the statement is interrupted once `timeout` elapses.
+The connection is restored (`query_only` off, progress handler removed) before it returns to
+the pool; if that fails the connection is closed instead.
+
+# Errors
+
+Returns [`sqlx::Error`] when the statement fails, including SQLite's `interrupted` error
+when the time limit is hit and `attempt to write a readonly database` for writes.
+"#]
+pub async fn run_read_only(
+    db: &Db,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<QueryResult, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    sqlx::query("PRAGMA query_only = ON")
+        .execute(&mut *conn)
+        .await?;
+    let deadline = Instant::now() + timeout;
+    conn.lock_handle()
+        .await?
+        .set_progress_handler(1_000, move || Instant::now() < deadline);
+
+    let result = collect_rows(&mut conn, sql, max_rows).await;
+
+    let restored = async {
+        conn.lock_handle().await?.remove_progress_handler();
+        sqlx::query("PRAGMA query_only = OFF")
+            .execute(&mut *conn)
+            .await
+    }
+    .await;
+    if restored.is_err() {
+        let _ = conn.close().await;
+    }
+    result
+}
+
+async fn collect_rows(
+    conn: &mut sqlx::SqliteConnection,
+    sql: &str,
+    max_rows: usize,
+) -> Result<QueryResult, sqlx::Error> {
+    // Wrapping the statement as a subquery keeps it a pure SELECT and lets SQLite apply the limit.
+    let wrapped = format!("SELECT * FROM ({sql}) LIMIT ?");
+    let fetched = sqlx::query(&wrapped)
+        .bind(max_rows as i64 + 1)
+        .fetch_all(&mut *conn)
+        .await?;
+    let columns = match fetched.first() {
+        Some(row) => row.columns().iter().map(|c| c.name().to_string()).collect(),
+        None => conn
+            .describe(&wrapped)
+            .await?
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect(),
+    };
+    let truncated = fetched.len() > max_rows;
+    let rows = fetched
+        .iter()
+        .take(max_rows)
+        .map(row_to_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+fn row_to_json(row: &SqliteRow) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let mut out = Vec::with_capacity(row.len());
+    for i in 0..row.len() {
+        let raw = row.try_get_raw(i)?;
+        if raw.is_null() {
+            out.push(serde_json::Value::Null);
+            continue;
+        }
+        // The value's type info reflects its storage class, not the declared column type.
+        let value = match raw.type_info().name() {
+            "INTEGER" => serde_json::Value::from(row.try_get_unchecked::<i64, _>(i)?),
+            "REAL" => serde_json::Value::from(row.try_get_unchecked::<f64, _>(i)?),
+            "BLOB" => serde_json::Value::from(
+                general_purpose::STANDARD.encode(row.try_get_unchecked::<Vec<u8>, _>(i)?),
+            ),
+            _ => serde_json::Value::from(row.try_get_unchecked::<String, _>(i)?),
+        };
+        out.push(value);
+    }
+    Ok(out)
+}
@@ -12,20 +12,45 @@ For an end-to-end server setup example, see [`router`].
 "#]
 
 use crate::auth::{self, LoginPayload, current_user_from_cookie};
-use crate::middleware::auth_layer::RequireSessionJson;
+use crate::middleware::auth_layer::{RequireSessionCookie, RequireSessionJson};
+use crate::middleware::stack::MiddlewareConfig;
+use crate::reload::SessionKey;
 use crate::security::csrf::{CsrfGuard, issue_csrf_cookie};
+use crate::security::device::{ClientIp, DeviceFingerprint};
+use crate::security::lockout::{self, LoginAttempt};
+use crate::security::password;
+use crate::security::signature;
 use crate::{
+    completeness, dashboard,
     db::Db,
+    deprecation::DeprecationCounters,
     error::ApiError,
-    handlers,
-    models::{ExerciseInput, FrictionTelemetryInput, NoteInput, SleepInput},
+    events::EventBus,
+    extract::{DateRange, ValidPath},
+    features::{Features, VersionInfo},
+    feeds,
+    handlers::{self, EDIT_WINDOW_OVERRIDE, EditLock, TimeContext},
+    i18n::{DurationUnit, Rounding, Units, duration_hours},
+    importers::{IngestSource, MappingImportRequest, WeightSource},
+    integrity::{IntegrityCheck, IntegrityState},
+    models::{
+        AlertHistoryQuery, AlertRules, ApiTokenInput, AttachmentUpload, AuditQuery, AuditReason,
+        BodyMetricInput, DayBoundary, DisturbanceInput, ExerciseInput, ExperimentInput,
+        FrictionTelemetryInput, IntensityLevels, MedicationEventInput, MedicationInput, MoodInput,
+        NoteInput, PublicSummarySettings, RedactionSettings, RoutineChecklist, RoutineInput,
+        ShareLinkInput, SleepGoal, SleepInput, SleepListItem, SleepPatch, SleepTimerStop,
+        TimeRounding,
+    },
+    negotiate::ResponseFormat,
+    now, plan, public, reports,
+    time::SharedClock,
     trends,
 };
 use axum::http::StatusCode;
 use axum::response::{Html, IntoResponse, Redirect};
 use axum::{
     Json, Router,
-    extract::{Form, Path, State},
+    extract::{Form, State},
     routing::{get, post},
 };
 use axum_extra::extract::cookie::{Cookie, Key, PrivateCookieJar, SameSite};
@@ -36,23 +61,142 @@ use serde_json::json;
 Routes:
 - `GET /api/health`
 - `HEAD /api/health`
+- `GET /api/version`
 - `POST /api/login`
 - `POST /api/login.json`
 - `POST /api/logout`
 - `GET /api/session`
+- `GET /api/account/devices`
+- `DELETE /api/account/devices/{id}`
+- `POST /api/account/email`
+- `GET /api/account/email/confirm/{secret}` (no auth; the secret is the credential)
+- `GET /api/tokens`
+- `POST /api/tokens`
+- `DELETE /api/tokens/{id}`
+- `GET /api/share-links`
+- `POST /api/share-links`
+- `DELETE /api/share-links/{id}`
+- `GET /api/shares/{id}/stats`
+- `GET /api/shared/{secret}` (no auth; the secret is the credential)
 - `GET /api/settings/timezone`
 - `POST /api/settings/timezone`
+- `GET /api/settings/routine`
+- `POST /api/settings/routine`
+- `GET /api/settings/sleep-goal`
+- `POST /api/settings/sleep-goal`
+- `GET /api/goals`
+- `PUT /api/goals`
+- `GET /api/settings/day-boundary`
+- `POST /api/settings/day-boundary`
+- `GET /api/settings/public-summary`
+- `POST /api/settings/public-summary`
+- `GET /api/settings/redaction`
+- `POST /api/settings/redaction`
+- `GET /api/settings/alerts`
+- `PUT /api/settings/alerts`
+- `GET /api/alerts/history`
+- `GET /api/settings/locale`
+- `POST /api/settings/locale`
+- `GET /api/settings/units`
+- `POST /api/settings/units`
+- `GET /api/settings/time-rounding`
+- `POST /api/settings/time-rounding`
 - `POST /api/sleep`
 - `GET /api/sleep/date/{date}`
 - `PUT /api/sleep/{id}`
+- `PATCH /api/sleep/{id}`
 - `DELETE /api/sleep/{id}`
+- `POST /api/sleep/{id}/star`
+- `DELETE /api/sleep/{id}/star`
+- `GET /api/exercise`
 - `POST /api/exercise`
+- `PUT /api/exercise/{id}`
+- `DELETE /api/exercise/{id}`
+- `GET /api/exercise/zones`
+- `GET /api/notes`
 - `POST /api/note`
-- `POST /api/personalization/friction-telemetry`
-- `GET /api/personalization/friction-backlog`
+- `PUT /api/note/{id}`
+- `DELETE /api/note/{id}`
+- `POST /api/note/{id}/star`
+- `DELETE /api/note/{id}/star`
+- `GET /api/starred`
+- `GET /api/export?format=json|csv&from=&to=&anonymize=` (streamed)
+- `GET /api/attachments?date=`
+- `POST /api/attachments?date=&filename=`
+- `GET /api/attachment/{id}` (also `HEAD`; resumable with `Range`)
+- `GET /api/attachment/{id}/thumb`
+- `GET /api/routine/{date}`
+- `POST /api/routine/{date}`
+- `GET /api/mood`
+- `POST /api/mood`
+- `GET /api/body-metrics`
+- `POST /api/body-metrics`
+- `PUT /api/body-metrics/{id}`
+- `DELETE /api/body-metrics/{id}`
+- `POST /api/body-metrics/import/{source}`
+- `POST /api/ingest/{source}` (feature `webhooks`)
+- `POST /api/import/mapping-preview`
+- `POST /api/import/with-mapping`
+- `POST /api/import/sleep` (`multipart/form-data`, `?dry_run=true`)
+- `GET /api/disturbances`
+- `POST /api/disturbances`
+- `PUT /api/disturbances/{id}`
+- `DELETE /api/disturbances/{id}`
+- `GET /api/medications`
+- `POST /api/medications`
+- `PUT /api/medications/{id}`
+- `DELETE /api/medications/{id}`
+- `GET /api/medication-events`
+- `POST /api/medication-events`
+- `PUT /api/medication-events/{id}`
+- `DELETE /api/medication-events/{id}`
+- `GET /api/experiments`
+- `POST /api/experiments`
+- `PUT /api/experiments/{id}`
+- `DELETE /api/experiments/{id}`
+- `GET /api/experiments/{id}/results`
+- `POST /api/personalization/friction-telemetry` (feature `telemetry`)
+- `GET /api/personalization/friction-backlog` (feature `telemetry`)
 - `GET /api/trends/sleep-bars`
 - `GET /api/trends/summary`
 - `GET /api/trends/personalization`
+- `GET /api/trends/routine`
+- `GET /api/trends/aids`
+- `GET /api/trends/tags`
+- `GET /api/trends/awakenings`
+- `GET /api/trends/correlation`
+- `GET /api/trends/compare`
+- `GET /api/trends/period-compare`
+- `GET /api/trends/decompose`
+- `GET /api/trends/context`
+- `GET /api/trends/sleep-debt`
+- `GET /api/now`
+- `GET /api/now/bedtime-status`
+- `GET /api/now/today`
+- `GET /api/dashboard`
+- `GET /api/reports/weekly`
+- `GET /api/plan/week`
+- `GET /api/public/summary` (no auth; when enabled in settings)
+- `GET /api/feeds/reports.xml` (API token as bearer or `?token=`)
+- `GET /api/stats/completeness`
+- `GET /api/schema/{type}`
+- `GET /api/admin/schema`
+- `POST /api/admin/query`
+- `GET /api/admin/jobs`
+- `GET /api/admin/integrity`
+- `GET /api/admin/daily-sleep/verify`
+- `GET /api/admin/audit`
+- `POST /api/admin/jobs/{name}/run`
+- `GET /api/admin/schema-changes`
+- `POST /api/admin/schema-changes/{name}/switch`
+- `GET /api/admin/backups`
+- `POST /api/admin/backups`
+- `GET /api/admin/backups/{name}/{file}` (also `HEAD`; resumable with `Range`)
+- `POST /api/admin/compare-export`
+- `GET /api/admin/deprecations`
+- `GET /api/admin/metrics`
+
+Routes marked with a feature are only registered when it is enabled (see [`crate::features`]).
 
 # Example
 
@@ -76,9 +220,15 @@ let app = sleep_api::app::router(db);
 
 Holds shared components that extractors rely on:
 - [`Db`] — SQLx pool
-- [`Key`] — cookie crypto key for [`PrivateCookieJar`]
+- [`SessionKey`] — cookie crypto key for [`PrivateCookieJar`], following config reloads
+- [`EventBus`] — domain events emitted by mutations
+- [`SharedClock`] — current time (frozen in tests and demo instances)
+- [`Features`] — feature flags read at startup; disabled subsystems are not routed
+- [`IntegrityState`] — last database integrity check; writes are refused while it found corruption
+- [`DeprecationCounters`] — calls to legacy endpoints, buffered before they are stored
 
-Implements `FromRef` for `Db` and `Key` so handlers can extract them via `State<Db>` and extractors like `PrivateCookieJar`.
+Implements `FromRef` for `Db`, `Key` (the current [`SessionKey`]), `EventBus`, `SharedClock`, `Features`, `IntegrityState` and `DeprecationCounters` so handlers can extract them via `State<Db>` and extractors like `PrivateCookieJar`.
+`State<TimeContext>` yields a [`TimeContext`] read from the clock at extraction time.
 
 # Example
 
@@ -86,18 +236,70 @@ Implements `FromRef` for `Db` and `Key` so handlers can extract them via `State<
 # use axum::Router;
 # use axum_extra::extract::cookie::Key;
 # async fn demo(db: sleep_api::db::Db) {
-let state = sleep_api::app::AppState { db, key: sleep_api::config::session_key() };
+let state = sleep_api::app::AppState {
+    db,
+    key: sleep_api::config::session_key().into(),
+    events: sleep_api::events::EventBus::new(),
+    clock: sleep_api::config::clock(),
+    features: sleep_api::config::features(),
+    integrity: Default::default(),
+    deprecations: Default::default(),
+};
 let app: Router<sleep_api::app::AppState> = Router::new().with_state(state);
 # }
 ```
 
 [`Db`]: crate::db::Db
-[`Key`]: axum_extra::extract::cookie::Key
+[`SessionKey`]: crate::reload::SessionKey
+[`EventBus`]: crate::events::EventBus
+[`SharedClock`]: crate::time::SharedClock
+[`Features`]: crate::features::Features
+[`IntegrityState`]: crate::integrity::IntegrityState
+[`DeprecationCounters`]: crate::deprecation::DeprecationCounters
+[`TimeContext`]: crate::handlers::TimeContext
 [`PrivateCookieJar`]: axum_extra::extract::cookie::PrivateCookieJar
 "#]
 pub struct AppState {
     pub db: Db,
-    pub key: Key,
+    pub key: SessionKey,
+    pub events: EventBus,
+    pub clock: SharedClock,
+    pub features: Features,
+    pub integrity: IntegrityState,
+    pub deprecations: DeprecationCounters,
+}
+
+impl AppState {
+    /// State with the configured clock and features, a fresh event bus, and no integrity check yet.
+    pub fn new(db: Db, key: impl Into<SessionKey>) -> Self {
+        AppState {
+            db,
+            key: key.into(),
+            events: EventBus::new(),
+            clock: crate::config::clock(),
+            features: crate::config::features(),
+            integrity: IntegrityState::default(),
+            deprecations: DeprecationCounters::default(),
+        }
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Features {
+    fn from_ref(s: &AppState) -> Features {
+        s.features
+    }
+}
+
+impl axum::extract::FromRef<AppState> for IntegrityState {
+    fn from_ref(s: &AppState) -> IntegrityState {
+        s.integrity.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for DeprecationCounters {
+    fn from_ref(s: &AppState) -> DeprecationCounters {
+        s.deprecations.clone()
+    }
 }
 
 impl axum::extract::FromRef<AppState> for Db {
@@ -106,62 +308,448 @@ impl axum::extract::FromRef<AppState> for Db {
     }
 }
 
+impl axum::extract::FromRef<AppState> for EventBus {
+    fn from_ref(s: &AppState) -> EventBus {
+        s.events.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for SharedClock {
+    fn from_ref(s: &AppState) -> SharedClock {
+        s.clock.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for TimeContext {
+    fn from_ref(s: &AppState) -> TimeContext {
+        TimeContext::from_clock(&*s.clock)
+    }
+}
+
+#[doc = r#"Extracts the request's [`EditLock`]: the configured no-edit window, lifted when the
+request carries `X-Admin-Override: edit-window`, and the `sessions_per_day` quota set by the
+quota middleware."#]
+impl axum::extract::FromRequestParts<AppState> for EditLock {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let overridden = parts
+            .headers
+            .get("x-admin-override")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(EDIT_WINDOW_OVERRIDE));
+        let mut lock = if overridden {
+            EditLock::none()
+        } else {
+            let time = TimeContext::from_clock(&*state.clock);
+            EditLock::for_window(&state.db, &time, crate::config::edit_window_days()).await
+        };
+        lock.sessions_per_day = parts
+            .extensions
+            .get::<crate::middleware::quota::SessionsPerDay>()
+            .map(|limit| limit.0);
+        Ok(lock)
+    }
+}
+
+#[doc = r#"Extracts the optional [`AuditReason`] body of a destructive request.
+
+An empty body means no reason was given; otherwise the body must be JSON. Malformed bodies,
+invalid reason codes, and a missing `reason` while `AUDIT_REASON_REQUIRED` is set are all
+rejected with 400."#]
+pub struct AuditBody(pub AuditReason);
+
+impl<S: Send + Sync> axum::extract::FromRequest<S> for AuditBody {
+    type Rejection = ApiError;
+
+    async fn from_request(req: axum::extract::Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = axum::body::Bytes::from_request(req, state)
+            .await
+            .map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+        let reason = if body.iter().all(u8::is_ascii_whitespace) {
+            AuditReason::default()
+        } else {
+            serde_json::from_slice::<AuditReason>(&body)
+                .map_err(|e| ApiError::InvalidInput(format!("invalid audit reason body: {e}")))?
+        };
+        reason.validate()?;
+        if reason.reason.is_none() && crate::config::audit_reason_required() {
+            return Err(ApiError::InvalidInput(
+                "reason is required for this operation".into(),
+            ));
+        }
+        Ok(AuditBody(reason))
+    }
+}
+
 impl axum::extract::FromRef<AppState> for Key {
     fn from_ref(s: &AppState) -> Key {
-        s.key.clone()
+        s.key.current()
     }
 }
 
+// Library entry points; the server binary builds its `AppState` itself.
+#[allow(dead_code)]
 pub fn router(db: Db) -> Router {
-    let key: Key = crate::config::session_key();
-    let enable_hsts = crate::config::hsts_enabled();
+    router_with_key(db, SessionKey::from_config())
+}
 
-    let state = AppState {
-        db,
-        key: key.clone(),
-    };
-    let router = Router::new()
-        .route("/", get(root))
-        .route("/api/health", get(health_get).head(health_head))
-        .route("/api/login", post(post_login))
-        .route("/api/login.json", post(post_login_json))
-        .route("/api/logout", post(post_logout))
-        .route("/api/session", get(api_session))
-        .route(
-            "/api/settings/timezone",
-            get(get_settings_timezone).post(post_settings_timezone),
-        )
-        .route("/api/sleep", post(create_sleep))
-        .route("/api/sleep/date/{date}", get(get_sleep))
-        // Register methods for /api/sleep/{id} explicitly to avoid any chaining ambiguity
-        .route("/api/sleep/{id}", get(get_sleep_by_id))
-        .route("/api/sleep/{id}", axum::routing::put(update_sleep))
-        .route("/api/sleep/{id}", axum::routing::delete(delete_sleep))
-        .route("/api/sleep/recent", get(get_sleep_recent))
-        .route("/api/sleep/range", get(get_sleep_range))
-        .route("/api/exercise", post(create_exercise))
-        .route("/api/exercise/intensity", get(get_exercise_intensity))
-        .route("/api/note", post(create_note))
-        .route(
-            "/api/personalization/friction-telemetry",
-            post(post_friction_telemetry),
-        )
-        .route(
-            "/api/personalization/friction-backlog",
-            get(get_friction_backlog),
-        )
-        .route("/api/trends/sleep-bars", get(trends::sleep_bars))
-        .route("/api/trends/summary", get(trends::summary))
-        .route("/api/trends/personalization", get(trends::personalization));
+#[doc = r#"Build the router with an explicit cookie key (a fixed [`Key`] or a [`SessionKey`]).
+
+Same routes as [`router`], e.g. for embedding the API with a key managed elsewhere.
+
+[`Key`]: axum_extra::extract::cookie::Key
+[`SessionKey`]: crate::reload::SessionKey
+"#]
+#[allow(dead_code)]
+pub fn router_with_key(db: Db, key: impl Into<SessionKey>) -> Router {
+    router_with_state(AppState::new(db, key))
+}
+
+#[doc = r#"Build the router around a prepared [`AppState`], e.g. with a [`FixedClock`].
+
+Applies the server's middleware stack ([`MiddlewareConfig::from_config`]): usage quotas
+([`crate::config::quotas`]) and rate limits ([`crate::config::rate_limits`]) are read here,
+re-read after each config reload, and applied to every route.
+
+[`FixedClock`]: crate::time::FixedClock
+[`MiddlewareConfig::from_config`]: crate::middleware::stack::MiddlewareConfig::from_config
+"#]
+pub fn router_with_state(state: AppState) -> Router {
+    router_with_middleware(state, MiddlewareConfig::from_config())
+}
+
+#[doc = r#"Build the router around a prepared [`AppState`] with a chosen middleware stack.
+
+For embedders that apply some layers themselves or want compression and request tracing; see
+[`crate::middleware::stack`]. `POST /api/csp-report` is mounted when `middleware` has a CSP
+report URI.
+"#]
+pub fn router_with_middleware(state: AppState, middleware: MiddlewareConfig) -> Router {
+    let features = state.features;
+
+    let mut router =
+        Router::new()
+            .route("/", get(root))
+            .route("/api/health", get(health_get).head(health_head))
+            .route("/api/version", get(get_version))
+            .route("/api/login", post(post_login))
+            .route("/api/login.json", post(post_login_json))
+            .route("/api/logout", post(post_logout))
+            .route("/api/session", get(api_session))
+            .route("/api/account/devices", get(get_account_devices))
+            .route("/api/account/email", post(post_account_email))
+            .route(
+                "/api/account/email/confirm/{secret}",
+                get(get_account_email_confirm),
+            )
+            .route(
+                "/api/account/devices/{id}",
+                axum::routing::delete(delete_account_device),
+            )
+            .route("/api/tokens", get(get_api_tokens).post(create_api_token))
+            .route("/api/tokens/{id}", axum::routing::delete(delete_api_token))
+            .route(
+                "/api/share-links",
+                get(get_share_links).post(create_share_link),
+            )
+            .route(
+                "/api/share-links/{id}",
+                axum::routing::delete(delete_share_link),
+            )
+            .route("/api/shares/{id}/stats", get(get_share_link_stats))
+            .route("/api/shared/{secret}", get(get_shared))
+            .route(
+                "/api/settings/timezone",
+                get(get_settings_timezone).post(post_settings_timezone),
+            )
+            .route(
+                "/api/settings/routine",
+                get(get_settings_routine).post(post_settings_routine),
+            )
+            .route(
+                "/api/settings/intensity-levels",
+                get(get_settings_intensity_levels).post(post_settings_intensity_levels),
+            )
+            .route(
+                "/api/settings/sleep-goal",
+                get(get_settings_sleep_goal).post(post_settings_sleep_goal),
+            )
+            .route(
+                "/api/goals",
+                get(get_settings_sleep_goal).put(post_settings_sleep_goal),
+            )
+            .route(
+                "/api/settings/day-boundary",
+                get(get_settings_day_boundary).post(post_settings_day_boundary),
+            )
+            .route(
+                "/api/settings/public-summary",
+                get(get_settings_public_summary).post(post_settings_public_summary),
+            )
+            .route(
+                "/api/settings/redaction",
+                get(get_settings_redaction).post(post_settings_redaction),
+            )
+            .route(
+                "/api/settings/alerts",
+                get(get_settings_alerts).put(put_settings_alerts),
+            )
+            .route("/api/alerts/history", get(get_alert_history))
+            .route(
+                "/api/settings/locale",
+                get(get_settings_locale).post(post_settings_locale),
+            )
+            .route(
+                "/api/settings/units",
+                get(get_settings_units).post(post_settings_units),
+            )
+            .route(
+                "/api/settings/time-rounding",
+                get(get_settings_time_rounding).post(post_settings_time_rounding),
+            )
+            .route("/api/sleep", post(create_sleep))
+            .route("/api/sleep/date/{date}", get(get_sleep))
+            // Register methods for /api/sleep/{id} explicitly to avoid any chaining ambiguity
+            .route("/api/sleep/{id}", get(get_sleep_by_id))
+            .route("/api/sleep/{id}", axum::routing::put(update_sleep))
+            .route("/api/sleep/{id}", axum::routing::patch(patch_sleep))
+            .route("/api/sleep/{id}", axum::routing::delete(delete_sleep))
+            .route("/api/sleep/recent", get(get_sleep_recent))
+            .route("/api/sleep/range", get(get_sleep_range))
+            .route("/api/sleep/missing", get(get_sleep_missing))
+            .route("/api/sleep/start", post(post_sleep_start))
+            .route("/api/sleep/stop", post(post_sleep_stop))
+            .route(
+                "/api/sleep/active",
+                get(get_sleep_active).delete(delete_sleep_active),
+            )
+            .route(
+                "/api/sleep/{id}/star",
+                post(star_sleep).delete(unstar_sleep),
+            )
+            .route("/api/exercise", get(get_exercise).post(create_exercise))
+            .route(
+                "/api/exercise/{id}",
+                axum::routing::put(update_exercise).delete(delete_exercise),
+            )
+            .route("/api/exercise/intensity", get(get_exercise_intensity))
+            .route("/api/exercise/zones", get(get_exercise_zones))
+            .route("/api/notes", get(get_notes))
+            .route("/api/note", post(create_note))
+            .route(
+                "/api/note/{id}",
+                axum::routing::put(update_note).delete(delete_note),
+            )
+            .route("/api/note/{id}/star", post(star_note).delete(unstar_note))
+            .route("/api/starred", get(get_starred))
+            .route("/api/export", get(get_export))
+            .route(
+                "/api/attachments",
+                get(get_attachments).post(post_attachment).layer(
+                    axum::extract::DefaultBodyLimit::max(crate::attachments::MAX_ATTACHMENT_BYTES),
+                ),
+            )
+            .route("/api/attachment/{id}", get(get_attachment_file))
+            .route("/api/attachment/{id}/thumb", get(get_attachment_thumb))
+            .route("/api/routine/{date}", get(get_routine).post(post_routine))
+            .route("/api/mood", get(get_mood).post(post_mood))
+            .route(
+                "/api/body-metrics",
+                get(get_body_metrics).post(create_body_metric),
+            )
+            .route(
+                "/api/body-metrics/{id}",
+                axum::routing::put(update_body_metric).delete(delete_body_metric),
+            )
+            .route(
+                "/api/body-metrics/import/{source}",
+                post(import_body_metrics),
+            )
+            .route("/api/import/mapping-preview", post(post_import_preview))
+            .route("/api/import/with-mapping", post(post_import_with_mapping))
+            .route("/api/import/sleep", post(post_import_sleep))
+            .route(
+                "/api/disturbances",
+                get(get_disturbances).post(create_disturbance),
+            )
+            .route(
+                "/api/disturbances/{id}",
+                axum::routing::put(update_disturbance).delete(delete_disturbance),
+            )
+            .route(
+                "/api/medications",
+                get(get_medications).post(create_medication),
+            )
+            .route(
+                "/api/medications/{id}",
+                axum::routing::put(update_medication).delete(delete_medication),
+            )
+            .route(
+                "/api/medication-events",
+                get(get_medication_events).post(create_medication_event),
+            )
+            .route(
+                "/api/medication-events/{id}",
+                axum::routing::put(update_medication_event).delete(delete_medication_event),
+            )
+            .route(
+                "/api/experiments",
+                get(get_experiments).post(create_experiment),
+            )
+            .route(
+                "/api/experiments/{id}",
+                axum::routing::put(update_experiment).delete(delete_experiment),
+            )
+            .route("/api/experiments/{id}/results", get(get_experiment_results))
+            .route("/api/trends/sleep-bars", get(trends::sleep_bars))
+            .route("/api/trends/summary", get(trends::summary))
+            .route("/api/trends/personalization", get(trends::personalization))
+            .route("/api/trends/routine", get(trends::routine))
+            .route("/api/trends/aids", get(trends::aids))
+            .route("/api/trends/tags", get(trends::tags))
+            .route("/api/trends/awakenings", get(trends::awakenings))
+            .route("/api/trends/correlation", get(trends::correlation))
+            .route("/api/trends/compare", get(trends::compare))
+            .route("/api/trends/period-compare", get(trends::period_compare))
+            .route("/api/trends/decompose", get(trends::decompose))
+            .route("/api/trends/context", get(trends::context))
+            .route("/api/trends/sleep-debt", get(trends::sleep_debt))
+            .route("/api/trends/regularity", get(trends::regularity))
+            .route("/api/trends/histogram", get(trends::histogram))
+            .route("/api/now", get(now::now))
+            .route("/api/now/bedtime-status", get(now::bedtime_status))
+            .route("/api/now/today", get(now::today))
+            .route("/api/dashboard", get(dashboard::dashboard))
+            .route("/api/reports/weekly", get(reports::weekly))
+            .route("/api/plan/week", get(plan::plan_week))
+            .route("/api/public/summary", get(public::summary))
+            .route("/api/feeds/reports.xml", get(feeds::reports))
+            .route("/api/stats/completeness", get(completeness::completeness))
+            .route("/api/schema/{type}", get(get_json_schema))
+            .route("/api/admin/schema", get(get_admin_schema))
+            .route("/api/admin/query", post(post_admin_query))
+            .route("/api/admin/jobs", get(get_admin_jobs))
+            .route("/api/admin/integrity", get(get_admin_integrity))
+            .route(
+                "/api/admin/daily-sleep/verify",
+                get(get_admin_daily_sleep_verify),
+            )
+            .route("/api/admin/audit", get(get_admin_audit))
+            .route("/api/admin/csp-reports", get(get_admin_csp_reports))
+            .route("/api/admin/jobs/{name}/run", post(post_admin_job_run))
+            .route("/api/admin/schema-changes", get(get_admin_schema_changes))
+            .route(
+                "/api/admin/schema-changes/{name}/switch",
+                post(post_admin_schema_change_switch),
+            )
+            .route(
+                "/api/admin/backups",
+                get(get_admin_backups).post(post_admin_backup),
+            )
+            .route(
+                "/api/admin/backups/{name}/{file}",
+                get(get_admin_backup_file),
+            )
+            .route("/api/admin/compare-export", post(post_admin_compare_export))
+            .route("/api/admin/deprecations", get(get_admin_deprecations))
+            .route("/api/admin/metrics", get(get_admin_metrics));
+    if middleware.csp_reporting().report_uri.is_some() {
+        router = router.route(
+            "/api/csp-report",
+            post(post_csp_report).layer(axum::extract::DefaultBodyLimit::max(
+                crate::csp_reports::MAX_REPORT_BYTES,
+            )),
+        );
+    }
+    if features.webhooks {
+        router = router.route("/api/ingest/{source}", post(post_ingest));
+    }
+    if features.telemetry {
+        router = router
+            .route(
+                "/api/personalization/friction-telemetry",
+                post(post_friction_telemetry),
+            )
+            .route(
+                "/api/personalization/friction-backlog",
+                get(get_friction_backlog),
+            );
+    }
+
+    // After every route: the 405 fallback only reaches routes registered before it.
+    let router = router
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed);
 
-    let router = router.with_state(state);
+    let integrity = state.integrity.clone();
+    let router = router
+        .with_state(state.clone())
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::deprecation::track,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            integrity,
+            crate::integrity::refuse_writes_when_degraded,
+        ));
 
-    crate::security::headers::apply(router, enable_hsts)
+    middleware.apply(router, &state)
 }
 
-// Health endpoints for SvelteKit UI
-async fn health_get() -> Json<serde_json::Value> {
-    Json(json!({"status":"ok"}))
+#[doc = r#"Fallback for paths no route matches.
+
+Responses:
+- 404 Not Found — `application/problem+json` with `code: "route_not_found"` and the path
+"#]
+async fn route_not_found(uri: axum::http::Uri) -> crate::error::Problem {
+    crate::error::Problem::new(StatusCode::NOT_FOUND, "route_not_found", "Route not found")
+        .detail(format!("No route matches {}", uri.path()))
+        .with("path", uri.path())
+}
+
+#[doc = r#"Fallback for a known path called with a method it does not support.
+
+Responses:
+- 405 Method Not Allowed — `application/problem+json` with `code: "method_not_allowed"`; the
+  router adds an `Allow` header listing the path's methods
+"#]
+async fn method_not_allowed(
+    method: axum::http::Method,
+    uri: axum::http::Uri,
+) -> crate::error::Problem {
+    crate::error::Problem::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "method_not_allowed",
+        "Method not allowed",
+    )
+    .detail(format!(
+        "{method} is not supported on {}; see the Allow header",
+        uri.path()
+    ))
+    .with("method", method.as_str())
+    .with("path", uri.path())
+}
+
+// Health endpoints for SvelteKit UI. Still 200 when degraded so probes don't restart a
+// server whose database needs salvaging rather than a restart.
+async fn health_get(State(integrity): State<IntegrityState>) -> Json<serde_json::Value> {
+    match integrity.last() {
+        Some(report) if !report.ok => Json(json!({"status":"degraded","integrity": report})),
+        _ => Json(json!({"status":"ok"})),
+    }
+}
+
+#[doc = r#"Report the server version, active feature flags and last config reload.
+
+Accepts: `GET /api/version`
+- Returns [`crate::features::VersionInfo`]; no authentication required.
+"#]
+async fn get_version(State(features): State<Features>) -> Json<VersionInfo> {
+    Json(VersionInfo::new(features))
 }
 async fn health_head() -> StatusCode {
     StatusCode::OK
@@ -173,133 +761,531 @@ async fn api_session(jar: PrivateCookieJar) -> Json<serde_json::Value> {
     Json(json!({"authenticated": authed}))
 }
 
-#[derive(serde::Deserialize)]
-struct TimezonePayload {
-    timezone: String,
-}
+#[doc = r#"List devices that have logged in.
 
-#[derive(serde::Serialize)]
-struct TimezoneResponse {
-    timezone: String,
+Accepts: `GET /api/account/devices`
+- Returns [`crate::models::KnownDevice`] entries, most recently seen first; `current` marks the
+  device making the request. Devices are identified by a hash of user agent and network (see
+  [`crate::security::device`]).
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — array of devices
+- 401 Unauthorized
+"#]
+async fn get_account_devices(
+    State(db): State<Db>,
+    device: DeviceFingerprint,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::list_devices(&db, &device).await?))
 }
 
-#[doc = r#"Root endpoint.
+#[doc = r#"Forget a known device.
 
-Returns 204 No Content. This API-only server does not serve HTML; the UI is a separate SvelteKit app.
+Accepts: `DELETE /api/account/devices/{id}`
+- The device's next login counts as unseen again and triggers a new-device notification.
+- Existing sessions are not revoked.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 204 No Content
+- 204 No Content — forgotten or already absent
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
 "#]
-async fn root() -> StatusCode {
-    StatusCode::NO_CONTENT
+async fn delete_account_device(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _deleted = handlers::forget_device(&db, &events, id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-#[doc = r#"Login (form) and issue session + CSRF cookies.
+#[doc = r#"Request a login email change.
 
-Accepts: `POST /api/login` (`application/x-www-form-urlencoded`)
-- Body: `{ email, password }`
-- On success:
-  - Issues encrypted session cookie (see [`crate::config::session_cookie_name`])
-  - Issues CSRF cookie (see [`crate::config::csrf_cookie_name`])
-  - Redirects to `/`
+Accepts: `POST /api/account/email` with JSON [`crate::models::EmailChangeInput`]
+- Re-checks `current_password`, then sends a confirmation link
+  (`/api/account/email/confirm/{secret}`, valid for 24 hours) through the notification channels
+  ([`crate::notify`]). The login email is unchanged until the link is opened.
+- The request is recorded in the audit log.
 
 Security:
-- Verifies credentials against `ADMIN_EMAIL` + `ADMIN_PASSWORD_HASH`
-- Cookie names/flags vary with `COOKIE_SECURE`; see [`crate::config::session_cookie_name`] / [`crate::config::csrf_cookie_name`]
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 303 See Other — on success (redirect to `/`)
-- 401 Unauthorized — on invalid credentials (HTML body)
+- 202 Accepted — [`crate::models::PendingEmailChange`]
+- 400 Bad Request — invalid email, or already the login email
+- 401 Unauthorized
+- 403 Forbidden — wrong current password, or CSRF failure
+"#]
+async fn post_account_email(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(input): Json<crate::models::EmailChangeInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let now = clock.now_utc().naive_utc();
+    let pending = handlers::request_email_change(&db, now, input).await?;
+    Ok((StatusCode::ACCEPTED, Json(pending)))
+}
 
-Example:
-```bash
-curl -i -X POST http://localhost:8080/api/login \
-  -H 'Content-Type: application/x-www-form-urlencoded' \
-  -d 'email=admin@example.com&password=...' \
-  -c cookies.txt
-```
+#[doc = r#"Confirm a login email change.
 
-See also: [`crate::auth::{verify_login, create_session_cookie}`], [`crate::security::csrf::issue_csrf_cookie`]
+Accepts: `GET /api/account/email/confirm/{secret}`
+- Switches the login email to the requested address and returns `{"email": "..."}`. Existing
+  sessions stay valid; later logins use the new email.
+- Other pending changes are discarded; the switch is recorded in the audit log.
+
+Security:
+- No session; the secret in the path is the credential. Responses are `Cache-Control:
+  no-store` and `Referrer-Policy: no-referrer`.
+
+Responses:
+- 200 OK — `{"email": "..."}`
+- 404 Not Found — unknown, expired or already used link
 "#]
-async fn post_login(
-    jar: PrivateCookieJar,
-    Form(creds): Form<LoginPayload>,
-) -> axum::response::Response {
-    if auth::verify_login(&creds.email, &creds.password) {
-        let jar = auth::create_session_cookie(jar, "admin");
-        let jar = jar.add(issue_csrf_cookie());
-        (jar, Redirect::to("/")).into_response()
-    } else {
-        (
-            StatusCode::UNAUTHORIZED,
-            Html("Invalid credentials".to_string()),
-        )
-            .into_response()
-    }
+async fn get_account_email_confirm(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    State(clock): State<SharedClock>,
+    ValidPath(secret): ValidPath<String>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    use axum::http::header;
+
+    let now = clock.now_utc().naive_utc();
+    let email = handlers::confirm_email_change(&db, &events, now, &secret).await?;
+    Ok((
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::REFERRER_POLICY, "no-referrer"),
+        ],
+        Json(json!({ "email": email })),
+    ))
 }
 
-#[doc = r#"Login (JSON) and issue session + CSRF cookies.
+#[doc = r#"List API tokens.
 
-Accepts: `POST /api/login.json` (`application/json`)
-- Body: `{ "email": "...", "password": "..." }`
-- On success: `{"ok": true}` and `Set-Cookie` headers for session + CSRF
+Accepts: `GET /api/tokens`
+- Returns [`crate::models::ApiToken`] entries, newest first, with `last_used_at` and an
+  `expired` flag. Secrets are never returned after creation.
+
+Security:
+- Requires the browser session cookie ([`RequireSessionCookie`]); API tokens cannot manage tokens
 
 Responses:
-- 200 OK — on success
-- 401 Unauthorized — `{"error":"unauthorized"}`
+- 200 OK — array of tokens
+- 401 Unauthorized
+"#]
+async fn get_api_tokens(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let now = clock.now_utc().naive_utc();
+    Ok(Json(handlers::list_api_tokens(&db, now).await?))
+}
 
-Note:
-- JSON route is functionally equivalent to the form `/login`. Prefer `/login` for browser-based flows.
+#[doc = r#"Create an API token.
 
-Example:
-```bash
-curl -i -X POST http://localhost:8080/api/login.json \
-  -H 'Content-Type: application/json' \
-  -d '{"email":"admin@example.com","password":"..."}' \
-  -c cookies.txt
-```
+Accepts: `POST /api/tokens` with JSON [`ApiTokenInput`]
+- `scope`: `read` (GET only, data routes; see [`ApiScope`](crate::models::ApiScope)), `write:sleep` (read plus sleep writes) or `admin` (everything)
+- `expires_in_days`: 1–365, default 90
+- Returns [`crate::models::CreatedApiToken`]; `secret` is shown only in this response. Send it
+  as `Authorization: Bearer <secret>`; such requests skip CSRF.
 
-See also: [`crate::auth::{verify_login, create_session_cookie}`], [`crate::security::csrf::issue_csrf_cookie`]
+Security:
+- Requires the browser session cookie ([`RequireSessionCookie`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — token and secret
+- 400 Bad Request — invalid name, scope or expiry
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
 "#]
-async fn post_login_json(
-    jar: PrivateCookieJar,
-    Json(creds): Json<LoginPayload>,
-) -> axum::response::Response {
-    if auth::verify_login(&creds.email, &creds.password) {
-        let jar = auth::create_session_cookie(jar, "admin");
-        let jar = jar.add(issue_csrf_cookie());
-        (jar, Json(json!({"ok": true}))).into_response()
-    } else {
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(json!({"error":"unauthorized"})),
-        )
-            .into_response()
-    }
+async fn create_api_token(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    State(clock): State<SharedClock>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+    _csrf: CsrfGuard,
+    Json(input): Json<ApiTokenInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let now = clock.now_utc().naive_utc();
+    let created = handlers::create_api_token(&db, &events, now, input).await?;
+    Ok((StatusCode::CREATED, Json(created)))
 }
 
-#[doc = r#"Logout and clear cookies.
+#[doc = r#"Revoke an API token.
 
-Accepts: `POST /api/logout`
+Accepts: `DELETE /api/tokens/{id}`
 
 Security:
-- Requires a valid CSRF header (double-submit) via [`CsrfGuard`]
-- Session is cleared if present; operation is idempotent
+- Requires the browser session cookie ([`RequireSessionCookie`])
+- Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 204 No Content — session + CSRF cookies cleared
+- 204 No Content — revoked or already absent
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn delete_api_token(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _revoked = handlers::revoke_api_token(&db, &events, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-Example:
-```bash
-curl -i -X POST http://localhost:8080/api/logout \
-  -H "Cookie: __Host-session=...; __Host-csrf=..." \
-  -H "X-CSRF-Token: <csrf cookie value>"
-```
+#[doc = r#"List share links.
 
-See also: [`crate::auth::clear_session_cookie`], [`crate::security::csrf::CsrfGuard`]
+Accepts: `GET /api/share-links`
+- Returns [`crate::models::ShareLink`] entries, newest first, with `last_used_at`, `use_count`
+  and an `expired` flag (past `expires_at` or `max_uses`). Secrets are never returned after
+  creation.
+
+Security:
+- Requires the browser session cookie ([`RequireSessionCookie`])
+
+Responses:
+- 200 OK — array of links
+- 401 Unauthorized
 "#]
-async fn post_logout(mut jar: PrivateCookieJar, _csrf: CsrfGuard) -> axum::response::Response {
-    jar = auth::clear_session_cookie(jar);
+async fn get_share_links(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let now = clock.now_utc().naive_utc();
+    Ok(Json(handlers::list_share_links(&db, now).await?))
+}
+
+#[doc = r#"Create a read-only link to a date range.
+
+Accepts: `POST /api/share-links` with JSON [`ShareLinkInput`]
+- `from` / `to`: inclusive range, at most 366 days
+- `expires_in_days`: 1–90, default 7
+- `max_uses`: optional view limit, 1–10000
+- Returns [`crate::models::CreatedShareLink`]; `secret` is shown only in this response. The
+  link is `/api/shared/{secret}`.
+
+Security:
+- Requires the browser session cookie ([`RequireSessionCookie`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — link and secret
+- 400 Bad Request — invalid range, expiry or use limit
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn create_share_link(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    State(clock): State<SharedClock>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+    _csrf: CsrfGuard,
+    Json(input): Json<ShareLinkInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let now = clock.now_utc().naive_utc();
+    let created = handlers::create_share_link(&db, &events, now, input).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+#[doc = r#"Show how a share link has been used.
+
+Accepts: `GET /api/shares/{id}/stats`
+- Returns [`crate::models::ShareLinkStats`]: the view count and limit, the last view, and each
+  client address that opened the link with its own count, so a leaked link stands out and can
+  be revoked.
+
+Security:
+- Requires the browser session cookie ([`RequireSessionCookie`])
+
+Responses:
+- 200 OK — usage statistics
+- 401 Unauthorized
+- 404 Not Found — unknown or revoked link
+"#]
+async fn get_share_link_stats(
+    State(db): State<Db>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::share_link_stats(&db, id).await?))
+}
+
+#[doc = r#"Revoke a share link.
+
+Accepts: `DELETE /api/share-links/{id}`
+
+Security:
+- Requires the browser session cookie ([`RequireSessionCookie`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — revoked or already absent
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn delete_share_link(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionCookie { _user_id: _ }: RequireSessionCookie,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _revoked = handlers::revoke_share_link(&db, &events, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Open a share link.
+
+Accepts: `GET /api/shared/{secret}`
+- Returns [`crate::models::SharedView`]: the sleep sessions, exercise and notes of the link's
+  range, redacted with the `share` policy ([`crate::redaction`]).
+
+Security:
+- No session; the secret in the path is the credential. Responses are `Cache-Control:
+  no-store` and `Referrer-Policy: no-referrer` so the URL does not leak further.
+
+Responses:
+- 200 OK — [`crate::models::SharedView`]; the view is counted and recorded with the client
+  address (see `GET /api/shares/{id}/stats`)
+- 404 Not Found — unknown, revoked, expired or used-up link
+"#]
+async fn get_shared(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    ClientIp(ip): ClientIp,
+    ValidPath(secret): ValidPath<String>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    use axum::http::header;
+
+    let now = clock.now_utc().naive_utc();
+    let view = handlers::shared_view(&db, &secret, now, ip).await?;
+    Ok((
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::REFERRER_POLICY, "no-referrer"),
+        ],
+        Json(view),
+    ))
+}
+
+#[derive(serde::Deserialize)]
+struct TimezonePayload {
+    timezone: String,
+}
+
+#[derive(serde::Serialize)]
+struct TimezoneResponse {
+    timezone: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct LocaleSetting {
+    locale: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct UnitsSetting {
+    units: String,
+}
+
+#[doc = r#"Root endpoint.
+
+Returns 204 No Content. This API-only server does not serve HTML; the UI is a separate SvelteKit app.
+
+Responses:
+- 204 No Content
+"#]
+async fn root() -> StatusCode {
+    StatusCode::NO_CONTENT
+}
+
+#[doc = r#"Login (form) and issue session + CSRF cookies.
+
+Accepts: `POST /api/login` (`application/x-www-form-urlencoded`)
+- Body: `{ email, password }`
+- On success:
+  - Issues encrypted session cookie (see [`crate::config::session_cookie_name`])
+  - Issues CSRF cookie (see [`crate::config::csrf_cookie_name`])
+  - Redirects to `/`
+- Records the login device (see [`crate::security::device`]) and notifies on an unseen one
+
+Security:
+- Verifies credentials against `ADMIN_EMAIL` (or the confirmed replacement, see
+  `POST /api/account/email`) + `ADMIN_PASSWORD_HASH`, upgrading a hash weaker than
+  the hashing policy (see [`crate::security::password`])
+- Cookie names/flags vary with `COOKIE_SECURE`; see [`crate::config::session_cookie_name`] / [`crate::config::csrf_cookie_name`]
+
+Responses:
+- 303 See Other — on success (redirect to `/`)
+- 401 Unauthorized — on invalid credentials (HTML body)
+- 429 Too Many Requests — `{"error":"locked","retry_after_s":N}` while the account or address
+  is locked out (see [`crate::security::lockout`]), or too many failed logins this minute (see
+  [`crate::middleware::rate_limit`])
+
+Example:
+```bash
+curl -i -X POST http://localhost:8080/api/login \
+  -H 'Content-Type: application/x-www-form-urlencoded' \
+  -d 'email=admin@example.com&password=...' \
+  -c cookies.txt
+```
+
+See also: [`crate::auth::{verify_login, create_session_cookie}`], [`crate::security::csrf::issue_csrf_cookie`]
+"#]
+async fn post_login(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    device: DeviceFingerprint,
+    ClientIp(ip): ClientIp,
+    jar: PrivateCookieJar,
+    Form(creds): Form<LoginPayload>,
+) -> axum::response::Response {
+    match check_login(&db, &clock, ip, &creds).await {
+        LoginAttempt::Accepted => {
+            if let Err(e) = handlers::record_login_device(&db, &device).await {
+                tracing::warn!(error = ?e, "failed to record login device");
+            }
+            let jar = auth::create_session_cookie(jar, "admin");
+            let jar = jar.add(issue_csrf_cookie());
+            (jar, Redirect::to("/")).into_response()
+        }
+        LoginAttempt::Rejected => (
+            StatusCode::UNAUTHORIZED,
+            Html("Invalid credentials".to_string()),
+        )
+            .into_response(),
+        LoginAttempt::Locked(secs) => lockout::locked_response(secs),
+    }
+}
+
+/// Verify `creds` through the login lockout ([`crate::security::lockout`]), upgrading a weak
+/// password hash on success ([`crate::security::password`]).
+async fn check_login(
+    db: &Db,
+    clock: &SharedClock,
+    ip: Option<std::net::IpAddr>,
+    creds: &LoginPayload,
+) -> LoginAttempt {
+    let policy = crate::config::login_lockout();
+    let now = clock.now_utc().naive_utc();
+    let login_email = crate::repository::get_login_email(db, &crate::config::admin_email()).await;
+    let configured = crate::config::admin_password_hash();
+    let hash = password::effective_hash(db, &creds.email, &configured).await;
+    let attempt = lockout::attempt(db, policy.as_ref(), &creds.email, ip, now, || {
+        auth::verify_login(&creds.email, &creds.password, &login_email, &hash)
+    })
+    .await;
+    if matches!(attempt, LoginAttempt::Accepted) {
+        let hashing = crate::config::password_hash_policy();
+        if let Err(e) = password::rehash_if_weaker(
+            db,
+            &hashing,
+            &creds.email,
+            &creds.password,
+            &hash,
+            &configured,
+        )
+        .await
+        {
+            tracing::warn!(error = ?e, "failed to rehash password");
+        }
+    }
+    attempt
+}
+
+#[doc = r#"Login (JSON) and issue session + CSRF cookies.
+
+Accepts: `POST /api/login.json` (`application/json`)
+- Body: `{ "email": "...", "password": "..." }`
+- On success: `{"ok": true}` and `Set-Cookie` headers for session + CSRF
+- Records the login device (see [`crate::security::device`]) and notifies on an unseen one
+
+Responses:
+- 200 OK — on success
+- 401 Unauthorized — `{"error":"unauthorized"}`
+- 429 Too Many Requests — `{"error":"locked","retry_after_s":N}` while the account or address
+  is locked out after repeated failures (see [`crate::security::lockout`]), or too many failed
+  logins this minute (see [`crate::middleware::rate_limit`])
+
+Note:
+- JSON route is functionally equivalent to the form `/login`. Prefer `/login` for browser-based flows.
+
+Example:
+```bash
+curl -i -X POST http://localhost:8080/api/login.json \
+  -H 'Content-Type: application/json' \
+  -d '{"email":"admin@example.com","password":"..."}' \
+  -c cookies.txt
+```
+
+See also: [`crate::auth::{verify_login, create_session_cookie}`], [`crate::security::csrf::issue_csrf_cookie`]
+"#]
+async fn post_login_json(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    device: DeviceFingerprint,
+    ClientIp(ip): ClientIp,
+    jar: PrivateCookieJar,
+    Json(creds): Json<LoginPayload>,
+) -> axum::response::Response {
+    match check_login(&db, &clock, ip, &creds).await {
+        LoginAttempt::Accepted => {
+            if let Err(e) = handlers::record_login_device(&db, &device).await {
+                tracing::warn!(error = ?e, "failed to record login device");
+            }
+            let jar = auth::create_session_cookie(jar, "admin");
+            let jar = jar.add(issue_csrf_cookie());
+            (jar, Json(json!({"ok": true}))).into_response()
+        }
+        LoginAttempt::Rejected => (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error":"unauthorized"})),
+        )
+            .into_response(),
+        LoginAttempt::Locked(secs) => lockout::locked_response(secs),
+    }
+}
+
+#[doc = r#"Logout and clear cookies.
+
+Accepts: `POST /api/logout`
+
+Security:
+- Requires a valid CSRF header (double-submit) via [`CsrfGuard`]
+- Session is cleared if present; operation is idempotent
+
+Responses:
+- 204 No Content — session + CSRF cookies cleared
+
+Example:
+```bash
+curl -i -X POST http://localhost:8080/api/logout \
+  -H "Cookie: __Host-session=...; __Host-csrf=..." \
+  -H "X-CSRF-Token: <csrf cookie value>"
+```
+
+See also: [`crate::auth::clear_session_cookie`], [`crate::security::csrf::CsrfGuard`]
+"#]
+async fn post_logout(mut jar: PrivateCookieJar, _csrf: CsrfGuard) -> axum::response::Response {
+    jar = auth::clear_session_cookie(jar);
     let csrf = Cookie::build((crate::config::csrf_cookie_name(), String::new()))
         .path("/")
         .secure(crate::config::cookie_secure())
@@ -310,417 +1296,3170 @@ async fn post_logout(mut jar: PrivateCookieJar, _csrf: CsrfGuard) -> axum::respo
     (jar, StatusCode::NO_CONTENT).into_response()
 }
 
-#[doc = r#"Set the user timezone.
+#[doc = r#"Set the user timezone.
+
+Accepts: `POST /api/settings/timezone` (`application/json`)
+- Body: `{ "timezone": "Asia/Tokyo" }`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF header equal to CSRF cookie ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid timezone
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_timezone(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(payload): Json<TimezonePayload>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_user_timezone(&db, &events, payload.timezone).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Get the user timezone.
+
+Accepts: `GET /api/settings/timezone`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `{ "timezone": "Asia/Tokyo" }`
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_timezone(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let timezone = handlers::get_user_timezone(&db).await;
+    Ok(Json(TimezoneResponse { timezone }))
+}
+
+#[doc = r#"Create a sleep session.
+
+Accepts: `POST /api/sleep[?infer_date=true]` (`application/json`)
+- Body: [`SleepInput`]
+- With `infer_date=true`, `date` may be omitted: the wake date is inferred from `wake_time` and
+  the current time in the configured timezone (today if that wake time has passed, otherwise
+  yesterday; see [`crate::time::infer_wake_date`]). To avoid double-logging, an inferred date
+  that already has a session waking within 90 minutes is rejected. The response then also
+  carries the `date` used.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF header equal to CSRF cookie ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`, or `{"id": <number>, "date": "YYYY-MM-DD", "date_inferred":
+  true}` when the date was inferred
+- 400 Bad Request — invalid input, overlap, or (inferred date) the morning is already logged
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+Example:
+```bash
+curl -i -X POST http://localhost:8080/api/sleep \
+  -H "Cookie: __Host-session=...; __Host-csrf=..." \
+  -H "X-CSRF-Token: <csrf cookie value>" \
+  -H "Content-Type: application/json" \
+  -d '{"date":"2025-06-17","bed_time":"22:05:00","wake_time":"06:30:00","latency_min":10,"awakenings":0,"quality":4}'
+```
+
+See also: [`crate::handlers::create_sleep`], [`crate::middleware::auth_layer::RequireSessionJson`], [`crate::security::csrf::CsrfGuard`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn create_sleep(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    axum::extract::Query(params): axum::extract::Query<CreateSleepParams>,
+    req: axum::extract::Request,
+) -> axum::response::Response {
+    use axum::extract::FromRequest;
+
+    if !params.infer_date {
+        let Json(input) = match Json::<SleepInput>::from_request(req, &()).await {
+            Ok(json) => json,
+            Err(rejection) => return rejection.into_response(),
+        };
+        return match handlers::create_sleep(&db, &events, &time, &lock, input).await {
+            Ok(id) => (StatusCode::CREATED, Json(json!({"id": id}))).into_response(),
+            Err(e) => ApiError::from(e).into_response(),
+        };
+    }
+
+    let Json(mut body) = match Json::<serde_json::Value>::from_request(req, &()).await {
+        Ok(json) => json,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let inferred = match handlers::infer_sleep_date(&db, &time, &mut body).await {
+        Ok(inferred) => inferred,
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+    let input = match serde_json::to_vec(&body).map(|bytes| Json::<SleepInput>::from_bytes(&bytes))
+    {
+        Ok(Ok(Json(input))) => input,
+        Ok(Err(rejection)) => return rejection.into_response(),
+        Err(e) => return ApiError::InvalidInput(e.to_string()).into_response(),
+    };
+    let date = input.date;
+    let created = match inferred {
+        Some(_) => handlers::create_sleep_inferred(&db, &events, &time, &lock, input).await,
+        None => handlers::create_sleep(&db, &events, &time, &lock, input).await,
+    };
+    match created {
+        Ok(id) if inferred.is_some() => (
+            StatusCode::CREATED,
+            Json(json!({"id": id, "date": date, "date_inferred": true})),
+        )
+            .into_response(),
+        Ok(id) => (StatusCode::CREATED, Json(json!({"id": id}))).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct CreateSleepParams {
+    #[serde(default)]
+    infer_date: bool,
+}
+
+#[doc = r#"Start the sleep timer.
+
+Accepts: `POST /api/sleep/start` (no body)
+- Records the current time as the bed time of the session `POST /api/sleep/stop` will create.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF header equal to CSRF cookie ([`CsrfGuard`])
+
+Responses:
+- 201 Created — [`ActiveSleep`](crate::models::ActiveSleep)
+- 400 Bad Request — a timer is already running
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure
+
+See also: [`crate::handlers::start_sleep_timer`]
+"#]
+async fn post_sleep_start(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let active = handlers::start_sleep_timer(&db, &time).await?;
+    Ok((StatusCode::CREATED, Json(active)))
+}
+
+#[doc = r#"Stop the sleep timer and create the session it measured.
+
+Accepts: `POST /api/sleep/stop` (optional `application/json` body)
+- Body: [`SleepTimerStop`] (`latency_min`, `awakenings`, `quality`; all optional)
+- Bed time is the timer's start and wake time is now, both in the configured timezone; the
+  wake date is today's local date.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF header equal to CSRF cookie ([`CsrfGuard`])
+
+Responses:
+- 201 Created — the created [`SleepSession`](crate::models::SleepSession)
+- 400 Bad Request — invalid input, overlap, or the timer ran for 24 hours or more (the timer
+  keeps running; cancel it with `DELETE /api/sleep/active`)
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no timer is running
+- 429 Too Many Requests — `{code:"quota_exceeded"}`, the wake date already has
+  `QUOTA_SESSIONS_PER_DAY` sessions (the timer keeps running)
+
+See also: [`crate::handlers::stop_sleep_timer`]
+"#]
+async fn post_sleep_stop(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    stop: Option<Json<SleepTimerStop>>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let stop = stop.map(|Json(stop)| stop).unwrap_or_default();
+    let session = handlers::stop_sleep_timer(&db, &events, &time, &lock, stop).await?;
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+#[doc = r#"Get the running sleep timer.
+
+Accepts: `GET /api/sleep/active`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`ActiveSleep`](crate::models::ActiveSleep), or `null` when no timer is running
+- 401 Unauthorized — no/invalid session
+
+See also: [`crate::handlers::get_active_sleep`]
+"#]
+async fn get_sleep_active(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::get_active_sleep(&db, &time).await?))
+}
+
+#[doc = r#"Cancel the running sleep timer without recording a session.
+
+Accepts: `DELETE /api/sleep/active`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — cancelled
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no timer is running
+
+See also: [`crate::handlers::cancel_sleep_timer`]
+"#]
+async fn delete_sleep_active(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    if handlers::cancel_sleep_timer(&db).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+#[doc = r#"Get the pre-sleep routine checklist.
+
+Accepts: `GET /api/settings/routine`
+- Returns the configured [`RoutineChecklist`], or the default checklist when none is saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`RoutineChecklist`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_routine(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<RoutineChecklist> {
+    Json(crate::repository::get_routine_checklist(&db).await)
+}
+
+#[doc = r#"Replace the pre-sleep routine checklist.
+
+Accepts: `POST /api/settings/routine` (`application/json`)
+- Body: [`RoutineChecklist`], e.g. `{"items": [{"id": "read_20_min", "label": "Read for 20 minutes"}]}`
+- Already recorded evenings keep their entries; removed items simply stop being offered.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`RoutineChecklist`]
+- 400 Bad Request — invalid checklist
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_routine(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(checklist): Json<RoutineChecklist>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::set_routine_checklist(&db, &events, checklist).await?,
+    ))
+}
+
+#[doc = r#"Get the accepted exercise intensity levels.
+
+Accepts: `GET /api/settings/intensity-levels`
+- Returns the configured [`IntensityLevels`] (lowest first), or none/light/hard when none are saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`IntensityLevels`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_intensity_levels(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<IntensityLevels> {
+    Json(crate::repository::get_intensity_levels(&db).await)
+}
+
+#[doc = r#"Replace the accepted exercise intensity levels.
+
+Accepts: `POST /api/settings/intensity-levels` (`application/json`)
+- Body: [`IntensityLevels`], e.g. `{"levels": ["none", "light", "moderate", "hard"]}`
+- `none`, `light`, and `hard` must stay, in that order; custom levels may go anywhere.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`IntensityLevels`]
+- 400 Bad Request — invalid level list
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_intensity_levels(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(levels): Json<IntensityLevels>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::set_intensity_levels(&db, &events, levels).await?,
+    ))
+}
+
+#[doc = r#"Get the sleep goal.
+
+Accepts: `GET /api/goals` (also served at `GET /api/settings/sleep-goal`)
+- Returns the saved [`SleepGoal`], or the default (23:00, 8 hours) when none is saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`SleepGoal`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_sleep_goal(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<SleepGoal> {
+    Json(crate::repository::get_sleep_goal(&db).await)
+}
+
+#[doc = r#"Replace the sleep goal.
+
+Accepts: `PUT /api/goals` or `POST /api/settings/sleep-goal` (`application/json`)
+- Body: [`SleepGoal`], e.g. `{"target_bedtime": "22:30:00", "target_duration_min": 450}`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`SleepGoal`]
+- 400 Bad Request — invalid goal
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_sleep_goal(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(goal): Json<SleepGoal>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::set_sleep_goal(&db, &events, goal).await?))
+}
+
+#[doc = r#"Get when the logical day starts.
+
+Accepts: `GET /api/settings/day-boundary`
+- Returns the saved [`DayBoundary`], or midnight when none is saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`DayBoundary`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_day_boundary(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<DayBoundary> {
+    Json(crate::repository::get_day_boundary(&db).await)
+}
+
+#[doc = r#"Set when the logical day starts.
+
+Accepts: `POST /api/settings/day-boundary` (`application/json`)
+- Body: [`DayBoundary`], e.g. `{"day_start": "04:00:00"}` or `{"day_start": "18:00:00"}`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`DayBoundary`]
+- 400 Bad Request — `day_start` is not a whole minute
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_day_boundary(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(boundary): Json<DayBoundary>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::set_day_boundary(&db, &events, boundary).await?,
+    ))
+}
+
+#[doc = r#"Get what the public summary exposes.
+
+Accepts: `GET /api/settings/public-summary`
+- Returns the saved [`PublicSummarySettings`], or disabled with no fields when none are saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`PublicSummarySettings`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_public_summary(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<PublicSummarySettings> {
+    Json(crate::repository::get_public_summary_settings(&db).await)
+}
+
+#[doc = r#"Enable or disable the public summary and choose its fields.
+
+Accepts: `POST /api/settings/public-summary` (`application/json`)
+- Body: [`PublicSummarySettings`], e.g.
+  `{"enabled": true, "fields": ["avg_duration_month", "logging_streak"]}`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`PublicSummarySettings`]
+- 400 Bad Request — repeated field
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_public_summary(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(settings): Json<PublicSummarySettings>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::set_public_summary_settings(&db, &events, settings).await?,
+    ))
+}
+
+#[doc = r#"Get the redaction policy of each audience.
+
+Accepts: `GET /api/settings/redaction`
+- Returns the saved [`RedactionSettings`], or the defaults when none are saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`RedactionSettings`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_redaction(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<RedactionSettings> {
+    Json(crate::repository::get_redaction_settings(&db).await)
+}
+
+#[doc = r#"Set what share links, the public summary and anonymized exports reveal.
+
+Accepts: `POST /api/settings/redaction` (`application/json`)
+- Body: [`RedactionSettings`], one policy per audience, e.g.
+  `{"share": {"note_bodies": false, "time_rounding_min": 15, "check_in": false}, "public": {...},
+  "export": {...}}`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`RedactionSettings`]
+- 400 Bad Request — unsupported `time_rounding_min`
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_redaction(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(settings): Json<RedactionSettings>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::set_redaction_settings(&db, &events, settings).await?,
+    ))
+}
+
+#[doc = r#"Get the configured alert rules.
+
+Accepts: `GET /api/settings/alerts`
+- Returns the saved [`AlertRules`], or no rules when none are saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`AlertRules`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_alerts(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<AlertRules> {
+    Json(crate::repository::get_alert_rules(&db).await)
+}
+
+#[doc = r#"Replace the alert rules evaluated nightly by the `alert_evaluation` job.
+
+Accepts: `PUT /api/settings/alerts` (`application/json`)
+- Body: [`AlertRules`], e.g.
+  `{"rules": [{"id": "short_week", "metric": "duration_min", "comparison": "below",
+  "threshold": 360, "condition": "average", "nights": 7}]}`
+- Fired alerts are delivered through the notification channels (see [`crate::notify`]) and
+  listed by `GET /api/alerts/history`.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`AlertRules`]
+- 400 Bad Request — invalid or duplicate rule id, `nights` outside 1..=90, more than 20 rules
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn put_settings_alerts(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(rules): Json<AlertRules>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::set_alert_rules(&db, &events, rules).await?))
+}
+
+#[doc = r#"List fired alerts, newest first.
+
+Accepts: `GET /api/alerts/history?limit=50`
+- `limit`: 1..=500 (default 50). See [`AlertHistoryQuery`].
+- Each entry is a [`crate::models::AlertEvent`]: the rule, the evaluated wake date, the
+  observed value, the message, and the channels it was delivered to.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — array of alerts
+- 400 Bad Request — `limit` out of range
+- 401 Unauthorized
+"#]
+async fn get_alert_history(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    axum::extract::Query(query): axum::extract::Query<AlertHistoryQuery>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::list_alert_history(&db, &query).await?))
+}
+
+#[doc = r#"Get the default response locale.
+
+Accepts: `GET /api/settings/locale`
+- Used for report and insight text when `Accept-Language` names no supported locale.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `{ "locale": "en" }`
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_locale(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Json<LocaleSetting> {
+    let locale = crate::repository::get_locale(&db).await;
+    Json(LocaleSetting {
+        locale: locale.tag().to_string(),
+    })
+}
+
+#[doc = r#"Set the default response locale.
+
+Accepts: `POST /api/settings/locale` (`application/json`)
+- Body: `{ "locale": "ja" }` — one of `en`, `ja`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — unsupported locale
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_locale(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(payload): Json<LocaleSetting>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_locale(&db, &events, &payload.locale).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Get the duration unit preference.
+
+Accepts: `GET /api/settings/units`
+- `hours` (default) or `minutes`; applies to report/insight text and `duration_hours` fields.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `{ "units": "hours" }`
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_units(
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Units(unit): Units,
+) -> Json<UnitsSetting> {
+    Json(UnitsSetting {
+        units: unit.as_str().to_string(),
+    })
+}
+
+#[doc = r#"Set the duration unit preference.
+
+Accepts: `POST /api/settings/units` (`application/json`)
+- Body: `{ "units": "minutes" }` — one of `hours`, `minutes`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — unsupported units
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_units(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(payload): Json<UnitsSetting>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_duration_unit(&db, &events, &payload.units).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(serde::Deserialize)]
+struct ExportParams {
+    #[serde(default)]
+    anonymize: bool,
+}
+
+#[doc = r#"Get the bed/wake time rounding of trends and exports.
+
+Accepts: `GET /api/settings/time-rounding`
+- Returns the saved [`TimeRounding`], or `{"step_min": 1}` (exact times) when none is saved.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`TimeRounding`]
+- 401 Unauthorized — no/invalid session
+"#]
+async fn get_settings_time_rounding(
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Rounding(rounding): Rounding,
+) -> Json<TimeRounding> {
+    Json(rounding)
+}
+
+#[doc = r#"Set the bed/wake time rounding of trends and exports.
+
+Accepts: `POST /api/settings/time-rounding` (`application/json`)
+- Body: [`TimeRounding`], e.g. `{"step_min": 15}`; one of 1 (exact), 5, 10, 15
+- Stored sessions keep their exact times.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — saved [`TimeRounding`]
+- 400 Bad Request — unsupported `step_min`
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_settings_time_rounding(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(rounding): Json<TimeRounding>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::set_time_rounding(&db, &events, rounding).await?,
+    ))
+}
+
+#[doc = r#"Export every sleep session, exercise event and note, as a resumable download.
+
+Accepts: `GET|HEAD /api/export?format=json|csv&from=YYYY-MM-DD&to=YYYY-MM-DD[&anonymize=true]`
+- `from` / `to` are optional; an omitted bound leaves that side open
+  ([`crate::extract::OpenDateRange`]).
+- `anonymize=true` redacts every record with the `export` policy of the redaction settings
+  ([`crate::redaction`]); by default note bodies are dropped and times rounded to 15 minutes.
+- Bed and wake times are rounded to the time rounding preference
+  (`GET/POST /api/settings/time-rounding`) first; exact by default.
+- The format is negotiated like other tabular endpoints ([`ResponseFormat`]); JSON unless
+  `format=csv` or `Accept: text/csv`.
+- The document is rendered in pages ([`crate::data_export`]) into a temporary file
+  ([`crate::download::spool`]), never held in memory, then served like a backup download
+  ([`crate::download::serve_resumable`]): the `ETag` is the SHA-256 of the document, so
+  `Range` with `If-Range` resumes an interrupted transfer while the data is unchanged and
+  restarts it with `200` once it has changed.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `application/json` or `text/csv`, `Content-Disposition: attachment`
+- 206 Partial Content — the requested byte range
+- 400 Bad Request — invalid `format`, `from` or `to`, or `from` after `to`
+- 401 Unauthorized
+- 416 Range Not Satisfiable
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn get_export(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Rounding(rounding): Rounding,
+    format: ResponseFormat,
+    range: crate::extract::OpenDateRange,
+    axum::extract::Query(params): axum::extract::Query<ExportParams>,
+    method: axum::http::Method,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    use axum::http::{HeaderValue, header};
+
+    let (content_type, filename) = match (format, params.anonymize) {
+        (ResponseFormat::Json, false) => ("application/json", "sleep-export.json"),
+        (ResponseFormat::Json, true) => ("application/json", "sleep-export-anonymized.json"),
+        (ResponseFormat::Csv, false) => ("text/csv; charset=utf-8", "sleep-export.csv"),
+        (ResponseFormat::Csv, true) => ("text/csv; charset=utf-8", "sleep-export-anonymized.csv"),
+    };
+    let redaction = if params.anonymize {
+        Some(crate::redaction::policy(&db, crate::models::Audience::Export).await)
+    } else {
+        None
+    };
+    let (path, sha256) = crate::download::spool(crate::data_export::stream(
+        db, range.from, range.to, format, rounding, redaction,
+    ))
+    .await?;
+    let mut response =
+        crate::download::serve_resumable(&method, &headers, &path, &sha256, filename).await;
+    let _ = tokio::fs::remove_file(&path).await;
+    if response.status().is_success() {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    Ok(response)
+}
+
+/// `ATTACHMENT_DIR` for the current tenant; attachments respond 404 when it is not configured.
+fn attachment_root() -> Result<std::path::PathBuf, ApiError> {
+    crate::config::attachment_dir().ok_or(ApiError::NotFound)
+}
+
+#[derive(serde::Deserialize)]
+struct AttachmentListParams {
+    date: chrono::NaiveDate,
+}
+
+#[doc = r#"List the attachments of a day.
+
+Accepts: `GET /api/attachments?date=YYYY-MM-DD`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<Attachment>` oldest first (empty when the day has none)
+- 400 Bad Request — missing or invalid `date`
+- 401 Unauthorized
+- 404 Not Found — `ATTACHMENT_DIR` is not set
+"#]
+async fn get_attachments(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    axum::extract::Query(q): axum::extract::Query<AttachmentListParams>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    attachment_root()?;
+    Ok(Json(handlers::list_attachments(&db, q.date).await?))
+}
+
+#[doc = r#"Attach a file (photo, audio memo) to a day.
+
+Accepts: `POST /api/attachments?date=YYYY-MM-DD&filename=<name>`
+- The request body is the file, at most 10 MiB; its `Content-Type` is stored with it
+  (`application/octet-stream` when absent).
+- Images get their pixel size and a thumbnail, audio its duration; see [`crate::attachments`].
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — [`crate::models::Attachment`]
+- 400 Bad Request — invalid query, empty body, or a date in the no-edit window
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — `ATTACHMENT_DIR` is not set
+- 413 Payload Too Large
+
+See also: [`crate::handlers::create_attachment`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn post_attachment(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    axum::extract::Query(upload): axum::extract::Query<AttachmentUpload>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let root = attachment_root()?;
+    let content_type = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream");
+    let created =
+        handlers::create_attachment(&db, &events, &lock, &root, upload, content_type, body).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+#[doc = r#"Download an attachment's original file.
+
+Accepts: `GET|HEAD /api/attachment/{id}`
+- Served with its stored `Content-Type`; the `ETag` is its SHA-256. Supports `Range` and
+  `If-Range` like backup downloads (see [`crate::download`]).
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK / 206 Partial Content
+- 401 Unauthorized
+- 404 Not Found — unknown attachment, or `ATTACHMENT_DIR` is not set
+- 416 Range Not Satisfiable
+"#]
+async fn get_attachment_file(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    ValidPath(id): ValidPath<i64>,
+    method: axum::http::Method,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let root = attachment_root()?;
+    let attachment = crate::repository::find_attachment(&db, id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let path = crate::attachments::original_path(&root, id);
+    let mut response = crate::download::serve_resumable(
+        &method,
+        &headers,
+        &path,
+        &attachment.sha256,
+        &attachment.filename,
+    )
+    .await;
+    if response.status().is_success()
+        && let Ok(value) = axum::http::HeaderValue::from_str(&attachment.content_type)
+    {
+        response
+            .headers_mut()
+            .insert(axum::http::header::CONTENT_TYPE, value);
+    }
+    Ok(response)
+}
+
+#[doc = r#"Get an attachment's thumbnail, for previews in the day view.
+
+Accepts: `GET /api/attachment/{id}/thumb`
+- A JPEG at most 256 pixels on its longer side, made at upload time. Carries the original's
+  `ETag`; `If-None-Match` with it returns 304.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `image/jpeg`
+- 304 Not Modified
+- 401 Unauthorized
+- 404 Not Found — unknown attachment, no thumbnail (not an image, undecodable, or the server
+  was built without the `image` feature), or `ATTACHMENT_DIR` is not set
+"#]
+async fn get_attachment_thumb(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    ValidPath(id): ValidPath<i64>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    use axum::http::header;
+
+    let root = attachment_root()?;
+    let attachment = crate::repository::find_attachment(&db, id)
+        .await?
+        .filter(|a| a.has_thumb)
+        .ok_or(ApiError::NotFound)?;
+    let etag = format!("\"{}\"", attachment.sha256);
+    let cache = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+    ];
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|v| v.as_bytes() == etag.as_bytes())
+    {
+        return Ok((StatusCode::NOT_MODIFIED, cache).into_response());
+    }
+    let bytes = match tokio::fs::read(crate::attachments::thumb_path(&root, id)).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(ApiError::NotFound),
+        Err(e) => return Err(crate::error::Error::from(e).into()),
+    };
+    Ok((
+        cache,
+        [(header::CONTENT_TYPE, "image/jpeg".to_string())],
+        bytes,
+    )
+        .into_response())
+}
+
+#[doc = r#"Get the routine entries recorded for an evening.
+
+Accepts: `GET /api/routine/{date}`
+- Path param `date`: `YYYY-MM-DD`, the evening the routine applies to
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<RoutineEntry>` (empty when nothing was recorded)
+- 401 Unauthorized
+"#]
+async fn get_routine(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    ValidPath(date): ValidPath<chrono::NaiveDate>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        crate::repository::list_routine_entries(&db, date).await?,
+    ))
+}
+
+#[doc = r#"Record which routine items were done on an evening.
+
+Accepts: `POST /api/routine/{date}` (`application/json`)
+- Path param `date`: `YYYY-MM-DD`, the evening the routine applies to; outcomes are taken
+  from the sleep that wakes on the following date
+- Body: [`RoutineInput`] — ids of completed items; other checklist items are stored as not done
+- Re-posting a date replaces its entries.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content
+- 400 Bad Request — unknown item id
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::record_routine`]
+"#]
+async fn post_routine(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    ValidPath(date): ValidPath<chrono::NaiveDate>,
+    lock: EditLock,
+    Json(input): Json<RoutineInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::record_routine(&db, &events, &lock, date, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Record a day's mood / energy rating.
+
+Accepts: `POST /api/mood` (`application/json`)
+- Body: [`MoodInput`] — `{"date": "YYYY-MM-DD", "mood": 1..=5}`
+- Re-posting a date replaces its rating.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content
+- 400 Bad Request — `mood` outside 1..=5
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::record_mood`]
+"#]
+async fn post_mood(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<MoodInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::record_mood(&db, &events, &lock, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Get sleep sessions for a wake date.
+
+Accepts: `GET /api/sleep/date/{date}`
+- Path param `date`: `YYYY-MM-DD` (wake date)
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<SleepSession>` (may be empty)
+- 401 Unauthorized — no/invalid session
+
+See also: [`crate::handlers::get_sleep_by_date`]
+"#]
+async fn get_sleep(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    ValidPath(date): ValidPath<chrono::NaiveDate>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let sessions = handlers::get_sleep_by_date(&db, date).await?;
+    Ok(Json(sessions))
+}
+
+#[doc = r#"Update a sleep session by id.
+
+Accepts: `PUT /api/sleep/{id}` (`application/json`)
+- Body: [`SleepInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no entry for id
+
+See also: [`crate::handlers::update_sleep`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn update_sleep(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<SleepInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_sleep(&db, &events, &time, &lock, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Update only some fields of a sleep session.
+
+Accepts: `PATCH /api/sleep/{id}` (`application/json`)
+- Body: [`SleepPatch`]; absent fields keep their stored values, e.g. `{"quality": 4}`.
+- `duration_min` is recomputed only when the date or times change.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — the updated [`crate::models::SleepSession`]
+- 400 Bad Request — merged record invalid, or overlaps another session
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no entry for id
+
+See also: [`crate::handlers::patch_sleep`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn patch_sleep(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(patch): Json<SleepPatch>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::patch_sleep(&db, &events, &time, &lock, id, patch).await?,
+    ))
+}
+
+#[doc = r#"Delete a sleep session by id.
+
+Accepts: `DELETE /api/sleep/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized — no/invalid session
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::delete_sleep`]
+"#]
+async fn delete_sleep(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_sleep(&db, &events, &lock, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Create an exercise entry.
+
+Accepts: `POST /exercise` (`application/json`)
+- Body: [`ExerciseInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::create_exercise`]
+"#]
+async fn create_exercise(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<ExerciseInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_exercise(&db, &events, &lock, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update an exercise event by id.
+
+Accepts: `PUT /api/exercise/{id}` (`application/json`)
+- Body: [`ExerciseInput`]; replaces every field, heart-rate zones included
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid exercise, unknown intensity level, or a second daily intensity
+  entry (no time, no duration) for the date
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no exercise event for id
+
+See also: [`crate::handlers::update_exercise`]
+"#]
+async fn update_exercise(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<ExerciseInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_exercise(&db, &events, &lock, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete an exercise event by id.
+
+Accepts: `DELETE /api/exercise/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+- Links to external sources (Strava, Garmin) are removed with the event
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::delete_exercise`]
+"#]
+async fn delete_exercise(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_exercise(&db, &events, &lock, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Create a note.
+
+Accepts: `POST /note` (`application/json`)
+- Body: [`NoteInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 400 Bad Request — invalid note, unknown `session_id`, or `date` is not the linked session's
+  wake date
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::create_note`]
+"#]
+async fn create_note(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<NoteInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_note(&db, &events, &lock, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update a note by id.
+
+Accepts: `PUT /api/note/{id}` (`application/json`)
+- Body: [`NoteInput`]; a starred note stays starred
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid note, unknown `session_id`, or `date` is not the linked session's
+  wake date
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no note for id
+
+See also: [`crate::handlers::update_note`]
+"#]
+async fn update_note(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<NoteInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_note(&db, &events, &lock, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete a note by id.
+
+Accepts: `DELETE /api/note/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::delete_note`]
+"#]
+async fn delete_note(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_note(&db, &events, &lock, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Star a sleep session.
+
+Accepts: `POST /api/sleep/{id}/star`
+- Starred sessions are listed by `GET /api/starred` and flagged `starred` in
+  `GET /api/sleep/{id}`. Starring a starred session again is a no-op.
+- Not subject to the no-edit window.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no session for id
+
+See also: [`crate::handlers::set_starred`]
+"#]
+async fn star_sleep(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_starred(&db, &events, "sleep", id, true).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Remove the star from a sleep session.
+
+Accepts: `DELETE /api/sleep/{id}/star`
+- Unstarring an unstarred session is a no-op.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no session for id
+"#]
+async fn unstar_sleep(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_starred(&db, &events, "sleep", id, false).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Star a note.
+
+Accepts: `POST /api/note/{id}/star`
+- Same semantics as `POST /api/sleep/{id}/star`.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no note for id
+"#]
+async fn star_note(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_starred(&db, &events, "note", id, true).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Remove the star from a note.
+
+Accepts: `DELETE /api/note/{id}/star`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no note for id
+"#]
+async fn unstar_note(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::set_starred(&db, &events, "note", id, false).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"List starred sleep sessions and notes.
+
+Accepts: `GET /api/starred`
+- Returns [`crate::models::Starred`]: both lists newest first, for comparison views that pull
+  up remarkable nights (best/worst, experiment baselines).
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `{"sleep": [...], "notes": [...]}`
+- 401 Unauthorized
+"#]
+async fn get_starred(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::list_starred(&db).await?))
+}
+
+#[doc = r#"Create or replace the body metrics reading for a date.
+
+Accepts: `POST /api/body-metrics` (`application/json`)
+- Body: [`BodyMetricInput`]
+- Readings are unique per date; posting an existing date replaces it.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 400 Bad Request — invalid reading
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::create_body_metric`]
+"#]
+async fn create_body_metric(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<BodyMetricInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_body_metric(&db, &events, &lock, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update a body metrics reading by id.
+
+Accepts: `PUT /api/body-metrics/{id}` (`application/json`)
+- Body: [`BodyMetricInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid reading or date already taken
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no reading for id
+"#]
+async fn update_body_metric(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<BodyMetricInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_body_metric(&db, &events, &lock, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete a body metrics reading by id.
+
+Accepts: `DELETE /api/body-metrics/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+"#]
+async fn delete_body_metric(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_body_metric(&db, &events, &lock, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Log an external sleep disturbance.
+
+Accepts: `POST /api/disturbances` (`application/json`)
+- Body: [`DisturbanceInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 400 Bad Request — invalid event
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::create_disturbance`]
+"#]
+async fn create_disturbance(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<DisturbanceInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_disturbance(&db, &events, &lock, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update a disturbance event by id.
+
+Accepts: `PUT /api/disturbances/{id}` (`application/json`)
+- Body: [`DisturbanceInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid event
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no event for id
+"#]
+async fn update_disturbance(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<DisturbanceInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_disturbance(&db, &events, &lock, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete a disturbance event by id.
+
+Accepts: `DELETE /api/disturbances/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+"#]
+async fn delete_disturbance(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_disturbance(&db, &events, &lock, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"List medications.
+
+Accepts: `GET /api/medications`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<Medication>` ordered by name
+- 401 Unauthorized
+"#]
+async fn get_medications(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(crate::repository::list_medications(&db).await?))
+}
+
+#[doc = r#"Create a medication.
+
+Accepts: `POST /api/medications` (`application/json`)
+- Body: [`MedicationInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 400 Bad Request — invalid medication, or the name is already in use
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+
+See also: [`crate::handlers::create_medication`]
+"#]
+async fn create_medication(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(input): Json<MedicationInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_medication(&db, &events, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update a medication by id.
+
+Accepts: `PUT /api/medications/{id}` (`application/json`)
+- Body: [`MedicationInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid medication, or the name is already in use
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no medication for id
+"#]
+async fn update_medication(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(input): Json<MedicationInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_medication(&db, &events, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete a medication by id, together with its logged events.
+
+Accepts: `DELETE /api/medications/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn delete_medication(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_medication(&db, &events, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Log a medication intake for a night.
+
+Accepts: `POST /api/medication-events` (`application/json`)
+- Body: [`MedicationEventInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 400 Bad Request — invalid event or unknown `medication_id`
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+
+See also: [`crate::handlers::create_medication_event`]
+"#]
+async fn create_medication_event(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<MedicationEventInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_medication_event(&db, &events, &lock, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update a medication event by id.
+
+Accepts: `PUT /api/medication-events/{id}` (`application/json`)
+- Body: [`MedicationEventInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid event or unknown `medication_id`
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — no event for id
+"#]
+async fn update_medication_event(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(input): Json<MedicationEventInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_medication_event(&db, &events, &lock, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete a medication event by id.
+
+Accepts: `DELETE /api/medication-events/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+"#]
+async fn delete_medication_event(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_medication_event(&db, &events, &lock, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"List experiments.
+
+Accepts: `GET /api/experiments`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<Experiment>`, most recent start first
+- 401 Unauthorized
+"#]
+async fn get_experiments(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(crate::repository::list_experiments(&db).await?))
+}
+
+#[doc = r#"Create an experiment.
+
+Accepts: `POST /api/experiments` (`application/json`)
+- Body: [`ExperimentInput`]; omit `end_date` while the experiment is running.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 201 Created — `{"id": <number>}`
+- 400 Bad Request — invalid experiment
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+
+See also: [`crate::handlers::create_experiment`]
+"#]
+async fn create_experiment(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(input): Json<ExperimentInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_experiment(&db, &events, input).await?;
+    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Update an experiment by id.
+
+Accepts: `PUT /api/experiments/{id}` (`application/json`)
+- Body: [`ExperimentInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — updated
+- 400 Bad Request — invalid experiment
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — no experiment for id
+"#]
+async fn update_experiment(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(input): Json<ExperimentInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    handlers::update_experiment(&db, &events, id, input).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Delete an experiment by id.
+
+Accepts: `DELETE /api/experiments/{id}`
+- Optional JSON body [`AuditReason`] (`{"reason": "duplicate", "note": "..."}`), recorded in the
+  audit log; `reason` is required when `AUDIT_REASON_REQUIRED` is set
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 204 No Content — deleted or already absent
+- 400 Bad Request — malformed body, invalid reason code, or missing required reason
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn delete_experiment(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    AuditBody(reason): AuditBody,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let _affected = handlers::delete_experiment(&db, &events, id, &reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(serde::Deserialize)]
+struct ExperimentResultsParams {
+    baseline: Option<String>,
+}
+
+#[doc = r#"Compare nights during an experiment with a baseline.
+
+Accepts: `GET /api/experiments/{id}/results?baseline=before|outside`
+- `before` (default): the same number of days immediately before `start_date`
+- `outside`: every logged night outside the experiment period
+- Running experiments are evaluated through today (user timezone).
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`crate::models::ExperimentResults`] with per-metric means, differences, and 95% CIs
+- 400 Bad Request — invalid `baseline`
+- 401 Unauthorized
+- 404 Not Found — no experiment for id
+
+See also: [`crate::handlers::experiment_results`]
+"#]
+async fn get_experiment_results(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    ValidPath(id): ValidPath<i64>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    axum::extract::Query(params): axum::extract::Query<ExperimentResultsParams>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        handlers::experiment_results(&db, &time, id, params.baseline.as_deref()).await?,
+    ))
+}
+
+#[doc = r#"Import body metrics from a third-party export.
+
+Accepts: `POST /api/body-metrics/import/{source}`
+- `source`: `withings` (body: `weight.csv` from the Withings data export) or
+  `fitbit` (body: Web API weight log JSON, metric units)
+- One reading per date is kept (earliest of the day); existing dates are replaced.
+- The whole import is written in a single transaction.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — `{"source": "withings", "imported": <number>}`
+- 400 Bad Request — unknown source or unparsable payload
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or the entry is older than the no-edit window
+  (`EDIT_WINDOW_DAYS`; bypass with `X-Admin-Override: edit-window`)
+- 404 Not Found — the source's integration is disabled (`FEATURE_INTEGRATIONS_<SOURCE>`)
+
+See also: [`crate::importers::parse_weight_export`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn import_body_metrics(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    ValidPath(source): ValidPath<String>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    State(features): State<Features>,
+    lock: EditLock,
+    payload: String,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    if let Ok(source) = source.parse::<WeightSource>()
+        && !features.weight_import(source)
+    {
+        return Err(ApiError::NotFound);
+    }
+    let summary = handlers::import_body_metrics(&db, &events, &lock, &source, &payload).await?;
+    Ok(Json(summary))
+}
+
+#[doc = r#"Preview a spreadsheet import with a column mapping.
+
+Accepts: `POST /api/import/mapping-preview` (`application/json`)
+- Body: [`MappingImportRequest`], e.g.
+  `{"csv": "Night of,In bed,Up\n2025-06-01,23:10,6:45\n", "mapping": {"date": "Night of", "bed_time": "In bed", "wake_time": "Up"}}`
+- Nothing is written.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — [`handlers::MappingPreview`] (parsed rows and every issue)
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+
+See also: [`crate::importers::parse_mapped_csv`]
+"#]
+async fn post_import_preview(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(req): Json<MappingImportRequest>,
+) -> Json<handlers::MappingPreview> {
+    Json(handlers::preview_mapping_import(&db, &time, &req).await)
+}
+
+#[doc = r#"Import a spreadsheet with a column mapping.
+
+Accepts: `POST /api/import/with-mapping` (`application/json`)
+- Body: [`MappingImportRequest`], as for `POST /api/import/mapping-preview`
+- Nothing is written unless every row is valid; rows overlapping recorded sleep are skipped.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — [`handlers::MappingImportSummary`]
+- 400 Bad Request — a row or the mapping is invalid (the first issue is reported)
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure, or a row is older than the no-edit window
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn post_import_with_mapping(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    State(time): State<TimeContext>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    Json(req): Json<MappingImportRequest>,
+) -> Result<Json<handlers::MappingImportSummary>, ApiError> {
+    Ok(Json(
+        handlers::import_with_mapping(&db, &events, &time, &lock, &req).await?,
+    ))
+}
+
+#[derive(serde::Deserialize)]
+struct SleepImportParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[doc = r#"Import historical sleep from an uploaded CSV.
+
+Accepts: `POST /api/import/sleep[?dry_run=true]` (`multipart/form-data`)
+- Part `file`: the CSV (UTF-8).
+- Part `mapping`: a [`crate::importers::ColumnMapping`] as JSON, e.g.
+  `{"date": "Night of", "bed_time": "In bed", "wake_time": "Up", "quality": "Rating"}`
+- Every row is validated like `POST /api/sleep`. Nothing is written unless all rows are valid;
+  then they are inserted in one transaction, skipping rows that overlap recorded sleep.
+- `dry_run=true` validates and runs the import in a rolled-back transaction, so the report
+  shows what a real run would do.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — [`handlers::SleepImportReport`] (always, for a dry run)
+- 400 Bad Request — missing `file` or `mapping` part, or an invalid mapping JSON
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 422 Unprocessable Entity — [`handlers::SleepImportReport`] listing the rejected rows; nothing
+  was written
+
+See also: [`crate::handlers::import_sleep_csv`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn post_import_sleep(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    State(time): State<TimeContext>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    lock: EditLock,
+    axum::extract::Query(params): axum::extract::Query<SleepImportParams>,
+    mut multipart: axum::extract::Multipart,
+) -> Result<axum::response::Response, ApiError> {
+    let bad_part = |e: axum::extract::multipart::MultipartError| {
+        ApiError::InvalidInput(format!("invalid multipart body: {}", e.body_text()))
+    };
+    let (mut csv, mut mapping) = (None, None);
+    while let Some(field) = multipart.next_field().await.map_err(bad_part)? {
+        match field.name() {
+            Some("file") => csv = Some(field.text().await.map_err(bad_part)?),
+            Some("mapping") => {
+                let raw = field.bytes().await.map_err(bad_part)?;
+                mapping = Some(
+                    serde_json::from_slice::<crate::importers::ColumnMapping>(&raw)
+                        .map_err(|e| ApiError::InvalidInput(format!("invalid mapping: {e}")))?,
+                );
+            }
+            _ => {}
+        }
+    }
+    let csv = csv.ok_or_else(|| ApiError::InvalidInput("missing part: file".into()))?;
+    let mapping = mapping.ok_or_else(|| ApiError::InvalidInput("missing part: mapping".into()))?;
+    let csv = csv.strip_prefix('\u{feff}').unwrap_or(&csv);
+
+    let report =
+        handlers::import_sleep_csv(&db, &events, &time, &lock, csv, &mapping, params.dry_run)
+            .await?;
+    let status = if report.errors.is_empty() || report.dry_run {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    Ok((status, Json(report)).into_response())
+}
+
+#[doc = r#"Collect Content-Security-Policy violation reports sent by browsers.
+
+Accepts: `POST /api/csp-report`
+- Body: a `report-uri` report (`{"csp-report":{...}}`) or a Reporting API list of
+  `csp-violation` reports, whatever the `Content-Type`.
+- Registered only when `CSP_REPORT_URI` is set (see [`crate::config::csp_reporting`]).
+
+Security:
+- No session or CSRF, as browsers send reports without credentials. Bounded instead: bodies are
+  capped at [`crate::csp_reports::MAX_REPORT_BYTES`], storage at
+  [`crate::csp_reports::REPORTS_PER_MIN`] reports per minute and
+  [`crate::csp_reports::MAX_STORED_REPORTS`] reports overall.
+
+Responses:
+- 204 No Content — stored
+- 400 Bad Request — no CSP violation in the body
+- 413 Payload Too Large
+- 429 Too Many Requests — per-minute budget spent
+- 404 Not Found — reporting not configured
+
+See also: [`crate::csp_reports::store`]
+"#]
+async fn post_csp_report(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    body: axum::body::Bytes,
+) -> Result<StatusCode, ApiError> {
+    crate::csp_reports::store(&db, &body, clock.now_utc().naive_utc()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[doc = r#"Receive a webhook push from a wearable/automation app.
+
+Accepts: `POST /api/ingest/{source}`
+- `source`: `health-auto-export` (REST API automation JSON), `tasker` (`{"sleep":[...],"exercise":[...]}`),
+  `strava` (activities with their heart-rate zones) or `garmin` (Garmin Connect activity summaries)
+- Sleep and timed exercise are recorded; entries already present are skipped so retried
+  pushes are harmless. Pushed sleep has no rating and is stored with quality 3.
+- Entries may carry an `external_ref` ([`crate::models::ExternalRef`]); it is stored with the
+  new entry, and later pushes with the same reference are skipped even when the entry changed.
+
+Security:
+- No session or CSRF; the raw body must be signed with the source's shared secret
+  (`INGEST_SECRET_<SOURCE>`) in `X-Signature-256: sha256=<hex>`
+  (see [`crate::security::signature`]).
+
+Responses:
+- 200 OK — [`handlers::IngestSummary`]
+- 400 Bad Request — unparsable payload or an invalid entry (nothing is written)
+- 401 Unauthorized — missing or invalid signature
+- 403 Forbidden — an entry is older than the no-edit window
+- 404 Not Found — unknown source, no secret configured for it, or its integration disabled
+
+See also: [`crate::importers::parse_ingest`]
+"#]
+#[allow(clippy::too_many_arguments)]
+async fn post_ingest(
+    State(db): State<Db>,
+    State(events): State<EventBus>,
+    State(time): State<TimeContext>,
+    State(features): State<Features>,
+    ValidPath(source): ValidPath<String>,
+    headers: axum::http::HeaderMap,
+    lock: EditLock,
+    body: axum::body::Bytes,
+) -> Result<axum::response::Response, ApiError> {
+    let source: IngestSource = source.parse().map_err(|_| ApiError::NotFound)?;
+    if !features.ingest(source) {
+        return Err(ApiError::NotFound);
+    }
+    let secret = crate::config::ingest_secret(source.as_str()).ok_or(ApiError::NotFound)?;
+    let signature_header = headers
+        .get(signature::SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !signature::verify(secret.as_bytes(), &body, signature_header) {
+        return Ok((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error":"unauthorized","detail":"invalid signature"})),
+        )
+            .into_response());
+    }
+    let payload = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::InvalidInput("body must be UTF-8".into()))?;
+    let summary = handlers::ingest(&db, &events, &time, &lock, source, payload).await?;
+    Ok(Json(summary).into_response())
+}
+
+#[derive(serde::Deserialize)]
+struct FrictionBacklogParams {
+    window_days: Option<i64>,
+    to: Option<String>,
+}
+
+#[doc = r#"Ingest one friction telemetry event.
+
+Accepts: `POST /api/personalization/friction-telemetry` (`application/json`)
+- Body: [`FrictionTelemetryInput`]
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+- Requires CSRF ([`CsrfGuard`])
+
+Successful submissions (no `error_kind`, no `follow_up_failure`) are sampled 1 in
+`TELEMETRY_SUCCESS_SAMPLE_RATE`; see [`crate::handlers::create_friction_telemetry`].
+
+Responses:
+- 201 Created — `{ "id": <number> }`
+- 202 Accepted — `{ "id": null }`, the submission was sampled out and not stored
+- 400 Bad Request — invalid telemetry payload
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+"#]
+async fn post_friction_telemetry(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(input): Json<FrictionTelemetryInput>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = handlers::create_friction_telemetry(&db, input).await?;
+    let status = if id.is_some() {
+        StatusCode::CREATED
+    } else {
+        StatusCode::ACCEPTED
+    };
+    Ok((status, Json(json!({"id": id}))))
+}
+
+#[doc = r#"Return ranked friction backlog proposals with evidence.
+
+Accepts: `GET /api/personalization/friction-backlog?window_days=28&to=YYYY-MM-DD`
+- `window_days` optional rolling window (1..=365), default 28
+- `to` optional inclusive end date, defaults to server current UTC date
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — ranked proposals with evidence, expected benefit, confidence and rollback condition
+- 400 Bad Request — invalid params
+- 401 Unauthorized
+"#]
+async fn get_friction_backlog(
+    State(db): State<Db>,
+    State(time): State<TimeContext>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    axum::extract::Query(params): axum::extract::Query<FrictionBacklogParams>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let parsed_to = match params.to {
+        Some(value) => Some(
+            chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .map_err(|_| ApiError::InvalidInput("invalid to date".into()))?,
+        ),
+        None => None,
+    };
+    let window_days = params.window_days.unwrap_or(28);
+    let response = handlers::friction_backlog(&db, &time, window_days, parsed_to).await?;
+    Ok(Json(response))
+}
+
+#[derive(serde::Deserialize)]
+struct RecentParams {
+    days: Option<i32>,
+}
+
+/// Longest span accepted by the list-by-range endpoints.
+const MAX_RANGE_DAYS: i64 = 62;
+
+fn with_duration_hours(mut items: Vec<SleepListItem>, unit: DurationUnit) -> Vec<SleepListItem> {
+    for item in &mut items {
+        item.duration_hours = duration_hours(unit, item.duration_min);
+    }
+    items
+}
+
+#[doc = r#"List recent sleep entries.
+
+Accepts: `GET /api/sleep/recent?days=7`
+- days clamped to [1, 31]; defaults to 7 when missing
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<SleepListItem>` (ordered desc by date; `duration_hours` when units are hours)
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_sleep_recent(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Units(unit): Units,
+    axum::extract::Query(params): axum::extract::Query<RecentParams>,
+) -> impl IntoResponse {
+    let days = match params.days {
+        None => 7,
+        Some(d) if (1..=31).contains(&d) => d,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"code":"bad_request","message":"days must be between 1 and 31"})),
+            )
+                .into_response();
+        }
+    };
+    match crate::repository::list_recent_sleep(&db, days).await {
+        Ok(items) => Json(with_duration_hours(items, unit)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SleepRangeParams {
+    tag: Option<String>,
+}
+
+#[doc = r#"List sleep sessions in an inclusive date range.
+
+Accepts: `GET /api/sleep/range?from=YYYY-MM-DD&to=YYYY-MM-DD[&tag=travel]`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+- `tag`: only sessions carrying this tag (matched case-insensitively)
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<SleepListItem>` (per-session rows ordered asc by date; `duration_hours` when units are hours)
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_sleep_range(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Units(unit): Units,
+    range: DateRange<MAX_RANGE_DAYS>,
+    axum::extract::Query(params): axum::extract::Query<SleepRangeParams>,
+) -> impl IntoResponse {
+    let items = match params.tag.as_deref().map(|t| t.trim().to_lowercase()) {
+        Some(tag) => {
+            crate::repository::list_sleep_range_tagged(&db, range.from, range.to, &tag).await
+        }
+        None => crate::repository::list_sleep_range(&db, range.from, range.to).await,
+    };
+    match items {
+        Ok(items) => Json(with_duration_hours(items, unit)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Longest range accepted by `GET /api/sleep/missing`.
+const MAX_MISSING_DAYS: i64 = 366;
+
+#[doc = r#"List wake dates without sleep logged in an inclusive date range.
+
+Accepts: `GET /api/sleep/missing?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 366 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<date>` ascending; every day of the range that no session wakes on
+- 400 Bad Request — `{code,message}` on invalid params
+
+See also: [`crate::repository::list_missing_sleep_dates`]
+"#]
+async fn get_sleep_missing(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_MISSING_DAYS>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(
+        crate::repository::list_missing_sleep_dates(&db, range.from, range.to).await?,
+    ))
+}
+
+#[doc = r#"Get a sleep session by id.
+
+Accepts: `GET /api/sleep/{id}`
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`SleepSession`], with the notes linked to it in `notes`
+- 401 Unauthorized — no/invalid session
+- 404 Not Found — no entry for id
+"#]
+async fn get_sleep_by_id(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    ValidPath(id): ValidPath<i64>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    match crate::repository::find_sleep_by_id(&db, id).await? {
+        Some(s) => Ok(Json(s)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+#[doc = r#"List exercise intensity for a date range.
+
+Accepts: `GET /api/exercise/intensity?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<{date, intensity, sessions, total_min}>` ordered asc by date; the highest
+  level per date, ranked by the configured [`IntensityLevels`], with the day's event count
+  and total exercise minutes
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_exercise_intensity(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    let levels = crate::repository::get_intensity_levels(&db).await;
+    match crate::repository::list_exercise_intensity(&db, range.from, range.to, &levels).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"Aggregate heart-rate zone minutes for a date range.
+
+Accepts: `GET /api/exercise/zones?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — [`handlers::ExerciseZones`]: totals and per-date minutes in Z1–Z5 over the
+  exercise that recorded zones (Strava/Garmin pushes, or `hr_zones` in [`ExerciseInput`])
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_exercise_zones(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> Result<Json<handlers::ExerciseZones>, ApiError> {
+    Ok(Json(
+        handlers::exercise_zones(&db, range.from, range.to).await?,
+    ))
+}
+
+#[doc = r#"List body metrics readings for a date range.
+
+Accepts: `GET /api/body-metrics?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<BodyMetric>` ordered asc by date
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_body_metrics(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    match crate::repository::list_body_metrics_range(&db, range.from, range.to).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"List exercise events for a date range.
+
+Accepts: `GET /api/exercise?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<ExerciseEvent>` ordered asc by date, then creation
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_exercise(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    match crate::repository::list_exercise_range(&db, range.from, range.to).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"List notes for a date range.
+
+Accepts: `GET /api/notes?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<Note>` ordered asc by date, then creation
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_notes(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    match crate::repository::list_notes_range(&db, range.from, range.to).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"List disturbance events for a date range.
+
+Accepts: `GET /api/disturbances?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Dates are wake dates; the day view passes `from == to`.
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<Disturbance>` ordered asc by date and time
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_disturbances(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    match crate::repository::list_disturbances_range(&db, range.from, range.to).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"List mood ratings for a date range.
+
+Accepts: `GET /api/mood?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<MoodEntry>` ordered asc by date; days without a rating are omitted
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_mood(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    match crate::repository::list_mood_range(&db, range.from, range.to).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"List medication events for a date range.
+
+Accepts: `GET /api/medication-events?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Dates are wake dates; the day view passes `from == to`.
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 62 days
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`])
+
+Responses:
+- 200 OK — `Vec<MedicationEvent>` ordered asc by date and time
+- 400 Bad Request — `{code,message}` on invalid params
+"#]
+async fn get_medication_events(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_RANGE_DAYS>,
+) -> impl IntoResponse {
+    match crate::repository::list_medication_events_range(&db, range.from, range.to).await {
+        Ok(items) => Json(items).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+#[doc = r#"JSON Schema for an API type.
+
+Accepts: `GET /api/schema/{type}` where `{type}` is a model name such as `SleepInput`,
+`SleepListItem` or `SummaryResponse` (case-sensitive).
+- Returns a standalone draft 2020-12 schema (referenced types under `$defs`) whose
+  constraints mirror the server-side validation rules, so external form builders and
+  validators can stay in sync.
+
+Security:
+- Public; the schemas carry no user data and describe the published API.
+
+Responses:
+- 200 OK — `application/schema+json`
+- 404 Not Found — unknown type name
+"#]
+async fn get_json_schema(
+    ValidPath(name): ValidPath<String>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let schema = crate::typegen::json_schema(&name).ok_or(ApiError::NotFound)?;
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "application/schema+json")],
+        Json(schema),
+    ))
+}
+
+#[doc = r#"Describe the live database schema.
 
-Accepts: `POST /api/settings/timezone` (`application/json`)
-- Body: `{ "timezone": "Asia/Tokyo" }`
+Accepts: `GET /api/admin/schema`
+- Returns tables and views (with columns, declared types, and `CREATE` statements) read
+  from the running database, plus the highest applied migration version.
+- Intended for external tooling (ETL, BI) that needs to introspect an instance.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
-- Requires CSRF header equal to CSRF cookie ([`CsrfGuard`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 204 No Content — updated
-- 400 Bad Request — invalid timezone
-- 401 Unauthorized — no/invalid session
-- 403 Forbidden — CSRF failure
+- 200 OK — [`crate::models::SchemaDescription`]
+- 401 Unauthorized
 "#]
-async fn post_settings_timezone(
+async fn get_admin_schema(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    _csrf: CsrfGuard,
-    Json(payload): Json<TimezonePayload>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    handlers::set_user_timezone(&db, payload.timezone).await?;
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(crate::repository::describe_schema(&db).await?))
 }
 
-#[doc = r#"Get the user timezone.
+#[doc = r#"Run an ad-hoc read-only SQL query.
 
-Accepts: `GET /api/settings/timezone`
+Accepts: `POST /api/admin/query` (`application/json`)
+- Body: `{"sql": "SELECT ..."}` — a single `SELECT`/`WITH` statement.
+- Runs on a `query_only` connection with a row limit (`ADMIN_QUERY_MAX_ROWS`, default 500)
+  and a time limit (`ADMIN_QUERY_TIMEOUT_MS`, default 2000).
+- Disabled unless `ADMIN_QUERY_ENABLED` is set; responds 404 otherwise.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
+- Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 200 OK — `{ "timezone": "Asia/Tokyo" }`
-- 401 Unauthorized — no/invalid session
+- 200 OK — [`crate::admin_query::QueryResult`]
+- 400 Bad Request — rejected statement, SQL error, or time limit exceeded
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — feature disabled
+
+See also: [`crate::handlers::run_admin_query`]
 "#]
-async fn get_settings_timezone(
+async fn post_admin_query(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    Json(req): Json<crate::admin_query::QueryRequest>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let timezone = handlers::get_user_timezone(&db).await;
-    Ok(Json(TimezoneResponse { timezone }))
+    Ok(Json(handlers::run_admin_query(&db, req).await?))
 }
 
-#[doc = r#"Create a sleep session.
+#[doc = r#"List background jobs and their recent runs.
 
-Accepts: `POST /api/sleep` (`application/json`)
-- Body: [`SleepInput`]
+Accepts: `GET /api/admin/jobs`
+- Returns `{"jobs": [<name>...], "runs": [JobRun...]}` with the 50 most recent runs, newest first.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
-- Requires CSRF header equal to CSRF cookie ([`CsrfGuard`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 201 Created — `{"id": <number>}`
-- 401 Unauthorized — no/invalid session
-- 403 Forbidden — CSRF failure
-
-Example:
-```bash
-curl -i -X POST http://localhost:8080/api/sleep \
-  -H "Cookie: __Host-session=...; __Host-csrf=..." \
-  -H "X-CSRF-Token: <csrf cookie value>" \
-  -H "Content-Type: application/json" \
-  -d '{"date":"2025-06-17","bed_time":"22:05:00","wake_time":"06:30:00","latency_min":10,"awakenings":0,"quality":4}'
-```
+- 200 OK
+- 401 Unauthorized
 
-See also: [`crate::handlers::create_sleep`], [`crate::middleware::auth_layer::RequireSessionJson`], [`crate::security::csrf::CsrfGuard`]
+See also: [`crate::jobs`]
 "#]
-async fn create_sleep(
+async fn get_admin_jobs(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    _csrf: CsrfGuard,
-    Json(input): Json<SleepInput>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let id = handlers::create_sleep(&db, input).await?;
-    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+    Ok(Json(handlers::list_jobs(&db).await?))
 }
 
-#[doc = r#"Get sleep sessions for a wake date.
+#[derive(serde::Deserialize)]
+struct IntegrityParams {
+    #[serde(default)]
+    check: IntegrityCheck,
+}
 
-Accepts: `GET /api/sleep/date/{date}`
-- Path param `date`: `YYYY-MM-DD` (wake date)
+#[doc = r#"Check the database for corruption.
+
+Accepts: `GET /api/admin/integrity?check=quick|full`
+- Runs `PRAGMA quick_check` (default) or `PRAGMA integrity_check` and returns
+  [`crate::integrity::IntegrityReport`]. The result replaces the startup check: corruption puts
+  the server in degraded mode (writes answer 503), and a clean report lifts it.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 200 OK — `Vec<SleepSession>` (may be empty)
-- 401 Unauthorized — no/invalid session
+- 200 OK — also when corruption was found (`ok: false`)
+- 400 Bad Request — unknown `check`
+- 401 Unauthorized
 
-See also: [`crate::handlers::get_sleep_by_date`]
+See also: [`crate::integrity`]
 "#]
-async fn get_sleep(
+async fn get_admin_integrity(
     State(db): State<Db>,
+    State(integrity): State<IntegrityState>,
+    State(clock): State<SharedClock>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    Path(date): Path<chrono::NaiveDate>,
+    axum::extract::Query(q): axum::extract::Query<IntegrityParams>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let sessions = handlers::get_sleep_by_date(&db, date).await?;
-    Ok(Json(sessions))
+    Ok(Json(
+        handlers::check_integrity(&db, &integrity, q.check, clock.now_utc()).await?,
+    ))
 }
 
-#[doc = r#"Update a sleep session by id.
+/// Longest range accepted by `GET /api/admin/daily-sleep/verify`.
+const MAX_VERIFY_DAYS: i64 = 3660;
 
-Accepts: `PUT /api/sleep/{id}` (`application/json`)
-- Body: [`SleepInput`]
+#[doc = r#"Compare the materialized daily sleep table with the sessions it is computed from.
+
+Accepts: `GET /api/admin/daily-sleep/verify?from=YYYY-MM-DD&to=YYYY-MM-DD`
+- Validated by [`DateRange`]: `from <= to`, range length ≤ 3660 days
+- Returns [`crate::integrity::DailySleepVerification`]: every wake date in the range where the
+  `daily_sleep` row is missing, orphaned or differs from the aggregate `v_daily_sleep`
+  computed before it was materialized, with both versions of the row.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
-- Requires CSRF ([`CsrfGuard`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 204 No Content — updated
-- 401 Unauthorized — no/invalid session
-- 403 Forbidden — CSRF failure
-- 404 Not Found — no entry for id
+- 200 OK — also when discrepancies were found (`ok: false`)
+- 400 Bad Request — invalid range
+- 401 Unauthorized
 
-See also: [`crate::handlers::update_sleep`]
+See also: [`crate::integrity::verify_daily_sleep`]
 "#]
-async fn update_sleep(
+async fn get_admin_daily_sleep_verify(
     State(db): State<Db>,
-    Path(id): Path<i64>,
+    State(clock): State<SharedClock>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    _csrf: CsrfGuard,
-    Json(input): Json<SleepInput>,
+    range: DateRange<MAX_VERIFY_DAYS>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    handlers::update_sleep(&db, id, input).await?;
-    Ok(StatusCode::NO_CONTENT)
+    let report =
+        crate::integrity::verify_daily_sleep(&db, range.from, range.to, clock.now_utc()).await?;
+    if !report.ok {
+        tracing::warn!(
+            discrepancies = report.discrepancies.len(),
+            "daily_sleep differs from the sessions"
+        );
+    }
+    Ok(Json(report))
 }
 
-#[doc = r#"Delete a sleep session by id.
+#[doc = r#"List the audit log of destructive operations, one page at a time.
 
-Accepts: `DELETE /api/sleep/{id}`
+Accepts: `GET /api/admin/audit?entity=&action=&from=&to=&cursor=&limit=`
+- Returns [`crate::models::AuditPage`]: entries newest first, plus `next_cursor` to pass back
+  as `cursor` for the next page (`null` on the last page). See [`crate::models::AuditQuery`].
+- `Accept: text/csv` or `format=csv` returns the page's entries as CSV; the next cursor is then
+  only available in the `X-Next-Cursor` response header (set for both formats).
+- Entries archived by the `telemetry_archive` job ([`crate::jobs::ARCHIVED_TABLES`]) are
+  included.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
-- Requires CSRF ([`CsrfGuard`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 204 No Content — deleted or already absent
-- 401 Unauthorized — no/invalid session
-- 403 Forbidden — CSRF failure
-
-See also: [`crate::handlers::delete_sleep`]
+- 200 OK
+- 400 Bad Request — invalid filters, `limit` outside 1..=1000, or `from` after `to`
+- 401 Unauthorized
 "#]
-async fn delete_sleep(
+async fn get_admin_audit(
     State(db): State<Db>,
-    Path(id): Path<i64>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    _csrf: CsrfGuard,
+    format: ResponseFormat,
+    axum::extract::Query(query): axum::extract::Query<AuditQuery>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let _affected = handlers::delete_sleep(&db, id).await?;
-    Ok(StatusCode::NO_CONTENT)
+    let page = handlers::list_audit_log(&db, &query).await?;
+    let mut headers = axum::http::HeaderMap::new();
+    if let Some(cursor) = page.next_cursor {
+        headers.insert("x-next-cursor", cursor.into());
+    }
+    Ok((headers, format.render(page)))
 }
 
-#[doc = r#"Create an exercise entry.
+#[doc = r#"Summarize recent Content-Security-Policy violation reports.
 
-Accepts: `POST /exercise` (`application/json`)
-- Body: [`ExerciseInput`]
+Accepts: `GET /api/admin/csp-reports?days=`
+- Returns [`crate::csp_reports::CspReportSummary`] for the last `days` days (default 7, at
+  most 90): the total and the reports grouped by violated directive, blocked URI and
+  disposition, most frequent first.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
-- Requires CSRF ([`CsrfGuard`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 201 Created — `{"id": <number>}`
+- 200 OK
+- 400 Bad Request — `days` outside 1..=90
 - 401 Unauthorized
-- 403 Forbidden — CSRF failure
 
-See also: [`crate::handlers::create_exercise`]
+See also: [`crate::csp_reports::summarize`]
 "#]
-async fn create_exercise(
+async fn get_admin_csp_reports(
     State(db): State<Db>,
+    State(clock): State<SharedClock>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    _csrf: CsrfGuard,
-    Json(input): Json<ExerciseInput>,
+    axum::extract::Query(q): axum::extract::Query<crate::csp_reports::SummaryQuery>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let id = handlers::create_exercise(&db, input).await?;
-    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+    Ok(Json(
+        crate::csp_reports::summarize(&db, &q, clock.now_utc().naive_utc()).await?,
+    ))
 }
 
-#[doc = r#"Create a note.
+#[doc = r#"Report how often legacy and aliased endpoints are still called.
 
-Accepts: `POST /note` (`application/json`)
-- Body: [`NoteInput`]
+Accepts: `GET /api/admin/deprecations`
+- Returns [`crate::deprecation::DeprecationReport`]: for every tracked endpoint its replacement,
+  call count, and first and last call, so compatibility shims can be removed once unused.
+  Counts include calls not yet flushed to the database.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
-- Requires CSRF ([`CsrfGuard`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 201 Created — `{"id": <number>}`
+- 200 OK
 - 401 Unauthorized
-- 403 Forbidden — CSRF failure
 
-See also: [`crate::handlers::create_note`]
+See also: [`crate::deprecation::track`]
 "#]
-async fn create_note(
+async fn get_admin_deprecations(
     State(db): State<Db>,
+    State(counters): State<DeprecationCounters>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    _csrf: CsrfGuard,
-    Json(input): Json<NoteInput>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let id = handlers::create_note(&db, input).await?;
-    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+    Ok(Json(crate::deprecation::report(&db, &counters).await?))
 }
 
-#[derive(serde::Deserialize)]
-struct FrictionBacklogParams {
-    window_days: Option<i64>,
-    to: Option<String>,
+#[doc = r#"Expose server metrics in the Prometheus text format.
+
+Accepts: `GET /api/admin/metrics`
+- Returns `text/plain; version=0.0.4`: `sleep_api_deprecated_calls_total{endpoint}`, the calls
+  to each legacy endpoint since startup ([`crate::deprecation::metrics`]).
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`]) or an `admin` API token, so a scraper
+  can use a bearer token.
+
+Responses:
+- 200 OK
+- 401 Unauthorized
+
+See also: [`crate::deprecation::track`]
+"#]
+async fn get_admin_metrics(
+    State(counters): State<DeprecationCounters>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> impl axum::response::IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4",
+        )],
+        crate::deprecation::metrics(&counters),
+    )
 }
 
-#[doc = r#"Ingest one friction telemetry event.
+#[doc = r#"Run a background job immediately, outside its schedule.
 
-Accepts: `POST /api/personalization/friction-telemetry` (`application/json`)
-- Body: [`FrictionTelemetryInput`]
+Accepts: `POST /api/admin/jobs/{name}/run`
+- Runs synchronously and returns the recorded run; job failures are reported in the run's
+  `status`/`detail` rather than as an HTTP error.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 - Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 201 Created — `{ "id": <number> }`
-- 400 Bad Request — invalid telemetry payload
+- 200 OK — [`crate::models::JobRun`]
 - 401 Unauthorized
 - 403 Forbidden — CSRF failure
+- 404 Not Found — unknown job
+
+See also: [`crate::jobs::run_job`]
 "#]
-async fn post_friction_telemetry(
+async fn post_admin_job_run(
     State(db): State<Db>,
+    State(time): State<TimeContext>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
     _csrf: CsrfGuard,
-    Json(input): Json<FrictionTelemetryInput>,
+    ValidPath(name): ValidPath<String>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let id = handlers::create_friction_telemetry(&db, input).await?;
-    Ok((StatusCode::CREATED, Json(json!({"id": id}))))
+    Ok(Json(handlers::run_job_now(&db, &time, &name).await?))
 }
 
-#[doc = r#"Return ranked friction backlog proposals with evidence.
+#[doc = r#"List in-progress expand/contract schema changes.
 
-Accepts: `GET /api/personalization/friction-backlog?window_days=28&to=YYYY-MM-DD`
-- `window_days` optional rolling window (1..=365), default 28
-- `to` optional inclusive end date, defaults to server current UTC date
+Accepts: `GET /api/admin/schema-changes`
+- Returns one [`crate::schema_change::SchemaChangeStatus`] per registered change: its phase
+  (`dual_write`, `backfilled`, `read_new`) and how many rows still await the backfill.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 200 OK — ranked proposals with evidence, expected benefit, confidence and rollback condition
-- 400 Bad Request — invalid params
+- 200 OK
 - 401 Unauthorized
+
+See also: [`crate::schema_change`]
 "#]
-async fn get_friction_backlog(
+async fn get_admin_schema_changes(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    axum::extract::Query(params): axum::extract::Query<FrictionBacklogParams>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let parsed_to = match params.to {
-        Some(value) => Some(
-            chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
-                .map_err(|_| ApiError::InvalidInput("invalid to date".into()))?,
-        ),
-        None => None,
-    };
-    let window_days = params.window_days.unwrap_or(28);
-    let response = handlers::friction_backlog(&db, window_days, parsed_to).await?;
-    Ok(Json(response))
+    Ok(Json(handlers::list_schema_changes(&db).await?))
 }
 
-#[derive(serde::Deserialize)]
-struct RecentParams {
-    days: Option<i32>,
+#[doc = r#"Switch reads of a schema change to its new column.
+
+Accepts: `POST /api/admin/schema-changes/{name}/switch`
+- Allowed once the backfill job has marked the change `backfilled`; repeating it is a no-op.
+
+Security:
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
+- Requires CSRF ([`CsrfGuard`])
+
+Responses:
+- 200 OK — [`crate::schema_change::SchemaChangeStatus`]
+- 400 Bad Request — backfill not finished
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — unknown change
+
+See also: [`crate::handlers::switch_schema_change`]
+"#]
+async fn post_admin_schema_change_switch(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    _csrf: CsrfGuard,
+    ValidPath(name): ValidPath<String>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::switch_schema_change(&db, &name).await?))
 }
 
-#[derive(serde::Deserialize)]
-struct RangeParams {
-    from: chrono::NaiveDate,
-    to: chrono::NaiveDate,
+/// `BACKUP_DIR` for the current tenant; backups respond 404 when it is not configured.
+fn backup_root() -> Result<std::path::PathBuf, ApiError> {
+    crate::config::backup_dir().ok_or(ApiError::NotFound)
 }
 
-#[doc = r#"List recent sleep entries.
+#[doc = r#"List backups, newest first.
 
-Accepts: `GET /api/sleep/recent?days=7`
-- days clamped to [1, 31]; defaults to 7 when missing
+Accepts: `GET /api/admin/backups`
+- Returns [`crate::export::ExportSummary`] entries for each export directory under `BACKUP_DIR`
+  (written by `POST /api/admin/backups` or `sleepctl export --out $BACKUP_DIR/<name>`).
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 200 OK — `Vec<SleepListItem>` (ordered desc by date)
-- 400 Bad Request — `{code,message}` on invalid params
+- 200 OK
+- 401 Unauthorized
+- 404 Not Found — `BACKUP_DIR` is not set
 "#]
-async fn get_sleep_recent(
-    State(db): State<Db>,
+async fn get_admin_backups(
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    axum::extract::Query(params): axum::extract::Query<RecentParams>,
-) -> impl IntoResponse {
-    let days = match params.days {
-        None => 7,
-        Some(d) if (1..=31).contains(&d) => d,
-        _ => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"code":"bad_request","message":"days must be between 1 and 31"})),
-            )
-                .into_response();
-        }
-    };
-    match crate::repository::list_recent_sleep(&db, days).await {
-        Ok(items) => Json(items).into_response(),
-        Err(e) => ApiError::Db(e).into_response(),
-    }
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    Ok(Json(handlers::list_backups(&backup_root()?)?))
 }
 
-#[doc = r#"List sleep sessions in an inclusive date range.
+#[doc = r#"Write a backup: a full export (database snapshot, sessions CSV, signed manifest).
 
-Accepts: `GET /api/sleep/range?from=YYYY-MM-DD&to=YYYY-MM-DD`
-- Validates `from <= to`
-- Range length must be ≤ 62 days
+Accepts: `POST /api/admin/backups`
+- Writes into `BACKUP_DIR/<UTC timestamp>` and returns its [`crate::export::ExportSummary`].
+  The manifest is signed with `EXPORT_SIGNING_KEY` when set.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
+- Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 200 OK — `Vec<SleepListItem>` (per-session rows ordered asc by date)
-- 400 Bad Request — `{code,message}` on invalid params
+- 201 Created
+- 400 Bad Request — a backup with the same timestamp exists, or the database is in-memory
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+- 404 Not Found — `BACKUP_DIR` is not set
+
+See also: [`crate::export`]
 "#]
-async fn get_sleep_range(
+async fn post_admin_backup(
     State(db): State<Db>,
+    State(clock): State<SharedClock>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    axum::extract::Query(params): axum::extract::Query<RangeParams>,
-) -> impl IntoResponse {
-    if params.from > params.to {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"code":"bad_request","message":"from must be <= to"})),
-        )
-            .into_response();
-    }
-    let span_days = (params.to - params.from).num_days() + 1;
-    if span_days > 62 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"code":"bad_request","message":"range must be <= 62 days"})),
-        )
-            .into_response();
-    }
-    match crate::repository::list_sleep_range(&db, params.from, params.to).await {
-        Ok(items) => Json(items).into_response(),
-        Err(e) => ApiError::Db(e).into_response(),
-    }
+    _csrf: CsrfGuard,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let root = backup_root()?;
+    let key = crate::config::export_signing_key();
+    let created = handlers::create_backup(&db, &root, key.as_deref(), clock.now_utc()).await?;
+    Ok((StatusCode::CREATED, Json(created)))
 }
 
-#[doc = r#"Get a sleep session by id.
+#[doc = r#"Report which sessions changed or disappeared since a backup was taken.
 
-Accepts: `GET /api/sleep/{id}`
+Accepts: `POST /api/admin/compare-export` (`application/json`)
+- Body: the backup's `manifest.json` as downloaded ([`crate::export::Manifest`], version 2 or
+  later). Sessions are matched by id against its per-record digests.
+- With `EXPORT_SIGNING_KEY` set, a signed manifest must match it.
+- Returns [`crate::export::CompareReport`]: `changed` and `missing` records, with counts of
+  `unchanged` and `added` sessions. Nothing is written.
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
+- Requires CSRF ([`CsrfGuard`])
 
 Responses:
-- 200 OK — [`SleepSession`]
-- 401 Unauthorized — no/invalid session
-- 404 Not Found — no entry for id
+- 200 OK
+- 400 Bad Request — malformed or version 1 manifest, or signature mismatch
+- 401 Unauthorized
+- 403 Forbidden — CSRF failure
+
+See also: [`crate::export::compare_export`]
 "#]
-async fn get_sleep_by_id(
+async fn post_admin_compare_export(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    Path(id): Path<i64>,
+    _csrf: CsrfGuard,
+    Json(manifest): Json<crate::export::Manifest>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    match crate::repository::find_sleep_by_id(&db, id).await? {
-        Some(s) => Ok(Json(s)),
-        None => Err(ApiError::NotFound),
-    }
+    let key = crate::config::export_signing_key();
+    Ok(Json(
+        handlers::compare_backup(&db, &manifest, key.as_deref()).await?,
+    ))
 }
 
-#[doc = r#"List exercise intensity for a date range.
+#[doc = r#"Download one file of a backup, resumably.
 
-Accepts: `GET /api/exercise/intensity?from=YYYY-MM-DD&to=YYYY-MM-DD`
-- Validates `from <= to`
-- Range length must be ≤ 62 days
+Accepts: `GET|HEAD /api/admin/backups/{name}/{file}`
+- Only files listed in the backup's manifest are served. The `ETag` is the file's SHA-256 from
+  the manifest, so a resumed download can be verified against the manifest afterwards.
+- Supports `Range: bytes=<start>-[<end>]` and `If-Range: <etag>` to continue an interrupted
+  transfer; the body is never content-encoded. See [`crate::download`].
 
 Security:
-- Requires authenticated session ([`RequireSessionJson`])
+- Requires authenticated session ([`RequireSessionJson`]); the single session user is the admin.
 
 Responses:
-- 200 OK — `Vec<{date, intensity}>` ordered asc by date
-- 400 Bad Request — `{code,message}` on invalid params
+- 200 OK — whole file (also when `If-Range` does not match)
+- 206 Partial Content — the requested range
+- 401 Unauthorized
+- 404 Not Found — unknown backup or file, or `BACKUP_DIR` is not set
+- 416 Range Not Satisfiable
 "#]
-async fn get_exercise_intensity(
-    State(db): State<Db>,
+async fn get_admin_backup_file(
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
-    axum::extract::Query(params): axum::extract::Query<RangeParams>,
-) -> impl IntoResponse {
-    if params.from > params.to {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"code":"bad_request","message":"from must be <= to"})),
-        )
-            .into_response();
-    }
-    let span_days = (params.to - params.from).num_days() + 1;
-    if span_days > 62 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"code":"bad_request","message":"range must be <= 62 days"})),
-        )
-            .into_response();
-    }
-    match crate::repository::list_exercise_intensity(&db, params.from, params.to).await {
-        Ok(items) => Json(items).into_response(),
-        Err(e) => ApiError::Db(e).into_response(),
-    }
+    axum::extract::Path((name, file)): axum::extract::Path<(String, String)>,
+    method: axum::http::Method,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let (path, entry) =
+        crate::export::find_file(&backup_root()?, &name, &file).ok_or(ApiError::NotFound)?;
+    Ok(
+        crate::download::serve_resumable(&method, &headers, &path, &entry.sha256, &entry.path)
+            .await,
+    )
 }
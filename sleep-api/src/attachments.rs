@@ -0,0 +1,165 @@
+#![doc = r#"Attachments: files kept alongside a day's entries
+
+Photos and audio memos are uploaded with `POST /api/attachments` and stored under
+`ATTACHMENT_DIR` (see [`config::attachment_dir`]) as `<id>`, next to the `attachments` row
+holding their metadata. [`extract`] runs once at upload time so the day view never decodes an
+original:
+
+- Images (`image/*`): pixel size and a JPEG thumbnail at most [`THUMB_EDGE`] pixels on its
+  longer side, stored as `<id>.thumb.jpg` and served by `GET /api/attachment/{id}/thumb`. EXIF
+  orientation is applied first, so phone photos are upright. Needs the `image` cargo feature
+  (on by default); without it images are stored without size or thumbnail.
+- Audio (`audio/*`): duration in milliseconds, read from the container header or, when the
+  header has no frame count (e.g. some WebM/Ogg recordings), by walking the packets.
+
+Files the server cannot decode are still stored; their metadata fields stay empty.
+
+[`config::attachment_dir`]: crate::config::attachment_dir
+"#]
+
+use std::path::{Path, PathBuf};
+
+/// Largest accepted upload, in bytes (an audio memo of several minutes fits comfortably).
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Longer side of generated thumbnails, in pixels.
+#[cfg_attr(not(feature = "image"), allow(dead_code))]
+pub const THUMB_EDGE: u32 = 256;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[doc = r#"Metadata extracted from an uploaded file by [`extract`]."#]
+pub struct Media {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration_ms: Option<u64>,
+    /// JPEG preview bytes, for images.
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+#[doc = r#"Extract [`Media`] from `bytes` according to `content_type`.
+
+Decoding is CPU-bound; call it from a blocking task. Undecodable input yields
+[`Media::default`] rather than an error.
+
+# Example
+
+```rust
+# use sleep_api::attachments::{extract, Media};
+assert_eq!(extract("text/plain", b"hello"), Media::default());
+assert_eq!(extract("audio/wav", b"not a wav file"), Media::default());
+```
+"#]
+pub fn extract(content_type: &str, bytes: &[u8]) -> Media {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if essence.starts_with("image/") {
+        image_media(bytes)
+    } else if essence.starts_with("audio/") {
+        Media {
+            duration_ms: audio_duration_ms(&essence, bytes),
+            ..Media::default()
+        }
+    } else {
+        Media::default()
+    }
+}
+
+/// Path of the original file of attachment `id`.
+pub fn original_path(root: &Path, id: i64) -> PathBuf {
+    root.join(id.to_string())
+}
+
+/// Path of the thumbnail of attachment `id`.
+pub fn thumb_path(root: &Path, id: i64) -> PathBuf {
+    root.join(format!("{id}.thumb.jpg"))
+}
+
+#[cfg(feature = "image")]
+fn image_media(bytes: &[u8]) -> Media {
+    use image::{DynamicImage, ImageDecoder, ImageReader, metadata::Orientation};
+
+    let decoded = (|| {
+        let mut decoder = ImageReader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()?
+            .into_decoder()
+            .ok()?;
+        let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+        let mut img = DynamicImage::from_decoder(decoder).ok()?;
+        img.apply_orientation(orientation);
+        Some(img)
+    })();
+    let Some(img) = decoded else {
+        return Media::default();
+    };
+
+    let thumb = img.thumbnail(THUMB_EDGE, THUMB_EDGE).to_rgb8();
+    let mut jpeg = Vec::new();
+    let encoded = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 80)
+        .encode_image(&thumb)
+        .is_ok();
+    Media {
+        width: Some(img.width()),
+        height: Some(img.height()),
+        duration_ms: None,
+        thumbnail: encoded.then_some(jpeg),
+    }
+}
+
+#[cfg(not(feature = "image"))]
+fn image_media(_bytes: &[u8]) -> Media {
+    Media::default()
+}
+
+fn audio_duration_ms(mime: &str, bytes: &[u8]) -> Option<u64> {
+    use symphonia::core::{
+        formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+    };
+
+    let source = MediaSourceStream::new(
+        Box::new(std::io::Cursor::new(bytes.to_vec())),
+        Default::default(),
+    );
+    let mut hint = Hint::new();
+    hint.mime_type(mime);
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
+    let mut format = probed.format;
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let time_base = params.time_base.or_else(|| {
+        params
+            .sample_rate
+            .map(|rate| symphonia::core::units::TimeBase::new(1, rate))
+    })?;
+
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            // No frame count in the header: the end of the last packet is the duration.
+            let mut end = 0;
+            while let Ok(packet) = format.next_packet() {
+                if packet.track_id() == track_id {
+                    end = end.max(packet.ts() + packet.dur());
+                }
+            }
+            end
+        }
+    };
+    if frames == 0 {
+        return None;
+    }
+    let time = time_base.calc_time(frames);
+    Some(time.seconds * 1000 + (time.frac * 1000.0).round() as u64)
+}
@@ -75,39 +75,23 @@ pub fn current_user_from_cookie(jar: &PrivateCookieJar) -> Option<UserId> {
         .map(|c| c.value().to_string())
 }
 
-/// Verify provided email + password against configured ADMIN_EMAIL + ADMIN_PASSWORD_HASH.
-#[doc = r#"Verify provided `email` and `password` against configured admin credentials.
+/// Verify provided email + password against the login email and an admin password hash.
+#[doc = r#"Verify `email` against `login_email` and `password` against `hash`.
 
-Reads:
-- `ADMIN_EMAIL`
-- `ADMIN_PASSWORD_HASH` (`$argon2id$...`)
+`login_email` is `ADMIN_EMAIL`, or the address it was changed to (see
+`POST /api/account/email`). `hash` is the configured `ADMIN_PASSWORD_HASH` (`$argon2id$...`),
+or the upgraded hash stored for it (see [`security::password::effective_hash`]). An empty
+`hash` (no password configured) never matches.
 
-Returns `true` on a valid match; otherwise `false`."#]
-pub fn verify_login(email: &str, password: &str) -> bool {
-    use argon2::{
-        Argon2,
-        password_hash::{PasswordHash, PasswordVerifier},
-    };
+Returns `true` on a valid match; otherwise `false`.
 
-    let admin_email = crate::config::admin_email();
-    if email != admin_email {
-        return false;
-    }
-    let hash = crate::config::admin_password_hash();
-    if hash.is_empty() {
-        // Lock out if not configured
+[`security::password::effective_hash`]: crate::security::password::effective_hash
+"#]
+pub fn verify_login(email: &str, password: &str, login_email: &str, hash: &str) -> bool {
+    if email != login_email || hash.is_empty() {
         return false;
     }
-    let parsed = match PasswordHash::new(&hash) {
-        Ok(p) => p,
-        Err(e) => {
-            tracing::warn!(error=?e, "invalid ADMIN_PASSWORD_HASH value");
-            return false;
-        }
-    };
-    Argon2::default()
-        .verify_password(password.as_bytes(), &parsed)
-        .is_ok()
+    crate::security::password::verify(password, hash)
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,7 +1,9 @@
 //! Password hash generator (Argon2id)
 //!
 //! Reads a password from stdin and prints an `$argon2id$...` hash suitable for
-//! the `ADMIN_PASSWORD_HASH` environment variable.
+//! the `ADMIN_PASSWORD_HASH` environment variable. The hash uses the Argon2
+//! parameters of `ARGON2_MEMORY_KIB` / `ARGON2_ITERATIONS` / `ARGON2_PARALLELISM`
+//! (see `sleep_api::config::password_hash_policy`).
 //!
 //! Usage (examples):
 //! ```text
@@ -10,11 +12,6 @@
 //!
 //! Note: Input is echoed. For non-echoing input, consider the `rpassword` crate.
 
-use argon2::password_hash::rand_core::OsRng;
-use argon2::{
-    Argon2,
-    password_hash::{PasswordHasher, SaltString},
-};
 use std::io::{self, Read};
 
 fn main() {
@@ -28,10 +25,8 @@ fn main() {
         .expect("failed to read stdin");
     let password = buf.trim_end_matches(&['\n', '\r'][..]).as_bytes();
 
-    let salt = SaltString::generate(OsRng);
-    let argon2 = Argon2::default();
-    let hash = argon2
-        .hash_password(password, &salt)
+    let hash = sleep_api::config::password_hash_policy()
+        .hash(password)
         .expect("hashing failed");
     println!("{hash}");
 }
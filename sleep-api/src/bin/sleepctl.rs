@@ -0,0 +1,306 @@
+//! SleepTracker maintenance CLI
+//!
+//! Subcommands:
+//! - `gen-types [--out PATH] [--check]` — write the UI's TypeScript API types generated
+//!   from the Rust models (default `sleep-ui/src/lib/api-types.gen.ts`). With `--check`,
+//!   nothing is written and the exit code is 1 when the file is stale.
+//! - `export --out DIR` — write a full export of `DATABASE_URL` (database snapshot, sessions
+//!   CSV, and a manifest signed with `EXPORT_SIGNING_KEY`; see `sleep_api::export`).
+//! - `verify-export DIR [--require-signature]` — check every file of an export against its
+//!   manifest and the manifest signature. Exits 1 on a corrupted or missing file, an invalid
+//!   signature, or (with `--require-signature`) a signature that could not be checked.
+//! - `salvage SRC --out DEST` — copy every readable row of a corrupted database file into a new,
+//!   migrated file (see `sleep_api::integrity::salvage`). `DEST` must not exist. Exits 1 when
+//!   rows were lost or the new file fails its integrity check; the new file is kept either way.
+//! - `smoke --url URL [--email EMAIL]` — log in to a running instance, create, read and delete a
+//!   test sleep session, read trends and log out (see `sleep_api::smoke`). The password is read
+//!   from `SMOKE_PASSWORD`; the email defaults to `SMOKE_EMAIL`. Exits 1 when any step fails.
+//!
+//! Usage (examples):
+//! ```text
+//! cargo run -p sleep-api --bin sleepctl -- gen-types
+//! cargo run -p sleep-api --bin sleepctl -- gen-types --check
+//! cargo run -p sleep-api --bin sleepctl -- export --out backups/2025-06-01
+//! cargo run -p sleep-api --bin sleepctl -- verify-export backups/2025-06-01 --require-signature
+//! cargo run -p sleep-api --bin sleepctl -- salvage data/sleep.db --out data/sleep.salvaged.db
+//! SMOKE_PASSWORD=... cargo run -p sleep-api --bin sleepctl -- smoke --url https://sleep.example.org --email me@example.org
+//! ```
+
+use sleep_api::export::{self, SignatureStatus};
+use sleep_api::integrity;
+use sleep_api::smoke;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const DEFAULT_TYPES_OUT: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../sleep-ui/src/lib/api-types.gen.ts"
+);
+
+const USAGE: &str = "usage: sleepctl gen-types [--out PATH] [--check]
+       sleepctl export --out DIR
+       sleepctl verify-export DIR [--require-signature]
+       sleepctl salvage SRC --out DEST
+       sleepctl smoke --url URL [--email EMAIL]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("gen-types") => gen_types(&args[1..]),
+        Some("export") => export_cmd(&args[1..]),
+        Some("verify-export") => verify_export_cmd(&args[1..]),
+        Some("salvage") => salvage_cmd(&args[1..]),
+        Some("smoke") => smoke_cmd(&args[1..]),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn gen_types(args: &[String]) -> ExitCode {
+    let mut out = PathBuf::from(DEFAULT_TYPES_OUT);
+    let mut check = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--out" => match args.next() {
+                Some(path) => out = PathBuf::from(path),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::from(2);
+                }
+            },
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let generated = sleep_api::typegen::typescript();
+    if check {
+        let current = std::fs::read_to_string(&out).unwrap_or_default();
+        if current == generated {
+            return ExitCode::SUCCESS;
+        }
+        eprintln!("{} is out of date; run `sleepctl gen-types`", out.display());
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = std::fs::write(&out, generated) {
+        eprintln!("failed to write {}: {e}", out.display());
+        return ExitCode::FAILURE;
+    }
+    eprintln!("wrote {}", out.display());
+    ExitCode::SUCCESS
+}
+
+fn export_cmd(args: &[String]) -> ExitCode {
+    let out = match args {
+        [flag, dir] if flag == "--out" => PathBuf::from(dir),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("failed to start runtime: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = runtime.block_on(async {
+        let db = sleep_api::db::connect().await?;
+        let key = sleep_api::config::export_signing_key();
+        export::write_export(&db, &out, key.as_deref(), chrono::Utc::now()).await
+    });
+    match result {
+        Ok(manifest) => {
+            for file in &manifest.files {
+                eprintln!("{}  {} ({} bytes)", file.sha256, file.path, file.size);
+            }
+            if manifest.signature.is_none() {
+                eprintln!("warning: EXPORT_SIGNING_KEY is not set; manifest is unsigned");
+            }
+            eprintln!("wrote {}", out.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("export failed: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn verify_export_cmd(args: &[String]) -> ExitCode {
+    let mut dir = None;
+    let mut require_signature = false;
+    for arg in args {
+        match arg.as_str() {
+            "--require-signature" => require_signature = true,
+            other if dir.is_none() && !other.starts_with("--") => dir = Some(PathBuf::from(other)),
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(dir) = dir else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    dotenvy::dotenv().ok();
+    let key = sleep_api::config::export_signing_key();
+    let report = match export::verify_export(&dir, key.as_deref()) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("verify failed: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for path in &report.verified {
+        eprintln!("ok        {path}");
+    }
+    for path in &report.corrupted {
+        eprintln!("CORRUPTED {path}");
+    }
+    for path in &report.missing {
+        eprintln!("MISSING   {path}");
+    }
+    for path in &report.unlisted {
+        eprintln!("unlisted  {path}");
+    }
+    let signature = match report.signature {
+        SignatureStatus::Valid => "valid",
+        SignatureStatus::Invalid => "INVALID",
+        SignatureStatus::Unsigned => "unsigned manifest",
+        SignatureStatus::Unchecked => "not checked (EXPORT_SIGNING_KEY is not set)",
+    };
+    eprintln!("signature: {signature}");
+
+    let signed = report.signature == SignatureStatus::Valid;
+    if report.ok() && (signed || !require_signature) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn salvage_cmd(args: &[String]) -> ExitCode {
+    let (src, dest) = match args {
+        [src, flag, dest] if flag == "--out" => (PathBuf::from(src), PathBuf::from(dest)),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("failed to start runtime: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let report = match runtime.block_on(integrity::salvage(&src, &dest)) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("salvage failed: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for table in &report.tables {
+        match &table.error {
+            Some(error) => eprintln!("UNREADABLE {}: {error}", table.table),
+            None if table.lost > 0 => eprintln!(
+                "partial    {}: {} copied, {} lost",
+                table.table, table.copied, table.lost
+            ),
+            None => eprintln!("ok         {}: {} copied", table.table, table.copied),
+        }
+    }
+    if report.foreign_key_violations > 0 {
+        eprintln!(
+            "warning: {} rows reference lost parents",
+            report.foreign_key_violations
+        );
+    }
+    for problem in &report.integrity.problems {
+        eprintln!("new file integrity: {problem}");
+    }
+    eprintln!("wrote {}", dest.display());
+    if report.complete() && report.integrity.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn smoke_cmd(args: &[String]) -> ExitCode {
+    dotenvy::dotenv().ok();
+    let mut url = None;
+    let mut email = std::env::var("SMOKE_EMAIL").ok();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--url" => &mut url,
+            "--email" => &mut email,
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        };
+        match args.next() {
+            Some(value) => *slot = Some(value.clone()),
+            None => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(url) = url else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let Some(email) = email else {
+        eprintln!("smoke: pass --email or set SMOKE_EMAIL");
+        return ExitCode::from(2);
+    };
+    let Ok(password) = std::env::var("SMOKE_PASSWORD") else {
+        eprintln!("smoke: set SMOKE_PASSWORD");
+        return ExitCode::from(2);
+    };
+
+    let client = match smoke::client() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("failed to build HTTP client: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("failed to start runtime: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let report = runtime.block_on(smoke::run(&client, &url, &email, &password));
+    for step in &report.steps {
+        eprintln!(
+            "{:<6} {:<7} {:>5}ms  {}",
+            if step.ok { "ok" } else { "FAILED" },
+            step.name,
+            step.elapsed.as_millis(),
+            step.detail
+        );
+    }
+    if report.ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
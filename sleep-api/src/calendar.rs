@@ -0,0 +1,81 @@
+#![doc = r#"Day series
+
+Helpers that expand an inclusive `from`/`to` range into every day in it, so gap-aware features
+(completeness, missing entries, per-day series with holes) share one calendar expansion:
+
+- [`days`] iterates the dates in Rust.
+- [`fill_days`] aligns dated rows to the full series, with `None` for days without a row.
+- [`DAY_SERIES_CTE`] does the same in SQL, for queries that `LEFT JOIN` data onto each day.
+
+# Example
+
+```rust
+# use chrono::NaiveDate;
+# use sleep_api::calendar::{days, fill_days};
+let d = |day| NaiveDate::from_ymd_opt(2025, 6, day).unwrap();
+assert_eq!(days(d(1), d(3)).count(), 3);
+assert_eq!(
+    fill_days(d(1), d(3), [(d(2), 7.5)]),
+    vec![(d(1), None), (d(2), Some(7.5)), (d(3), None)]
+);
+```
+"#]
+
+use chrono::NaiveDate;
+
+#[doc = r#"Recursive CTE `days(day)` with one `YYYY-MM-DD` row per date from the first to the
+second bound parameter, inclusive.
+
+Prefix a query with `WITH RECURSIVE` and this CTE and bind `from` and `to` first:
+
+```sql
+WITH RECURSIVE {DAY_SERIES_CTE}
+SELECT d.day FROM days d LEFT JOIN notes n ON n.date = d.day WHERE n.id IS NULL
+```
+"#]
+pub const DAY_SERIES_CTE: &str = "days(day) AS ( \
+     SELECT date(?) \
+     UNION ALL \
+     SELECT date(day, '+1 day') FROM days WHERE day < date(?) \
+ )";
+
+#[doc = r#"Every date from `from` to `to`, inclusive (empty when `from > to`)."#]
+pub fn days(from: NaiveDate, to: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    from.iter_days().take_while(move |d| *d <= to)
+}
+
+#[doc = r#"One entry per date from `from` to `to`, carrying the value of `rows` for that date.
+
+Rows outside the range are ignored; when several rows share a date, the last one wins."#]
+pub fn fill_days<T>(
+    from: NaiveDate,
+    to: NaiveDate,
+    rows: impl IntoIterator<Item = (NaiveDate, T)>,
+) -> Vec<(NaiveDate, Option<T>)> {
+    let mut series: Vec<(NaiveDate, Option<T>)> = days(from, to).map(|d| (d, None)).collect();
+    for (date, value) in rows {
+        if date < from {
+            continue;
+        }
+        if let Some(slot) = series.get_mut((date - from).num_days() as usize) {
+            slot.1 = Some(value);
+        }
+    }
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, day).unwrap()
+    }
+
+    #[test]
+    fn fill_days_ignores_rows_outside_the_range() {
+        let series = fill_days(d(2), d(3), [(d(1), 1), (d(3), 3), (d(3), 4), (d(9), 9)]);
+        assert_eq!(series, vec![(d(2), None), (d(3), Some(4))]);
+        assert!(fill_days(d(3), d(2), [(d(3), 1)]).is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+#![doc = r#"Data completeness
+
+Reports, per day, which kinds of data were logged and how complete the range is overall.
+The UI's "streak of complete days" and the reminder logic read this instead of querying
+each table themselves.
+
+Endpoints:
+- `GET /api/stats/completeness?from=YYYY-MM-DD&to=YYYY-MM-DD`
+
+Data kinds, keyed by the day they describe:
+- `sleep`: a sleep session wakes on the day.
+- `exercise`: any exercise entry on the day, including an explicit `none` intensity.
+- `note`: a non-empty note on the day.
+- `factors`: context for the night ending on the day: the pre-sleep routine recorded for
+  the previous evening, a sleep aid on that night's session, or a disturbance.
+- `check_in`: the morning check-in (`wake_feeling`) on the day's sleep.
+
+A day's `score_pct` is the share of the five kinds present; a day is `complete` when all are.
+"#]
+
+use crate::middleware::auth_layer::RequireSessionJson;
+use crate::{calendar, db::Db, error::ApiError, extract::DateRange};
+use axum::{Json, extract::State};
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sqlx::Sqlite;
+use std::collections::{BTreeMap, HashSet};
+
+/// Longest range accepted by `GET /api/stats/completeness`.
+const MAX_COMPLETENESS_DAYS: i64 = 366;
+
+/// Number of data kinds scored per day.
+const DATA_KINDS: usize = 5;
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Which data kinds are present on one day."#]
+pub struct DayCompleteness {
+    pub date: NaiveDate,
+    pub sleep: bool,
+    pub exercise: bool,
+    pub note: bool,
+    pub factors: bool,
+    pub check_in: bool,
+    pub score_pct: f64,
+    pub complete: bool,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[doc = r#"Per-day completeness with range totals.
+
+- `completeness_pct`: mean of the daily scores.
+- `current_streak`: consecutive complete days ending at `to` (0 when `to` is incomplete).
+- `longest_streak`: longest run of complete days within the range.
+"#]
+pub struct CompletenessResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: Vec<DayCompleteness>,
+    pub complete_days: usize,
+    pub completeness_pct: f64,
+    pub current_streak: usize,
+    pub longest_streak: usize,
+}
+
+const PRESENCE_SQL: &str = r#"
+    SELECT wake_date AS date, 'sleep' AS kind
+    FROM v_daily_sleep WHERE wake_date BETWEEN ?1 AND ?2
+    UNION
+    SELECT wake_date, 'check_in'
+    FROM v_daily_sleep WHERE wake_date BETWEEN ?1 AND ?2 AND wake_feeling IS NOT NULL
+    UNION
+    SELECT date, 'exercise'
+    FROM exercise_events WHERE date BETWEEN ?1 AND ?2
+    UNION
+    SELECT date, 'note'
+    FROM notes WHERE date BETWEEN ?1 AND ?2 AND TRIM(COALESCE(body, '')) <> ''
+    UNION
+    SELECT date(date, '+1 day'), 'factors'
+    FROM routine_entries WHERE date BETWEEN date(?1, '-1 day') AND date(?2, '-1 day')
+    UNION
+    SELECT COALESCE(s.session_date, s.date), 'factors'
+    FROM sleep_aids a JOIN sleep_sessions s ON s.id = a.session_id
+    WHERE COALESCE(s.session_date, s.date) BETWEEN ?1 AND ?2
+    UNION
+    SELECT date, 'factors'
+    FROM disturbances WHERE date BETWEEN ?1 AND ?2
+"#;
+
+#[doc = r#"Compute completeness for every day in `from..=to`.
+
+# Errors
+- Returns [`sqlx::Error`] on database errors.
+"#]
+pub async fn completeness_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<CompletenessResponse, sqlx::Error> {
+    let rows = sqlx::query_as::<Sqlite, (NaiveDate, String)>(PRESENCE_SQL)
+        .bind(from)
+        .bind(to)
+        .fetch_all(db)
+        .await?;
+    let present: HashSet<(NaiveDate, String)> = rows.into_iter().collect();
+    Ok(summarize(from, to, &present))
+}
+
+fn summarize(
+    from: NaiveDate,
+    to: NaiveDate,
+    present: &HashSet<(NaiveDate, String)>,
+) -> CompletenessResponse {
+    let has = |date: NaiveDate, kind: &str| present.contains(&(date, kind.to_string()));
+    let mut by_date = BTreeMap::new();
+    for date in calendar::days(from, to) {
+        let flags = [
+            has(date, "sleep"),
+            has(date, "exercise"),
+            has(date, "note"),
+            has(date, "factors"),
+            has(date, "check_in"),
+        ];
+        let count = flags.iter().filter(|f| **f).count();
+        by_date.insert(
+            date,
+            DayCompleteness {
+                date,
+                sleep: flags[0],
+                exercise: flags[1],
+                note: flags[2],
+                factors: flags[3],
+                check_in: flags[4],
+                score_pct: round1(count as f64 * 100.0 / DATA_KINDS as f64),
+                complete: count == DATA_KINDS,
+            },
+        );
+    }
+    let days: Vec<DayCompleteness> = by_date.into_values().collect();
+
+    let complete_days = days.iter().filter(|d| d.complete).count();
+    let completeness_pct = if days.is_empty() {
+        0.0
+    } else {
+        round1(days.iter().map(|d| d.score_pct).sum::<f64>() / days.len() as f64)
+    };
+    let current_streak = days.iter().rev().take_while(|d| d.complete).count();
+    let mut longest_streak = 0;
+    let mut run = 0;
+    for day in &days {
+        run = if day.complete { run + 1 } else { 0 };
+        longest_streak = longest_streak.max(run);
+    }
+
+    CompletenessResponse {
+        from,
+        to,
+        days,
+        complete_days,
+        completeness_pct,
+        current_streak,
+        longest_streak,
+    }
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+#[doc = r#"Return per-day data completeness for a date range.
+
+Accepts: `GET /api/stats/completeness?from=YYYY-MM-DD&to=YYYY-MM-DD` (at most 366 days)
+
+Errors:
+- Returns an API error for an invalid or too long range.
+- Returns an API error on database failures.
+"#]
+pub async fn completeness(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_COMPLETENESS_DAYS>,
+) -> Result<Json<CompletenessResponse>, ApiError> {
+    Ok(Json(completeness_range(&db, range.from, range.to).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, day).unwrap()
+    }
+
+    fn all_kinds(day: u32) -> Vec<(NaiveDate, String)> {
+        ["sleep", "exercise", "note", "factors", "check_in"]
+            .iter()
+            .map(|k| (d(day), k.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn scores_and_streaks() {
+        let mut present: HashSet<_> = [1, 2, 4, 5].into_iter().flat_map(all_kinds).collect();
+        present.insert((d(3), "sleep".into()));
+        present.insert((d(3), "check_in".into()));
+
+        let out = summarize(d(1), d(5), &present);
+        assert_eq!(out.days.len(), 5);
+        assert_eq!(out.days[2].score_pct, 40.0);
+        assert!(!out.days[2].complete && out.days[2].sleep && !out.days[2].note);
+        assert_eq!(out.complete_days, 4);
+        assert_eq!(out.completeness_pct, 88.0);
+        assert_eq!(out.current_streak, 2);
+        assert_eq!(out.longest_streak, 2);
+
+        let empty = summarize(d(1), d(1), &HashSet::new());
+        assert_eq!((empty.completeness_pct, empty.current_streak), (0.0, 0));
+    }
+}
@@ -7,10 +7,94 @@ time computations. See also: [`time::compute_duration_min`].
 "#]
 
 use chrono_tz::Tz;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Values from the config file, consulted before the process environment once loaded.
+struct ConfigFile {
+    path: PathBuf,
+    /// Variables set in the process environment before the file was read; these win.
+    process: HashSet<String>,
+    values: HashMap<String, String>,
+}
+
+static CONFIG_FILE: RwLock<Option<ConfigFile>> = RwLock::new(None);
+
+/// Read a setting: process environment first, then the (re)loaded config file.
+fn var(name: impl AsRef<str>) -> Result<String, std::env::VarError> {
+    let name = name.as_ref();
+    let file = CONFIG_FILE.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(file) = file.as_ref()
+        && !file.process.contains(name)
+    {
+        return file
+            .values
+            .get(name)
+            .cloned()
+            .ok_or(std::env::VarError::NotPresent);
+    }
+    std::env::var(name)
+}
+
+fn read_config_file(path: &std::path::Path) -> Result<HashMap<String, String>, String> {
+    dotenvy::from_path_iter(path)
+        .and_then(|iter| iter.collect::<Result<HashMap<_, _>, _>>())
+        .map_err(|e| e.to_string())
+}
+
+#[doc = r#"Load the config file at startup so that [`reload_config_file`] can re-read it later.
+
+The file is `CONFIG_FILE` when set, else the `.env` found by [`dotenvy`]. Variables already
+set in the process environment keep precedence over the file, at startup and on every reload.
+Returns the loaded path, or `None` when there is no file (settings then come from the
+environment only and reloads are refused)."#]
+pub fn load_config_file() -> Option<PathBuf> {
+    let process: HashSet<String> = std::env::vars_os()
+        .filter_map(|(k, _)| k.into_string().ok())
+        .collect();
+    let path = match std::env::var("CONFIG_FILE") {
+        Ok(p) if !p.trim().is_empty() => {
+            let path = PathBuf::from(p);
+            // Also export it, for startup-only settings read from the environment (DATABASE_URL).
+            dotenvy::from_path(&path).ok();
+            path
+        }
+        _ => dotenvy::dotenv().ok()?,
+    };
+    match read_config_file(&path) {
+        Ok(values) => {
+            *CONFIG_FILE.write().unwrap_or_else(|e| e.into_inner()) = Some(ConfigFile {
+                path: path.clone(),
+                process,
+                values,
+            });
+            Some(path)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "ignoring unreadable config file");
+            None
+        }
+    }
+}
+
+#[doc = r#"Re-read the config file loaded by [`load_config_file`].
+
+On success, settings read per use (admin credentials, notification webhook, ingest secrets,
+edit window, ...) see the new values immediately; components built once are rebuilt by
+[`crate::reload`]. On failure the previous values stay in effect."#]
+pub fn reload_config_file() -> Result<PathBuf, String> {
+    let mut guard = CONFIG_FILE.write().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = guard.as_mut() else {
+        return Err("no config file was loaded at startup".into());
+    };
+    file.values = read_config_file(&file.path)?;
+    Ok(file.path.clone())
+}
 
 fn env_flag(name: &str, default: bool) -> bool {
-    match std::env::var(name) {
+    match var(name) {
         Ok(v) => v == "1" || v.eq_ignore_ascii_case("true"),
         Err(_) => default,
     }
@@ -44,7 +128,7 @@ assert_eq!(tz2, chrono_tz::Asia::Tokyo);
 [`time::compute_duration_min`]: crate::time::compute_duration_min
 "#]
 pub fn app_tz() -> Tz {
-    let name = std::env::var("APP_TZ").unwrap_or_else(|_| "Asia/Tokyo".to_string());
+    let name = var("APP_TZ").unwrap_or_else(|_| "Asia/Tokyo".to_string());
     Tz::from_str(&name).unwrap_or(chrono_tz::Asia::Tokyo)
 }
 
@@ -62,7 +146,10 @@ assert_eq!(sleep_api::config::admin_email(), "owner@example.com");
 # }
 ```"#]
 pub fn admin_email() -> String {
-    std::env::var("ADMIN_EMAIL").unwrap_or_else(|_| "admin@example.com".to_string())
+    if let Some(tenant) = crate::tenant::current() {
+        return var(crate::tenant::env_var_name(&tenant, "ADMIN_EMAIL")).unwrap_or_default();
+    }
+    var("ADMIN_EMAIL").unwrap_or_else(|_| "admin@example.com".to_string())
 }
 
 /// Return the admin password hash from ADMIN_PASSWORD_HASH (argon2id string).
@@ -70,9 +157,16 @@ pub fn admin_email() -> String {
 #[doc = r#"Return the admin password hash from `ADMIN_PASSWORD_HASH`.
 
 Expected format is an Argon2id hash string (e.g., `$argon2id$...`). Returns empty string if unset,
-which causes login verification to fail."#]
+which causes login verification to fail.
+
+In multi-tenant mode both values come from `TENANT_<NAME>_ADMIN_EMAIL` and
+`TENANT_<NAME>_ADMIN_PASSWORD_HASH` instead, with no fallback to the global ones."#]
 pub fn admin_password_hash() -> String {
-    std::env::var("ADMIN_PASSWORD_HASH").unwrap_or_default()
+    let name = match crate::tenant::current() {
+        Some(tenant) => crate::tenant::env_var_name(&tenant, "ADMIN_PASSWORD_HASH"),
+        None => "ADMIN_PASSWORD_HASH".to_string(),
+    };
+    var(name).unwrap_or_default()
 }
 
 /// Build a cookie Key from SESSION_SECRET if provided (base64), otherwise generate a random key.
@@ -85,18 +179,20 @@ If the environment variable is absent or invalid, a new random key is generated.
 [`Key`]: axum_extra::extract::cookie::Key
 "#]
 pub fn session_key() -> axum_extra::extract::cookie::Key {
+    session_secret_key().unwrap_or_else(axum_extra::extract::cookie::Key::generate)
+}
+
+/// Cookie key derived from `SESSION_SECRET`, or `None` when it is unset or not valid base64.
+pub fn session_secret_key() -> Option<axum_extra::extract::cookie::Key> {
     use base64::{Engine as _, engine::general_purpose};
-    if let Ok(val) = std::env::var("SESSION_SECRET") {
-        match general_purpose::STANDARD.decode(val.as_bytes()) {
-            Ok(bytes) => {
-                return axum_extra::extract::cookie::Key::derive_from(&bytes);
-            }
-            Err(e) => {
-                tracing::warn!(error = ?e, "Invalid base64 in SESSION_SECRET, generating random key");
-            }
+    let val = var("SESSION_SECRET").ok()?;
+    match general_purpose::STANDARD.decode(val.as_bytes()) {
+        Ok(bytes) => Some(axum_extra::extract::cookie::Key::derive_from(&bytes)),
+        Err(e) => {
+            tracing::warn!(error = ?e, "Invalid base64 in SESSION_SECRET");
+            None
         }
     }
-    axum_extra::extract::cookie::Key::generate()
 }
 
 /// Whether to enable the HSTS header. Controlled by ENABLE_HSTS=1/true.
@@ -108,6 +204,20 @@ pub fn hsts_enabled() -> bool {
     env_flag("ENABLE_HSTS", false)
 }
 
+#[doc = r#"CSP violation reporting settings for [`crate::security::headers::apply`].
+
+- `CSP_REPORT_URI`: where browsers send violation reports, e.g. `/api/csp-report` to collect
+  them here (see [`crate::csp_reports`]) or an external collector. Unset or empty disables
+  reporting and the collection endpoint.
+- `CSP_REPORT_ONLY=1/true`: also send the strict policy as `Content-Security-Policy-Report-Only`,
+  so its violations are reported without being blocked."#]
+pub fn csp_reporting() -> crate::security::headers::CspReporting {
+    crate::security::headers::CspReporting {
+        report_uri: var("CSP_REPORT_URI").ok().filter(|s| !s.trim().is_empty()),
+        report_only_strict: env_flag("CSP_REPORT_ONLY", false),
+    }
+}
+
 /// Whether to mark cookies as Secure. Controlled by COOKIE_SECURE=1/true (default: true).
 pub fn cookie_secure() -> bool {
     env_flag("COOKIE_SECURE", true) // default secure for safety
@@ -140,7 +250,7 @@ pub fn csrf_cookie_name() -> &'static str {
 /// - Defaults to 12 hours when unset or invalid
 /// - Set to "0" to disable Max-Age (session-only cookie)
 pub fn session_ttl() -> Option<time::Duration> {
-    match std::env::var("SESSION_TTL_HOURS") {
+    match var("SESSION_TTL_HOURS") {
         Ok(v) => {
             let v = v.trim();
             if v.is_empty() {
@@ -164,5 +274,448 @@ pub fn session_ttl() -> Option<time::Duration> {
 
 /// API bind address. Defaults to `0.0.0.0:8080`.
 pub fn api_bind_addr() -> String {
-    std::env::var("API_BIND_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".to_string())
+    var("API_BIND_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".to_string())
+}
+
+/// Whether `POST /api/admin/query` is enabled. Controlled by ADMIN_QUERY_ENABLED=1/true (default: false).
+pub fn admin_query_enabled() -> bool {
+    env_flag("ADMIN_QUERY_ENABLED", false)
+}
+
+#[doc = r#"Shared secret for webhook pushes to `POST /api/ingest/{source}`.
+
+Read from `INGEST_SECRET_<SOURCE>` (e.g. `INGEST_SECRET_HEALTH_AUTO_EXPORT`); in multi-tenant mode
+from `TENANT_<NAME>_INGEST_SECRET_<SOURCE>`. `None` when unset or empty, which disables the source."#]
+pub fn ingest_secret(source: &str) -> Option<String> {
+    let suffix = format!(
+        "INGEST_SECRET_{}",
+        source.to_ascii_uppercase().replace('-', "_")
+    );
+    let name = match crate::tenant::current() {
+        Some(tenant) => crate::tenant::env_var_name(&tenant, &suffix),
+        None => suffix,
+    };
+    var(name).ok().filter(|s| !s.is_empty())
+}
+
+#[doc = r#"Age in days after which entries become read-only (the no-edit window).
+
+Controlled by `EDIT_WINDOW_DAYS` (e.g. `90`); unset, invalid, or `0` disables the lock. Requests
+carrying `X-Admin-Override: edit-window` bypass it. See [`crate::handlers::EditLock`]."#]
+pub fn edit_window_days() -> Option<i64> {
+    var("EDIT_WINDOW_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| *n > 0)
+}
+
+#[doc = r#"Whether destructive operations must carry a reason code for the audit log.
+
+Controlled by `AUDIT_REASON_REQUIRED=1/true` (default: false). When set, deletes without a
+`reason` in their [`AuditReason`](crate::models::AuditReason) body are rejected with 400."#]
+pub fn audit_reason_required() -> bool {
+    env_flag("AUDIT_REASON_REQUIRED", false)
+}
+
+#[doc = r#"Per-module feature flags, read when the router is built.
+
+Each flag is `FEATURE_<NAME>=1/true` or `0/false`:
+- `FEATURE_TRENDS_CACHE` (default: off)
+- `FEATURE_WEBHOOKS`, `FEATURE_TELEMETRY` (default: on)
+- `FEATURE_INTEGRATIONS_WITHINGS`, `FEATURE_INTEGRATIONS_FITBIT`,
+  `FEATURE_INTEGRATIONS_HEALTH_AUTO_EXPORT`, `FEATURE_INTEGRATIONS_TASKER`,
+  `FEATURE_INTEGRATIONS_STRAVA`, `FEATURE_INTEGRATIONS_GARMIN` (default: on)
+
+See [`crate::features`] for what each flag gates."#]
+pub fn features() -> crate::features::Features {
+    let defaults = crate::features::Features::default();
+    let flag = |name: &str, default: bool| env_flag(&format!("FEATURE_{name}"), default);
+    crate::features::Features {
+        trends_cache: flag("TRENDS_CACHE", defaults.trends_cache),
+        webhooks: flag("WEBHOOKS", defaults.webhooks),
+        telemetry: flag("TELEMETRY", defaults.telemetry),
+        integrations: crate::features::Integrations {
+            withings: flag("INTEGRATIONS_WITHINGS", defaults.integrations.withings),
+            fitbit: flag("INTEGRATIONS_FITBIT", defaults.integrations.fitbit),
+            health_auto_export: flag(
+                "INTEGRATIONS_HEALTH_AUTO_EXPORT",
+                defaults.integrations.health_auto_export,
+            ),
+            tasker: flag("INTEGRATIONS_TASKER", defaults.integrations.tasker),
+            strava: flag("INTEGRATIONS_STRAVA", defaults.integrations.strava),
+            garmin: flag("INTEGRATIONS_GARMIN", defaults.integrations.garmin),
+        },
+    }
+}
+
+#[doc = r#"Per-instance usage quotas enforced by [`crate::middleware::quota`].
+
+Each limit is off when its variable is unset, `0`, or invalid:
+- `QUOTA_SESSIONS_PER_DAY` — sleep sessions per wake date
+- `QUOTA_NOTES_PER_DAY` — notes per date
+- `QUOTA_MAX_BODY_BYTES` — request body size, including import uploads
+- `QUOTA_API_CALLS_PER_MIN` — `/api` requests per minute per API token or session user, or
+  per client address for other callers (logins not counted)"#]
+pub fn quotas() -> crate::middleware::quota::Quotas {
+    let limit = |name: &str| {
+        var(name)
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|n| *n > 0)
+    };
+    crate::middleware::quota::Quotas {
+        sessions_per_day: limit("QUOTA_SESSIONS_PER_DAY"),
+        notes_per_day: limit("QUOTA_NOTES_PER_DAY"),
+        max_body_bytes: limit("QUOTA_MAX_BODY_BYTES"),
+        api_calls_per_min: limit("QUOTA_API_CALLS_PER_MIN"),
+    }
+}
+
+#[doc = r#"Request rate limits enforced by [`crate::middleware::rate_limit`], per minute.
+
+- `RATE_LIMIT_LOGIN_PER_MIN` — failed logins per client address (default 10)
+- `RATE_LIMIT_LOGIN_ACCOUNT_PER_MIN` — failed logins per account email (default 5)
+- `RATE_LIMIT_WRITES_PER_MIN` — `POST`/`PUT`/`PATCH`/`DELETE` `/api` requests per client
+  address (default off)
+
+`0` disables a limit; invalid values keep the default."#]
+pub fn rate_limits() -> crate::middleware::rate_limit::RateLimits {
+    let limit = |name: &str, default: Option<u64>| match var(name) {
+        Ok(v) => match v.trim().parse::<u64>() {
+            Ok(0) => None,
+            Ok(n) => Some(n),
+            Err(_) => default,
+        },
+        Err(_) => default,
+    };
+    let defaults = crate::middleware::rate_limit::RateLimits::default();
+    crate::middleware::rate_limit::RateLimits {
+        login_per_ip: limit("RATE_LIMIT_LOGIN_PER_MIN", defaults.login_per_ip),
+        login_per_account: limit(
+            "RATE_LIMIT_LOGIN_ACCOUNT_PER_MIN",
+            defaults.login_per_account,
+        ),
+        writes_per_ip: limit("RATE_LIMIT_WRITES_PER_MIN", defaults.writes_per_ip),
+    }
+}
+
+#[doc = r#"Login lockout policy for [`crate::security::lockout`]; `None` when disabled.
+
+- `LOGIN_LOCKOUT_THRESHOLD` — failed logins per account or address before a lock (default 5;
+  `0` disables the lockout)
+- `LOGIN_LOCKOUT_BASE_SECS` — first lock duration, doubled on each further lock (default 60)
+- `LOGIN_LOCKOUT_MAX_SECS` — longest lock (default 3600)
+
+Invalid values keep the default; a maximum below the base is raised to the base."#]
+pub fn login_lockout() -> Option<crate::security::lockout::LockoutPolicy> {
+    let defaults = crate::security::lockout::LockoutPolicy::default();
+    let threshold = var("LOGIN_LOCKOUT_THRESHOLD")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(defaults.threshold);
+    if threshold == 0 {
+        return None;
+    }
+    let secs = |name: &str, default: i64| {
+        var(name)
+            .ok()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(default)
+    };
+    let base_secs = secs("LOGIN_LOCKOUT_BASE_SECS", defaults.base_secs);
+    Some(crate::security::lockout::LockoutPolicy {
+        threshold,
+        base_secs,
+        max_secs: secs("LOGIN_LOCKOUT_MAX_SECS", defaults.max_secs).max(base_secs),
+    })
+}
+
+#[doc = r#"Argon2id parameters for new password hashes ([`crate::security::password`]).
+
+- `ARGON2_MEMORY_KIB` — memory cost in KiB (default 19456)
+- `ARGON2_ITERATIONS` — passes (default 2)
+- `ARGON2_PARALLELISM` — lanes (default 1)
+
+Invalid values keep the default; a combination Argon2 rejects falls back to the defaults."#]
+pub fn password_hash_policy() -> crate::security::password::HashPolicy {
+    let defaults = crate::security::password::HashPolicy::default();
+    let num = |name: &str, default: u32| {
+        var(name)
+            .ok()
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(default)
+    };
+    let policy = crate::security::password::HashPolicy {
+        memory_kib: num("ARGON2_MEMORY_KIB", defaults.memory_kib),
+        iterations: num("ARGON2_ITERATIONS", defaults.iterations),
+        parallelism: num("ARGON2_PARALLELISM", defaults.parallelism),
+    };
+    match policy.params() {
+        Ok(_) => policy,
+        Err(e) => {
+            tracing::warn!(error = %e, "invalid Argon2 parameters; using defaults");
+            defaults
+        }
+    }
+}
+
+/// Maximum rows returned by `POST /api/admin/query`.
+/// - Controlled by `ADMIN_QUERY_MAX_ROWS`
+/// - Defaults to 500 when unset or invalid
+pub fn admin_query_max_rows() -> usize {
+    var("ADMIN_QUERY_MAX_ROWS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(500)
+}
+
+/// Time limit for a single `POST /api/admin/query` statement.
+/// - Controlled by `ADMIN_QUERY_TIMEOUT_MS`
+/// - Defaults to 2000 ms when unset or invalid
+pub fn admin_query_timeout() -> std::time::Duration {
+    let ms = var("ADMIN_QUERY_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(2000);
+    std::time::Duration::from_millis(ms)
+}
+
+/// Daily quiet window for database maintenance, in the user timezone.
+/// - Controlled by `MAINTENANCE_WINDOW` (`HH:MM-HH:MM`)
+/// - Defaults to `03:00-05:00` when unset or invalid
+pub fn maintenance_window() -> crate::jobs::QuietWindow {
+    let default = crate::jobs::QuietWindow {
+        start: chrono::NaiveTime::from_hms_opt(3, 0, 0).unwrap_or_default(),
+        end: chrono::NaiveTime::from_hms_opt(5, 0, 0).unwrap_or_default(),
+    };
+    match var("MAINTENANCE_WINDOW") {
+        Ok(v) => v.parse().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Invalid MAINTENANCE_WINDOW; using default 03:00-05:00");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Minimum number of days between `VACUUM` runs during maintenance.
+/// - Controlled by `MAINTENANCE_VACUUM_DAYS`
+/// - Defaults to 7 when unset or invalid; `0` disables vacuuming
+pub fn maintenance_vacuum_days() -> i64 {
+    var("MAINTENANCE_VACUUM_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| *n >= 0)
+        .unwrap_or(7)
+}
+
+/// Age after which append-only telemetry rows are moved to yearly archive tables.
+/// - Controlled by `TELEMETRY_RETENTION_DAYS`
+/// - Defaults to 180 when unset or invalid
+pub fn telemetry_retention_days() -> i64 {
+    var("TELEMETRY_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(180)
+}
+
+/// Record 1 in N successful friction telemetry submissions (errors are always recorded).
+/// - Controlled by `TELEMETRY_SUCCESS_SAMPLE_RATE`
+/// - Defaults to 1 (record everything) when unset or invalid
+pub fn telemetry_success_sample_rate() -> u32 {
+    var("TELEMETRY_SUCCESS_SAMPLE_RATE")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(1)
+}
+
+/// Rows copied per statement by the schema backfill job.
+/// - Controlled by `SCHEMA_BACKFILL_BATCH`
+/// - Defaults to 500 when unset or invalid
+pub fn schema_backfill_batch() -> i64 {
+    var("SCHEMA_BACKFILL_BATCH")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(500)
+}
+
+/// Whether the client address is taken from `X-Forwarded-For` for device fingerprints and
+/// rate limits.
+/// Controlled by TRUST_PROXY_HEADERS=1/true (default: false); enable only behind a proxy
+/// that overwrites the header.
+pub fn trust_proxy_headers() -> bool {
+    env_flag("TRUST_PROXY_HEADERS", false)
+}
+
+/// URL receiving notifications (e.g. fired alerts) as signed JSON `POST`s.
+/// - Controlled by `NOTIFY_WEBHOOK_URL`
+/// - Unset or empty disables the webhook channel; notifications are still logged
+pub fn notify_webhook_url() -> Option<String> {
+    var("NOTIFY_WEBHOOK_URL")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// Secret for the `X-Signature-256` header of notification webhooks.
+/// - Controlled by `NOTIFY_WEBHOOK_SECRET`
+/// - Unset or empty sends webhooks unsigned
+pub fn notify_webhook_secret() -> Option<String> {
+    var("NOTIFY_WEBHOOK_SECRET").ok().filter(|s| !s.is_empty())
+}
+
+/// Key signing export manifests (`sleepctl export`, `POST /api/admin/backups`, `sleepctl verify-export`).
+/// - Controlled by `EXPORT_SIGNING_KEY`
+/// - Unset or empty writes unsigned manifests and skips the signature check
+pub fn export_signing_key() -> Option<Vec<u8>> {
+    var("EXPORT_SIGNING_KEY")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(String::into_bytes)
+}
+
+#[doc = r#"Directory holding backups written and served by `/api/admin/backups`, one export
+directory (see [`crate::export`]) per backup.
+
+Controlled by `BACKUP_DIR`; unset or empty disables the backup endpoints. In multi-tenant mode
+each tenant gets its own `<BACKUP_DIR>/<tenant>` subdirectory."#]
+pub fn backup_dir() -> Option<std::path::PathBuf> {
+    let root = std::path::PathBuf::from(var("BACKUP_DIR").ok().filter(|s| !s.is_empty())?);
+    Some(match crate::tenant::current() {
+        Some(tenant) => root.join(tenant),
+        None => root,
+    })
+}
+
+#[doc = r#"Directory holding uploaded attachments and their thumbnails (see
+[`crate::attachments`]).
+
+Controlled by `ATTACHMENT_DIR`; unset or empty disables the attachment endpoints. In
+multi-tenant mode each tenant gets its own `<ATTACHMENT_DIR>/<tenant>` subdirectory."#]
+pub fn attachment_dir() -> Option<std::path::PathBuf> {
+    let root = std::path::PathBuf::from(var("ATTACHMENT_DIR").ok().filter(|s| !s.is_empty())?);
+    Some(match crate::tenant::current() {
+        Some(tenant) => root.join(tenant),
+        None => root,
+    })
+}
+
+/// Instant the server clock is frozen at, for demo instances.
+/// - Controlled by `FROZEN_TIME` (RFC 3339, e.g. `2025-06-01T21:00:00+09:00`)
+/// - Unset, empty, or invalid values keep the system clock
+pub fn frozen_time() -> Option<chrono::DateTime<chrono::Utc>> {
+    let raw = var("FROZEN_TIME").ok()?;
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    match chrono::DateTime::parse_from_rfc3339(raw) {
+        Ok(t) => Some(t.with_timezone(&chrono::Utc)),
+        Err(e) => {
+            tracing::warn!(value = %raw, error = %e, "ignoring invalid FROZEN_TIME");
+            None
+        }
+    }
+}
+
+/// Clock for the server: a [`FixedClock`] at [`frozen_time`] when set, else the system clock.
+///
+/// [`FixedClock`]: crate::time::FixedClock
+pub fn clock() -> crate::time::SharedClock {
+    match frozen_time() {
+        Some(at) => std::sync::Arc::new(crate::time::FixedClock(at)),
+        None => std::sync::Arc::new(crate::time::SystemClock),
+    }
+}
+
+/// Implementation serving `GET /api/trends/summary` during the SQL aggregation rollout.
+/// - Controlled by `TRENDS_SUMMARY_AGGREGATION` (`rust`, `sql`, or `shadow`)
+/// - Unset, empty, or invalid values keep the Rust aggregation
+pub fn trends_summary_aggregation() -> crate::trends::SummaryAggregation {
+    var("TRENDS_SUMMARY_AGGREGATION")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .and_then(|v| {
+            v.parse()
+                .map_err(|e| {
+                    tracing::warn!(error = %e, "Invalid TRENDS_SUMMARY_AGGREGATION; using rust");
+                })
+                .ok()
+        })
+        .unwrap_or(crate::trends::SummaryAggregation::Rust)
+}
+
+/// Percentage of summary requests compared against the SQL path in shadow mode.
+/// - Controlled by `TRENDS_SUMMARY_SHADOW_PCT` (0..=100)
+/// - Defaults to 10 when unset or invalid
+pub fn trends_summary_shadow_pct() -> u8 {
+    var("TRENDS_SUMMARY_SHADOW_PCT")
+        .ok()
+        .and_then(|v| v.trim().parse::<u8>().ok())
+        .filter(|p| *p <= 100)
+        .unwrap_or(10)
+}
+
+#[cfg(feature = "simulator")]
+#[doc = r#"Simulator settings, or `None` unless `SIMULATOR=1/true`.
+
+- `SIMULATOR_INTERVAL_SECS`: seconds between ticks (default 60)
+- `SIMULATOR_DAYS`: wake dates kept filled, ending today (default 28, at most 366)
+- `SIMULATOR_SEED`: seed for reproducible nights (default: the start time)
+
+See [`crate::simulator`]."#]
+pub fn simulator() -> Option<crate::simulator::SimulatorConfig> {
+    if !env_flag("SIMULATOR", false) {
+        return None;
+    }
+    let defaults = crate::simulator::SimulatorConfig::default();
+    let parsed = |name: &str| var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+    Some(crate::simulator::SimulatorConfig {
+        interval_secs: parsed("SIMULATOR_INTERVAL_SECS")
+            .filter(|s| *s > 0)
+            .unwrap_or(defaults.interval_secs),
+        days: parsed("SIMULATOR_DAYS")
+            .filter(|d| (1..=366).contains(d))
+            .map_or(defaults.days, |d| d as u32),
+        seed: parsed("SIMULATOR_SEED"),
+    })
+}
+
+/// Multi-tenant mode, or `None` for a single database.
+/// - Controlled by `TENANT_MODE` (`subdomain` or `path`)
+/// - Unset, empty, or invalid values keep single-tenant mode
+pub fn tenant_mode() -> Option<crate::tenant::TenantMode> {
+    let v = var("TENANT_MODE").ok()?;
+    if v.trim().is_empty() {
+        return None;
+    }
+    v.parse()
+        .map_err(|e| {
+            tracing::warn!(error = %e, "Invalid TENANT_MODE; staying single-tenant");
+        })
+        .ok()
+}
+
+/// Directory holding one `{tenant}.sqlite` file per tenant.
+/// - Controlled by `TENANT_DATA_DIR`
+/// - Defaults to `./tenants`
+pub fn tenant_data_dir() -> std::path::PathBuf {
+    var("TENANT_DATA_DIR")
+        .unwrap_or_else(|_| "./tenants".to_string())
+        .into()
+}
+
+/// Tenants allowed in multi-tenant mode, from comma-separated `TENANTS` (e.g. `alice,bob`).
+pub fn tenant_names() -> Vec<String> {
+    var("TENANTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
 }
@@ -0,0 +1,299 @@
+#![doc = r#"CSP violation reports
+
+Browsers post a report whenever the Content-Security-Policy (or, with `CSP_REPORT_ONLY`, the
+strict report-only policy) blocks or would block something; see
+[`security::headers`](crate::security::headers). Setting `CSP_REPORT_URI=/api/csp-report`
+sends those reports here, which shows what the strict policy would break before it is
+enforced.
+
+Endpoints:
+- `POST /api/csp-report` — [`store`]; registered only when `CSP_REPORT_URI` is set.
+  Accepts both the `report-uri` format (`application/csp-report`, `{"csp-report":{...}}`) and
+  the Reporting API format (`application/reports+json`, a list of `csp-violation` reports).
+- `GET /api/admin/csp-reports?days=` — [`summarize`], grouped by directive and blocked URI.
+
+The collection endpoint is unauthenticated, since browsers send reports without credentials,
+so it is bounded: bodies above [`MAX_REPORT_BYTES`] are refused, at most
+[`REPORTS_PER_MIN`] reports are stored per minute, and only the newest
+[`MAX_STORED_REPORTS`] are kept. Query strings and fragments are stripped from every URI
+before it is stored, as they can carry secrets (e.g. a feed token).
+"#]
+
+use crate::{db::Db, error::ApiError};
+use chrono::{Duration as ChronoDuration, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Sqlite;
+
+/// Largest accepted report body, in bytes.
+pub const MAX_REPORT_BYTES: usize = 16 * 1024;
+
+/// Reports stored per minute, across all senders; the rest are answered with `429`.
+pub const REPORTS_PER_MIN: i64 = 60;
+
+/// Reports kept; older ones are deleted as new ones arrive.
+pub const MAX_STORED_REPORTS: i64 = 1000;
+
+/// Longest stored text field, in characters.
+const MAX_FIELD_CHARS: usize = 512;
+
+/// Default and largest `days` of `GET /api/admin/csp-reports`.
+const DEFAULT_SUMMARY_DAYS: i64 = 7;
+const MAX_SUMMARY_DAYS: i64 = 90;
+
+/// Groups returned by the summary, most frequent first.
+const MAX_SUMMARY_GROUPS: i64 = 100;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[doc = r#"One violation, normalized from either report format."#]
+pub struct CspViolation {
+    pub document_uri: String,
+    pub violated_directive: String,
+    pub blocked_uri: String,
+    pub source_file: Option<String>,
+    pub line_number: Option<i64>,
+    /// `enforce` or `report` (from the report-only policy).
+    pub disposition: String,
+    pub sample: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SummaryQuery {
+    pub days: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Violations of one directive by one blocked URI (and disposition).
+
+`document_uri` and `source_file` are taken from the most recent report of the group."#]
+pub struct CspViolationGroup {
+    pub violated_directive: String,
+    pub blocked_uri: String,
+    pub disposition: String,
+    pub count: i64,
+    pub first_seen: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    pub document_uri: String,
+    pub source_file: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Response of `GET /api/admin/csp-reports`: reports received since `since` (UTC),
+grouped, most frequent first (at most 100 groups)."#]
+pub struct CspReportSummary {
+    pub since: NaiveDateTime,
+    pub total: i64,
+    pub groups: Vec<CspViolationGroup>,
+}
+
+#[doc = r##"Extract the violations from a report body in either format.
+
+Unknown report types and malformed entries are skipped; fields are truncated and URIs lose
+their query string and fragment.
+
+# Example
+
+```rust
+# use sleep_api::csp_reports::parse_reports;
+let legacy = br#"{"csp-report":{"document-uri":"https://sleep.example/?token=abc",
+    "violated-directive":"script-src-elem","blocked-uri":"inline","disposition":"report"}}"#;
+let v = parse_reports(legacy);
+assert_eq!(v[0].document_uri, "https://sleep.example/");
+assert_eq!(v[0].violated_directive, "script-src-elem");
+
+let reporting_api = br#"[{"type":"csp-violation","body":{"documentURL":"https://sleep.example/",
+    "effectiveDirective":"img-src","blockedURL":"https://cdn.example/a.png","disposition":"enforce"}}]"#;
+assert_eq!(parse_reports(reporting_api)[0].blocked_uri, "https://cdn.example/a.png");
+assert!(parse_reports(b"not json").is_empty());
+```
+"##]
+pub fn parse_reports(body: &[u8]) -> Vec<CspViolation> {
+    let Ok(value) = serde_json::from_slice::<Value>(body) else {
+        return Vec::new();
+    };
+    match value {
+        Value::Object(ref obj) if obj.contains_key("csp-report") => {
+            from_legacy(&obj["csp-report"]).into_iter().collect()
+        }
+        Value::Array(reports) => reports
+            .iter()
+            .filter(|r| r.get("type").and_then(Value::as_str) == Some("csp-violation"))
+            .filter_map(|r| from_reporting_api(r.get("body")?))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn from_legacy(report: &Value) -> Option<CspViolation> {
+    let directive =
+        text(report, "effective-directive").or_else(|| text(report, "violated-directive"));
+    Some(CspViolation {
+        document_uri: uri(&text(report, "document-uri")?),
+        violated_directive: directive?,
+        blocked_uri: uri(&text(report, "blocked-uri").unwrap_or_default()),
+        source_file: text(report, "source-file").map(|s| uri(&s)),
+        line_number: report.get("line-number").and_then(Value::as_i64),
+        disposition: text(report, "disposition").unwrap_or_else(|| "enforce".into()),
+        sample: text(report, "script-sample"),
+    })
+}
+
+fn from_reporting_api(body: &Value) -> Option<CspViolation> {
+    Some(CspViolation {
+        document_uri: uri(&text(body, "documentURL")?),
+        violated_directive: text(body, "effectiveDirective")?,
+        blocked_uri: uri(&text(body, "blockedURL").unwrap_or_default()),
+        source_file: text(body, "sourceFile").map(|s| uri(&s)),
+        line_number: body.get("lineNumber").and_then(Value::as_i64),
+        disposition: text(body, "disposition").unwrap_or_else(|| "enforce".into()),
+        sample: text(body, "sample"),
+    })
+}
+
+/// Non-empty string field, truncated to [`MAX_FIELD_CHARS`].
+fn text(obj: &Value, key: &str) -> Option<String> {
+    let s = obj.get(key)?.as_str()?.trim();
+    (!s.is_empty()).then(|| s.chars().take(MAX_FIELD_CHARS).collect())
+}
+
+/// `s` without its query string and fragment.
+fn uri(s: &str) -> String {
+    s.split(['?', '#']).next().unwrap_or_default().to_string()
+}
+
+#[doc = r#"Store the violations in `body` received at `now` (UTC).
+
+Returns how many were stored. Reports beyond the per-minute budget are dropped; when nothing
+could be stored because the budget is spent, returns [`ApiError::QuotaExceeded`].
+
+# Errors
+
+- [`ApiError::InvalidInput`] when the body contains no CSP violation.
+- [`ApiError::QuotaExceeded`] when [`REPORTS_PER_MIN`] reports were already stored in the
+  last minute.
+"#]
+pub async fn store(db: &Db, body: &[u8], now: NaiveDateTime) -> Result<usize, ApiError> {
+    let violations = parse_reports(body);
+    if violations.is_empty() {
+        return Err(ApiError::InvalidInput("no CSP violation in report".into()));
+    }
+    let recent: i64 =
+        sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM csp_reports WHERE received_at > ?")
+            .bind(now - ChronoDuration::minutes(1))
+            .fetch_one(db)
+            .await?;
+    let budget = (REPORTS_PER_MIN - recent).max(0) as usize;
+    if budget == 0 {
+        return Err(ApiError::QuotaExceeded(format!(
+            "more than {REPORTS_PER_MIN} CSP reports per minute"
+        )));
+    }
+
+    let mut tx = db.begin().await?;
+    let stored = violations.len().min(budget);
+    for v in violations.into_iter().take(budget) {
+        sqlx::query::<Sqlite>(
+            "INSERT INTO csp_reports (received_at, document_uri, violated_directive, blocked_uri, \
+             source_file, line_number, disposition, sample) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(now)
+        .bind(v.document_uri)
+        .bind(v.violated_directive)
+        .bind(v.blocked_uri)
+        .bind(v.source_file)
+        .bind(v.line_number)
+        .bind(v.disposition)
+        .bind(v.sample)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query::<Sqlite>(
+        "DELETE FROM csp_reports WHERE id <= \
+         (SELECT id FROM csp_reports ORDER BY id DESC LIMIT 1 OFFSET ?)",
+    )
+    .bind(MAX_STORED_REPORTS)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(stored)
+}
+
+#[doc = r#"Group the reports received after `since`, most frequent first.
+
+# Errors
+
+Returns [`ApiError::InvalidInput`] when `days` is outside 1..=90, or a database error.
+"#]
+pub async fn summarize(
+    db: &Db,
+    query: &SummaryQuery,
+    now: NaiveDateTime,
+) -> Result<CspReportSummary, ApiError> {
+    let days = query.days.unwrap_or(DEFAULT_SUMMARY_DAYS);
+    if !(1..=MAX_SUMMARY_DAYS).contains(&days) {
+        return Err(ApiError::InvalidInput(format!(
+            "days must be between 1 and {MAX_SUMMARY_DAYS}"
+        )));
+    }
+    let since = now - ChronoDuration::days(days);
+    let total: i64 =
+        sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM csp_reports WHERE received_at > ?")
+            .bind(since)
+            .fetch_one(db)
+            .await?;
+    type GroupRow = (
+        String,
+        String,
+        String,
+        i64,
+        NaiveDateTime,
+        NaiveDateTime,
+        String,
+        Option<String>,
+    );
+    let rows = sqlx::query_as::<Sqlite, GroupRow>(
+        "SELECT g.violated_directive, g.blocked_uri, g.disposition, g.n, g.first_seen, \
+                g.last_seen, r.document_uri, r.source_file \
+         FROM (SELECT violated_directive, blocked_uri, disposition, COUNT(*) AS n, \
+                      MIN(received_at) AS first_seen, MAX(received_at) AS last_seen, \
+                      MAX(id) AS last_id \
+               FROM csp_reports WHERE received_at > ? \
+               GROUP BY violated_directive, blocked_uri, disposition) g \
+         JOIN csp_reports r ON r.id = g.last_id \
+         ORDER BY g.n DESC, g.last_seen DESC LIMIT ?",
+    )
+    .bind(since)
+    .bind(MAX_SUMMARY_GROUPS)
+    .fetch_all(db)
+    .await?;
+    let groups = rows
+        .into_iter()
+        .map(
+            |(
+                violated_directive,
+                blocked_uri,
+                disposition,
+                count,
+                first_seen,
+                last_seen,
+                document_uri,
+                source_file,
+            )| CspViolationGroup {
+                violated_directive,
+                blocked_uri,
+                disposition,
+                count,
+                first_seen,
+                last_seen,
+                document_uri,
+                source_file,
+            },
+        )
+        .collect();
+    Ok(CspReportSummary {
+        since,
+        total,
+        groups,
+    })
+}
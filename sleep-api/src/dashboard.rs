@@ -0,0 +1,304 @@
+#![doc = r#"Home dashboard
+
+One payload with the headline numbers of the UI home page, so it loads with a single request
+instead of stitching together sleep, trends, completeness, bedtime, and personalization calls.
+
+Endpoints:
+- `GET /api/dashboard?days=30`
+
+All dates are wake dates ending at `as_of`, the logical day of the current time in the user's
+timezone (see [`DayBoundary`]). `days` (1..=365, default 30) sets the long average window and
+the range searched for complete-day streaks.
+
+[`DayBoundary`]: crate::models::DayBoundary
+"#]
+
+use crate::middleware::auth_layer::RequireSessionJson;
+use crate::now::{DEBT_WINDOW_DAYS, nearest_bedtime, sleep_debt};
+use crate::time::SharedClock;
+use crate::trends::{ActionRecommendation, RecommendationStatus, personalization_as_of};
+use crate::{completeness, db::Db, error::ApiError, repository};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime, NaiveTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite};
+
+/// Window of the short average.
+const WEEK_DAYS: i64 = 7;
+
+/// Default `days` of `GET /api/dashboard`.
+const DEFAULT_DAYS: i64 = 30;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/dashboard`.
+
+- `days`: long average window and streak search range, 1..=365 (default 30).
+"#]
+pub struct DashboardQuery {
+    pub days: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, FromRow, JsonSchema)]
+#[doc = r#"The most recent logged night up to `as_of` (all sessions waking that day)."#]
+pub struct LastNight {
+    pub wake_date: NaiveDate,
+    pub bed_time: NaiveTime,
+    pub wake_time: NaiveTime,
+    pub duration_min: i32,
+    pub quality: Option<i32>,
+    pub wake_feeling: Option<i32>,
+    pub session_count: i32,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Averages over the logged nights among the last `days` wake dates."#]
+pub struct AverageWindow {
+    pub days: i64,
+    pub nights_logged: usize,
+    pub avg_duration_min: Option<f64>,
+    pub avg_quality: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Current streaks ending at `as_of`.
+
+- `logging`: consecutive wake dates with sleep logged. An unlogged `as_of` does not break it
+  yet; counting then starts the day before.
+- `complete` / `longest_complete`: complete days (see [`crate::completeness`]) within the
+  `days` window.
+"#]
+pub struct Streaks {
+    pub logging: usize,
+    pub complete: usize,
+    pub longest_complete: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"What a [`Reminder`] asks for."#]
+pub enum ReminderKind {
+    /// No sleep logged for `as_of` yet.
+    LogSleep,
+    /// `as_of` sleep is logged without the morning check-in.
+    CheckIn,
+    /// The target bedtime from the sleep goal.
+    Bedtime,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"A pending action. `due_at` (local) and `minutes_until` are set for timed reminders;
+`minutes_until` is negative once the time has passed."#]
+pub struct Reminder {
+    pub kind: ReminderKind,
+    pub due_at: Option<NaiveDateTime>,
+    pub minutes_until: Option<i64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Response of `GET /api/dashboard`.
+
+- `sleep_debt_min`: as in `GET /api/now/bedtime-status`, over the last `debt_window_days`.
+- `top_insight`: the first recommended action of the personalization analysis over `days`,
+  or `None` when every action is suppressed.
+"#]
+pub struct Dashboard {
+    pub as_of: NaiveDate,
+    pub local_time: NaiveDateTime,
+    pub days: i64,
+    pub last_night: Option<LastNight>,
+    pub week: AverageWindow,
+    pub period: AverageWindow,
+    pub streaks: Streaks,
+    pub sleep_debt_min: i64,
+    pub debt_window_days: i64,
+    pub reminders: Vec<Reminder>,
+    pub top_insight: Option<ActionRecommendation>,
+}
+
+#[doc = r#"Return the home dashboard payload.
+
+Errors:
+- Returns an API error when `days` is outside 1..=365.
+- Returns an API error on database failures.
+"#]
+pub async fn dashboard(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Query(q): Query<DashboardQuery>,
+) -> Result<Json<Dashboard>, ApiError> {
+    let days = q.days.unwrap_or(DEFAULT_DAYS);
+    if !(1..=365).contains(&days) {
+        return Err(ApiError::InvalidInput(
+            "days must be between 1 and 365".into(),
+        ));
+    }
+
+    let tz = repository::get_user_timezone(&db).await;
+    let local_time = clock.now_utc().with_timezone(&tz).naive_local();
+    let as_of = repository::get_day_boundary(&db).await.day_of(local_time);
+    let goal = repository::get_sleep_goal(&db).await;
+
+    let window = days.max(WEEK_DAYS).max(DEBT_WINDOW_DAYS);
+    let nights = sqlx::query_as::<Sqlite, LastNight>(
+        r#"
+        SELECT wake_date, bed_time, wake_time, duration_min, quality, wake_feeling, session_count
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(as_of - ChronoDuration::days(window - 1))
+    .bind(as_of)
+    .fetch_all(&db)
+    .await?;
+
+    let wake_dates: Vec<NaiveDate> = nights.iter().map(|n| n.wake_date).collect();
+    let debt_durations: Vec<i32> = since(&nights, as_of, DEBT_WINDOW_DAYS)
+        .map(|n| n.duration_min)
+        .collect();
+    let streak_from = as_of - ChronoDuration::days(days - 1);
+    let completeness = completeness::completeness_range(&db, streak_from, as_of).await?;
+    let top_insight = personalization_as_of(&db, as_of, days)
+        .await?
+        .recommendations
+        .into_iter()
+        .find(|r| matches!(r.status, RecommendationStatus::Recommended));
+
+    let bedtime_at = nearest_bedtime(local_time, goal.target_bedtime);
+    let mut reminders = Vec::new();
+    match nights.last().filter(|n| n.wake_date == as_of) {
+        None => reminders.push(Reminder {
+            kind: ReminderKind::LogSleep,
+            due_at: None,
+            minutes_until: None,
+        }),
+        Some(n) if n.wake_feeling.is_none() => reminders.push(Reminder {
+            kind: ReminderKind::CheckIn,
+            due_at: None,
+            minutes_until: None,
+        }),
+        Some(_) => {}
+    }
+    reminders.push(Reminder {
+        kind: ReminderKind::Bedtime,
+        due_at: Some(bedtime_at),
+        minutes_until: Some((bedtime_at - local_time).num_minutes()),
+    });
+
+    Ok(Json(Dashboard {
+        as_of,
+        local_time,
+        days,
+        last_night: nights.last().cloned(),
+        week: average(&nights, as_of, WEEK_DAYS),
+        period: average(&nights, as_of, days),
+        streaks: Streaks {
+            logging: logging_streak(&wake_dates, as_of),
+            complete: completeness.current_streak,
+            longest_complete: completeness.longest_streak,
+        },
+        sleep_debt_min: sleep_debt(&debt_durations, goal.target_duration_min),
+        debt_window_days: DEBT_WINDOW_DAYS,
+        reminders,
+        top_insight,
+    }))
+}
+
+/// Nights among the last `days` wake dates ending at `as_of`.
+fn since(nights: &[LastNight], as_of: NaiveDate, days: i64) -> impl Iterator<Item = &LastNight> {
+    let from = as_of - ChronoDuration::days(days - 1);
+    nights
+        .iter()
+        .filter(move |n| n.wake_date >= from && n.wake_date <= as_of)
+}
+
+fn average(nights: &[LastNight], as_of: NaiveDate, days: i64) -> AverageWindow {
+    let window: Vec<&LastNight> = since(nights, as_of, days).collect();
+    let mean = |values: Vec<f64>| {
+        (!values.is_empty()).then(|| round1(values.iter().sum::<f64>() / values.len() as f64))
+    };
+    AverageWindow {
+        days,
+        nights_logged: window.len(),
+        avg_duration_min: mean(window.iter().map(|n| f64::from(n.duration_min)).collect()),
+        avg_quality: mean(
+            window
+                .iter()
+                .filter_map(|n| n.quality.map(f64::from))
+                .collect(),
+        ),
+    }
+}
+
+/// Consecutive logged wake dates (ascending) ending at `as_of`, or the day before when `as_of`
+/// is unlogged.
+pub(crate) fn logging_streak(wake_dates: &[NaiveDate], as_of: NaiveDate) -> usize {
+    let mut expected = match wake_dates.last() {
+        Some(d) if *d == as_of => as_of,
+        _ => as_of - ChronoDuration::days(1),
+    };
+    let mut streak = 0;
+    for date in wake_dates.iter().rev() {
+        if *date != expected {
+            break;
+        }
+        streak += 1;
+        expected -= ChronoDuration::days(1);
+    }
+    streak
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn night(day: u32, duration_min: i32, quality: Option<i32>) -> LastNight {
+        LastNight {
+            wake_date: NaiveDate::from_ymd_opt(2025, 6, day).unwrap(),
+            bed_time: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            wake_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            duration_min,
+            quality,
+            wake_feeling: None,
+            session_count: 1,
+        }
+    }
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, day).unwrap()
+    }
+
+    #[test]
+    fn logging_streak_tolerates_unlogged_today() {
+        let dates = [d(1), d(3), d(4)];
+        assert_eq!(logging_streak(&dates, d(4)), 2);
+        assert_eq!(logging_streak(&dates, d(5)), 2);
+        assert_eq!(logging_streak(&dates, d(6)), 0);
+        assert_eq!(logging_streak(&[], d(6)), 0);
+    }
+
+    #[test]
+    fn averages_cover_window_only() {
+        let nights = [
+            night(1, 300, Some(2)),
+            night(8, 420, Some(3)),
+            night(9, 480, None),
+        ];
+        let week = average(&nights, d(9), 7);
+        assert_eq!(week.nights_logged, 2);
+        assert_eq!(week.avg_duration_min, Some(450.0));
+        assert_eq!(week.avg_quality, Some(3.0));
+
+        let empty = average(&nights, d(20), 7);
+        assert_eq!((empty.nights_logged, empty.avg_duration_min), (0, None));
+    }
+}
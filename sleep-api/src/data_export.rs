@@ -0,0 +1,357 @@
+#![doc = r#"Streaming data export (`GET /api/export`)
+
+[`stream`] renders every sleep session (with its metrics), exercise event and note in a date
+range as one JSON document or one CSV table. Rows are read from [`repository`] in keyset
+pages of [`EXPORT_PAGE_ROWS`] and each page is yielded as soon as it is rendered, so a
+multi-year history never sits in memory at once. The endpoint spools the stream to a temporary
+file ([`download::spool`]) so the download can be resumed with `Range` / `If-Range`.
+
+- JSON: `{"sleep":[SleepListItem...],"exercise":[ExerciseEvent...],"notes":[Note...]}`, each
+  list ordered by date, then id.
+- CSV: one header row ([`CSV_HEADER`]), then one row per record. The `type` column is
+  `sleep`, `exercise` or `note`; cells that do not apply to a type are empty (an exercise's
+  length is in `duration_min`).
+
+Bed and wake times are rounded to the saved [`TimeRounding`] preference (exact by default);
+the stored sessions keep their exact times.
+
+With a [`RedactionPolicy`] (`?anonymize=true`, the `export` audience of
+[`redaction`]) every page is redacted before it is rendered, so the anonymized document has the
+same layout with note bodies stripped and times rounded as configured.
+
+A database error part way ends the stream with that error instead of a document that looks
+complete; the endpoint then answers `500` rather than serving the partial file.
+
+[`repository`]: crate::repository
+[`redaction`]: crate::redaction
+[`download::spool`]: crate::download::spool
+"#]
+
+use crate::{
+    db::Db,
+    error::Error,
+    models::{ExerciseEvent, Note, RedactionPolicy, SleepListItem, TimeRounding},
+    negotiate::{ResponseFormat, cell},
+    redaction::Redact,
+    repository,
+};
+use axum::body::Bytes;
+use chrono::NaiveDate;
+use futures_util::Stream;
+
+/// Rows read from the database per query (and rendered per chunk).
+pub const EXPORT_PAGE_ROWS: i64 = 500;
+
+/// Columns of the CSV export.
+pub const CSV_HEADER: &[&str] = &[
+    "type",
+    "id",
+    "date",
+    "bed_time",
+    "wake_time",
+    "latency_min",
+    "awakenings",
+    "quality",
+    "duration_min",
+    "wake_feeling",
+    "sleep_inertia_min",
+    "intensity",
+    "start_time",
+    "body",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Sleep,
+    Exercise,
+    Notes,
+}
+
+impl Section {
+    fn key(self) -> &'static str {
+        match self {
+            Section::Sleep => "sleep",
+            Section::Exercise => "exercise",
+            Section::Notes => "notes",
+        }
+    }
+
+    fn next(self) -> Option<Section> {
+        match self {
+            Section::Sleep => Some(Section::Exercise),
+            Section::Exercise => Some(Section::Notes),
+            Section::Notes => None,
+        }
+    }
+}
+
+enum Page {
+    Sleep(Vec<SleepListItem>),
+    Exercise(Vec<ExerciseEvent>),
+    Notes(Vec<Note>),
+}
+
+impl Page {
+    async fn fetch(
+        db: &Db,
+        section: Section,
+        from: NaiveDate,
+        to: NaiveDate,
+        after: Option<(NaiveDate, i64)>,
+    ) -> Result<Page, Error> {
+        let limit = EXPORT_PAGE_ROWS;
+        Ok(match section {
+            Section::Sleep => {
+                Page::Sleep(repository::export_sleep_page(db, from, to, after, limit).await?)
+            }
+            Section::Exercise => {
+                Page::Exercise(repository::export_exercise_page(db, from, to, after, limit).await?)
+            }
+            Section::Notes => {
+                Page::Notes(repository::export_notes_page(db, from, to, after, limit).await?)
+            }
+        })
+    }
+
+    /// Round the bed and wake times of sleep rows; other sections are unchanged.
+    fn round_times(self, rounding: TimeRounding) -> Page {
+        match self {
+            Page::Sleep(rows) => Page::Sleep(
+                rows.into_iter()
+                    .map(|s| SleepListItem {
+                        bed_time: rounding.round(s.bed_time),
+                        wake_time: rounding.round(s.wake_time),
+                        ..s
+                    })
+                    .collect(),
+            ),
+            page => page,
+        }
+    }
+
+    fn redact(self, policy: &RedactionPolicy) -> Page {
+        match self {
+            Page::Sleep(rows) => Page::Sleep(rows.redact(policy)),
+            Page::Exercise(rows) => Page::Exercise(rows.redact(policy)),
+            Page::Notes(rows) => Page::Notes(rows.redact(policy)),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Page::Sleep(rows) => rows.len(),
+            Page::Exercise(rows) => rows.len(),
+            Page::Notes(rows) => rows.len(),
+        }
+    }
+
+    /// Keyset position of the last row.
+    fn last_key(&self) -> Option<(NaiveDate, i64)> {
+        match self {
+            Page::Sleep(rows) => rows.last().map(|r| (r.date, r.id)),
+            Page::Exercise(rows) => rows.last().map(|r| (r.date, r.id)),
+            Page::Notes(rows) => rows.last().map(|r| (r.date, r.id)),
+        }
+    }
+
+    /// Append the rows as JSON array elements; `first` says whether the array is still empty.
+    fn write_json(&self, out: &mut Vec<u8>, mut first: bool) -> Result<(), Error> {
+        fn each<T: serde::Serialize>(
+            rows: &[T],
+            out: &mut Vec<u8>,
+            first: &mut bool,
+        ) -> Result<(), Error> {
+            for row in rows {
+                if !std::mem::take(first) {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, row).map_err(std::io::Error::from)?;
+            }
+            Ok(())
+        }
+        match self {
+            Page::Sleep(rows) => each(rows, out, &mut first),
+            Page::Exercise(rows) => each(rows, out, &mut first),
+            Page::Notes(rows) => each(rows, out, &mut first),
+        }
+    }
+
+    fn csv_rows(&self) -> Vec<[String; 14]> {
+        let empty = String::new;
+        match self {
+            Page::Sleep(rows) => rows
+                .iter()
+                .map(|s| {
+                    [
+                        "sleep".into(),
+                        s.id.to_string(),
+                        s.date.to_string(),
+                        s.bed_time.to_string(),
+                        s.wake_time.to_string(),
+                        s.latency_min.to_string(),
+                        s.awakenings.to_string(),
+                        s.quality.to_string(),
+                        cell(s.duration_min),
+                        cell(s.wake_feeling),
+                        cell(s.sleep_inertia_min),
+                        empty(),
+                        empty(),
+                        empty(),
+                    ]
+                })
+                .collect(),
+            Page::Exercise(rows) => rows
+                .iter()
+                .map(|e| {
+                    [
+                        "exercise".into(),
+                        e.id.to_string(),
+                        e.date.to_string(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        cell(e.duration_min),
+                        empty(),
+                        empty(),
+                        e.intensity.clone(),
+                        cell(e.start_time),
+                        empty(),
+                    ]
+                })
+                .collect(),
+            Page::Notes(rows) => rows
+                .iter()
+                .map(|n| {
+                    [
+                        "note".into(),
+                        n.id.to_string(),
+                        n.date.to_string(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        empty(),
+                        n.body.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    fn write_csv(&self, out: &mut Vec<u8>) -> Result<(), Error> {
+        let mut writer = csv::Writer::from_writer(out);
+        for row in self.csv_rows() {
+            writer.write_record(&row).map_err(std::io::Error::from)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+struct Cursor {
+    db: Db,
+    format: ResponseFormat,
+    from: NaiveDate,
+    to: NaiveDate,
+    rounding: TimeRounding,
+    redaction: Option<RedactionPolicy>,
+    /// `None` once the document is closed.
+    section: Option<Section>,
+    after: Option<(NaiveDate, i64)>,
+    started: bool,
+}
+
+impl Cursor {
+    /// Render the next non-empty chunk, or `None` at the end of the document.
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>, Error> {
+        let mut out = Vec::new();
+        if !self.started {
+            self.started = true;
+            match self.format {
+                ResponseFormat::Json => out.extend_from_slice(b"{\"sleep\":["),
+                ResponseFormat::Csv => {
+                    let mut writer = csv::Writer::from_writer(&mut out);
+                    writer
+                        .write_record(CSV_HEADER)
+                        .map_err(std::io::Error::from)?;
+                    writer.flush()?;
+                }
+            }
+        }
+        while let Some(section) = self.section {
+            let mut page = Page::fetch(&self.db, section, self.from, self.to, self.after)
+                .await?
+                .round_times(self.rounding);
+            if let Some(policy) = &self.redaction {
+                page = page.redact(policy);
+            }
+            match self.format {
+                ResponseFormat::Json => page.write_json(&mut out, self.after.is_none())?,
+                ResponseFormat::Csv => page.write_csv(&mut out)?,
+            }
+            if page.len() as i64 == EXPORT_PAGE_ROWS {
+                self.after = page.last_key();
+                return Ok(Some(Bytes::from(out)));
+            }
+            // A short page ends the section.
+            self.after = None;
+            self.section = section.next();
+            if self.format == ResponseFormat::Json {
+                match self.section {
+                    Some(next) => {
+                        out.extend_from_slice(format!("],\"{}\":[", next.key()).as_bytes())
+                    }
+                    None => out.extend_from_slice(b"]}"),
+                }
+            }
+            if !out.is_empty() && page.len() > 0 {
+                return Ok(Some(Bytes::from(out)));
+            }
+        }
+        Ok((!out.is_empty()).then(|| Bytes::from(out)))
+    }
+}
+
+#[doc = r#"Stream the records dated within [from, to] as `format`, page by page, with bed and
+wake times rounded to `rounding` and redacted with `redaction` when given.
+
+The returned stream owns a clone of the pool and is `'static`, so it can back a response body
+directly (`axum::body::Body::from_stream`)."#]
+pub fn stream(
+    db: Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    format: ResponseFormat,
+    rounding: TimeRounding,
+    redaction: Option<RedactionPolicy>,
+) -> impl Stream<Item = Result<Bytes, Error>> + Send + 'static {
+    let cursor = Cursor {
+        db,
+        format,
+        from,
+        to,
+        rounding,
+        redaction,
+        section: Some(Section::Sleep),
+        after: None,
+        started: false,
+    };
+    futures_util::stream::unfold(Some(cursor), |cursor| async move {
+        let mut cursor = cursor?;
+        match cursor.next_chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(cursor))),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::error!(error = ?e, "data export failed");
+                Some((Err(e), None))
+            }
+        }
+    })
+}
@@ -6,7 +6,11 @@ and enforce SQLite foreign key constraints.
 [`Db`]: crate::db::Db
 "#]
 
-use sqlx::{Pool, Sqlite, sqlite::SqlitePoolOptions};
+use crate::error::{ConfigError, Error};
+use sqlx::{
+    Pool, Sqlite,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
 
 /// Pooled Sqlite connection handle used by the application.
 ///
@@ -32,19 +36,15 @@ sqlx::query("SELECT 1").execute(&db).await?;
 ```
 
 # Errors
-- Returns [`sqlx::Error::Configuration`] if `DATABASE_URL` is missing.
-- Returns other [`sqlx::Error`] variants if the connection or PRAGMA execution fails.
+- Returns [`Error::Config`] if `DATABASE_URL` is missing.
+- Returns [`Error::Database`] if the connection or PRAGMA execution fails.
 
-[`sqlx::Error::Configuration`]: sqlx::Error
+[`Error::Config`]: crate::error::Error::Config
+[`Error::Database`]: crate::error::Error::Database
 "#]
-pub async fn connect() -> Result<Db, sqlx::Error> {
+pub async fn connect() -> Result<Db, Error> {
     dotenvy::dotenv().ok();
-    use std::io;
-    let url = std::env::var("DATABASE_URL").map_err(|_| {
-        sqlx::Error::Configuration(
-            io::Error::new(io::ErrorKind::NotFound, "DATABASE_URL missing").into(),
-        )
-    })?;
+    let url = std::env::var("DATABASE_URL").map_err(|_| ConfigError::missing("DATABASE_URL"))?;
 
     let pool = SqlitePoolOptions::new().connect(&url).await?;
     sqlx::query("PRAGMA foreign_keys = ON")
@@ -52,3 +52,23 @@ pub async fn connect() -> Result<Db, sqlx::Error> {
         .await?;
     Ok(pool)
 }
+
+#[doc = r#"Open (creating if missing) the SQLite database file at `path` with foreign keys enabled.
+
+Used by [`tenant::TenantRegistry`] for per-tenant databases. Foreign keys are set on every
+pooled connection.
+
+# Errors
+- Returns [`Error::Database`] if the file cannot be created or opened.
+
+[`Error::Database`]: crate::error::Error::Database
+
+[`tenant::TenantRegistry`]: crate::tenant::TenantRegistry
+"#]
+pub async fn connect_file(path: &std::path::Path) -> Result<Db, Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .foreign_keys(true);
+    Ok(SqlitePoolOptions::new().connect_with(options).await?)
+}
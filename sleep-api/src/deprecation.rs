@@ -0,0 +1,401 @@
+#![doc = r#"Deprecated endpoint telemetry
+
+Counts calls to legacy and aliased endpoints so maintainers can see when a compatibility shim
+is no longer used and can be removed. [`track`] wraps every route and counts each request
+matching [`LEGACY_ENDPOINTS`] in the router's [`DeprecationCounters`], in memory. Counts reach
+the database in batches: every [`FLUSH_INTERVAL`] they are added to `deprecated_usage`, except
+while the database is degraded (see [`crate::integrity`]), when they stay buffered until it
+recovers. Counts still buffered when the server stops are lost.
+
+- `GET /api/admin/deprecations` reports stored plus buffered counts through [`report`].
+- `GET /api/admin/metrics` exposes the calls since startup as the Prometheus counter
+  `sleep_api_deprecated_calls_total{endpoint="..."}` ([`metrics`]).
+
+Tracked endpoints:
+- `POST /api/login.json` — the JSON login, deprecated in the OpenAPI spec in favour of
+  `POST /api/login`.
+- `POST /api/login` with a form-encoded body — the pre-`/api` form login, still used by the
+  browser login page, so it has no replacement yet; counting it shows which clients remain.
+- `GET|POST /api/settings/sleep-goal` — alias of `GET|PUT /api/goals`.
+
+Counting never fails a request: a failed flush is logged and its counts are kept for the next
+one.
+"#]
+
+use crate::{db::Db, error::ApiError, integrity::IntegrityState, time::SharedClock};
+use axum::{
+    extract::{Request, State},
+    http::{Method, header},
+    middleware::Next,
+    response::Response,
+};
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sqlx::{FromRow, Sqlite};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often buffered counts are added to `deprecated_usage`.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A legacy endpoint: its counter key, how to recognise a request to it, and its replacement.
+pub struct LegacyEndpoint {
+    pub endpoint: &'static str,
+    pub replacement: Option<&'static str>,
+    matches: fn(&Request) -> bool,
+}
+
+/// Every tracked endpoint, in report order.
+pub const LEGACY_ENDPOINTS: &[LegacyEndpoint] = &[
+    LegacyEndpoint {
+        endpoint: "POST /api/login.json",
+        replacement: Some("POST /api/login"),
+        matches: |req| req.method() == Method::POST && req.uri().path() == "/api/login.json",
+    },
+    LegacyEndpoint {
+        endpoint: "POST /api/login (form)",
+        replacement: None,
+        matches: |req| {
+            req.method() == Method::POST
+                && req.uri().path() == "/api/login"
+                && req
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"))
+        },
+    },
+    LegacyEndpoint {
+        endpoint: "GET /api/settings/sleep-goal",
+        replacement: Some("GET /api/goals"),
+        matches: |req| {
+            req.method() == Method::GET && req.uri().path() == "/api/settings/sleep-goal"
+        },
+    },
+    LegacyEndpoint {
+        endpoint: "POST /api/settings/sleep-goal",
+        replacement: Some("PUT /api/goals"),
+        matches: |req| {
+            req.method() == Method::POST && req.uri().path() == "/api/settings/sleep-goal"
+        },
+    },
+];
+
+/// The tracked endpoint `req` calls, if any.
+pub fn legacy_endpoint(req: &Request) -> Option<&'static LegacyEndpoint> {
+    LEGACY_ENDPOINTS.iter().find(|e| (e.matches)(req))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pending {
+    calls: i64,
+    first_seen: NaiveDateTime,
+    last_seen: NaiveDateTime,
+}
+
+impl Pending {
+    fn merge(&mut self, other: Pending) {
+        self.calls += other.calls;
+        self.first_seen = self.first_seen.min(other.first_seen);
+        self.last_seen = self.last_seen.max(other.last_seen);
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    /// Calls not yet written to `deprecated_usage`.
+    pending: Mutex<HashMap<&'static str, Pending>>,
+    /// Calls since the router was built, for [`metrics`].
+    totals: Mutex<HashMap<&'static str, u64>>,
+    /// Whether the flush task was started.
+    flushing: AtomicBool,
+}
+
+#[derive(Clone, Default)]
+#[doc = r#"Call counters of one router, held in [`AppState`](crate::app::AppState).
+
+[`track`] counts into them and starts the flush task on the first tracked call; the task stops
+once the router is dropped."#]
+pub struct DeprecationCounters(Arc<Counters>);
+
+impl DeprecationCounters {
+    /// Count one call to `endpoint` at `now` (UTC).
+    pub fn count(&self, endpoint: &'static str, now: NaiveDateTime) {
+        let call = Pending {
+            calls: 1,
+            first_seen: now,
+            last_seen: now,
+        };
+        lock(&self.0.pending)
+            .entry(endpoint)
+            .and_modify(|p| p.merge(call))
+            .or_insert(call);
+        *lock(&self.0.totals).entry(endpoint).or_default() += 1;
+    }
+
+    /// Calls per tracked endpoint since the router was built, in [`LEGACY_ENDPOINTS`] order.
+    pub fn totals(&self) -> Vec<(&'static str, u64)> {
+        let totals = lock(&self.0.totals);
+        LEGACY_ENDPOINTS
+            .iter()
+            .map(|e| (e.endpoint, totals.get(e.endpoint).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    #[doc = r#"Add the buffered counts to `deprecated_usage`, unless `integrity` reports a
+degraded database.
+
+# Errors
+
+Returns the database error of the first failed write; the counts not written stay buffered.
+"#]
+    pub async fn flush(&self, db: &Db, integrity: &IntegrityState) -> Result<(), sqlx::Error> {
+        if integrity.degraded() {
+            return Ok(());
+        }
+        let batch: Vec<_> = lock(&self.0.pending).drain().collect();
+        for (i, (endpoint, counts)) in batch.iter().enumerate() {
+            if let Err(e) = write(db, endpoint, *counts).await {
+                let mut pending = lock(&self.0.pending);
+                for (endpoint, counts) in &batch[i..] {
+                    pending
+                        .entry(endpoint)
+                        .and_modify(|p| p.merge(*counts))
+                        .or_insert(*counts);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Start flushing every [`FLUSH_INTERVAL`], once per router.
+    fn start_flushing(&self, db: Db, integrity: IntegrityState) {
+        if self.0.flushing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let counters = Arc::downgrade(&self.0);
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(FLUSH_INTERVAL);
+            tick.tick().await;
+            loop {
+                tick.tick().await;
+                let Some(counters) = counters.upgrade() else {
+                    return;
+                };
+                if let Err(e) = DeprecationCounters(counters).flush(&db, &integrity).await {
+                    tracing::warn!(error = ?e, "failed to store deprecated endpoint counts");
+                }
+            }
+        });
+    }
+}
+
+fn lock<T>(m: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[doc = r#"Middleware counting requests to [`LEGACY_ENDPOINTS`], whatever their outcome."#]
+pub async fn track(
+    State(counters): State<DeprecationCounters>,
+    State(db): State<Db>,
+    State(integrity): State<IntegrityState>,
+    State(clock): State<SharedClock>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if let Some(legacy) = legacy_endpoint(&req) {
+        counters.count(legacy.endpoint, clock.now_utc().naive_utc());
+        counters.start_flushing(db, integrity);
+    }
+    next.run(req).await
+}
+
+async fn write(db: &Db, endpoint: &str, counts: Pending) -> Result<(), sqlx::Error> {
+    sqlx::query::<Sqlite>(
+        "INSERT INTO deprecated_usage (endpoint, calls, first_seen, last_seen) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(endpoint) DO UPDATE SET calls = calls + excluded.calls, \
+         first_seen = MIN(first_seen, excluded.first_seen), \
+         last_seen = MAX(last_seen, excluded.last_seen)",
+    )
+    .bind(endpoint)
+    .bind(counts.calls)
+    .bind(counts.first_seen)
+    .bind(counts.last_seen)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Usage of one legacy endpoint; `calls` is 0 and the dates `None` when never called."#]
+pub struct DeprecatedEndpointUsage {
+    pub endpoint: String,
+    pub replacement: Option<String>,
+    pub calls: i64,
+    pub first_seen: Option<NaiveDateTime>,
+    pub last_seen: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Response of `GET /api/admin/deprecations`: every tracked endpoint, in
+[`LEGACY_ENDPOINTS`] order."#]
+pub struct DeprecationReport {
+    pub endpoints: Vec<DeprecatedEndpointUsage>,
+}
+
+#[derive(FromRow)]
+struct UsageRow {
+    endpoint: String,
+    calls: i64,
+    first_seen: NaiveDateTime,
+    last_seen: NaiveDateTime,
+}
+
+#[doc = r#"Counters for every tracked endpoint, including the ones never called: the stored
+counts plus those still buffered in `counters`.
+
+# Errors
+
+Returns a database error.
+"#]
+pub async fn report(
+    db: &Db,
+    counters: &DeprecationCounters,
+) -> Result<DeprecationReport, ApiError> {
+    let rows = sqlx::query_as::<Sqlite, UsageRow>(
+        "SELECT endpoint, calls, first_seen, last_seen FROM deprecated_usage",
+    )
+    .fetch_all(db)
+    .await?;
+    let pending = lock(&counters.0.pending).clone();
+    let endpoints = LEGACY_ENDPOINTS
+        .iter()
+        .map(|e| {
+            let stored = rows
+                .iter()
+                .find(|r| r.endpoint == e.endpoint)
+                .map(|r| Pending {
+                    calls: r.calls,
+                    first_seen: r.first_seen,
+                    last_seen: r.last_seen,
+                });
+            let counts = match (stored, pending.get(e.endpoint)) {
+                (Some(mut stored), Some(buffered)) => {
+                    stored.merge(*buffered);
+                    Some(stored)
+                }
+                (stored, buffered) => stored.or(buffered.copied()),
+            };
+            DeprecatedEndpointUsage {
+                endpoint: e.endpoint.to_string(),
+                replacement: e.replacement.map(str::to_string),
+                calls: counts.map_or(0, |c| c.calls),
+                first_seen: counts.map(|c| c.first_seen),
+                last_seen: counts.map(|c| c.last_seen),
+            }
+        })
+        .collect();
+    Ok(DeprecationReport { endpoints })
+}
+
+#[doc = r#"Calls since startup in the Prometheus text format, one sample per tracked endpoint.
+
+# Example
+
+```rust
+use sleep_api::deprecation::{DeprecationCounters, metrics};
+
+let counters = DeprecationCounters::default();
+let now = chrono::DateTime::UNIX_EPOCH.naive_utc();
+counters.count("POST /api/login.json", now);
+let text = metrics(&counters);
+assert!(text.contains("sleep_api_deprecated_calls_total{endpoint=\"POST /api/login.json\"} 1\n"));
+```
+"#]
+pub fn metrics(counters: &DeprecationCounters) -> String {
+    let mut out = String::from(
+        "# HELP sleep_api_deprecated_calls_total Calls to legacy or aliased endpoints since startup.\n\
+         # TYPE sleep_api_deprecated_calls_total counter\n",
+    );
+    for (endpoint, calls) in counters.totals() {
+        let _ = writeln!(
+            out,
+            "sleep_api_deprecated_calls_total{{endpoint=\"{endpoint}\"}} {calls}"
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn usage_table() -> Db {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE deprecated_usage (endpoint TEXT PRIMARY KEY, calls INTEGER NOT NULL, \
+             first_seen DATETIME NOT NULL, last_seen DATETIME NOT NULL)",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        db
+    }
+
+    fn at(h: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn flush_adds_buffered_counts_unless_degraded() {
+        let db = usage_table().await;
+        let counters = DeprecationCounters::default();
+        let endpoint = LEGACY_ENDPOINTS[0].endpoint;
+        counters.count(endpoint, at(1));
+        counters.count(endpoint, at(2));
+
+        let degraded = IntegrityState::default();
+        degraded.record(crate::integrity::IntegrityReport {
+            ok: false,
+            check: crate::integrity::IntegrityCheck::Quick,
+            problems: vec!["malformed".into()],
+            checked_at: chrono::Utc::now(),
+        });
+        counters.flush(&db, &degraded).await.unwrap();
+        let stored = sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM deprecated_usage")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(stored, 0);
+
+        let healthy = IntegrityState::default();
+        counters.flush(&db, &healthy).await.unwrap();
+        counters.count(endpoint, at(3));
+        counters.flush(&db, &healthy).await.unwrap();
+        let row = sqlx::query_as::<Sqlite, UsageRow>(
+            "SELECT endpoint, calls, first_seen, last_seen FROM deprecated_usage",
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(
+            (row.calls, row.first_seen, row.last_seen),
+            (3, at(1), at(3))
+        );
+        assert_eq!(counters.totals()[0], (endpoint, 3));
+
+        counters.count(endpoint, at(4));
+        let report = report(&db, &counters).await.unwrap();
+        assert_eq!(report.endpoints[0].calls, 4);
+        assert_eq!(report.endpoints[0].last_seen, Some(at(4)));
+        assert_eq!(report.endpoints[1].calls, 0);
+    }
+}
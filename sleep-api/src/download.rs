@@ -0,0 +1,119 @@
+#![doc = r#"Resumable file downloads
+
+[`serve_resumable`] serves a file from disk so an interrupted transfer of a large backup or
+export can continue where it stopped instead of starting over:
+
+- Responses carry `Accept-Ranges: bytes`. A single `Range: bytes=<start>-[<end>]` gets
+  `206 Partial Content` with `Content-Range`; multipart or unsatisfiable ranges get `416`.
+- Every response carries the caller's strong `ETag`. `If-Range` honours the range only when it
+  equals that ETag; otherwise (a different ETag, or a date) the whole file is sent with `200`,
+  so a client never splices bytes of two different files.
+- Bodies are never content-encoded, whatever `Accept-Encoding` asks for: offsets always count
+  bytes of the file on disk, which on-the-fly compression would break.
+- `HEAD` returns the headers only; `Content-Disposition: attachment` names the file.
+
+Generated documents (`GET /api/export`) have no file of their own: [`spool`] writes the
+rendered stream to a temporary file first and hashes it on the way, so the same data always
+gets the same strong ETag and a resumed request can be checked against it.
+"#]
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderValue, Method, Request, header},
+    response::Response,
+};
+use futures_util::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+/// Distinguishes concurrent spools of this process in the temporary directory.
+static SPOOLED: AtomicU64 = AtomicU64::new(0);
+
+#[doc = r#"Write `chunks` to a new file in the system temporary directory and return its path
+with the lowercase hex SHA-256 of its bytes, ready for [`serve_resumable`].
+
+The caller removes the file once it is served; on Unix that is safe as soon as
+[`serve_resumable`] has returned, since the response holds the file open.
+
+# Errors
+The first error of `chunks`, or an I/O error writing the file; the partial file is removed
+either way."#]
+pub async fn spool<S, E>(chunks: S) -> Result<(PathBuf, String), E>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: From<std::io::Error>,
+{
+    let path = std::env::temp_dir().join(format!(
+        "sleep-api-spool-{}-{}",
+        std::process::id(),
+        SPOOLED.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = async {
+        let mut file = tokio::fs::File::create(&path).await?;
+        let mut digest = Sha256::new();
+        let mut chunks = std::pin::pin!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            digest.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok::<_, E>(hex::encode(digest.finalize()))
+    }
+    .await;
+    match written {
+        Ok(sha256) => Ok((path, sha256)),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            Err(e)
+        }
+    }
+}
+
+#[doc = r#"Serve `path` for `method` (`GET` or `HEAD`) honouring the request's `Range` and
+`If-Range` headers.
+
+`etag` identifies the file's content (e.g. its SHA-256) and must change whenever the bytes
+do; `filename` is offered to the client in `Content-Disposition`."#]
+pub async fn serve_resumable(
+    method: &Method,
+    headers: &HeaderMap,
+    path: &Path,
+    etag: &str,
+    filename: &str,
+) -> Response {
+    let etag = format!("\"{etag}\"");
+    let range_applies = headers
+        .get(header::IF_RANGE)
+        .is_none_or(|v| v.as_bytes() == etag.as_bytes());
+
+    // Only the range headers are forwarded: no Accept-Encoding, so the body is the file as is.
+    let mut request = Request::builder().method(method.clone()).uri("/");
+    if range_applies && let Some(range) = headers.get(header::RANGE) {
+        request = request.header(header::RANGE, range);
+    }
+    let request = request.body(Body::empty()).unwrap_or_default();
+    let response = match ServeFile::new(path).oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+
+    let (mut parts, body) = response.into_parts();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(header::ETAG, value);
+    }
+    let disposition = format!("attachment; filename=\"{}\"", filename.replace('"', ""));
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        parts.headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    parts.headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-cache"),
+    );
+    Response::from_parts(parts, Body::new(body))
+}
@@ -1,7 +1,16 @@
+#![doc = r#"Errors
+
+[`Error`] (re-exported as `sleep_api::Error`) is returned by the public library functions
+([`crate::db::connect`], [`crate::repository`], [`crate::handlers`]) so embedders can match
+on variants instead of messages. At the HTTP boundary it converts into [`ApiError`], which is
+rendered as `{code, message}` JSON; [`Problem`] renders RFC 9457 `application/problem+json`
+bodies.
+"#]
+
 use crate::domain::DomainError;
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde_json::json;
@@ -9,6 +18,89 @@ use thiserror::Error;
 use tracing::error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
+#[doc = r#"Error returned by the library's public functions.
+
+New variants may be added; match with a wildcard arm. [`std::error::Error::source`] exposes
+the underlying [`DomainError`], [`sqlx::Error`], migration, [`ConfigError`] or I/O error.
+
+# Example
+
+```rust,no_run
+# async fn demo() {
+match sleep_api::db::connect().await {
+    Ok(_db) => {}
+    Err(sleep_api::Error::Config(e)) => eprintln!("fix {}: {}", e.name, e.message),
+    Err(e) => eprintln!("cannot open database: {e}"),
+}
+# }
+```
+"#]
+pub enum Error {
+    /// Input failed validation or violates a domain rule.
+    #[error("invalid input: {0}")]
+    Domain(#[from] DomainError),
+    /// A database operation failed.
+    #[error("database error")]
+    Database(#[from] sqlx::Error),
+    /// Applying migrations failed.
+    #[error("migration failed")]
+    Migration(#[from] sqlx::migrate::MigrateError),
+    /// A required setting is missing or invalid.
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    /// The requested record does not exist.
+    #[error("not found")]
+    NotFound,
+    /// The operation is not allowed in the current state (e.g. a locked entry).
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    /// A configured usage quota is used up (see [`crate::middleware::quota`]).
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(String),
+    /// Reading or writing a file (exports, backups) failed.
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Shorthand for a [`DomainError::InvalidInput`].
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Error::Domain(DomainError::InvalidInput(message.into()))
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid configuration: {name}: {message}")]
+#[doc = r#"A missing or invalid setting, named by its environment variable."#]
+pub struct ConfigError {
+    pub name: &'static str,
+    pub message: String,
+}
+
+impl ConfigError {
+    /// The variable `name` is required but unset.
+    pub fn missing(name: &'static str) -> Self {
+        ConfigError {
+            name,
+            message: "not set".into(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[doc = r#"Handler error mapped to an HTTP status.
+
+- `Db` → 500 `{code:"internal"}`
+- `NotFound` → 404 `{code:"not_found"}`
+- `InvalidInput(message)` → 400 `{code:"bad_request", message}`
+- `Forbidden(message)` → 403 `{code:"forbidden", message}`
+- `PayloadTooLarge(message)` → 413 `{code:"payload_too_large", message}`
+- `QuotaExceeded(message)` → 429 `{code:"quota_exceeded", message}`
+- `RateLimited(message)` → 429 `{code:"rate_limited", message}` (see [`crate::middleware::rate_limit`])
+- `Degraded(message)` → 503 `{code:"degraded", message}` (see [`crate::integrity`])
+- `Internal(error)` → 500 `{code:"internal"}`
+"#]
 pub enum ApiError {
     #[error("database error: {0}")]
     Db(#[from] sqlx::Error),
@@ -16,6 +108,18 @@ pub enum ApiError {
     NotFound,
     #[error("invalid input: {0}")]
     InvalidInput(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("payload too large: {0}")]
+    PayloadTooLarge(String),
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    #[error("degraded: {0}")]
+    Degraded(String),
+    #[error("internal error: {0}")]
+    Internal(#[source] Error),
 }
 
 impl IntoResponse for ApiError {
@@ -39,6 +143,39 @@ impl IntoResponse for ApiError {
                 Json(json!({"code":"bad_request","message": msg})),
             )
                 .into_response(),
+            ApiError::Forbidden(msg) => (
+                StatusCode::FORBIDDEN,
+                Json(json!({"code":"forbidden","message": msg})),
+            )
+                .into_response(),
+            ApiError::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(json!({"code":"payload_too_large","message": msg})),
+            )
+                .into_response(),
+            ApiError::QuotaExceeded(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"code":"quota_exceeded","message": msg})),
+            )
+                .into_response(),
+            ApiError::RateLimited(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"code":"rate_limited","message": msg})),
+            )
+                .into_response(),
+            ApiError::Degraded(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({"code":"degraded","message": msg})),
+            )
+                .into_response(),
+            ApiError::Internal(e) => {
+                error!(?e, "internal error");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"code":"internal","message":"internal error"})),
+                )
+                    .into_response()
+            }
         }
     }
 }
@@ -49,3 +186,73 @@ impl From<DomainError> for ApiError {
         ApiError::InvalidInput(err.to_string())
     }
 }
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Domain(e) => e.into(),
+            Error::Database(e) => ApiError::Db(e),
+            Error::NotFound => ApiError::NotFound,
+            Error::Forbidden(msg) => ApiError::Forbidden(msg),
+            Error::QuotaExceeded(msg) => ApiError::QuotaExceeded(msg),
+            other => ApiError::Internal(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[doc = r#"RFC 9457 `application/problem+json` error body.
+
+Serialized as `{type, title, status, detail, ...extensions}`. `code` is always included as an
+extension so clients can branch on it like the `{code, message}` errors elsewhere.
+"#]
+pub struct Problem {
+    status: StatusCode,
+    title: String,
+    detail: Option<String>,
+    extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Problem {
+    /// Create a problem with a machine-readable `code` and a short human `title`.
+    pub fn new(status: StatusCode, code: &str, title: impl Into<String>) -> Self {
+        let mut extensions = serde_json::Map::new();
+        extensions.insert("code".into(), json!(code));
+        Problem {
+            status,
+            title: title.into(),
+            detail: None,
+            extensions,
+        }
+    }
+
+    /// Set the occurrence-specific `detail`.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Add an extension member.
+    pub fn with(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.extensions.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let mut body = self.extensions;
+        body.insert("type".into(), json!("about:blank"));
+        body.insert("title".into(), json!(self.title));
+        body.insert("status".into(), json!(self.status.as_u16()));
+        if let Some(detail) = self.detail {
+            body.insert("detail".into(), json!(detail));
+        }
+        (
+            self.status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(body),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,252 @@
+#![doc = r#"Domain events
+
+Every mutation in the handler layer emits one typed [`DomainEvent`] on the router's
+[`EventBus`] after the change is committed. Side channels (outbox, server-sent events,
+cache invalidation, insight refresh) subscribe to the bus instead of each patching every
+handler.
+
+Delivery is in-process and best-effort: events are dropped when nobody subscribes, and a
+subscriber that lags more than [`EVENT_BUFFER`] events behind sees
+`RecvError::Lagged`. Each router (and so each tenant) has its own bus.
+
+# Example
+
+```rust
+# use sleep_api::events::{DomainEvent, EventBus};
+# use chrono::NaiveDate;
+# #[tokio::main(flavor = "current_thread")]
+# async fn main() {
+let bus = EventBus::new();
+let mut rx = bus.subscribe();
+let date = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+bus.emit(DomainEvent::SleepDeleted { id: 7 });
+assert_eq!(rx.recv().await.unwrap(), DomainEvent::SleepDeleted { id: 7 });
+assert_eq!(DomainEvent::NoteCreated { id: 1, date }.name(), "note_created");
+# }
+```
+"#]
+
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before it starts lagging.
+pub const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[doc = r#"A committed change to user data or settings.
+
+Serialized with a `type` tag, e.g. `{"type":"sleep_created","id":1,"date":"2025-06-01","duration_min":450}`.
+Dates are the entry's own date (wake date for sleep) so consumers can invalidate per day.
+"#]
+pub enum DomainEvent {
+    SleepCreated {
+        id: i64,
+        date: NaiveDate,
+        duration_min: i32,
+    },
+    SleepUpdated {
+        id: i64,
+        date: NaiveDate,
+        duration_min: i32,
+    },
+    SleepDeleted {
+        id: i64,
+    },
+    ExerciseCreated {
+        id: i64,
+        date: NaiveDate,
+    },
+    ExerciseUpdated {
+        id: i64,
+        date: NaiveDate,
+    },
+    ExerciseDeleted {
+        id: i64,
+    },
+    NoteCreated {
+        id: i64,
+        date: NaiveDate,
+    },
+    NoteUpdated {
+        id: i64,
+        date: NaiveDate,
+    },
+    NoteDeleted {
+        id: i64,
+    },
+    BodyMetricSaved {
+        id: i64,
+        date: NaiveDate,
+    },
+    BodyMetricDeleted {
+        id: i64,
+    },
+    BodyMetricsImported {
+        source: &'static str,
+        imported: usize,
+    },
+    DisturbanceSaved {
+        id: i64,
+        date: NaiveDate,
+    },
+    DisturbanceDeleted {
+        id: i64,
+    },
+    ExperimentSaved {
+        id: i64,
+    },
+    ExperimentDeleted {
+        id: i64,
+    },
+    MedicationSaved {
+        id: i64,
+    },
+    MedicationDeleted {
+        id: i64,
+    },
+    MedicationEventSaved {
+        id: i64,
+        date: NaiveDate,
+    },
+    MedicationEventDeleted {
+        id: i64,
+    },
+    RoutineRecorded {
+        date: NaiveDate,
+    },
+    MoodRecorded {
+        date: NaiveDate,
+    },
+    /// A sleep session or note (`entity` is `sleep` or `note`) was starred or unstarred.
+    StarChanged {
+        entity: &'static str,
+        id: i64,
+        starred: bool,
+    },
+    /// A file was attached to a day (`POST /api/attachments`).
+    AttachmentCreated {
+        id: i64,
+        date: NaiveDate,
+    },
+    /// An API token was created (`POST /api/tokens`).
+    ApiTokenCreated {
+        id: i64,
+    },
+    /// An API token was revoked (`DELETE /api/tokens/{id}`).
+    ApiTokenRevoked {
+        id: i64,
+    },
+    /// A share link was created (`POST /api/share-links`).
+    ShareLinkCreated {
+        id: i64,
+    },
+    /// A share link was revoked (`DELETE /api/share-links/{id}`).
+    ShareLinkRevoked {
+        id: i64,
+    },
+    /// A known login device was removed from `GET /api/account/devices`.
+    DeviceForgotten {
+        id: i64,
+    },
+    /// A settings key changed (`timezone`, `sleep_goal`, `account_email`, ...).
+    SettingChanged {
+        key: &'static str,
+    },
+}
+
+impl DomainEvent {
+    /// The serialized `type` tag, e.g. `sleep_created`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::SleepCreated { .. } => "sleep_created",
+            DomainEvent::SleepUpdated { .. } => "sleep_updated",
+            DomainEvent::SleepDeleted { .. } => "sleep_deleted",
+            DomainEvent::ExerciseCreated { .. } => "exercise_created",
+            DomainEvent::ExerciseUpdated { .. } => "exercise_updated",
+            DomainEvent::ExerciseDeleted { .. } => "exercise_deleted",
+            DomainEvent::NoteCreated { .. } => "note_created",
+            DomainEvent::NoteUpdated { .. } => "note_updated",
+            DomainEvent::NoteDeleted { .. } => "note_deleted",
+            DomainEvent::BodyMetricSaved { .. } => "body_metric_saved",
+            DomainEvent::BodyMetricDeleted { .. } => "body_metric_deleted",
+            DomainEvent::BodyMetricsImported { .. } => "body_metrics_imported",
+            DomainEvent::DisturbanceSaved { .. } => "disturbance_saved",
+            DomainEvent::DisturbanceDeleted { .. } => "disturbance_deleted",
+            DomainEvent::ExperimentSaved { .. } => "experiment_saved",
+            DomainEvent::ExperimentDeleted { .. } => "experiment_deleted",
+            DomainEvent::MedicationSaved { .. } => "medication_saved",
+            DomainEvent::MedicationDeleted { .. } => "medication_deleted",
+            DomainEvent::MedicationEventSaved { .. } => "medication_event_saved",
+            DomainEvent::MedicationEventDeleted { .. } => "medication_event_deleted",
+            DomainEvent::RoutineRecorded { .. } => "routine_recorded",
+            DomainEvent::MoodRecorded { .. } => "mood_recorded",
+            DomainEvent::StarChanged { .. } => "star_changed",
+            DomainEvent::AttachmentCreated { .. } => "attachment_created",
+            DomainEvent::ApiTokenCreated { .. } => "api_token_created",
+            DomainEvent::ApiTokenRevoked { .. } => "api_token_revoked",
+            DomainEvent::ShareLinkCreated { .. } => "share_link_created",
+            DomainEvent::ShareLinkRevoked { .. } => "share_link_revoked",
+            DomainEvent::DeviceForgotten { .. } => "device_forgotten",
+            DomainEvent::SettingChanged { .. } => "setting_changed",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[doc = r#"Fan-out channel for [`DomainEvent`]s. Cheap to clone; clones share subscribers."#]
+pub struct EventBus {
+    tx: broadcast::Sender<DomainEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUFFER);
+        EventBus { tx }
+    }
+
+    #[doc = r#"Publish `event` to all current subscribers. Never blocks or fails."#]
+    pub fn emit(&self, event: DomainEvent) {
+        tracing::debug!(event = event.name(), ?event, "domain event");
+        // Err only means there are no subscribers right now.
+        let _ = self.tx.send(event);
+    }
+
+    #[doc = r#"Receive every event emitted after this call."#]
+    // Consumed by library users and tests; the server has no subscribers yet.
+    #[allow(dead_code)]
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.tx.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_with_type_tag() {
+        let event = DomainEvent::SleepCreated {
+            id: 3,
+            date: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+            duration_min: 450,
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"type": "sleep_created", "id": 3, "date": "2025-06-01", "duration_min": 450})
+        );
+        let setting = DomainEvent::SettingChanged { key: "timezone" };
+        assert_eq!(
+            serde_json::to_value(&setting).unwrap()["type"],
+            setting.name()
+        );
+    }
+}
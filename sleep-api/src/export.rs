@@ -0,0 +1,538 @@
+#![doc = r#"Full exports with an integrity manifest
+
+`sleepctl export --out DIR` writes a self-contained copy of the instance for cold storage:
+
+- `sleep.sqlite` — a consistent snapshot of the whole database (`VACUUM INTO`), restorable by
+  pointing `DATABASE_URL` at it.
+- `sleep_sessions.csv` — every sleep session with its metrics, readable without SQLite.
+- `manifest.json` — a [`Manifest`] listing each file's size and SHA-256 and a digest of every
+  exported session, signed with HMAC-SHA256 (`sha256=<hex>`, see [`signature`]) keyed with
+  `EXPORT_SIGNING_KEY`. Without a key the manifest is written unsigned.
+
+`sleepctl verify-export DIR` ([`verify_export`]) recomputes every hash and checks the signature,
+so silent corruption or tampering of an archive is detected before it is needed.
+[`compare_export`] (`POST /api/admin/compare-export`) goes the other way: it checks the live
+database against a downloaded manifest and lists the sessions changed or deleted since.
+
+The server writes the same exports as backups under `BACKUP_DIR` (`POST /api/admin/backups`)
+and serves their files for resumable download; [`list_exports`] and [`find_file`] back those
+endpoints.
+
+The signature covers the manifest without its `signature` field, serialized as compact JSON
+with fields in declaration order. Version 1 manifests have no `records`; their signature
+covers `version`, `created_at` and `files` only, and they are still read and verified.
+
+[`signature`]: crate::security::signature
+"#]
+
+use crate::negotiate::{CsvTable, cell, to_csv};
+use crate::security::signature;
+use crate::{db::Db, models::SleepListItem, repository};
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Manifest file name inside an export directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Database snapshot file name inside an export directory.
+pub const DATABASE_FILE: &str = "sleep.sqlite";
+
+/// Sleep session CSV file name inside an export directory.
+pub const SESSIONS_FILE: &str = "sleep_sessions.csv";
+
+/// Manifest format version written by [`write_export`]; version 2 added `records`.
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// Oldest manifest format [`read_manifest`] accepts.
+const MIN_MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+#[doc = r#"Failure to write or read an export."#]
+pub enum ExportError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("database error: {0}")]
+    Db(#[from] crate::error::Error),
+    #[error("csv error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("invalid manifest: {0}")]
+    Manifest(String),
+}
+
+impl From<sqlx::Error> for ExportError {
+    fn from(err: sqlx::Error) -> Self {
+        ExportError::Db(err.into())
+    }
+}
+
+impl From<ExportError> for crate::error::Error {
+    fn from(err: ExportError) -> Self {
+        match err {
+            ExportError::Io(e) => e.into(),
+            ExportError::Db(e) => e,
+            ExportError::Csv(e) => std::io::Error::from(e).into(),
+            ExportError::Manifest(message) => crate::error::Error::invalid(message),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"One exported file: path relative to the export directory, size, and SHA-256 (hex)."#]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"One exported sleep session: its id, wake date, and the SHA-256 (hex) of the session
+as exported (see [`record_digest`])."#]
+pub struct ManifestRecord {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Contents of `manifest.json`.
+
+`records` lists every session in `sleep_sessions.csv` by id; it is empty in version 1
+manifests. `signature` is `None` for exports written without `EXPORT_SIGNING_KEY`.
+"#]
+pub struct Manifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<ManifestFile>,
+    #[serde(default)]
+    pub records: Vec<ManifestRecord>,
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"An export directory found by [`list_exports`]: its name, manifest files, and whether
+the manifest is signed (the signature itself is not checked here)."#]
+pub struct ExportSummary {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<ManifestFile>,
+    pub signed: bool,
+}
+
+impl ExportSummary {
+    /// Summary of `manifest`, stored in the directory `name`.
+    pub fn new(name: impl Into<String>, manifest: Manifest) -> Self {
+        ExportSummary {
+            name: name.into(),
+            created_at: manifest.created_at,
+            signed: manifest.signature.is_some(),
+            files: manifest.files,
+        }
+    }
+}
+
+/// The signed part of a [`Manifest`]; `records` only from version 2 on.
+#[derive(Serialize)]
+struct SignedPart<'a> {
+    version: u32,
+    created_at: &'a DateTime<Utc>,
+    files: &'a [ManifestFile],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    records: Option<&'a [ManifestRecord]>,
+}
+
+impl Manifest {
+    fn signed_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(&SignedPart {
+            version: self.version,
+            created_at: &self.created_at,
+            files: &self.files,
+            records: (self.version >= 2).then_some(self.records.as_slice()),
+        })
+        .unwrap_or_default()
+    }
+
+    fn signature_status(&self, key: Option<&[u8]>) -> SignatureStatus {
+        match (&self.signature, key) {
+            (None, _) => SignatureStatus::Unsigned,
+            (Some(_), None) => SignatureStatus::Unchecked,
+            (Some(sig), Some(key)) if signature::verify(key, &self.signed_bytes(), sig) => {
+                SignatureStatus::Valid
+            }
+            (Some(_), Some(_)) => SignatureStatus::Invalid,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"State of the manifest signature found by [`verify_export`] and [`compare_export`]."#]
+pub enum SignatureStatus {
+    /// Signature present and matching the key.
+    Valid,
+    /// Signature present but not matching (tampered manifest or different key).
+    Invalid,
+    /// The manifest was written without a key.
+    Unsigned,
+    /// Signature present but no key was given to check it.
+    Unchecked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[doc = r#"Result of [`verify_export`]. The export is intact when [`VerifyReport::ok`] holds.
+
+- `corrupted`: listed files whose size or hash differs.
+- `missing`: listed files that do not exist.
+- `unlisted`: files in the directory the manifest does not mention (reported, not an error).
+"#]
+pub struct VerifyReport {
+    pub signature: SignatureStatus,
+    pub verified: Vec<String>,
+    pub corrupted: Vec<String>,
+    pub missing: Vec<String>,
+    pub unlisted: Vec<String>,
+}
+
+impl VerifyReport {
+    #[doc = r#"Whether every listed file matches and the signature is not invalid.
+
+An unsigned or unchecked signature is accepted; callers that require signed exports should
+check [`VerifyReport::signature`] as well.
+"#]
+    pub fn ok(&self) -> bool {
+        self.corrupted.is_empty()
+            && self.missing.is_empty()
+            && self.signature != SignatureStatus::Invalid
+    }
+}
+
+struct SessionRows(Vec<SleepListItem>);
+
+impl CsvTable for SessionRows {
+    const HEADER: &'static [&'static str] = &[
+        "id",
+        "date",
+        "bed_time",
+        "wake_time",
+        "latency_min",
+        "awakenings",
+        "quality",
+        "duration_min",
+        "wake_feeling",
+        "sleep_inertia_min",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .map(|s| {
+                vec![
+                    s.id.to_string(),
+                    s.date.to_string(),
+                    s.bed_time.to_string(),
+                    s.wake_time.to_string(),
+                    s.latency_min.to_string(),
+                    s.awakenings.to_string(),
+                    s.quality.to_string(),
+                    cell(s.duration_min),
+                    cell(s.wake_feeling),
+                    cell(s.sleep_inertia_min),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[doc = r#"Write a full export of `db` into `dir` (created if needed) and return its manifest.
+
+`key` signs the manifest; pass `None` to write it unsigned.
+
+# Errors
+
+Returns [`ExportError`] when `dir` already holds an export, or on database and I/O failures.
+"#]
+pub async fn write_export(
+    db: &Db,
+    dir: &Path,
+    key: Option<&[u8]>,
+    now: DateTime<Utc>,
+) -> Result<Manifest, ExportError> {
+    std::fs::create_dir_all(dir)?;
+    let database = dir.join(DATABASE_FILE);
+    if database.exists() || dir.join(MANIFEST_FILE).exists() {
+        return Err(ExportError::Manifest(format!(
+            "{} already contains an export",
+            dir.display()
+        )));
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(database.to_string_lossy().into_owned())
+        .execute(db)
+        .await?;
+    if !database.is_file() {
+        return Err(ExportError::Manifest(
+            "database snapshot was not written (in-memory databases cannot be exported)".into(),
+        ));
+    }
+    let sessions = all_sessions(db).await?;
+    let records = sessions.iter().map(manifest_record).collect();
+    std::fs::write(dir.join(SESSIONS_FILE), to_csv(&SessionRows(sessions))?)?;
+
+    let mut files = Vec::new();
+    for name in [DATABASE_FILE, SESSIONS_FILE] {
+        files.push(describe_file(dir, name)?);
+    }
+    let mut manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_at: now,
+        files,
+        records,
+        signature: None,
+    };
+    manifest.signature = key.map(|k| signature::sign(k, &manifest.signed_bytes()));
+    let json =
+        serde_json::to_vec_pretty(&manifest).map_err(|e| ExportError::Manifest(e.to_string()))?;
+    std::fs::write(dir.join(MANIFEST_FILE), json)?;
+    Ok(manifest)
+}
+
+#[doc = r#"Check the export in `dir` against its manifest.
+
+`key` checks the signature; with `None` a signed manifest is reported as
+[`SignatureStatus::Unchecked`].
+
+# Errors
+
+Returns [`ExportError`] when the manifest is missing, unreadable, of an unknown version, or
+lists a path outside the export directory.
+"#]
+pub fn verify_export(dir: &Path, key: Option<&[u8]>) -> Result<VerifyReport, ExportError> {
+    let manifest = read_manifest(dir)?;
+    let signature = manifest.signature_status(key);
+
+    let mut report = VerifyReport {
+        signature,
+        verified: Vec::new(),
+        corrupted: Vec::new(),
+        missing: Vec::new(),
+        unlisted: Vec::new(),
+    };
+    for file in &manifest.files {
+        let path = contained_path(dir, &file.path)?;
+        if !path.is_file() {
+            report.missing.push(file.path.clone());
+            continue;
+        }
+        if describe_file(dir, &file.path)? == *file {
+            report.verified.push(file.path.clone());
+        } else {
+            report.corrupted.push(file.path.clone());
+        }
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name != MANIFEST_FILE && !manifest.files.iter().any(|f| f.path == name) {
+            report.unlisted.push(name);
+        }
+    }
+    report.unlisted.sort();
+    Ok(report)
+}
+
+#[doc = r#"Read and parse the manifest of the export in `dir`.
+
+# Errors
+
+Returns [`ExportError`] when the manifest is missing, unreadable, or of an unknown version.
+"#]
+pub fn read_manifest(dir: &Path) -> Result<Manifest, ExportError> {
+    let raw = std::fs::read(dir.join(MANIFEST_FILE))?;
+    let manifest: Manifest =
+        serde_json::from_slice(&raw).map_err(|e| ExportError::Manifest(e.to_string()))?;
+    if !(MIN_MANIFEST_VERSION..=MANIFEST_VERSION).contains(&manifest.version) {
+        return Err(ExportError::Manifest(format!(
+            "unsupported manifest version {}",
+            manifest.version
+        )));
+    }
+    Ok(manifest)
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Result of [`compare_export`]: how the live sessions differ from an export's records.
+
+- `changed`: records whose session still exists but no longer matches its digest.
+- `missing`: records whose session has been deleted since the export.
+- `unchanged`: number of records that still match.
+- `added`: number of sessions created since the export (not in its records).
+
+`changed` and `missing` carry the manifest's entries, ordered by wake date then id.
+"#]
+pub struct CompareReport {
+    pub created_at: DateTime<Utc>,
+    pub signature: SignatureStatus,
+    pub unchanged: usize,
+    pub changed: Vec<ManifestRecord>,
+    pub missing: Vec<ManifestRecord>,
+    pub added: usize,
+}
+
+#[doc = r#"Compare the sessions in `db` with the records of a previously written `manifest`.
+
+`key` checks the signature like [`verify_export`]. Sessions are matched by id and compared by
+[`record_digest`], so edits of any exported field count as a change.
+
+# Errors
+
+Returns [`ExportError::Manifest`] when the manifest is of an unknown version, predates
+per-record digests (version 1), or its signature does not match `key`; and
+[`ExportError::Db`] on database failures.
+"#]
+pub async fn compare_export(
+    db: &Db,
+    manifest: &Manifest,
+    key: Option<&[u8]>,
+) -> Result<CompareReport, ExportError> {
+    if !(MIN_MANIFEST_VERSION..=MANIFEST_VERSION).contains(&manifest.version) {
+        return Err(ExportError::Manifest(format!(
+            "unsupported manifest version {}",
+            manifest.version
+        )));
+    }
+    if manifest.version < 2 {
+        return Err(ExportError::Manifest(format!(
+            "manifest version {} has no per-record digests; compare a newer export",
+            manifest.version
+        )));
+    }
+    let signature = manifest.signature_status(key);
+    if signature == SignatureStatus::Invalid {
+        return Err(ExportError::Manifest(
+            "manifest signature does not match EXPORT_SIGNING_KEY".into(),
+        ));
+    }
+
+    let current: std::collections::HashMap<i64, String> = all_sessions(db)
+        .await?
+        .iter()
+        .map(|s| (s.id, record_digest(s)))
+        .collect();
+    let mut report = CompareReport {
+        created_at: manifest.created_at,
+        signature,
+        unchanged: 0,
+        changed: Vec::new(),
+        missing: Vec::new(),
+        added: 0,
+    };
+    for record in &manifest.records {
+        match current.get(&record.id) {
+            Some(digest) if *digest == record.sha256 => report.unchanged += 1,
+            Some(_) => report.changed.push(record.clone()),
+            None => report.missing.push(record.clone()),
+        }
+    }
+    let exported: std::collections::HashSet<i64> = manifest.records.iter().map(|r| r.id).collect();
+    report.added = current.keys().filter(|id| !exported.contains(id)).count();
+    report.changed.sort_by_key(|r| (r.date, r.id));
+    report.missing.sort_by_key(|r| (r.date, r.id));
+    Ok(report)
+}
+
+#[doc = r#"SHA-256 (hex) of a session as exported: its compact JSON [`SleepListItem`] form.
+
+Any change to an exported field, including recomputed ones like `duration_min`, changes the
+digest.
+"#]
+pub fn record_digest(session: &SleepListItem) -> String {
+    hex::encode(Sha256::digest(
+        serde_json::to_vec(session).unwrap_or_default(),
+    ))
+}
+
+fn manifest_record(session: &SleepListItem) -> ManifestRecord {
+    ManifestRecord {
+        id: session.id,
+        date: session.date,
+        sha256: record_digest(session),
+    }
+}
+
+async fn all_sessions(db: &Db) -> Result<Vec<SleepListItem>, ExportError> {
+    Ok(repository::list_sleep_range(
+        db,
+        NaiveDate::from_ymd_opt(1, 1, 1).unwrap_or_default(),
+        NaiveDate::from_ymd_opt(9999, 12, 31).unwrap_or_default(),
+    )
+    .await?)
+}
+
+#[doc = r#"Exports directly under `root`, newest first.
+
+Subdirectories without a readable manifest are skipped; a missing `root` yields an empty list.
+
+# Errors
+
+Returns [`ExportError::Io`] when `root` exists but cannot be listed.
+"#]
+pub fn list_exports(root: &Path) -> Result<Vec<ExportSummary>, ExportError> {
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut exports = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        match read_manifest(&entry.path()) {
+            Ok(manifest) => exports.push(ExportSummary::new(name, manifest)),
+            Err(e) => tracing::debug!(export = %name, error = %e, "skipping directory"),
+        }
+    }
+    exports.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+    Ok(exports)
+}
+
+#[doc = r#"Locate `file` of the export `name` under `root`: its path on disk and manifest entry.
+
+Only plain names are accepted and only files listed in the manifest are returned, so a
+request can never reach outside the export. `None` when the export or file does not exist.
+"#]
+pub fn find_file(root: &Path, name: &str, file: &str) -> Option<(PathBuf, ManifestFile)> {
+    let dir = contained_path(root, name).ok()?;
+    let manifest = read_manifest(&dir).ok()?;
+    let entry = manifest.files.into_iter().find(|f| f.path == file)?;
+    let path = contained_path(&dir, &entry.path).ok()?;
+    path.is_file().then_some((path, entry))
+}
+
+/// `name` inside `dir`, rejecting anything but a plain file name.
+fn contained_path(dir: &Path, name: &str) -> Result<PathBuf, ExportError> {
+    let plain = !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\']);
+    if !plain {
+        return Err(ExportError::Manifest(format!(
+            "file path {name:?} is outside the export"
+        )));
+    }
+    Ok(dir.join(name))
+}
+
+fn describe_file(dir: &Path, name: &str) -> Result<ManifestFile, ExportError> {
+    let path = contained_path(dir, name)?;
+    let mut hasher = Sha256::new();
+    let mut file = std::fs::File::open(path)?;
+    let size = std::io::copy(&mut file, &mut hasher)?;
+    Ok(ManifestFile {
+        path: name.to_string(),
+        size,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
@@ -0,0 +1,281 @@
+#![doc = r#"Request extractors with structured errors
+
+- [`DateRange`] parses and validates `from`/`to` query parameters for range endpoints.
+- [`OpenDateRange`] does the same where either bound may be omitted (exports).
+- [`ValidPath`] wraps Axum's [`Path`] so an unparsable `{date}` or `{id}` segment yields a
+  `400 application/problem+json` body naming the parameter, the offending value, and the
+  expected format, instead of Axum's plain-text "Invalid URL" rejection.
+
+[`Path`]: axum::extract::Path
+"#]
+
+use crate::error::{ApiError, Problem};
+use axum::{
+    extract::{FromRequestParts, Path, Query, RawPathParams, rejection::PathRejection},
+    http::{StatusCode, request::Parts},
+};
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+
+#[doc = r#"Human-readable format expected for a path parameter type."#]
+pub trait PathFormat {
+    /// Shown as `expected` in the problem body, e.g. `YYYY-MM-DD`.
+    const EXPECTED: &'static str;
+}
+
+impl PathFormat for NaiveDate {
+    const EXPECTED: &'static str = "YYYY-MM-DD";
+}
+
+impl PathFormat for i64 {
+    const EXPECTED: &'static str = "integer";
+}
+
+impl PathFormat for String {
+    const EXPECTED: &'static str = "string";
+}
+
+#[derive(Debug)]
+#[doc = r#"Single path parameter extractor returning problem+json on parse failure.
+
+Response body on failure:
+
+```json
+{
+  "type": "about:blank",
+  "title": "Invalid path parameter",
+  "status": 400,
+  "detail": "Path parameter `date` must be YYYY-MM-DD, got \"2025-13-01\"",
+  "code": "invalid_path_param",
+  "param": "date",
+  "value": "2025-13-01",
+  "expected": "YYYY-MM-DD"
+}
+```
+"#]
+pub struct ValidPath<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for ValidPath<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + PathFormat + Send,
+{
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(ValidPath(value)),
+            Err(rejection) => {
+                let raw = RawPathParams::from_request_parts(parts, state).await.ok();
+                let (param, value) = raw
+                    .as_ref()
+                    .and_then(|p| p.iter().next())
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .unwrap_or_default();
+                Err(path_problem(rejection, &param, &value, T::EXPECTED))
+            }
+        }
+    }
+}
+
+fn path_problem(rejection: PathRejection, param: &str, value: &str, expected: &str) -> Problem {
+    if rejection.status() != StatusCode::BAD_REQUEST {
+        tracing::error!(error = %rejection.body_text(), "path extraction failed");
+        return Problem::new(rejection.status(), "internal", "Path extraction failed");
+    }
+    Problem::new(
+        StatusCode::BAD_REQUEST,
+        "invalid_path_param",
+        "Invalid path parameter",
+    )
+    .detail(format!(
+        "Path parameter `{param}` must be {expected}, got {value:?}"
+    ))
+    .with("param", param)
+    .with("value", value)
+    .with("expected", expected)
+}
+
+#[derive(serde::Deserialize)]
+struct RawRange {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Inclusive `from`/`to` date range read from the query string.
+
+Every range endpoint extracts this instead of parsing dates itself, so all of them report
+the same `400 {code:"bad_request", message}` errors:
+- `from is required` / `to is required`
+- `from must be a date in YYYY-MM-DD format` (likewise for `to`)
+- `from must be <= to`
+- `range must be <= {MAX_DAYS} days` (inclusive span; only when `MAX_DAYS` is set)
+
+Other query parameters are ignored, so handlers can extract their own `Query<T>` alongside.
+
+# Example
+
+```rust
+# use sleep_api::extract::DateRange;
+let r = DateRange::<62>::parse(Some("2025-06-01"), Some("2025-06-07")).unwrap();
+assert_eq!(r.days(), 7);
+assert!(DateRange::<62>::parse(Some("2025-01-01"), Some("2025-06-01")).is_err());
+```
+"#]
+pub struct DateRange<const MAX_DAYS: i64 = { i64::MAX }> {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl<const MAX_DAYS: i64> DateRange<MAX_DAYS> {
+    #[doc = r#"Parse and validate raw `from`/`to` values.
+
+# Errors
+- Returns [`ApiError::InvalidInput`] for missing or malformed dates, `from > to`, or a span
+  longer than `MAX_DAYS`.
+"#]
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> Result<Self, ApiError> {
+        let from = parse_date_param(from, "from")?;
+        let to = parse_date_param(to, "to")?;
+        if from > to {
+            return Err(ApiError::InvalidInput("from must be <= to".into()));
+        }
+        let range = DateRange { from, to };
+        if range.days() > MAX_DAYS {
+            return Err(ApiError::InvalidInput(format!(
+                "range must be <= {MAX_DAYS} days"
+            )));
+        }
+        Ok(range)
+    }
+
+    /// Inclusive number of days in the range.
+    pub fn days(&self) -> i64 {
+        (self.to - self.from).num_days() + 1
+    }
+}
+
+fn parse_date_param(value: Option<&str>, field: &str) -> Result<NaiveDate, ApiError> {
+    let value = value.ok_or_else(|| ApiError::InvalidInput(format!("{field} is required")))?;
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| ApiError::InvalidInput(format!("{field} must be a date in YYYY-MM-DD format")))
+}
+
+impl<S, const MAX_DAYS: i64> FromRequestParts<S> for DateRange<MAX_DAYS>
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(raw) = Query::<RawRange>::try_from_uri(&parts.uri)
+            .map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+        Self::parse(raw.from.as_deref(), raw.to.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Inclusive `from`/`to` range where either bound may be omitted.
+
+A missing `from` means [`OpenDateRange::EARLIEST`] and a missing `to` [`OpenDateRange::LATEST`],
+so `?from=2025-01-01` selects everything since that day. Present values are validated with
+the same messages as [`DateRange`].
+
+# Example
+
+```rust
+# use sleep_api::extract::OpenDateRange;
+let r = OpenDateRange::parse(Some("2025-06-01"), None).unwrap();
+assert_eq!(r.to, OpenDateRange::LATEST);
+assert!(OpenDateRange::parse(Some("2025-06-02"), Some("2025-06-01")).is_err());
+```
+"#]
+pub struct OpenDateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl OpenDateRange {
+    /// Lower bound used when `from` is omitted.
+    pub const EARLIEST: NaiveDate = NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+    /// Upper bound used when `to` is omitted.
+    pub const LATEST: NaiveDate = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+
+    #[doc = r#"Parse optional raw `from`/`to` values.
+
+# Errors
+- Returns [`ApiError::InvalidInput`] for malformed dates or `from > to`.
+"#]
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> Result<Self, ApiError> {
+        let from = from
+            .map(|v| parse_date_param(Some(v), "from"))
+            .transpose()?
+            .unwrap_or(Self::EARLIEST);
+        let to = to
+            .map(|v| parse_date_param(Some(v), "to"))
+            .transpose()?
+            .unwrap_or(Self::LATEST);
+        if from > to {
+            return Err(ApiError::InvalidInput("from must be <= to".into()));
+        }
+        Ok(OpenDateRange { from, to })
+    }
+}
+
+impl<S> FromRequestParts<S> for OpenDateRange
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(raw) = Query::<RawRange>::try_from_uri(&parts.uri)
+            .map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+        // `?from=&to=` leaves a bound open, like omitting it.
+        let present = |v: Option<String>| v.filter(|s| !s.is_empty());
+        Self::parse(present(raw.from).as_deref(), present(raw.to).as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(err: ApiError) -> String {
+        match err {
+            ApiError::InvalidInput(m) => m,
+            other => panic!("unexpected error {other:?}"),
+        }
+    }
+
+    #[test]
+    fn date_range_errors_are_uniform() {
+        let err = |from, to| message(DateRange::<7>::parse(from, to).unwrap_err());
+        assert_eq!(err(None, Some("2025-06-01")), "from is required");
+        assert_eq!(
+            err(Some("2025-06-01"), Some("06/02/2025")),
+            "to must be a date in YYYY-MM-DD format"
+        );
+        assert_eq!(
+            err(Some("2025-06-02"), Some("2025-06-01")),
+            "from must be <= to"
+        );
+        assert_eq!(
+            err(Some("2025-06-01"), Some("2025-06-08")),
+            "range must be <= 7 days"
+        );
+        assert_eq!(
+            DateRange::<7>::parse(Some("2025-06-01"), Some("2025-06-07"))
+                .unwrap()
+                .days(),
+            7
+        );
+    }
+
+    #[test]
+    fn unbounded_range_accepts_long_spans() {
+        let r: DateRange = DateRange::parse(Some("2000-01-01"), Some("2025-01-01")).unwrap();
+        assert!(r.days() > 9000);
+    }
+}
@@ -0,0 +1,114 @@
+#![doc = r#"Per-module feature flags
+
+[`Features`] is read once when the router is built (see [`config::features`]) and stored in
+[`AppState`](crate::app::AppState). Disabled subsystems are left out of routing, so their
+endpoints return 404 rather than half-working:
+
+- `webhooks` — `POST /api/ingest/{source}`
+- `telemetry` — `POST /api/personalization/friction-telemetry` and
+  `GET /api/personalization/friction-backlog`
+- `integrations.*` — one source of `POST /api/body-metrics/import/{source}`
+  (`withings`, `fitbit`) or `POST /api/ingest/{source}` (`health_auto_export`, `tasker`,
+  `strava`, `garmin`)
+- `trends_cache` — reserved for trends response caching; there is no cache yet, so it has
+  no effect
+
+The active flags are reported by `GET /api/version`.
+
+[`config::features`]: crate::config::features
+"#]
+
+use crate::importers::{IngestSource, WeightSource};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[doc = r#"On/off switches for optional subsystems. [`Default`] enables everything except
+`trends_cache`.
+
+# Example
+
+```rust
+# use sleep_api::{features::Features, importers::WeightSource};
+let mut features = Features::default();
+features.integrations.fitbit = false;
+assert!(features.weight_import(WeightSource::Withings));
+assert!(!features.weight_import(WeightSource::Fitbit));
+```
+"#]
+pub struct Features {
+    pub trends_cache: bool,
+    pub webhooks: bool,
+    pub telemetry: bool,
+    pub integrations: Integrations,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[doc = r#"Per-source switches for third-party imports and pushes."#]
+pub struct Integrations {
+    pub withings: bool,
+    pub fitbit: bool,
+    pub health_auto_export: bool,
+    pub tasker: bool,
+    pub strava: bool,
+    pub garmin: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            trends_cache: false,
+            webhooks: true,
+            telemetry: true,
+            integrations: Integrations {
+                withings: true,
+                fitbit: true,
+                health_auto_export: true,
+                tasker: true,
+                strava: true,
+                garmin: true,
+            },
+        }
+    }
+}
+
+impl Features {
+    /// Whether body metrics exports from `source` may be imported.
+    pub fn weight_import(&self, source: WeightSource) -> bool {
+        match source {
+            WeightSource::Withings => self.integrations.withings,
+            WeightSource::Fitbit => self.integrations.fitbit,
+        }
+    }
+
+    /// Whether webhook pushes from `source` are accepted (requires `webhooks` as well).
+    pub fn ingest(&self, source: IngestSource) -> bool {
+        self.webhooks
+            && match source {
+                IngestSource::HealthAutoExport => self.integrations.health_auto_export,
+                IngestSource::Tasker => self.integrations.tasker,
+                IngestSource::Strava => self.integrations.strava,
+                IngestSource::Garmin => self.integrations.garmin,
+            }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[doc = r#"Response of `GET /api/version`: the crate version, the active feature flags, and the
+outcome of the last config reload (`null` until the first `SIGHUP`)."#]
+pub struct VersionInfo {
+    pub version: &'static str,
+    pub features: Features,
+    pub config_reload: Option<crate::reload::ReloadStatus>,
+}
+
+impl VersionInfo {
+    /// Version info for this build with `features`.
+    pub fn new(features: Features) -> Self {
+        VersionInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            features,
+            config_reload: crate::reload::last_reload(),
+        }
+    }
+}
@@ -0,0 +1,299 @@
+#![doc = r#"Report feeds
+
+`GET /api/feeds/reports.xml` publishes an Atom feed with one entry per completed ISO week
+(Monday to Sunday) that has logged nights, newest first, covering the last [`FEED_WEEKS`]
+weeks. Each entry summarizes the week (nights logged, average duration and quality, and the
+change in average duration from the previous week) so a feed reader archives the reports
+without anyone opening the app.
+
+Feed readers usually cannot send an `Authorization` header, so the feed is authenticated by
+an API token passed either as `Authorization: Bearer <secret>` or as `?token=<secret>`. Any
+unexpired token works (every scope may read); without one the feed answers `401`. The feed
+is therefore off until a token is created for it, and revoking that token turns it off again.
+Tokens in URLs end up in reader configs and proxy logs, so a dedicated `read` token is
+recommended.
+
+Text is localized from `Accept-Language`, falling back to the saved locale, and durations
+follow the saved duration unit.
+"#]
+
+use crate::i18n::{DurationUnit, Lang, Locale, Units, fmt_minutes, tr};
+use crate::models::ApiScope;
+use crate::security::token::{bearer_token, hash_secret};
+use crate::time::SharedClock;
+use crate::{db::Db, error::ApiError, repository};
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use sqlx::Sqlite;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// Number of most recent completed weeks the feed covers.
+pub const FEED_WEEKS: i64 = 12;
+
+/// Router-relative path of the feed, checked against the token's scope.
+const FEED_PATH: &str = "/api/feeds/reports.xml";
+
+#[derive(Deserialize, Debug, Default)]
+pub struct FeedQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[doc = r#"Aggregates of one Monday-to-Sunday week, by wake date."#]
+pub struct WeeklyReport {
+    pub week_start: NaiveDate,
+    pub nights: i64,
+    pub avg_duration_min: Option<f64>,
+    pub avg_quality: Option<f64>,
+}
+
+impl WeeklyReport {
+    /// ISO week label, e.g. `2025-W23`.
+    pub fn label(&self) -> String {
+        let week = self.week_start.iso_week();
+        format!("{}-W{:02}", week.year(), week.week())
+    }
+
+    fn week_end(&self) -> NaiveDate {
+        self.week_start + ChronoDuration::days(6)
+    }
+}
+
+#[doc = r#"Serve the weekly report feed as `application/atom+xml`.
+
+Errors:
+- `401` without a valid, unexpired API token.
+- Returns an API error on database failures.
+"#]
+pub async fn reports(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    Lang(locale): Lang,
+    Units(unit): Units,
+    headers: HeaderMap,
+    Query(q): Query<FeedQuery>,
+) -> Result<Response, ApiError> {
+    let now = clock.now_utc();
+    let secret = bearer_token(&headers).or(q.token.as_deref());
+    let token = match secret {
+        Some(secret) => {
+            repository::use_api_token(&db, &hash_secret(secret), now.naive_utc()).await?
+        }
+        None => None,
+    };
+    let authorized = token.is_some_and(|t| {
+        t.expires_at > now.naive_utc()
+            && ApiScope::from_str(&t.scope).is_ok_and(|s| s.allows("GET", FEED_PATH))
+    });
+    if !authorized {
+        return Ok((
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error":"unauthorized"})),
+        )
+            .into_response());
+    }
+
+    let tz = repository::get_user_timezone(&db).await;
+    let today = repository::get_day_boundary(&db)
+        .await
+        .day_of(now.with_timezone(&tz).naive_local());
+    let this_week = today - ChronoDuration::days(today.weekday().num_days_from_monday() as i64);
+    // One extra week so the oldest entry still has a previous week to compare against.
+    let from = this_week - ChronoDuration::weeks(FEED_WEEKS + 1);
+    let reports = weekly_reports(&db, from, this_week).await?;
+
+    let body = render_atom(&reports, locale, unit, tz, this_week);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/atom+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=3600"),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[doc = r#"Weekly aggregates of nights waking in [from, until), oldest first; weeks without
+logged nights are omitted. `from` must be a Monday."#]
+pub async fn weekly_reports(
+    db: &Db,
+    from: NaiveDate,
+    until: NaiveDate,
+) -> Result<Vec<WeeklyReport>, sqlx::Error> {
+    let rows = sqlx::query_as::<Sqlite, (NaiveDate, Option<i64>, Option<i64>)>(
+        "SELECT wake_date, duration_min, quality FROM v_daily_sleep \
+         WHERE wake_date >= ? AND wake_date < ? ORDER BY wake_date ASC",
+    )
+    .bind(from)
+    .bind(until)
+    .fetch_all(db)
+    .await?;
+
+    let mut out: Vec<(WeeklyReport, Vec<i64>, Vec<i64>)> = Vec::new();
+    for (date, duration, quality) in rows {
+        let week_start = date - ChronoDuration::days(date.weekday().num_days_from_monday() as i64);
+        if out
+            .last()
+            .is_none_or(|(r, _, _)| r.week_start != week_start)
+        {
+            out.push((
+                WeeklyReport {
+                    week_start,
+                    nights: 0,
+                    avg_duration_min: None,
+                    avg_quality: None,
+                },
+                Vec::new(),
+                Vec::new(),
+            ));
+        }
+        if let Some((report, durations, qualities)) = out.last_mut() {
+            report.nights += 1;
+            durations.extend(duration);
+            qualities.extend(quality);
+        }
+    }
+    let mean = |v: &[i64]| (!v.is_empty()).then(|| v.iter().sum::<i64>() as f64 / v.len() as f64);
+    Ok(out
+        .into_iter()
+        .map(|(mut report, durations, qualities)| {
+            report.avg_duration_min = mean(&durations);
+            report.avg_quality = mean(&qualities);
+            report
+        })
+        .collect())
+}
+
+#[doc = r#"Render `reports` (oldest first) as an Atom document, newest entry first.
+
+Each entry is dated at the local midnight ending its week; the feed is dated at the start of
+`this_week`, the end of the newest week it can contain."#]
+pub fn render_atom(
+    reports: &[WeeklyReport],
+    locale: Locale,
+    unit: DurationUnit,
+    tz: Tz,
+    this_week: NaiveDate,
+) -> String {
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"{}\">\n\
+         <id>urn:sleeptracker:reports:weekly</id>\n\
+         <title>{}</title>\n\
+         <updated>{}</updated>\n\
+         <author><name>SleepTracker</name></author>\n",
+        locale.tag(),
+        escape(&tr(locale, "feed-title", &[])),
+        local_midnight(tz, this_week).to_rfc3339(),
+    );
+    let oldest = this_week - ChronoDuration::weeks(FEED_WEEKS);
+    for (i, report) in reports.iter().enumerate().rev() {
+        if report.week_start < oldest {
+            break;
+        }
+        let previous = i
+            .checked_sub(1)
+            .map(|p| &reports[p])
+            .filter(|p| p.week_start == report.week_start - ChronoDuration::weeks(1));
+        let label = report.label();
+        let title = tr(
+            locale,
+            "feed-report-title",
+            &[
+                ("week", label.clone()),
+                ("from", report.week_start.to_string()),
+                ("to", report.week_end().to_string()),
+            ],
+        );
+        let updated = local_midnight(tz, report.week_start + ChronoDuration::weeks(1));
+        let _ = write!(
+            xml,
+            "<entry>\n\
+             <id>urn:sleeptracker:reports:weekly:{label}</id>\n\
+             <title>{}</title>\n\
+             <updated>{}</updated>\n\
+             <content type=\"text\">{}</content>\n\
+             </entry>\n",
+            escape(&title),
+            updated.to_rfc3339(),
+            escape(&summary(report, previous, locale, unit)),
+        );
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn summary(
+    report: &WeeklyReport,
+    previous: Option<&WeeklyReport>,
+    locale: Locale,
+    unit: DurationUnit,
+) -> String {
+    let mut lines = vec![tr(
+        locale,
+        "feed-report-nights",
+        &[("nights", report.nights.to_string())],
+    )];
+    if let Some(avg) = report.avg_duration_min {
+        lines.push(tr(
+            locale,
+            "feed-report-duration",
+            &[("avg", fmt_minutes(locale, unit, avg.round() as i64))],
+        ));
+    }
+    if let Some(avg) = report.avg_quality {
+        lines.push(tr(
+            locale,
+            "feed-report-quality",
+            &[("avg", format!("{avg:.1}"))],
+        ));
+    }
+    if let (Some(now), Some(before)) = (
+        report.avg_duration_min,
+        previous.and_then(|p| p.avg_duration_min),
+    ) {
+        let delta = (now - before).round() as i64;
+        lines.push(tr(
+            locale,
+            "feed-report-delta",
+            &[
+                ("sign", if delta < 0 { "-" } else { "+" }.to_string()),
+                ("delta", fmt_minutes(locale, unit, delta.abs())),
+            ],
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Start of `date` in `tz`; a midnight skipped by DST falls back to UTC midnight.
+fn local_midnight(tz: Tz, date: NaiveDate) -> DateTime<Tz> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight))
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -1,105 +1,2300 @@
+#![doc = r#"Handler logic behind the HTTP routes
+
+Each function validates its input, talks to [`repository`](crate::repository), and emits a
+[`DomainEvent`] on success. The axum handlers in [`app`](crate::app) only extract and
+respond, so these functions can be called directly, without a router or HTTP, in tests and
+tools.
+
+Time-dependent logic (duration recompute, "today") reads the clock and timezone from a
+[`TimeContext`] so it can be pinned, e.g. around DST transitions.
+
+Validation order for sleep writes: input validation, then window/duration computation in the
+user timezone, then the overlap check; an invalid entry is reported as invalid even when it
+would also overlap.
+
+Writes to dated entries also take an [`EditLock`]: entries older than the configured no-edit
+window are read-only (both the stored date and the new date are checked on updates).
+
+Deletes take an [`AuditReason`] and append a row to the audit log when something was removed.
+
+Failures are reported as the crate [`Error`]; the axum layer converts it into
+[`ApiError`](crate::error::ApiError) for the HTTP response.
+
+# Example
+
+```rust
+# use sleep_api::{handlers::{self, EditLock, TimeContext}, events::EventBus, models::SleepInput};
+# #[tokio::main(flavor = "current_thread")]
+# async fn main() -> Result<(), Box<dyn std::error::Error>> {
+# let db = sqlx::sqlite::SqlitePoolOptions::new().connect("sqlite::memory:").await?;
+# sqlx::migrate::Migrator::new(std::path::Path::new("../migrations")).await?.run(&db).await?;
+let time = TimeContext::fixed("2025-03-09T12:00:00Z".parse()?, chrono_tz::America::New_York);
+let input: SleepInput = serde_json::from_value(serde_json::json!({
+    "date": "2025-03-09", "bed_time": "23:00", "wake_time": "07:00",
+    "latency_min": 10, "awakenings": 0, "quality": 4
+}))?;
+let id = handlers::create_sleep(&db, &EventBus::new(), &time, &EditLock::none(), input).await?;
+let saved = handlers::get_sleep_by_date(&db, "2025-03-09".parse()?).await?;
+assert_eq!(saved[0].id, id);
+# Ok(()) }
+```
+"#]
+
 use crate::{
+    admin_query::{self, QueryRequest, QueryResult},
+    attachments, config,
     db::Db,
-    error::ApiError,
-    models::{ExerciseInput, FrictionTelemetryInput, NoteInput, SleepInput, SleepSession},
+    error::Error,
+    events::{DomainEvent, EventBus},
+    export::{self, ExportSummary},
+    importers::{
+        self, ImportIssue, IngestEntry, IngestSource, MappedRow, MappingImportRequest, WeightSource,
+    },
+    integrity::{IntegrityCheck, IntegrityReport, IntegrityState},
+    jobs::{self, Job},
+    models::{
+        ActiveSleep, AlertEvent, AlertHistoryQuery, AlertRules, ApiToken, ApiTokenInput,
+        Attachment, AttachmentUpload, Audience, AuditPage, AuditQuery, AuditReason,
+        BodyMetricInput, CreatedApiToken, CreatedShareLink, DayBoundary, DisturbanceInput,
+        EmailChangeInput, ExerciseInput, ExerciseZoneDay, Experiment, ExperimentInput,
+        ExperimentMetricResult, ExperimentResults, FrictionTelemetryInput, GroupSummary,
+        HrZoneMinutes, IntensityLevels, JobRun, KnownDevice, MedicationEventInput, MedicationInput,
+        MoodInput, NoteInput, PendingEmailChange, PublicSummarySettings, RedactionSettings,
+        RoutineChecklist, RoutineEntry, RoutineInput, RoutineItem, ShareLink, ShareLinkInput,
+        ShareLinkStats, SharedView, SleepGoal, SleepInput, SleepListItem, SleepPatch, SleepSession,
+        SleepTimerStop, Starred, TimeRounding,
+    },
+    notify::{self, Notification},
+    redaction::{self, Redact},
     repository,
+    schema_change::{self, SchemaChangeStatus, SchemaPhase},
+    security::{device::DeviceFingerprint, password, token},
+    time::Clock,
 };
-use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, NaiveDateTime, Timelike, Utc};
 use chrono_tz::Tz;
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::str::FromStr;
-fn is_overlap_db_error(err: &sqlx::Error) -> bool {
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Clone, Copy)]
+#[doc = r#"Clock and timezone used by time-dependent handler logic.
+
+Handlers serving a request use [`TimeContext::from_clock`] with the application
+[`Clock`](crate::time::Clock) and the timezone saved in settings. Tests pin both with
+[`TimeContext::fixed`].
+"#]
+pub struct TimeContext {
+    /// The instant treated as "now".
+    pub now: DateTime<Utc>,
+    /// Timezone override; `None` reads the saved setting.
+    pub timezone: Option<Tz>,
+}
+
+impl TimeContext {
+    /// The clock's current time and the saved timezone.
+    pub fn from_clock(clock: &dyn Clock) -> Self {
+        TimeContext {
+            now: clock.now_utc(),
+            timezone: None,
+        }
+    }
+
+    /// A fixed instant and timezone, ignoring the clock and settings.
+    // Used by tests and library callers; the server always runs on the system clock.
+    #[allow(dead_code)]
+    pub fn fixed(now: DateTime<Utc>, timezone: Tz) -> Self {
+        TimeContext {
+            now,
+            timezone: Some(timezone),
+        }
+    }
+
+    /// The timezone override, else the saved setting.
+    pub async fn timezone(&self, db: &Db) -> Tz {
+        match self.timezone {
+            Some(tz) => tz,
+            None => repository::get_user_timezone(db).await,
+        }
+    }
+
+    /// Timezone for the duration of `input`: its own `timezone` when set, else
+    /// [`timezone`](Self::timezone).
+    pub async fn timezone_for(&self, db: &Db, input: &SleepInput) -> Tz {
+        match input.tz() {
+            Some(tz) => tz,
+            None => self.timezone(db).await,
+        }
+    }
+
+    /// Local date of [`now`](Self::now) in [`timezone`](Self::timezone).
+    pub async fn today(&self, db: &Db) -> NaiveDate {
+        self.now
+            .with_timezone(&self.timezone(db).await)
+            .date_naive()
+    }
+}
+
+/// Header value of `X-Admin-Override` that lifts the [`EditLock`] for one request.
+pub const EDIT_WINDOW_OVERRIDE: &str = "edit-window";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[doc = r#"No-edit window: entries dated before `cutoff` are read-only.
+
+Requests get `today - EDIT_WINDOW_DAYS` as the cutoff (see
+[`config::edit_window_days`](crate::config::edit_window_days)), or no cutoff when the window is
+disabled or the request carries `X-Admin-Override: edit-window`. This protects history from
+buggy clients re-saving or deleting old records in bulk.
+
+The lock also carries the `sessions_per_day` quota
+([`SessionsPerDay`](crate::middleware::quota::SessionsPerDay)), which [`create_sleep`] applies
+once the session's date is known; the override header does not lift it.
+"#]
+pub struct EditLock {
+    /// First date that may still be written; `None` means unrestricted.
+    pub cutoff: Option<NaiveDate>,
+    /// Most sleep sessions a wake date may hold; `None` means unlimited.
+    pub sessions_per_day: Option<u64>,
+}
+
+impl EditLock {
+    /// No restriction (window disabled, overridden, or a trusted caller).
+    pub fn none() -> Self {
+        EditLock {
+            cutoff: None,
+            sessions_per_day: None,
+        }
+    }
+
+    /// Lock entries older than `days` days before the [`TimeContext`]'s today.
+    pub async fn for_window(db: &Db, time: &TimeContext, days: Option<i64>) -> Self {
+        match days {
+            Some(days) => EditLock {
+                cutoff: Some(time.today(db).await - ChronoDuration::days(days)),
+                sessions_per_day: None,
+            },
+            None => EditLock::none(),
+        }
+    }
+
+    /// [`Error::Forbidden`] when `date` falls before the cutoff.
+    pub fn check(&self, date: NaiveDate) -> Result<(), Error> {
+        match self.cutoff {
+            Some(cutoff) if date < cutoff => Err(Error::Forbidden(format!(
+                "entries dated before {cutoff} are read-only; send X-Admin-Override: {EDIT_WINDOW_OVERRIDE} to change them"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_overlap_db_error(err: &Error) -> bool {
     match err {
-        sqlx::Error::Database(db_err) => db_err
+        Error::Database(sqlx::Error::Database(db_err)) => db_err
             .message()
             .contains("sleep session overlaps existing session"),
         _ => false,
     }
 }
 
-pub async fn create_sleep(db: &Db, input: SleepInput) -> Result<i64, ApiError> {
-    input.validate()?;
-    let (bed_dt, wake_dt) =
-        crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
-    let tz = repository::get_user_timezone(db).await;
-    let duration =
-        crate::time::compute_duration_min(input.date, input.bed_time, input.wake_time, tz)?;
-    if repository::has_sleep_overlap(db, bed_dt, wake_dt, None).await? {
-        return Err(ApiError::InvalidInput(
-            "sleep session overlaps existing session".into(),
-        ));
+#[doc = r#"Create a sleep session and return its id.
+
+Duration is computed in the session's own `timezone` when set, else the [`TimeContext`] timezone
+(DST-aware). The sleep efficiency stored
+with it is derived from that duration, `latency_min` and `waso_min`
+([`crate::time::sleep_efficiency`]); clients never send it.
+
+# Errors
+- [`Error::Domain`] for invalid input or an overlap with an existing session
+- [`Error::Forbidden`] when the date is inside the no-edit window
+- [`Error::QuotaExceeded`] when the date already has `lock.sessions_per_day` sessions
+- [`Error::Database`] on database failures
+"#]
+pub async fn create_sleep(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    input: SleepInput,
+) -> Result<i64, Error> {
+    input.validate()?;
+    lock.check(input.date)?;
+    if let Some(limit) = lock.sessions_per_day
+        && repository::count_sleep_sessions_on(db, input.date).await? as u64 >= limit
+    {
+        return Err(Error::QuotaExceeded(format!(
+            "at most {limit} sleep sessions per day ({} is full)",
+            input.date
+        )));
+    }
+    let (bed_dt, wake_dt) =
+        crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
+    let tz = time.timezone_for(db, &input).await;
+    let duration =
+        crate::time::compute_duration_min(input.date, input.bed_time, input.wake_time, tz)?;
+    if repository::has_sleep_overlap(db, bed_dt, wake_dt, None).await? {
+        return Err(Error::invalid("sleep session overlaps existing session"));
+    }
+    match repository::insert_sleep(db, &input, duration).await {
+        Ok(id) => {
+            events.emit(DomainEvent::SleepCreated {
+                id,
+                date: input.date,
+                duration_min: duration,
+            });
+            Ok(id)
+        }
+        Err(e) if is_overlap_db_error(&e) => {
+            Err(Error::invalid("sleep session overlaps existing session"))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Wake times this close on the same date count as the same morning for [`create_sleep_inferred`].
+const DUPLICATE_WAKE_WINDOW_MIN: i64 = 90;
+
+#[doc = r#"Fill in the wake date of a raw `POST /api/sleep?infer_date=true` body.
+
+When `date` is missing or `null`, it is inferred from `wake_time` and the current time in the
+configured timezone, counting days from the configured day boundary
+([`DayBoundary::infer_wake_date`], so it agrees with `GET /api/now`), and written into `body`.
+Returns the inferred date, or `None` when the body already has a date (or is not an object, which
+deserialization then reports).
+
+# Errors
+
+Returns [`Error::Domain`] when `date` must be inferred but `wake_time` is missing or invalid.
+"#]
+pub async fn infer_sleep_date(
+    db: &Db,
+    time: &TimeContext,
+    body: &mut serde_json::Value,
+) -> Result<Option<NaiveDate>, Error> {
+    let Some(obj) = body.as_object_mut() else {
+        return Ok(None);
+    };
+    if obj.get("date").is_some_and(|d| !d.is_null()) {
+        return Ok(None);
+    }
+    let wake = obj
+        .get("wake_time")
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| Error::invalid("wake_time is required to infer the date"))?;
+    let wake_time = crate::time::parse_flexible_time(wake)
+        .map_err(|message| Error::invalid(format!("wake_time: {message}")))?;
+    let tz = time.timezone(db).await;
+    let now = time.now.with_timezone(&tz).naive_local();
+    let date = repository::get_day_boundary(db)
+        .await
+        .infer_wake_date(now, wake_time);
+    obj.insert("date".into(), serde_json::Value::String(date.to_string()));
+    Ok(Some(date))
+}
+
+#[doc = r#"[`create_sleep`] for a session whose date was inferred, refusing to log the same
+morning twice.
+
+# Errors
+- [`Error::Domain`] when the date already has a session waking within 90 minutes of
+  `input.wake_time`; the client should send `date` explicitly to log another one
+- Otherwise as [`create_sleep`]
+"#]
+pub async fn create_sleep_inferred(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    input: SleepInput,
+) -> Result<i64, Error> {
+    let existing = repository::find_sleep_by_date(db, input.date).await?;
+    if let Some(same) = existing
+        .iter()
+        .find(|s| (s.wake_time - input.wake_time).num_minutes().abs() <= DUPLICATE_WAKE_WINDOW_MIN)
+    {
+        return Err(Error::invalid(format!(
+            "sleep waking at {} on {} is already logged; send date to log another session",
+            same.wake_time.format("%H:%M"),
+            input.date
+        )));
+    }
+    create_sleep(db, events, time, lock, input).await
+}
+
+/// Longest run of the sleep timer that [`stop_sleep_timer`] turns into a session.
+const MAX_TIMER_HOURS: i64 = 24;
+
+fn active_sleep(started_at: NaiveDateTime, now: DateTime<Utc>, tz: Tz) -> ActiveSleep {
+    ActiveSleep {
+        started_at,
+        bed_time: started_at.and_utc().with_timezone(&tz).time(),
+        elapsed_min: (now.naive_utc() - started_at).num_minutes(),
+    }
+}
+
+#[doc = r#"The running sleep timer, if any."#]
+pub async fn get_active_sleep(db: &Db, time: &TimeContext) -> Result<Option<ActiveSleep>, Error> {
+    let Some(started_at) = repository::get_sleep_timer(db).await? else {
+        return Ok(None);
+    };
+    let tz = time.timezone(db).await;
+    Ok(Some(active_sleep(started_at, time.now, tz)))
+}
+
+#[doc = r#"Start the sleep timer now; the bed time of the session [`stop_sleep_timer`] creates.
+
+# Errors
+- [`Error::Domain`] when a timer is already running
+- [`Error::Database`] on database failures
+"#]
+pub async fn start_sleep_timer(db: &Db, time: &TimeContext) -> Result<ActiveSleep, Error> {
+    let started_at = time.now.naive_utc();
+    if !repository::start_sleep_timer(db, started_at).await? {
+        return Err(Error::invalid(
+            "a sleep timer is already running; stop or cancel it first",
+        ));
+    }
+    let tz = time.timezone(db).await;
+    Ok(active_sleep(started_at, time.now, tz))
+}
+
+#[doc = r#"Stop the sleep timer and record the session it measured.
+
+Bed and wake times are the timer's start and now, as local clock times in the configured
+timezone (truncated to the minute); the wake date is today's local date. The remaining fields
+come from `stop` (see [`SleepTimerStop`]). The session goes through [`create_sleep`], and the
+timer is only cleared once it is saved, so a rejected stop can be retried or the timer
+cancelled with [`cancel_sleep_timer`].
+
+# Errors
+- [`Error::NotFound`] when no timer is running
+- [`Error::Domain`] when the timer ran for 24 hours or more, or the session is invalid or
+  overlaps another one
+- Otherwise as [`create_sleep`]
+"#]
+pub async fn stop_sleep_timer(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    stop: SleepTimerStop,
+) -> Result<SleepSession, Error> {
+    let Some(started_at) = repository::get_sleep_timer(db).await? else {
+        return Err(Error::NotFound);
+    };
+    if time.now.naive_utc() - started_at >= ChronoDuration::hours(MAX_TIMER_HOURS) {
+        return Err(Error::invalid(format!(
+            "the sleep timer ran for more than {MAX_TIMER_HOURS} hours; cancel it and log the \
+             session manually"
+        )));
+    }
+    let tz = time.timezone(db).await;
+    let to_minute = |t: chrono::NaiveTime| t.with_second(0).and_then(|t| t.with_nanosecond(0));
+    let bed = started_at.and_utc().with_timezone(&tz).naive_local();
+    let wake = time.now.with_timezone(&tz).naive_local();
+    let input = SleepInput {
+        date: wake.date(),
+        bed_time: to_minute(bed.time()).unwrap_or(bed.time()),
+        wake_time: to_minute(wake.time()).unwrap_or(wake.time()),
+        latency_min: stop.latency_min.unwrap_or(0),
+        awakenings: stop.awakenings.unwrap_or(0),
+        quality: stop.quality(),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
+    };
+    let id = create_sleep(db, events, time, lock, input).await?;
+    repository::clear_sleep_timer(db).await?;
+    repository::find_sleep_by_id(db, id)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+#[doc = r#"Discard the running sleep timer without recording a session. Returns whether one was
+running."#]
+pub async fn cancel_sleep_timer(db: &Db) -> Result<bool, Error> {
+    repository::clear_sleep_timer(db).await
+}
+
+#[doc = r#"Sessions whose wake date is `date`."#]
+pub async fn get_sleep_by_date(
+    db: &Db,
+    date: chrono::NaiveDate,
+) -> Result<Vec<SleepSession>, Error> {
+    repository::find_sleep_by_date(db, date).await
+}
+
+#[doc = r#"Replace session `id`, recomputing its duration like [`create_sleep`].
+
+# Errors
+- [`Error::Domain`] for invalid input or an overlap with another session
+- [`Error::Forbidden`] when the stored or new date is inside the no-edit window
+- [`Error::NotFound`] when `id` does not exist
+- [`Error::Database`] on database failures
+"#]
+pub async fn update_sleep(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    id: i64,
+    input: SleepInput,
+) -> Result<(), Error> {
+    input.validate()?;
+    lock.check(input.date)?;
+    if let Some(existing) = repository::find_sleep_by_id(db, id).await? {
+        lock.check(existing.date)?;
+    }
+    let (bed_dt, wake_dt) =
+        crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
+    let tz = time.timezone_for(db, &input).await;
+    let duration =
+        crate::time::compute_duration_min(input.date, input.bed_time, input.wake_time, tz)?;
+    if repository::has_sleep_overlap(db, bed_dt, wake_dt, Some(id)).await? {
+        return Err(Error::invalid("sleep session overlaps existing session"));
+    }
+    let updated = match repository::update_sleep(db, id, &input, duration).await {
+        Ok(updated) => updated,
+        Err(e) if is_overlap_db_error(&e) => {
+            return Err(Error::invalid("sleep session overlaps existing session"));
+        }
+        Err(e) => return Err(e),
+    };
+    if !updated {
+        return Err(Error::NotFound);
+    }
+    events.emit(DomainEvent::SleepUpdated {
+        id,
+        date: input.date,
+        duration_min: duration,
+    });
+    Ok(())
+}
+
+#[doc = r#"Change only the fields set in `patch` on session `id` and return the updated session.
+
+The merge happens in [`repository::patch_sleep`]; the duration is recomputed only when the date,
+times or session timezone change. Checks mirror [`update_sleep`]: the stored and resulting dates must be outside
+the no-edit window, and changed times must not overlap another session.
+
+# Errors
+- [`Error::Domain`] for an invalid merged record or an overlap with another session
+- [`Error::Forbidden`] when the stored or new date is inside the no-edit window
+- [`Error::NotFound`] when `id` does not exist
+- [`Error::Database`] on database failures
+"#]
+pub async fn patch_sleep(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    id: i64,
+    patch: SleepPatch,
+) -> Result<SleepSession, Error> {
+    let existing = repository::find_sleep_by_id(db, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    lock.check(existing.date)?;
+    let merged = patch.apply(&existing)?;
+    lock.check(merged.date)?;
+    if (merged.date, merged.bed_time, merged.wake_time)
+        != (existing.date, existing.bed_time, existing.wake_time)
+    {
+        let (bed_dt, wake_dt) =
+            crate::time::sleep_window_bounds(merged.date, merged.bed_time, merged.wake_time)?;
+        if repository::has_sleep_overlap(db, bed_dt, wake_dt, Some(id)).await? {
+            return Err(Error::invalid("sleep session overlaps existing session"));
+        }
+    }
+    let tz = time.timezone(db).await;
+    let (merged, duration) = match repository::patch_sleep(db, id, &patch, tz).await {
+        Ok(Some(patched)) => patched,
+        Ok(None) => return Err(Error::NotFound),
+        Err(e) if is_overlap_db_error(&e) => {
+            return Err(Error::invalid("sleep session overlaps existing session"));
+        }
+        Err(e) => return Err(e),
+    };
+    events.emit(DomainEvent::SleepUpdated {
+        id,
+        date: merged.date,
+        duration_min: duration,
+    });
+    repository::find_sleep_by_id(db, id)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+#[doc = r#"Delete session `id`; returns the number of rows removed (0 when missing)."#]
+pub async fn delete_sleep(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    if let Some(existing) = repository::find_sleep_by_id(db, id).await? {
+        lock.check(existing.date)?;
+    }
+    let affected = repository::delete_sleep(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "sleep_session", Some(id), reason).await?;
+        events.emit(DomainEvent::SleepDeleted { id });
+    }
+    Ok(affected)
+}
+
+#[doc = r#"Record an exercise entry and return its id."#]
+pub async fn create_exercise(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    input: ExerciseInput,
+) -> Result<i64, Error> {
+    input.validate()?;
+    repository::get_intensity_levels(db)
+        .await
+        .check(&input.intensity)?;
+    lock.check(input.date)?;
+    let id = repository::insert_exercise(db, &input).await?;
+    events.emit(DomainEvent::ExerciseCreated {
+        id,
+        date: input.date,
+    });
+    Ok(id)
+}
+
+#[doc = r#"Replace exercise event `id`; fails if it would become a second daily intensity entry
+for its date."#]
+pub async fn update_exercise(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    input: ExerciseInput,
+) -> Result<(), Error> {
+    input.validate()?;
+    repository::get_intensity_levels(db)
+        .await
+        .check(&input.intensity)?;
+    lock.check(input.date)?;
+    if let Some(existing) = repository::find_exercise_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    match repository::update_exercise(db, id, &input).await {
+        Ok(true) => {
+            events.emit(DomainEvent::ExerciseUpdated {
+                id,
+                date: input.date,
+            });
+            Ok(())
+        }
+        Ok(false) => Err(Error::NotFound),
+        Err(e) if is_unique_violation(&e) => Err(Error::invalid(
+            "a daily intensity entry already exists for that date",
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+#[doc = r#"Delete exercise event `id`; returns the number of rows removed."#]
+pub async fn delete_exercise(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    if let Some(existing) = repository::find_exercise_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    let affected = repository::delete_exercise(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "exercise", Some(id), reason).await?;
+        events.emit(DomainEvent::ExerciseDeleted { id });
+    }
+    Ok(affected)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Response of `GET /api/exercise/zones`: heart-rate zone minutes in [from, to].
+
+`sessions` and `totals` cover every exercise event with zones; `days` breaks them down per
+date (dates without zone data are omitted), ready to be lined up with the nights that follow.
+"#]
+pub struct ExerciseZones {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub sessions: i64,
+    pub totals: HrZoneMinutes,
+    pub days: Vec<ExerciseZoneDay>,
+}
+
+#[doc = r#"Aggregate heart-rate zone minutes of the exercise dated in [from, to]."#]
+pub async fn exercise_zones(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<ExerciseZones, Error> {
+    let days = repository::list_exercise_zones(db, from, to).await?;
+    let mut totals = HrZoneMinutes::default();
+    for day in &days {
+        totals.z1 += day.zones.z1;
+        totals.z2 += day.zones.z2;
+        totals.z3 += day.zones.z3;
+        totals.z4 += day.zones.z4;
+        totals.z5 += day.zones.z5;
+    }
+    Ok(ExerciseZones {
+        from,
+        to,
+        sessions: days.iter().map(|d| d.sessions).sum(),
+        totals,
+        days,
+    })
+}
+
+/// Reject a `session_id` that does not exist or whose wake date differs from the note's.
+async fn check_note_session(db: &Db, input: &NoteInput) -> Result<(), Error> {
+    let Some(session_id) = input.session_id else {
+        return Ok(());
+    };
+    match repository::find_sleep_date(db, session_id).await? {
+        None => Err(Error::invalid("unknown session_id")),
+        Some(date) if date != input.date => Err(Error::invalid(format!(
+            "date must be the linked session's wake date ({date})"
+        ))),
+        Some(_) => Ok(()),
+    }
+}
+
+#[doc = r#"Record a note and return its id.
+
+A `session_id` must name an existing session whose wake date is the note's `date`; otherwise
+the note is rejected with `400`.
+"#]
+pub async fn create_note(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    input: NoteInput,
+) -> Result<i64, Error> {
+    input.validate()?;
+    check_note_session(db, &input).await?;
+    lock.check(input.date)?;
+    let id = repository::insert_note(db, &input).await?;
+    events.emit(DomainEvent::NoteCreated {
+        id,
+        date: input.date,
+    });
+    Ok(id)
+}
+
+#[doc = r#"Replace note `id`, including its session link (see [`create_note`])."#]
+pub async fn update_note(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    input: NoteInput,
+) -> Result<(), Error> {
+    input.validate()?;
+    check_note_session(db, &input).await?;
+    lock.check(input.date)?;
+    if let Some(existing) = repository::find_note_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    if repository::update_note(db, id, &input).await? {
+        events.emit(DomainEvent::NoteUpdated {
+            id,
+            date: input.date,
+        });
+        Ok(())
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+#[doc = r#"Delete note `id`; returns the number of rows removed."#]
+pub async fn delete_note(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    if let Some(existing) = repository::find_note_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    let affected = repository::delete_note(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "note", Some(id), reason).await?;
+        events.emit(DomainEvent::NoteDeleted { id });
+    }
+    Ok(affected)
+}
+
+#[doc = r#"Star or unstar a sleep session (`entity` `sleep`) or note (`entity` `note`).
+
+Stars are bookkeeping, not data: the no-edit window does not apply, and setting the current
+state again succeeds.
+
+# Errors
+
+Returns [`Error::NotFound`] when the record does not exist.
+"#]
+pub async fn set_starred(
+    db: &Db,
+    events: &EventBus,
+    entity: &'static str,
+    id: i64,
+    starred: bool,
+) -> Result<(), Error> {
+    let found = match entity {
+        "sleep" => repository::set_sleep_starred(db, id, starred).await?,
+        "note" => repository::set_note_starred(db, id, starred).await?,
+        _ => false,
+    };
+    if !found {
+        return Err(Error::NotFound);
+    }
+    events.emit(DomainEvent::StarChanged {
+        entity,
+        id,
+        starred,
+    });
+    Ok(())
+}
+
+#[doc = r#"List starred sleep sessions and notes, newest first."#]
+pub async fn list_starred(db: &Db) -> Result<Starred, Error> {
+    Ok(Starred {
+        sleep: repository::list_starred_sleep(db).await?,
+        notes: repository::list_starred_notes(db).await?,
+    })
+}
+
+fn is_unique_violation(err: &Error) -> bool {
+    match err {
+        Error::Database(sqlx::Error::Database(db_err)) => {
+            db_err.message().contains("UNIQUE constraint failed")
+        }
+        _ => false,
+    }
+}
+
+#[doc = r#"Save the body metrics reading for a date (one per date; replaces an existing one)."#]
+pub async fn create_body_metric(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    input: BodyMetricInput,
+) -> Result<i64, Error> {
+    input.validate()?;
+    lock.check(input.date)?;
+    let id = repository::upsert_body_metric(db, &input, "manual").await?;
+    events.emit(DomainEvent::BodyMetricSaved {
+        id,
+        date: input.date,
+    });
+    Ok(id)
+}
+
+#[doc = r#"Replace body metrics reading `id`; fails if another reading has the new date."#]
+pub async fn update_body_metric(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    input: BodyMetricInput,
+) -> Result<(), Error> {
+    input.validate()?;
+    lock.check(input.date)?;
+    if let Some(existing) = repository::find_body_metric_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    match repository::update_body_metric(db, id, &input).await {
+        Ok(true) => {
+            events.emit(DomainEvent::BodyMetricSaved {
+                id,
+                date: input.date,
+            });
+            Ok(())
+        }
+        Ok(false) => Err(Error::NotFound),
+        Err(e) if is_unique_violation(&e) => Err(Error::invalid(
+            "a body metrics reading already exists for that date",
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+#[doc = r#"Delete body metrics reading `id`; returns the number of rows removed."#]
+pub async fn delete_body_metric(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    if let Some(existing) = repository::find_body_metric_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    let affected = repository::delete_body_metric(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "body_metric", Some(id), reason).await?;
+        events.emit(DomainEvent::BodyMetricDeleted { id });
+    }
+    Ok(affected)
+}
+
+#[doc = r#"Record a disturbance and return its id."#]
+pub async fn create_disturbance(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    input: DisturbanceInput,
+) -> Result<i64, Error> {
+    input.validate()?;
+    lock.check(input.date)?;
+    let id = repository::insert_disturbance(db, &input).await?;
+    events.emit(DomainEvent::DisturbanceSaved {
+        id,
+        date: input.date,
+    });
+    Ok(id)
+}
+
+#[doc = r#"Replace disturbance `id`."#]
+pub async fn update_disturbance(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    input: DisturbanceInput,
+) -> Result<(), Error> {
+    input.validate()?;
+    lock.check(input.date)?;
+    if let Some(existing) = repository::find_disturbance_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    if repository::update_disturbance(db, id, &input).await? {
+        events.emit(DomainEvent::DisturbanceSaved {
+            id,
+            date: input.date,
+        });
+        Ok(())
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+#[doc = r#"Delete disturbance `id`; returns the number of rows removed."#]
+pub async fn delete_disturbance(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    if let Some(existing) = repository::find_disturbance_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    let affected = repository::delete_disturbance(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "disturbance", Some(id), reason).await?;
+        events.emit(DomainEvent::DisturbanceDeleted { id });
+    }
+    Ok(affected)
+}
+
+fn trimmed_experiment(input: ExperimentInput) -> ExperimentInput {
+    ExperimentInput {
+        name: input.name.trim().to_string(),
+        description: input
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty()),
+        ..input
+    }
+}
+
+#[doc = r#"Create an experiment (name and description trimmed) and return its id."#]
+pub async fn create_experiment(
+    db: &Db,
+    events: &EventBus,
+    input: ExperimentInput,
+) -> Result<i64, Error> {
+    let input = trimmed_experiment(input);
+    input.validate()?;
+    let id = repository::insert_experiment(db, &input).await?;
+    events.emit(DomainEvent::ExperimentSaved { id });
+    Ok(id)
+}
+
+#[doc = r#"Replace experiment `id`."#]
+pub async fn update_experiment(
+    db: &Db,
+    events: &EventBus,
+    id: i64,
+    input: ExperimentInput,
+) -> Result<(), Error> {
+    let input = trimmed_experiment(input);
+    input.validate()?;
+    if repository::update_experiment(db, id, &input).await? {
+        events.emit(DomainEvent::ExperimentSaved { id });
+        Ok(())
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+#[doc = r#"Delete experiment `id`; returns the number of rows removed."#]
+pub async fn delete_experiment(
+    db: &Db,
+    events: &EventBus,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    let affected = repository::delete_experiment(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "experiment", Some(id), reason).await?;
+        events.emit(DomainEvent::ExperimentDeleted { id });
+    }
+    Ok(affected)
+}
+
+fn trimmed_medication(input: MedicationInput) -> MedicationInput {
+    MedicationInput {
+        name: input.name.trim().to_string(),
+        dose: input
+            .dose
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty()),
+        ..input
+    }
+}
+
+#[doc = r#"Create a medication (name and dose trimmed) and return its id.
+
+A name already in use is rejected with `400`.
+"#]
+pub async fn create_medication(
+    db: &Db,
+    events: &EventBus,
+    input: MedicationInput,
+) -> Result<i64, Error> {
+    let input = trimmed_medication(input);
+    input.validate()?;
+    match repository::insert_medication(db, &input).await {
+        Ok(id) => {
+            events.emit(DomainEvent::MedicationSaved { id });
+            Ok(id)
+        }
+        Err(e) if is_unique_violation(&e) => {
+            Err(Error::invalid("a medication with that name already exists"))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[doc = r#"Replace medication `id`."#]
+pub async fn update_medication(
+    db: &Db,
+    events: &EventBus,
+    id: i64,
+    input: MedicationInput,
+) -> Result<(), Error> {
+    let input = trimmed_medication(input);
+    input.validate()?;
+    match repository::update_medication(db, id, &input).await {
+        Ok(true) => {
+            events.emit(DomainEvent::MedicationSaved { id });
+            Ok(())
+        }
+        Ok(false) => Err(Error::NotFound),
+        Err(e) if is_unique_violation(&e) => {
+            Err(Error::invalid("a medication with that name already exists"))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[doc = r#"Delete medication `id` and its logged events; returns the number of medications removed."#]
+pub async fn delete_medication(
+    db: &Db,
+    events: &EventBus,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    let affected = repository::delete_medication(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "medication", Some(id), reason).await?;
+        events.emit(DomainEvent::MedicationDeleted { id });
+    }
+    Ok(affected)
+}
+
+async fn checked_medication_event(
+    db: &Db,
+    input: MedicationEventInput,
+) -> Result<MedicationEventInput, Error> {
+    let input = MedicationEventInput {
+        dose: input
+            .dose
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty()),
+        ..input
+    };
+    input.validate()?;
+    if !repository::medication_exists(db, input.medication_id).await? {
+        return Err(Error::invalid("unknown medication_id"));
+    }
+    Ok(input)
+}
+
+#[doc = r#"Log a medication intake and return its id.
+
+An unknown `medication_id` is rejected with `400`.
+"#]
+pub async fn create_medication_event(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    input: MedicationEventInput,
+) -> Result<i64, Error> {
+    let input = checked_medication_event(db, input).await?;
+    lock.check(input.date)?;
+    let id = repository::insert_medication_event(db, &input).await?;
+    events.emit(DomainEvent::MedicationEventSaved {
+        id,
+        date: input.date,
+    });
+    Ok(id)
+}
+
+#[doc = r#"Replace medication event `id`."#]
+pub async fn update_medication_event(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    input: MedicationEventInput,
+) -> Result<(), Error> {
+    let input = checked_medication_event(db, input).await?;
+    lock.check(input.date)?;
+    if let Some(existing) = repository::find_medication_event_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    if repository::update_medication_event(db, id, &input).await? {
+        events.emit(DomainEvent::MedicationEventSaved {
+            id,
+            date: input.date,
+        });
+        Ok(())
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+#[doc = r#"Delete medication event `id`; returns the number of rows removed."#]
+pub async fn delete_medication_event(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    id: i64,
+    reason: &AuditReason,
+) -> Result<u64, Error> {
+    reason.validate()?;
+    if let Some(existing) = repository::find_medication_event_date(db, id).await? {
+        lock.check(existing)?;
+    }
+    let affected = repository::delete_medication_event(db, id).await?;
+    if affected > 0 {
+        repository::insert_audit_entry(db, "delete", "medication_event", Some(id), reason).await?;
+        events.emit(DomainEvent::MedicationEventDeleted { id });
+    }
+    Ok(affected)
+}
+
+#[doc = r#"Record a successful login from `device`, notifying when the device is new.
+
+The first device ever recorded does not trigger a notification. Delivery runs in the
+background so a slow notification channel never delays the login.
+"#]
+pub async fn record_login_device(db: &Db, device: &DeviceFingerprint) -> Result<(), Error> {
+    let had_devices = repository::count_known_devices(db).await? > 0;
+    let is_new = repository::record_device_login(db, &device.hash, &device.label).await?;
+    if is_new && had_devices {
+        let notification = Notification {
+            kind: "new_device",
+            title: "New login device".to_string(),
+            body: format!("Signed in from a new device: {}", device.label),
+        };
+        tokio::spawn(async move {
+            notify::deliver(&notification).await;
+        });
+    }
+    Ok(())
+}
+
+#[doc = r#"List known login devices, flagging the one making the request."#]
+pub async fn list_devices(db: &Db, current: &DeviceFingerprint) -> Result<Vec<KnownDevice>, Error> {
+    repository::list_known_devices(db, &current.hash).await
+}
+
+#[doc = r#"Forget a known device; its next login counts as new again. Idempotent."#]
+pub async fn forget_device(db: &Db, events: &EventBus, id: i64) -> Result<bool, Error> {
+    let deleted = repository::delete_known_device(db, id).await?;
+    if deleted {
+        events.emit(DomainEvent::DeviceForgotten { id });
+    }
+    Ok(deleted)
+}
+
+/// Hours an email change confirmation link stays valid.
+pub const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
+
+#[doc = r#"Start a login email change after re-checking the current password.
+
+The confirmation link (`/api/account/email/confirm/{secret}`) is sent through the notification
+channels in the background (see [`notify`]) and the request is recorded in the audit log. The
+login email only changes once the link is opened ([`confirm_email_change`]).
+
+# Errors
+
+- [`Error::Domain`] for an invalid body, or when `new_email` already is the login email.
+- [`Error::Forbidden`] when `current_password` is wrong.
+"#]
+pub async fn request_email_change(
+    db: &Db,
+    now: NaiveDateTime,
+    input: EmailChangeInput,
+) -> Result<PendingEmailChange, Error> {
+    input.validate()?;
+    let login_email = repository::get_login_email(db, &crate::config::admin_email()).await;
+    let hash =
+        password::effective_hash(db, &login_email, &crate::config::admin_password_hash()).await;
+    if hash.is_empty() || !password::verify(&input.current_password, &hash) {
+        return Err(Error::Forbidden("current password is incorrect".into()));
+    }
+    let new_email = input.new_email().to_string();
+    if new_email.eq_ignore_ascii_case(&login_email) {
+        return Err(Error::invalid("new_email is already the login email"));
+    }
+
+    let secret = token::generate_email_change_secret();
+    let expires_at = now + ChronoDuration::hours(EMAIL_CHANGE_TTL_HOURS);
+    let id = repository::insert_email_change(
+        db,
+        &token::hash_secret(&secret),
+        &new_email,
+        &login_email,
+        expires_at,
+    )
+    .await?;
+    let audit = AuditReason {
+        reason: None,
+        note: Some(format!("{login_email} -> {new_email}")),
+    };
+    repository::insert_audit_entry(db, "request", "account_email", Some(id), &audit).await?;
+
+    let notification = Notification {
+        kind: "email_change",
+        title: "Confirm your new login email".to_string(),
+        body: format!(
+            "Open /api/account/email/confirm/{secret} within {EMAIL_CHANGE_TTL_HOURS} hours to \
+             sign in as {new_email}. Ignore this if you did not ask for it."
+        ),
+    };
+    tokio::spawn(async move {
+        notify::deliver(&notification).await;
+    });
+    Ok(PendingEmailChange {
+        new_email,
+        expires_at,
+    })
+}
+
+#[doc = r#"Confirm an email change with the secret from its link and return the new login email.
+
+Discards any other pending change and records the switch in the audit log.
+
+# Errors
+
+Returns [`Error::NotFound`] for an unknown, expired or already used secret, or one requested
+before a later change of the login email.
+"#]
+pub async fn confirm_email_change(
+    db: &Db,
+    events: &EventBus,
+    now: NaiveDateTime,
+    secret: &str,
+) -> Result<String, Error> {
+    let configured = crate::config::admin_email();
+    let login_email = repository::get_login_email(db, &configured).await;
+    let (id, new_email) = repository::confirm_email_change(
+        db,
+        &token::hash_secret(secret),
+        now,
+        &login_email,
+        &configured,
+    )
+    .await?
+    .ok_or(Error::NotFound)?;
+    let audit = AuditReason {
+        reason: None,
+        note: Some(format!("{login_email} -> {new_email}")),
+    };
+    repository::insert_audit_entry(db, "confirm", "account_email", Some(id), &audit).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "account_email",
+    });
+    Ok(new_email)
+}
+
+#[doc = r#"Create an API token expiring `input.expires_in_days` after `now` and return it with
+its secret (shown once)."#]
+pub async fn create_api_token(
+    db: &Db,
+    events: &EventBus,
+    now: NaiveDateTime,
+    input: ApiTokenInput,
+) -> Result<CreatedApiToken, Error> {
+    input.validate()?;
+    let secret = token::generate_secret();
+    let expires_at = now + ChronoDuration::days(i64::from(input.expires_in_days()));
+    let id = repository::insert_api_token(
+        db,
+        input.name.trim(),
+        input.scope.as_str(),
+        &token::hash_secret(&secret),
+        expires_at,
+    )
+    .await?;
+    let token = repository::find_api_token(db, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    events.emit(DomainEvent::ApiTokenCreated { id });
+    Ok(CreatedApiToken { token, secret })
+}
+
+#[doc = r#"List API tokens, flagging those expired at `now`."#]
+pub async fn list_api_tokens(db: &Db, now: NaiveDateTime) -> Result<Vec<ApiToken>, Error> {
+    let mut tokens = repository::list_api_tokens(db).await?;
+    for token in &mut tokens {
+        token.expired = token.expires_at <= now;
+    }
+    Ok(tokens)
+}
+
+#[doc = r#"Revoke an API token; requests using it are rejected from then on. Idempotent."#]
+pub async fn revoke_api_token(db: &Db, events: &EventBus, id: i64) -> Result<bool, Error> {
+    let deleted = repository::delete_api_token(db, id).await?;
+    if deleted {
+        events.emit(DomainEvent::ApiTokenRevoked { id });
+    }
+    Ok(deleted)
+}
+
+#[doc = r#"Create a share link for `input`'s date range expiring `input.expires_in_days` after
+`now`, and return it with its secret (shown once)."#]
+pub async fn create_share_link(
+    db: &Db,
+    events: &EventBus,
+    now: NaiveDateTime,
+    input: ShareLinkInput,
+) -> Result<CreatedShareLink, Error> {
+    input.validate()?;
+    let secret = token::generate_share_secret();
+    let expires_at = now + ChronoDuration::days(i64::from(input.expires_in_days()));
+    let id = repository::insert_share_link(
+        db,
+        &token::hash_secret(&secret),
+        input.from,
+        input.to,
+        expires_at,
+        input.max_uses,
+    )
+    .await?;
+    let link = repository::find_share_link(db, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    events.emit(DomainEvent::ShareLinkCreated { id });
+    Ok(CreatedShareLink { link, secret })
+}
+
+#[doc = r#"List share links, flagging those expired at `now` or used up."#]
+pub async fn list_share_links(db: &Db, now: NaiveDateTime) -> Result<Vec<ShareLink>, Error> {
+    let mut links = repository::list_share_links(db).await?;
+    for link in &mut links {
+        link.expired =
+            link.expires_at <= now || link.max_uses.is_some_and(|max| link.use_count >= max);
+    }
+    Ok(links)
+}
+
+#[doc = r#"Usage of share link `id`: view counts and the client addresses that opened it.
+
+# Errors
+
+Returns [`Error::NotFound`] when the link does not exist (or was revoked).
+"#]
+pub async fn share_link_stats(db: &Db, id: i64) -> Result<ShareLinkStats, Error> {
+    let link = repository::find_share_link(db, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let visitors = repository::list_share_link_visitors(db, id).await?;
+    Ok(ShareLinkStats {
+        id,
+        use_count: link.use_count,
+        max_uses: link.max_uses,
+        last_used_at: link.last_used_at,
+        distinct_ips: visitors.len(),
+        visitors,
+    })
+}
+
+#[doc = r#"Revoke a share link; opening it answers 404 from then on. Idempotent."#]
+pub async fn revoke_share_link(db: &Db, events: &EventBus, id: i64) -> Result<bool, Error> {
+    let deleted = repository::delete_share_link(db, id).await?;
+    if deleted {
+        events.emit(DomainEvent::ShareLinkRevoked { id });
+    }
+    Ok(deleted)
+}
+
+/// Records of each kind returned by one share link view.
+const SHARED_VIEW_ROWS: i64 = 2000;
+
+#[doc = r#"The records behind the share link `secret` at `now`, redacted with the share policy.
+
+The view is counted against the link's `max_uses` and recorded for client address `ip` (see
+[`share_link_stats`]).
+
+# Errors
+
+Returns [`Error::NotFound`] when the link is unknown, revoked, expired or used up.
+"#]
+pub async fn shared_view(
+    db: &Db,
+    secret: &str,
+    now: NaiveDateTime,
+    ip: Option<std::net::IpAddr>,
+) -> Result<SharedView, Error> {
+    let ip = ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+    let link = repository::use_share_link(db, &token::hash_secret(secret), now, &ip)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let (from, to) = (link.from_date, link.to_date);
+    let policy = redaction::policy(db, Audience::Share).await;
+    Ok(SharedView {
+        from,
+        to,
+        sleep: repository::export_sleep_page(db, from, to, None, SHARED_VIEW_ROWS)
+            .await?
+            .redact(&policy),
+        exercise: repository::export_exercise_page(db, from, to, None, SHARED_VIEW_ROWS)
+            .await?
+            .redact(&policy),
+        notes: repository::export_notes_page(db, from, to, None, SHARED_VIEW_ROWS)
+            .await?
+            .redact(&policy),
+    })
+}
+
+/// Accessor for one metric on a daily row.
+type DailyMetric = fn(&SleepListItem) -> Option<i32>;
+
+/// Metrics compared by [`experiment_results`], with their accessor on a daily row.
+const EXPERIMENT_METRICS: [(&str, DailyMetric); 5] = [
+    ("duration_min", |r| r.duration_min),
+    ("quality", |r| Some(r.quality)),
+    ("latency_min", |r| Some(r.latency_min)),
+    ("awakenings", |r| Some(r.awakenings)),
+    ("wake_feeling", |r| r.wake_feeling),
+];
+
+fn group_summary(values: &[f64]) -> GroupSummary {
+    let (mean, sd) = crate::stats::mean_sd(values);
+    GroupSummary {
+        n: values.len(),
+        mean,
+        sd,
     }
-    match repository::insert_sleep(db, &input, duration).await {
-        Ok(id) => Ok(id),
-        Err(e) if is_overlap_db_error(&e) => Err(ApiError::InvalidInput(
-            "sleep session overlaps existing session".into(),
-        )),
-        Err(e) => Err(e.into()),
+}
+
+fn compare_experiment_metrics(
+    during: &[&SleepListItem],
+    baseline: &[&SleepListItem],
+) -> Vec<ExperimentMetricResult> {
+    EXPERIMENT_METRICS
+        .iter()
+        .map(|(metric, get)| {
+            let collect = |rows: &[&SleepListItem]| -> Vec<f64> {
+                rows.iter().filter_map(|r| get(r)).map(f64::from).collect()
+            };
+            let (a, b) = (collect(during), collect(baseline));
+            let (during, baseline) = (group_summary(&a), group_summary(&b));
+            let ci = crate::stats::welch_ci95(&a, &b);
+            ExperimentMetricResult {
+                metric,
+                diff: match (during.mean, baseline.mean) {
+                    (Some(x), Some(y)) => Some(x - y),
+                    _ => None,
+                },
+                ci95_low: ci.map(|c| c.1),
+                ci95_high: ci.map(|c| c.2),
+                inference: crate::stats::inference::compare_groups(&a, &b),
+                during,
+                baseline,
+            }
+        })
+        .collect()
+}
+
+#[doc = r#"Compare experiment `id` against its baseline (`before`, default, or `outside`).
+
+An open-ended experiment runs through [`TimeContext::today`].
+"#]
+pub async fn experiment_results(
+    db: &Db,
+    time: &TimeContext,
+    id: i64,
+    baseline: Option<&str>,
+) -> Result<ExperimentResults, Error> {
+    let baseline = baseline.unwrap_or("before");
+    if baseline != "before" && baseline != "outside" {
+        return Err(Error::invalid("baseline must be before or outside"));
     }
+    let experiment: Experiment = repository::find_experiment(db, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let today = time.today(db).await;
+    let period_from = experiment.start_date;
+    let period_to = experiment.end_date.unwrap_or(today).max(period_from);
+    let days = (period_to - period_from).num_days() + 1;
+
+    let (from, to) = if baseline == "before" {
+        (period_from - ChronoDuration::days(days), period_to)
+    } else {
+        // Every logged night; bounds stay within the YYYY-MM-DD text format stored in SQLite.
+        (
+            NaiveDate::from_ymd_opt(1, 1, 1).expect("valid date"),
+            NaiveDate::from_ymd_opt(9999, 12, 31).expect("valid date"),
+        )
+    };
+    let rows = repository::list_daily_sleep_range(db, from, to).await?;
+    let (during, outside): (Vec<&SleepListItem>, Vec<&SleepListItem>) = rows
+        .iter()
+        .partition(|r| r.date >= period_from && r.date <= period_to);
+
+    Ok(ExperimentResults {
+        metrics: compare_experiment_metrics(&during, &outside),
+        experiment,
+        baseline: baseline.to_string(),
+        period_from,
+        period_to,
+    })
 }
 
-pub async fn get_sleep_by_date(
+#[derive(Serialize, JsonSchema)]
+pub struct BodyMetricsImportSummary {
+    pub source: &'static str,
+    pub imported: usize,
+}
+
+#[doc = r#"Parse a third-party weight export (`source`: see [`WeightSource`]) and upsert its readings."#]
+pub async fn import_body_metrics(
     db: &Db,
-    date: chrono::NaiveDate,
-) -> Result<Vec<SleepSession>, ApiError> {
-    Ok(repository::find_sleep_by_date(db, date).await?)
+    events: &EventBus,
+    lock: &EditLock,
+    source: &str,
+    payload: &str,
+) -> Result<BodyMetricsImportSummary, Error> {
+    let source = WeightSource::from_str(source)?;
+    let readings = importers::parse_weight_export(source, payload)?;
+    for reading in &readings {
+        lock.check(reading.date)?;
+    }
+    let imported = repository::upsert_body_metrics_batch(db, &readings, source.as_str()).await?;
+    events.emit(DomainEvent::BodyMetricsImported {
+        source: source.as_str(),
+        imported,
+    });
+    Ok(BodyMetricsImportSummary {
+        source: source.as_str(),
+        imported,
+    })
 }
 
-pub async fn update_sleep(db: &Db, id: i64, input: SleepInput) -> Result<(), ApiError> {
-    input.validate()?;
-    let (bed_dt, wake_dt) =
-        crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
-    let tz = repository::get_user_timezone(db).await;
-    let duration =
-        crate::time::compute_duration_min(input.date, input.bed_time, input.wake_time, tz)?;
-    if repository::has_sleep_overlap(db, bed_dt, wake_dt, Some(id)).await? {
-        return Err(ApiError::InvalidInput(
-            "sleep session overlaps existing session".into(),
-        ));
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Outcome of one webhook push; entries already recorded are counted as skipped."#]
+pub struct IngestSummary {
+    pub source: &'static str,
+    pub sleep_imported: usize,
+    pub sleep_skipped: usize,
+    pub exercise_imported: usize,
+    pub exercise_skipped: usize,
+}
+
+#[doc = r#"Normalize a pushed payload (`source`: see [`IngestSource`]) and record its entries.
+
+The whole payload is validated before anything is written. Pushes are retried by senders, so
+entries whose external reference is already recorded, sleep that overlaps an existing session,
+and timed exercise already logged at the same start are skipped rather than rejected. New
+entries are linked to their external reference, if any.
+"#]
+pub async fn ingest(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    source: IngestSource,
+    payload: &str,
+) -> Result<IngestSummary, Error> {
+    let tz = time.timezone(db).await;
+    let day = repository::get_day_boundary(db).await;
+    let batch = importers::parse_ingest(source, payload, tz, day)?;
+    for date in batch
+        .sleep
+        .iter()
+        .map(|s| s.input.date)
+        .chain(batch.exercise.iter().map(|e| e.input.date))
+    {
+        lock.check(date)?;
     }
-    let updated = match repository::update_sleep(db, id, &input, duration).await {
-        Ok(updated) => updated,
-        Err(e) if is_overlap_db_error(&e) => {
-            return Err(ApiError::InvalidInput(
-                "sleep session overlaps existing session".into(),
-            ));
+    let mut summary = IngestSummary {
+        source: source.as_str(),
+        sleep_imported: 0,
+        sleep_skipped: 0,
+        exercise_imported: 0,
+        exercise_skipped: 0,
+    };
+    for IngestEntry {
+        input,
+        external_ref,
+    } in batch.sleep
+    {
+        if let Some(r) = &external_ref
+            && repository::has_external_ref(db, r).await?
+        {
+            summary.sleep_skipped += 1;
+            continue;
+        }
+        let (bed_dt, wake_dt) =
+            crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
+        if repository::has_sleep_overlap(db, bed_dt, wake_dt, None).await? {
+            summary.sleep_skipped += 1;
+            continue;
+        }
+        let id = create_sleep(db, events, time, lock, input).await?;
+        if let Some(r) = &external_ref {
+            repository::insert_sleep_external_ref(db, id, r).await?;
+        }
+        summary.sleep_imported += 1;
+    }
+    for IngestEntry {
+        input,
+        external_ref,
+    } in batch.exercise
+    {
+        if let Some(r) = &external_ref
+            && repository::has_external_ref(db, r).await?
+        {
+            summary.exercise_skipped += 1;
+            continue;
+        }
+        if let Some(start) = input.start_time
+            && repository::has_exercise_at(db, input.date, start).await?
+        {
+            summary.exercise_skipped += 1;
+            continue;
+        }
+        let id = create_exercise(db, events, lock, input).await?;
+        if let Some(r) = &external_ref {
+            repository::insert_exercise_external_ref(db, id, r).await?;
         }
-        Err(e) => return Err(e.into()),
+        summary.exercise_imported += 1;
+    }
+    Ok(summary)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Result of `POST /api/import/mapping-preview`.
+
+`rows` holds the first [`MAPPING_PREVIEW_ROWS`] valid rows; `issues` lists every problem.
+
+[`MAPPING_PREVIEW_ROWS`]: crate::importers::MAPPING_PREVIEW_ROWS
+"#]
+pub struct MappingPreview {
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub rows: Vec<MappedRow>,
+    pub issues: Vec<ImportIssue>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Outcome of `POST /api/import/with-mapping`; rows overlapping recorded sleep are skipped."#]
+pub struct MappingImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+#[doc = r#"Parse a spreadsheet with a column mapping without writing anything."#]
+pub async fn preview_mapping_import(
+    db: &Db,
+    time: &TimeContext,
+    req: &MappingImportRequest,
+) -> MappingPreview {
+    let tz = time.timezone(db).await;
+    let mapped = importers::parse_mapped_csv(&req.csv, &req.mapping, tz);
+    let valid_rows = mapped.rows.len();
+    MappingPreview {
+        total_rows: valid_rows + mapped.issues.iter().filter(|i| i.row > 0).count(),
+        valid_rows,
+        rows: mapped
+            .rows
+            .into_iter()
+            .take(importers::MAPPING_PREVIEW_ROWS)
+            .collect(),
+        issues: mapped.issues,
+    }
+}
+
+#[doc = r#"Import a spreadsheet with a column mapping.
+
+Nothing is written unless every row parses; the first issue is returned as
+[`Error::Domain`] otherwise. As with webhook pushes, rows overlapping sleep that is
+already recorded (including earlier rows of the same file) are skipped, so re-running an
+import is harmless.
+"#]
+pub async fn import_with_mapping(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    req: &MappingImportRequest,
+) -> Result<MappingImportSummary, Error> {
+    let tz = time.timezone(db).await;
+    let mapped = importers::parse_mapped_csv(&req.csv, &req.mapping, tz);
+    if let Some(issue) = mapped.issues.first() {
+        let location = match (issue.row, &issue.column) {
+            (0, _) => "mapping".to_string(),
+            (row, Some(column)) => format!("row {row} ({column})"),
+            (row, None) => format!("row {row}"),
+        };
+        let more = match mapped.issues.len() - 1 {
+            0 => String::new(),
+            n => format!(" ({n} more issues)"),
+        };
+        return Err(Error::invalid(format!(
+            "{location}: {}{more}",
+            issue.message
+        )));
+    }
+    for row in &mapped.rows {
+        lock.check(row.input.date)?;
+    }
+    let mut summary = MappingImportSummary {
+        imported: 0,
+        skipped: 0,
     };
-    if !updated {
-        return Err(ApiError::NotFound);
+    for MappedRow { input, .. } in mapped.rows {
+        let (bed_dt, wake_dt) =
+            crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
+        if repository::has_sleep_overlap(db, bed_dt, wake_dt, None).await? {
+            summary.skipped += 1;
+            continue;
+        }
+        create_sleep(db, events, time, lock, input).await?;
+        summary.imported += 1;
     }
-    Ok(())
+    Ok(summary)
 }
 
-pub async fn delete_sleep(db: &Db, id: i64) -> Result<u64, ApiError> {
-    repository::delete_sleep(db, id).await.map_err(Into::into)
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Per-row report of `POST /api/import/sleep`.
+
+- `total_rows`: non-blank data rows in the file.
+- `imported`: rows written, or with `dry_run` the rows that would be.
+- `skipped`: valid rows overlapping recorded sleep or an earlier row of the file.
+- `errors`: every rejected row (`row` counts the header as row 1; `row: 0` is a problem with
+  the mapping). When non-empty nothing is written.
+"#]
+pub struct SleepImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub imported: usize,
+    pub skipped: usize,
+    pub errors: Vec<ImportIssue>,
 }
 
-pub async fn create_exercise(db: &Db, input: ExerciseInput) -> Result<i64, ApiError> {
-    input.validate()?;
-    Ok(repository::insert_exercise(db, &input).await?)
+#[doc = r#"Import a sleep history CSV with a column mapping, all or nothing.
+
+Each row is parsed and validated like `POST /api/sleep` (see
+[`importers::parse_mapped_csv`]); rows inside the no-edit window are rejected too. When any
+row is rejected nothing is written and the report lists every error. Otherwise the rows are
+inserted in a single transaction, skipping overlaps as [`import_with_mapping`] does. With
+`dry_run` the transaction is rolled back, so the report is exactly what a real run would do.
+"#]
+pub async fn import_sleep_csv(
+    db: &Db,
+    events: &EventBus,
+    time: &TimeContext,
+    lock: &EditLock,
+    csv: &str,
+    mapping: &importers::ColumnMapping,
+    dry_run: bool,
+) -> Result<SleepImportReport, Error> {
+    let tz = time.timezone(db).await;
+    let mapped = importers::parse_mapped_csv(csv, mapping, tz);
+    let mut errors = mapped.issues;
+    let total_rows = mapped.rows.len() + errors.iter().filter(|i| i.row > 0).count();
+    let mut rows = Vec::with_capacity(mapped.rows.len());
+    for MappedRow { row, input } in mapped.rows {
+        if let Err(e) = lock.check(input.date) {
+            errors.push(ImportIssue {
+                row,
+                column: Some(mapping.date.clone()),
+                message: match e {
+                    Error::Forbidden(message) => message,
+                    other => other.to_string(),
+                },
+            });
+            continue;
+        }
+        let duration = crate::time::compute_duration_min(
+            input.date,
+            input.bed_time,
+            input.wake_time,
+            input.tz().unwrap_or(tz),
+        )?;
+        rows.push((input, duration));
+    }
+    let mut report = SleepImportReport {
+        dry_run,
+        total_rows,
+        imported: 0,
+        skipped: 0,
+        errors,
+    };
+    if !report.errors.is_empty() {
+        report.errors.sort_by_key(|issue| issue.row);
+        return Ok(report);
+    }
+
+    let ids = repository::import_sleeps(db, &rows, !dry_run).await?;
+    for ((input, duration), id) in rows.iter().zip(&ids) {
+        match id {
+            Some(id) => {
+                report.imported += 1;
+                if !dry_run {
+                    events.emit(DomainEvent::SleepCreated {
+                        id: *id,
+                        date: input.date,
+                        duration_min: *duration,
+                    });
+                }
+            }
+            None => report.skipped += 1,
+        }
+    }
+    Ok(report)
+}
+
+#[doc = r#"Run a validated read-only query; [`Error::NotFound`] when the feature is disabled."#]
+pub async fn run_admin_query(db: &Db, req: QueryRequest) -> Result<QueryResult, Error> {
+    if !config::admin_query_enabled() {
+        return Err(Error::NotFound);
+    }
+    let sql = admin_query::validate_select(&req.sql)?;
+    admin_query::run_read_only(
+        db,
+        sql,
+        config::admin_query_max_rows(),
+        config::admin_query_timeout(),
+    )
+    .await
+    .map_err(|e| match e {
+        // The SQL is caller-supplied, so engine errors (syntax, read-only, interrupted) are input errors.
+        sqlx::Error::Database(db_err) => Error::invalid(db_err.message()),
+        other => Error::Database(other),
+    })
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct JobsOverview {
+    pub jobs: Vec<&'static str>,
+    pub runs: Vec<JobRun>,
+}
+
+#[doc = r#"Registered maintenance jobs with their last runs."#]
+pub async fn list_jobs(db: &Db) -> Result<JobsOverview, Error> {
+    let runs = repository::list_job_runs(db, 50).await?;
+    Ok(JobsOverview {
+        jobs: Job::ALL.into_iter().map(Job::name).collect(),
+        runs,
+    })
+}
+
+#[doc = r#"Backups under `root`, newest first. See [`crate::export::list_exports`]."#]
+pub fn list_backups(root: &std::path::Path) -> Result<Vec<ExportSummary>, Error> {
+    Ok(export::list_exports(root)?)
+}
+
+#[doc = r#"Write a full export of `db` as a new backup under `root`, named after `now`
+(e.g. `20250601T120000Z`); `key` signs its manifest.
+
+# Errors
+
+Returns [`Error::Domain`] when a backup with that name exists or the database is in-memory,
+and [`Error::Database`] / [`Error::Io`] on database and file failures.
+"#]
+pub async fn create_backup(
+    db: &Db,
+    root: &std::path::Path,
+    key: Option<&[u8]>,
+    now: DateTime<Utc>,
+) -> Result<ExportSummary, Error> {
+    let name = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let manifest = export::write_export(db, &root.join(&name), key, now).await?;
+    Ok(ExportSummary::new(name, manifest))
+}
+
+#[doc = r#"Compare the live sessions with a downloaded backup `manifest`; `key` checks its
+signature. See [`crate::export::compare_export`].
+
+# Errors
+
+Returns [`Error::Domain`] for a version 1 manifest (no per-record digests), an unknown version
+or a signature that does not match, and [`Error::Database`] on database failures.
+"#]
+pub async fn compare_backup(
+    db: &Db,
+    manifest: &export::Manifest,
+    key: Option<&[u8]>,
+) -> Result<export::CompareReport, Error> {
+    Ok(export::compare_export(db, manifest, key).await?)
+}
+
+#[doc = r#"Store an uploaded file for `upload.date` under `root` and return its metadata.
+
+Size, thumbnail and duration are extracted on a blocking thread (see
+[`crate::attachments::extract`]) before anything is written. The row is inserted first so
+the files can be named by its id; it is removed again if writing them fails.
+
+# Errors
+
+Returns [`Error::Domain`] for an invalid file name, an empty body, or a date inside the
+no-edit window, and [`Error::Database`] / [`Error::Io`] on database and file failures.
+"#]
+pub async fn create_attachment(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    root: &std::path::Path,
+    upload: AttachmentUpload,
+    content_type: &str,
+    bytes: axum::body::Bytes,
+) -> Result<Attachment, Error> {
+    upload.validate()?;
+    lock.check(upload.date)?;
+    if bytes.is_empty() {
+        return Err(Error::invalid("attachment is empty"));
+    }
+    let sha256 = {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(&bytes))
+    };
+    let media = {
+        let (content_type, bytes) = (content_type.to_string(), bytes.clone());
+        tokio::task::spawn_blocking(move || attachments::extract(&content_type, &bytes))
+            .await
+            .unwrap_or_default()
+    };
+
+    let id = repository::insert_attachment(
+        db,
+        &upload,
+        content_type,
+        bytes.len() as i64,
+        &sha256,
+        &media,
+    )
+    .await?;
+    let written = async {
+        tokio::fs::create_dir_all(root).await?;
+        tokio::fs::write(attachments::original_path(root, id), &bytes).await?;
+        if let Some(thumb) = &media.thumbnail {
+            tokio::fs::write(attachments::thumb_path(root, id), thumb).await?;
+        }
+        Ok::<_, std::io::Error>(())
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(attachments::original_path(root, id)).await;
+        let _ = tokio::fs::remove_file(attachments::thumb_path(root, id)).await;
+        repository::delete_attachment(db, id).await?;
+        return Err(e.into());
+    }
+
+    events.emit(DomainEvent::AttachmentCreated {
+        id,
+        date: upload.date,
+    });
+    repository::find_attachment(db, id)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+#[doc = r#"List the attachments of a day, oldest first."#]
+pub async fn list_attachments(db: &Db, date: NaiveDate) -> Result<Vec<Attachment>, Error> {
+    repository::list_attachments(db, date).await
+}
+
+#[doc = r#"Run an integrity check and make it the router's current status.
+
+A report with findings puts the server in degraded mode; a clean one lifts it. See
+[`crate::integrity`]."#]
+pub async fn check_integrity(
+    db: &Db,
+    state: &IntegrityState,
+    kind: IntegrityCheck,
+    now: DateTime<Utc>,
+) -> Result<IntegrityReport, Error> {
+    let report = crate::integrity::check(db, kind, now).await?;
+    if !report.ok {
+        tracing::error!(problems = ?report.problems, "database corruption detected");
+    }
+    state.record(report.clone());
+    Ok(report)
+}
+
+#[doc = r#"Run maintenance job `name` immediately."#]
+pub async fn run_job_now(db: &Db, time: &TimeContext, name: &str) -> Result<JobRun, Error> {
+    let job = Job::from_str(name).map_err(|_| Error::NotFound)?;
+    let id = jobs::run_job(db, job, time.now.naive_utc()).await?;
+    repository::find_job_run(db, id)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+#[doc = r#"Status of every registered schema change (see [`crate::schema_change`])."#]
+pub async fn list_schema_changes(db: &Db) -> Result<Vec<SchemaChangeStatus>, Error> {
+    let mut out = Vec::with_capacity(schema_change::CHANGES.len());
+    for change in schema_change::CHANGES {
+        out.push(SchemaChangeStatus::load(db, change).await?);
+    }
+    Ok(out)
+}
+
+#[doc = r#"Switch reads of schema change `name` to its new column.
+
+Idempotent once switched.
+
+# Errors
+
+- [`Error::NotFound`] for an unknown change.
+- [`Error::Domain`] while the backfill has not finished.
+"#]
+pub async fn switch_schema_change(db: &Db, name: &str) -> Result<SchemaChangeStatus, Error> {
+    let change = schema_change::find(name).ok_or(Error::NotFound)?;
+    let mut status = SchemaChangeStatus::load(db, change).await?;
+    match status.phase {
+        SchemaPhase::ReadNew => {}
+        SchemaPhase::Backfilled if status.pending_rows == 0 => {
+            repository::set_schema_phase(db, change.name, SchemaPhase::ReadNew.name()).await?;
+            status.phase = SchemaPhase::ReadNew;
+        }
+        _ => {
+            return Err(Error::invalid(format!(
+                "backfill of {name} has not finished ({} rows pending)",
+                status.pending_rows
+            )));
+        }
+    }
+    Ok(status)
+}
+
+/// Page size of [`list_audit_log`] when no `limit` is given.
+pub const DEFAULT_AUDIT_PAGE: i64 = 100;
+
+/// Largest page [`list_audit_log`] returns.
+pub const MAX_AUDIT_PAGE: i64 = 1000;
+
+#[doc = r#"One page of audit log entries matching `query`, newest first.
+
+# Errors
+
+Returns [`Error::Domain`] for a `limit` outside 1..=1000 or `from` after `to`.
+"#]
+pub async fn list_audit_log(db: &Db, query: &AuditQuery) -> Result<AuditPage, Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_PAGE);
+    if !(1..=MAX_AUDIT_PAGE).contains(&limit) {
+        return Err(Error::invalid(format!(
+            "limit must be between 1 and {MAX_AUDIT_PAGE}"
+        )));
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(Error::invalid("from must be <= to"));
+    }
+    // Fetch one extra row to learn whether another page follows.
+    let mut entries = repository::list_audit_entries(db, query, limit + 1).await?;
+    let next_cursor = if entries.len() as i64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(|e| e.id)
+    } else {
+        None
+    };
+    Ok(AuditPage {
+        entries,
+        next_cursor,
+    })
+}
+
+#[doc = r#"Validate and save the sleep goal."#]
+pub async fn set_sleep_goal(
+    db: &Db,
+    events: &EventBus,
+    goal: SleepGoal,
+) -> Result<SleepGoal, Error> {
+    goal.validate()?;
+    repository::set_sleep_goal(db, &goal).await?;
+    events.emit(DomainEvent::SettingChanged { key: "sleep_goal" });
+    Ok(goal)
+}
+
+#[doc = r#"Validate and save what the public summary exposes."#]
+pub async fn set_public_summary_settings(
+    db: &Db,
+    events: &EventBus,
+    settings: PublicSummarySettings,
+) -> Result<PublicSummarySettings, Error> {
+    settings.validate()?;
+    repository::set_public_summary_settings(db, &settings).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "public_summary",
+    });
+    Ok(settings)
+}
+
+#[doc = r#"Validate and save the redaction policy of each audience."#]
+pub async fn set_redaction_settings(
+    db: &Db,
+    events: &EventBus,
+    settings: RedactionSettings,
+) -> Result<RedactionSettings, Error> {
+    settings.validate()?;
+    repository::set_redaction_settings(db, &settings).await?;
+    events.emit(DomainEvent::SettingChanged { key: "redaction" });
+    Ok(settings)
+}
+
+#[doc = r#"Validate and save the alert rules evaluated by the nightly alert job."#]
+pub async fn set_alert_rules(
+    db: &Db,
+    events: &EventBus,
+    rules: AlertRules,
+) -> Result<AlertRules, Error> {
+    rules.validate()?;
+    repository::set_alert_rules(db, &rules).await?;
+    events.emit(DomainEvent::SettingChanged { key: "alert_rules" });
+    Ok(rules)
+}
+
+/// Alerts returned by [`list_alert_history`] when no `limit` is given.
+const DEFAULT_ALERT_HISTORY: i64 = 50;
+/// Largest accepted `limit` of [`list_alert_history`].
+const MAX_ALERT_HISTORY: i64 = 500;
+
+#[doc = r#"List fired alerts, newest first.
+
+# Errors
+
+Returns [`Error::Domain`] for a `limit` outside 1..=500.
+"#]
+pub async fn list_alert_history(
+    db: &Db,
+    query: &AlertHistoryQuery,
+) -> Result<Vec<AlertEvent>, Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_ALERT_HISTORY);
+    if !(1..=MAX_ALERT_HISTORY).contains(&limit) {
+        return Err(Error::invalid(format!(
+            "limit must be between 1 and {MAX_ALERT_HISTORY}"
+        )));
+    }
+    repository::list_alert_events(db, limit).await
 }
 
-pub async fn create_note(db: &Db, input: NoteInput) -> Result<i64, ApiError> {
+#[doc = r#"Validate and save when the logical day starts.
+
+Only affects entries assigned to days afterwards; existing entries keep their date.
+"#]
+pub async fn set_day_boundary(
+    db: &Db,
+    events: &EventBus,
+    boundary: DayBoundary,
+) -> Result<DayBoundary, Error> {
+    boundary.validate()?;
+    repository::set_day_boundary(db, &boundary).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "day_boundary",
+    });
+    Ok(boundary)
+}
+
+#[doc = r#"Validate and save the bed/wake time rounding of trends and exports.
+
+Stored sessions are not touched; only what is shown and aggregated changes.
+"#]
+pub async fn set_time_rounding(
+    db: &Db,
+    events: &EventBus,
+    rounding: TimeRounding,
+) -> Result<TimeRounding, Error> {
+    rounding.validate()?;
+    repository::set_time_rounding(db, &rounding).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "time_rounding",
+    });
+    Ok(rounding)
+}
+
+#[doc = r#"Validate and save the exercise intensity levels.
+
+Recorded events keep their level even when it is removed; it then ranks below every listed
+level in the per-day maximum.
+"#]
+pub async fn set_intensity_levels(
+    db: &Db,
+    events: &EventBus,
+    levels: IntensityLevels,
+) -> Result<IntensityLevels, Error> {
+    levels.validate()?;
+    repository::set_intensity_levels(db, &levels).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "intensity_levels",
+    });
+    Ok(levels)
+}
+
+#[doc = r#"Validate and save the routine checklist (labels trimmed)."#]
+pub async fn set_routine_checklist(
+    db: &Db,
+    events: &EventBus,
+    checklist: RoutineChecklist,
+) -> Result<RoutineChecklist, Error> {
+    let checklist = RoutineChecklist {
+        items: checklist
+            .items
+            .into_iter()
+            .map(|item| RoutineItem {
+                label: item.label.trim().to_string(),
+                ..item
+            })
+            .collect(),
+    };
+    checklist.validate()?;
+    repository::set_routine_checklist(db, &checklist).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "routine_checklist",
+    });
+    Ok(checklist)
+}
+
+#[doc = r#"Record the mood rating of `input.date`, replacing any earlier one."#]
+pub async fn record_mood(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    input: MoodInput,
+) -> Result<(), Error> {
     input.validate()?;
-    Ok(repository::insert_note(db, &input).await?)
+    lock.check(input.date)?;
+    repository::upsert_mood(db, &input).await?;
+    events.emit(DomainEvent::MoodRecorded { date: input.date });
+    Ok(())
+}
+
+#[doc = r#"Record which checklist items were done on the evening of `date`."#]
+pub async fn record_routine(
+    db: &Db,
+    events: &EventBus,
+    lock: &EditLock,
+    date: NaiveDate,
+    input: RoutineInput,
+) -> Result<(), Error> {
+    lock.check(date)?;
+    let checklist = repository::get_routine_checklist(db).await;
+    if let Some(unknown) = input
+        .done
+        .iter()
+        .find(|id| !checklist.items.iter().any(|item| &item.id == *id))
+    {
+        return Err(Error::invalid(format!("unknown routine item: {unknown}")));
+    }
+    let entries: Vec<RoutineEntry> = checklist
+        .items
+        .iter()
+        .map(|item| RoutineEntry {
+            item_id: item.id.clone(),
+            done: input.done.contains(&item.id),
+        })
+        .collect();
+    repository::replace_routine_entries(db, date, &entries).await?;
+    events.emit(DomainEvent::RoutineRecorded { date });
+    Ok(())
 }
 
-pub async fn set_user_timezone(db: &Db, timezone: String) -> Result<(), ApiError> {
-    let tz = Tz::from_str(timezone.trim())
-        .map_err(|_| ApiError::InvalidInput("invalid timezone".into()))?;
+#[doc = r#"Save the user timezone (IANA name)."#]
+pub async fn set_user_timezone(db: &Db, events: &EventBus, timezone: String) -> Result<(), Error> {
+    let tz = Tz::from_str(timezone.trim()).map_err(|_| Error::invalid("invalid timezone"))?;
     repository::set_user_timezone(db, tz.name()).await?;
+    events.emit(DomainEvent::SettingChanged { key: "timezone" });
+    Ok(())
+}
+
+#[doc = r#"Save the default response locale (`en` or `ja`)."#]
+pub async fn set_locale(db: &Db, events: &EventBus, locale: &str) -> Result<(), Error> {
+    let locale = locale.parse().map_err(Error::invalid)?;
+    repository::set_locale(db, locale).await?;
+    events.emit(DomainEvent::SettingChanged { key: "locale" });
     Ok(())
 }
 
+#[doc = r#"Save the duration unit preference (`hours` or `minutes`)."#]
+pub async fn set_duration_unit(db: &Db, events: &EventBus, units: &str) -> Result<(), Error> {
+    let unit = units.parse().map_err(Error::invalid)?;
+    repository::set_duration_unit(db, unit).await?;
+    events.emit(DomainEvent::SettingChanged {
+        key: "duration_unit",
+    });
+    Ok(())
+}
+
+#[doc = r#"The saved user timezone name."#]
 pub async fn get_user_timezone(db: &Db) -> String {
     let tz = repository::get_user_timezone(db).await;
     tz.name().to_string()
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum FrictionProposalConfidence {
     High,
@@ -116,7 +2311,7 @@ impl FrictionProposalConfidence {
     }
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 pub struct FrictionProposalEvidence {
     pub current_occurrences: i64,
     pub prior_occurrences: i64,
@@ -130,7 +2325,7 @@ pub struct FrictionProposalEvidence {
     pub prior_follow_up_failure_rate: f64,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 pub struct FrictionBacklogProposal {
     pub rank: usize,
     pub action_key: String,
@@ -143,14 +2338,14 @@ pub struct FrictionBacklogProposal {
     pub auto_promoted: bool,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 pub struct FrictionBacklogWindow {
     pub from: NaiveDate,
     pub to: NaiveDate,
     pub submit_count: i64,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 pub struct FrictionBacklogResponse {
     pub as_of: NaiveDate,
     pub window_days: i64,
@@ -160,63 +2355,78 @@ pub struct FrictionBacklogResponse {
     pub proposals: Vec<FrictionBacklogProposal>,
 }
 
-fn validate_friction_input(input: &FrictionTelemetryInput) -> Result<(), ApiError> {
+fn validate_friction_input(input: &FrictionTelemetryInput) -> Result<(), Error> {
     if input.form_time_ms < 0 {
-        return Err(ApiError::InvalidInput(
-            "form_time_ms must be >= 0".to_string(),
-        ));
+        return Err(Error::invalid("form_time_ms must be >= 0".to_string()));
     }
     if input.retry_count < 0 {
-        return Err(ApiError::InvalidInput(
-            "retry_count must be >= 0".to_string(),
-        ));
+        return Err(Error::invalid("retry_count must be >= 0".to_string()));
     }
     Ok(())
 }
 
+/// Successful friction submissions seen since start, for 1-in-N sampling.
+static FRICTION_SUCCESSES: AtomicU64 = AtomicU64::new(0);
+
+#[doc = r#"Append one friction telemetry event and return its id.
+
+Submissions with an `error_kind` or a `follow_up_failure` are always stored. Of the successful
+ones, only 1 in `TELEMETRY_SUCCESS_SAMPLE_RATE` is stored, with that rate as its
+`sample_weight` so aggregates stay unbiased; the others return `Ok(None)`.
+"#]
 pub async fn create_friction_telemetry(
     db: &Db,
     mut input: FrictionTelemetryInput,
-) -> Result<i64, ApiError> {
+) -> Result<Option<i64>, Error> {
     validate_friction_input(&input)?;
     input.error_kind = input
         .error_kind
         .map(|value| value.trim().to_lowercase())
         .filter(|value| !value.is_empty());
-    Ok(repository::insert_friction_telemetry(db, &input).await?)
+    let mut sample_weight = 1;
+    if input.error_kind.is_none() && !input.follow_up_failure {
+        sample_weight = config::telemetry_success_sample_rate();
+        let seen = FRICTION_SUCCESSES.fetch_add(1, Ordering::Relaxed);
+        if !seen.is_multiple_of(u64::from(sample_weight)) {
+            return Ok(None);
+        }
+    }
+    repository::insert_friction_telemetry(db, &input, sample_weight)
+        .await
+        .map(Some)
 }
 
-fn start_of_day(date: NaiveDate) -> Result<NaiveDateTime, ApiError> {
+fn start_of_day(date: NaiveDate) -> Result<NaiveDateTime, Error> {
     date.and_hms_opt(0, 0, 0)
-        .ok_or_else(|| ApiError::InvalidInput("invalid date range".into()))
+        .ok_or_else(|| Error::invalid("invalid date range"))
 }
 
-fn end_of_day(date: NaiveDate) -> Result<NaiveDateTime, ApiError> {
+fn end_of_day(date: NaiveDate) -> Result<NaiveDateTime, Error> {
     date.and_hms_opt(23, 59, 59)
-        .ok_or_else(|| ApiError::InvalidInput("invalid date range".into()))
+        .ok_or_else(|| Error::invalid("invalid date range"))
 }
 
+#[doc = r#"Rank friction proposals over the `window_days` ending at `to` (default: today, UTC)."#]
 pub async fn friction_backlog(
     db: &Db,
+    time: &TimeContext,
     window_days: i64,
     to: Option<NaiveDate>,
-) -> Result<FrictionBacklogResponse, ApiError> {
+) -> Result<FrictionBacklogResponse, Error> {
     if !(1..=365).contains(&window_days) {
-        return Err(ApiError::InvalidInput(
-            "window_days must be between 1 and 365".into(),
-        ));
+        return Err(Error::invalid("window_days must be between 1 and 365"));
     }
 
-    let as_of = to.unwrap_or_else(|| Utc::now().date_naive());
+    let as_of = to.unwrap_or_else(|| time.now.date_naive());
     let current_from = as_of
         .checked_sub_signed(ChronoDuration::days(window_days - 1))
-        .ok_or_else(|| ApiError::InvalidInput("invalid date range".into()))?;
+        .ok_or_else(|| Error::invalid("invalid date range"))?;
     let prior_to = current_from
         .pred_opt()
-        .ok_or_else(|| ApiError::InvalidInput("invalid date range".into()))?;
+        .ok_or_else(|| Error::invalid("invalid date range"))?;
     let prior_from = prior_to
         .checked_sub_signed(ChronoDuration::days(window_days - 1))
-        .ok_or_else(|| ApiError::InvalidInput("invalid date range".into()))?;
+        .ok_or_else(|| Error::invalid("invalid date range"))?;
 
     let current_agg =
         repository::aggregate_friction_window(db, start_of_day(current_from)?, end_of_day(as_of)?)
@@ -401,11 +2611,121 @@ mod tests {
             latency_min: 10,
             awakenings: 1,
             quality: Quality(4),
+            wake_feeling: None,
+            sleep_inertia_min: None,
+            waso_min: None,
+            aids: Vec::new(),
+            tags: Vec::new(),
+            timezone: None,
         };
-        let id = create_sleep(&db, input.clone()).await.unwrap();
+        let events = EventBus::new();
+        let mut rx = events.subscribe();
+        let id = create_sleep(
+            &db,
+            &events,
+            &TimeContext::from_clock(&crate::time::SystemClock),
+            &EditLock::none(),
+            input.clone(),
+        )
+        .await
+        .unwrap();
         let fetched = get_sleep_by_date(&db, input.date).await.unwrap();
         assert_eq!(fetched.len(), 1);
         assert_eq!(fetched[0].id, id);
         assert_eq!(fetched[0].bed_time, input.bed_time);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DomainEvent::SleepCreated {
+                id,
+                date: input.date,
+                duration_min: 60
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_mutations_emit_nothing() {
+        let db = setup().await;
+        let events = EventBus::new();
+        let mut rx = events.subscribe();
+        assert_eq!(
+            delete_sleep(&db, &events, &EditLock::none(), 42, &AuditReason::default())
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(
+            set_user_timezone(&db, &events, "Mars/Olympus".into())
+                .await
+                .is_err()
+        );
+        set_user_timezone(&db, &events, "Asia/Tokyo".into())
+            .await
+            .unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            DomainEvent::SettingChanged { key: "timezone" }
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_edit_lock_protects_old_entries() {
+        let db = setup().await;
+        let events = EventBus::new();
+        let time = TimeContext::fixed(
+            "2025-06-30T12:00:00Z".parse().unwrap(),
+            chrono_tz::Asia::Tokyo,
+        );
+        let lock = EditLock::for_window(&db, &time, Some(14)).await;
+        assert_eq!(lock.cutoff, NaiveDate::from_ymd_opt(2025, 6, 16));
+        assert_eq!(
+            EditLock::for_window(&db, &time, None).await,
+            EditLock::none()
+        );
+
+        let note = |day| NoteInput {
+            date: NaiveDate::from_ymd_opt(2025, 6, day).unwrap(),
+            body: Some("x".into()),
+            tags: Vec::new(),
+            session_id: None,
+        };
+        let err = create_note(&db, &events, &lock, note(15))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Forbidden(m) if m.contains("2025-06-16")),
+            "{err:?}"
+        );
+        create_note(&db, &events, &lock, note(16)).await.unwrap();
+
+        // Existing old entries cannot be moved into the window or deleted.
+        let old = DisturbanceInput {
+            date: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+            time: chrono::NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+            kind: crate::models::DisturbanceKind::Noise,
+            duration_min: 5,
+        };
+        let id = create_disturbance(&db, &events, &EditLock::none(), old.clone())
+            .await
+            .unwrap();
+        let moved = DisturbanceInput {
+            date: NaiveDate::from_ymd_opt(2025, 6, 29).unwrap(),
+            ..old
+        };
+        assert!(matches!(
+            update_disturbance(&db, &events, &lock, id, moved).await,
+            Err(Error::Forbidden(_))
+        ));
+        assert!(matches!(
+            delete_disturbance(&db, &events, &lock, id, &AuditReason::default()).await,
+            Err(Error::Forbidden(_))
+        ));
+        assert_eq!(
+            delete_disturbance(&db, &events, &EditLock::none(), id, &AuditReason::default())
+                .await
+                .unwrap(),
+            1
+        );
     }
 }
@@ -0,0 +1,356 @@
+#![doc = r#"Localized response text
+
+Report endpoints and insight strings (bedtime recommendations, population-context
+messages, comparison warnings) are rendered through [Fluent] resources bundled in the crate
+under `locales/{en,ja}.ftl`.
+
+The locale for a request is chosen by the [`Lang`] extractor:
+1. the best supported match in `Accept-Language`, else
+2. the saved default (`GET/POST /api/settings/locale`), else
+3. English.
+
+Durations in text follow the [`DurationUnit`] preference (`GET/POST /api/settings/units`),
+read by the [`Units`] extractor. With `hours`, duration-bearing responses also carry a
+`duration_hours` float next to `duration_min` (see [`duration_hours`]).
+
+Bed and wake times in trends and exports follow the [`TimeRounding`] preference
+(`GET/POST /api/settings/time-rounding`), read by the [`Rounding`] extractor.
+
+Numbers are formatted by the caller and passed as strings, so output does not depend on
+Fluent's number formatting.
+
+[Fluent]: https://projectfluent.org/
+[`TimeRounding`]: crate::models::TimeRounding
+"#]
+
+use crate::db::Db;
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use fluent_bundle::{FluentArgs, FluentResource, concurrent::FluentBundle};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[doc = r#"Supported response locale."#]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Ja];
+
+    /// BCP 47 tag, e.g. `ja`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+
+    fn from_primary_subtag(tag: &str) -> Option<Locale> {
+        let primary = tag.split(['-', '_']).next().unwrap_or(tag);
+        Locale::ALL
+            .into_iter()
+            .find(|l| l.tag().eq_ignore_ascii_case(primary))
+    }
+
+    #[doc = r#"Pick the best supported locale from an `Accept-Language` header value.
+
+Entries are ranked by `q` (default 1, ties keep header order); `q=0` and `*` are ignored.
+
+# Example
+
+```rust
+# use sleep_api::i18n::Locale;
+assert_eq!(Locale::negotiate("fr-FR, ja-JP;q=0.8, en;q=0.5"), Some(Locale::Ja));
+assert_eq!(Locale::negotiate("de, *;q=0.1"), None);
+```
+"#]
+    pub fn negotiate(accept_language: &str) -> Option<Locale> {
+        let mut ranked: Vec<(f32, Locale)> = accept_language
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = parts.next()?.trim();
+                let q = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                if q <= 0.0 {
+                    return None;
+                }
+                Some((q, Locale::from_primary_subtag(tag)?))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.first().map(|(_, l)| *l)
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Locale::ALL
+            .into_iter()
+            .find(|l| l.tag().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unsupported locale {s:?}; expected en or ja"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[doc = r#"Preferred unit for durations in report text and responses."#]
+pub enum DurationUnit {
+    /// `1h 05m`, `6.4 h`; responses include `duration_hours`.
+    #[default]
+    Hours,
+    /// `65 min`; responses carry `duration_min` only.
+    Minutes,
+}
+
+impl DurationUnit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DurationUnit::Hours => "hours",
+            DurationUnit::Minutes => "minutes",
+        }
+    }
+}
+
+impl std::str::FromStr for DurationUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hours" => Ok(DurationUnit::Hours),
+            "minutes" => Ok(DurationUnit::Minutes),
+            _ => Err(format!(
+                "unsupported units {s:?}; expected hours or minutes"
+            )),
+        }
+    }
+}
+
+#[doc = r#"`duration_hours` companion for a `duration_min` value: hours rounded to 2 decimals,
+or `None` unless the preference is [`DurationUnit::Hours`].
+
+# Example
+
+```rust
+# use sleep_api::i18n::{DurationUnit, duration_hours};
+assert_eq!(duration_hours(DurationUnit::Hours, Some(450)), Some(7.5));
+assert_eq!(duration_hours(DurationUnit::Hours, Some(400)), Some(6.67));
+assert_eq!(duration_hours(DurationUnit::Minutes, Some(450)), None);
+```
+"#]
+pub fn duration_hours(unit: DurationUnit, min: Option<i32>) -> Option<f64> {
+    match unit {
+        DurationUnit::Hours => min.map(|m| (f64::from(m) / 60.0 * 100.0).round() / 100.0),
+        DurationUnit::Minutes => None,
+    }
+}
+
+fn bundle(locale: Locale) -> &'static FluentBundle<FluentResource> {
+    static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+    let bundles = BUNDLES.get_or_init(|| {
+        Locale::ALL
+            .into_iter()
+            .map(|l| {
+                let source = match l {
+                    Locale::En => include_str!("../locales/en.ftl"),
+                    Locale::Ja => include_str!("../locales/ja.ftl"),
+                };
+                let resource =
+                    FluentResource::try_new(source.to_string()).unwrap_or_else(|(res, errors)| {
+                        tracing::error!(locale = %l, ?errors, "invalid Fluent resource");
+                        res
+                    });
+                let langid = l.tag().parse().unwrap_or_default();
+                let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+                // Plain output; no Unicode isolation marks around placeables.
+                bundle.set_use_isolating(false);
+                if let Err(errors) = bundle.add_resource(resource) {
+                    tracing::error!(locale = %l, ?errors, "duplicate Fluent messages");
+                }
+                bundle
+            })
+            .collect()
+    });
+    &bundles[Locale::ALL.iter().position(|l| *l == locale).unwrap_or(0)]
+}
+
+#[doc = r#"Render message `id` in `locale` with string arguments.
+
+Falls back to English, then to the message id itself, when a message is missing.
+
+# Example
+
+```rust
+# use sleep_api::i18n::{Locale, tr};
+assert_eq!(tr(Locale::En, "duration-m", &[("minutes", "25".into())]), "25m");
+assert_eq!(tr(Locale::Ja, "duration-m", &[("minutes", "25".into())]), "25分");
+```
+"#]
+pub fn tr(locale: Locale, id: &str, args: &[(&str, String)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (k, v) in args {
+        fluent_args.set(*k, v.clone());
+    }
+    for l in [locale, Locale::En] {
+        let bundle = bundle(l);
+        if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+            let mut errors = vec![];
+            let out = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+            if !errors.is_empty() {
+                tracing::warn!(%id, locale = %l, ?errors, "Fluent formatting errors");
+            }
+            return out.into_owned();
+        }
+    }
+    tracing::warn!(%id, "missing Fluent message");
+    id.to_string()
+}
+
+#[doc = r#"Format a minute count in the preferred unit.
+
+`Hours` gives `1h 05m` / `25m` (or `1時間05分` / `25分`); `Minutes` gives `65 min` (`65分`).
+"#]
+pub fn fmt_minutes(locale: Locale, unit: DurationUnit, min: i64) -> String {
+    if unit == DurationUnit::Hours && min >= 60 {
+        tr(
+            locale,
+            "duration-hm",
+            &[
+                ("hours", (min / 60).to_string()),
+                ("minutes", format!("{:02}", min % 60)),
+            ],
+        )
+    } else if unit == DurationUnit::Hours {
+        tr(locale, "duration-m", &[("minutes", min.to_string())])
+    } else {
+        tr(locale, "duration-total-m", &[("minutes", min.to_string())])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Request locale negotiated from `Accept-Language`, falling back to the saved default.
+
+Never rejects; unsupported or missing headers use the settings default.
+"#]
+pub struct Lang(pub Locale);
+
+impl<S> FromRequestParts<S> for Lang
+where
+    Db: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let header_locale = parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(Locale::negotiate);
+        Ok(Lang(match header_locale {
+            Some(l) => l,
+            None => crate::repository::get_locale(&Db::from_ref(state)).await,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Saved [`DurationUnit`] preference for the request. Never rejects."#]
+pub struct Units(pub DurationUnit);
+
+impl<S> FromRequestParts<S> for Units
+where
+    Db: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Units(
+            crate::repository::get_duration_unit(&Db::from_ref(state)).await,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Saved [`TimeRounding`](crate::models::TimeRounding) preference for the request. Never
+rejects."#]
+pub struct Rounding(pub crate::models::TimeRounding);
+
+impl<S> FromRequestParts<S> for Rounding
+where
+    Db: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Rounding(
+            crate::repository::get_time_rounding(&Db::from_ref(state)).await,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_ids(source: &str) -> Vec<&str> {
+        let mut ids: Vec<&str> = source
+            .lines()
+            .filter(|l| l.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|l| l.split_once(" =").map(|(id, _)| id))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn locales_define_the_same_messages() {
+        assert_eq!(
+            message_ids(include_str!("../locales/en.ftl")),
+            message_ids(include_str!("../locales/ja.ftl"))
+        );
+    }
+
+    #[test]
+    fn negotiate_ranks_by_quality() {
+        assert_eq!(Locale::negotiate("ja"), Some(Locale::Ja));
+        assert_eq!(
+            Locale::negotiate("en-US,en;q=0.9,ja;q=0.8"),
+            Some(Locale::En)
+        );
+        assert_eq!(Locale::negotiate("ja;q=0.2, EN-gb;q=0.7"), Some(Locale::En));
+        assert_eq!(Locale::negotiate("ja;q=0, fr"), None);
+        assert_eq!(Locale::negotiate(""), None);
+    }
+
+    #[test]
+    fn selectors_and_durations() {
+        assert_eq!(fmt_minutes(Locale::En, DurationUnit::Hours, 65), "1h 05m");
+        assert_eq!(
+            fmt_minutes(Locale::Ja, DurationUnit::Hours, 65),
+            "1時間05分"
+        );
+        assert_eq!(fmt_minutes(Locale::En, DurationUnit::Minutes, 65), "65 min");
+        assert_eq!(fmt_minutes(Locale::Ja, DurationUnit::Minutes, 65), "65分");
+        assert_eq!(tr(Locale::Ja, "no-such-message", &[]), "no-such-message");
+    }
+}
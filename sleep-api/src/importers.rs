@@ -0,0 +1,1078 @@
+#![doc = r#"Third-party data importers
+
+Parsers that normalize exports from external services into crate input types.
+Parsing is pure (no I/O); persistence is handled by [`repository`].
+
+Supported weight sources:
+- Withings: the `weight.csv` file from the Withings data export
+  (`Date,"Weight (kg)","Fat mass (kg)",...`).
+- Fitbit: the Web API body log response (`GET /1/user/-/body/log/weight/...`,
+  `{"weight":[{"date":"2025-06-01","time":"07:10:00","weight":72.4,"fat":18.5}]}`), metric units.
+
+When a source reports several readings for the same date, the earliest reading of
+the day is kept so that morning weigh-ins stay comparable.
+
+Pushed (webhook) sources, see [`IngestSource`]:
+- Health Auto Export: the REST API automation body (`{"data":{"metrics":[...],"workouts":[...]}}`).
+  The `sleep_analysis` metric becomes sleep sessions and workouts become timed exercise; a
+  workout's `id` becomes its [`ExternalRef`].
+- Tasker: a JSON body built in the task, `{"sleep":[SleepInput...],"exercise":[ExerciseInput...]}`.
+  Each entry may carry an `external_ref` (e.g. the Fitbit log or Strava activity it relays).
+- Strava: a JSON array of activities (`GET /api/v3/activities/{id}` bodies), each with the
+  activity's `GET /api/v3/activities/{id}/zones` response added as `zones`. Strava webhooks only
+  announce an activity id, so a relay fetches both and forwards them.
+- Garmin: a JSON array of Garmin Connect activity summaries (as listed by the activity search),
+  whose `hrTimeInZone_1..5` hold seconds per heart-rate zone.
+
+Strava and Garmin activities become timed exercise with [`HrZoneMinutes`]; an activity is
+`hard` when at least a quarter of its zone time is in Z4–Z5, `light` otherwise. Their activity
+ids become [`ExternalRef`]s.
+
+Personal spreadsheets of past sleep are read with a caller-supplied [`ColumnMapping`]
+(see [`parse_mapped_csv`]), so no converter is needed per spreadsheet layout.
+
+[`repository`]: crate::repository
+[`ExternalRef`]: crate::models::ExternalRef
+[`HrZoneMinutes`]: crate::models::HrZoneMinutes
+"#]
+
+use crate::domain::DomainError;
+use crate::models::{
+    BodyMetricInput, DayBoundary, ExerciseInput, ExternalRef, HrZoneMinutes, Intensity, Quality,
+    SleepInput,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[doc = r#"External source of body metrics readings.
+
+Parses from the lowercase source name (`"withings"` or `"fitbit"`).
+"#]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightSource {
+    Withings,
+    Fitbit,
+}
+
+impl WeightSource {
+    #[doc = r#"Return the lowercase source name stored alongside imported rows."#]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WeightSource::Withings => "withings",
+            WeightSource::Fitbit => "fitbit",
+        }
+    }
+}
+
+impl FromStr for WeightSource {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "withings" => Ok(WeightSource::Withings),
+            "fitbit" => Ok(WeightSource::Fitbit),
+            other => Err(DomainError::InvalidInput(format!(
+                "unsupported import source: {other}"
+            ))),
+        }
+    }
+}
+
+#[doc = r#"Parse a weight export from `source` into one validated reading per date.
+
+# Example
+
+```rust
+# use sleep_api::domain::DomainError;
+# use sleep_api::importers::{parse_weight_export, WeightSource};
+# fn main() -> Result<(), DomainError> {
+let csv = "Date,\"Weight (kg)\",\"Fat mass (kg)\"\n\"2025-06-01 07:10:00\",72.0,14.4\n";
+let readings = parse_weight_export(WeightSource::Withings, csv)?;
+assert_eq!(readings.len(), 1);
+assert_eq!(readings[0].body_fat_pct, Some(20.0));
+# Ok(()) }
+```
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when the payload cannot be parsed or a reading
+fails [`BodyMetricInput::validate`].
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+[`BodyMetricInput::validate`]: crate::models::BodyMetricInput::validate
+"#]
+pub fn parse_weight_export(
+    source: WeightSource,
+    payload: &str,
+) -> Result<Vec<BodyMetricInput>, DomainError> {
+    let readings = match source {
+        WeightSource::Withings => parse_withings_csv(payload)?,
+        WeightSource::Fitbit => parse_fitbit_json(payload)?,
+    };
+
+    // Keep the earliest reading per date.
+    let mut by_date: BTreeMap<NaiveDate, (NaiveDateTime, BodyMetricInput)> = BTreeMap::new();
+    for (at, reading) in readings {
+        match by_date.get(&reading.date) {
+            Some((existing_at, _)) if *existing_at <= at => {}
+            _ => {
+                by_date.insert(reading.date, (at, reading));
+            }
+        }
+    }
+
+    let out: Vec<BodyMetricInput> = by_date.into_values().map(|(_, r)| r).collect();
+    for reading in &out {
+        reading.validate()?;
+    }
+    Ok(out)
+}
+
+fn parse_withings_csv(payload: &str) -> Result<Vec<(NaiveDateTime, BodyMetricInput)>, DomainError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(payload.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| DomainError::InvalidInput(format!("invalid withings csv: {e}")))?
+        .clone();
+    let col = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let date_idx = col("Date")
+        .ok_or_else(|| DomainError::InvalidInput("withings csv missing Date column".into()))?;
+    let weight_idx = col("Weight (kg)").ok_or_else(|| {
+        DomainError::InvalidInput("withings csv missing Weight (kg) column".into())
+    })?;
+    let fat_idx = col("Fat mass (kg)");
+
+    let mut out = Vec::new();
+    for (line, record) in reader.records().enumerate() {
+        let record =
+            record.map_err(|e| DomainError::InvalidInput(format!("invalid withings csv: {e}")))?;
+        let row = line + 2;
+        let at = NaiveDateTime::parse_from_str(
+            record.get(date_idx).unwrap_or("").trim(),
+            "%Y-%m-%d %H:%M:%S",
+        )
+        .map_err(|_| DomainError::InvalidInput(format!("row {row}: invalid Date")))?;
+        let weight_kg = parse_optional_f64(record.get(weight_idx), row, "Weight (kg)")?;
+        let fat_kg = match fat_idx {
+            Some(i) => parse_optional_f64(record.get(i), row, "Fat mass (kg)")?,
+            None => None,
+        };
+        if weight_kg.is_none() && fat_kg.is_none() {
+            continue;
+        }
+        let body_fat_pct = match (weight_kg, fat_kg) {
+            (Some(w), Some(f)) if w > 0.0 => Some(f / w * 100.0),
+            _ => None,
+        };
+        out.push((
+            at,
+            BodyMetricInput {
+                date: at.date(),
+                weight_kg,
+                body_fat_pct,
+            },
+        ));
+    }
+    Ok(out)
+}
+
+fn parse_optional_f64(
+    value: Option<&str>,
+    row: usize,
+    field: &str,
+) -> Result<Option<f64>, DomainError> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(v) => v
+            .parse::<f64>()
+            .map(Some)
+            .map_err(|_| DomainError::InvalidInput(format!("row {row}: invalid {field}"))),
+    }
+}
+
+#[derive(Deserialize)]
+struct FitbitWeightLog {
+    weight: Vec<FitbitWeightEntry>,
+}
+
+#[derive(Deserialize)]
+struct FitbitWeightEntry {
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+    weight: Option<f64>,
+    fat: Option<f64>,
+}
+
+fn parse_fitbit_json(payload: &str) -> Result<Vec<(NaiveDateTime, BodyMetricInput)>, DomainError> {
+    let log: FitbitWeightLog = serde_json::from_str(payload)
+        .map_err(|e| DomainError::InvalidInput(format!("invalid fitbit weight log: {e}")))?;
+    Ok(log
+        .weight
+        .into_iter()
+        .filter(|e| e.weight.is_some() || e.fat.is_some())
+        .map(|e| {
+            let at = NaiveDateTime::new(e.date, e.time.unwrap_or(NaiveTime::MIN));
+            (
+                at,
+                BodyMetricInput {
+                    date: e.date,
+                    weight_kg: e.weight,
+                    body_fat_pct: e.fat,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Quality recorded for pushed sleep, which carries no rating; edit the entry to change it.
+pub const INGEST_DEFAULT_QUALITY: u8 = 3;
+
+/// Workouts at or above this intensity (kcal/hr·kg, i.e. METs) are recorded as `hard`.
+const HARD_WORKOUT_METS: f64 = 6.0;
+
+#[doc = r#"Source of pushed (webhook) payloads for `POST /api/ingest/{source}`.
+
+Parses from the path segment (`"health-auto-export"`, `"tasker"`, `"strava"` or `"garmin"`).
+"#]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestSource {
+    HealthAutoExport,
+    Tasker,
+    Strava,
+    Garmin,
+}
+
+impl IngestSource {
+    #[doc = r#"Return the path segment naming this source."#]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IngestSource::HealthAutoExport => "health-auto-export",
+            IngestSource::Tasker => "tasker",
+            IngestSource::Strava => "strava",
+            IngestSource::Garmin => "garmin",
+        }
+    }
+}
+
+impl FromStr for IngestSource {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "health-auto-export" => Ok(IngestSource::HealthAutoExport),
+            "tasker" => Ok(IngestSource::Tasker),
+            "strava" => Ok(IngestSource::Strava),
+            "garmin" => Ok(IngestSource::Garmin),
+            other => Err(DomainError::InvalidInput(format!(
+                "unsupported ingest source: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[doc = r#"One pushed entry with the reference to its origin, when the source provides one."#]
+pub struct IngestEntry<T> {
+    #[serde(flatten)]
+    pub input: T,
+    #[serde(default)]
+    pub external_ref: Option<ExternalRef>,
+}
+
+impl<T> From<T> for IngestEntry<T> {
+    fn from(input: T) -> Self {
+        IngestEntry {
+            input,
+            external_ref: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[doc = r#"Sleep and exercise entries normalized from one pushed payload."#]
+pub struct IngestBatch {
+    #[serde(default)]
+    pub sleep: Vec<IngestEntry<SleepInput>>,
+    #[serde(default)]
+    pub exercise: Vec<IngestEntry<ExerciseInput>>,
+}
+
+#[doc = r##"Parse a pushed payload from `source` into validated sleep and exercise inputs.
+
+Timestamps carrying an offset are converted to `tz` (the user's timezone) before being
+split into the local date and time the models store. Workouts are assigned to the logical day
+of their start under `day` (see [`DayBoundary`]). Every entry is validated, including
+the DST-aware duration, so a payload is either accepted whole or rejected.
+
+# Example
+
+```rust
+# use sleep_api::domain::DomainError;
+# use sleep_api::importers::{parse_ingest, IngestSource};
+# use sleep_api::models::DayBoundary;
+# fn main() -> Result<(), DomainError> {
+let body = r#"{"data":{"metrics":[{"name":"sleep_analysis","units":"hr","data":[
+  {"date":"2025-06-01 00:00:00 +0900","inBedStart":"2025-05-31 23:00:00 +0900",
+   "sleepStart":"2025-05-31 23:15:00 +0900","sleepEnd":"2025-06-01 07:00:00 +0900"}]}]}}"#;
+let batch = parse_ingest(
+    IngestSource::HealthAutoExport,
+    body,
+    chrono_tz::Asia::Tokyo,
+    DayBoundary::default(),
+)?;
+assert_eq!(batch.sleep[0].input.latency_min, 15);
+# Ok(()) }
+```
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when the payload cannot be parsed or an entry
+fails validation.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"##]
+pub fn parse_ingest(
+    source: IngestSource,
+    payload: &str,
+    tz: Tz,
+    day: DayBoundary,
+) -> Result<IngestBatch, DomainError> {
+    let batch = match source {
+        IngestSource::HealthAutoExport => parse_health_auto_export(payload, tz, day)?,
+        IngestSource::Tasker => serde_json::from_str(payload)
+            .map_err(|e| DomainError::InvalidInput(format!("invalid tasker payload: {e}")))?,
+        IngestSource::Strava => parse_strava(payload, day)?,
+        IngestSource::Garmin => parse_garmin(payload, day)?,
+    };
+    for entry in &batch.sleep {
+        let sleep = &entry.input;
+        sleep.validate()?;
+        crate::time::compute_duration_min(
+            sleep.date,
+            sleep.bed_time,
+            sleep.wake_time,
+            sleep.tz().unwrap_or(tz),
+        )?;
+    }
+    for entry in &batch.exercise {
+        entry.input.validate()?;
+    }
+    let refs = batch
+        .sleep
+        .iter()
+        .filter_map(|e| e.external_ref.as_ref())
+        .chain(
+            batch
+                .exercise
+                .iter()
+                .filter_map(|e| e.external_ref.as_ref()),
+        );
+    let mut seen = std::collections::HashSet::new();
+    for r in refs {
+        r.validate()?;
+        if !seen.insert((&r.source, &r.external_id)) {
+            return Err(DomainError::InvalidInput(format!(
+                "duplicate external reference {}:{}",
+                r.source, r.external_id
+            )));
+        }
+    }
+    Ok(batch)
+}
+
+#[derive(Deserialize)]
+struct HaePayload {
+    data: HaeData,
+}
+
+#[derive(Deserialize)]
+struct HaeData {
+    #[serde(default)]
+    metrics: Vec<HaeMetric>,
+    #[serde(default)]
+    workouts: Vec<HaeWorkout>,
+}
+
+#[derive(Deserialize)]
+struct HaeMetric {
+    name: String,
+    #[serde(default)]
+    data: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HaeSleep {
+    sleep_start: String,
+    sleep_end: String,
+    in_bed_start: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HaeWorkout {
+    id: Option<String>,
+    start: String,
+    end: String,
+    intensity: Option<HaeQuantity>,
+}
+
+#[derive(Deserialize)]
+struct HaeQuantity {
+    qty: f64,
+}
+
+fn parse_hae_time(raw: &str, tz: Tz) -> Result<NaiveDateTime, DomainError> {
+    DateTime::parse_from_str(raw.trim(), "%Y-%m-%d %H:%M:%S %z")
+        .map(|t| t.with_timezone(&tz).naive_local())
+        .map_err(|_| DomainError::InvalidInput(format!("invalid health auto export time: {raw}")))
+}
+
+fn parse_health_auto_export(
+    payload: &str,
+    tz: Tz,
+    day: DayBoundary,
+) -> Result<IngestBatch, DomainError> {
+    let invalid = |e: serde_json::Error| {
+        DomainError::InvalidInput(format!("invalid health auto export payload: {e}"))
+    };
+    let payload: HaePayload = serde_json::from_str(payload).map_err(invalid)?;
+    let mut batch = IngestBatch::default();
+
+    for metric in payload
+        .data
+        .metrics
+        .iter()
+        .filter(|m| m.name == "sleep_analysis")
+    {
+        for entry in &metric.data {
+            let entry: HaeSleep = serde_json::from_value(entry.clone()).map_err(invalid)?;
+            let asleep = parse_hae_time(&entry.sleep_start, tz)?;
+            let wake = parse_hae_time(&entry.sleep_end, tz)?;
+            let latency_min = match entry.in_bed_start.as_deref() {
+                Some(raw) => (asleep - parse_hae_time(raw, tz)?)
+                    .num_minutes()
+                    .clamp(0, 180) as i32,
+                None => 0,
+            };
+            let bed = asleep - chrono::Duration::minutes(i64::from(latency_min));
+            batch.sleep.push(IngestEntry::from(SleepInput {
+                date: wake.date(),
+                bed_time: bed.time(),
+                wake_time: wake.time(),
+                latency_min,
+                awakenings: 0,
+                quality: Quality(INGEST_DEFAULT_QUALITY),
+                wake_feeling: None,
+                sleep_inertia_min: None,
+                waso_min: None,
+                aids: Vec::new(),
+                tags: Vec::new(),
+                timezone: None,
+            }));
+        }
+    }
+
+    for workout in payload.data.workouts {
+        let start = parse_hae_time(&workout.start, tz)?;
+        let end = parse_hae_time(&workout.end, tz)?;
+        let intensity = match workout.intensity {
+            Some(q) if q.qty >= HARD_WORKOUT_METS => Intensity::Hard,
+            _ => Intensity::Light,
+        };
+        batch.exercise.push(IngestEntry {
+            input: ExerciseInput {
+                date: day.day_of(start),
+                intensity,
+                start_time: Some(start.time()),
+                duration_min: Some((end - start).num_minutes().max(1) as i32),
+                hr_zones: None,
+            },
+            external_ref: workout.id.map(|id| ExternalRef {
+                source: IngestSource::HealthAutoExport.as_str().to_string(),
+                external_id: id,
+                url: None,
+            }),
+        });
+    }
+    Ok(batch)
+}
+
+/// Share of zone time in Z4–Z5 from which a Strava/Garmin activity counts as `hard`.
+const HARD_ZONE_SHARE: f64 = 0.25;
+
+fn seconds_to_minutes(seconds: f64) -> i32 {
+    (seconds.max(0.0) / 60.0).round() as i32
+}
+
+fn zone_intensity(zones: Option<&HrZoneMinutes>) -> Intensity {
+    match zones {
+        Some(z)
+            if z.total() > 0 && f64::from(z.high()) >= HARD_ZONE_SHARE * f64::from(z.total()) =>
+        {
+            Intensity::Hard
+        }
+        _ => Intensity::Light,
+    }
+}
+
+fn timed_exercise(
+    start: NaiveDateTime,
+    seconds: f64,
+    zones: Option<HrZoneMinutes>,
+    day: DayBoundary,
+    external_ref: ExternalRef,
+) -> IngestEntry<ExerciseInput> {
+    IngestEntry {
+        input: ExerciseInput {
+            date: day.day_of(start),
+            intensity: zone_intensity(zones.as_ref()),
+            start_time: Some(start.time()),
+            duration_min: Some(seconds_to_minutes(seconds).max(1)),
+            hr_zones: zones,
+        },
+        external_ref: Some(external_ref),
+    }
+}
+
+#[derive(Deserialize)]
+struct StravaActivity {
+    id: u64,
+    start_date_local: String,
+    elapsed_time: f64,
+    #[serde(default)]
+    zones: Vec<StravaZone>,
+}
+
+#[derive(Deserialize)]
+struct StravaZone {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    distribution_buckets: Vec<StravaBucket>,
+}
+
+#[derive(Deserialize)]
+struct StravaBucket {
+    time: f64,
+}
+
+fn parse_strava(payload: &str, day: DayBoundary) -> Result<IngestBatch, DomainError> {
+    let activities: Vec<StravaActivity> = serde_json::from_str(payload)
+        .map_err(|e| DomainError::InvalidInput(format!("invalid strava payload: {e}")))?;
+    let mut batch = IngestBatch::default();
+    for activity in activities {
+        // `start_date_local` is wall-clock time even though it carries a `Z`.
+        let raw = activity.start_date_local.trim().trim_end_matches('Z');
+        let start = NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S").map_err(|_| {
+            DomainError::InvalidInput(format!(
+                "invalid strava start_date_local: {}",
+                activity.start_date_local
+            ))
+        })?;
+        let zones = activity
+            .zones
+            .iter()
+            .find(|z| z.kind == "heartrate")
+            .map(|z| {
+                let minutes: Vec<i32> = z
+                    .distribution_buckets
+                    .iter()
+                    .map(|b| seconds_to_minutes(b.time))
+                    .collect();
+                if minutes.len() != 5 {
+                    return Err(DomainError::InvalidInput(format!(
+                        "strava activity {} has {} heart-rate zones, expected 5",
+                        activity.id,
+                        minutes.len()
+                    )));
+                }
+                Ok(HrZoneMinutes {
+                    z1: minutes[0],
+                    z2: minutes[1],
+                    z3: minutes[2],
+                    z4: minutes[3],
+                    z5: minutes[4],
+                })
+            })
+            .transpose()?;
+        batch.exercise.push(timed_exercise(
+            start,
+            activity.elapsed_time,
+            zones,
+            day,
+            ExternalRef {
+                source: IngestSource::Strava.as_str().to_string(),
+                external_id: activity.id.to_string(),
+                url: Some(format!("https://www.strava.com/activities/{}", activity.id)),
+            },
+        ));
+    }
+    Ok(batch)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GarminActivity {
+    activity_id: u64,
+    start_time_local: String,
+    duration: f64,
+    #[serde(rename = "hrTimeInZone_1")]
+    hr_time_in_zone_1: Option<f64>,
+    #[serde(rename = "hrTimeInZone_2")]
+    hr_time_in_zone_2: Option<f64>,
+    #[serde(rename = "hrTimeInZone_3")]
+    hr_time_in_zone_3: Option<f64>,
+    #[serde(rename = "hrTimeInZone_4")]
+    hr_time_in_zone_4: Option<f64>,
+    #[serde(rename = "hrTimeInZone_5")]
+    hr_time_in_zone_5: Option<f64>,
+}
+
+fn parse_garmin(payload: &str, day: DayBoundary) -> Result<IngestBatch, DomainError> {
+    let activities: Vec<GarminActivity> = serde_json::from_str(payload)
+        .map_err(|e| DomainError::InvalidInput(format!("invalid garmin payload: {e}")))?;
+    let mut batch = IngestBatch::default();
+    for a in activities {
+        let start = NaiveDateTime::parse_from_str(a.start_time_local.trim(), "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| {
+            DomainError::InvalidInput(format!(
+                "invalid garmin startTimeLocal: {}",
+                a.start_time_local
+            ))
+        })?;
+        let seconds = [
+            a.hr_time_in_zone_1,
+            a.hr_time_in_zone_2,
+            a.hr_time_in_zone_3,
+            a.hr_time_in_zone_4,
+            a.hr_time_in_zone_5,
+        ];
+        // Activities recorded without a heart-rate sensor have no zone fields at all.
+        let zones = seconds.iter().any(Option::is_some).then(|| {
+            let [z1, z2, z3, z4, z5] = seconds.map(|s| seconds_to_minutes(s.unwrap_or(0.0)));
+            HrZoneMinutes { z1, z2, z3, z4, z5 }
+        });
+        batch.exercise.push(timed_exercise(
+            start,
+            a.duration,
+            zones,
+            day,
+            ExternalRef {
+                source: IngestSource::Garmin.as_str().to_string(),
+                external_id: a.activity_id.to_string(),
+                url: Some(format!(
+                    "https://connect.garmin.com/modern/activity/{}",
+                    a.activity_id
+                )),
+            },
+        ));
+    }
+    Ok(batch)
+}
+
+/// Data rows returned in a mapping preview.
+pub const MAPPING_PREVIEW_ROWS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Which spreadsheet column feeds each sleep field, for `POST /api/import/*`.
+
+Values are header names of the CSV. `date` (the wake date), `bed_time`, and `wake_time` are
+required; unmapped optional fields take `latency_min: 0`, `awakenings: 0`, and `quality: 3`.
+
+- `date_format`: chrono format of the date column (default `%Y-%m-%d`, e.g. `%d/%m/%Y`).
+- Times accept `HH:MM`, `HH:MM:SS`, or `h:mm AM/PM` (see [`parse_flexible_time`]).
+- `delimiter`: field separator (default `,`; e.g. `;` or a tab).
+
+[`parse_flexible_time`]: crate::time::parse_flexible_time
+"#]
+pub struct ColumnMapping {
+    pub date: String,
+    pub bed_time: String,
+    pub wake_time: String,
+    #[serde(default)]
+    pub latency_min: Option<String>,
+    #[serde(default)]
+    pub awakenings: Option<String>,
+    #[serde(default)]
+    pub quality: Option<String>,
+    #[serde(default)]
+    pub wake_feeling: Option<String>,
+    #[serde(default)]
+    pub date_format: Option<String>,
+    #[serde(default)]
+    pub delimiter: Option<char>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Body of `POST /api/import/mapping-preview` and `POST /api/import/with-mapping`."#]
+pub struct MappingImportRequest {
+    pub csv: String,
+    pub mapping: ColumnMapping,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[doc = r#"A problem with one CSV row (`row` counts the header as row 1) or, with `row: 0`,
+with the mapping itself."#]
+pub struct ImportIssue {
+    pub row: usize,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[doc = r#"A CSV row parsed into a sleep entry."#]
+pub struct MappedRow {
+    pub row: usize,
+    pub input: SleepInput,
+}
+
+#[doc = r#"Rows of a mapped CSV: every valid row plus an issue for every rejected one."#]
+pub struct MappedCsv {
+    pub rows: Vec<MappedRow>,
+    pub issues: Vec<ImportIssue>,
+}
+
+#[doc = r##"Parse a spreadsheet export into sleep inputs using a caller-supplied [`ColumnMapping`].
+
+Unlike the fixed-format importers, bad rows do not abort parsing: each is reported as an
+[`ImportIssue`] so a preview can show every problem at once. Rows are validated like
+`POST /api/sleep`, including the DST-aware duration in `tz`.
+
+# Example
+
+```rust
+# use sleep_api::importers::{parse_mapped_csv, ColumnMapping};
+let csv = "Night of,In bed,Up,Rating\n2025-06-01,23:10,6:45,4\n2025-06-02,late,7:00,3\n";
+let mapping = ColumnMapping {
+    date: "Night of".into(),
+    bed_time: "In bed".into(),
+    wake_time: "Up".into(),
+    latency_min: None,
+    awakenings: None,
+    quality: Some("Rating".into()),
+    wake_feeling: None,
+    date_format: None,
+    delimiter: None,
+};
+let mapped = parse_mapped_csv(csv, &mapping, chrono_tz::Asia::Tokyo);
+assert_eq!(mapped.rows.len(), 1);
+assert_eq!(mapped.issues[0].row, 3);
+assert_eq!(mapped.issues[0].column.as_deref(), Some("In bed"));
+```
+"##]
+pub fn parse_mapped_csv(csv: &str, mapping: &ColumnMapping, tz: Tz) -> MappedCsv {
+    let mut out = MappedCsv {
+        rows: Vec::new(),
+        issues: Vec::new(),
+    };
+    let mapping_issue = |message: String| ImportIssue {
+        row: 0,
+        column: None,
+        message,
+    };
+    let delimiter = mapping.delimiter.unwrap_or(',');
+    if !delimiter.is_ascii() {
+        out.issues
+            .push(mapping_issue("delimiter must be an ASCII character".into()));
+        return out;
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .delimiter(delimiter as u8)
+        .from_reader(csv.as_bytes());
+    let headers = match reader.headers() {
+        Ok(h) => h.clone(),
+        Err(e) => {
+            out.issues.push(mapping_issue(format!("invalid csv: {e}")));
+            return out;
+        }
+    };
+
+    let mut index = |column: &str| {
+        let found = headers.iter().position(|h| h.trim() == column.trim());
+        if found.is_none() {
+            out.issues
+                .push(mapping_issue(format!("column {column:?} not found")));
+        }
+        found
+    };
+    let date_idx = index(&mapping.date);
+    let bed_idx = index(&mapping.bed_time);
+    let wake_idx = index(&mapping.wake_time);
+    let optional = [
+        &mapping.latency_min,
+        &mapping.awakenings,
+        &mapping.quality,
+        &mapping.wake_feeling,
+    ]
+    .map(|column| column.as_deref().map(|c| (c, index(c))));
+    let (Some(date_idx), Some(bed_idx), Some(wake_idx)) = (date_idx, bed_idx, wake_idx) else {
+        return out;
+    };
+    if optional.iter().flatten().any(|(_, idx)| idx.is_none()) {
+        return out;
+    }
+    let date_format = mapping.date_format.as_deref().unwrap_or("%Y-%m-%d");
+
+    for (line, record) in reader.records().enumerate() {
+        let row = line + 2;
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                out.issues.push(ImportIssue {
+                    row,
+                    column: None,
+                    message: format!("invalid csv: {e}"),
+                });
+                continue;
+            }
+        };
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let required = [
+            (mapping.date.as_str(), date_idx),
+            (mapping.bed_time.as_str(), bed_idx),
+            (mapping.wake_time.as_str(), wake_idx),
+        ];
+        match mapped_row(&record, required, &optional, date_format, tz) {
+            Ok(input) => out.rows.push(MappedRow { row, input }),
+            Err((column, message)) => out.issues.push(ImportIssue {
+                row,
+                column: column.map(str::to_string),
+                message,
+            }),
+        }
+    }
+    out
+}
+
+type RowError<'a> = (Option<&'a str>, String);
+
+fn mapped_row<'a>(
+    record: &csv::StringRecord,
+    [
+        (date_col, date_idx),
+        (bed_col, bed_idx),
+        (wake_col, wake_idx),
+    ]: [(&'a str, usize); 3],
+    optional: &[Option<(&'a str, Option<usize>)>; 4],
+    date_format: &str,
+    tz: Tz,
+) -> Result<SleepInput, RowError<'a>> {
+    let cell = |idx: usize| record.get(idx).unwrap_or("").trim();
+    let number = |field: Option<(&'a str, Option<usize>)>| -> Result<Option<i32>, RowError<'a>> {
+        match field {
+            Some((column, Some(idx))) if !cell(idx).is_empty() => cell(idx)
+                .parse::<i32>()
+                .map(Some)
+                .map_err(|_| (Some(column), format!("invalid number {:?}", cell(idx)))),
+            _ => Ok(None),
+        }
+    };
+
+    let date = NaiveDate::parse_from_str(cell(date_idx), date_format).map_err(|_| {
+        (
+            Some(date_col),
+            format!("invalid date {:?} (expected {date_format})", cell(date_idx)),
+        )
+    })?;
+    let bed_time =
+        crate::time::parse_flexible_time(cell(bed_idx)).map_err(|e| (Some(bed_col), e))?;
+    let wake_time =
+        crate::time::parse_flexible_time(cell(wake_idx)).map_err(|e| (Some(wake_col), e))?;
+    let quality = match number(optional[2])? {
+        Some(q) => u8::try_from(q)
+            .ok()
+            .and_then(|q| Quality::try_from(q).ok())
+            .ok_or_else(|| (optional[2].map(|(c, _)| c), "quality must be 1..=5".into()))?,
+        None => Quality(INGEST_DEFAULT_QUALITY),
+    };
+    let input = SleepInput {
+        date,
+        bed_time,
+        wake_time,
+        latency_min: number(optional[0])?.unwrap_or(0),
+        awakenings: number(optional[1])?.unwrap_or(0),
+        quality,
+        wake_feeling: number(optional[3])?,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
+    };
+    input.validate().map_err(|e| (None, e.to_string()))?;
+    crate::time::compute_duration_min(date, bed_time, wake_time, tz)
+        .map_err(|e| (None, e.to_string()))?;
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn withings_keeps_earliest_reading_per_day() {
+        let csv = "Date,\"Weight (kg)\",\"Fat mass (kg)\",\"Bone mass (kg)\",Comments\n\
+                   \"2025-06-02 21:00:00\",73.0,,,\n\
+                   \"2025-06-02 07:00:00\",72.0,14.4,3.1,\n\
+                   \"2025-06-01 07:05:00\",72.5,,,\n";
+        let readings = parse_weight_export(WeightSource::Withings, csv).expect("parse");
+        assert_eq!(readings.len(), 2);
+        assert_eq!(
+            readings[0].date,
+            NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+        );
+        assert_eq!(readings[0].body_fat_pct, None);
+        assert_eq!(readings[1].weight_kg, Some(72.0));
+        assert_eq!(readings[1].body_fat_pct, Some(20.0));
+    }
+
+    #[test]
+    fn fitbit_log_is_parsed_and_validated() {
+        let json = r#"{"weight":[
+            {"bmi":23.1,"date":"2025-06-01","fat":18.5,"logId":1,"source":"Aria","time":"07:10:00","weight":72.4},
+            {"bmi":23.0,"date":"2025-06-02","logId":2,"source":"API","time":"07:00:00","weight":72.1}
+        ]}"#;
+        let readings = parse_weight_export(WeightSource::Fitbit, json).expect("parse");
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].body_fat_pct, Some(18.5));
+        assert_eq!(readings[1].body_fat_pct, None);
+
+        let bad = r#"{"weight":[{"date":"2025-06-01","weight":-1.0}]}"#;
+        assert!(parse_weight_export(WeightSource::Fitbit, bad).is_err());
+    }
+
+    #[test]
+    fn health_auto_export_converts_to_user_timezone() {
+        let body = r#"{"data":{"metrics":[
+            {"name":"step_count","units":"count","data":[{"date":"2025-06-01 00:00:00 +0000","qty":9000}]},
+            {"name":"sleep_analysis","units":"hr","data":[
+                {"date":"2025-06-01 00:00:00 +0000","sleepStart":"2025-05-31 14:20:00 +0000",
+                 "sleepEnd":"2025-05-31 22:00:00 +0000","asleep":7.6}]}],
+            "workouts":[
+                {"id":"6F2C1A","name":"Running","start":"2025-06-01 18:00:00 +0900","end":"2025-06-01 18:40:00 +0900",
+                 "intensity":{"qty":9.1,"units":"kcal/hr·kg"}},
+                {"name":"Walking","start":"2025-06-01 12:00:00 +0900","end":"2025-06-01 12:30:00 +0900"}]}}"#;
+        let batch = parse_ingest(
+            IngestSource::HealthAutoExport,
+            body,
+            chrono_tz::Asia::Tokyo,
+            DayBoundary::default(),
+        )
+        .expect("parse");
+        assert_eq!(batch.sleep.len(), 1);
+        let sleep = &batch.sleep[0].input;
+        assert_eq!(sleep.date, NaiveDate::from_ymd_opt(2025, 6, 1).unwrap());
+        assert_eq!(sleep.bed_time, NaiveTime::from_hms_opt(23, 20, 0).unwrap());
+        assert_eq!(sleep.wake_time, NaiveTime::from_hms_opt(7, 0, 0).unwrap());
+        assert_eq!(sleep.latency_min, 0);
+        assert_eq!(batch.exercise.len(), 2);
+        assert_eq!(batch.exercise[0].input.intensity, Intensity::Hard);
+        assert_eq!(batch.exercise[0].input.duration_min, Some(40));
+        assert_eq!(
+            batch.exercise[0]
+                .external_ref
+                .as_ref()
+                .map(|r| r.external_id.as_str()),
+            Some("6F2C1A")
+        );
+        assert_eq!(batch.exercise[1].input.intensity, Intensity::Light);
+        assert!(batch.exercise[1].external_ref.is_none());
+    }
+
+    #[test]
+    fn health_auto_export_assigns_workouts_to_logical_day() {
+        let body = r#"{"data":{"metrics":[],"workouts":[
+            {"name":"Running","start":"2025-06-02 01:30:00 +0900","end":"2025-06-02 02:00:00 +0900"}]}}"#;
+        let night_owl = DayBoundary {
+            day_start: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+        };
+        let batch = parse_ingest(
+            IngestSource::HealthAutoExport,
+            body,
+            chrono_tz::Asia::Tokyo,
+            night_owl,
+        )
+        .expect("parse");
+        assert_eq!(
+            batch.exercise[0].input.date,
+            NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+        );
+        assert_eq!(
+            batch.exercise[0].input.start_time,
+            Some(NaiveTime::from_hms_opt(1, 30, 0).unwrap())
+        );
+    }
+
+    fn mapping(date: &str) -> ColumnMapping {
+        ColumnMapping {
+            date: date.into(),
+            bed_time: "Bed".into(),
+            wake_time: "Wake".into(),
+            latency_min: Some("Latency".into()),
+            awakenings: None,
+            quality: Some("Q".into()),
+            wake_feeling: None,
+            date_format: Some("%d/%m/%Y".into()),
+            delimiter: Some(';'),
+        }
+    }
+
+    #[test]
+    fn mapped_csv_reports_every_bad_row() {
+        let csv = "Day;Bed;Wake;Latency;Q\n\
+                   01/06/2025;23:00;07:00;15;4\n\
+                   ;;;;\n\
+                   2025-06-02;23:00;07:00;;3\n\
+                   03/06/2025;11:30 pm;6:30 AM;;9\n\
+                   04/06/2025;23:00;07:00;;\n";
+        let mapped = parse_mapped_csv(csv, &mapping("Day"), chrono_tz::UTC);
+        let rows: Vec<usize> = mapped.rows.iter().map(|r| r.row).collect();
+        assert_eq!(rows, vec![2, 6]);
+        assert_eq!(mapped.rows[0].input.latency_min, 15);
+        assert_eq!(mapped.rows[1].input.quality.value(), INGEST_DEFAULT_QUALITY);
+        let issues: Vec<(usize, Option<&str>)> = mapped
+            .issues
+            .iter()
+            .map(|i| (i.row, i.column.as_deref()))
+            .collect();
+        assert_eq!(issues, vec![(4, Some("Day")), (5, Some("Q"))]);
+
+        let missing = parse_mapped_csv(csv, &mapping("Night"), chrono_tz::UTC);
+        assert!(missing.rows.is_empty());
+        assert_eq!(missing.issues[0].row, 0);
+        assert!(missing.issues[0].message.contains("\"Night\""));
+    }
+
+    #[test]
+    fn tasker_payload_is_validated() {
+        let ok = r#"{"exercise":[{"date":"2025-06-01","intensity":"light","start_time":"07:00:00","duration_min":20}]}"#;
+        let batch = parse_ingest(
+            IngestSource::Tasker,
+            ok,
+            chrono_tz::UTC,
+            DayBoundary::default(),
+        )
+        .expect("parse");
+        assert!(batch.sleep.is_empty());
+        assert_eq!(batch.exercise.len(), 1);
+
+        let bad = r#"{"sleep":[{"date":"2025-06-01","bed_time":"23:00","wake_time":"07:00",
+            "latency_min":500,"awakenings":0,"quality":4}]}"#;
+        assert!(
+            parse_ingest(
+                IngestSource::Tasker,
+                bad,
+                chrono_tz::UTC,
+                DayBoundary::default()
+            )
+            .is_err()
+        );
+    }
+}
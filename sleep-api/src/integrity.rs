@@ -0,0 +1,512 @@
+#![doc = r#"Database corruption detection and salvage
+
+- [`check`] runs `PRAGMA quick_check` or `PRAGMA integrity_check` and returns an
+  [`IntegrityReport`]. SQLite "malformed"/"not a database" errors count as findings, not as
+  failures of the check itself.
+- The server checks its database on startup ([`startup_check`]) and again on
+  `GET /api/admin/integrity`. The last report lives in the router's [`IntegrityState`]; while
+  it shows corruption the server is *degraded*: [`refuse_writes_when_degraded`] answers
+  mutating requests with `503 {"code":"degraded"}`, `GET /api/health` reports
+  `"status":"degraded"`, and startup skips migrations and background jobs.
+- [`salvage`] (`sleepctl salvage`) copies every readable row of a damaged file into a fresh,
+  migrated database, chunk by chunk with a row-by-row fallback, and reports what was lost.
+- [`verify_daily_sleep`] (`GET /api/admin/daily-sleep/verify`) compares the materialized
+  `daily_sleep` table with the aggregate recomputed from the sessions and lists every wake date
+  where they disagree.
+"#]
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use crate::db::{self, Db};
+use crate::error::{ApiError, Error};
+
+/// Most findings kept from one check; SQLite stops reporting after this many.
+const MAX_PROBLEMS: u32 = 100;
+
+/// Rows copied per statement by [`salvage`] before falling back to single rows.
+const SALVAGE_CHUNK: i64 = 256;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"Which SQLite check to run.
+
+- `quick`: `PRAGMA quick_check` — page and record structure, no index consistency (fast).
+- `full`: `PRAGMA integrity_check` — additionally verifies every index against its table.
+"#]
+pub enum IntegrityCheck {
+    #[default]
+    Quick,
+    Full,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Outcome of an integrity check.
+
+`problems` lists SQLite's findings (at most 100); it is empty when `ok` is `true`."#]
+pub struct IntegrityReport {
+    pub ok: bool,
+    pub check: IntegrityCheck,
+    pub problems: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Default)]
+#[doc = r#"Last integrity report for one database, shared by the router and its middleware.
+
+No report (never checked) counts as healthy."#]
+pub struct IntegrityState(Arc<RwLock<Option<IntegrityReport>>>);
+
+impl IntegrityState {
+    /// Replace the last report.
+    pub fn record(&self, report: IntegrityReport) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Some(report);
+    }
+
+    /// The last recorded report, if any.
+    pub fn last(&self) -> Option<IntegrityReport> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Whether the last check found corruption.
+    pub fn degraded(&self) -> bool {
+        self.last().is_some_and(|r| !r.ok)
+    }
+}
+
+#[doc = r#"Run `kind` against `db`.
+
+# Errors
+
+Returns [`Error::Database`] when the check cannot run for reasons other than corruption
+(e.g. the pool is closed).
+
+[`Error::Database`]: crate::error::Error::Database
+"#]
+pub async fn check(
+    db: &Db,
+    kind: IntegrityCheck,
+    now: DateTime<Utc>,
+) -> Result<IntegrityReport, Error> {
+    let sql = match kind {
+        IntegrityCheck::Quick => format!("PRAGMA quick_check({MAX_PROBLEMS})"),
+        IntegrityCheck::Full => format!("PRAGMA integrity_check({MAX_PROBLEMS})"),
+    };
+    let problems = match sqlx::query_scalar::<_, String>(&sql).fetch_all(db).await {
+        Ok(rows) if rows.len() == 1 && rows[0] == "ok" => Vec::new(),
+        Ok(rows) => rows,
+        Err(e) if is_corruption(&e) => vec![e.to_string()],
+        Err(e) => return Err(e.into()),
+    };
+    Ok(IntegrityReport {
+        ok: problems.is_empty(),
+        check: kind,
+        problems,
+        checked_at: now,
+    })
+}
+
+#[doc = r#"Quick-check `db` at startup and return the state to hand to the router.
+
+Corruption is logged as an error; a check that cannot run is logged and leaves the state
+unchecked."#]
+pub async fn startup_check(db: &Db, now: DateTime<Utc>) -> IntegrityState {
+    let state = IntegrityState::default();
+    match check(db, IntegrityCheck::Quick, now).await {
+        Ok(report) if report.ok => state.record(report),
+        Ok(report) => {
+            tracing::error!(
+                problems = ?report.problems,
+                "database corruption detected; serving read-only. Run `sleepctl salvage` to recover"
+            );
+            state.record(report);
+        }
+        Err(error) => tracing::warn!(%error, "startup integrity check failed to run"),
+    }
+    state
+}
+
+/// SQLite result codes `SQLITE_CORRUPT` (11) and `SQLITE_NOTADB` (26), extended codes included.
+fn is_corruption(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(db) => db
+            .code()
+            .and_then(|c| c.parse::<i32>().ok())
+            .is_some_and(|c| matches!(c & 0xff, 11 | 26)),
+        _ => false,
+    }
+}
+
+#[doc = r#"Middleware answering mutating requests with `503 {"code":"degraded"}` while the last
+integrity check found corruption.
+
+Reads (`GET`, `HEAD`, `OPTIONS`), login and logout still pass so data can be inspected and
+exported."#]
+pub async fn refuse_writes_when_degraded(
+    State(state): State<IntegrityState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let session = matches!(
+        req.uri().path(),
+        "/api/login" | "/api/login.json" | "/api/logout"
+    );
+    if read || session || !state.degraded() {
+        return next.run(req).await;
+    }
+    ApiError::Degraded(
+        "database corruption detected; writes are disabled until it is salvaged".into(),
+    )
+    .into_response()
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[doc = r#"Rows recovered for one table by [`salvage`].
+
+- `copied`: rows written to the new database.
+- `lost`: rows (or row ids) that could not be read; an upper bound when ids have gaps.
+- `error`: set when the table could not be read at all.
+"#]
+pub struct SalvagedTable {
+    pub table: String,
+    pub copied: u64,
+    pub lost: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[doc = r#"Result of [`salvage`]: per-table counts plus checks of the new database.
+
+`foreign_key_violations` counts rows whose parent was lost; `integrity` is a full check of
+the new file."#]
+pub struct SalvageReport {
+    pub tables: Vec<SalvagedTable>,
+    pub foreign_key_violations: u64,
+    pub integrity: IntegrityReport,
+}
+
+impl SalvageReport {
+    /// Whether every table was copied without losing rows.
+    pub fn complete(&self) -> bool {
+        self.tables.iter().all(|t| t.lost == 0 && t.error.is_none())
+    }
+}
+
+/// Tables computed from others; [`salvage`] rebuilds them instead of copying possibly stale rows.
+pub const DERIVED_TABLES: &[&str] = &["daily_sleep"];
+
+#[doc = r#"Copy what can be read from the damaged database `src` into a new file `dest`.
+
+`dest` is created and migrated to the current schema first; then every application table
+present in both is copied (columns in common only), 256 rows at a time. A chunk
+that fails to read is retried row by row, and rows that still fail are counted as lost.
+Rows from `src` replace any defaults the migrations seeded. Derived tables
+([`DERIVED_TABLES`]) are not copied but rebuilt from the salvaged rows. `src` is never written
+to.
+
+# Errors
+
+- Returns [`Error::Domain`] if `dest` already exists.
+- Returns [`Error::Database`] / [`Error::Migration`] if `dest` cannot be created or `src`'s
+  schema cannot be read at all.
+
+[`Error::Domain`]: crate::error::Error::Domain
+[`Error::Database`]: crate::error::Error::Database
+[`Error::Migration`]: crate::error::Error::Migration
+"#]
+pub async fn salvage(src: &Path, dest: &Path) -> Result<SalvageReport, Error> {
+    if dest.exists() {
+        return Err(Error::invalid(format!("{} already exists", dest.display())));
+    }
+    let fresh = db::connect_file(dest).await?;
+    sqlx::migrate!("../migrations").run(&fresh).await?;
+    fresh.close().await;
+
+    // One connection, foreign keys off: rows arrive in table order, not dependency order.
+    let options = SqliteConnectOptions::new()
+        .filename(dest)
+        .foreign_keys(false);
+    let out = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+    sqlx::query("ATTACH DATABASE ? AS old")
+        .bind(src.to_string_lossy().into_owned())
+        .execute(&out)
+        .await?;
+
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM main.sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name <> '_sqlx_migrations' ORDER BY name",
+    )
+    .fetch_all(&out)
+    .await?;
+    let old_tables: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM old.sqlite_master WHERE type = 'table'")
+            .fetch_all(&out)
+            .await?;
+
+    let mut report = Vec::new();
+    for table in tables
+        .into_iter()
+        .filter(|t| old_tables.contains(t) && !DERIVED_TABLES.contains(&t.as_str()))
+    {
+        report.push(salvage_table(&out, table).await);
+    }
+    crate::repository::rebuild_daily_sleep(&out).await?;
+
+    sqlx::query("DETACH DATABASE old").execute(&out).await?;
+    let violations: Vec<String> = sqlx::query_scalar("PRAGMA foreign_key_check")
+        .fetch_all(&out)
+        .await?;
+    let integrity = check(&out, IntegrityCheck::Full, Utc::now()).await?;
+    out.close().await;
+    Ok(SalvageReport {
+        tables: report,
+        foreign_key_violations: violations.len() as u64,
+        integrity,
+    })
+}
+
+async fn salvage_table(out: &Db, table: String) -> SalvagedTable {
+    let mut result = SalvagedTable {
+        table,
+        copied: 0,
+        lost: 0,
+        error: None,
+    };
+    // Table names come from sqlite_master; quote them anyway.
+    let quoted = format!("\"{}\"", result.table.replace('"', "\"\""));
+    let columns = match common_columns(out, &result.table).await {
+        Ok(c) => c,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let max_rowid: Option<i64> =
+        match sqlx::query_scalar(&format!("SELECT max(rowid) FROM old.{quoted}"))
+            .fetch_one(out)
+            .await
+        {
+            Ok(max) => max,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        };
+    let Some(max_rowid) = max_rowid else {
+        return result;
+    };
+
+    let next_rowid = format!("SELECT min(rowid) FROM old.{quoted} WHERE rowid >= ?");
+    let insert = format!(
+        "INSERT OR REPLACE INTO main.{quoted} ({columns}) \
+         SELECT {columns} FROM old.{quoted} WHERE rowid BETWEEN ? AND ?"
+    );
+    let mut lo = 1;
+    while lo <= max_rowid {
+        // Skip id gaps; when the seek itself fails, walk the range instead.
+        match sqlx::query_scalar::<_, Option<i64>>(&next_rowid)
+            .bind(lo)
+            .fetch_one(out)
+            .await
+        {
+            Ok(Some(next)) => lo = next,
+            Ok(None) => break,
+            Err(_) => {}
+        }
+        let hi = (lo + SALVAGE_CHUNK - 1).min(max_rowid);
+        match sqlx::query(&insert).bind(lo).bind(hi).execute(out).await {
+            Ok(done) => result.copied += done.rows_affected(),
+            Err(_) => {
+                for id in lo..=hi {
+                    match sqlx::query(&insert).bind(id).bind(id).execute(out).await {
+                        Ok(done) => result.copied += done.rows_affected(),
+                        Err(_) => result.lost += 1,
+                    }
+                }
+            }
+        }
+        lo = hi + 1;
+    }
+    result
+}
+
+/// Comma-separated, quoted columns of `table` present in both `main` and `old`.
+async fn common_columns(out: &Db, table: &str) -> Result<String, sqlx::Error> {
+    let main: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?, 'main')")
+        .bind(table)
+        .fetch_all(out)
+        .await?;
+    let old: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?, 'old')")
+        .bind(table)
+        .fetch_all(out)
+        .await?;
+    Ok(main
+        .iter()
+        .filter(|c| old.contains(c))
+        .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, sqlx::FromRow, JsonSchema)]
+#[doc = r#"One wake date of `daily_sleep`, as stored or as computed from the sessions."#]
+pub struct DailySleepRow {
+    pub wake_date: NaiveDate,
+    pub id: i64,
+    pub bed_time: NaiveTime,
+    pub wake_time: NaiveTime,
+    pub latency_min: Option<i32>,
+    pub awakenings: Option<i32>,
+    pub quality: Option<i32>,
+    pub duration_min: Option<i32>,
+    pub session_count: i64,
+    pub wake_feeling: Option<i32>,
+    pub sleep_inertia_min: Option<i32>,
+    pub longest_segment_min: Option<i32>,
+    pub waso_min: Option<i32>,
+    pub efficiency: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"How a wake date of `daily_sleep` disagrees with the sessions.
+
+- `missing`: the sessions have the night, the table has no row.
+- `orphaned`: the table has a row, no session wakes on that date.
+- `mismatch`: both have the night with different values (see `fields`).
+"#]
+pub enum DiscrepancyKind {
+    Missing,
+    Orphaned,
+    Mismatch,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"One wake date where the stored row differs from the computed one.
+
+`fields` names the differing columns (empty unless `kind` is `mismatch`)."#]
+pub struct DailySleepDiscrepancy {
+    pub wake_date: NaiveDate,
+    pub kind: DiscrepancyKind,
+    pub fields: Vec<String>,
+    pub stored: Option<DailySleepRow>,
+    pub computed: Option<DailySleepRow>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Outcome of [`verify_daily_sleep`] over the wake dates `from..=to`.
+
+`nights` counts the wake dates present on either side; `ok` is `true` when
+`discrepancies` is empty."#]
+pub struct DailySleepVerification {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub ok: bool,
+    pub nights: u32,
+    pub discrepancies: Vec<DailySleepDiscrepancy>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[doc = r#"Compare `daily_sleep` row by row with the aggregate it materializes, recomputed from
+`sleep_sessions` and `sleep_metrics` as `v_daily_sleep` did before migration
+`0034_daily_sleep_table.sql`.
+
+The triggers should keep both identical; a clean report over the dates in use is what
+allows readers to move off the view. Rows that drifted are repaired by
+[`rebuild_daily_sleep`].
+
+# Errors
+
+Returns [`Error::Database`] on database errors.
+
+[`rebuild_daily_sleep`]: crate::repository::rebuild_daily_sleep
+[`Error::Database`]: crate::error::Error::Database
+"#]
+pub async fn verify_daily_sleep(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    now: DateTime<Utc>,
+) -> Result<DailySleepVerification, Error> {
+    let mut stored: BTreeMap<NaiveDate, DailySleepRow> =
+        crate::repository::list_daily_sleep_stored(db, from, to)
+            .await?
+            .into_iter()
+            .map(|r| (r.wake_date, r))
+            .collect();
+    let computed = crate::repository::list_daily_sleep_computed(db, from, to).await?;
+
+    let mut nights = stored.len() as u32;
+    let mut discrepancies = Vec::new();
+    for row in computed {
+        let date = row.wake_date;
+        let discrepancy = match stored.remove(&date) {
+            None => {
+                nights += 1;
+                DailySleepDiscrepancy {
+                    wake_date: date,
+                    kind: DiscrepancyKind::Missing,
+                    fields: Vec::new(),
+                    stored: None,
+                    computed: Some(row),
+                }
+            }
+            Some(kept) if kept == row => continue,
+            Some(kept) => DailySleepDiscrepancy {
+                wake_date: date,
+                kind: DiscrepancyKind::Mismatch,
+                fields: differing_fields(&kept, &row),
+                stored: Some(kept),
+                computed: Some(row),
+            },
+        };
+        discrepancies.push(discrepancy);
+    }
+    discrepancies.extend(stored.into_values().map(|row| DailySleepDiscrepancy {
+        wake_date: row.wake_date,
+        kind: DiscrepancyKind::Orphaned,
+        fields: Vec::new(),
+        stored: Some(row),
+        computed: None,
+    }));
+    discrepancies.sort_by_key(|d| d.wake_date);
+
+    Ok(DailySleepVerification {
+        from,
+        to,
+        ok: discrepancies.is_empty(),
+        nights,
+        discrepancies,
+        checked_at: now,
+    })
+}
+
+/// Column names whose values differ between two rows of the same wake date.
+fn differing_fields(a: &DailySleepRow, b: &DailySleepRow) -> Vec<String> {
+    let (serde_json::Value::Object(a), serde_json::Value::Object(b)) = (
+        serde_json::to_value(a).unwrap_or_default(),
+        serde_json::to_value(b).unwrap_or_default(),
+    ) else {
+        return Vec::new();
+    };
+    a.into_iter()
+        .filter(|(field, value)| b.get(field) != Some(value))
+        .map(|(field, _)| field)
+        .collect()
+}
@@ -0,0 +1,331 @@
+#![doc = r#"Background jobs
+
+A small scheduler for periodic maintenance work. The binary calls [`spawn_scheduler`] after
+migrations; every tick it checks which jobs are due and runs them. Each execution is recorded
+in `job_runs` (see [`repository::list_job_runs`]) and surfaced by `GET /api/admin/jobs`.
+
+Jobs:
+- [`Job::SqliteMaintenance`] — `PRAGMA optimize` and `ANALYZE` once per day inside the quiet
+  window (`MAINTENANCE_WINDOW`, local time in the user timezone), plus `VACUUM` when the last
+  vacuum is older than `MAINTENANCE_VACUUM_DAYS`. SQLite never shrinks the database file on its
+  own, so vacuuming is what reclaims space after purges.
+- [`Job::TelemetryArchive`] — once per day inside the same window, moves rows of the tables in
+  [`ARCHIVED_TABLES`] older than `TELEMETRY_RETENTION_DAYS` into yearly archive tables
+  (`<table>_archive_<year>`), keeping the hot tables small for rolling-window aggregates.
+- [`Job::SchemaBackfill`] — every tick while a [`schema_change`](crate::schema_change) is in
+  its dual-write phase, copies up to [`BACKFILL_BATCHES_PER_RUN`] batches of
+  `SCHEMA_BACKFILL_BATCH` rows into the new column, and marks the change backfilled once no
+  rows are left. Not limited to the quiet window: batches are small enough to interleave with
+  normal writes.
+- [`Job::AlertEvaluation`] — once per day inside the quiet window, evaluates the alert rules
+  (`GET/PUT /api/settings/alerts`) over the nights ending at the last completed day (the
+  logical day of the run, see [`DayBoundary`](crate::models::DayBoundary), minus one). Each
+  firing rule is recorded in `alert_events` at most once per day and delivered through
+  [`notify`](crate::notify).
+
+Due checks and retention cutoffs use the application [`Clock`](crate::time::Clock), so a frozen clock
+(`FROZEN_TIME`) also freezes maintenance scheduling.
+
+[`repository::list_job_runs`]: crate::repository::list_job_runs
+"#]
+
+use crate::notify::{self, Notification};
+use crate::schema_change::{self, SchemaPhase};
+use crate::time::SharedClock;
+use crate::{config, db::Db, error::Error, repository};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, NaiveTime};
+use std::str::FromStr;
+
+/// How often the scheduler checks for due jobs.
+const TICK: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Minimum spacing between two scheduled runs of a daily job (one run per quiet window).
+const MIN_DAILY_JOB_SPACING_HOURS: i64 = 12;
+
+/// Append-only tables moved by [`Job::TelemetryArchive`], as `(table, timestamp column)`.
+/// Archived audit entries are still listed by `GET /api/admin/audit`.
+pub const ARCHIVED_TABLES: &[(&str, &str)] = &[
+    ("personalization_friction_events", "recorded_at"),
+    ("audit_log", "recorded_at"),
+];
+
+/// Upper bound on backfill statements per [`Job::SchemaBackfill`] run.
+pub const BACKFILL_BATCHES_PER_RUN: usize = 20;
+
+#[doc = r#"Known background jobs. Parses from the job name (e.g. `"sqlite_maintenance"`)."#]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    SqliteMaintenance,
+    TelemetryArchive,
+    SchemaBackfill,
+    AlertEvaluation,
+}
+
+impl Job {
+    /// All jobs, in the order the scheduler evaluates them.
+    pub const ALL: [Job; 4] = [
+        Job::SchemaBackfill,
+        Job::TelemetryArchive,
+        Job::SqliteMaintenance,
+        Job::AlertEvaluation,
+    ];
+
+    #[doc = r#"Return the job name stored in `job_runs.job`."#]
+    pub fn name(self) -> &'static str {
+        match self {
+            Job::SqliteMaintenance => "sqlite_maintenance",
+            Job::TelemetryArchive => "telemetry_archive",
+            Job::SchemaBackfill => "schema_backfill",
+            Job::AlertEvaluation => "alert_evaluation",
+        }
+    }
+}
+
+impl FromStr for Job {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Job::ALL.into_iter().find(|j| j.name() == s).ok_or(())
+    }
+}
+
+#[doc = r#"Daily quiet window in local time. Windows may wrap past midnight (e.g. `23:00-02:00`).
+
+# Example
+
+```rust
+# use sleep_api::jobs::QuietWindow;
+# use chrono::NaiveTime;
+let w: QuietWindow = "23:00-02:00".parse().unwrap();
+assert!(w.contains(NaiveTime::from_hms_opt(1, 30, 0).unwrap()));
+assert!(!w.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+```
+"#]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietWindow {
+    #[doc = r#"Return whether `t` falls in `[start, end)`."#]
+    pub fn contains(&self, t: NaiveTime) -> bool {
+        if self.start <= self.end {
+            t >= self.start && t < self.end
+        } else {
+            t >= self.start || t < self.end
+        }
+    }
+}
+
+impl FromStr for QuietWindow {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("invalid window {s:?}; expected HH:MM-HH:MM"))?;
+        let parse = |v: &str| {
+            NaiveTime::parse_from_str(v.trim(), "%H:%M")
+                .map_err(|_| format!("invalid window {s:?}; expected HH:MM-HH:MM"))
+        };
+        Ok(QuietWindow {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+#[doc = r#"Spawn the scheduler loop on the Tokio runtime.
+
+The first check happens after one tick so startup is not slowed by maintenance work.
+"#]
+pub fn spawn_scheduler(db: Db, clock: SharedClock) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            for job in Job::ALL {
+                let now_utc = clock.now_utc().naive_utc();
+                match is_due(&db, job, now_utc).await {
+                    Ok(true) => {
+                        if let Err(e) = run_job(&db, job, now_utc).await {
+                            tracing::warn!(error = ?e, job = job.name(), "failed to record job run");
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(error = ?e, job = job.name(), "job due check failed"),
+                }
+            }
+        }
+    })
+}
+
+async fn is_due(db: &Db, job: Job, now_utc: NaiveDateTime) -> Result<bool, Error> {
+    match job {
+        Job::SqliteMaintenance | Job::TelemetryArchive | Job::AlertEvaluation => {
+            let tz = repository::get_user_timezone(db).await;
+            let local = now_utc.and_utc().with_timezone(&tz).time();
+            if !config::maintenance_window().contains(local) {
+                return Ok(false);
+            }
+            let last = repository::last_successful_job_run(db, job.name(), "%").await?;
+            Ok(last.is_none_or(|at| {
+                now_utc - at >= ChronoDuration::hours(MIN_DAILY_JOB_SPACING_HOURS)
+            }))
+        }
+        Job::SchemaBackfill => {
+            for change in schema_change::CHANGES {
+                if change.phase(db).await? == SchemaPhase::DualWrite {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+    }
+}
+
+#[doc = r#"Run `job` as of `now_utc` and record the outcome in `job_runs`. Returns the run id.
+
+Failures are recorded on the run (status `error`) rather than returned; only a failure to
+record the run itself is an error.
+"#]
+pub async fn run_job(db: &Db, job: Job, now_utc: NaiveDateTime) -> Result<i64, Error> {
+    let id = repository::start_job_run(db, job.name()).await?;
+    let outcome = match job {
+        Job::SqliteMaintenance => sqlite_maintenance(db, now_utc).await,
+        Job::TelemetryArchive => telemetry_archive(db, now_utc).await,
+        Job::SchemaBackfill => schema_backfill(db).await,
+        Job::AlertEvaluation => alert_evaluation(db, now_utc).await,
+    };
+    match outcome {
+        Ok(detail) => {
+            tracing::info!(job = job.name(), %detail, "job finished");
+            repository::finish_job_run(db, id, "ok", Some(&detail)).await?;
+        }
+        Err(e) => {
+            tracing::warn!(error = ?e, job = job.name(), "job failed");
+            repository::finish_job_run(db, id, "error", Some(&e.to_string())).await?;
+        }
+    }
+    Ok(id)
+}
+
+async fn sqlite_maintenance(db: &Db, now_utc: NaiveDateTime) -> Result<String, Error> {
+    sqlx::query("PRAGMA optimize").execute(db).await?;
+    sqlx::query("ANALYZE").execute(db).await?;
+
+    let mut detail = String::from("optimize, analyze");
+    let vacuum_due = match config::maintenance_vacuum_days() {
+        0 => false,
+        days => {
+            let last =
+                repository::last_successful_job_run(db, Job::SqliteMaintenance.name(), "%vacuum%")
+                    .await?;
+            last.is_none_or(|at| now_utc - at >= ChronoDuration::days(days))
+        }
+    };
+    if vacuum_due {
+        let before = database_size_bytes(db).await?;
+        sqlx::query("VACUUM").execute(db).await?;
+        let after = database_size_bytes(db).await?;
+        detail.push_str(&format!(
+            ", vacuum (reclaimed {} bytes)",
+            (before - after).max(0)
+        ));
+    }
+    Ok(detail)
+}
+
+async fn telemetry_archive(db: &Db, now_utc: NaiveDateTime) -> Result<String, Error> {
+    let cutoff = now_utc - ChronoDuration::days(config::telemetry_retention_days());
+    let mut parts = Vec::new();
+    for (table, ts_column) in ARCHIVED_TABLES {
+        for (year, moved) in repository::archive_rows_before(db, table, ts_column, cutoff).await? {
+            parts.push(format!("{table}_archive_{year}: {moved}"));
+        }
+    }
+    Ok(if parts.is_empty() {
+        "nothing to archive".to_string()
+    } else {
+        format!("moved {}", parts.join(", "))
+    })
+}
+
+async fn schema_backfill(db: &Db) -> Result<String, Error> {
+    let batch = config::schema_backfill_batch();
+    let mut parts = Vec::new();
+    for change in schema_change::CHANGES {
+        if change.phase(db).await? != SchemaPhase::DualWrite {
+            continue;
+        }
+        let mut copied = 0;
+        for _ in 0..BACKFILL_BATCHES_PER_RUN {
+            let n = change.backfill_batch(db, batch).await?;
+            copied += n;
+            if n == 0 {
+                break;
+            }
+            // Let queued writers take the lock between batches.
+            tokio::task::yield_now().await;
+        }
+        let pending = change.pending_rows(db).await?;
+        if pending == 0 {
+            repository::set_schema_phase(db, change.name, SchemaPhase::Backfilled.name()).await?;
+            parts.push(format!("{}: {copied} rows, done", change.name));
+        } else {
+            parts.push(format!("{}: {copied} rows, {pending} pending", change.name));
+        }
+    }
+    Ok(if parts.is_empty() {
+        "nothing to backfill".to_string()
+    } else {
+        parts.join(", ")
+    })
+}
+
+async fn alert_evaluation(db: &Db, now_utc: NaiveDateTime) -> Result<String, Error> {
+    let rules = repository::get_alert_rules(db).await;
+    let enabled: Vec<_> = rules.rules.iter().filter(|r| r.enabled).collect();
+    if enabled.is_empty() {
+        return Ok("no alert rules".to_string());
+    }
+    let tz = repository::get_user_timezone(db).await;
+    let local = now_utc.and_utc().with_timezone(&tz).naive_local();
+    let as_of = repository::get_day_boundary(db).await.day_of(local) - ChronoDuration::days(1);
+
+    let mut fired = Vec::new();
+    for rule in &enabled {
+        let nights =
+            repository::daily_metric_values(db, rule.metric, rule.window_start(as_of), as_of)
+                .await?;
+        let Some(value) = rule.evaluate(&nights, as_of) else {
+            continue;
+        };
+        let message = rule.message(value);
+        let Some(id) = repository::insert_alert_event(db, &rule.id, as_of, value, &message).await?
+        else {
+            continue;
+        };
+        let delivered = notify::deliver(&Notification {
+            kind: "alert",
+            title: format!("Sleep alert: {}", rule.id),
+            body: message,
+        })
+        .await;
+        repository::set_alert_delivery(db, id, &delivered.join(",")).await?;
+        fired.push(rule.id.as_str());
+    }
+    Ok(format!(
+        "{as_of}: {} rules, fired [{}]",
+        enabled.len(),
+        fired.join(", ")
+    ))
+}
+
+async fn database_size_bytes(db: &Db) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<sqlx::Sqlite, i64>(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+    )
+    .fetch_one(db)
+    .await?)
+}
@@ -2,22 +2,72 @@
 SleepTracker API library
 
 This crate provides the building blocks of a small sleep‑tracking API built with Axum and SQLx.
-It exposes modules for HTTP routing, persistence, domain models and time handling.
+It exposes modules for HTTP routing, persistence, domain models and time handling. Models,
+validation and time helpers live in the `sleep-core` crate (no Axum or SQLx dependency, so a
+WASM frontend can share them) and are re-exported here under their original paths.
 
 Key modules:
+- [`admin_query`] — sandboxed read-only SQL for the admin query endpoint.
 - [`app`] — HTTP router wiring all routes.
+- [`attachments`] — files attached to a day, with thumbnails and audio durations.
+- [`calendar`] — day series over a date range for gap-aware queries.
+- [`completeness`] — per-day data completeness and complete-day streaks.
+- [`csp_reports`] — collection and summary of Content-Security-Policy violation reports.
+- [`dashboard`] — aggregated home page payload.
+- [`deprecation`] — call counters for legacy and aliased endpoints.
+- [`data_export`] — streaming JSON/CSV export of all records (`GET /api/export`).
 - [`db`] — database pool and connection utilities.
-- [`models`] — input/output types with validation.
+- [`domain`] — the validation error type (re-exported from `sleep-core`).
+- [`download`] — resumable file downloads (`Range` / `If-Range`) for backups and exports.
+- [`error`] — the crate [`Error`] for library consumers; HTTP error types and their JSON / problem+json bodies.
+- [`events`] — typed domain events emitted by every mutation.
+- [`export`] — full exports with a signed integrity manifest (`sleepctl export`).
+- [`extract`] — request extractors (date ranges, path params) with uniform errors.
+- [`feeds`] — token-protected Atom feed of weekly reports.
+- [`features`] — per-module feature flags gating optional subsystems.
+- [`handlers`] — handler logic callable without HTTP (validation, duration recompute).
+- [`i18n`] — localized report and insight strings (Accept-Language, en/ja).
+- [`importers`] — parsers for third-party exports (Withings, Fitbit).
+- [`integrity`] — database corruption checks, degraded read-only mode, and salvage.
+- [`jobs`] — background job scheduler (database maintenance, alert evaluation).
+- [`middleware`] — session extractors, quotas, rate limits, and the composable middleware stack.
+- [`models`] — input/output types with validation (re-exported from `sleep-core`).
+- [`negotiate`] — JSON/CSV response content negotiation.
+- [`notify`] — outgoing notification channels (log, signed webhook).
+- [`now`] — current-status endpoints (bedtime countdown).
+- [`plan`] — weekly bed/wake plan around busy times.
+- [`public`] — opt-in unauthenticated summary for embedding.
+- [`redaction`] — per-audience redaction of shared, public and exported data.
+- [`reload`] — config reload on `SIGHUP` without restarting.
+- [`reports`] — weekly report comparing an ISO week with the one before.
 - [`repository`] — persistence operations.
-- [`time`] — time and duration helpers including DST‑aware computations.
+- [`schema_change`] — expand/contract helpers for downtime-free column moves.
+- [`server`] — one-call server bootstrap ([`Server::builder`]) with a shutdown handle.
+- `simulator` — generated sleep data for demos and soak tests (`simulator` cargo feature).
+- [`smoke`] — end-to-end smoke check of a live deployment (`sleepctl smoke`).
+- [`stats`] — numeric routines behind trends (seasonal decomposition).
+- [`tenant`] — optional multi-tenant mode (one SQLite file per tenant).
+- [`time`] — time and duration helpers including DST‑aware computations (re-exported from
+  `sleep-core`).
 - [`trends`] — aggregation endpoints.
 	- Includes `sleep-bars`, `summary`, and `personalization` trend routes.
+- [`typegen`] — TypeScript declarations for the UI generated from the models.
 
 Why: use this crate to embed the API server in your binary, or reuse its types and helpers like [`compute_duration_min`].
 
 # Example
 
-Bootstrapping a Router:
+Running the server as the binary does (connect, migrate, serve on `API_BIND_ADDR`):
+
+```rust,no_run
+# async fn demo() -> Result<(), sleep_api::Error> {
+let server = sleep_api::Server::builder().build().await?;
+server.wait().await?;
+# Ok(())
+# }
+```
+
+Or bootstrapping just a Router to mount in your own app:
 
 ```rust,no_run
 # use std::error::Error;
@@ -36,25 +86,94 @@ Additional references:
 
 See also: [`time`], [`repository`], and [`models`].
 
+[`admin_query`]: crate::admin_query
 [`app`]: crate::app
+[`calendar`]: crate::calendar
+[`completeness`]: crate::completeness
+[`csp_reports`]: crate::csp_reports
+[`dashboard`]: crate::dashboard
 [`db`]: crate::db
+[`domain`]: crate::domain
+[`error`]: crate::error
+[`events`]: crate::events
+[`export`]: crate::export
+[`extract`]: crate::extract
+[`features`]: crate::features
+[`handlers`]: crate::handlers
+[`i18n`]: crate::i18n
+[`importers`]: crate::importers
+[`jobs`]: crate::jobs
+[`middleware`]: crate::middleware
 [`models`]: crate::models
+[`negotiate`]: crate::negotiate
+[`notify`]: crate::notify
+[`now`]: crate::now
+[`plan`]: crate::plan
+[`public`]: crate::public
+[`redaction`]: crate::redaction
+[`reload`]: crate::reload
+[`reports`]: crate::reports
 [`repository`]: crate::repository
+[`schema_change`]: crate::schema_change
+[`server`]: crate::server
+[`Server::builder`]: crate::server::Server::builder
+[`smoke`]: crate::smoke
+[`stats`]: crate::stats
+[`tenant`]: crate::tenant
 [`time`]: crate::time
 [`trends`]: crate::trends
+[`typegen`]: crate::typegen
 [`compute_duration_min`]: crate::time::compute_duration_min
+[`Error`]: crate::Error
 "#]
 
+pub mod admin_query;
 pub mod app;
+pub mod attachments;
 pub mod auth;
+pub mod calendar;
+pub mod completeness;
 pub mod config;
+pub mod csp_reports;
+pub mod dashboard;
+pub mod data_export;
 pub mod db;
-pub mod domain;
-mod error;
-mod handlers;
+pub mod deprecation;
+pub mod download;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod extract;
+pub mod features;
+pub mod feeds;
+pub mod handlers;
+pub mod i18n;
+pub mod importers;
+pub mod integrity;
+pub mod jobs;
 pub mod middleware;
-pub mod models;
+pub mod negotiate;
+pub mod notify;
+pub mod now;
+pub mod plan;
+pub mod public;
+pub mod redaction;
+pub mod reload;
+pub mod reports;
 pub mod repository;
+pub mod schema_change;
 pub mod security;
-pub mod time;
+pub mod server;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+pub mod smoke;
+pub mod stats;
+pub mod tenant;
 pub mod trends;
+pub mod typegen;
+
+pub use error::Error;
+pub use server::Server;
+
+// Kept at their old paths so `sleep_api::models::...` and friends keep working.
+pub use sleep_core::{domain, models, time};
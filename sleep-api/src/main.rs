@@ -1,29 +1,83 @@
+mod admin_query;
 mod app;
+mod attachments;
 mod auth;
+mod calendar;
+mod completeness;
 mod config;
+mod csp_reports;
+mod dashboard;
+mod data_export;
 mod db;
-mod domain;
+mod deprecation;
+mod download;
 mod error;
+mod events;
+// `verify_export` is used by `sleepctl`, not by the server.
+#[allow(dead_code)]
+mod export;
+mod extract;
+mod features;
+mod feeds;
 mod handlers;
+mod i18n;
+mod importers;
+// `salvage` is used by `sleepctl`, not by the server.
+#[allow(dead_code)]
+mod integrity;
+mod jobs;
 mod middleware;
-mod models;
+mod negotiate;
+mod notify;
+mod now;
+mod plan;
+mod public;
+mod redaction;
+mod reload;
+mod reports;
 mod repository;
+mod schema_change;
 mod security;
-mod time;
+mod server;
+#[cfg(feature = "simulator")]
+mod simulator;
+mod stats;
+mod tenant;
 mod trends;
+mod typegen;
 
-use crate::db::connect;
+use sleep_core::{domain, models, time};
 use tokio::net::TcpListener;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt::init();
-    let pool = connect().await?;
-    sqlx::migrate!("../migrations").run(&pool).await?;
-    let app = app::router(pool);
+    if let Some(path) = config::load_config_file() {
+        tracing::info!(path = %path.display(), "loaded config file; SIGHUP reloads it");
+    }
+    tokio::spawn(reload::watch_sighup(config::clock()));
+    if let Some(at) = config::frozen_time() {
+        tracing::warn!(%at, "clock frozen by FROZEN_TIME");
+    }
+    let Some(mode) = config::tenant_mode() else {
+        server::Server::builder().build().await?.wait().await?;
+        return Ok(());
+    };
+    let tenants = config::tenant_names();
+    tracing::info!(?mode, count = tenants.len(), "multi-tenant mode");
+    let app = tenant::router(tenant::TenantRegistry::new(
+        mode,
+        config::tenant_data_dir(),
+        tenants,
+        reload::SessionKey::from_config(),
+    ));
     let bind_addr = config::api_bind_addr();
     let listener = TcpListener::bind(&bind_addr).await?;
     tracing::info!(%bind_addr, "API listening");
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
@@ -2,8 +2,18 @@
 
 Provides extractors to require a valid session:
 - [`RequireSessionJson`] → returns `401` JSON (`{"error":"unauthorized"}`) on failure
+- [`RequireSessionCookie`] → same, but only accepts the browser session cookie (token management)
 
-These extractors read the encrypted `__Host-session` cookie via [`PrivateCookieJar`]. They require that the application state implements [`FromRef`] for [`Key`], which is provided by [`app::AppState`].
+These extractors read the encrypted `__Host-session` cookie via [`PrivateCookieJar`]. With the
+[`AuthDisabled`] request extension set, both accept every request. They require that the application state implements [`FromRef`] for [`Key`], [`Db`] and [`SharedClock`], which is provided by [`app::AppState`].
+
+# API tokens
+
+A request carrying `Authorization: Bearer <secret>` is authenticated by that token
+alone (the cookie is ignored):
+- unknown or expired token → `401` (`{"error":"unauthorized"}`)
+- token scope does not cover the method/path (see [`ApiScope::allows`]) → `403` (`{"error":"insufficient_scope"}`)
+- otherwise the token's `last_used_at` is stamped and the user id is `token:<id>`
 
 # Example
 
@@ -23,6 +33,8 @@ See also:
 - [`crate::security::csrf`] for CSRF protection guard
 "#]
 
+use std::str::FromStr;
+
 use axum::extract::{FromRef, FromRequestParts};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
@@ -30,6 +42,22 @@ use axum_extra::extract::cookie::{Key, PrivateCookieJar};
 use serde_json::json;
 
 use crate::auth::{UserId, current_user_from_cookie};
+use crate::db::Db;
+use crate::models::ApiScope;
+use crate::repository;
+use crate::security::token::{bearer_token, hash_secret};
+use crate::time::SharedClock;
+
+#[derive(Debug, Clone, Copy)]
+#[doc = r#"Request extension that makes the session extractors accept every request.
+
+Inserted by [`ServerBuilder::disable_auth_for_tests`](crate::server::ServerBuilder::disable_auth_for_tests)
+only; requests then run as the user `auth-disabled`. Never set it on a reachable server.
+"#]
+pub struct AuthDisabled;
+
+/// User id of requests accepted because of [`AuthDisabled`].
+const AUTH_DISABLED_USER: &str = "auth-disabled";
 
 /// Extractor that requires an authenticated session for JSON APIs.
 /// On failure, returns 401 with a JSON error payload.
@@ -38,6 +66,64 @@ pub struct RequireSessionJson {
 }
 
 impl<S> FromRequestParts<S> for RequireSessionJson
+where
+    S: Send + Sync,
+    Key: FromRef<S>,
+    Db: FromRef<S>,
+    SharedClock: FromRef<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        if parts.extensions.get::<AuthDisabled>().is_some() {
+            return Ok(Self {
+                _user_id: AUTH_DISABLED_USER.into(),
+            });
+        }
+        if let Some(secret) = bearer_token(&parts.headers) {
+            let db = Db::from_ref(state);
+            let now = SharedClock::from_ref(state).now_utc().naive_utc();
+            let token = repository::use_api_token(&db, &hash_secret(secret), now)
+                .await
+                .map_err(|e| {
+                    tracing::error!(error = ?e, "api token lookup failed");
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                })?
+                .filter(|t| t.expires_at > now)
+                .ok_or_else(unauthorized)?;
+            let allowed = ApiScope::from_str(&token.scope)
+                .is_ok_and(|scope| scope.allows(parts.method.as_str(), parts.uri.path()));
+            if !allowed {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    axum::Json(json!({"error":"insufficient_scope"})),
+                )
+                    .into_response());
+            }
+            return Ok(Self {
+                _user_id: format!("token:{}", token.id),
+            });
+        }
+        let jar = PrivateCookieJar::from_request_parts(parts, state)
+            .await
+            .map_err(|_| unauthorized())?;
+        match current_user_from_cookie(&jar) {
+            Some(uid) => Ok(Self { _user_id: uid }),
+            None => Err(unauthorized()),
+        }
+    }
+}
+
+/// Extractor that requires the browser session cookie; API tokens are rejected with 401.
+/// Guards token management so a token can never mint or revoke tokens.
+pub struct RequireSessionCookie {
+    pub _user_id: UserId,
+}
+
+impl<S> FromRequestParts<S> for RequireSessionCookie
 where
     S: Send + Sync,
     Key: FromRef<S>,
@@ -48,6 +134,14 @@ where
         parts: &mut axum::http::request::Parts,
         state: &S,
     ) -> Result<Self, Self::Rejection> {
+        if parts.extensions.get::<AuthDisabled>().is_some() {
+            return Ok(Self {
+                _user_id: AUTH_DISABLED_USER.into(),
+            });
+        }
+        if bearer_token(&parts.headers).is_some() {
+            return Err(unauthorized());
+        }
         let jar = PrivateCookieJar::from_request_parts(parts, state)
             .await
             .map_err(|_| unauthorized())?;
@@ -0,0 +1,122 @@
+#![doc = r#"Response compression
+
+[`gzip`] compresses responses for clients sending `Accept-Encoding: gzip`. It is off in the
+server's stack and enabled by embedders through
+[`MiddlewareConfig::compression`](crate::middleware::stack::MiddlewareConfig::compression).
+
+Only bodies of known length between [`MIN_BYTES`] and [`MAX_BYTES`] with a textual content type
+(JSON, CSV, HTML, JavaScript, SVG, ...) are compressed, so event streams and file downloads pass
+through untouched. Responses advertising `Accept-Ranges` (resumable downloads such as
+`GET /api/export`) are never compressed, since their byte offsets count the uncompressed body. Compressed responses carry `Content-Encoding: gzip` and
+`Vary: Accept-Encoding`, and a strong `ETag` becomes weak since the bytes differ.
+"#]
+
+use std::io::Write;
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use flate2::{Compression, write::GzEncoder};
+
+/// Smaller bodies are sent as they are; gzip framing would outweigh the savings.
+pub const MIN_BYTES: u64 = 1024;
+
+/// Larger bodies are sent as they are rather than buffered for compression.
+pub const MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+#[doc = r#"Gzip the response when the client accepts it and the body is worth compressing."#]
+pub async fn gzip(req: Request, next: Next) -> Response {
+    let accepts_gzip = accepts_gzip(req.headers());
+    let res = next.run(req).await;
+    if !accepts_gzip || !compressible(&res) {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BYTES as usize).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "reading response body for compression failed");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let compressed = match encoder.write_all(&bytes).and_then(|_| encoder.finish()) {
+        Ok(compressed) => compressed,
+        Err(e) => {
+            tracing::warn!(error = %e, "gzip compression failed");
+            return Response::from_parts(parts, Body::from(bytes));
+        }
+    };
+
+    let headers = &mut parts.headers;
+    headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    headers.remove(header::CONTENT_LENGTH);
+    headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    if let Some(etag) = headers.get(header::ETAG).and_then(|v| v.to_str().ok())
+        && !etag.starts_with("W/")
+        && let Ok(weak) = HeaderValue::from_str(&format!("W/{etag}"))
+    {
+        headers.insert(header::ETAG, weak);
+    }
+    Response::from_parts(parts, Body::from(compressed))
+}
+
+/// Whether `Accept-Encoding` lists gzip (or `*`) without `q=0`.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            let refused = params.any(|p| {
+                p.strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !refused
+        })
+}
+
+/// Whether `res` has a textual, not yet encoded body of a size worth compressing.
+fn compressible(res: &Response) -> bool {
+    if matches!(
+        res.status(),
+        StatusCode::NO_CONTENT | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED
+    ) {
+        return false;
+    }
+    let headers = res.headers();
+    if headers.contains_key(header::CONTENT_ENCODING)
+        || headers.contains_key(header::CONTENT_RANGE)
+        || headers.contains_key(header::ACCEPT_RANGES)
+    {
+        return false;
+    }
+    let textual = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| {
+            let mime = ct.split(';').next().unwrap_or_default().trim();
+            mime.starts_with("text/") && mime != "text/event-stream"
+                || mime.ends_with("/json")
+                || mime.ends_with("+json")
+                || mime.ends_with("/javascript")
+                || mime.ends_with("/xml")
+                || mime.ends_with("+xml")
+        })
+        .unwrap_or(false);
+    textual
+        && res
+            .body()
+            .size_hint()
+            .exact()
+            .is_some_and(|len| (MIN_BYTES..=MAX_BYTES).contains(&len))
+}
@@ -1,9 +1,14 @@
 #![doc = r#"Middleware utilities
 
-Authentication-related extractors for protecting routes.
+Authentication-related extractors for protecting routes, usage quotas, rate limits, and the
+composable stack that applies them.
 
 Modules:
 - [`auth_layer`] — extractors that require a valid session (`__Host-session`)
+- [`compression`] — gzip for responses to clients that accept it
+- [`quota`] — soft limits on request rate, body size, and daily entries
+- [`rate_limit`] — per-address and per-account limits on failed logins and writes
+- [`stack`] — [`MiddlewareConfig`](stack::MiddlewareConfig), which layers wrap the router
 
 See also:
 - [`crate::security::csrf`] for CSRF enforcement on mutating requests
@@ -11,3 +16,7 @@ See also:
 "#]
 
 pub mod auth_layer;
+pub mod compression;
+pub mod quota;
+pub mod rate_limit;
+pub mod stack;
@@ -0,0 +1,254 @@
+#![doc = r#"Usage quotas
+
+Soft limits for shared or exposed instances, configured by [`config::quotas`] and applied to
+every route by [`enforce`]:
+
+- `api_calls_per_min` — `/api` requests per minute, counted per API token for bearer requests,
+  per session user otherwise, and per client address ([`client_ip`]) for everything else
+  (including unknown tokens). Exceeding it returns `429 {code:"quota_exceeded"}` with a
+  `Retry-After` header. `/api/health` and the login routes are never counted, so callers
+  without a session cannot spend the budget the owner needs to sign in.
+- `max_body_bytes` — request bodies (JSON and import uploads alike) above the limit return
+  `413 {code:"payload_too_large"}`.
+- `sessions_per_day` — creating a sleep session returns `429` once its wake date already has
+  that many sessions. The limit travels to the handler as [`SessionsPerDay`] and is checked by
+  [`handlers::create_sleep`] once the date is known, so inferred dates (`?infer_date=true`) and
+  sessions recorded by the sleep timer count too.
+- `notes_per_day` — `POST /api/note` returns `429` once the `date` in the body already has that
+  many notes.
+
+All limits are off by default.
+
+[`config::quotas`]: crate::config::quotas
+[`handlers::create_sleep`]: crate::handlers::create_sleep
+[`client_ip`]: crate::security::device::client_ip
+"#]
+
+use crate::auth::current_user_from_cookie;
+use crate::middleware::rate_limit::Buckets;
+use crate::reload::{Reloadable, SessionKey};
+use crate::security::device::client_ip;
+use crate::security::token::{bearer_token, hash_secret};
+use crate::{db::Db, error::ApiError, repository};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{Extensions, HeaderMap, HeaderValue, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::PrivateCookieJar;
+use chrono::NaiveDate;
+use sqlx::Sqlite;
+use std::time::Instant;
+
+/// Routes never counted against `api_calls_per_min`.
+const UNCOUNTED_PATHS: &[&str] = &["/api/health", "/api/login", "/api/login.json"];
+
+/// Body size read when only the notes limit needs the `date` (axum's default body limit).
+const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[doc = r#"Configured limits; `None` disables a limit. [`Default`] disables all of them."#]
+pub struct Quotas {
+    pub sessions_per_day: Option<u64>,
+    pub notes_per_day: Option<u64>,
+    pub max_body_bytes: Option<u64>,
+    pub api_calls_per_min: Option<u64>,
+}
+
+#[derive(Clone)]
+#[doc = r#"State of the [`enforce`] middleware: limits plus per-caller call counters."#]
+pub struct QuotaState {
+    quotas: Reloadable<Quotas>,
+    db: Db,
+    key: SessionKey,
+    calls: Buckets,
+}
+
+impl QuotaState {
+    /// Quota state for one router (one database, one session key).
+    pub fn new(quotas: Reloadable<Quotas>, db: Db, key: SessionKey) -> Self {
+        QuotaState {
+            quotas,
+            db,
+            key,
+            calls: Buckets::default(),
+        }
+    }
+
+    /// Bucket of a request's caller: its API token, else its session user, else its client
+    /// address.
+    async fn caller(&self, headers: &HeaderMap, extensions: &Extensions) -> String {
+        if let Some(secret) = bearer_token(headers) {
+            let token =
+                sqlx::query_scalar::<Sqlite, i64>("SELECT id FROM api_tokens WHERE token_hash = ?")
+                    .bind(hash_secret(secret))
+                    .fetch_optional(&self.db)
+                    .await;
+            if let Ok(Some(id)) = token {
+                return format!("token:{id}");
+            }
+        } else {
+            let jar = PrivateCookieJar::from_headers(headers, self.key.current());
+            if let Some(user) = current_user_from_cookie(&jar) {
+                return format!("user:{user}");
+            }
+        }
+        match client_ip(headers, extensions) {
+            Some(ip) => format!("ip:{ip}"),
+            None => "ip:unknown".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Request extension set by [`enforce`] when `sessions_per_day` is configured; the
+[`EditLock`](crate::handlers::EditLock) extractor picks it up."#]
+pub struct SessionsPerDay(pub u64);
+
+#[derive(serde::Deserialize)]
+struct DatedBody {
+    date: NaiveDate,
+}
+
+#[doc = r#"Middleware applying [`Quotas`] before the request reaches its handler."#]
+pub async fn enforce(State(state): State<QuotaState>, req: Request, next: Next) -> Response {
+    match check(&state, req).await {
+        Ok(req) => next.run(req).await,
+        Err(resp) => resp,
+    }
+}
+
+async fn check(state: &QuotaState, mut req: Request) -> Result<Request, Response> {
+    let quotas = state.quotas.get();
+    if let Some(limit) = quotas.sessions_per_day {
+        req.extensions_mut().insert(SessionsPerDay(limit));
+    }
+    let path = req.uri().path();
+
+    if let Some(limit) = quotas.api_calls_per_min
+        && path.starts_with("/api/")
+        && !UNCOUNTED_PATHS.contains(&path)
+    {
+        let caller = state.caller(req.headers(), req.extensions()).await;
+        if let Err(retry) = state.calls.check(&caller, limit, Instant::now()) {
+            let secs = retry.as_secs().max(1);
+            return Err((
+                [(header::RETRY_AFTER, HeaderValue::from(secs))],
+                ApiError::QuotaExceeded(format!("more than {limit} API calls per minute")),
+            )
+                .into_response());
+        }
+    }
+
+    if let Some(max) = quotas.max_body_bytes {
+        let declared = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        if declared.is_some_and(|len| len > max) {
+            return Err(too_large(max));
+        }
+    }
+
+    let notes_per_day = match (req.method(), path) {
+        (&Method::POST, "/api/note") => quotas.notes_per_day,
+        _ => None,
+    };
+    if notes_per_day.is_none() && (quotas.max_body_bytes.is_none() || req.method() == Method::GET) {
+        return Ok(req);
+    }
+
+    // Buffer the body to enforce its size without a Content-Length and to read its date.
+    let limit = quotas
+        .max_body_bytes
+        .map_or(DEFAULT_BODY_LIMIT, |max| max as usize);
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, limit)
+        .await
+        .map_err(|_| too_large(limit as u64))?;
+
+    if let Some(limit) = notes_per_day
+        && let Ok(DatedBody { date }) = serde_json::from_slice(&bytes)
+    {
+        let count = repository::count_notes_on(&state.db, date)
+            .await
+            .map_err(|e| ApiError::from(e).into_response())?;
+        if count as u64 >= limit {
+            return Err(ApiError::QuotaExceeded(format!(
+                "at most {limit} notes per day ({date} is full)"
+            ))
+            .into_response());
+        }
+    }
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+fn too_large(max: u64) -> Response {
+    ApiError::PayloadTooLarge(format!("request body exceeds {max} bytes")).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn caller(state: &QuotaState, req: Request) -> String {
+        state.caller(req.headers(), req.extensions()).await
+    }
+
+    #[tokio::test]
+    async fn callers_without_a_session_are_keyed_by_address() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let state = QuotaState::new(
+            Reloadable::fixed(Quotas::default()),
+            db,
+            axum_extra::extract::cookie::Key::generate().into(),
+        );
+        let from = |addr: &str| {
+            let mut req = Request::new(Body::empty());
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo::<std::net::SocketAddr>(
+                    addr.parse().unwrap(),
+                ));
+            req
+        };
+        assert_eq!(caller(&state, from("192.0.2.1:4000")).await, "ip:192.0.2.1");
+        assert_eq!(caller(&state, from("192.0.2.1:4001")).await, "ip:192.0.2.1");
+        assert_eq!(caller(&state, from("192.0.2.2:4000")).await, "ip:192.0.2.2");
+        assert_eq!(
+            caller(&state, Request::new(Body::empty())).await,
+            "ip:unknown"
+        );
+    }
+
+    #[tokio::test]
+    async fn bearer_callers_are_keyed_by_token() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE api_tokens (id INTEGER PRIMARY KEY, token_hash TEXT)")
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO api_tokens (id, token_hash) VALUES (7, ?)")
+            .bind(hash_secret("slt_known"))
+            .execute(&db)
+            .await
+            .unwrap();
+        let state = QuotaState::new(
+            Reloadable::fixed(Quotas::default()),
+            db,
+            axum_extra::extract::cookie::Key::generate().into(),
+        );
+        let bearer = |secret: &str| {
+            let mut req = Request::new(Body::empty());
+            req.headers_mut().insert(
+                header::AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {secret}")).unwrap(),
+            );
+            req
+        };
+        assert_eq!(caller(&state, bearer("slt_known")).await, "token:7");
+        assert_eq!(caller(&state, bearer("slt_unknown")).await, "ip:unknown");
+    }
+}
@@ -0,0 +1,260 @@
+#![doc = r#"Request rate limits
+
+Per-minute limits against credential stuffing and write floods, configured by
+[`config::rate_limits`] and applied to every route by [`enforce`]:
+
+- `login_per_ip` / `login_per_account` — failed `POST /api/login` and `POST /api/login.json`
+  attempts per client address and per account email (case-insensitive). Once either budget is
+  spent, further attempts from that address or for that account are refused until the window
+  ends, even with the right password. Each attempt takes a slot before it reaches the handler,
+  so concurrent attempts cannot overrun the budget; the slot is given back unless the login
+  fails with `401`, so successful logins do not count.
+- `writes_per_ip` — `POST`/`PUT`/`PATCH`/`DELETE` requests to other `/api` routes per client
+  address.
+
+Refused requests get `429 {code:"rate_limited"}` with a `Retry-After` header. The client
+address is the TCP peer, or the first `X-Forwarded-For` entry with `TRUST_PROXY_HEADERS` (see
+[`client_ip`]); requests without a known address share one bucket. Counters live in memory and
+start over on restart.
+
+[`config::rate_limits`]: crate::config::rate_limits
+[`client_ip`]: crate::security::device::client_ip
+"#]
+
+use crate::error::ApiError;
+use crate::reload::Reloadable;
+use crate::security::device::client_ip;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Length of a rate-limit window.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Routes whose failed attempts count towards the login limits.
+const LOGIN_PATHS: &[&str] = &["/api/login", "/api/login.json"];
+
+/// Largest login body read to find the account email.
+const MAX_LOGIN_BODY: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Configured limits per minute; `None` disables a limit.
+
+[`Default`] allows 10 failed logins per address and 5 per account, and leaves writes
+unlimited."#]
+pub struct RateLimits {
+    pub login_per_ip: Option<u64>,
+    pub login_per_account: Option<u64>,
+    pub writes_per_ip: Option<u64>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            login_per_ip: Some(10),
+            login_per_account: Some(5),
+            writes_per_ip: None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+#[doc = r#"Per-key request counters over one-minute windows, used by [`enforce`] and by the
+per-minute call quota of [`quota::enforce`](crate::middleware::quota::enforce)."#]
+pub struct Buckets(Arc<Mutex<HashMap<String, (Instant, u64)>>>);
+
+impl Buckets {
+    /// Count a request for `key`; on rejection returns how long until the window resets.
+    pub fn check(&self, key: &str, limit: u64, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > 4096 {
+            buckets.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        }
+        let (start, used) = buckets.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= WINDOW {
+            *start = now;
+            *used = 0;
+        }
+        if *used >= limit {
+            return Err(WINDOW.saturating_sub(now.duration_since(*start)));
+        }
+        *used += 1;
+        Ok(())
+    }
+
+    /// Give back a request counted for `key` at `counted_at`, unless its window has since reset.
+    pub fn refund(&self, key: &str, counted_at: Instant) {
+        let mut buckets = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((start, used)) = buckets.get_mut(key)
+            && *start <= counted_at
+        {
+            *used = used.saturating_sub(1);
+        }
+    }
+}
+
+#[derive(Clone)]
+#[doc = r#"State of the [`enforce`] middleware: limits plus per-key counters."#]
+pub struct RateLimitState {
+    limits: Reloadable<RateLimits>,
+    buckets: Buckets,
+}
+
+impl RateLimitState {
+    /// Rate-limit state for one router.
+    pub fn new(limits: Reloadable<RateLimits>) -> Self {
+        RateLimitState {
+            limits,
+            buckets: Buckets::default(),
+        }
+    }
+}
+
+#[doc = r#"Middleware applying [`RateLimits`] before the request reaches its handler."#]
+pub async fn enforce(State(state): State<RateLimitState>, req: Request, next: Next) -> Response {
+    let limits = state.limits.get();
+    let path = req.uri().path();
+    let ip = client_ip(req.headers(), req.extensions())
+        .map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+    let now = Instant::now();
+
+    if req.method() == Method::POST && LOGIN_PATHS.contains(&path) {
+        if limits.login_per_ip.is_none() && limits.login_per_account.is_none() {
+            return next.run(req).await;
+        }
+        // The account is in the body; buffer it (logins are tiny) and hand it on.
+        let (parts, body) = req.into_parts();
+        let Ok(bytes) = axum::body::to_bytes(body, MAX_LOGIN_BODY).await else {
+            return ApiError::PayloadTooLarge(format!("login body exceeds {MAX_LOGIN_BODY} bytes"))
+                .into_response();
+        };
+        let keys: Vec<(String, u64)> = [
+            limits.login_per_ip.map(|n| (format!("login-ip:{ip}"), n)),
+            limits.login_per_account.and_then(|n| {
+                login_email(&parts.headers, &bytes)
+                    .map(|email| (format!("login-account:{email}"), n))
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        for (i, (key, limit)) in keys.iter().enumerate() {
+            if let Err(retry) = state.buckets.check(key, *limit, now) {
+                for (counted, _) in &keys[..i] {
+                    state.buckets.refund(counted, now);
+                }
+                return too_many(retry, "too many failed logins; try again later");
+            }
+        }
+        let res = next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+        if res.status() != StatusCode::UNAUTHORIZED {
+            for (key, _) in &keys {
+                state.buckets.refund(key, now);
+            }
+        }
+        return res;
+    }
+
+    if let Some(limit) = limits.writes_per_ip
+        && path.starts_with("/api/")
+        && matches!(
+            *req.method(),
+            Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+        )
+        && let Err(retry) = state.buckets.check(&format!("write:{ip}"), limit, now)
+    {
+        return too_many(retry, &format!("more than {limit} writes per minute"));
+    }
+    next.run(req).await
+}
+
+/// Account email of a JSON or form login body, trimmed and lowercased.
+fn login_email(headers: &HeaderMap, body: &[u8]) -> Option<String> {
+    let is_form = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+    let email = if is_form {
+        std::str::from_utf8(body)
+            .ok()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("email="))
+            .map(|v| {
+                percent_encoding::percent_decode_str(&v.replace('+', " "))
+                    .decode_utf8_lossy()
+                    .into_owned()
+            })?
+    } else {
+        serde_json::from_slice::<serde_json::Value>(body)
+            .ok()?
+            .get("email")?
+            .as_str()?
+            .to_string()
+    };
+    let email = email.trim().to_lowercase();
+    (!email.is_empty()).then_some(email)
+}
+
+fn too_many(retry: Duration, message: &str) -> Response {
+    let secs = retry.as_secs().max(1);
+    (
+        [(header::RETRY_AFTER, HeaderValue::from(secs))],
+        ApiError::RateLimited(message.to_string()),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refunds_stay_in_their_window() {
+        let buckets = Buckets::default();
+        let t0 = Instant::now();
+        assert!(buckets.check("a", 1, t0).is_ok());
+        let retry = buckets
+            .check("a", 1, t0 + Duration::from_secs(20))
+            .unwrap_err();
+        assert_eq!(retry, Duration::from_secs(40));
+        // Other keys have their own bucket.
+        assert!(buckets.check("b", 1, t0).is_ok());
+        buckets.refund("a", t0);
+        assert!(buckets.check("a", 1, t0).is_ok());
+        // The window reset after the request was counted: the new window keeps its count.
+        assert!(buckets.check("a", 1, t0 + WINDOW).is_ok());
+        buckets.refund("a", t0);
+        assert!(buckets.check("a", 1, t0 + WINDOW).is_err());
+        buckets.refund("missing", t0);
+    }
+
+    #[test]
+    fn login_email_from_json_and_form() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            login_email(
+                &headers,
+                br#"{"email":" Admin@Example.com ","password":"x"}"#
+            ),
+            Some("admin@example.com".into())
+        );
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        assert_eq!(
+            login_email(&headers, b"password=x&email=admin%40example.com"),
+            Some("admin@example.com".into())
+        );
+        assert_eq!(login_email(&headers, b"password=x"), None);
+    }
+}
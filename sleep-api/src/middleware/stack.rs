@@ -0,0 +1,222 @@
+#![doc = r#"Composable middleware stack
+
+[`MiddlewareConfig`] chooses which layers wrap the router built by
+[`app::router_with_middleware`]. The server uses [`MiddlewareConfig::from_config`]; an
+application embedding the API can switch off what it already does itself (e.g. security
+headers set by its own router, or rate limits at a gateway) or add what the server leaves out
+by default:
+
+| Layer | Default | What it does |
+|---|---|---|
+| `security_headers` | on | [`security::headers::apply`] with the `hsts` and `csp` settings |
+| `csrf` | on | double-submit check of [`CsrfGuard`] on mutating routes |
+| `rate_limits` | on | [`rate_limit::enforce`], limits from [`config::rate_limits`] |
+| `quotas` | on | [`quota::enforce`], limits from [`config::quotas`] |
+| `compression` | off | [`compression::gzip`] for clients sending `Accept-Encoding: gzip` |
+| `tracing` | off | one `tracing` span and log line per request ([`trace_requests`]) |
+
+Turning `csrf` off only makes sense when the embedding application protects mutating requests
+itself; session cookies are otherwise exposed to cross-site requests. Refusing writes while the
+database is degraded ([`integrity::refuse_writes_when_degraded`]) is not optional.
+
+# Example
+
+```rust,no_run
+# async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+use sleep_api::middleware::stack::MiddlewareConfig;
+
+let db = sleep_api::db::connect().await?;
+let middleware = MiddlewareConfig::from_config()
+    .security_headers(false)
+    .compression(true)
+    .tracing(true);
+let api = sleep_api::app::router_with_middleware(
+    sleep_api::app::AppState::new(db, sleep_api::config::session_key()),
+    middleware,
+);
+let app = axum::Router::new().merge(api);
+# let _ = app;
+# Ok(())
+# }
+```
+
+[`app::router_with_middleware`]: crate::app::router_with_middleware
+[`security::headers::apply`]: crate::security::headers::apply
+[`CsrfGuard`]: crate::security::csrf::CsrfGuard
+[`rate_limit::enforce`]: crate::middleware::rate_limit::enforce
+[`quota::enforce`]: crate::middleware::quota::enforce
+[`compression::gzip`]: crate::middleware::compression::gzip
+[`config::rate_limits`]: crate::config::rate_limits
+[`config::quotas`]: crate::config::quotas
+[`integrity::refuse_writes_when_degraded`]: crate::integrity::refuse_writes_when_degraded
+"#]
+
+use crate::app::AppState;
+use crate::middleware::quota::{self, QuotaState};
+use crate::middleware::rate_limit::{self, RateLimitState};
+use crate::reload::Reloadable;
+use crate::security::csrf::CsrfDisabled;
+use crate::security::headers::CspReporting;
+use axum::{Router, extract::Request, middleware::Next, response::Response};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[doc = r#"Which layers wrap the API router. Built from [`from_config`](Self::from_config) or
+[`Default`] (every protective layer on, HSTS and CSP reporting off, no compression or request
+tracing), then adjusted with the builder methods."#]
+pub struct MiddlewareConfig {
+    security_headers: bool,
+    hsts: bool,
+    csp: CspReporting,
+    csrf: bool,
+    rate_limits: bool,
+    quotas: bool,
+    compression: bool,
+    tracing: bool,
+}
+
+impl Default for MiddlewareConfig {
+    fn default() -> Self {
+        MiddlewareConfig {
+            security_headers: true,
+            hsts: false,
+            csp: CspReporting::default(),
+            csrf: true,
+            rate_limits: true,
+            quotas: true,
+            compression: false,
+            tracing: false,
+        }
+    }
+}
+
+impl MiddlewareConfig {
+    #[doc = r#"The server's stack: [`Default`] with HSTS ([`config::hsts_enabled`]) and CSP
+reporting ([`config::csp_reporting`]) from the environment.
+
+[`config::hsts_enabled`]: crate::config::hsts_enabled
+[`config::csp_reporting`]: crate::config::csp_reporting"#]
+    pub fn from_config() -> Self {
+        MiddlewareConfig {
+            hsts: crate::config::hsts_enabled(),
+            csp: crate::config::csp_reporting(),
+            ..MiddlewareConfig::default()
+        }
+    }
+
+    /// CSP reporting settings, for mounting the report collection route.
+    pub(crate) fn csp_reporting(&self) -> &CspReporting {
+        &self.csp
+    }
+
+    /// Wrap `router` (already given `state`) in the selected layers.
+    pub(crate) fn apply(&self, mut router: Router, state: &AppState) -> Router {
+        if !self.csrf {
+            router = router.layer(axum::Extension(CsrfDisabled));
+        }
+        if self.quotas {
+            let quotas = Reloadable::new(crate::config::quotas(), |_| crate::config::quotas());
+            let quota = QuotaState::new(quotas, state.db.clone(), state.key.clone());
+            router = router.layer(axum::middleware::from_fn_with_state(quota, quota::enforce));
+        }
+        if self.rate_limits {
+            let rate_limits = Reloadable::new(crate::config::rate_limits(), |_| {
+                crate::config::rate_limits()
+            });
+            router = router.layer(axum::middleware::from_fn_with_state(
+                RateLimitState::new(rate_limits),
+                rate_limit::enforce,
+            ));
+        }
+        if self.security_headers {
+            router = crate::security::headers::apply(router, self.hsts, &self.csp);
+        }
+        if self.compression {
+            router = router.layer(axum::middleware::from_fn(
+                crate::middleware::compression::gzip,
+            ));
+        }
+        if self.tracing {
+            router = router.layer(axum::middleware::from_fn(trace_requests));
+        }
+        router
+    }
+}
+
+// Builder for library users; the server runs the stack from `from_config` unchanged.
+#[allow(dead_code)]
+impl MiddlewareConfig {
+    /// Set `X-Content-Type-Options`, `X-Frame-Options`, `Referrer-Policy` and the CSP headers.
+    pub fn security_headers(mut self, on: bool) -> Self {
+        self.security_headers = on;
+        self
+    }
+
+    /// Send `Strict-Transport-Security` (only with security headers on).
+    pub fn hsts(mut self, on: bool) -> Self {
+        self.hsts = on;
+        self
+    }
+
+    /// Where CSP violations are reported; a report URI also mounts `POST /api/csp-report`.
+    pub fn csp(mut self, csp: CspReporting) -> Self {
+        self.csp = csp;
+        self
+    }
+
+    /// Enforce the double-submit CSRF check on mutating routes.
+    pub fn csrf(mut self, on: bool) -> Self {
+        self.csrf = on;
+        self
+    }
+
+    /// Apply the per-minute login and write limits.
+    pub fn rate_limits(mut self, on: bool) -> Self {
+        self.rate_limits = on;
+        self
+    }
+
+    /// Apply the usage quotas (request rate, body size, daily entries).
+    pub fn quotas(mut self, on: bool) -> Self {
+        self.quotas = on;
+        self
+    }
+
+    /// Gzip responses for clients that accept it.
+    pub fn compression(mut self, on: bool) -> Self {
+        self.compression = on;
+        self
+    }
+
+    /// Log every request with its status and latency.
+    pub fn tracing(mut self, on: bool) -> Self {
+        self.tracing = on;
+        self
+    }
+}
+
+#[doc = r#"Run the request inside an `http_request` span and log its status and latency at
+`info` (`warn` for 5xx)."#]
+pub async fn trace_requests(req: Request, next: Next) -> Response {
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "http_request",
+        method = %req.method(),
+        path = %req.uri().path(),
+    );
+    let started = Instant::now();
+    async move {
+        let res = next.run(req).await;
+        let status = res.status().as_u16();
+        let latency_ms = started.elapsed().as_millis() as u64;
+        if res.status().is_server_error() {
+            tracing::warn!(status, latency_ms, "request failed");
+        } else {
+            tracing::info!(status, latency_ms, "request served");
+        }
+        res
+    }
+    .instrument(span)
+    .await
+}
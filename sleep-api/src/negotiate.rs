@@ -0,0 +1,187 @@
+#![doc = r#"Response content negotiation (JSON or CSV)
+
+Endpoints that return tabular data extract a [`ResponseFormat`] and wrap their body in
+[`Negotiated`], so one route serves both the JSON API and quick spreadsheet pulls:
+
+- `?format=csv` (or `?format=json`) selects the format explicitly and wins over headers.
+- Otherwise `Accept: text/csv` selects CSV; the first of `text/csv` / `application/json`
+  listed in `Accept` wins, and anything else (including `*/*` or no header) yields JSON.
+
+CSV bodies are rendered from the [`CsvTable`] implementation of the response type: a fixed
+header row followed by one row per record, with empty cells for missing values.
+"#]
+
+use crate::error::ApiError;
+use axum::{
+    Json,
+    extract::{FromRequestParts, Query},
+    http::{StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[doc = r#"Negotiated response format for a request.
+
+Rejects an unknown `format` query value with `400 {code:"bad_request"}`.
+
+# Example
+
+```rust
+# use sleep_api::negotiate::ResponseFormat;
+assert_eq!(ResponseFormat::from_accept("text/csv"), ResponseFormat::Csv);
+assert_eq!(ResponseFormat::from_accept("application/json, text/csv"), ResponseFormat::Json);
+assert_eq!(ResponseFormat::from_accept("*/*"), ResponseFormat::Json);
+```
+"#]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ResponseFormat {
+    /// Format preferred by an `Accept` header value (JSON unless `text/csv` is listed first).
+    pub fn from_accept(accept: &str) -> Self {
+        accept
+            .split(',')
+            .map(|range| range.split(';').next().unwrap_or_default().trim())
+            .find_map(|media| {
+                if media.eq_ignore_ascii_case("text/csv") {
+                    Some(ResponseFormat::Csv)
+                } else if media.eq_ignore_ascii_case("application/json") {
+                    Some(ResponseFormat::Json)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    /// Wrap `body` to be rendered in this format.
+    pub fn render<T>(self, body: T) -> Negotiated<T> {
+        Negotiated { format: self, body }
+    }
+}
+
+#[derive(Deserialize)]
+struct FormatParam {
+    format: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ResponseFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let param = Query::<FormatParam>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .and_then(|Query(p)| p.format);
+        match param.as_deref() {
+            Some("csv") => Ok(ResponseFormat::Csv),
+            Some("json") => Ok(ResponseFormat::Json),
+            Some(_) => Err(ApiError::InvalidInput("format must be json or csv".into())),
+            None => Ok(parts
+                .headers
+                .get(header::ACCEPT)
+                .and_then(|v| v.to_str().ok())
+                .map(ResponseFormat::from_accept)
+                .unwrap_or_default()),
+        }
+    }
+}
+
+#[doc = r#"A response body that can be rendered as a CSV table.
+
+`rows` must yield rows with the same number of cells as [`CsvTable::HEADER`]; use [`cell`]
+for optional values so missing data becomes an empty cell.
+"#]
+pub trait CsvTable {
+    /// Column names of the header row.
+    const HEADER: &'static [&'static str];
+
+    /// Data rows, in display order.
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// Format an optional value as a CSV cell (empty when `None`).
+pub fn cell<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[doc = r#"Render `table` as CSV bytes, header row first.
+
+# Errors
+
+Returns a [`csv::Error`] when a row's length differs from the header.
+"#]
+pub fn to_csv<T: CsvTable>(table: &T) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(T::HEADER)?;
+    for row in table.rows() {
+        writer.write_record(&row)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+#[derive(Debug)]
+#[doc = r#"Response body rendered as JSON or CSV according to the negotiated [`ResponseFormat`].
+
+CSV responses use `Content-Type: text/csv; charset=utf-8`."#]
+pub struct Negotiated<T> {
+    pub format: ResponseFormat,
+    pub body: T,
+}
+
+impl<T: Serialize + CsvTable> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        match self.format {
+            ResponseFormat::Json => Json(self.body).into_response(),
+            ResponseFormat::Csv => match to_csv(&self.body) {
+                Ok(bytes) => {
+                    ([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], bytes).into_response()
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "csv rendering failed");
+                    (StatusCode::INTERNAL_SERVER_ERROR, "csv rendering failed").into_response()
+                }
+            },
+        }
+    }
+}
+
+// Model types live in `sleep-core`, which knows nothing about CSV rendering.
+impl CsvTable for crate::models::AuditPage {
+    const HEADER: &'static [&'static str] = &[
+        "id",
+        "recorded_at",
+        "action",
+        "entity",
+        "entity_id",
+        "reason",
+        "note",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.entries
+            .iter()
+            .map(|e| {
+                vec![
+                    e.id.to_string(),
+                    e.recorded_at.to_string(),
+                    e.action.clone(),
+                    e.entity.clone(),
+                    cell(e.entity_id),
+                    cell(e.reason.as_deref()),
+                    cell(e.note.as_deref()),
+                ]
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,74 @@
+#![doc = r#"Notification channels
+
+Outgoing notifications (fired alert rules, see [`crate::jobs`], logins from unseen devices, see
+[`crate::security::device`], and login email change confirmation links, see
+`POST /api/account/email`) are delivered to every configured channel:
+
+- `log` — always on: an `info` event on the `notify` tracing target, so notifications show up
+  in the server log even without any other channel.
+- `webhook` — when `NOTIFY_WEBHOOK_URL` is set, a JSON `POST` of the [`Notification`]. With
+  `NOTIFY_WEBHOOK_SECRET` the body is signed like incoming pushes
+  (`X-Signature-256: sha256=<hex>`, see [`signature`]), so receivers can reuse the same check.
+
+Delivery is best effort: a failing channel is logged and skipped, and [`deliver`] reports which
+channels accepted the notification.
+
+[`signature`]: crate::security::signature
+"#]
+
+use crate::config;
+use crate::security::signature::{self, SIGNATURE_HEADER};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Time limit for one webhook delivery.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[doc = r#"A notification as sent to the webhook channel.
+
+- `kind`: what triggered it (`alert`, `new_device` or `email_change`).
+- `title` / `body`: short and long human-readable text.
+"#]
+pub struct Notification {
+    pub kind: &'static str,
+    pub title: String,
+    pub body: String,
+}
+
+#[doc = r#"Send `notification` to every configured channel.
+
+Returns the names of the channels that accepted it, in delivery order (always starting with
+`log`).
+"#]
+pub async fn deliver(notification: &Notification) -> Vec<&'static str> {
+    tracing::info!(
+        target: "notify",
+        kind = notification.kind,
+        title = %notification.title,
+        body = %notification.body,
+        "notification"
+    );
+    let mut delivered = vec!["log"];
+
+    if let Some(url) = config::notify_webhook_url() {
+        match send_webhook(&url, notification).await {
+            Ok(()) => delivered.push("webhook"),
+            Err(e) => tracing::warn!(error = %e, "notification webhook failed"),
+        }
+    }
+    delivered
+}
+
+async fn send_webhook(url: &str, notification: &Notification) -> Result<(), reqwest::Error> {
+    let body = serde_json::to_vec(notification).unwrap_or_default();
+    let mut req = reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = config::notify_webhook_secret() {
+        req = req.header(SIGNATURE_HEADER, signature::sign(secret.as_bytes(), &body));
+    }
+    req.body(body).send().await?.error_for_status()?;
+    Ok(())
+}
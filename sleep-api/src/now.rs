@@ -0,0 +1,324 @@
+#![doc = r#"Current-status API
+
+Endpoints answering "what should I do now", computed from the sleep goal
+(`GET/POST /api/settings/sleep-goal`), recent history, and the current time in the user's
+timezone.
+
+Endpoints:
+- `GET /api/now` — the server clock, the effective timezone, and today's wake date
+- `GET /api/now/bedtime-status`
+- `GET /api/now/today` — the logical day under the configured day boundary
+  (`GET/POST /api/settings/day-boundary`)
+
+Except for `GET /api/now`, which exists to report the server clock, clients may pass their own
+clock as `now` (RFC 3339) so widgets and bots see consistent values even when the server clock
+drifts; otherwise the server time is used.
+
+The `recommendation` text follows the request locale and duration unit (see [`crate::i18n`]).
+"#]
+
+use crate::i18n::{DurationUnit, Lang, Locale, Units, fmt_minutes, tr};
+use crate::middleware::auth_layer::RequireSessionJson;
+use crate::time::SharedClock;
+use crate::{db::Db, error::ApiError, repository};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::{
+    DateTime, Duration as ChronoDuration, NaiveDate, NaiveDateTime, NaiveTime, Offset, Utc,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::Sqlite;
+
+/// Nights (wake dates ending today) considered for sleep debt.
+pub(crate) const DEBT_WINDOW_DAYS: i64 = 7;
+
+/// Sleep debt above which the recommendation suggests an earlier bedtime.
+const DEBT_NUDGE_MIN: i64 = 60;
+
+/// Largest earlier-bedtime shift suggested in one evening.
+const MAX_EARLY_SHIFT_MIN: i64 = 60;
+
+/// Minutes before bedtime when the recommendation switches to winding down.
+const WIND_DOWN_MIN: i64 = 30;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/now/bedtime-status` and `GET /api/now/today`.
+
+- `now`: optional client time, RFC 3339 with offset (e.g. `2025-06-01T21:30:00+09:00`).
+"#]
+pub struct BedtimeStatusQuery {
+    pub now: Option<String>,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[doc = r#"The logical day for the current time.
+
+- `date`: the day `local_time` belongs to under `day_start` (see [`DayBoundary`]). Quick-log
+  forms use it as the default date of notes, exercise, and factors, and as the wake date of
+  a sleep entry logged on waking.
+
+[`DayBoundary`]: crate::models::DayBoundary
+"#]
+pub struct TodayStatus {
+    pub timezone: String,
+    pub local_time: NaiveDateTime,
+    pub day_start: NaiveTime,
+    pub date: NaiveDate,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[doc = r#"The server's view of "now", so clients need not do date math around midnight and DST.
+
+- `now`: the server's current instant (UTC).
+- `timezone`: the effective user timezone (`/api/settings/timezone`, else `APP_TZ`);
+  `utc_offset_min` is its offset at `now`, which changes across DST transitions.
+- `local_time`: `now` in `timezone`, without offset.
+- `wake_date`: today's wake date, i.e. the logical day of `local_time` under the day boundary
+  (as `date` in [`TodayStatus`]); a sleep logged on waking now belongs to it.
+- `sleep_logged`: whether a session waking on `wake_date` is already recorded.
+"#]
+pub struct NowStatus {
+    pub now: DateTime<Utc>,
+    pub timezone: String,
+    pub utc_offset_min: i32,
+    pub local_time: NaiveDateTime,
+    pub wake_date: NaiveDate,
+    pub sleep_logged: bool,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[doc = r#"Countdown to the target bedtime with sleep debt and a short recommendation.
+
+- `local_time`: the evaluated time in the user's timezone.
+- `bedtime_at`: the target bedtime occurrence nearest to `local_time` (local).
+- `minutes_until_bedtime`: negative once the target has passed.
+- `sleep_debt_min`: shortfall against `target_duration_min`, summed over logged nights among
+  the last `debt_window_days` wake dates; surplus nights do not offset debt.
+"#]
+pub struct BedtimeStatus {
+    pub timezone: String,
+    pub local_time: NaiveDateTime,
+    pub target_bedtime: NaiveTime,
+    pub target_duration_min: i32,
+    pub bedtime_at: NaiveDateTime,
+    pub minutes_until_bedtime: i64,
+    pub sleep_debt_min: i64,
+    pub debt_window_days: i64,
+    pub nights_logged: usize,
+    pub recommendation: String,
+}
+
+#[doc = r#"Return minutes until the target bedtime, current sleep debt, and a recommendation.
+
+Errors:
+- Returns an API error when `now` is not RFC 3339.
+- Returns an API error on database failures.
+"#]
+pub async fn bedtime_status(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Lang(locale): Lang,
+    Units(unit): Units,
+    Query(q): Query<BedtimeStatusQuery>,
+) -> Result<Json<BedtimeStatus>, ApiError> {
+    let now_utc = parse_now(q.now.as_deref(), &clock)?;
+    let tz = repository::get_user_timezone(&db).await;
+    let local_time = now_utc.with_timezone(&tz).naive_local();
+    let goal = repository::get_sleep_goal(&db).await;
+
+    let today = local_time.date();
+    let from = today - ChronoDuration::days(DEBT_WINDOW_DAYS - 1);
+    let durations = sqlx::query_scalar::<Sqlite, i32>(
+        r#"
+        SELECT duration_min
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(today)
+    .fetch_all(&db)
+    .await?;
+
+    let bedtime_at = nearest_bedtime(local_time, goal.target_bedtime);
+    let minutes_until_bedtime = (bedtime_at - local_time).num_minutes();
+    let sleep_debt_min = sleep_debt(&durations, goal.target_duration_min);
+
+    Ok(Json(BedtimeStatus {
+        timezone: tz.name().to_string(),
+        local_time,
+        target_bedtime: goal.target_bedtime,
+        target_duration_min: goal.target_duration_min,
+        bedtime_at,
+        minutes_until_bedtime,
+        sleep_debt_min,
+        debt_window_days: DEBT_WINDOW_DAYS,
+        nights_logged: durations.len(),
+        recommendation: recommendation(locale, unit, minutes_until_bedtime, sleep_debt_min),
+    }))
+}
+
+#[doc = r#"Return the server's current instant, the effective timezone, today's wake date, and
+whether sleep is already logged for it.
+
+Unlike the other endpoints here, `now` is not accepted: the server clock is the answer.
+
+Errors:
+- Returns an API error on database failures.
+"#]
+pub async fn now(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+) -> Result<Json<NowStatus>, ApiError> {
+    let now_utc = clock.now_utc();
+    let tz = repository::get_user_timezone(&db).await;
+    let local = now_utc.with_timezone(&tz);
+    let local_time = local.naive_local();
+    let wake_date = repository::get_day_boundary(&db).await.day_of(local_time);
+    let sleep_logged = repository::count_sleep_sessions_on(&db, wake_date).await? > 0;
+    Ok(Json(NowStatus {
+        now: now_utc,
+        timezone: tz.name().to_string(),
+        utc_offset_min: local.offset().fix().local_minus_utc() / 60,
+        local_time,
+        wake_date,
+        sleep_logged,
+    }))
+}
+
+#[doc = r#"Return the logical date for now (or the client's `now`) in the user's timezone.
+
+Errors:
+- Returns an API error when `now` is not RFC 3339.
+"#]
+pub async fn today(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Query(q): Query<BedtimeStatusQuery>,
+) -> Result<Json<TodayStatus>, ApiError> {
+    let now_utc = parse_now(q.now.as_deref(), &clock)?;
+    let tz = repository::get_user_timezone(&db).await;
+    let local_time = now_utc.with_timezone(&tz).naive_local();
+    let boundary = repository::get_day_boundary(&db).await;
+    Ok(Json(TodayStatus {
+        timezone: tz.name().to_string(),
+        local_time,
+        day_start: boundary.day_start,
+        date: boundary.day_of(local_time),
+    }))
+}
+
+fn parse_now(raw: Option<&str>, clock: &SharedClock) -> Result<DateTime<Utc>, ApiError> {
+    match raw {
+        Some(raw) => Ok(DateTime::parse_from_rfc3339(raw)
+            .map_err(|_| ApiError::InvalidInput("now must be an RFC 3339 timestamp".into()))?
+            .with_timezone(&Utc)),
+        None => Ok(clock.now_utc()),
+    }
+}
+
+/// The occurrence of `target` (yesterday, today, or tomorrow) closest to `now`.
+pub(crate) fn nearest_bedtime(now: NaiveDateTime, target: NaiveTime) -> NaiveDateTime {
+    let today: NaiveDate = now.date();
+    [today.pred_opt(), Some(today), today.succ_opt()]
+        .into_iter()
+        .flatten()
+        .map(|d| d.and_time(target))
+        .min_by_key(|at| (*at - now).num_seconds().abs())
+        .unwrap_or_else(|| today.and_time(target))
+}
+
+pub(crate) fn sleep_debt(durations: &[i32], target_min: i32) -> i64 {
+    durations
+        .iter()
+        .map(|d| i64::from((target_min - d).max(0)))
+        .sum()
+}
+
+fn recommendation(locale: Locale, unit: DurationUnit, minutes_until: i64, debt_min: i64) -> String {
+    let fmt = |min| fmt_minutes(locale, unit, min);
+    if minutes_until < 0 {
+        return tr(locale, "now-past-bedtime", &[("past", fmt(-minutes_until))]);
+    }
+    if debt_min >= DEBT_NUDGE_MIN {
+        let early = (debt_min / 2).min(MAX_EARLY_SHIFT_MIN);
+        return if minutes_until <= early {
+            tr(locale, "now-debt-bed-now", &[("debt", fmt(debt_min))])
+        } else {
+            tr(
+                locale,
+                "now-debt-go-early",
+                &[
+                    ("debt", fmt(debt_min)),
+                    ("early", fmt(early)),
+                    ("until", fmt(minutes_until - early)),
+                ],
+            )
+        };
+    }
+    if minutes_until <= WIND_DOWN_MIN {
+        tr(locale, "now-wind-down", &[("until", fmt(minutes_until))])
+    } else {
+        tr(locale, "now-on-track", &[("until", fmt(minutes_until))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn nearest_bedtime_wraps_midnight() {
+        let target = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
+        assert_eq!(nearest_bedtime(at(1, 21, 30), target), at(1, 23, 0));
+        // Just after midnight the bedtime that matters is last night's.
+        assert_eq!(nearest_bedtime(at(2, 0, 45), target), at(1, 23, 0));
+
+        let early = NaiveTime::from_hms_opt(0, 30, 0).unwrap();
+        assert_eq!(nearest_bedtime(at(1, 22, 0), early), at(2, 0, 30));
+    }
+
+    #[test]
+    fn sleep_debt_ignores_surplus() {
+        assert_eq!(sleep_debt(&[420, 480, 540, 450], 480), 90);
+        assert_eq!(sleep_debt(&[], 480), 0);
+    }
+
+    #[test]
+    fn recommendation_cases() {
+        let en = |until, debt| recommendation(Locale::En, DurationUnit::Hours, until, debt);
+        assert_eq!(en(-20, 0), "20m past your target bedtime; head to bed now.");
+        assert_eq!(
+            en(150, 90),
+            "Sleep debt is 1h 30m; aim for bed 45m early, in 1h 45m."
+        );
+        assert_eq!(
+            en(30, 200),
+            "Sleep debt is 3h 20m; head to bed now to catch up."
+        );
+        assert_eq!(en(25, 0), "Bedtime in 25m; start winding down.");
+        assert_eq!(en(125, 30), "On track: bedtime in 2h 05m.");
+        assert_eq!(
+            recommendation(Locale::Ja, DurationUnit::Hours, 125, 30),
+            "順調です。就寝まであと2時間05分。"
+        );
+        assert_eq!(
+            recommendation(Locale::En, DurationUnit::Minutes, 150, 90),
+            "Sleep debt is 90 min; aim for bed 45 min early, in 105 min."
+        );
+    }
+}
@@ -0,0 +1,281 @@
+#![doc = r#"Weekly sleep plan
+
+Proposes bed and wake times for the next seven nights from the sleep goal
+(`GET/POST /api/settings/sleep-goal`), moving a night's window around busy times and flagging
+nights where the goal cannot fit.
+
+Endpoints:
+- `GET /api/plan/week?busy=...`
+
+There is no calendar integration yet, so busy times come from the client: `busy` is a
+comma-separated list of local `start/end` intervals (`YYYY-MM-DDTHH:MM[:SS]`), e.g. exported
+from a calendar app.
+
+Each night is planned independently:
+1. The target window starts at `target_bedtime` and lasts `target_duration_min`.
+2. Busy intervals are removed from the target window widened by [`MAX_SHIFT_MIN`] on both
+   sides.
+3. The window is placed in the free gap that keeps bedtime closest to the target. When no gap
+   is long enough, the night is flagged `attainable: false` and the longest gap is proposed.
+"#]
+
+use crate::middleware::auth_layer::RequireSessionJson;
+use crate::time::SharedClock;
+use crate::{db::Db, error::ApiError, repository};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime, NaiveTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Nights planned by `GET /api/plan/week`.
+const PLAN_NIGHTS: i64 = 7;
+
+/// Largest bedtime or wake time shift away from the goal considered when avoiding busy times.
+pub const MAX_SHIFT_MIN: i64 = 120;
+
+/// Most busy intervals accepted in one request.
+const MAX_BUSY: usize = 200;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/plan/week`.
+
+- `busy`: optional comma-separated local intervals `start/end`, e.g.
+  `2025-06-03T06:00/2025-06-03T08:00,2025-06-05T22:00/2025-06-06T00:30`.
+"#]
+pub struct PlanWeekQuery {
+    pub busy: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Proposed sleep for the night ending on `wake_date`.
+
+- `bed_at` / `wake_at`: proposed local times; `None` when busy times leave no free time at all.
+- `shift_min`: proposed bedtime minus the goal bedtime (negative = earlier).
+- `attainable`: whether the full goal duration fits.
+- `conflicts`: busy intervals overlapping the goal window.
+"#]
+pub struct PlannedNight {
+    pub wake_date: NaiveDate,
+    pub bed_at: Option<NaiveDateTime>,
+    pub wake_at: Option<NaiveDateTime>,
+    pub planned_duration_min: i64,
+    pub shift_min: i64,
+    pub attainable: bool,
+    pub conflicts: usize,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[doc = r#"Response of `GET /api/plan/week`: the goal and one proposal per upcoming night,
+starting with tonight (wake date `as_of` + 1)."#]
+pub struct WeekPlan {
+    pub as_of: NaiveDate,
+    pub target_bedtime: NaiveTime,
+    pub target_duration_min: i32,
+    pub nights: Vec<PlannedNight>,
+}
+
+#[doc = r#"Return a seven-night plan around the given busy times.
+
+Errors:
+- Returns an API error when `busy` is malformed, an interval ends before it starts, or more
+  than 200 intervals are given.
+"#]
+pub async fn plan_week(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Query(q): Query<PlanWeekQuery>,
+) -> Result<Json<WeekPlan>, ApiError> {
+    let busy = parse_busy(q.busy.as_deref().unwrap_or_default())?;
+    let tz = repository::get_user_timezone(&db).await;
+    let local_time = clock.now_utc().with_timezone(&tz).naive_local();
+    let as_of = repository::get_day_boundary(&db).await.day_of(local_time);
+    let goal = repository::get_sleep_goal(&db).await;
+    let duration = ChronoDuration::minutes(i64::from(goal.target_duration_min));
+
+    let nights = (1..=PLAN_NIGHTS)
+        .map(|offset| {
+            let wake_date = as_of + ChronoDuration::days(offset);
+            plan_night(wake_date, goal.target_bedtime, duration, &busy)
+        })
+        .collect();
+
+    Ok(Json(WeekPlan {
+        as_of,
+        target_bedtime: goal.target_bedtime,
+        target_duration_min: goal.target_duration_min,
+        nights,
+    }))
+}
+
+fn parse_busy(raw: &str) -> Result<Vec<(NaiveDateTime, NaiveDateTime)>, ApiError> {
+    let parse = |s: &str| {
+        let s = s.trim();
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+            .map_err(|_| ApiError::InvalidInput(format!("invalid busy time {s:?}")))
+    };
+    let busy = raw
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(|interval| {
+            let (start, end) = interval.split_once('/').ok_or_else(|| {
+                ApiError::InvalidInput(format!("busy interval {interval:?} must be start/end"))
+            })?;
+            let (start, end) = (parse(start)?, parse(end)?);
+            if end <= start {
+                return Err(ApiError::InvalidInput(format!(
+                    "busy interval {interval:?} ends before it starts"
+                )));
+            }
+            Ok((start, end))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if busy.len() > MAX_BUSY {
+        return Err(ApiError::InvalidInput(format!(
+            "at most {MAX_BUSY} busy intervals"
+        )));
+    }
+    Ok(busy)
+}
+
+/// Goal bedtime for the night ending on `wake_date` (the evening before, unless before noon).
+fn target_bed(wake_date: NaiveDate, bedtime: NaiveTime) -> NaiveDateTime {
+    if bedtime >= NaiveTime::from_hms_opt(12, 0, 0).expect("valid time") {
+        (wake_date - ChronoDuration::days(1)).and_time(bedtime)
+    } else {
+        wake_date.and_time(bedtime)
+    }
+}
+
+fn plan_night(
+    wake_date: NaiveDate,
+    bedtime: NaiveTime,
+    duration: ChronoDuration,
+    busy: &[(NaiveDateTime, NaiveDateTime)],
+) -> PlannedNight {
+    let bed = target_bed(wake_date, bedtime);
+    let wake = bed + duration;
+    let shift = ChronoDuration::minutes(MAX_SHIFT_MIN);
+    let conflicts = busy.iter().filter(|(s, e)| *s < wake && *e > bed).count();
+    let gaps = free_gaps(bed - shift, wake + shift, busy);
+
+    let fitting = gaps
+        .iter()
+        .filter(|(start, end)| *end - *start >= duration)
+        .map(|(start, end)| bed.clamp(*start, *end - duration))
+        .min_by_key(|at| (*at - bed).num_minutes().abs());
+    let (bed_at, wake_at, attainable) = match fitting {
+        Some(at) => (Some(at), Some(at + duration), true),
+        None => match gaps.iter().max_by_key(|(start, end)| *end - *start) {
+            Some((start, end)) => (Some(*start), Some(*end), false),
+            None => (None, None, false),
+        },
+    };
+
+    PlannedNight {
+        wake_date,
+        bed_at,
+        wake_at,
+        planned_duration_min: match (bed_at, wake_at) {
+            (Some(b), Some(w)) => (w - b).num_minutes(),
+            _ => 0,
+        },
+        shift_min: bed_at.map_or(0, |b| (b - bed).num_minutes()),
+        attainable,
+        conflicts,
+    }
+}
+
+/// Free intervals of `from..to` after removing `busy`, in order.
+fn free_gaps(
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    busy: &[(NaiveDateTime, NaiveDateTime)],
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut blocked: Vec<_> = busy
+        .iter()
+        .filter(|(s, e)| *s < to && *e > from)
+        .copied()
+        .collect();
+    blocked.sort();
+    let mut gaps = Vec::new();
+    let mut cursor = from;
+    for (start, end) in blocked {
+        if start > cursor {
+            gaps.push((cursor, start));
+        }
+        cursor = cursor.max(end);
+    }
+    if cursor < to {
+        gaps.push((cursor, to));
+    }
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    fn night(busy: &[(NaiveDateTime, NaiveDateTime)]) -> PlannedNight {
+        plan_night(
+            NaiveDate::from_ymd_opt(2025, 6, 3).unwrap(),
+            NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            ChronoDuration::minutes(480),
+            busy,
+        )
+    }
+
+    #[test]
+    fn plan_keeps_goal_without_conflicts() {
+        let plan = night(&[(at(3, 9, 0), at(3, 17, 0))]);
+        assert_eq!(plan.bed_at, Some(at(2, 23, 0)));
+        assert_eq!(plan.wake_at, Some(at(3, 7, 0)));
+        assert!(plan.attainable);
+        assert_eq!((plan.shift_min, plan.conflicts), (0, 0));
+    }
+
+    #[test]
+    fn plan_moves_around_early_meeting_and_late_event() {
+        // A 06:00 flight: go to bed an hour earlier.
+        let plan = night(&[(at(3, 6, 0), at(3, 9, 0))]);
+        assert_eq!(plan.bed_at, Some(at(2, 22, 0)));
+        assert_eq!((plan.shift_min, plan.conflicts), (-60, 1));
+        assert!(plan.attainable);
+
+        // A late dinner until 23:30: go to bed after it.
+        let plan = night(&[(at(2, 19, 0), at(2, 23, 30))]);
+        assert_eq!(plan.bed_at, Some(at(2, 23, 30)));
+        assert_eq!(plan.shift_min, 30);
+    }
+
+    #[test]
+    fn plan_flags_unattainable_nights() {
+        let plan = night(&[(at(2, 20, 0), at(3, 0, 0)), (at(3, 5, 0), at(3, 12, 0))]);
+        assert!(!plan.attainable);
+        assert_eq!(plan.bed_at, Some(at(3, 0, 0)));
+        assert_eq!(plan.planned_duration_min, 300);
+
+        let plan = night(&[(at(2, 12, 0), at(3, 12, 0))]);
+        assert_eq!((plan.bed_at, plan.planned_duration_min), (None, 0));
+    }
+
+    #[test]
+    fn parse_busy_validates_intervals() {
+        let busy = parse_busy("2025-06-03T06:00/2025-06-03T08:00:00, ").unwrap();
+        assert_eq!(busy, vec![(at(3, 6, 0), at(3, 8, 0))]);
+        assert!(parse_busy("").unwrap().is_empty());
+        assert!(parse_busy("2025-06-03T06:00").is_err());
+        assert!(parse_busy("2025-06-03T08:00/2025-06-03T06:00").is_err());
+    }
+}
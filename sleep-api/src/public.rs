@@ -0,0 +1,113 @@
+#![doc = r#"Public (unauthenticated) stats
+
+Coarse aggregates for embedding a widget on a personal website. Disabled by default and
+configured with `GET/POST /api/settings/public-summary` ([`PublicSummarySettings`]): only the
+listed [`PublicField`]s are included, and while disabled the endpoint returns 404. The average
+duration is rounded with the `public` policy of [`redaction`] (5 minutes by default).
+
+Endpoints:
+- `GET /api/public/summary`
+
+Responses carry `Access-Control-Allow-Origin: *` so the widget can be fetched from another
+origin; no cookies are read or set.
+
+[`PublicSummarySettings`]: crate::models::PublicSummarySettings
+[`PublicField`]: crate::models::PublicField
+[`redaction`]: crate::redaction
+"#]
+
+use crate::dashboard::logging_streak;
+use crate::models::{Audience, PublicField};
+use crate::time::SharedClock;
+use crate::{db::Db, error::ApiError, redaction, repository};
+use axum::{
+    Json,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate};
+use schemars::JsonSchema;
+use serde::Serialize;
+use sqlx::Sqlite;
+
+/// How far back the logging streak is counted.
+const STREAK_LOOKBACK_DAYS: i64 = 365;
+
+#[derive(Serialize, Debug, Default, PartialEq, JsonSchema)]
+#[doc = r#"Response of `GET /api/public/summary`.
+
+`month` (`YYYY-MM`) is the month the `*_month` fields cover, up to today. Fields not enabled
+in the settings, or without data, are omitted.
+"#]
+pub struct PublicSummary {
+    pub month: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_duration_min_month: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_quality_month: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nights_logged_month: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging_streak: Option<usize>,
+}
+
+#[doc = r#"Return the configured public aggregates.
+
+Errors:
+- [`ApiError::NotFound`] while the public summary is disabled.
+- Returns an API error on database failures.
+"#]
+pub async fn summary(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+) -> Result<Response, ApiError> {
+    let settings = repository::get_public_summary_settings(&db).await;
+    if !settings.enabled {
+        return Err(ApiError::NotFound);
+    }
+    let tz = repository::get_user_timezone(&db).await;
+    let local_time = clock.now_utc().with_timezone(&tz).naive_local();
+    let today = repository::get_day_boundary(&db).await.day_of(local_time);
+    let month_start = today.with_day(1).unwrap_or(today);
+
+    let mut out = PublicSummary {
+        month: today.format("%Y-%m").to_string(),
+        ..PublicSummary::default()
+    };
+    let (nights, avg_duration, avg_quality) =
+        sqlx::query_as::<Sqlite, (i64, Option<f64>, Option<f64>)>(
+            r#"
+        SELECT COUNT(*), AVG(duration_min), AVG(quality)
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        "#,
+        )
+        .bind(month_start)
+        .bind(today)
+        .fetch_one(&db)
+        .await?;
+    if settings.exposes(PublicField::NightsLoggedMonth) {
+        out.nights_logged_month = Some(nights);
+    }
+    if settings.exposes(PublicField::AvgDurationMonth) {
+        let policy = redaction::policy(&db, Audience::Public).await;
+        out.avg_duration_min_month =
+            avg_duration.map(|avg| i64::from(policy.round_minutes(avg.round() as i32)));
+    }
+    if settings.exposes(PublicField::AvgQualityMonth) {
+        out.avg_quality_month = avg_quality.map(|avg| (avg * 10.0).round() / 10.0);
+    }
+    if settings.exposes(PublicField::LoggingStreak) {
+        let dates = sqlx::query_scalar::<Sqlite, NaiveDate>(
+            "SELECT wake_date FROM v_daily_sleep WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC",
+        )
+        .bind(today - ChronoDuration::days(STREAK_LOOKBACK_DAYS))
+        .bind(today)
+        .fetch_all(&db)
+        .await?;
+        out.logging_streak = Some(logging_streak(&dates, today));
+    }
+
+    Ok(([(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")], Json(out)).into_response())
+}
@@ -0,0 +1,147 @@
+#![doc = r#"Redaction of data leaving the authenticated API
+
+Every route that hands records to someone other than the signed-in user goes through this
+module instead of trimming fields itself:
+
+- share links (`GET /api/shared/{secret}`) — [`Audience::Share`]
+- the public summary (`GET /api/public/summary`) — [`Audience::Public`]
+- anonymized exports (`GET /api/export?anonymize=true`) — [`Audience::Export`]
+
+The [`RedactionPolicy`] of each audience is configured with `GET/POST /api/settings/redaction`
+([`RedactionSettings`]) and applied with [`Redact::redact`]: note bodies and the morning
+check-in can be stripped (a note's tags go with its body), and clock times and durations are rounded (15 minutes by default).
+
+# Example
+
+```rust
+# use chrono::{NaiveDate, NaiveTime};
+# use sleep_api::models::{Note, RedactionPolicy};
+# use sleep_api::redaction::Redact;
+let note = Note {
+    id: 1,
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+    body: Some("private".into()),
+    tags: vec!["sick".into()],
+    session_id: None,
+};
+let redacted = note.redact(&RedactionPolicy::STRICT);
+assert_eq!(redacted.body, None);
+assert!(redacted.tags.is_empty());
+```
+
+[`Audience::Share`]: crate::models::Audience::Share
+[`Audience::Public`]: crate::models::Audience::Public
+[`Audience::Export`]: crate::models::Audience::Export
+[`RedactionPolicy`]: crate::models::RedactionPolicy
+[`RedactionSettings`]: crate::models::RedactionSettings
+"#]
+
+use crate::db::Db;
+use crate::models::{Audience, ExerciseEvent, Note, RedactionPolicy, SleepListItem};
+use crate::repository;
+
+#[doc = r#"A record that can be coarsened for an audience."#]
+pub trait Redact {
+    /// The record with the fields `policy` does not allow stripped or rounded.
+    fn redact(self, policy: &RedactionPolicy) -> Self;
+}
+
+impl Redact for SleepListItem {
+    fn redact(self, policy: &RedactionPolicy) -> Self {
+        let duration_min = self.duration_min.map(|d| policy.round_minutes(d));
+        SleepListItem {
+            bed_time: policy.round_time(self.bed_time),
+            wake_time: policy.round_time(self.wake_time),
+            duration_min,
+            duration_hours: self
+                .duration_hours
+                .and(duration_min)
+                .map(|m| (f64::from(m) / 60.0 * 100.0).round() / 100.0),
+            wake_feeling: self.wake_feeling.filter(|_| policy.check_in),
+            sleep_inertia_min: self.sleep_inertia_min.filter(|_| policy.check_in),
+            waso_min: self.waso_min.map(|m| policy.round_minutes(m)),
+            ..self
+        }
+    }
+}
+
+impl Redact for ExerciseEvent {
+    fn redact(self, policy: &RedactionPolicy) -> Self {
+        ExerciseEvent {
+            start_time: self.start_time.map(|t| policy.round_time(t)),
+            duration_min: self.duration_min.map(|d| policy.round_minutes(d)),
+            ..self
+        }
+    }
+}
+
+impl Redact for Note {
+    fn redact(self, policy: &RedactionPolicy) -> Self {
+        Note {
+            body: self.body.filter(|_| policy.note_bodies),
+            tags: if policy.note_bodies {
+                self.tags
+            } else {
+                Vec::new()
+            },
+            ..self
+        }
+    }
+}
+
+impl<T: Redact> Redact for Vec<T> {
+    fn redact(self, policy: &RedactionPolicy) -> Self {
+        self.into_iter().map(|r| r.redact(policy)).collect()
+    }
+}
+
+#[doc = r#"The saved policy for `audience` (the default policy when none is saved)."#]
+pub async fn policy(db: &Db, audience: Audience) -> RedactionPolicy {
+    repository::get_redaction_settings(db)
+        .await
+        .policy(audience)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn sleep_times_are_rounded_and_check_in_dropped() {
+        let item = SleepListItem {
+            id: 1,
+            date: NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(),
+            bed_time: time(23, 53),
+            wake_time: time(6, 41),
+            latency_min: 10,
+            awakenings: 1,
+            quality: 4,
+            duration_min: Some(408),
+            wake_feeling: Some(3),
+            sleep_inertia_min: Some(12),
+            waso_min: Some(22),
+            efficiency: Some(0.922),
+            duration_hours: Some(6.8),
+        };
+        let out = item.clone().redact(&RedactionPolicy::STRICT);
+        assert_eq!(out.bed_time, time(0, 0));
+        assert_eq!(out.wake_time, time(6, 45));
+        assert_eq!(out.duration_min, Some(405));
+        assert_eq!(out.duration_hours, Some(6.75));
+        assert_eq!((out.waso_min, out.efficiency), (Some(15), Some(0.922)));
+        assert_eq!((out.wake_feeling, out.sleep_inertia_min), (None, None));
+        assert_eq!(out.latency_min, 10);
+
+        let open = RedactionPolicy {
+            note_bodies: true,
+            time_rounding_min: 1,
+            check_in: true,
+        };
+        assert_eq!(item.clone().redact(&open), item);
+    }
+}
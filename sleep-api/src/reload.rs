@@ -0,0 +1,225 @@
+#![doc = r#"Configuration reload on `SIGHUP`
+
+`kill -HUP <pid>` re-reads the config file (see [`config::load_config_file`]) without
+restarting, so open connections and in-flight requests are unaffected:
+
+- Settings read on every use (admin credentials, notification webhook URL/secret, ingest
+  secrets, edit window, ...) pick up the new values immediately.
+- Components built once per router hold a [`Reloadable`] and rebuild on their next use:
+  the session cookie key ([`SessionKey`], including per-tenant keys derived from it) and
+  usage quotas.
+- Startup-only settings (bind address, database, feature flags, `FROZEN_TIME`) still need
+  a restart.
+
+Each reload bumps a process-wide generation and is logged; the last outcome is reported
+by `GET /api/version` as `config_reload` (see [`ReloadStatus`]). A failed reload (e.g. an
+unreadable file) keeps the previous values.
+
+Changing `SESSION_SECRET` invalidates existing sessions, exactly like a restart would. When
+it is unset or invalid after a reload, the current key is kept rather than replaced by a
+random one.
+
+[`config::load_config_file`]: crate::config::load_config_file
+"#]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use axum_extra::extract::cookie::Key;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::config;
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static LAST_RELOAD: Mutex<Option<ReloadStatus>> = Mutex::new(None);
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Outcome of the most recent config reload."#]
+pub struct ReloadStatus {
+    /// Number of successful reloads since startup.
+    pub generation: u64,
+    pub at: DateTime<Utc>,
+    pub ok: bool,
+    /// Why the reload failed; previous values stay in effect.
+    pub error: Option<String>,
+}
+
+/// Number of successful reloads since startup.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// Outcome of the most recent reload, or `None` when none happened yet.
+pub fn last_reload() -> Option<ReloadStatus> {
+    LAST_RELOAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[doc = r#"Re-read the config file and mark reloadable components stale.
+
+Returns the recorded [`ReloadStatus`]; failures are logged and leave the generation as is."#]
+pub fn reload(now: DateTime<Utc>) -> ReloadStatus {
+    let status = match config::reload_config_file() {
+        Ok(path) => {
+            let generation = GENERATION.fetch_add(1, Ordering::AcqRel) + 1;
+            tracing::info!(path = %path.display(), generation, "configuration reloaded");
+            ReloadStatus {
+                generation,
+                at: now,
+                ok: true,
+                error: None,
+            }
+        }
+        Err(error) => {
+            tracing::error!(%error, "configuration reload failed; keeping previous values");
+            ReloadStatus {
+                generation: generation(),
+                at: now,
+                ok: false,
+                error: Some(error),
+            }
+        }
+    };
+    *LAST_RELOAD.lock().unwrap_or_else(|e| e.into_inner()) = Some(status.clone());
+    status
+}
+
+#[doc = r#"Reload the configuration on every `SIGHUP` until the process exits.
+
+Spawn once at startup. A no-op on non-Unix platforms."#]
+pub async fn watch_sighup(clock: crate::time::SharedClock) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!(error = ?e, "cannot listen for SIGHUP; config reload disabled");
+                return;
+            }
+        };
+        while hangups.recv().await.is_some() {
+            reload(clock.now_utc());
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = clock;
+}
+
+type Build<T> = dyn Fn(&T) -> T + Send + Sync;
+
+#[doc = r#"A value rebuilt from configuration after each reload.
+
+Cheap to clone; clones share the cached value. [`Reloadable::get`] returns the cached value
+while the generation is unchanged, so the build closure only runs once per reload."#]
+pub struct Reloadable<T> {
+    build: Arc<Build<T>>,
+    cached: Arc<RwLock<(u64, T)>>,
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Reloadable {
+            build: self.build.clone(),
+            cached: self.cached.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Reloadable<T> {
+    /// Build now and again after each reload; `build` receives the previous value.
+    pub fn new(initial: T, build: impl Fn(&T) -> T + Send + Sync + 'static) -> Self {
+        Reloadable {
+            build: Arc::new(build),
+            cached: Arc::new(RwLock::new((generation(), initial))),
+        }
+    }
+
+    /// A value that never changes on reload.
+    pub fn fixed(value: T) -> Self {
+        Self::new(value, T::clone)
+    }
+
+    /// Current value, rebuilt first if a reload happened since it was last built.
+    pub fn get(&self) -> T {
+        let current = generation();
+        {
+            let cached = self.cached.read().unwrap_or_else(|e| e.into_inner());
+            if cached.0 == current {
+                return cached.1.clone();
+            }
+        }
+        let mut cached = self.cached.write().unwrap_or_else(|e| e.into_inner());
+        if cached.0 != current {
+            let value = (self.build)(&cached.1);
+            *cached = (current, value);
+        }
+        cached.1.clone()
+    }
+}
+
+#[derive(Clone)]
+#[doc = r#"Cookie encryption key that follows `SESSION_SECRET` across reloads.
+
+A plain [`Key`] converts into a fixed `SessionKey` (tests, explicit keys)."#]
+pub struct SessionKey(Reloadable<Key>);
+
+impl SessionKey {
+    /// Key from `SESSION_SECRET`, re-derived on reload (kept when the secret becomes unusable).
+    pub fn from_config() -> Self {
+        SessionKey(Reloadable::new(config::session_key(), |prev: &Key| {
+            config::session_secret_key().unwrap_or_else(|| prev.clone())
+        }))
+    }
+
+    /// Key derived from this one and `context`; follows this key across reloads.
+    pub fn derive(&self, context: &[u8]) -> Self {
+        let base = self.clone();
+        let context = context.to_vec();
+        let derive = move || {
+            let mut material = base.current().master().to_vec();
+            material.extend_from_slice(&context);
+            Key::derive_from(&material)
+        };
+        SessionKey(Reloadable::new(derive(), move |_| derive()))
+    }
+
+    /// The key to use now.
+    pub fn current(&self) -> Key {
+        self.0.get()
+    }
+}
+
+impl From<Key> for SessionKey {
+    fn from(key: Key) -> Self {
+        SessionKey(Reloadable::fixed(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reloadable_rebuilds_once_per_generation() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = calls.clone();
+        let value = Reloadable::new(0u64, move |prev| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            prev + 1
+        });
+        assert_eq!(value.get(), 0);
+        GENERATION.fetch_add(1, Ordering::AcqRel);
+        assert_eq!(value.get(), 1);
+        assert_eq!(value.clone().get(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let fixed = Reloadable::fixed("same");
+        GENERATION.fetch_add(1, Ordering::AcqRel);
+        assert_eq!(fixed.get(), "same");
+    }
+}
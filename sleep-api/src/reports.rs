@@ -0,0 +1,271 @@
+#![doc = r#"Weekly reports
+
+`GET /api/reports/weekly?week=2025-W25` summarizes one ISO week (Monday to Sunday, by wake
+date) and compares it with the week before, for the UI digest and report emails.
+
+Each week reports the nights logged, average duration, quality and sleep latency, bedtime
+consistency (the standard deviation of bedtimes, in minutes; lower is steadier), and the
+number of days with exercise. `highlights` lists up to [`MAX_HIGHLIGHTS`] notes of the week,
+starred notes first. Without `week`, the current week of the logical day in the user's
+timezone (see [`DayBoundary`]) is reported, so far.
+
+The Atom feed in [`crate::feeds`] publishes a shorter summary of completed weeks.
+
+[`DayBoundary`]: crate::models::DayBoundary
+"#]
+
+use crate::middleware::auth_layer::RequireSessionJson;
+use crate::time::SharedClock;
+use crate::trends::std_dev;
+use crate::{db::Db, error::ApiError, repository};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, Timelike, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::Sqlite;
+
+/// Most notes listed in [`WeeklyReportResponse::highlights`].
+pub const MAX_HIGHLIGHTS: usize = 3;
+
+/// Longest note excerpt in a highlight, in characters.
+const HIGHLIGHT_CHARS: usize = 200;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/reports/weekly`.
+
+- `week`: ISO week as `YYYY-Www` (e.g. `2025-W25`); defaults to the current week.
+"#]
+pub struct WeeklyReportQuery {
+    pub week: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Aggregates of one ISO week; averages are `None` without logged nights, and
+`bedtime_sd_min` needs at least two."#]
+pub struct WeekStats {
+    pub week: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub nights_logged: usize,
+    pub avg_duration_min: Option<f64>,
+    pub avg_quality: Option<f64>,
+    pub avg_latency_min: Option<f64>,
+    pub bedtime_sd_min: Option<f64>,
+    pub exercise_days: i64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"`current` minus `prior` for each metric; `None` when either side has no value."#]
+pub struct WeekChange {
+    pub nights_logged: i64,
+    pub avg_duration_min: Option<f64>,
+    pub avg_quality: Option<f64>,
+    pub avg_latency_min: Option<f64>,
+    pub bedtime_sd_min: Option<f64>,
+    pub exercise_days: i64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"A note of the week; `excerpt` is the body cut to 200 characters."#]
+pub struct NoteHighlight {
+    pub date: NaiveDate,
+    pub excerpt: String,
+    pub starred: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Response of `GET /api/reports/weekly`."#]
+pub struct WeeklyReportResponse {
+    pub current: WeekStats,
+    pub prior: WeekStats,
+    pub change: WeekChange,
+    pub highlights: Vec<NoteHighlight>,
+}
+
+#[doc = r#"Return the weekly report.
+
+Errors:
+- Returns an API error when `week` is not a valid `YYYY-Www` week.
+- Returns an API error on database failures.
+"#]
+pub async fn weekly(
+    State(db): State<Db>,
+    State(clock): State<SharedClock>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Query(q): Query<WeeklyReportQuery>,
+) -> Result<Json<WeeklyReportResponse>, ApiError> {
+    let monday = match q.week.as_deref() {
+        Some(week) => parse_iso_week(week).ok_or_else(|| {
+            ApiError::InvalidInput("week must be an ISO week like 2025-W25".into())
+        })?,
+        None => {
+            let tz = repository::get_user_timezone(&db).await;
+            let local = clock.now_utc().with_timezone(&tz).naive_local();
+            let today = repository::get_day_boundary(&db).await.day_of(local);
+            week_start(today)
+        }
+    };
+    let current = week_stats(&db, monday).await?;
+    let prior = week_stats(&db, monday - ChronoDuration::weeks(1)).await?;
+    let highlights = highlights(&db, current.from, current.to).await?;
+    Ok(Json(WeeklyReportResponse {
+        change: change(&current, &prior),
+        current,
+        prior,
+        highlights,
+    }))
+}
+
+#[doc = r#"Monday of the ISO week `YYYY-Www`, or `None` when it is malformed or does not exist.
+
+# Example
+
+```rust
+# use chrono::NaiveDate;
+# use sleep_api::reports::parse_iso_week;
+assert_eq!(parse_iso_week("2025-W25"), NaiveDate::from_ymd_opt(2025, 6, 16));
+assert_eq!(parse_iso_week("2025-W54"), None);
+```
+"#]
+pub fn parse_iso_week(s: &str) -> Option<NaiveDate> {
+    let (year, week) = s.trim().split_once("-W")?;
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - ChronoDuration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn week_label(monday: NaiveDate) -> String {
+    let week = monday.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+async fn week_stats(db: &Db, monday: NaiveDate) -> Result<WeekStats, ApiError> {
+    let sunday = monday + ChronoDuration::days(6);
+    let nights = sqlx::query_as::<Sqlite, (NaiveTime, NaiveTime, i64, Option<i64>, Option<i64>)>(
+        "SELECT bed_time, wake_time, duration_min, quality, latency_min FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC",
+    )
+    .bind(monday)
+    .bind(sunday)
+    .fetch_all(db)
+    .await?;
+    let exercise_days = sqlx::query_scalar::<Sqlite, i64>(
+        "SELECT COUNT(DISTINCT date) FROM exercise_events \
+         WHERE date BETWEEN ? AND ? AND intensity != 'none'",
+    )
+    .bind(monday)
+    .bind(sunday)
+    .fetch_one(db)
+    .await?;
+
+    let bedtimes: Vec<f64> = nights
+        .iter()
+        .map(|(bed, wake, ..)| bed_relative_min(*bed, *wake))
+        .collect();
+    Ok(WeekStats {
+        week: week_label(monday),
+        from: monday,
+        to: sunday,
+        nights_logged: nights.len(),
+        avg_duration_min: mean(nights.iter().map(|n| Some(n.2))),
+        avg_quality: mean(nights.iter().map(|n| n.3)),
+        avg_latency_min: mean(nights.iter().map(|n| n.4)),
+        bedtime_sd_min: std_dev(&bedtimes).map(round1),
+        exercise_days,
+    })
+}
+
+/// Minutes from the wake date's midnight to bed time; negative when going to bed the day
+/// before, so bedtimes either side of midnight stay comparable.
+fn bed_relative_min(bed: NaiveTime, wake: NaiveTime) -> f64 {
+    let bed_min = f64::from(bed.hour() * 60 + bed.minute());
+    if bed > wake {
+        bed_min - 24.0 * 60.0
+    } else {
+        bed_min
+    }
+}
+
+fn mean(values: impl Iterator<Item = Option<i64>>) -> Option<f64> {
+    let values: Vec<i64> = values.flatten().collect();
+    (!values.is_empty()).then(|| round1(values.iter().sum::<i64>() as f64 / values.len() as f64))
+}
+
+fn change(current: &WeekStats, prior: &WeekStats) -> WeekChange {
+    let diff = |a: Option<f64>, b: Option<f64>| Some(round1(a? - b?));
+    WeekChange {
+        nights_logged: current.nights_logged as i64 - prior.nights_logged as i64,
+        avg_duration_min: diff(current.avg_duration_min, prior.avg_duration_min),
+        avg_quality: diff(current.avg_quality, prior.avg_quality),
+        avg_latency_min: diff(current.avg_latency_min, prior.avg_latency_min),
+        bedtime_sd_min: diff(current.bedtime_sd_min, prior.bedtime_sd_min),
+        exercise_days: current.exercise_days - prior.exercise_days,
+    }
+}
+
+async fn highlights(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<NoteHighlight>, ApiError> {
+    let notes = sqlx::query_as::<Sqlite, (NaiveDate, String, bool)>(
+        "SELECT date, body, starred FROM notes \
+         WHERE date BETWEEN ? AND ? AND body IS NOT NULL AND TRIM(body) != '' \
+         ORDER BY starred DESC, date DESC, id DESC LIMIT ?",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(MAX_HIGHLIGHTS as i64)
+    .fetch_all(db)
+    .await?;
+    Ok(notes
+        .into_iter()
+        .map(|(date, body, starred)| NoteHighlight {
+            date,
+            excerpt: excerpt(body.trim()),
+            starred,
+        })
+        .collect())
+}
+
+fn excerpt(body: &str) -> String {
+    match body.char_indices().nth(HIGHLIGHT_CHARS) {
+        Some((cut, _)) => format!("{}…", body[..cut].trim_end()),
+        None => body.to_string(),
+    }
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn bedtimes_across_midnight_are_comparable() {
+        let bedtimes = [
+            bed_relative_min(t(23, 30), t(7, 0)),
+            bed_relative_min(t(0, 30), t(7, 0)),
+        ];
+        assert_eq!(bedtimes, [-30.0, 30.0]);
+        assert_eq!(std_dev(&bedtimes), Some(30.0));
+    }
+
+    #[test]
+    fn long_notes_are_cut() {
+        assert_eq!(excerpt("short"), "short");
+        let long = "a".repeat(HIGHLIGHT_CHARS + 5);
+        assert_eq!(excerpt(&long).chars().count(), HIGHLIGHT_CHARS + 1);
+    }
+}
@@ -5,6 +5,9 @@ Operations that touch multiple tables use a single transaction to keep data cons
 
 Why: prefer using these helpers over ad-hoc queries to ensure invariants and transactional correctness.
 
+Every function returns the crate [`Error`]; failures from SQLx surface as [`Error::Database`]
+with the [`sqlx::Error`] as its `source()`.
+
 See also:
 - [`models`] for data shapes
 - [`time::compute_duration_min`] for deriving duration values
@@ -13,19 +16,31 @@ See also:
 [`models`]: crate::models
 [`time::compute_duration_min`]: crate::time::compute_duration_min
 [`insert_sleep`]: crate::repository::insert_sleep
+[`Error`]: crate::error::Error
+[`Error::Database`]: crate::error::Error::Database
 "#]
 
 use crate::{
+    attachments::Media,
     db::Db,
+    error::Error,
+    i18n::{DurationUnit, Locale},
+    integrity::DailySleepRow,
     models::{
-        DateIntensity, ExerciseInput, FrictionErrorKindAggregate, FrictionTelemetryEvent,
-        FrictionTelemetryInput, FrictionWindowAggregate, NoteInput, SleepInput, SleepListItem,
-        SleepSession,
+        AlertEvent, AlertMetric, AlertRules, ApiToken, Attachment, AttachmentUpload, AuditEntry,
+        AuditQuery, AuditReason, BodyMetric, BodyMetricInput, DateIntensity, DayBoundary,
+        Disturbance, DisturbanceInput, ExerciseEvent, ExerciseInput, ExerciseZoneDay, Experiment,
+        ExperimentInput, ExternalRef, FrictionErrorKindAggregate, FrictionTelemetryEvent,
+        FrictionTelemetryInput, FrictionWindowAggregate, HrZoneMinutes, IntensityLevels, JobRun,
+        KnownDevice, Medication, MedicationEvent, MedicationEventInput, MedicationInput, MoodEntry,
+        MoodInput, Note, NoteInput, PublicSummarySettings, RedactionSettings, RoutineChecklist,
+        RoutineEntry, SchemaColumn, SchemaDescription, SchemaObject, ShareLink, ShareLinkVisitor,
+        SleepGoal, SleepInput, SleepListItem, SleepPatch, SleepSession, TimeRounding,
     },
 };
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use chrono_tz::Tz;
-use sqlx::{Sqlite, Transaction};
+use sqlx::{QueryBuilder, Sqlite, Transaction};
 use std::str::FromStr;
 
 #[doc = r#"Resolve the user timezone from app_settings (fallback to APP_TZ / Asia/Tokyo)."#]
@@ -48,7 +63,7 @@ pub async fn get_user_timezone(db: &Db) -> Tz {
 }
 
 #[doc = r#"Persist the user timezone in app_settings (upsert)."#]
-pub async fn set_user_timezone(db: &Db, timezone: &str) -> Result<(), sqlx::Error> {
+pub async fn set_user_timezone(db: &Db, timezone: &str) -> Result<(), Error> {
     sqlx::query::<Sqlite>(
         "INSERT INTO app_settings(key, value) VALUES ('user_timezone', ?) \
          ON CONFLICT(key) DO UPDATE SET value = excluded.value",
@@ -59,15 +74,386 @@ pub async fn set_user_timezone(db: &Db, timezone: &str) -> Result<(), sqlx::Erro
     Ok(())
 }
 
+#[doc = r#"Load the routine checklist from app_settings (falls back to the default checklist)."#]
+pub async fn get_routine_checklist(db: &Db) -> RoutineChecklist {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'routine_checklist' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid routine_checklist; using default");
+            RoutineChecklist::default()
+        }),
+        Ok(None) => RoutineChecklist::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read routine_checklist; using default");
+            RoutineChecklist::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the routine checklist in app_settings (upsert)."#]
+pub async fn set_routine_checklist(db: &Db, checklist: &RoutineChecklist) -> Result<(), Error> {
+    let value = serde_json::to_string(checklist).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('routine_checklist', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the exercise intensity levels from app_settings (falls back to none/light/hard)."#]
+pub async fn get_intensity_levels(db: &Db) -> IntensityLevels {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'intensity_levels' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid intensity_levels; using default");
+            IntensityLevels::default()
+        }),
+        Ok(None) => IntensityLevels::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read intensity_levels; using default");
+            IntensityLevels::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the exercise intensity levels in app_settings (upsert)."#]
+pub async fn set_intensity_levels(db: &Db, levels: &IntensityLevels) -> Result<(), Error> {
+    let value = serde_json::to_string(levels).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('intensity_levels', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the public summary settings from app_settings (falls back to disabled)."#]
+pub async fn get_public_summary_settings(db: &Db) -> PublicSummarySettings {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'public_summary' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid public_summary; using default");
+            PublicSummarySettings::default()
+        }),
+        Ok(None) => PublicSummarySettings::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read public_summary; using default");
+            PublicSummarySettings::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the public summary settings in app_settings (upsert)."#]
+pub async fn set_public_summary_settings(
+    db: &Db,
+    settings: &PublicSummarySettings,
+) -> Result<(), Error> {
+    let value = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('public_summary', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the redaction settings from app_settings (falls back to the defaults)."#]
+pub async fn get_redaction_settings(db: &Db) -> RedactionSettings {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'redaction' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid redaction; using default");
+            RedactionSettings::default()
+        }),
+        Ok(None) => RedactionSettings::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read redaction; using default");
+            RedactionSettings::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the redaction settings in app_settings (upsert)."#]
+pub async fn set_redaction_settings(db: &Db, settings: &RedactionSettings) -> Result<(), Error> {
+    let value = serde_json::to_string(settings).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('redaction', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the alert rules from app_settings (falls back to no rules)."#]
+pub async fn get_alert_rules(db: &Db) -> AlertRules {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'alert_rules' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid alert_rules; using default");
+            AlertRules::default()
+        }),
+        Ok(None) => AlertRules::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read alert_rules; using default");
+            AlertRules::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the alert rules in app_settings (upsert)."#]
+pub async fn set_alert_rules(db: &Db, rules: &AlertRules) -> Result<(), Error> {
+    let value = serde_json::to_string(rules).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('alert_rules', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the day boundary from app_settings (falls back to midnight)."#]
+pub async fn get_day_boundary(db: &Db) -> DayBoundary {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'day_boundary' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid day_boundary; using default");
+            DayBoundary::default()
+        }),
+        Ok(None) => DayBoundary::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read day_boundary; using default");
+            DayBoundary::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the day boundary in app_settings (upsert)."#]
+pub async fn set_day_boundary(db: &Db, boundary: &DayBoundary) -> Result<(), Error> {
+    let value = serde_json::to_string(boundary).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('day_boundary', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the bed/wake time rounding preference from app_settings (falls back to exact
+times)."#]
+pub async fn get_time_rounding(db: &Db) -> TimeRounding {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'time_rounding' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!(error = ?e, "invalid time_rounding; using default");
+            TimeRounding::default()
+        }),
+        Ok(None) => TimeRounding::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read time_rounding; using default");
+            TimeRounding::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the bed/wake time rounding preference in app_settings (upsert)."#]
+pub async fn set_time_rounding(db: &Db, rounding: &TimeRounding) -> Result<(), Error> {
+    let value = serde_json::to_string(rounding).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('time_rounding', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(value)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the sleep goal from the `goals` table (falls back to the default goal)."#]
+pub async fn get_sleep_goal(db: &Db) -> SleepGoal {
+    let result = sqlx::query_as::<Sqlite, (NaiveTime, i32)>(
+        "SELECT target_bedtime, target_duration_min FROM goals WHERE id = 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some((target_bedtime, target_duration_min))) => SleepGoal {
+            target_bedtime,
+            target_duration_min,
+        },
+        Ok(None) => SleepGoal::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read sleep goal; using default");
+            SleepGoal::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the sleep goal in the `goals` table (upsert)."#]
+pub async fn set_sleep_goal(db: &Db, goal: &SleepGoal) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(
+        "INSERT INTO goals(id, target_bedtime, target_duration_min) VALUES (1, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET target_bedtime = excluded.target_bedtime, \
+         target_duration_min = excluded.target_duration_min, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(goal.target_bedtime)
+    .bind(goal.target_duration_min)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the default response locale from app_settings (falls back to English)."#]
+pub async fn get_locale(db: &Db) -> Locale {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'locale' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => value.parse().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "invalid locale; using default");
+            Locale::default()
+        }),
+        Ok(None) => Locale::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read locale; using default");
+            Locale::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the default response locale in app_settings (upsert)."#]
+pub async fn set_locale(db: &Db, locale: Locale) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('locale', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(locale.tag())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the duration unit preference from app_settings (falls back to hours)."#]
+pub async fn get_duration_unit(db: &Db) -> DurationUnit {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'duration_unit' LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await;
+
+    match result {
+        Ok(Some(value)) => value.parse().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "invalid duration unit; using default");
+            DurationUnit::default()
+        }),
+        Ok(None) => DurationUnit::default(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read duration unit; using default");
+            DurationUnit::default()
+        }
+    }
+}
+
+#[doc = r#"Persist the duration unit preference in app_settings (upsert)."#]
+pub async fn set_duration_unit(db: &Db, unit: DurationUnit) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES ('duration_unit', ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(unit.as_str())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Load the stored rollout phase of schema change `name` (see [`crate::schema_change`])."#]
+pub async fn get_schema_phase(db: &Db, name: &str) -> Result<Option<String>, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, String>(
+            "SELECT value FROM app_settings WHERE key = ? LIMIT 1",
+        )
+        .bind(format!("schema_phase:{name}"))
+        .fetch_optional(db)
+        .await?,
+    )
+}
+
+#[doc = r#"Persist the rollout phase of schema change `name` in app_settings (upsert)."#]
+pub async fn set_schema_phase(db: &Db, name: &str, phase: &str) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(
+        "INSERT INTO app_settings(key, value) VALUES (?, ?) \
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(format!("schema_phase:{name}"))
+    .bind(phase)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
 #[doc = r#"Return whether the given sleep window overlaps any existing session.
 
 Overlap is inclusive; end == start is treated as overlapping."#]
-pub async fn has_sleep_overlap(
-    db: &Db,
+pub async fn has_sleep_overlap<'e, E>(
+    db: E,
     bed_dt: NaiveDateTime,
     wake_dt: NaiveDateTime,
     exclude_id: Option<i64>,
-) -> Result<bool, sqlx::Error> {
+) -> Result<bool, Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
     let base_sql = r#"
         SELECT 1
         FROM sleep_sessions s
@@ -98,10 +484,33 @@ pub async fn has_sleep_overlap(
     Ok(exists.is_some())
 }
 
+#[doc = r#"Count sleep sessions on wake date `date`."#]
+pub async fn count_sleep_sessions_on(db: &Db, date: NaiveDate) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, i64>(
+        "SELECT COUNT(*) FROM sleep_sessions WHERE COALESCE(session_date, date) = ?",
+    )
+    .bind(date)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"Count notes on `date`."#]
+pub async fn count_notes_on(db: &Db, date: NaiveDate) -> Result<i64, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM notes WHERE date = ?")
+            .bind(date)
+            .fetch_one(db)
+            .await?,
+    )
+}
+
 #[doc = r#"Insert a sleep session and its metrics in a single transaction.
 
-The session row is written to `sleep_sessions` and the metrics to `sleep_metrics`.
-Pass a precomputed `duration_min` (see [`time::compute_duration_min`]).
+The session row is written to `sleep_sessions`, the metrics to `sleep_metrics`, any
+aids to `sleep_aids`, and any tags to `sleep_session_tags`. The wake date is written to both `date` and `session_date` (dual-write
+for [`schema_change::SESSION_DATE`]).
+Pass a precomputed `duration_min` (see [`time::compute_duration_min`]); the session's
+efficiency is derived from it ([`time::sleep_efficiency`]), here and on every update.
 
 # Example
 
@@ -122,6 +531,12 @@ let input = SleepInput {
     latency_min: 10,
     awakenings: 1,
     quality: Quality(4),
+    wake_feeling: None,
+    sleep_inertia_min: None,
+    waso_min: None,
+    aids: Vec::new(),
+    tags: Vec::new(),
+    timezone: None,
 };
 let tz = sleep_api::config::app_tz();
 let dur = sleep_api::time::compute_duration_min(input.date, input.bed_time, input.wake_time, tz)?;
@@ -130,40 +545,92 @@ let id = repository::insert_sleep(&db, &input, dur).await?;
 ```
 
 # Errors
-- Returns [`sqlx::Error`] on database connection or execution errors.
+- Returns [`Error::Database`] on database connection or execution errors.
 
 [`time::compute_duration_min`]: crate::time::compute_duration_min
+[`time::sleep_efficiency`]: crate::time::sleep_efficiency
+[`schema_change::SESSION_DATE`]: crate::schema_change::SESSION_DATE
 "#]
-pub async fn insert_sleep(
-    db: &Db,
+pub async fn insert_sleep(db: &Db, input: &SleepInput, duration_min: i32) -> Result<i64, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let id = insert_sleep_in(&mut tx, input, duration_min).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+async fn insert_sleep_in(
+    tx: &mut Transaction<'_, Sqlite>,
     input: &SleepInput,
     duration_min: i32,
-) -> Result<i64, sqlx::Error> {
-    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+) -> Result<i64, Error> {
     let res = sqlx::query::<Sqlite>(
-        "INSERT INTO sleep_sessions(date, bed_time, wake_time, session_date) VALUES (?, ?, ?, ?)",
+        "INSERT INTO sleep_sessions(date, bed_time, wake_time, session_date, timezone) VALUES (?, ?, ?, ?, ?)",
     )
     .bind(input.date)
     .bind(input.bed_time)
     .bind(input.wake_time)
     .bind(input.date)
-    .execute(&mut *tx)
+    .bind(input.timezone.as_deref())
+    .execute(&mut **tx)
     .await?;
     let id = res.last_insert_rowid();
     sqlx::query::<Sqlite>(
-        "INSERT INTO sleep_metrics(session_id, latency_min, awakenings, quality, duration_min) VALUES (?, ?, ?, ?, ?)"
+        "INSERT INTO sleep_metrics(session_id, latency_min, awakenings, quality, duration_min, wake_feeling, sleep_inertia_min, waso_min, efficiency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(id)
     .bind(input.latency_min)
     .bind(input.awakenings)
     .bind(input.quality.value() as i32)
     .bind(duration_min)
-    .execute(&mut *tx)
+    .bind(input.wake_feeling)
+    .bind(input.sleep_inertia_min)
+    .bind(input.waso_min)
+    .bind(crate::time::sleep_efficiency(
+        duration_min,
+        input.latency_min,
+        input.waso_min,
+    ))
+    .execute(&mut **tx)
     .await?;
-    tx.commit().await?;
+    replace_sleep_aids(tx, id, &input.normalized_aids()).await?;
+    replace_tags(tx, TagLink::Sleep, id, &input.normalized_tags()).await?;
     Ok(id)
 }
 
+#[doc = r#"Insert many validated sleep sessions in one transaction.
+
+`rows` pairs each input with its precomputed duration. A row overlapping a recorded session,
+or an earlier row of the same batch, is skipped and gets `None`; the others get their new id.
+With `commit: false` the transaction is rolled back, so the result previews exactly what a
+committed run would do.
+
+# Errors
+- Returns [`Error::Database`] on database errors; nothing is written then.
+"#]
+pub async fn import_sleeps(
+    db: &Db,
+    rows: &[(SleepInput, i32)],
+    commit: bool,
+) -> Result<Vec<Option<i64>>, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let mut ids = Vec::with_capacity(rows.len());
+    for (input, duration_min) in rows {
+        let (bed_dt, wake_dt) =
+            crate::time::sleep_window_bounds(input.date, input.bed_time, input.wake_time)?;
+        if has_sleep_overlap(&mut *tx, bed_dt, wake_dt, None).await? {
+            ids.push(None);
+            continue;
+        }
+        ids.push(Some(insert_sleep_in(&mut tx, input, *duration_min).await?));
+    }
+    if commit {
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+    Ok(ids)
+}
+
 #[doc = r#"List sleep sessions by wake date.
 
 Returns an empty list if no sessions exist for the provided date.
@@ -171,20 +638,23 @@ Returns an empty list if no sessions exist for the provided date.
 See the example on [`insert_sleep`].
 
 # Errors
-- Returns [`sqlx::Error`] on database errors.
+- Returns [`Error::Database`] on database errors.
 "#]
-pub async fn find_sleep_by_date(
-    db: &Db,
-    date: NaiveDate,
-) -> Result<Vec<SleepSession>, sqlx::Error> {
-    sqlx::query_as::<Sqlite, SleepSession>(
+pub async fn find_sleep_by_date(db: &Db, date: NaiveDate) -> Result<Vec<SleepSession>, Error> {
+    let mut sessions = sqlx::query_as::<Sqlite, SleepSession>(
         r#"SELECT s.id,
                   COALESCE(s.session_date, s.date) AS date,
                   s.bed_time,
                   s.wake_time,
                   m.latency_min,
                   m.awakenings,
-                  m.quality
+                  m.quality,
+                  m.wake_feeling,
+                  m.sleep_inertia_min,
+                  m.waso_min,
+                  m.efficiency,
+                  s.timezone,
+                  s.starred
            FROM sleep_sessions s
            JOIN sleep_metrics m ON m.session_id = s.id
            WHERE COALESCE(s.session_date, s.date) = ?
@@ -192,7 +662,13 @@ pub async fn find_sleep_by_date(
     )
     .bind(date)
     .fetch_all(db)
-    .await
+    .await?;
+    for session in &mut sessions {
+        session.aids = list_sleep_aids(db, session.id).await?;
+        session.tags = list_tags(db, TagLink::Sleep, session.id).await?;
+        session.external_refs = list_sleep_external_refs(db, session.id).await?;
+    }
+    Ok(sessions)
 }
 
 #[doc = r#"Find a sleep session by id.
@@ -202,48 +678,200 @@ Returns `Ok(None)` if no session exists for the provided id.
 See the example on [`insert_sleep`].
 
 # Errors
-- Returns [`sqlx::Error`] on database errors.
+- Returns [`Error::Database`] on database errors.
 "#]
-pub async fn find_sleep_by_id(db: &Db, id: i64) -> Result<Option<SleepSession>, sqlx::Error> {
-    sqlx::query_as::<Sqlite, SleepSession>(
+pub async fn find_sleep_by_id(db: &Db, id: i64) -> Result<Option<SleepSession>, Error> {
+    let session = sqlx::query_as::<Sqlite, SleepSession>(
         r#"SELECT s.id,
                   COALESCE(s.session_date, s.date) AS date,
                   s.bed_time,
                   s.wake_time,
                   m.latency_min,
                   m.awakenings,
-                  m.quality
+                  m.quality,
+                  m.wake_feeling,
+                  m.sleep_inertia_min,
+                  m.waso_min,
+                  m.efficiency,
+                  s.timezone,
+                  s.starred
            FROM sleep_sessions s
            JOIN sleep_metrics m ON m.session_id = s.id
            WHERE s.id = ?"#,
     )
     .bind(id)
     .fetch_optional(db)
-    .await
+    .await?;
+    match session {
+        Some(mut session) => {
+            session.aids = list_sleep_aids(db, session.id).await?;
+            session.tags = list_tags(db, TagLink::Sleep, session.id).await?;
+            session.external_refs = list_sleep_external_refs(db, session.id).await?;
+            session.notes = list_session_notes(db, session.id).await?;
+            Ok(Some(session))
+        }
+        None => Ok(None),
+    }
 }
 
-#[doc = r#"Update a sleep session and its metrics in a single transaction.
-
-Requires a recomputed `duration_min`; see [`time::compute_duration_min`].
-See the example on [`insert_sleep`].
+#[doc = r#"Wake date of sleep session `id`, if it exists."#]
+pub async fn find_sleep_date(db: &Db, id: i64) -> Result<Option<NaiveDate>, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, NaiveDate>(
+        "SELECT COALESCE(session_date, date) FROM sleep_sessions WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?)
+}
 
-# Errors
-- Returns [`sqlx::Error`] on database errors.
-"#]
-pub async fn update_sleep(
-    db: &Db,
-    id: i64,
-    input: &SleepInput,
-    duration_min: i32,
-) -> Result<bool, sqlx::Error> {
-    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
-    let res = sqlx::query::<Sqlite>(
-        "UPDATE sleep_sessions SET date=?, bed_time=?, wake_time=?, session_date=? WHERE id=?",
+#[doc = r#"List the sleep aids recorded for a session, sorted by name."#]
+pub async fn list_sleep_aids(db: &Db, session_id: i64) -> Result<Vec<String>, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, String>(
+        "SELECT aid FROM sleep_aids WHERE session_id = ? ORDER BY aid ASC",
+    )
+    .bind(session_id)
+    .fetch_all(db)
+    .await?)
+}
+
+async fn replace_sleep_aids(
+    tx: &mut Transaction<'_, Sqlite>,
+    session_id: i64,
+    aids: &[String],
+) -> Result<(), Error> {
+    sqlx::query::<Sqlite>("DELETE FROM sleep_aids WHERE session_id = ?")
+        .bind(session_id)
+        .execute(&mut **tx)
+        .await?;
+    for aid in aids {
+        sqlx::query::<Sqlite>("INSERT INTO sleep_aids(session_id, aid) VALUES (?, ?)")
+            .bind(session_id)
+            .bind(aid)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+#[doc = r#"Kind of record a tag is attached to, with its link table (see `0036_tags.sql`)."#]
+#[derive(Clone, Copy, Debug)]
+pub enum TagLink {
+    Sleep,
+    Note,
+}
+
+impl TagLink {
+    fn table(self) -> &'static str {
+        match self {
+            TagLink::Sleep => "sleep_session_tags",
+            TagLink::Note => "note_tags",
+        }
+    }
+
+    fn owner_column(self) -> &'static str {
+        match self {
+            TagLink::Sleep => "session_id",
+            TagLink::Note => "note_id",
+        }
+    }
+}
+
+#[doc = r#"List the tags of a sleep session or note, sorted by name."#]
+pub async fn list_tags<'e, E>(db: E, link: TagLink, owner_id: i64) -> Result<Vec<String>, Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    Ok(sqlx::query_scalar::<Sqlite, String>(&format!(
+        "SELECT t.name FROM {} l JOIN tags t ON t.id = l.tag_id WHERE l.{} = ? ORDER BY t.name ASC",
+        link.table(),
+        link.owner_column()
+    ))
+    .bind(owner_id)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Replace the tags of a session or note with `tags` (already normalized), creating new names.
+async fn replace_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    link: TagLink,
+    owner_id: i64,
+    tags: &[String],
+) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(&format!(
+        "DELETE FROM {} WHERE {} = ?",
+        link.table(),
+        link.owner_column()
+    ))
+    .bind(owner_id)
+    .execute(&mut **tx)
+    .await?;
+    for tag in tags {
+        sqlx::query::<Sqlite>("INSERT INTO tags(name) VALUES (?) ON CONFLICT(name) DO NOTHING")
+            .bind(tag)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query::<Sqlite>(&format!(
+            "INSERT INTO {}({}, tag_id) SELECT ?, id FROM tags WHERE name = ?",
+            link.table(),
+            link.owner_column()
+        ))
+        .bind(owner_id)
+        .bind(tag)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Keep the notes linked to session `session_id` on its wake date `date`.
+async fn move_session_notes(
+    tx: &mut Transaction<'_, Sqlite>,
+    session_id: i64,
+    date: NaiveDate,
+) -> Result<(), Error> {
+    sqlx::query::<Sqlite>("UPDATE notes SET date = ? WHERE session_id = ? AND date <> ?")
+        .bind(date)
+        .bind(session_id)
+        .bind(date)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Load the tags of each note in `notes`.
+async fn attach_note_tags(db: &Db, notes: &mut [Note]) -> Result<(), Error> {
+    for note in notes {
+        note.tags = list_tags(db, TagLink::Note, note.id).await?;
+    }
+    Ok(())
+}
+
+#[doc = r#"Update a sleep session and its metrics in a single transaction.
+
+Requires a recomputed `duration_min`; see [`time::compute_duration_min`]. Like
+[`insert_sleep`], dual-writes `date` and `session_date`. Notes linked to the session are moved
+to the new wake date.
+See the example on [`insert_sleep`].
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn update_sleep(
+    db: &Db,
+    id: i64,
+    input: &SleepInput,
+    duration_min: i32,
+) -> Result<bool, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let res = sqlx::query::<Sqlite>(
+        "UPDATE sleep_sessions SET date=?, bed_time=?, wake_time=?, session_date=?, timezone=? WHERE id=?",
     )
     .bind(input.date)
     .bind(input.bed_time)
     .bind(input.wake_time)
     .bind(input.date)
+    .bind(input.timezone.as_deref())
     .bind(id)
     .execute(&mut *tx)
     .await?;
@@ -260,19 +888,154 @@ pub async fn update_sleep(
         }
     }
     sqlx::query::<Sqlite>(
-        "UPDATE sleep_metrics SET latency_min=?, awakenings=?, quality=?, duration_min=? WHERE session_id=?",
+        "UPDATE sleep_metrics SET latency_min=?, awakenings=?, quality=?, duration_min=?, wake_feeling=?, sleep_inertia_min=?, waso_min=?, efficiency=? WHERE session_id=?",
     )
     .bind(input.latency_min)
     .bind(input.awakenings)
     .bind(input.quality.value() as i32)
     .bind(duration_min)
+    .bind(input.wake_feeling)
+    .bind(input.sleep_inertia_min)
+    .bind(input.waso_min)
+    .bind(crate::time::sleep_efficiency(
+        duration_min,
+        input.latency_min,
+        input.waso_min,
+    ))
     .bind(id)
     .execute(&mut *tx)
     .await?;
+    replace_sleep_aids(&mut tx, id, &input.normalized_aids()).await?;
+    replace_tags(&mut tx, TagLink::Sleep, id, &input.normalized_tags()).await?;
+    move_session_notes(&mut tx, id, input.date).await?;
     tx.commit().await?;
     Ok(true)
 }
 
+#[doc = r#"Apply `patch` to session `id` in a single transaction.
+
+The stored row is read and merged inside the transaction (see [`SleepPatch::apply`]), so
+concurrent edits of other fields are not lost. `duration_min` is recomputed only when the date,
+a time or the session timezone actually changes (or was never computed), in the session's own
+timezone or else `tz`; otherwise the stored value is kept and `sleep_sessions` is not written. Returns the merged record and its duration, or
+`None` when `id` does not exist.
+
+# Errors
+- Returns [`Error::Domain`] when the merged record is invalid.
+- Returns [`Error::Database`] on database errors.
+
+[`SleepPatch::apply`]: crate::models::SleepPatch::apply
+[`Error::Domain`]: crate::error::Error::Domain
+"#]
+pub async fn patch_sleep(
+    db: &Db,
+    id: i64,
+    patch: &SleepPatch,
+    tz: Tz,
+) -> Result<Option<(SleepInput, i32)>, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let existing = sqlx::query_as::<Sqlite, SleepSession>(
+        r#"SELECT s.id,
+                  COALESCE(s.session_date, s.date) AS date,
+                  s.bed_time,
+                  s.wake_time,
+                  m.latency_min,
+                  m.awakenings,
+                  m.quality,
+                  m.wake_feeling,
+                  m.sleep_inertia_min,
+                  m.waso_min,
+                  m.efficiency,
+                  s.timezone,
+                  s.starred
+           FROM sleep_sessions s
+           JOIN sleep_metrics m ON m.session_id = s.id
+           WHERE s.id = ?"#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(mut existing) = existing else {
+        tx.rollback().await?;
+        return Ok(None);
+    };
+    existing.aids = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT aid FROM sleep_aids WHERE session_id = ? ORDER BY aid ASC",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    existing.tags = list_tags(&mut *tx, TagLink::Sleep, id).await?;
+    let stored_duration = sqlx::query_scalar::<Sqlite, Option<i32>>(
+        "SELECT duration_min FROM sleep_metrics WHERE session_id = ?",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let merged = patch.apply(&existing)?;
+    let times_changed = (
+        merged.date,
+        merged.bed_time,
+        merged.wake_time,
+        &merged.timezone,
+    ) != (
+        existing.date,
+        existing.bed_time,
+        existing.wake_time,
+        &existing.timezone,
+    );
+    let duration_min = match stored_duration {
+        Some(d) if !times_changed => d,
+        _ => crate::time::compute_duration_min(
+            merged.date,
+            merged.bed_time,
+            merged.wake_time,
+            merged.tz().unwrap_or(tz),
+        )?,
+    };
+    if times_changed {
+        sqlx::query::<Sqlite>(
+            "UPDATE sleep_sessions SET date=?, bed_time=?, wake_time=?, session_date=?, timezone=? WHERE id=?",
+        )
+        .bind(merged.date)
+        .bind(merged.bed_time)
+        .bind(merged.wake_time)
+        .bind(merged.date)
+        .bind(merged.timezone.as_deref())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        move_session_notes(&mut tx, id, merged.date).await?;
+    }
+    sqlx::query::<Sqlite>(
+        "UPDATE sleep_metrics SET latency_min=?, awakenings=?, quality=?, duration_min=?, wake_feeling=?, sleep_inertia_min=?, waso_min=?, efficiency=? WHERE session_id=?",
+    )
+    .bind(merged.latency_min)
+    .bind(merged.awakenings)
+    .bind(merged.quality.value() as i32)
+    .bind(duration_min)
+    .bind(merged.wake_feeling)
+    .bind(merged.sleep_inertia_min)
+    .bind(merged.waso_min)
+    .bind(crate::time::sleep_efficiency(
+        duration_min,
+        merged.latency_min,
+        merged.waso_min,
+    ))
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    if patch.aids.is_some() {
+        replace_sleep_aids(&mut tx, id, &merged.normalized_aids()).await?;
+    }
+    if patch.tags.is_some() {
+        replace_tags(&mut tx, TagLink::Sleep, id, &merged.normalized_tags()).await?;
+    }
+    tx.commit().await?;
+    Ok(Some((merged, duration_min)))
+}
+
 #[doc = r#"Delete a sleep session by id.
 
 Returns the number of rows affected (0 if no such id exists).
@@ -280,9 +1043,9 @@ Returns the number of rows affected (0 if no such id exists).
 See the example on [`insert_sleep`].
 
 # Errors
-- Returns [`sqlx::Error`] on database errors.
+- Returns [`Error::Database`] on database errors.
 "#]
-pub async fn delete_sleep(db: &Db, id: i64) -> Result<u64, sqlx::Error> {
+pub async fn delete_sleep(db: &Db, id: i64) -> Result<u64, Error> {
     let res = sqlx::query::<Sqlite>("DELETE FROM sleep_sessions WHERE id = ?")
         .bind(id)
         .execute(db)
@@ -290,11 +1053,121 @@ pub async fn delete_sleep(db: &Db, id: i64) -> Result<u64, sqlx::Error> {
     Ok(res.rows_affected())
 }
 
+/// Per-wake-date aggregate of `sleep_sessions` and `sleep_metrics` that `daily_sleep` stores;
+/// the triggers of migration `0040_sleep_efficiency.sql` repeat it.
+const DAILY_SLEEP_AGGREGATE: &str = r#"SELECT MIN(base.id) AS id,
+       base.wake_date AS wake_date,
+       time(MIN(base.bed_dt)) AS bed_time,
+       time(MAX(base.wake_dt)) AS wake_time,
+       CAST(AVG(base.latency_min) AS INTEGER) AS latency_min,
+       SUM(base.awakenings) AS awakenings,
+       CAST(AVG(base.quality) AS INTEGER) AS quality,
+       SUM(base.duration_min) AS duration_min,
+       COUNT(*) AS session_count,
+       CAST(AVG(base.wake_feeling) AS INTEGER) AS wake_feeling,
+       CAST(AVG(base.sleep_inertia_min) AS INTEGER) AS sleep_inertia_min,
+       MAX(base.duration_min) AS longest_segment_min,
+       SUM(base.waso_min) AS waso_min,
+       ROUND(SUM(base.efficiency * base.duration_min)
+           / SUM(CASE WHEN base.efficiency IS NOT NULL THEN base.duration_min END), 3) AS efficiency
+FROM (
+    SELECT s.id,
+           COALESCE(s.session_date, s.date) AS wake_date,
+           CASE
+               WHEN s.bed_time > s.wake_time
+                   THEN datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time, '-1 day')
+               ELSE datetime(COALESCE(s.session_date, s.date) || ' ' || s.bed_time)
+           END AS bed_dt,
+           datetime(COALESCE(s.session_date, s.date) || ' ' || s.wake_time) AS wake_dt,
+           m.latency_min,
+           m.awakenings,
+           m.quality,
+           m.duration_min,
+           m.wake_feeling,
+           m.sleep_inertia_min,
+           m.waso_min,
+           m.efficiency
+    FROM sleep_sessions s
+    JOIN sleep_metrics m ON m.session_id = s.id
+) base
+GROUP BY base.wake_date"#;
+
+#[doc = r#"Recompute every row of `daily_sleep`, the table behind `v_daily_sleep`.
+
+Triggers on `sleep_sessions` and `sleep_metrics` keep the table current (see migration
+`0034_daily_sleep_table.sql`); this is for rows written while they could not fire or copied
+from elsewhere, e.g. after [`crate::integrity::salvage`]. Returns the number of wake dates.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn rebuild_daily_sleep(db: &Db) -> Result<u64, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    sqlx::query::<Sqlite>("DELETE FROM daily_sleep")
+        .execute(&mut *tx)
+        .await?;
+    let res = sqlx::query::<Sqlite>(&format!(
+        "INSERT INTO daily_sleep (id, wake_date, bed_time, wake_time, latency_min, awakenings,
+                                  quality, duration_min, session_count, wake_feeling,
+                                  sleep_inertia_min, longest_segment_min, waso_min,
+                                  efficiency)
+         {DAILY_SLEEP_AGGREGATE}"
+    ))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"List the stored `daily_sleep` rows with a wake date in [from, to], by date.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn list_daily_sleep_stored(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DailySleepRow>, Error> {
+    Ok(sqlx::query_as::<Sqlite, DailySleepRow>(
+        r#"SELECT id, wake_date, bed_time, wake_time, latency_min, awakenings, quality,
+                  duration_min, session_count, wake_feeling, sleep_inertia_min,
+                  longest_segment_min, waso_min, efficiency
+           FROM daily_sleep
+           WHERE wake_date BETWEEN ? AND ?
+           ORDER BY wake_date"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Compute the `daily_sleep` rows for wake dates in [from, to] from `sleep_sessions` and
+`sleep_metrics`, the way `v_daily_sleep` did before it was materialized, by date.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn list_daily_sleep_computed(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DailySleepRow>, Error> {
+    Ok(sqlx::query_as::<Sqlite, DailySleepRow>(&format!(
+        "SELECT * FROM ({DAILY_SLEEP_AGGREGATE}) WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date"
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
 #[doc = r#"List last N daily sleep entries ordered by date DESC.
 
 Backed by the v_daily_sleep view. Maps wake_date -> date via SQL alias to match API struct."#]
-pub async fn list_recent_sleep(db: &Db, days: i32) -> Result<Vec<SleepListItem>, sqlx::Error> {
-    sqlx::query_as::<Sqlite, SleepListItem>(
+pub async fn list_recent_sleep(db: &Db, days: i32) -> Result<Vec<SleepListItem>, Error> {
+    Ok(sqlx::query_as::<Sqlite, SleepListItem>(
         r#"SELECT id,
                    wake_date AS date,
                    bed_time,
@@ -302,21 +1175,25 @@ pub async fn list_recent_sleep(db: &Db, days: i32) -> Result<Vec<SleepListItem>,
                    latency_min,
                    awakenings,
                    quality,
-                   duration_min
+                   duration_min,
+                   wake_feeling,
+                   sleep_inertia_min,
+                   waso_min,
+                   efficiency
           FROM v_daily_sleep
           ORDER BY date DESC
           LIMIT ?"#,
     )
     .bind(days)
     .fetch_all(db)
-    .await
+    .await?)
 }
 
 #[doc = r#"List exercise intensity by date in the inclusive range [from, to].
 
-For each date, returns the highest intensity among any events on that date.
-
-- "none" < "light" < "hard"
+For each date, returns the highest intensity among any events on that date, ranked by
+`levels` (by default "none" < "light" < "hard"), with the number of events and their total
+`duration_min`. Levels no longer in `levels` rank lowest.
 
 Ordered by date ASC.
 "#]
@@ -324,19 +1201,55 @@ pub async fn list_exercise_intensity(
     db: &Db,
     from: NaiveDate,
     to: NaiveDate,
-) -> Result<Vec<DateIntensity>, sqlx::Error> {
-    // Map intensity to ordinal to pick max, then map back to string
-    sqlx::query_as::<Sqlite, DateIntensity>(
+    levels: &IntensityLevels,
+) -> Result<Vec<DateIntensity>, Error> {
+    let ranking =
+        serde_json::to_string(&levels.levels).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    // Rank each event by its position in the level list, then keep the top one per date;
+    // the per-date count and minutes are windowed over the same partition
+    Ok(sqlx::query_as::<Sqlite, DateIntensity>(
+        r#"
+        SELECT date, intensity, sessions, total_min
+        FROM (
+          SELECT
+            e.date,
+            e.intensity,
+            ROW_NUMBER() OVER (
+              PARTITION BY e.date
+              ORDER BY COALESCE((SELECT j.key FROM json_each(?) j WHERE j.value = e.intensity), -1) DESC
+            ) AS rn,
+            COUNT(*) OVER (PARTITION BY e.date) AS sessions,
+            COALESCE(SUM(e.duration_min) OVER (PARTITION BY e.date), 0) AS total_min
+          FROM exercise_events e
+          WHERE e.date BETWEEN ? AND ?
+        )
+        WHERE rn = 1
+        ORDER BY date ASC
+        "#,
+    )
+    .bind(ranking)
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Sum heart-rate zone minutes per date in the inclusive range [from, to].
+
+Only exercise events that recorded zones count; dates without any are omitted. Ordered by
+date ASC.
+"#]
+pub async fn list_exercise_zones(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<ExerciseZoneDay>, Error> {
+    let rows = sqlx::query_as::<Sqlite, (NaiveDate, i64, i32, i32, i32, i32, i32)>(
         r#"
-        SELECT
-          date,
-          CASE MAX(CASE intensity WHEN 'none' THEN 0 WHEN 'light' THEN 1 WHEN 'hard' THEN 2 ELSE 0 END)
-            WHEN 2 THEN 'hard'
-            WHEN 1 THEN 'light'
-            ELSE 'none'
-          END AS intensity
+        SELECT date, COUNT(*), SUM(hr_z1_min), SUM(hr_z2_min), SUM(hr_z3_min),
+               SUM(hr_z4_min), SUM(hr_z5_min)
         FROM exercise_events
-        WHERE date BETWEEN ? AND ?
+        WHERE date BETWEEN ? AND ? AND hr_z1_min IS NOT NULL
         GROUP BY date
         ORDER BY date ASC
         "#,
@@ -344,7 +1257,15 @@ pub async fn list_exercise_intensity(
     .bind(from)
     .bind(to)
     .fetch_all(db)
-    .await
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(date, sessions, z1, z2, z3, z4, z5)| ExerciseZoneDay {
+            date,
+            sessions,
+            zones: HrZoneMinutes { z1, z2, z3, z4, z5 },
+        })
+        .collect())
 }
 
 #[doc = r#"List sleep sessions in the inclusive range [from, to] ordered by date ASC."#]
@@ -352,8 +1273,8 @@ pub async fn list_sleep_range(
     db: &Db,
     from: NaiveDate,
     to: NaiveDate,
-) -> Result<Vec<SleepListItem>, sqlx::Error> {
-    sqlx::query_as::<Sqlite, SleepListItem>(
+) -> Result<Vec<SleepListItem>, Error> {
+    Ok(sqlx::query_as::<Sqlite, SleepListItem>(
         r#"SELECT s.id,
                    COALESCE(s.session_date, s.date) AS date,
                    s.bed_time,
@@ -361,7 +1282,11 @@ pub async fn list_sleep_range(
                    m.latency_min,
                    m.awakenings,
                    m.quality,
-                   m.duration_min
+                   m.duration_min,
+                   m.wake_feeling,
+                   m.sleep_inertia_min,
+                   m.waso_min,
+                   m.efficiency
           FROM sleep_sessions s
           JOIN sleep_metrics m ON m.session_id = s.id
           WHERE COALESCE(s.session_date, s.date) BETWEEN ? AND ?
@@ -370,7 +1295,125 @@ pub async fn list_sleep_range(
     .bind(from)
     .bind(to)
     .fetch_all(db)
-    .await
+    .await?)
+}
+
+#[doc = r#"List sleep sessions in the inclusive range [from, to] tagged `tag`, ordered by date ASC.
+
+`tag` is matched against normalized names, so pass it trimmed and lowercased.
+"#]
+pub async fn list_sleep_range_tagged(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    tag: &str,
+) -> Result<Vec<SleepListItem>, Error> {
+    Ok(sqlx::query_as::<Sqlite, SleepListItem>(
+        r#"SELECT s.id,
+                   COALESCE(s.session_date, s.date) AS date,
+                   s.bed_time,
+                   s.wake_time,
+                   m.latency_min,
+                   m.awakenings,
+                   m.quality,
+                   m.duration_min,
+                   m.wake_feeling,
+                   m.sleep_inertia_min,
+                   m.waso_min,
+                   m.efficiency
+          FROM sleep_sessions s
+          JOIN sleep_metrics m ON m.session_id = s.id
+          JOIN sleep_session_tags st ON st.session_id = s.id
+          JOIN tags t ON t.id = st.tag_id
+          WHERE COALESCE(s.session_date, s.date) BETWEEN ? AND ? AND t.name = ?
+          ORDER BY date ASC, s.wake_time ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(tag)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"List the external references of a sleep session, sorted by source."#]
+pub async fn list_sleep_external_refs(db: &Db, session_id: i64) -> Result<Vec<ExternalRef>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ExternalRef>(
+        "SELECT source, external_id, url FROM external_refs \
+         WHERE sleep_session_id = ? ORDER BY source, external_id",
+    )
+    .bind(session_id)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Whether an entry with this external reference is already recorded."#]
+pub async fn has_external_ref(db: &Db, r: &ExternalRef) -> Result<bool, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, bool>(
+        "SELECT EXISTS(SELECT 1 FROM external_refs WHERE source = ? AND external_id = ?)",
+    )
+    .bind(&r.source)
+    .bind(&r.external_id)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"Link a sleep session to an external reference.
+
+Returns `false` when the reference is already linked (to this or another entry).
+"#]
+pub async fn insert_sleep_external_ref(
+    db: &Db,
+    session_id: i64,
+    r: &ExternalRef,
+) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO external_refs(source, external_id, url, sleep_session_id) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(source, external_id) DO NOTHING",
+    )
+    .bind(&r.source)
+    .bind(&r.external_id)
+    .bind(r.url.as_deref())
+    .bind(session_id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Link an exercise entry to an external reference.
+
+Returns `false` when the reference is already linked (to this or another entry).
+"#]
+pub async fn insert_exercise_external_ref(
+    db: &Db,
+    exercise_id: i64,
+    r: &ExternalRef,
+) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO external_refs(source, external_id, url, exercise_id) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(source, external_id) DO NOTHING",
+    )
+    .bind(&r.source)
+    .bind(&r.external_id)
+    .bind(r.url.as_deref())
+    .bind(exercise_id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Whether a timed exercise event already exists at `date` / `start_time` (re-pushed webhooks)."#]
+pub async fn has_exercise_at(
+    db: &Db,
+    date: NaiveDate,
+    start_time: NaiveTime,
+) -> Result<bool, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, bool>(
+        "SELECT EXISTS(SELECT 1 FROM exercise_events WHERE date = ? AND start_time = ?)",
+    )
+    .bind(date)
+    .bind(start_time)
+    .fetch_one(db)
+    .await?)
 }
 
 #[doc = r#"Insert an exercise event.
@@ -392,6 +1435,7 @@ let input = ExerciseInput {
     intensity: Intensity::Light,
     start_time: None,
     duration_min: Some(30),
+    hr_zones: None,
 };
 input.validate()?;
 let id = repository::insert_exercise(&db, &input).await?;
@@ -399,9 +1443,9 @@ let id = repository::insert_exercise(&db, &input).await?;
 ```
 
 # Errors
-- Returns [`sqlx::Error`] on database errors.
+- Returns [`Error::Database`] on database errors.
 "#]
-pub async fn insert_exercise(db: &Db, input: &ExerciseInput) -> Result<i64, sqlx::Error> {
+pub async fn insert_exercise(db: &Db, input: &ExerciseInput) -> Result<i64, Error> {
     // For "daily intensity" sentinel rows (no time and no duration), upsert by date
     if input.start_time.is_none() && input.duration_min.is_none() {
         let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
@@ -436,20 +1480,99 @@ pub async fn insert_exercise(db: &Db, input: &ExerciseInput) -> Result<i64, sqlx
 
     // Otherwise, treat as a normal exercise event insert
     let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let zones = input.hr_zones.map(|z| z.as_array());
+    let zone = |i: usize| zones.map(|z| z[i]);
     let res = sqlx::query::<Sqlite>(
-        "INSERT INTO exercise_events(date, intensity, start_time, duration_min) VALUES (?, ?, ?, ?)",
+        "INSERT INTO exercise_events(date, intensity, start_time, duration_min, \
+         hr_z1_min, hr_z2_min, hr_z3_min, hr_z4_min, hr_z5_min) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(input.date)
     .bind(input.intensity.to_string())
     .bind(input.start_time)
     .bind(input.duration_min)
+    .bind(zone(0))
+    .bind(zone(1))
+    .bind(zone(2))
+    .bind(zone(3))
+    .bind(zone(4))
     .execute(&mut *tx)
     .await?;
     tx.commit().await?;
     Ok(res.last_insert_rowid())
 }
 
-#[doc = r#"Insert a note for a particular date.
+#[doc = r#"Date of exercise event `id`, if it exists."#]
+pub async fn find_exercise_date(db: &Db, id: i64) -> Result<Option<NaiveDate>, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, NaiveDate>("SELECT date FROM exercise_events WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?,
+    )
+}
+
+#[doc = r#"List exercise events in the inclusive range [from, to] ordered by date, id ASC."#]
+pub async fn list_exercise_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<ExerciseEvent>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ExerciseEvent>(
+        r#"SELECT id, date, intensity, start_time, duration_min
+           FROM exercise_events
+           WHERE date BETWEEN ? AND ?
+           ORDER BY date ASC, id ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Update an exercise event by id, heart-rate zones included (cleared when `None`).
+
+Returns `Ok(false)` when no row exists for `id`.
+
+# Errors
+- Returns [`Error::Database`] on database errors, including a UNIQUE violation when
+  turning the event into a daily intensity entry (no time, no duration) on a date that
+  already has one.
+"#]
+pub async fn update_exercise(db: &Db, id: i64, input: &ExerciseInput) -> Result<bool, Error> {
+    let zones = input.hr_zones.map(|z| z.as_array());
+    let zone = |i: usize| zones.map(|z| z[i]);
+    let res = sqlx::query::<Sqlite>(
+        "UPDATE exercise_events SET date=?, intensity=?, start_time=?, duration_min=?, \
+         hr_z1_min=?, hr_z2_min=?, hr_z3_min=?, hr_z4_min=?, hr_z5_min=? WHERE id=?",
+    )
+    .bind(input.date)
+    .bind(input.intensity.to_string())
+    .bind(input.start_time)
+    .bind(input.duration_min)
+    .bind(zone(0))
+    .bind(zone(1))
+    .bind(zone(2))
+    .bind(zone(3))
+    .bind(zone(4))
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Delete an exercise event by id; its external references go with it.
+
+Returns the number of rows affected (0 if no such id exists).
+"#]
+pub async fn delete_exercise(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM exercise_events WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Insert a note for a particular date, with its tags.
 
 A `None` body is stored as NULL.
 
@@ -468,6 +1591,8 @@ sqlx::migrate::Migrator::new(std::path::Path::new("../migrations")).await?.run(&
 let input = NoteInput {
     date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
     body: Some("Slept well".to_string()),
+    tags: vec!["travel".into()],
+    session_id: None,
 };
 input.validate()?;
 let id = repository::insert_note(&db, &input).await?;
@@ -475,40 +1600,179 @@ let id = repository::insert_note(&db, &input).await?;
 ```
 
 # Errors
-- Returns [`sqlx::Error`] on database errors.
+- Returns [`Error::Database`] on database errors.
 "#]
-pub async fn insert_note(db: &Db, input: &NoteInput) -> Result<i64, sqlx::Error> {
-    let res = sqlx::query::<Sqlite>("INSERT INTO notes(date, body) VALUES (?, ?)")
+pub async fn insert_note(db: &Db, input: &NoteInput) -> Result<i64, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let res = sqlx::query::<Sqlite>("INSERT INTO notes(date, body, session_id) VALUES (?, ?, ?)")
         .bind(input.date)
         .bind(input.body.as_deref())
-        .execute(db)
+        .bind(input.session_id)
+        .execute(&mut *tx)
         .await?;
-    Ok(res.last_insert_rowid())
+    let id = res.last_insert_rowid();
+    replace_tags(&mut tx, TagLink::Note, id, &input.normalized_tags()).await?;
+    tx.commit().await?;
+    Ok(id)
 }
 
-#[doc = r#"Insert one append-only friction telemetry event.
+#[doc = r#"Date of note `id`, if it exists."#]
+pub async fn find_note_date(db: &Db, id: i64) -> Result<Option<NaiveDate>, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, NaiveDate>("SELECT date FROM notes WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?,
+    )
+}
 
-Stored in `personalization_friction_events` for rolling-window personalization analysis.
-"#]
-#[allow(dead_code)]
-pub async fn insert_friction_telemetry(
-    db: &Db,
+#[doc = r#"List notes in the inclusive range [from, to] ordered by date, id ASC, with their tags."#]
+pub async fn list_notes_range(db: &Db, from: NaiveDate, to: NaiveDate) -> Result<Vec<Note>, Error> {
+    let mut notes = sqlx::query_as::<Sqlite, Note>(
+        r#"SELECT id, date, body, session_id
+           FROM notes
+           WHERE date BETWEEN ? AND ?
+           ORDER BY date ASC, id ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?;
+    attach_note_tags(db, &mut notes).await?;
+    Ok(notes)
+}
+
+#[doc = r#"List the notes linked to sleep session `session_id` ordered by id, with their tags."#]
+pub async fn list_session_notes(db: &Db, session_id: i64) -> Result<Vec<Note>, Error> {
+    let mut notes = sqlx::query_as::<Sqlite, Note>(
+        "SELECT id, date, body, session_id FROM notes WHERE session_id = ? ORDER BY id ASC",
+    )
+    .bind(session_id)
+    .fetch_all(db)
+    .await?;
+    attach_note_tags(db, &mut notes).await?;
+    Ok(notes)
+}
+
+#[doc = r#"Update a note by id, replacing its tags. The star is kept.
+
+Returns `Ok(false)` when no row exists for `id`.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn update_note(db: &Db, id: i64, input: &NoteInput) -> Result<bool, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let res = sqlx::query::<Sqlite>("UPDATE notes SET date=?, body=?, session_id=? WHERE id=?")
+        .bind(input.date)
+        .bind(input.body.as_deref())
+        .bind(input.session_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if res.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+    replace_tags(&mut tx, TagLink::Note, id, &input.normalized_tags()).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+#[doc = r#"Delete a note by id.
+
+Returns the number of rows affected (0 if no such id exists).
+"#]
+pub async fn delete_note(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM notes WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Star or unstar a sleep session. Returns whether the session exists."#]
+pub async fn set_sleep_starred(db: &Db, id: i64, starred: bool) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>("UPDATE sleep_sessions SET starred = ? WHERE id = ?")
+        .bind(starred)
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Star or unstar a note. Returns whether the note exists."#]
+pub async fn set_note_starred(db: &Db, id: i64, starred: bool) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>("UPDATE notes SET starred = ? WHERE id = ?")
+        .bind(starred)
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"List starred sleep sessions, newest wake date first."#]
+pub async fn list_starred_sleep(db: &Db) -> Result<Vec<SleepListItem>, Error> {
+    Ok(sqlx::query_as::<Sqlite, SleepListItem>(
+        r#"SELECT s.id,
+                   COALESCE(s.session_date, s.date) AS date,
+                   s.bed_time,
+                   s.wake_time,
+                   m.latency_min,
+                   m.awakenings,
+                   m.quality,
+                   m.duration_min,
+                   m.wake_feeling,
+                   m.sleep_inertia_min,
+                   m.waso_min,
+                   m.efficiency
+          FROM sleep_sessions s
+          JOIN sleep_metrics m ON m.session_id = s.id
+          WHERE s.starred = 1
+          ORDER BY date DESC, s.wake_time DESC"#,
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"List starred notes, newest date first."#]
+pub async fn list_starred_notes(db: &Db) -> Result<Vec<Note>, Error> {
+    let mut notes = sqlx::query_as::<Sqlite, Note>(
+        "SELECT id, date, body, session_id FROM notes WHERE starred = 1 ORDER BY date DESC, id DESC",
+    )
+    .fetch_all(db)
+    .await?;
+    attach_note_tags(db, &mut notes).await?;
+    Ok(notes)
+}
+
+#[doc = r#"Insert one append-only friction telemetry event.
+
+Stored in `personalization_friction_events` for rolling-window personalization analysis.
+`sample_weight` is the number of submissions the event stands for (1 unless it was sampled).
+"#]
+#[allow(dead_code)]
+pub async fn insert_friction_telemetry(
+    db: &Db,
     input: &FrictionTelemetryInput,
-) -> Result<i64, sqlx::Error> {
+    sample_weight: u32,
+) -> Result<i64, Error> {
     let res = sqlx::query::<Sqlite>(
         r#"INSERT INTO personalization_friction_events(
                 form_time_ms,
                 error_kind,
                 retry_count,
                 immediate_edit,
-                follow_up_failure
-            ) VALUES (?, ?, ?, ?, ?)"#,
+                follow_up_failure,
+                sample_weight
+            ) VALUES (?, ?, ?, ?, ?, ?)"#,
     )
     .bind(input.form_time_ms)
     .bind(input.error_kind.as_deref())
     .bind(input.retry_count)
     .bind(input.immediate_edit)
     .bind(input.follow_up_failure)
+    .bind(sample_weight.max(1))
     .execute(db)
     .await?;
     Ok(res.last_insert_rowid())
@@ -520,8 +1784,8 @@ pub async fn list_friction_telemetry_window(
     db: &Db,
     from: NaiveDateTime,
     to: NaiveDateTime,
-) -> Result<Vec<FrictionTelemetryEvent>, sqlx::Error> {
-    sqlx::query_as::<Sqlite, FrictionTelemetryEvent>(
+) -> Result<Vec<FrictionTelemetryEvent>, Error> {
+    Ok(sqlx::query_as::<Sqlite, FrictionTelemetryEvent>(
         r#"SELECT
                 id,
                 recorded_at,
@@ -529,7 +1793,8 @@ pub async fn list_friction_telemetry_window(
                 error_kind,
                 retry_count,
                 immediate_edit,
-                follow_up_failure
+                follow_up_failure,
+                sample_weight
            FROM personalization_friction_events
            WHERE recorded_at BETWEEN ? AND ?
            ORDER BY recorded_at ASC, id ASC"#,
@@ -537,83 +1802,79 @@ pub async fn list_friction_telemetry_window(
     .bind(from)
     .bind(to)
     .fetch_all(db)
-    .await
+    .await?)
 }
 
-#[doc = r#"Compute aggregate friction metrics for an inclusive datetime window [from, to]."#]
+#[doc = r#"Compute aggregate friction metrics for an inclusive datetime window [from, to].
+
+Every event counts `sample_weight` times. The median is taken over the weighted sequence (the
+mean of its middle one or two values)."#]
 #[allow(dead_code)]
 pub async fn aggregate_friction_window(
     db: &Db,
     from: NaiveDateTime,
     to: NaiveDateTime,
-) -> Result<FrictionWindowAggregate, sqlx::Error> {
-    sqlx::query_as::<Sqlite, FrictionWindowAggregate>(
+) -> Result<FrictionWindowAggregate, Error> {
+    Ok(sqlx::query_as::<Sqlite, FrictionWindowAggregate>(
         r#"WITH windowed AS (
                SELECT
                  form_time_ms,
-                 error_kind,
+                 CASE WHEN error_kind IS NOT NULL AND TRIM(error_kind) != '' THEN 1 ELSE 0 END AS is_error,
                  retry_count,
                  immediate_edit,
-                 follow_up_failure
+                 follow_up_failure,
+                 sample_weight AS w
                FROM personalization_friction_events
                WHERE recorded_at BETWEEN ? AND ?
            ),
            ordered AS (
                SELECT
                  form_time_ms,
-                 ROW_NUMBER() OVER (ORDER BY form_time_ms) AS rn,
-                 COUNT(*) OVER () AS cnt
+                 SUM(w) OVER (ORDER BY form_time_ms ROWS UNBOUNDED PRECEDING) AS cum,
+                 SUM(w) OVER () AS total
                FROM windowed
            )
            SELECT
-             COUNT(*) AS submit_count,
-             COALESCE((SELECT AVG(CAST(form_time_ms AS REAL))
-                       FROM ordered
-                       WHERE rn IN ((cnt + 1) / 2, (cnt + 2) / 2)), 0.0) AS median_form_time_ms,
-             COALESCE(AVG(CAST(form_time_ms AS REAL)), 0.0) AS avg_form_time_ms,
-             COALESCE(SUM(CASE WHEN error_kind IS NOT NULL AND TRIM(error_kind) != '' THEN 1 ELSE 0 END), 0) AS error_count,
-             COALESCE(SUM(retry_count), 0) AS retries_total,
-             COALESCE(AVG(CAST(retry_count AS REAL)), 0.0) AS retries_avg,
-             COALESCE(SUM(CASE WHEN immediate_edit = 1 THEN 1 ELSE 0 END), 0) AS immediate_edit_count,
-             COALESCE(SUM(CASE WHEN follow_up_failure = 1 THEN 1 ELSE 0 END), 0) AS follow_up_failure_count,
+             COALESCE(SUM(w), 0) AS submit_count,
+             COUNT(*) AS recorded_count,
              COALESCE(
-                CAST(SUM(CASE WHEN error_kind IS NOT NULL AND TRIM(error_kind) != '' THEN 1 ELSE 0 END) AS REAL)
-                / NULLIF(COUNT(*), 0),
+                ((SELECT MIN(form_time_ms) FROM ordered WHERE cum >= (total + 1) / 2)
+                 + (SELECT MIN(form_time_ms) FROM ordered WHERE cum >= (total + 2) / 2)) / 2.0,
                 0.0
-             ) AS error_rate,
-             COALESCE(
-                CAST(SUM(CASE WHEN immediate_edit = 1 THEN 1 ELSE 0 END) AS REAL)
-                / NULLIF(COUNT(*), 0),
-                0.0
-             ) AS immediate_edit_rate,
-             COALESCE(
-                CAST(SUM(CASE WHEN follow_up_failure = 1 THEN 1 ELSE 0 END) AS REAL)
-                / NULLIF(COUNT(*), 0),
-                0.0
-             ) AS follow_up_failure_rate
+             ) AS median_form_time_ms,
+             COALESCE(CAST(SUM(w * form_time_ms) AS REAL) / NULLIF(SUM(w), 0), 0.0) AS avg_form_time_ms,
+             COALESCE(SUM(w * is_error), 0) AS error_count,
+             COALESCE(SUM(w * retry_count), 0) AS retries_total,
+             COALESCE(CAST(SUM(w * retry_count) AS REAL) / NULLIF(SUM(w), 0), 0.0) AS retries_avg,
+             COALESCE(SUM(w * immediate_edit), 0) AS immediate_edit_count,
+             COALESCE(SUM(w * follow_up_failure), 0) AS follow_up_failure_count,
+             COALESCE(CAST(SUM(w * is_error) AS REAL) / NULLIF(SUM(w), 0), 0.0) AS error_rate,
+             COALESCE(CAST(SUM(w * immediate_edit) AS REAL) / NULLIF(SUM(w), 0), 0.0) AS immediate_edit_rate,
+             COALESCE(CAST(SUM(w * follow_up_failure) AS REAL) / NULLIF(SUM(w), 0), 0.0) AS follow_up_failure_rate
            FROM windowed"#,
     )
     .bind(from)
     .bind(to)
     .fetch_one(db)
-    .await
+    .await?)
 }
 
-#[doc = r#"Aggregate recurrent friction clusters by normalized `error_kind` over [from, to]."#]
+#[doc = r#"Aggregate recurrent friction clusters by normalized `error_kind` over [from, to],
+weighting each event by its `sample_weight`."#]
 #[allow(dead_code)]
 pub async fn aggregate_friction_error_kinds_window(
     db: &Db,
     from: NaiveDateTime,
     to: NaiveDateTime,
-) -> Result<Vec<FrictionErrorKindAggregate>, sqlx::Error> {
-    sqlx::query_as::<Sqlite, FrictionErrorKindAggregate>(
+) -> Result<Vec<FrictionErrorKindAggregate>, Error> {
+    Ok(sqlx::query_as::<Sqlite, FrictionErrorKindAggregate>(
         r#"SELECT
                LOWER(TRIM(error_kind)) AS error_kind,
-               COUNT(*) AS occurrences,
-               COALESCE(SUM(retry_count), 0) AS retries_total,
-               COALESCE(AVG(CAST(form_time_ms AS REAL)), 0.0) AS avg_form_time_ms,
-               COALESCE(SUM(CASE WHEN immediate_edit = 1 THEN 1 ELSE 0 END), 0) AS immediate_edit_count,
-               COALESCE(SUM(CASE WHEN follow_up_failure = 1 THEN 1 ELSE 0 END), 0) AS follow_up_failure_count
+               SUM(sample_weight) AS occurrences,
+               COALESCE(SUM(sample_weight * retry_count), 0) AS retries_total,
+               COALESCE(CAST(SUM(sample_weight * form_time_ms) AS REAL) / SUM(sample_weight), 0.0) AS avg_form_time_ms,
+               COALESCE(SUM(sample_weight * immediate_edit), 0) AS immediate_edit_count,
+               COALESCE(SUM(sample_weight * follow_up_failure), 0) AS follow_up_failure_count
            FROM personalization_friction_events
            WHERE recorded_at BETWEEN ? AND ?
              AND error_kind IS NOT NULL
@@ -624,5 +1885,1460 @@ pub async fn aggregate_friction_error_kinds_window(
     .bind(from)
     .bind(to)
     .fetch_all(db)
-    .await
+    .await?)
+}
+
+#[doc = r#"Insert or replace the body metrics reading for `input.date`.
+
+Readings are unique per date; an existing reading for the same date is overwritten
+(including its `source`). Returns the row id.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn upsert_body_metric(
+    db: &Db,
+    input: &BodyMetricInput,
+    source: &str,
+) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, i64>(
+        r#"INSERT INTO body_metrics(date, weight_kg, body_fat_pct, source) VALUES (?, ?, ?, ?)
+           ON CONFLICT(date) DO UPDATE SET
+             weight_kg = excluded.weight_kg,
+             body_fat_pct = excluded.body_fat_pct,
+             source = excluded.source
+           RETURNING id"#,
+    )
+    .bind(input.date)
+    .bind(input.weight_kg)
+    .bind(input.body_fat_pct)
+    .bind(source)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"Upsert many body metrics readings from one import in a single transaction.
+
+Returns the number of readings written.
+
+# Errors
+- Returns [`Error::Database`] on database errors; no rows are written in that case.
+"#]
+pub async fn upsert_body_metrics_batch(
+    db: &Db,
+    inputs: &[BodyMetricInput],
+    source: &str,
+) -> Result<usize, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    for input in inputs {
+        sqlx::query::<Sqlite>(
+            r#"INSERT INTO body_metrics(date, weight_kg, body_fat_pct, source) VALUES (?, ?, ?, ?)
+               ON CONFLICT(date) DO UPDATE SET
+                 weight_kg = excluded.weight_kg,
+                 body_fat_pct = excluded.body_fat_pct,
+                 source = excluded.source"#,
+        )
+        .bind(input.date)
+        .bind(input.weight_kg)
+        .bind(input.body_fat_pct)
+        .bind(source)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(inputs.len())
+}
+
+#[doc = r#"List body metrics readings in the inclusive range [from, to] ordered by date ASC."#]
+pub async fn list_body_metrics_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<BodyMetric>, Error> {
+    Ok(sqlx::query_as::<Sqlite, BodyMetric>(
+        r#"SELECT id, date, weight_kg, body_fat_pct, source
+           FROM body_metrics
+           WHERE date BETWEEN ? AND ?
+           ORDER BY date ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Update a body metrics reading by id.
+
+Returns `Ok(false)` when no row exists for `id`. Manual edits reset `source` to `manual`.
+
+# Errors
+- Returns [`Error::Database`] on database errors, including a UNIQUE violation when
+  moving the reading onto a date that already has one.
+"#]
+pub async fn update_body_metric(db: &Db, id: i64, input: &BodyMetricInput) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "UPDATE body_metrics SET date=?, weight_kg=?, body_fat_pct=?, source='manual' WHERE id=?",
+    )
+    .bind(input.date)
+    .bind(input.weight_kg)
+    .bind(input.body_fat_pct)
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Delete a body metrics reading by id.
+
+Returns the number of rows affected (0 if no such id exists).
+"#]
+pub async fn delete_body_metric(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM body_metrics WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Date of body metrics reading `id`, if it exists."#]
+pub async fn find_body_metric_date(db: &Db, id: i64) -> Result<Option<NaiveDate>, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, NaiveDate>("SELECT date FROM body_metrics WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?,
+    )
+}
+
+#[doc = r#"Date of disturbance `id`, if it exists."#]
+pub async fn find_disturbance_date(db: &Db, id: i64) -> Result<Option<NaiveDate>, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, NaiveDate>("SELECT date FROM disturbances WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?,
+    )
+}
+
+#[doc = r#"Insert a disturbance event. Returns the row id.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn insert_disturbance(db: &Db, input: &DisturbanceInput) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, i64>(
+        "INSERT INTO disturbances(date, time, kind, duration_min) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(input.date)
+    .bind(input.time)
+    .bind(input.kind.to_string())
+    .bind(input.duration_min)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"List disturbance events in the inclusive range [from, to] ordered by date, time ASC."#]
+pub async fn list_disturbances_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Disturbance>, Error> {
+    Ok(sqlx::query_as::<Sqlite, Disturbance>(
+        r#"SELECT id, date, time, kind, duration_min
+           FROM disturbances
+           WHERE date BETWEEN ? AND ?
+           ORDER BY date ASC, time ASC, id ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Update a disturbance event by id.
+
+Returns `Ok(false)` when no row exists for `id`.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn update_disturbance(db: &Db, id: i64, input: &DisturbanceInput) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "UPDATE disturbances SET date=?, time=?, kind=?, duration_min=? WHERE id=?",
+    )
+    .bind(input.date)
+    .bind(input.time)
+    .bind(input.kind.to_string())
+    .bind(input.duration_min)
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Delete a disturbance event by id.
+
+Returns the number of rows affected (0 if no such id exists).
+"#]
+pub async fn delete_disturbance(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM disturbances WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Insert an experiment. Returns the row id.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn insert_experiment(db: &Db, input: &ExperimentInput) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, i64>(
+        "INSERT INTO experiments(name, start_date, end_date, description) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(&input.name)
+    .bind(input.start_date)
+    .bind(input.end_date)
+    .bind(&input.description)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"List all experiments, most recent start first."#]
+pub async fn list_experiments(db: &Db) -> Result<Vec<Experiment>, Error> {
+    Ok(sqlx::query_as::<Sqlite, Experiment>(
+        "SELECT id, name, start_date, end_date, description FROM experiments ORDER BY start_date DESC, id DESC",
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Find an experiment by id."#]
+pub async fn find_experiment(db: &Db, id: i64) -> Result<Option<Experiment>, Error> {
+    Ok(sqlx::query_as::<Sqlite, Experiment>(
+        "SELECT id, name, start_date, end_date, description FROM experiments WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"Update an experiment by id.
+
+Returns `Ok(false)` when no row exists for `id`.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn update_experiment(db: &Db, id: i64, input: &ExperimentInput) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "UPDATE experiments SET name=?, start_date=?, end_date=?, description=? WHERE id=?",
+    )
+    .bind(&input.name)
+    .bind(input.start_date)
+    .bind(input.end_date)
+    .bind(&input.description)
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Delete an experiment by id.
+
+Returns the number of rows affected (0 if no such id exists).
+"#]
+pub async fn delete_experiment(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM experiments WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Insert a medication. Returns the row id.
+
+# Errors
+- Returns [`Error::Database`] on database errors, including a unique violation on `name`.
+"#]
+pub async fn insert_medication(db: &Db, input: &MedicationInput) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, i64>(
+        "INSERT INTO medications(name, dose, prescription) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(&input.name)
+    .bind(&input.dose)
+    .bind(input.prescription)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"List all medications ordered by name."#]
+pub async fn list_medications(db: &Db) -> Result<Vec<Medication>, Error> {
+    Ok(sqlx::query_as::<Sqlite, Medication>(
+        "SELECT id, name, dose, prescription FROM medications ORDER BY name ASC, id ASC",
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Whether medication `id` exists."#]
+pub async fn medication_exists(db: &Db, id: i64) -> Result<bool, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, i64>("SELECT 1 FROM medications WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?
+            .is_some(),
+    )
+}
+
+#[doc = r#"Update a medication by id.
+
+Returns `Ok(false)` when no row exists for `id`.
+
+# Errors
+- Returns [`Error::Database`] on database errors, including a unique violation on `name`.
+"#]
+pub async fn update_medication(db: &Db, id: i64, input: &MedicationInput) -> Result<bool, Error> {
+    let res =
+        sqlx::query::<Sqlite>("UPDATE medications SET name=?, dose=?, prescription=? WHERE id=?")
+            .bind(&input.name)
+            .bind(&input.dose)
+            .bind(input.prescription)
+            .bind(id)
+            .execute(db)
+            .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Delete a medication by id, together with its logged events.
+
+Returns the number of medications removed (0 if no such id exists).
+"#]
+pub async fn delete_medication(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM medications WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Date of medication event `id`, if it exists."#]
+pub async fn find_medication_event_date(db: &Db, id: i64) -> Result<Option<NaiveDate>, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, NaiveDate>("SELECT date FROM medication_events WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?,
+    )
+}
+
+#[doc = r#"Insert a medication event. Returns the row id.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn insert_medication_event(db: &Db, input: &MedicationEventInput) -> Result<i64, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, i64>(
+        "INSERT INTO medication_events(medication_id, date, time, dose) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(input.medication_id)
+    .bind(input.date)
+    .bind(input.time)
+    .bind(&input.dose)
+    .fetch_one(db)
+    .await?)
+}
+
+#[doc = r#"List medication events in the inclusive range [from, to] ordered by date, time ASC.
+
+`dose` falls back to the medication's usual dose when the event has none.
+"#]
+pub async fn list_medication_events_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<MedicationEvent>, Error> {
+    Ok(sqlx::query_as::<Sqlite, MedicationEvent>(
+        r#"SELECT e.id, e.medication_id, m.name, e.date, e.time,
+                  COALESCE(e.dose, m.dose) AS dose
+           FROM medication_events e
+           JOIN medications m ON m.id = e.medication_id
+           WHERE e.date BETWEEN ? AND ?
+           ORDER BY e.date ASC, e.time ASC, e.id ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Distinct (date, medication name) pairs logged in the inclusive range [from, to]."#]
+pub async fn list_medication_nights_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(NaiveDate, String)>, Error> {
+    Ok(sqlx::query_as::<Sqlite, (NaiveDate, String)>(
+        r#"SELECT DISTINCT e.date, m.name
+           FROM medication_events e
+           JOIN medications m ON m.id = e.medication_id
+           WHERE e.date BETWEEN ? AND ?
+           ORDER BY e.date ASC, m.name ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Update a medication event by id.
+
+Returns `Ok(false)` when no row exists for `id`.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn update_medication_event(
+    db: &Db,
+    id: i64,
+    input: &MedicationEventInput,
+) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "UPDATE medication_events SET medication_id=?, date=?, time=?, dose=? WHERE id=?",
+    )
+    .bind(input.medication_id)
+    .bind(input.date)
+    .bind(input.time)
+    .bind(&input.dose)
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Delete a medication event by id.
+
+Returns the number of rows affected (0 if no such id exists).
+"#]
+pub async fn delete_medication_event(db: &Db, id: i64) -> Result<u64, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM medication_events WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[doc = r#"Insert or replace the mood rating of `input.date`.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn upsert_mood(db: &Db, input: &MoodInput) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(
+        "INSERT INTO mood_entries(date, mood) VALUES (?, ?) \
+         ON CONFLICT(date) DO UPDATE SET mood = excluded.mood",
+    )
+    .bind(input.date)
+    .bind(input.mood)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"List mood ratings in the inclusive range [from, to] ordered by date ASC."#]
+pub async fn list_mood_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<MoodEntry>, Error> {
+    Ok(sqlx::query_as::<Sqlite, MoodEntry>(
+        "SELECT date, mood FROM mood_entries WHERE date BETWEEN ? AND ? ORDER BY date ASC",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Wake dates in the inclusive range [from, to] without any sleep session, ascending.
+
+Expands the range with [`DAY_SERIES_CTE`](crate::calendar::DAY_SERIES_CTE)."#]
+pub async fn list_missing_sleep_dates(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<NaiveDate>, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, NaiveDate>(&format!(
+        "WITH RECURSIVE {} \
+         SELECT d.day FROM days d \
+         LEFT JOIN (SELECT wake_date FROM v_daily_sleep WHERE wake_date BETWEEN ? AND ?) v \
+           ON v.wake_date = d.day \
+         WHERE v.wake_date IS NULL \
+         ORDER BY d.day ASC",
+        crate::calendar::DAY_SERIES_CTE
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"List daily aggregates from `v_daily_sleep` in the inclusive range [from, to].
+
+Same shape as [`list_recent_sleep`], ordered by date ASC.
+"#]
+pub async fn list_daily_sleep_range(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<SleepListItem>, Error> {
+    Ok(sqlx::query_as::<Sqlite, SleepListItem>(
+        r#"SELECT id,
+                   wake_date AS date,
+                   bed_time,
+                   wake_time,
+                   latency_min,
+                   awakenings,
+                   quality,
+                   duration_min,
+                   wake_feeling,
+                   sleep_inertia_min,
+                   waso_min,
+                   efficiency
+          FROM v_daily_sleep
+          WHERE wake_date BETWEEN ? AND ?
+          ORDER BY date ASC"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Describe the live schema: tables and views with their columns and definitions.
+
+SQLite internal objects (`sqlite_*`) and `_sqlx_migrations` are excluded from `objects`;
+the latter is only used to report `schema_version`.
+
+# Errors
+- Returns [`Error::Database`] on database errors.
+"#]
+pub async fn describe_schema(db: &Db) -> Result<SchemaDescription, Error> {
+    let schema_version = sqlx::query_scalar::<Sqlite, Option<i64>>(
+        "SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1",
+    )
+    .fetch_one(db)
+    .await?;
+
+    let rows = sqlx::query_as::<Sqlite, (String, String, Option<String>)>(
+        r#"SELECT name, type, sql
+           FROM sqlite_master
+           WHERE type IN ('table', 'view')
+             AND name NOT LIKE 'sqlite_%'
+             AND name != '_sqlx_migrations'
+           ORDER BY type ASC, name ASC"#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut objects = Vec::with_capacity(rows.len());
+    for (name, kind, sql) in rows {
+        let columns = sqlx::query_as::<Sqlite, SchemaColumn>(
+            r#"SELECT name,
+                      type AS data_type,
+                      "notnull" AS not_null,
+                      dflt_value AS default_value,
+                      pk AS primary_key
+               FROM pragma_table_info(?)
+               ORDER BY cid ASC"#,
+        )
+        .bind(&name)
+        .fetch_all(db)
+        .await?;
+        objects.push(SchemaObject {
+            name,
+            kind,
+            sql,
+            columns,
+        });
+    }
+
+    Ok(SchemaDescription {
+        schema_version,
+        objects,
+    })
+}
+
+#[doc = r#"Record the start of a background job run and return its id."#]
+pub async fn start_job_run(db: &Db, job: &str) -> Result<i64, Error> {
+    let res = sqlx::query::<Sqlite>("INSERT INTO job_runs(job) VALUES (?)")
+        .bind(job)
+        .execute(db)
+        .await?;
+    Ok(res.last_insert_rowid())
+}
+
+#[doc = r#"Mark a job run as finished with `status` (`ok` or `error`) and an optional detail."#]
+pub async fn finish_job_run(
+    db: &Db,
+    id: i64,
+    status: &str,
+    detail: Option<&str>,
+) -> Result<(), Error> {
+    sqlx::query::<Sqlite>(
+        "UPDATE job_runs SET finished_at = CURRENT_TIMESTAMP, status = ?, detail = ? WHERE id = ?",
+    )
+    .bind(status)
+    .bind(detail)
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[doc = r#"Find a job run by id."#]
+pub async fn find_job_run(db: &Db, id: i64) -> Result<Option<JobRun>, Error> {
+    Ok(sqlx::query_as::<Sqlite, JobRun>(
+        "SELECT id, job, started_at, finished_at, status, detail FROM job_runs WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"List the most recent job runs, newest first."#]
+pub async fn list_job_runs(db: &Db, limit: i64) -> Result<Vec<JobRun>, Error> {
+    Ok(sqlx::query_as::<Sqlite, JobRun>(
+        r#"SELECT id, job, started_at, finished_at, status, detail
+           FROM job_runs
+           ORDER BY started_at DESC, id DESC
+           LIMIT ?"#,
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Per-night values of `metric` for wake dates in `[from, to]`, ascending."#]
+pub async fn daily_metric_values(
+    db: &Db,
+    metric: AlertMetric,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(NaiveDate, Option<f64>)>, Error> {
+    // The column name comes from a fixed enum, never from user input.
+    let sql = format!(
+        "SELECT wake_date, CAST({} AS REAL) FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC",
+        metric.column()
+    );
+    Ok(sqlx::query_as::<Sqlite, (NaiveDate, Option<f64>)>(&sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(db)
+        .await?)
+}
+
+#[doc = r#"Record a fired alert. Returns `None` when the rule already fired for `as_of`."#]
+pub async fn insert_alert_event(
+    db: &Db,
+    rule_id: &str,
+    as_of: NaiveDate,
+    value: f64,
+    message: &str,
+) -> Result<Option<i64>, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO alert_events(rule_id, as_of, value, message) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(rule_id, as_of) DO NOTHING",
+    )
+    .bind(rule_id)
+    .bind(as_of)
+    .bind(value)
+    .bind(message)
+    .execute(db)
+    .await?;
+    Ok((res.rows_affected() > 0).then(|| res.last_insert_rowid()))
+}
+
+#[doc = r#"Record which notification channels accepted alert `id`."#]
+pub async fn set_alert_delivery(db: &Db, id: i64, channels: &str) -> Result<(), Error> {
+    sqlx::query::<Sqlite>("UPDATE alert_events SET delivered_via = ? WHERE id = ?")
+        .bind(channels)
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+#[doc = r#"List the most recent fired alerts, newest first."#]
+pub async fn list_alert_events(db: &Db, limit: i64) -> Result<Vec<AlertEvent>, Error> {
+    Ok(sqlx::query_as::<Sqlite, AlertEvent>(
+        r#"SELECT id, rule_id, as_of, value, message, delivered_via, fired_at
+           FROM alert_events
+           ORDER BY fired_at DESC, id DESC
+           LIMIT ?"#,
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Record a login from the device with `fingerprint`.
+
+Returns `true` when the device was not known before; known devices get their `last_seen_at`,
+`login_count`, and `label` updated.
+"#]
+pub async fn record_device_login(db: &Db, fingerprint: &str, label: &str) -> Result<bool, Error> {
+    let updated = sqlx::query::<Sqlite>(
+        "UPDATE known_devices \
+         SET last_seen_at = CURRENT_TIMESTAMP, login_count = login_count + 1, label = ? \
+         WHERE fingerprint = ?",
+    )
+    .bind(label)
+    .bind(fingerprint)
+    .execute(db)
+    .await?
+    .rows_affected();
+    if updated > 0 {
+        return Ok(false);
+    }
+    let inserted = sqlx::query::<Sqlite>(
+        "INSERT INTO known_devices(fingerprint, label) VALUES (?, ?) \
+         ON CONFLICT(fingerprint) DO NOTHING",
+    )
+    .bind(fingerprint)
+    .bind(label)
+    .execute(db)
+    .await?
+    .rows_affected();
+    Ok(inserted > 0)
+}
+
+#[doc = r#"Number of known login devices."#]
+pub async fn count_known_devices(db: &Db) -> Result<i64, Error> {
+    Ok(
+        sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM known_devices")
+            .fetch_one(db)
+            .await?,
+    )
+}
+
+#[doc = r#"List known login devices, most recently seen first, flagging `current_fingerprint`."#]
+pub async fn list_known_devices(
+    db: &Db,
+    current_fingerprint: &str,
+) -> Result<Vec<KnownDevice>, Error> {
+    Ok(sqlx::query_as::<Sqlite, KnownDevice>(
+        r#"SELECT id, label, first_seen_at, last_seen_at, login_count,
+                  fingerprint = ? AS current
+           FROM known_devices
+           ORDER BY last_seen_at DESC, id DESC"#,
+    )
+    .bind(current_fingerprint)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Forget a known device. Returns whether a row was deleted."#]
+pub async fn delete_known_device(db: &Db, id: i64) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM known_devices WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Store a new API token by the hash of its secret and return its id."#]
+pub async fn insert_api_token(
+    db: &Db,
+    name: &str,
+    scope: &str,
+    token_hash: &str,
+    expires_at: NaiveDateTime,
+) -> Result<i64, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO api_tokens(name, scope, token_hash, expires_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(name)
+    .bind(scope)
+    .bind(token_hash)
+    .bind(expires_at)
+    .execute(db)
+    .await?;
+    Ok(res.last_insert_rowid())
+}
+
+#[doc = r#"Find an API token by id."#]
+pub async fn find_api_token(db: &Db, id: i64) -> Result<Option<ApiToken>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ApiToken>(
+        "SELECT id, name, scope, created_at, expires_at, last_used_at FROM api_tokens WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"List API tokens, newest first."#]
+pub async fn list_api_tokens(db: &Db) -> Result<Vec<ApiToken>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ApiToken>(
+        "SELECT id, name, scope, created_at, expires_at, last_used_at FROM api_tokens \
+         ORDER BY created_at DESC, id DESC",
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Look up the token whose secret hashes to `token_hash` and record its use at `now`.
+
+Expired tokens are returned unchanged (the caller rejects them) and their `last_used_at` is
+not touched.
+"#]
+pub async fn use_api_token(
+    db: &Db,
+    token_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<ApiToken>, Error> {
+    let token = sqlx::query_as::<Sqlite, ApiToken>(
+        "SELECT id, name, scope, created_at, expires_at, last_used_at FROM api_tokens \
+         WHERE token_hash = ?",
+    )
+    .bind(token_hash)
+    .fetch_optional(db)
+    .await?;
+    match token {
+        Some(mut token) if token.expires_at > now => {
+            sqlx::query::<Sqlite>("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(token.id)
+                .execute(db)
+                .await?;
+            token.last_used_at = Some(now);
+            Ok(Some(token))
+        }
+        other => Ok(other),
+    }
+}
+
+#[doc = r#"Revoke (delete) an API token. Returns whether a row was deleted."#]
+pub async fn delete_api_token(db: &Db, id: i64) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM api_tokens WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Store a new share link by the hash of its secret and return its id."#]
+pub async fn insert_share_link(
+    db: &Db,
+    token_hash: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    expires_at: NaiveDateTime,
+    max_uses: Option<u32>,
+) -> Result<i64, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO share_links(token_hash, from_date, to_date, expires_at, max_uses) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(token_hash)
+    .bind(from)
+    .bind(to)
+    .bind(expires_at)
+    .bind(max_uses)
+    .execute(db)
+    .await?;
+    Ok(res.last_insert_rowid())
+}
+
+#[doc = r#"Find a share link by id."#]
+pub async fn find_share_link(db: &Db, id: i64) -> Result<Option<ShareLink>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ShareLink>(
+        "SELECT id, from_date, to_date, created_at, expires_at, last_used_at, use_count, max_uses \
+         FROM share_links \
+         WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"List share links, newest first."#]
+pub async fn list_share_links(db: &Db) -> Result<Vec<ShareLink>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ShareLink>(
+        "SELECT id, from_date, to_date, created_at, expires_at, last_used_at, use_count, max_uses \
+         FROM share_links \
+         ORDER BY created_at DESC, id DESC",
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Look up the usable share link whose secret hashes to `token_hash` and record its
+use at `now` by client address `ip`. Unknown, expired and used-up links all yield `None`.
+
+The use count is checked and incremented in one statement, so concurrent views cannot exceed
+`max_uses`."#]
+pub async fn use_share_link(
+    db: &Db,
+    token_hash: &str,
+    now: NaiveDateTime,
+    ip: &str,
+) -> Result<Option<ShareLink>, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let link = sqlx::query_as::<Sqlite, ShareLink>(
+        "UPDATE share_links SET use_count = use_count + 1, last_used_at = ? \
+         WHERE token_hash = ? AND expires_at > ? AND (max_uses IS NULL OR use_count < max_uses) \
+         RETURNING id, from_date, to_date, created_at, expires_at, last_used_at, use_count, \
+         max_uses",
+    )
+    .bind(now)
+    .bind(token_hash)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(link) = link else {
+        return Ok(None);
+    };
+    sqlx::query::<Sqlite>(
+        "INSERT INTO share_link_visitors(link_id, ip, first_used_at, last_used_at) \
+         VALUES (?, ?, ?, ?) \
+         ON CONFLICT(link_id, ip) DO UPDATE SET use_count = use_count + 1, \
+         last_used_at = excluded.last_used_at",
+    )
+    .bind(link.id)
+    .bind(ip)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Some(link))
+}
+
+#[doc = r#"Client addresses that opened share link `id`, most recently seen first."#]
+pub async fn list_share_link_visitors(db: &Db, id: i64) -> Result<Vec<ShareLinkVisitor>, Error> {
+    Ok(sqlx::query_as::<Sqlite, ShareLinkVisitor>(
+        "SELECT ip, use_count, first_used_at, last_used_at FROM share_link_visitors \
+         WHERE link_id = ? ORDER BY last_used_at DESC, ip ASC",
+    )
+    .bind(id)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Revoke (delete) a share link. Returns whether a row was deleted."#]
+pub async fn delete_share_link(db: &Db, id: i64) -> Result<bool, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    sqlx::query::<Sqlite>("DELETE FROM share_link_visitors WHERE link_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let res = sqlx::query::<Sqlite>("DELETE FROM share_links WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Start time (UTC) of the running sleep timer, if any."#]
+pub async fn get_sleep_timer(db: &Db) -> Result<Option<NaiveDateTime>, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, NaiveDateTime>(
+        "SELECT started_at FROM sleep_timer WHERE id = 1",
+    )
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"Start the sleep timer at `started_at`. Returns `false` when one is already running."#]
+pub async fn start_sleep_timer(db: &Db, started_at: NaiveDateTime) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO sleep_timer(id, started_at) VALUES (1, ?) ON CONFLICT(id) DO NOTHING",
+    )
+    .bind(started_at)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"Discard the running sleep timer. Returns whether one was running."#]
+pub async fn clear_sleep_timer(db: &Db) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM sleep_timer WHERE id = 1")
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"The login email: the confirmed change stored for `configured` (`ADMIN_EMAIL`), or
+`configured` itself."#]
+pub async fn get_login_email(db: &Db, configured: &str) -> String {
+    let result = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT email FROM account_email WHERE id = 1 AND source_email = ?",
+    )
+    .bind(configured)
+    .fetch_optional(db)
+    .await;
+    match result {
+        Ok(Some(email)) => email,
+        Ok(None) => configured.to_string(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read account email; using ADMIN_EMAIL");
+            configured.to_string()
+        }
+    }
+}
+
+#[doc = r#"Store a pending login email change by the hash of its secret and return its id."#]
+pub async fn insert_email_change(
+    db: &Db,
+    token_hash: &str,
+    new_email: &str,
+    source_email: &str,
+    expires_at: NaiveDateTime,
+) -> Result<i64, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO email_changes(token_hash, new_email, source_email, expires_at) \
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(token_hash)
+    .bind(new_email)
+    .bind(source_email)
+    .bind(expires_at)
+    .execute(db)
+    .await?;
+    Ok(res.last_insert_rowid())
+}
+
+#[doc = r#"Confirm the pending email change with `token_hash` and switch the login email.
+
+In one transaction: marks the change confirmed, discards other pending changes, stores the
+new email as the override of `configured` (`ADMIN_EMAIL`), and moves an upgraded password
+hash from `login_email` to the new email.
+
+Returns `(id, new_email)`, or `None` when no unconfirmed change with that hash, requested for
+`login_email`, is still valid at `now`."#]
+pub async fn confirm_email_change(
+    db: &Db,
+    token_hash: &str,
+    now: NaiveDateTime,
+    login_email: &str,
+    configured: &str,
+) -> Result<Option<(i64, String)>, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let Some((id, new_email)) = sqlx::query_as::<Sqlite, (i64, String)>(
+        "SELECT id, new_email FROM email_changes \
+         WHERE token_hash = ? AND confirmed_at IS NULL AND expires_at > ? AND source_email = ?",
+    )
+    .bind(token_hash)
+    .bind(now)
+    .bind(login_email)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+    sqlx::query::<Sqlite>("UPDATE email_changes SET confirmed_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query::<Sqlite>("DELETE FROM email_changes WHERE confirmed_at IS NULL")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO account_email(id, email, source_email) VALUES (1, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET email = excluded.email, \
+         source_email = excluded.source_email, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&new_email)
+    .bind(configured)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query::<Sqlite>("DELETE FROM password_hashes WHERE account = ?")
+        .bind(&new_email)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query::<Sqlite>("UPDATE password_hashes SET account = ? WHERE account = ?")
+        .bind(&new_email)
+        .bind(login_email)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(Some((id, new_email)))
+}
+
+#[doc = r#"Append an entry to the audit log and return its id.
+
+`entity_id` is `None` for operations that are not about a single record."#]
+pub async fn insert_audit_entry(
+    db: &Db,
+    action: &str,
+    entity: &str,
+    entity_id: Option<i64>,
+    reason: &AuditReason,
+) -> Result<i64, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO audit_log(action, entity, entity_id, reason, note) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(action)
+    .bind(entity)
+    .bind(entity_id)
+    .bind(reason.reason.as_deref())
+    .bind(reason.note.as_deref())
+    .execute(db)
+    .await?;
+    Ok(res.last_insert_rowid())
+}
+
+#[doc = r#"List audit log entries matching `query`, newest (highest id) first.
+
+Entries moved to the yearly `audit_log_archive_<year>` tables by the telemetry archive job
+([`archive_rows_before`]) are listed with the hot ones; archived rows keep their ids, so the
+cursor pages through both.
+
+Filters on `entity`, `action`, the `from`/`to` dates of `recorded_at`, and `cursor` (only ids
+below it) are applied only when set; `query.limit` is ignored in favour of `limit`.
+"#]
+pub async fn list_audit_entries(
+    db: &Db,
+    query: &AuditQuery,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, Error> {
+    const COLUMNS: &str = "id, recorded_at, action, entity, entity_id, reason, note";
+    let archives = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT name FROM sqlite_master \
+         WHERE type = 'table' AND name GLOB 'audit_log_archive_[0-9]*' ORDER BY name",
+    )
+    .fetch_all(db)
+    .await?;
+    let mut source = format!("SELECT {COLUMNS} FROM audit_log");
+    for archive in archives {
+        source.push_str(&format!(" UNION ALL SELECT {COLUMNS} FROM {archive}"));
+    }
+    let mut qb =
+        QueryBuilder::<Sqlite>::new(format!("SELECT {COLUMNS} FROM ({source}) WHERE 1 = 1"));
+    if let Some(entity) = &query.entity {
+        qb.push(" AND entity = ").push_bind(entity);
+    }
+    if let Some(action) = &query.action {
+        qb.push(" AND action = ").push_bind(action);
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND recorded_at >= ")
+            .push_bind(from.and_time(NaiveTime::MIN));
+    }
+    if let Some(to) = query.to.and_then(|d| d.succ_opt()) {
+        qb.push(" AND recorded_at < ")
+            .push_bind(to.and_time(NaiveTime::MIN));
+    }
+    if let Some(cursor) = query.cursor {
+        qb.push(" AND id < ").push_bind(cursor);
+    }
+    qb.push(" ORDER BY id DESC LIMIT ").push_bind(limit);
+    Ok(qb.build_query_as::<AuditEntry>().fetch_all(db).await?)
+}
+
+#[doc = r#"Return the start time of the latest successful run of `job` whose detail matches
+the SQL `LIKE` pattern `detail_like` (use `%` to match any detail)."#]
+pub async fn last_successful_job_run(
+    db: &Db,
+    job: &str,
+    detail_like: &str,
+) -> Result<Option<NaiveDateTime>, Error> {
+    Ok(sqlx::query_scalar::<Sqlite, NaiveDateTime>(
+        r#"SELECT started_at
+           FROM job_runs
+           WHERE job = ? AND status = 'ok' AND COALESCE(detail, '') LIKE ?
+           ORDER BY started_at DESC, id DESC
+           LIMIT 1"#,
+    )
+    .bind(job)
+    .bind(detail_like)
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"Move rows of an append-only `table` older than `cutoff` into yearly archive tables.
+
+Rows are grouped by the year of `ts_column` and copied into `<table>_archive_<year>`, which is
+created on first use with the hot table's columns and an index on `ts_column`. The copy and the
+delete run in one transaction while an `archive_guard` row for `table` is held, so the table's
+append-only delete trigger only admits archive moves. Returns `(year, moved_rows)` per year.
+
+`table` and `ts_column` are interpolated into SQL and must be trusted identifiers.
+"#]
+pub async fn archive_rows_before(
+    db: &Db,
+    table: &str,
+    ts_column: &str,
+    cutoff: NaiveDateTime,
+) -> Result<Vec<(i32, u64)>, Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    let years = sqlx::query_scalar::<Sqlite, i32>(&format!(
+        "SELECT DISTINCT CAST(strftime('%Y', {ts_column}) AS INTEGER) FROM {table} \
+         WHERE {ts_column} < ? ORDER BY 1"
+    ))
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+    if years.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query::<Sqlite>("INSERT INTO archive_guard(table_name) VALUES (?)")
+        .bind(table)
+        .execute(&mut *tx)
+        .await?;
+    let mut moved = Vec::with_capacity(years.len());
+    for year in years {
+        let archive = format!("{table}_archive_{year}");
+        sqlx::query::<Sqlite>(&format!(
+            "CREATE TABLE IF NOT EXISTS {archive} AS SELECT * FROM {table} WHERE 0"
+        ))
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query::<Sqlite>(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_{archive}_{ts_column} ON {archive}({ts_column})"
+        ))
+        .execute(&mut *tx)
+        .await?;
+        // Archives created before a column was added to the source table lack it.
+        let missing = sqlx::query_scalar::<Sqlite, String>(
+            "SELECT name FROM pragma_table_info(?) \
+             WHERE name NOT IN (SELECT name FROM pragma_table_info(?))",
+        )
+        .bind(table)
+        .bind(&archive)
+        .fetch_all(&mut *tx)
+        .await?;
+        for column in missing {
+            sqlx::query::<Sqlite>(&format!("ALTER TABLE {archive} ADD COLUMN \"{column}\""))
+                .execute(&mut *tx)
+                .await?;
+        }
+        let columns = sqlx::query_scalar::<Sqlite, String>(
+            "SELECT '\"' || name || '\"' FROM pragma_table_info(?) ORDER BY cid",
+        )
+        .bind(table)
+        .fetch_all(&mut *tx)
+        .await?
+        .join(", ");
+        let filter =
+            format!("{ts_column} < ? AND CAST(strftime('%Y', {ts_column}) AS INTEGER) = ?");
+        sqlx::query::<Sqlite>(&format!(
+            "INSERT INTO {archive} ({columns}) SELECT {columns} FROM {table} WHERE {filter}"
+        ))
+        .bind(cutoff)
+        .bind(year)
+        .execute(&mut *tx)
+        .await?;
+        let res = sqlx::query::<Sqlite>(&format!("DELETE FROM {table} WHERE {filter}"))
+            .bind(cutoff)
+            .bind(year)
+            .execute(&mut *tx)
+            .await?;
+        moved.push((year, res.rows_affected()));
+    }
+    sqlx::query::<Sqlite>("DELETE FROM archive_guard WHERE table_name = ?")
+        .bind(table)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(moved)
+}
+
+#[doc = r#"Replace the routine entries recorded for an evening in a single transaction."#]
+pub async fn replace_routine_entries(
+    db: &Db,
+    date: NaiveDate,
+    entries: &[RoutineEntry],
+) -> Result<(), Error> {
+    let mut tx: Transaction<'_, Sqlite> = db.begin().await?;
+    sqlx::query::<Sqlite>("DELETE FROM routine_entries WHERE date = ?")
+        .bind(date)
+        .execute(&mut *tx)
+        .await?;
+    for entry in entries {
+        sqlx::query::<Sqlite>("INSERT INTO routine_entries(date, item_id, done) VALUES (?, ?, ?)")
+            .bind(date)
+            .bind(&entry.item_id)
+            .bind(entry.done)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+#[doc = r#"List the routine entries recorded for an evening, ordered by item id."#]
+pub async fn list_routine_entries(db: &Db, date: NaiveDate) -> Result<Vec<RoutineEntry>, Error> {
+    Ok(sqlx::query_as::<Sqlite, RoutineEntry>(
+        "SELECT item_id, done FROM routine_entries WHERE date = ? ORDER BY item_id ASC",
+    )
+    .bind(date)
+    .fetch_all(db)
+    .await?)
+}
+
+const ATTACHMENT_COLUMNS: &str = "id, date, filename, content_type, size_bytes, sha256, width, \
+                                  height, duration_ms, has_thumb, created_at";
+
+#[doc = r#"Insert an attachment's metadata and return its id. The files themselves are written
+by the caller (see [`crate::attachments`])."#]
+pub async fn insert_attachment(
+    db: &Db,
+    upload: &AttachmentUpload,
+    content_type: &str,
+    size_bytes: i64,
+    sha256: &str,
+    media: &Media,
+) -> Result<i64, Error> {
+    let res = sqlx::query::<Sqlite>(
+        "INSERT INTO attachments(date, filename, content_type, size_bytes, sha256, width, height, \
+         duration_ms, has_thumb) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(upload.date)
+    .bind(upload.filename.trim())
+    .bind(content_type)
+    .bind(size_bytes)
+    .bind(sha256)
+    .bind(media.width.map(i64::from))
+    .bind(media.height.map(i64::from))
+    .bind(media.duration_ms.map(|ms| ms as i64))
+    .bind(media.thumbnail.is_some())
+    .execute(db)
+    .await?;
+    Ok(res.last_insert_rowid())
+}
+
+#[doc = r#"Fetch an attachment's metadata by id."#]
+pub async fn find_attachment(db: &Db, id: i64) -> Result<Option<Attachment>, Error> {
+    Ok(sqlx::query_as::<Sqlite, Attachment>(&format!(
+        "SELECT {ATTACHMENT_COLUMNS} FROM attachments WHERE id = ?"
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await?)
+}
+
+#[doc = r#"List the attachments of a day, oldest first."#]
+pub async fn list_attachments(db: &Db, date: NaiveDate) -> Result<Vec<Attachment>, Error> {
+    Ok(sqlx::query_as::<Sqlite, Attachment>(&format!(
+        "SELECT {ATTACHMENT_COLUMNS} FROM attachments WHERE date = ? ORDER BY id ASC"
+    ))
+    .bind(date)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"Delete an attachment's metadata. Returns whether it existed."#]
+pub async fn delete_attachment(db: &Db, id: i64) -> Result<bool, Error> {
+    let res = sqlx::query::<Sqlite>("DELETE FROM attachments WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+#[doc = r#"One page of sleep sessions in [from, to] for exports, ordered by (date, id).
+
+Keyset pagination: pass the `(date, id)` of the last row of the previous page as `after`
+(`None` for the first page). An empty page means the range is exhausted.
+"#]
+pub async fn export_sleep_page(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    after: Option<(NaiveDate, i64)>,
+    limit: i64,
+) -> Result<Vec<SleepListItem>, Error> {
+    let (after_date, after_id) = after.unwrap_or((from, i64::MIN));
+    Ok(sqlx::query_as::<Sqlite, SleepListItem>(
+        r#"SELECT s.id,
+                   COALESCE(s.session_date, s.date) AS date,
+                   s.bed_time,
+                   s.wake_time,
+                   m.latency_min,
+                   m.awakenings,
+                   m.quality,
+                   m.duration_min,
+                   m.wake_feeling,
+                   m.sleep_inertia_min,
+                   m.waso_min,
+                   m.efficiency
+          FROM sleep_sessions s
+          JOIN sleep_metrics m ON m.session_id = s.id
+          WHERE COALESCE(s.session_date, s.date) BETWEEN ? AND ?
+            AND (COALESCE(s.session_date, s.date) > ?
+                 OR (COALESCE(s.session_date, s.date) = ? AND s.id > ?))
+          ORDER BY date ASC, s.id ASC
+          LIMIT ?"#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(after_date)
+    .bind(after_date)
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"One page of exercise events in [from, to] for exports, ordered by (date, id).
+
+Paged like [`export_sleep_page`]."#]
+pub async fn export_exercise_page(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    after: Option<(NaiveDate, i64)>,
+    limit: i64,
+) -> Result<Vec<ExerciseEvent>, Error> {
+    let (after_date, after_id) = after.unwrap_or((from, i64::MIN));
+    Ok(sqlx::query_as::<Sqlite, ExerciseEvent>(
+        "SELECT id, date, intensity, start_time, duration_min FROM exercise_events \
+         WHERE date BETWEEN ? AND ? AND (date > ? OR (date = ? AND id > ?)) \
+         ORDER BY date ASC, id ASC LIMIT ?",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(after_date)
+    .bind(after_date)
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await?)
+}
+
+#[doc = r#"One page of notes in [from, to] for exports, ordered by (date, id).
+
+Paged like [`export_sleep_page`]."#]
+pub async fn export_notes_page(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    after: Option<(NaiveDate, i64)>,
+    limit: i64,
+) -> Result<Vec<Note>, Error> {
+    let (after_date, after_id) = after.unwrap_or((from, i64::MIN));
+    let mut notes = sqlx::query_as::<Sqlite, Note>(
+        "SELECT id, date, body, session_id FROM notes \
+         WHERE date BETWEEN ? AND ? AND (date > ? OR (date = ? AND id > ?)) \
+         ORDER BY date ASC, id ASC LIMIT ?",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(after_date)
+    .bind(after_date)
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+    attach_note_tags(db, &mut notes).await?;
+    Ok(notes)
 }
@@ -0,0 +1,180 @@
+#![doc = r#"Downtime-free (expand/contract) schema changes
+
+Moving data to a new column in one migration rewrites every row while holding SQLite's write
+lock, which on an instance with years of data is an outage. Column moves are instead rolled out
+in steps, each of which is safe to deploy on its own:
+
+1. **Expand** — a migration adds the new column (nullable, no data copied) and the change is
+   registered in [`CHANGES`]. Its phase starts at [`SchemaPhase::DualWrite`].
+2. **Dual-write** — repository writes fill both the old and the new column (see
+   [`repository::insert_sleep`] for [`SESSION_DATE`]); reads use [`ColumnMove::read_expr`],
+   which falls back to the old column while rows are still unmigrated.
+3. **Backfill** — [`Job::SchemaBackfill`] copies the remaining rows in small batches
+   (`SCHEMA_BACKFILL_BATCH` rows per statement) so writers are never blocked for long, and
+   marks the change [`SchemaPhase::Backfilled`] once nothing is left.
+4. **Switch** — `POST /api/admin/schema-changes/{name}/switch` flips reads to the new column
+   ([`SchemaPhase::ReadNew`], see [`handlers::switch_schema_change`]). It is refused while
+   rows are still unmigrated.
+5. **Contract** — a later migration drops the old column and the entry leaves [`CHANGES`].
+
+The phase of each change is stored in `app_settings` (key `schema_phase:<name>`).
+
+[`repository::insert_sleep`]: crate::repository::insert_sleep
+[`Job::SchemaBackfill`]: crate::jobs::Job::SchemaBackfill
+[`handlers::switch_schema_change`]: crate::handlers::switch_schema_change
+"#]
+
+use crate::{db::Db, error::Error, repository};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"Rollout phase of a registered [`ColumnMove`]. Parses from its `snake_case` name."#]
+pub enum SchemaPhase {
+    DualWrite,
+    Backfilled,
+    ReadNew,
+}
+
+impl SchemaPhase {
+    #[doc = r#"Return the phase name stored in `app_settings`."#]
+    pub fn name(self) -> &'static str {
+        match self {
+            SchemaPhase::DualWrite => "dual_write",
+            SchemaPhase::Backfilled => "backfilled",
+            SchemaPhase::ReadNew => "read_new",
+        }
+    }
+}
+
+impl FromStr for SchemaPhase {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            SchemaPhase::DualWrite,
+            SchemaPhase::Backfilled,
+            SchemaPhase::ReadNew,
+        ]
+        .into_iter()
+        .find(|p| p.name() == s)
+        .ok_or_else(|| format!("unknown schema phase {s:?}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"A column being replaced by another column of the same table.
+
+`backfill_expr` computes the new value from the current row (usually just the old column).
+
+# Example
+
+```rust
+# use sleep_api::schema_change::{SESSION_DATE, SchemaPhase};
+assert_eq!(
+    SESSION_DATE.read_expr(SchemaPhase::DualWrite, "s"),
+    "COALESCE(s.session_date, s.date)"
+);
+assert_eq!(SESSION_DATE.read_expr(SchemaPhase::ReadNew, "s"), "s.session_date");
+```
+"#]
+pub struct ColumnMove {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub old_column: &'static str,
+    pub new_column: &'static str,
+    pub backfill_expr: &'static str,
+}
+
+/// `sleep_sessions.date` → `sleep_sessions.session_date` (wake-date based sessions).
+pub const SESSION_DATE: ColumnMove = ColumnMove {
+    name: "session_date",
+    table: "sleep_sessions",
+    old_column: "date",
+    new_column: "session_date",
+    backfill_expr: "date",
+};
+
+/// Column moves that have been expanded but not yet contracted.
+pub const CHANGES: &[ColumnMove] = &[SESSION_DATE];
+
+/// Look up a registered change by name.
+pub fn find(name: &str) -> Option<&'static ColumnMove> {
+    CHANGES.iter().find(|c| c.name == name)
+}
+
+impl ColumnMove {
+    #[doc = r#"SQL expression reading the column for a row of `alias` in `phase`."#]
+    #[allow(dead_code)]
+    pub fn read_expr(&self, phase: SchemaPhase, alias: &str) -> String {
+        match phase {
+            SchemaPhase::ReadNew => format!("{alias}.{}", self.new_column),
+            SchemaPhase::DualWrite | SchemaPhase::Backfilled => format!(
+                "COALESCE({alias}.{}, {alias}.{})",
+                self.new_column, self.old_column
+            ),
+        }
+    }
+
+    #[doc = r#"Current phase (defaults to [`SchemaPhase::DualWrite`] right after expansion)."#]
+    pub async fn phase(&self, db: &Db) -> Result<SchemaPhase, Error> {
+        Ok(repository::get_schema_phase(db, self.name)
+            .await?
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(SchemaPhase::DualWrite))
+    }
+
+    #[doc = r#"Number of rows whose new column has not been filled yet."#]
+    pub async fn pending_rows(&self, db: &Db) -> Result<i64, Error> {
+        let sql = format!(
+            "SELECT COUNT(*) FROM {} WHERE {} IS NULL AND {} IS NOT NULL",
+            self.table, self.new_column, self.old_column
+        );
+        Ok(sqlx::query_scalar::<sqlx::Sqlite, i64>(&sql)
+            .fetch_one(db)
+            .await?)
+    }
+
+    #[doc = r#"Fill the new column for up to `batch` unmigrated rows. Returns the rows updated."#]
+    pub async fn backfill_batch(&self, db: &Db, batch: i64) -> Result<u64, Error> {
+        let sql = format!(
+            "UPDATE {table} SET {new} = {expr} WHERE rowid IN (\
+                SELECT rowid FROM {table} WHERE {new} IS NULL AND {old} IS NOT NULL LIMIT ?)",
+            table = self.table,
+            new = self.new_column,
+            old = self.old_column,
+            expr = self.backfill_expr,
+        );
+        Ok(sqlx::query(&sql)
+            .bind(batch)
+            .execute(db)
+            .await?
+            .rows_affected())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[doc = r#"Status of a registered schema change, as listed by `GET /api/admin/schema-changes`."#]
+pub struct SchemaChangeStatus {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub old_column: &'static str,
+    pub new_column: &'static str,
+    pub phase: SchemaPhase,
+    pub pending_rows: i64,
+}
+
+impl SchemaChangeStatus {
+    /// Current status of `change`.
+    pub async fn load(db: &Db, change: &ColumnMove) -> Result<Self, Error> {
+        Ok(SchemaChangeStatus {
+            name: change.name,
+            table: change.table,
+            old_column: change.old_column,
+            new_column: change.new_column,
+            phase: change.phase(db).await?,
+            pending_rows: change.pending_rows(db).await?,
+        })
+    }
+}
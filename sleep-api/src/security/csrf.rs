@@ -4,9 +4,12 @@ Implements double-submit cookie protection for mutating requests:
 
 - Cookie `__Host-csrf` (Secure, SameSite=Lax, Path=/, not HttpOnly), value: URL-safe base64 token
 - Header `X-CSRF-Token` must match the cookie value (header is percent-decoded before comparison)
-- For mutating requests (POST, PUT, DELETE), [`CsrfGuard`] enforces:
+- For mutating requests (POST, PUT, PATCH, DELETE), [`CsrfGuard`] enforces:
   - Same-site heuristic using `Sec-Fetch-Site` if present (`same-origin` or `same-site`)
   - Exact match of header token to cookie value (after percent-decoding)
+- Requests carrying `Authorization: Bearer` (API tokens) are exempt: browsers never attach
+  that header on their own, and such requests are authenticated by the token alone, never
+  by the session cookie (see [`crate::middleware::auth_layer`])
 
 # Example
 
@@ -64,13 +67,22 @@ pub fn issue_csrf_cookie() -> Cookie<'static> {
         .build()
 }
 
+#[derive(Debug, Clone, Copy)]
+#[doc = r#"Request extension that switches [`CsrfGuard`] off.
+
+Inserted by [`MiddlewareConfig::csrf`](crate::middleware::stack::MiddlewareConfig::csrf)
+`(false)` for embedders that protect mutating requests themselves. Without such protection,
+session cookies can be used by cross-site requests.
+"#]
+pub struct CsrfDisabled;
+
 /// Guard extractor that enforces double-submit CSRF for mutating methods (POST/PUT/DELETE).
 /// - Requires a cookie "__Host-csrf"
 /// - Requires header "X-CSRF-Token" matching the cookie value
 /// - If "Sec-Fetch-Site" header is present, it must be "same-origin" or "same-site"
 #[doc = r#"Extractor that enforces double-submit CSRF for mutating methods (POST/PUT/DELETE).
 
-Enforcement:
+Enforcement (skipped for `Authorization: Bearer` requests and when [`CsrfDisabled`] is set):
 - If `Sec-Fetch-Site` header is present, it must be `same-origin` or `same-site`
 - Reads `__Host-csrf` cookie and compares it to `X-CSRF-Token` header (header is percent-decoded before comparison)
 - On failure, returns `403` with JSON payload: `{"error":"forbidden","detail":"csrf: ..."}`
@@ -89,8 +101,14 @@ where
     ) -> Result<Self, Self::Rejection> {
         // Only enforce on mutating methods
         let method = parts.method.clone();
-        let is_mutating = matches!(method, Method::POST | Method::PUT | Method::DELETE);
-        if !is_mutating {
+        let is_mutating = matches!(
+            method,
+            Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+        );
+        if !is_mutating
+            || crate::security::token::bearer_token(&parts.headers).is_some()
+            || parts.extensions.get::<CsrfDisabled>().is_some()
+        {
             return Ok(Self);
         }
 
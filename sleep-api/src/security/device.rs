@@ -0,0 +1,200 @@
+#![doc = r#"Login device fingerprints
+
+Each successful login records the device it came from (see `GET /api/account/devices`) so a
+login from an unseen device can be reported through the notification channels.
+
+A device is identified by the SHA-256 of its `User-Agent` and network prefix; neither raw value
+is stored:
+
+- The network prefix is the client address truncated to `/24` (IPv4) or `/48` (IPv6), so a
+  phone hopping between addresses of the same network stays one device.
+- The client address is the TCP peer, or the first `X-Forwarded-For` entry when
+  `TRUST_PROXY_HEADERS` is enabled (only behind a proxy that overwrites the header).
+
+Alongside the hash a coarse label such as `"Firefox on Linux"` is kept so devices can be told
+apart in the list.
+
+# Example
+
+```rust
+use sleep_api::security::device::DeviceFingerprint;
+
+let ua = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+let home = DeviceFingerprint::new(ua, Some("203.0.113.7".parse().unwrap()));
+let same_net = DeviceFingerprint::new(ua, Some("203.0.113.99".parse().unwrap()));
+assert_eq!(home, same_net);
+assert_eq!(home.label, "Firefox on Linux");
+assert_ne!(home, DeviceFingerprint::new(ua, Some("198.51.100.7".parse().unwrap())));
+```
+"#]
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{Extensions, HeaderMap, header, request::Parts};
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+/// Longest `User-Agent` prefix considered (longer headers are truncated before hashing).
+const MAX_USER_AGENT_LEN: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[doc = r#"Hashed identity and display label of the requesting device.
+
+Usable as an extractor; it never rejects (missing headers hash as empty values).
+"#]
+pub struct DeviceFingerprint {
+    /// Lowercase hex SHA-256 of the user agent and network prefix.
+    pub hash: String,
+    /// Coarse browser and OS, e.g. `"Chrome on Android"`.
+    pub label: String,
+}
+
+impl DeviceFingerprint {
+    #[doc = r#"Fingerprint a device from its `User-Agent` header and client address."#]
+    pub fn new(user_agent: &str, ip: Option<IpAddr>) -> Self {
+        let user_agent = truncate(user_agent.trim(), MAX_USER_AGENT_LEN);
+        let network = ip.map(network_prefix).unwrap_or_default();
+        let digest = Sha256::digest(format!("{user_agent}\n{network}").as_bytes());
+        DeviceFingerprint {
+            hash: hex::encode(digest),
+            label: describe_user_agent(user_agent),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for DeviceFingerprint {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let ip = client_ip(&parts.headers, &parts.extensions);
+        Ok(DeviceFingerprint::new(user_agent, ip))
+    }
+}
+
+#[doc = r#"Client address of a request: the first `X-Forwarded-For` entry when
+`TRUST_PROXY_HEADERS` is enabled, otherwise the TCP peer (`None` when the router is served
+without connect info, e.g. in tests)."#]
+pub fn client_ip(headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+    let forwarded = crate::config::trust_proxy_headers()
+        .then(|| {
+            headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .and_then(|v| v.trim().parse::<IpAddr>().ok())
+        })
+        .flatten();
+    forwarded.or_else(|| {
+        extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Extractor for [`client_ip`]; never rejects."#]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp(client_ip(&parts.headers, &parts.extensions)))
+    }
+}
+
+/// The address truncated to its `/24` (IPv4) or `/48` (IPv6) network.
+fn network_prefix(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{a}.{b}.{c}.0/24")
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => network_prefix(IpAddr::V4(v4)),
+            None => {
+                let s = v6.segments();
+                format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+            }
+        },
+    }
+}
+
+fn truncate(s: &str, max: usize) -> &str {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
+/// Coarse "browser on OS" description; only well-known tokens are recognized.
+fn describe_user_agent(ua: &str) -> String {
+    // Order matters: Edge and Chrome also claim Safari, Chrome on iOS claims Safari.
+    let browser = [
+        ("Edg/", "Edge"),
+        ("Firefox/", "Firefox"),
+        ("CriOS/", "Chrome"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ]
+    .into_iter()
+    .find(|(token, _)| ua.contains(token))
+    .map_or("Unknown browser", |(_, name)| name);
+    let os = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .into_iter()
+    .find(|(token, _)| ua.contains(token))
+    .map(|(_, name)| name);
+    match os {
+        Some(os) => format!("{browser} on {os}"),
+        None => browser.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_common_user_agents() {
+        let cases = [
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+                "Safari on iOS",
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0",
+                "Edge on Windows",
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+                "Chrome on Android",
+            ),
+            ("curl/8.5.0", "curl"),
+            ("", "Unknown browser"),
+        ];
+        for (ua, label) in cases {
+            assert_eq!(describe_user_agent(ua), label, "{ua}");
+        }
+    }
+
+    #[test]
+    fn network_prefix_groups_nearby_addresses() {
+        let p = |s: &str| network_prefix(s.parse().unwrap());
+        assert_eq!(p("192.0.2.10"), "192.0.2.0/24");
+        assert_eq!(p("::ffff:192.0.2.10"), "192.0.2.0/24");
+        assert_eq!(p("2001:db8:abcd:12::1"), "2001:db8:abcd::/48");
+    }
+}
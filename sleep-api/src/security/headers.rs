@@ -4,31 +4,100 @@ Adds common security headers to all responses:
 - `X-Content-Type-Options: nosniff`
 - `X-Frame-Options: DENY`
 - `Referrer-Policy: strict-origin-when-cross-origin`
-- Content Security Policy (baseline): `default-src 'self'; script-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; connect-src 'self'`
+- Content Security Policy (baseline, [`BASELINE_CSP`]): `default-src 'self'; script-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; connect-src 'self'`
 - Strict-Transport-Security when `ENABLE_HSTS=1/true`
 
+With a report URI configured ([`CspReporting`], see [`config::csp_reporting`]) the policy gains
+`report-uri` and `report-to` directives plus a matching `Reporting-Endpoints` header, and
+[`STRICT_CSP`] (no `'unsafe-inline'`) can be sent as `Content-Security-Policy-Report-Only` to
+find what would break before switching to it.
+
 # Example
 
 ```rust,no_run
 # let router: axum::Router<()> = axum::Router::new();
-let router = sleep_api::security::headers::apply(router, sleep_api::config::hsts_enabled());
+let router = sleep_api::security::headers::apply(
+    router,
+    sleep_api::config::hsts_enabled(),
+    &sleep_api::config::csp_reporting(),
+);
 ```
+
+[`config::csp_reporting`]: crate::config::csp_reporting
 "#]
 
 use axum::Router;
 use axum::http::{HeaderName, HeaderValue};
 use tower_http::set_header::SetResponseHeaderLayer;
 
+/// Policy enforced on every response.
+pub const BASELINE_CSP: &str = "default-src 'self'; script-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; connect-src 'self'";
+
+/// Target policy without inline scripts; only ever sent in report-only mode.
+pub const STRICT_CSP: &str =
+    "default-src 'self'; script-src 'self' https://cdn.jsdelivr.net; connect-src 'self'";
+
+/// Name of the `Reporting-Endpoints` entry the `report-to` directive refers to.
+const REPORT_GROUP: &str = "csp-endpoint";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[doc = r#"Where CSP violations are reported. [`Default`] reports nowhere.
+
+# Example
+
+```rust
+# use sleep_api::security::headers::{CspReporting, STRICT_CSP};
+let csp = CspReporting { report_uri: Some("/api/csp-report".into()), report_only_strict: true };
+assert_eq!(
+    csp.policy(STRICT_CSP),
+    format!("{STRICT_CSP}; report-uri /api/csp-report; report-to csp-endpoint")
+);
+```
+"#]
+pub struct CspReporting {
+    pub report_uri: Option<String>,
+    /// Also send [`STRICT_CSP`] as `Content-Security-Policy-Report-Only`.
+    pub report_only_strict: bool,
+}
+
+impl CspReporting {
+    /// `base` with the reporting directives appended when a report URI is set.
+    pub fn policy(&self, base: &str) -> String {
+        match &self.report_uri {
+            Some(uri) => format!("{base}; report-uri {uri}; report-to {REPORT_GROUP}"),
+            None => base.to_string(),
+        }
+    }
+}
+
 /// Apply common security headers to all responses.
 /// - X-Content-Type-Options: nosniff
 /// - X-Frame-Options: DENY
 /// - Referrer-Policy: strict-origin-when-cross-origin
-/// - Content-Security-Policy: default-src 'self'; script-src 'self' 'unsafe-inline'
+/// - Content-Security-Policy: [`BASELINE_CSP`], with reporting directives per `csp`
+/// - Content-Security-Policy-Report-Only: [`STRICT_CSP`] (when `csp.report_only_strict`)
+/// - Reporting-Endpoints (when `csp.report_uri` is set)
 /// - Strict-Transport-Security (optional when enable_hsts=true)
-pub fn apply<S>(mut router: Router<S>, enable_hsts: bool) -> Router<S>
+pub fn apply<S>(mut router: Router<S>, enable_hsts: bool, csp: &CspReporting) -> Router<S>
 where
     S: Clone + Send + Sync + 'static,
 {
+    // A report URI that cannot be sent in a header disables reporting rather than the CSP.
+    let csp = match csp.report_uri.as_deref().map(HeaderValue::from_str) {
+        Some(Err(_)) => {
+            tracing::warn!("CSP_REPORT_URI is not a valid header value; CSP reporting disabled");
+            CspReporting {
+                report_uri: None,
+                ..csp.clone()
+            }
+        }
+        _ => csp.clone(),
+    };
+    let header = |policy: &str| {
+        HeaderValue::from_str(&csp.policy(policy))
+            .unwrap_or_else(|_| HeaderValue::from_static(BASELINE_CSP))
+    };
+
     router = router
         .layer(SetResponseHeaderLayer::if_not_present(
             HeaderName::from_static("x-content-type-options"),
@@ -44,8 +113,24 @@ where
         ))
         .layer(SetResponseHeaderLayer::if_not_present(
             HeaderName::from_static("content-security-policy"),
-            HeaderValue::from_static("default-src 'self'; script-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; connect-src 'self'"),
+            header(BASELINE_CSP),
+        ));
+
+    if csp.report_only_strict {
+        router = router.layer(SetResponseHeaderLayer::if_not_present(
+            HeaderName::from_static("content-security-policy-report-only"),
+            header(STRICT_CSP),
+        ));
+    }
+
+    if let Some(uri) = &csp.report_uri
+        && let Ok(value) = HeaderValue::from_str(&format!("{REPORT_GROUP}=\"{uri}\""))
+    {
+        router = router.layer(SetResponseHeaderLayer::if_not_present(
+            HeaderName::from_static("reporting-endpoints"),
+            value,
         ));
+    }
 
     if enable_hsts {
         router = router.layer(SetResponseHeaderLayer::if_not_present(
@@ -0,0 +1,244 @@
+#![doc = r#"Login lockout with exponential backoff
+
+Failed logins are counted per account email and per client address in the `login_failures`
+table. After [`LockoutPolicy::threshold`] failures for a key, that key is locked: logins for
+the account (or from the address) are refused without checking the password until the lock
+ends. Each further lock of the same key lasts twice as long as the previous one, from
+[`LockoutPolicy::base_secs`] up to [`LockoutPolicy::max_secs`]. A successful login clears the
+account's and the address's history; a key without failures for [`RESET_AFTER_HOURS`] starts
+over.
+
+Locked attempts get `429 {"error":"locked","retry_after_s":N}` with a `Retry-After` header
+([`locked_response`]). Unlike the per-minute limits of
+[`middleware::rate_limit`](crate::middleware::rate_limit), locks are stored in the database,
+so they survive restarts and grow with repeated attacks.
+
+Configured by [`config::login_lockout`](crate::config::login_lockout).
+
+# Example
+
+```rust
+# use sleep_api::security::lockout::LockoutPolicy;
+let policy = LockoutPolicy { threshold: 5, base_secs: 60, max_secs: 3600 };
+assert_eq!(policy.lock_secs(1), 60);
+assert_eq!(policy.lock_secs(3), 240);
+assert_eq!(policy.lock_secs(10), 3600);
+```
+"#]
+
+use crate::{db::Db, error::Error};
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration as ChronoDuration, NaiveDateTime};
+use serde_json::json;
+use sqlx::Sqlite;
+use std::net::IpAddr;
+
+/// Hours without a failure after which a key's failures and lock count are forgotten.
+pub const RESET_AFTER_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"When to lock and for how long.
+
+[`Default`] locks after 5 failures, for 1 minute, doubling up to 1 hour."#]
+pub struct LockoutPolicy {
+    /// Failures of one key that trigger a lock.
+    pub threshold: u32,
+    /// Duration of the first lock, in seconds.
+    pub base_secs: i64,
+    /// Longest lock, in seconds.
+    pub max_secs: i64,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        LockoutPolicy {
+            threshold: 5,
+            base_secs: 60,
+            max_secs: 3600,
+        }
+    }
+}
+
+impl LockoutPolicy {
+    /// Duration of the `lockouts`-th lock of a key (1-based), in seconds.
+    pub fn lock_secs(&self, lockouts: u32) -> i64 {
+        let doublings = lockouts.saturating_sub(1).min(32);
+        self.base_secs
+            .saturating_mul(1_i64 << doublings)
+            .min(self.max_secs)
+    }
+}
+
+#[doc = r#"Outcome of [`attempt`]."#]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginAttempt {
+    /// The credentials were accepted.
+    Accepted,
+    /// The credentials were rejected and no lock applies yet.
+    Rejected,
+    /// The account or address is locked for this many more seconds.
+    Locked(i64),
+}
+
+#[doc = r#"Lockout keys of a login for `email` from `ip`.
+
+# Example
+
+```rust
+# use sleep_api::security::lockout::keys;
+let ip = "203.0.113.7".parse().ok();
+assert_eq!(keys(" Admin@Example.com ", ip), ["account:admin@example.com", "ip:203.0.113.7"]);
+assert_eq!(keys("admin@example.com", None), ["account:admin@example.com"]);
+```
+"#]
+pub fn keys(email: &str, ip: Option<IpAddr>) -> Vec<String> {
+    let mut keys = vec![format!("account:{}", email.trim().to_lowercase())];
+    if let Some(ip) = ip {
+        keys.push(format!("ip:{ip}"));
+    }
+    keys
+}
+
+#[doc = r#"Seconds until the latest lock of any of `keys` ends at `now`, if one is active."#]
+pub async fn locked_for(
+    db: &Db,
+    keys: &[String],
+    now: NaiveDateTime,
+) -> Result<Option<i64>, Error> {
+    let mut until: Option<NaiveDateTime> = None;
+    for key in keys {
+        let locked = sqlx::query_scalar::<Sqlite, NaiveDateTime>(
+            "SELECT locked_until FROM login_failures WHERE key = ? AND locked_until > ?",
+        )
+        .bind(key)
+        .bind(now)
+        .fetch_optional(db)
+        .await?;
+        until = until.max(locked);
+    }
+    Ok(until.map(|t| (t - now).num_seconds().max(1)))
+}
+
+#[doc = r#"Count a failed login for each of `keys` at `now`.
+
+Returns the lock duration in seconds when this failure locked a key."#]
+pub async fn record_failure(
+    db: &Db,
+    policy: &LockoutPolicy,
+    keys: &[String],
+    now: NaiveDateTime,
+) -> Result<Option<i64>, Error> {
+    let stale = now - ChronoDuration::hours(RESET_AFTER_HOURS);
+    let mut locked: Option<i64> = None;
+    let mut tx = db.begin().await?;
+    for key in keys {
+        let row = sqlx::query_as::<Sqlite, (u32, u32, NaiveDateTime)>(
+            "SELECT failures, lockouts, last_failure_at FROM login_failures WHERE key = ?",
+        )
+        .bind(key)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let (mut failures, mut lockouts) = match row {
+            Some((failures, lockouts, last)) if last > stale => (failures, lockouts),
+            _ => (0, 0),
+        };
+        failures += 1;
+        let mut locked_until = None;
+        if failures >= policy.threshold {
+            lockouts += 1;
+            failures = 0;
+            let secs = policy.lock_secs(lockouts);
+            locked_until = Some(now + ChronoDuration::seconds(secs));
+            locked = locked.max(Some(secs));
+        }
+        sqlx::query::<Sqlite>(
+            "INSERT INTO login_failures (key, failures, lockouts, locked_until, last_failure_at) \
+             VALUES (?, ?, ?, ?, ?) \
+             ON CONFLICT(key) DO UPDATE SET failures = excluded.failures, \
+             lockouts = excluded.lockouts, \
+             locked_until = COALESCE(excluded.locked_until, login_failures.locked_until), \
+             last_failure_at = excluded.last_failure_at",
+        )
+        .bind(key)
+        .bind(failures)
+        .bind(lockouts)
+        .bind(locked_until)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(locked)
+}
+
+#[doc = r#"Forget the failures and locks of `keys` after a successful login."#]
+pub async fn record_success(db: &Db, keys: &[String]) -> Result<(), Error> {
+    for key in keys {
+        sqlx::query::<Sqlite>("DELETE FROM login_failures WHERE key = ?")
+            .bind(key)
+            .execute(db)
+            .await?;
+    }
+    Ok(())
+}
+
+#[doc = r#"Check a login for `email` from `ip` at `now` against the lockout, verifying the
+password with `verify` only when no lock applies.
+
+Without a policy (lockout disabled) only `verify` runs. Database errors are logged and the
+attempt proceeds as if no failures were stored, so a broken table cannot lock the owner out.
+"#]
+pub async fn attempt(
+    db: &Db,
+    policy: Option<&LockoutPolicy>,
+    email: &str,
+    ip: Option<IpAddr>,
+    now: NaiveDateTime,
+    verify: impl FnOnce() -> bool,
+) -> LoginAttempt {
+    let Some(policy) = policy else {
+        return if verify() {
+            LoginAttempt::Accepted
+        } else {
+            LoginAttempt::Rejected
+        };
+    };
+    let keys = keys(email, ip);
+    match locked_for(db, &keys, now).await {
+        Ok(Some(secs)) => return LoginAttempt::Locked(secs),
+        Ok(None) => {}
+        Err(e) => tracing::warn!(error = ?e, "failed to read login lockout"),
+    }
+    if verify() {
+        if let Err(e) = record_success(db, &keys).await {
+            tracing::warn!(error = ?e, "failed to clear login failures");
+        }
+        return LoginAttempt::Accepted;
+    }
+    match record_failure(db, policy, &keys, now).await {
+        Ok(Some(secs)) => {
+            tracing::warn!(lock_secs = secs, "login locked after repeated failures");
+            LoginAttempt::Locked(secs)
+        }
+        Ok(None) => LoginAttempt::Rejected,
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to record login failure");
+            LoginAttempt::Rejected
+        }
+    }
+}
+
+#[doc = r#"`429 {"error":"locked","retry_after_s":N}` with `Retry-After: N`."#]
+pub fn locked_response(retry_after_s: i64) -> Response {
+    let secs = retry_after_s.max(1);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, HeaderValue::from(secs))],
+        Json(json!({"error": "locked", "retry_after_s": secs})),
+    )
+        .into_response()
+}
@@ -4,11 +4,21 @@ Provides CSRF protection (double-submit cookie) and common HTTP security headers
 
 Modules:
 - [`csrf`] — double-submit cookie issuance and request guard
+- [`device`] — hashed login device fingerprints
 - [`headers`] — response header layer (HSTS, CSP, X-Frame-Options, Referrer-Policy, etc.)
+- [`lockout`] — login lockout with exponential backoff after repeated failures
+- [`password`] — Argon2 hashing policy and rehash on login
+- [`signature`] — HMAC-SHA256 verification for signed webhook pushes
+- [`token`] — API token secrets and bearer header parsing
 
 See also:
 - [`crate::middleware::auth_layer`] for session-based access control
 "#]
 
 pub mod csrf;
+pub mod device;
 pub mod headers;
+pub mod lockout;
+pub mod password;
+pub mod signature;
+pub mod token;
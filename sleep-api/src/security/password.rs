@@ -0,0 +1,167 @@
+#![doc = r#"Password hashing policy and rehash on login
+
+New password hashes use Argon2id with the parameters of [`HashPolicy`], configured by
+[`config::password_hash_policy`](crate::config::password_hash_policy) (`ARGON2_MEMORY_KIB`,
+`ARGON2_ITERATIONS`, `ARGON2_PARALLELISM`). Verification reads the parameters from the stored
+hash, so raising the policy never locks anyone out.
+
+After a successful login whose hash is weaker than the policy (lower memory, iterations or
+parallelism, or not Argon2id), the password is hashed again under the policy and stored in the
+`password_hashes` table ([`rehash_if_weaker`]). Later logins check that stored hash instead of
+the configured `ADMIN_PASSWORD_HASH` ([`effective_hash`]). Each row remembers the configured
+hash it replaced, so changing `ADMIN_PASSWORD_HASH` (e.g. to rotate the password) takes effect
+immediately and the old row is ignored.
+
+# Example
+
+```rust
+# use argon2::password_hash::PasswordHash;
+# use sleep_api::security::password::HashPolicy;
+let weak = HashPolicy { memory_kib: 1024, iterations: 1, parallelism: 1 };
+let hash = weak.hash(b"secret").unwrap();
+let parsed = PasswordHash::new(&hash).unwrap();
+assert!(weak.is_met_by(&parsed));
+assert!(!HashPolicy::default().is_met_by(&parsed));
+```
+"#]
+
+use crate::{db::Db, error::Error};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+};
+use sqlx::Sqlite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Argon2id cost parameters for new password hashes.
+
+[`Default`] matches the `argon2` crate defaults (19 MiB, 2 iterations, 1 lane)."#]
+pub struct HashPolicy {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of passes.
+    pub iterations: u32,
+    /// Degree of parallelism (lanes).
+    pub parallelism: u32,
+}
+
+impl Default for HashPolicy {
+    fn default() -> Self {
+        HashPolicy {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl HashPolicy {
+    #[doc = r#"Argon2 parameters of the policy.
+
+# Errors
+
+Returns an error when the combination is rejected by Argon2 (e.g. memory below 8 KiB per
+lane)."#]
+    pub fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+    }
+
+    #[doc = r#"Hash `password` with a fresh salt into a PHC string (`$argon2id$...`).
+
+# Errors
+
+Returns an error for invalid parameters or when hashing fails."#]
+    pub fn hash(&self, password: &[u8]) -> Result<String, argon2::password_hash::Error> {
+        let params = self.params()?;
+        let salt = SaltString::generate(OsRng);
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(password, &salt)?
+            .to_string())
+    }
+
+    #[doc = r#"Whether `hash` is an Argon2id hash at least as strong as this policy in every
+parameter."#]
+    pub fn is_met_by(&self, hash: &PasswordHash<'_>) -> bool {
+        if hash.algorithm != argon2::ARGON2ID_IDENT || hash.version != Some(Version::V0x13.into()) {
+            return false;
+        }
+        match Params::try_from(hash) {
+            Ok(p) => {
+                p.m_cost() >= self.memory_kib
+                    && p.t_cost() >= self.iterations
+                    && p.p_cost() >= self.parallelism
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[doc = r#"Whether `password` matches the PHC string `hash`; `false` when the hash is malformed."#]
+pub fn verify(password: &str, hash: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "invalid password hash");
+            false
+        }
+    }
+}
+
+#[doc = r#"The hash to verify `account`'s password against: the rehashed one stored for the
+current `configured` hash, or `configured` itself."#]
+pub async fn effective_hash(db: &Db, account: &str, configured: &str) -> String {
+    let stored = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT hash FROM password_hashes WHERE account = ? AND source_hash = ?",
+    )
+    .bind(account)
+    .bind(configured)
+    .fetch_optional(db)
+    .await;
+    match stored {
+        Ok(Some(hash)) => hash,
+        Ok(None) => configured.to_string(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "failed to read stored password hash");
+            configured.to_string()
+        }
+    }
+}
+
+#[doc = r#"After a successful login with `password`, store a policy-strength hash for `account`
+when `current` (the hash it was verified against) is weaker than `policy`.
+
+Returns whether a new hash was stored.
+
+# Errors
+
+Returns an error when hashing or the database write fails; the login itself is unaffected."#]
+pub async fn rehash_if_weaker(
+    db: &Db,
+    policy: &HashPolicy,
+    account: &str,
+    password: &str,
+    current: &str,
+    configured: &str,
+) -> Result<bool, Error> {
+    if PasswordHash::new(current).is_ok_and(|h| policy.is_met_by(&h)) {
+        return Ok(false);
+    }
+    let hash = policy
+        .hash(password.as_bytes())
+        .map_err(|e| Error::invalid(format!("password hashing failed: {e}")))?;
+    sqlx::query::<Sqlite>(
+        "INSERT INTO password_hashes(account, hash, source_hash) VALUES (?, ?, ?) \
+         ON CONFLICT(account) DO UPDATE SET hash = excluded.hash, \
+         source_hash = excluded.source_hash, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(account)
+    .bind(hash)
+    .bind(configured)
+    .execute(db)
+    .await?;
+    tracing::info!("password rehashed under the current hashing policy");
+    Ok(true)
+}
@@ -0,0 +1,55 @@
+#![doc = r#"Webhook signatures (HMAC-SHA256)
+
+Pushed payloads (`POST /api/ingest/{source}`) are authenticated with a per-source shared
+secret instead of a session:
+
+- Header `X-Signature-256: sha256=<hex>` where `<hex>` is the lowercase hex HMAC-SHA256 of the
+  raw request body keyed with the source's secret
+- Comparison is constant-time
+
+# Example
+
+```rust
+use sleep_api::security::signature::{sign, verify};
+
+let header = sign(b"secret", b"{}");
+assert!(header.starts_with("sha256="));
+assert!(verify(b"secret", b"{}", &header));
+assert!(!verify(b"other", b"{}", &header));
+```
+"#]
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Request header carrying the body signature.
+pub const SIGNATURE_HEADER: &str = "x-signature-256";
+
+const PREFIX: &str = "sha256=";
+
+fn mac(secret: &[u8], body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac
+}
+
+#[doc = r#"Return the `sha256=<hex>` header value for `body` (what a sender computes).
+
+Also used to sign outgoing notification webhooks (see [`crate::notify`])."#]
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    format!(
+        "{PREFIX}{}",
+        hex::encode(mac(secret, body).finalize().into_bytes())
+    )
+}
+
+#[doc = r#"Check a `sha256=<hex>` header value against `body`; false for malformed values."#]
+pub fn verify(secret: &[u8], body: &[u8], header: &str) -> bool {
+    let Some(hex_sig) = header.trim().strip_prefix(PREFIX) else {
+        return false;
+    };
+    match hex::decode(hex_sig) {
+        Ok(sig) => mac(secret, body).verify_slice(&sig).is_ok(),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,77 @@
+#![doc = r#"API token secrets
+
+Bearer tokens (`Authorization: Bearer <secret>`) let scripts and widgets call the API without
+a browser session; see [`ApiScope`] for what each token may do.
+
+- A secret is `slt_` followed by 64 hex characters (32 random bytes), so leaked tokens are easy
+  to grep for.
+- Only the SHA-256 of the secret is stored. Secrets carry 256 bits of entropy, so a fast hash
+  is sufficient and lookups stay a single indexed query.
+
+# Example
+
+```rust
+use sleep_api::security::token::{generate_secret, hash_secret};
+
+let secret = generate_secret();
+assert!(secret.starts_with("slt_"));
+assert_eq!(hash_secret(&secret).len(), 64);
+assert_ne!(generate_secret(), secret);
+```
+
+[`ApiScope`]: crate::models::ApiScope
+"#]
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::http::{HeaderMap, header};
+use sha2::{Digest, Sha256};
+
+/// Prefix of every token secret.
+pub const SECRET_PREFIX: &str = "slt_";
+
+/// Prefix of every share link secret.
+pub const SHARE_PREFIX: &str = "sls_";
+
+/// Prefix of every email change confirmation secret.
+pub const EMAIL_CHANGE_PREFIX: &str = "sle_";
+
+#[doc = r#"Generate a new random token secret."#]
+pub fn generate_secret() -> String {
+    random_secret(SECRET_PREFIX)
+}
+
+#[doc = r#"Generate a new random share link secret (`sls_` and 64 hex characters)."#]
+pub fn generate_share_secret() -> String {
+    random_secret(SHARE_PREFIX)
+}
+
+#[doc = r#"Generate a new random email change confirmation secret (`sle_` and 64 hex characters)."#]
+pub fn generate_email_change_secret() -> String {
+    random_secret(EMAIL_CHANGE_PREFIX)
+}
+
+fn random_secret(prefix: &str) -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{prefix}{}", hex::encode(bytes))
+}
+
+#[doc = r#"Lowercase hex SHA-256 of a secret, as stored in `api_tokens.token_hash`,
+`share_links.token_hash` and `email_changes.token_hash`."#]
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+#[doc = r#"The bearer secret of the `Authorization` header, if the request carries one.
+
+Any `Bearer` header counts, even an empty or malformed one, so such requests are
+authenticated by token only and never fall back to the session cookie.
+"#]
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .unwrap_or_default();
+    let (scheme, secret) = value.split_once(' ').unwrap_or((value, ""));
+    scheme.eq_ignore_ascii_case("bearer").then(|| secret.trim())
+}
@@ -0,0 +1,312 @@
+#![doc = r#"Programmatic server construction
+
+[`Server::builder`] runs the bootstrap the `sleep-api` binary performs in single-database mode
+and returns a [`ServerHandle`] with the bound address and shutdown control:
+
+1. connect ([`db::connect`]) unless a pool is supplied with [`ServerBuilder::db`]
+2. quick integrity check ([`integrity::startup_check`]); a corrupt database is served
+   read-only, without migrations or jobs
+3. apply the embedded migrations and start the maintenance jobs ([`jobs::spawn_scheduler`]),
+   as selected by [`ServerConfig`]
+4. build the router ([`app::router_with_middleware`]) with the configured
+   [`MiddlewareConfig`]
+5. bind and serve, with `ConnectInfo<SocketAddr>` for per-address limits
+
+Multi-tenant mode ([`crate::tenant`]) is not covered; the binary still wires it by hand.
+
+# Example
+
+```rust,no_run
+# async fn demo() -> Result<(), sleep_api::Error> {
+use sleep_api::server::{Server, ServerConfig};
+
+let pool = sleep_api::db::connect().await?;
+let server = Server::builder()
+    .config(ServerConfig::default())
+    .db(pool)
+    .disable_auth_for_tests()
+    .build()
+    .await?;
+let base = format!("http://{}", server.local_addr());
+// ... drive the API at `base` ...
+server.shutdown().await?;
+# let _ = base;
+# Ok(())
+# }
+```
+
+[`db::connect`]: crate::db::connect
+[`integrity::startup_check`]: crate::integrity::startup_check
+[`jobs::spawn_scheduler`]: crate::jobs::spawn_scheduler
+[`app::router_with_middleware`]: crate::app::router_with_middleware
+"#]
+
+use crate::app::{self, AppState};
+use crate::db::Db;
+use crate::error::Error;
+use crate::middleware::auth_layer::AuthDisabled;
+use crate::middleware::stack::MiddlewareConfig;
+use crate::reload::SessionKey;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[doc = r#"Where and how [`ServerBuilder::build`] runs the server.
+
+[`from_config`](Self::from_config) is the binary's setup: `API_BIND_ADDR`, the server's
+middleware stack, migrations and maintenance jobs. [`Default`] suits tests and embedders: an
+ephemeral loopback port, [`MiddlewareConfig::default`], migrations on, jobs off.
+"#]
+pub struct ServerConfig {
+    bind_addr: String,
+    middleware: MiddlewareConfig,
+    migrate: bool,
+    jobs: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_addr: "127.0.0.1:0".into(),
+            middleware: MiddlewareConfig::default(),
+            migrate: true,
+            jobs: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    #[doc = r#"The binary's setup: bind to [`config::api_bind_addr`], the
+[`MiddlewareConfig::from_config`] stack, migrations and maintenance jobs on.
+
+[`config::api_bind_addr`]: crate::config::api_bind_addr"#]
+    pub fn from_config() -> Self {
+        ServerConfig {
+            bind_addr: crate::config::api_bind_addr(),
+            middleware: MiddlewareConfig::from_config(),
+            migrate: true,
+            jobs: true,
+        }
+    }
+}
+
+// Builder for library users; the binary runs `from_config` unchanged.
+#[allow(dead_code)]
+impl ServerConfig {
+    /// Address to listen on, e.g. `0.0.0.0:8080`; port `0` picks a free one.
+    pub fn bind_addr(mut self, addr: impl Into<String>) -> Self {
+        self.bind_addr = addr.into();
+        self
+    }
+
+    /// Layers wrapping the router (see [`crate::middleware::stack`]).
+    pub fn middleware(mut self, middleware: MiddlewareConfig) -> Self {
+        self.middleware = middleware;
+        self
+    }
+
+    /// Apply the embedded migrations before serving.
+    pub fn migrate(mut self, on: bool) -> Self {
+        self.migrate = on;
+        self
+    }
+
+    /// Start the background maintenance jobs.
+    pub fn jobs(mut self, on: bool) -> Self {
+        self.jobs = on;
+        self
+    }
+}
+
+#[doc = r#"Entry point for building a server; see the [module docs](self)."#]
+pub struct Server;
+
+impl Server {
+    /// A builder with no settings chosen yet.
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::default()
+    }
+}
+
+#[derive(Default)]
+#[doc = r#"Collects the settings of a [`Server`]; finish with [`build`](Self::build).
+
+Anything not set falls back to what the binary uses: [`ServerConfig::from_config`], a pool
+from [`db::connect`], and the session key from `SESSION_SECRET`.
+
+[`db::connect`]: crate::db::connect
+"#]
+pub struct ServerBuilder {
+    config: Option<ServerConfig>,
+    db: Option<Db>,
+    key: Option<SessionKey>,
+    auth_disabled: bool,
+}
+
+// Builder for library users; the binary only calls `build`.
+#[allow(dead_code)]
+impl ServerBuilder {
+    /// Use `config` instead of [`ServerConfig::from_config`].
+    pub fn config(mut self, config: ServerConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Serve `db` instead of connecting to `DATABASE_URL`.
+    pub fn db(mut self, db: Db) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Sign session cookies with `key` instead of the configured `SESSION_SECRET`.
+    pub fn session_key(mut self, key: impl Into<SessionKey>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    #[doc = r#"Accept every request without a session, API token or CSRF token.
+
+For tests only: anyone who can reach the address can read and change all data. Requests run
+as the user `auth-disabled` (see [`AuthDisabled`]).
+"#]
+    pub fn disable_auth_for_tests(mut self) -> Self {
+        self.auth_disabled = true;
+        self
+    }
+}
+
+impl ServerBuilder {
+    #[doc = r#"Connect, check, migrate, bind and start serving in the background.
+
+Returns once the listener is bound, so requests to [`ServerHandle::local_addr`] succeed right
+away.
+
+# Errors
+- [`Error::Config`] or [`Error::Database`] when connecting fails
+- [`Error::Migration`] when a migration fails
+- [`Error::Io`] when the address cannot be bound
+"#]
+    pub async fn build(self) -> Result<ServerHandle, Error> {
+        let config = self.config.unwrap_or_else(ServerConfig::from_config);
+        let db = match self.db {
+            Some(db) => db,
+            None => crate::db::connect().await?,
+        };
+        let key = self.key.unwrap_or_else(SessionKey::from_config);
+        let clock = crate::config::clock();
+        let integrity = crate::integrity::startup_check(&db, clock.now_utc()).await;
+        // A corrupt file is served read-only as is: no migrations, no maintenance jobs.
+        if !integrity.degraded() {
+            if config.migrate {
+                sqlx::migrate!("../migrations").run(&db).await?;
+            }
+            if config.jobs {
+                crate::jobs::spawn_scheduler(db.clone(), clock);
+            }
+        }
+        let state = AppState {
+            integrity,
+            ..AppState::new(db.clone(), key)
+        };
+        #[cfg(feature = "simulator")]
+        if let Some(sim) = crate::config::simulator()
+            && !state.integrity.degraded()
+        {
+            crate::simulator::spawn(
+                state.db.clone(),
+                state.events.clone(),
+                state.clock.clone(),
+                sim,
+            );
+        }
+
+        let mut middleware = config.middleware;
+        if self.auth_disabled {
+            tracing::warn!("authentication disabled; every request is accepted");
+            middleware = middleware.csrf(false);
+        }
+        let mut router = app::router_with_middleware(state, middleware);
+        if self.auth_disabled {
+            router = router.layer(axum::Extension(AuthDisabled));
+        }
+
+        let listener = TcpListener::bind(&config.bind_addr).await?;
+        let addr = listener.local_addr()?;
+        tracing::info!(bind_addr = %addr, "API listening");
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async {
+                let _ = signal.await;
+            })
+            .await
+        });
+        Ok(ServerHandle {
+            addr,
+            db,
+            shutdown,
+            task,
+        })
+    }
+}
+
+#[doc = r#"A running server from [`ServerBuilder::build`].
+
+Dropping the handle shuts the server down like [`shutdown`](Self::shutdown) without waiting
+for it; [`wait`](Self::wait) keeps it running until it fails.
+"#]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    db: Db,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<std::io::Result<()>>,
+}
+
+// Accessors for library users; the binary only calls `wait`.
+#[allow(dead_code)]
+impl ServerHandle {
+    /// The bound address, with the actual port when `0` was configured.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The pool the server uses, e.g. for seeding data in tests.
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    #[doc = r#"Stop accepting connections, let in-flight requests finish, and wait for the server
+to stop.
+
+# Errors
+- [`Error::Io`] when serving failed before the shutdown
+"#]
+    pub async fn shutdown(self) -> Result<(), Error> {
+        let _ = self.shutdown.send(());
+        join(self.task).await
+    }
+}
+
+impl ServerHandle {
+    #[doc = r#"Serve until the server stops on its own (it only does on an I/O error).
+
+# Errors
+- [`Error::Io`] when serving fails
+"#]
+    pub async fn wait(self) -> Result<(), Error> {
+        let _keep_running = self.shutdown;
+        join(self.task).await
+    }
+}
+
+async fn join(task: JoinHandle<std::io::Result<()>>) -> Result<(), Error> {
+    match task.await {
+        Ok(result) => Ok(result?),
+        Err(e) => Err(Error::Io(std::io::Error::other(e))),
+    }
+}
@@ -0,0 +1,244 @@
+#![doc = r#"Sleep data simulator
+
+Behind the `simulator` cargo feature (off by default). With `SIMULATOR=1` the server keeps
+writing plausible nights so events, webhooks and alert rules can be demonstrated and soak-tested
+without waiting for real nights to pass.
+
+Every `SIMULATOR_INTERVAL_SECS` (default 60) one [`tick`] runs over the window of the last
+`SIMULATOR_DAYS` wake dates (default 28, ending today in the user timezone):
+- the earliest day of the window without sleep gets a new night;
+- once the window is full, the night of a random day in it is re-rolled.
+
+Writes go through [`handlers`](crate::handlers) like API requests, so they are validated,
+audited and published on the [`EventBus`]. Nights vary around a 23:15 bedtime (40 minutes later
+at weekends) and about 7¼ hours of sleep, with quality and wake feeling following duration.
+`SIMULATOR_SEED` makes the sequence reproducible.
+
+The simulator only writes to a dedicated profile: a database marked with the `profile` setting
+`simulator`. [`claim_profile`] marks an empty database on first start and refuses one that
+already holds sleep entries, so real data is never mixed with simulated nights.
+"#]
+
+use crate::events::EventBus;
+use crate::handlers::{self, EditLock, TimeContext};
+use crate::models::{Quality, SleepInput};
+use crate::time::SharedClock;
+use crate::{db::Db, error::Error};
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, Weekday};
+use sqlx::Sqlite;
+
+/// Value of the `profile` setting that marks a database as the simulator's.
+pub const PROFILE: &str = "simulator";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Simulator settings, see [`config::simulator`](crate::config::simulator)."#]
+pub struct SimulatorConfig {
+    /// Seconds between two ticks.
+    pub interval_secs: u64,
+    /// Length of the window of wake dates kept filled, ending today.
+    pub days: u32,
+    /// Seed of the night generator; `None` seeds from the current time.
+    pub seed: Option<u64>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        SimulatorConfig {
+            interval_secs: 60,
+            days: 28,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"What one [`tick`] did: logged a new night or re-rolled an existing one."#]
+pub enum SimulatedNight {
+    Created { id: i64, date: NaiveDate },
+    Updated { id: i64, date: NaiveDate },
+}
+
+#[derive(Debug, Clone)]
+#[doc = r#"Small deterministic generator (xorshift64*) for simulated nights."#]
+pub struct SimRng(u64);
+
+impl SimRng {
+    /// Generator for `seed`; any seed works, including 0.
+    pub fn new(seed: u64) -> Self {
+        const MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+        // xorshift never leaves the all-zero state, which `seed == MIX` would start in.
+        SimRng(match seed ^ MIX {
+            0 => MIX,
+            state => state,
+        })
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Roughly normal with mean 0 and standard deviation 1 (sum of twelve uniforms).
+    fn normal(&mut self) -> f64 {
+        (0..12).map(|_| self.unit()).sum::<f64>() - 6.0
+    }
+}
+
+#[doc = r#"Make sure `db` is the simulator's profile, marking it when it holds no sleep yet.
+
+# Errors
+
+Returns [`Error::Forbidden`] when the database has sleep entries but is not marked, and a
+database error when the check fails."#]
+pub async fn claim_profile(db: &Db) -> Result<(), Error> {
+    let profile = sqlx::query_scalar::<Sqlite, String>(
+        "SELECT value FROM app_settings WHERE key = 'profile'",
+    )
+    .fetch_optional(db)
+    .await?;
+    match profile.as_deref() {
+        Some(PROFILE) => return Ok(()),
+        Some(other) => {
+            return Err(Error::Forbidden(format!(
+                "database belongs to profile {other:?}, not the simulator"
+            )));
+        }
+        None => {}
+    }
+    let sessions = sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM sleep_sessions")
+        .fetch_one(db)
+        .await?;
+    if sessions > 0 {
+        return Err(Error::Forbidden(
+            "database already holds sleep entries; point the simulator at a dedicated database"
+                .into(),
+        ));
+    }
+    sqlx::query::<Sqlite>("INSERT INTO app_settings(key, value) VALUES ('profile', ?)")
+        .bind(PROFILE)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+#[doc = r#"Spawn the simulator loop after claiming the profile.
+
+Logs and stops when the database is not a simulator profile; a failing tick is logged and the
+loop carries on."#]
+pub fn spawn(
+    db: Db,
+    events: EventBus,
+    clock: SharedClock,
+    config: SimulatorConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = claim_profile(&db).await {
+            tracing::error!(error = %e, "simulator not started");
+            return;
+        }
+        let seed = config
+            .seed
+            .unwrap_or_else(|| clock.now_utc().timestamp_nanos_opt().unwrap_or_default() as u64);
+        let mut rng = SimRng::new(seed);
+        tracing::warn!(
+            interval_secs = config.interval_secs,
+            days = config.days,
+            seed,
+            "simulator writing generated sleep data"
+        );
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            match tick(&db, &events, &clock, &config, &mut rng).await {
+                Ok(night) => tracing::debug!(?night, "simulated night"),
+                Err(e) => tracing::warn!(error = %e, "simulator tick failed"),
+            }
+        }
+    })
+}
+
+#[doc = r#"Run one simulator step: fill the earliest missing day of the window, or re-roll the
+night of a random day in it.
+
+# Errors
+
+Returns an error when reading or writing sleep fails."#]
+pub async fn tick(
+    db: &Db,
+    events: &EventBus,
+    clock: &SharedClock,
+    config: &SimulatorConfig,
+    rng: &mut SimRng,
+) -> Result<SimulatedNight, Error> {
+    let time = TimeContext::from_clock(&**clock);
+    let today = time.today(db).await;
+    let from = today - ChronoDuration::days(i64::from(config.days.max(1)) - 1);
+    let lock = EditLock::none();
+
+    let missing = sqlx::query_scalar::<Sqlite, NaiveDate>(
+        "WITH RECURSIVE days(d) AS (SELECT date(?) UNION ALL \
+         SELECT date(d, '+1 day') FROM days WHERE d < date(?)) \
+         SELECT d FROM days WHERE d NOT IN (SELECT wake_date FROM v_daily_sleep) \
+         ORDER BY d LIMIT 1",
+    )
+    .bind(from)
+    .bind(today)
+    .fetch_optional(db)
+    .await?;
+    if let Some(date) = missing {
+        let id =
+            handlers::create_sleep(db, events, &time, &lock, simulate_night(rng, date)).await?;
+        return Ok(SimulatedNight::Created { id, date });
+    }
+
+    let date = from + ChronoDuration::days((rng.unit() * f64::from(config.days.max(1))) as i64);
+    let id = sqlx::query_scalar::<Sqlite, i64>(
+        "SELECT id FROM sleep_sessions WHERE COALESCE(session_date, date) = ? \
+         ORDER BY id LIMIT 1",
+    )
+    .bind(date)
+    .fetch_one(db)
+    .await?;
+    handlers::update_sleep(db, events, &time, &lock, id, simulate_night(rng, date)).await?;
+    Ok(SimulatedNight::Updated { id, date })
+}
+
+#[doc = r#"Generate a plausible night waking on `date`.
+
+Bedtime is about 23:15 (23:55 before Saturday and Sunday wake dates) give or take 40 minutes,
+sleep lasts about 435 minutes give or take 50; quality and wake feeling rise with duration."#]
+pub fn simulate_night(rng: &mut SimRng, date: NaiveDate) -> SleepInput {
+    let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+    let bed_min = if weekend { 23 * 60 + 55 } else { 23 * 60 + 15 } as f64 + rng.normal() * 40.0;
+    let latency_min = (12.0 + rng.normal() * 6.0).clamp(2.0, 60.0).round() as i32;
+    let duration_min = (435.0 + rng.normal() * 50.0).clamp(240.0, 600.0).round() as i64;
+    let bed_min = (bed_min.round() as i64).rem_euclid(24 * 60);
+    let wake_min = (bed_min + i64::from(latency_min) + duration_min).rem_euclid(24 * 60);
+    let clock = |m: i64| NaiveTime::from_hms_opt((m / 60) as u32, (m % 60) as u32, 0).unwrap();
+
+    let rested = (duration_min as f64 - 435.0) / 50.0 + rng.normal() * 0.7;
+    let score = |offset: f64| (3.0 + rested + offset).round().clamp(1.0, 5.0) as i32;
+    let awakenings = (rng.unit() * 3.0) as i32;
+    SleepInput {
+        date,
+        bed_time: clock(bed_min),
+        wake_time: clock(wake_min),
+        latency_min,
+        awakenings,
+        quality: Quality(score(0.0) as u8),
+        wake_feeling: Some(score(-0.3)),
+        sleep_inertia_min: Some((20.0 - rested * 6.0).clamp(0.0, 90.0).round() as i32),
+        waso_min: Some(awakenings * 8),
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
+    }
+}
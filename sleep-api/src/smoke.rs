@@ -0,0 +1,328 @@
+#![doc = r#"End-to-end smoke check of a running instance (`sleepctl smoke`)
+
+[`run`] drives a deployment over HTTP the way a client would and reports each step:
+
+1. `health`: `GET /api/health` answers `"status":"ok"` (a degraded server fails here).
+2. `login`: `POST /api/login.json`, keeping the session and CSRF cookies.
+3. `create`: `POST /api/sleep` with a 30 minute test session on a free day, starting at
+   [`SMOKE_DATE`] and going back up to a week when that date already has sleep logged.
+4. `read`: `GET /api/sleep/{id}` returns the session.
+5. `trends`: `GET /api/trends/summary` for that day sees its 30 minutes.
+6. `delete`: `DELETE /api/sleep/{id}` (reason `test_data`), then the id answers 404.
+7. `logout`: `POST /api/logout`.
+
+The run stops at the first failing step, but the test session is still deleted and the client
+still logs out when those steps were reached. Writes carry `X-Admin-Override: edit-window`
+because the test date lies outside the no-edit window; they are recorded in the audit log and
+published as events like any other change.
+"#]
+
+use std::time::{Duration, Instant};
+
+use chrono::{Days, NaiveDate};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, header};
+use serde_json::{Value, json};
+
+use crate::handlers::EDIT_WINDOW_OVERRIDE;
+
+/// Wake date tried first for the test session; far enough back to be unused on most instances.
+pub const SMOKE_DATE: NaiveDate = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+
+/// How many earlier days are tried when [`SMOKE_DATE`] already has sleep logged.
+const FREE_DAY_ATTEMPTS: u64 = 7;
+
+/// Per-request timeout of the smoke client.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+#[doc = r#"Outcome of one smoke step: `detail` explains a failure or summarizes the success."#]
+pub struct SmokeStep {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[doc = r#"Steps run by [`run`], in order."#]
+pub struct SmokeReport {
+    pub steps: Vec<SmokeStep>,
+}
+
+impl SmokeReport {
+    /// Whether every step that ran succeeded (and at least one ran).
+    pub fn ok(&self) -> bool {
+        !self.steps.is_empty() && self.steps.iter().all(|s| s.ok)
+    }
+
+    fn record(&mut self, name: &'static str, started: Instant, result: Result<String, String>) {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        self.steps.push(SmokeStep {
+            name,
+            ok,
+            detail,
+            elapsed: started.elapsed(),
+        });
+    }
+}
+
+/// Cookies and CSRF token of a logged-in smoke client.
+struct Session {
+    cookie: String,
+    csrf: String,
+}
+
+#[doc = r#"Build the HTTP client used by `sleepctl smoke` ([`REQUEST_TIMEOUT`] per request).
+
+# Errors
+
+Returns an error when the TLS backend cannot be initialized."#]
+pub fn client() -> reqwest::Result<Client> {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("sleepctl-smoke/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+#[doc = r#"Run the smoke check against `base_url` (e.g. `https://sleep.example.org`), logging in
+as `email` with `password`. See the [module docs](self) for the steps."#]
+pub async fn run(client: &Client, base_url: &str, email: &str, password: &str) -> SmokeReport {
+    let base = base_url.trim_end_matches('/');
+    let mut report = SmokeReport::default();
+
+    let started = Instant::now();
+    let health = health(client, base).await;
+    let healthy = health.is_ok();
+    report.record("health", started, health);
+    if !healthy {
+        return report;
+    }
+
+    let started = Instant::now();
+    let session = match login(client, base, email, password).await {
+        Ok(session) => {
+            report.record("login", started, Ok("session issued".into()));
+            session
+        }
+        Err(e) => {
+            report.record("login", started, Err(e));
+            return report;
+        }
+    };
+
+    let started = Instant::now();
+    match create(client, base, &session).await {
+        Ok((id, date)) => {
+            report.record("create", started, Ok(format!("sleep {id} on {date}")));
+
+            let started = Instant::now();
+            let read = read(client, base, &session, id, date).await;
+            let readable = read.is_ok();
+            report.record("read", started, read);
+            if readable {
+                let started = Instant::now();
+                let trends = trends(client, base, &session, date).await;
+                report.record("trends", started, trends);
+            }
+
+            let started = Instant::now();
+            let deleted = delete(client, base, &session, id).await;
+            report.record("delete", started, deleted);
+        }
+        Err(e) => report.record("create", started, Err(e)),
+    }
+
+    let started = Instant::now();
+    let logout = logout(client, base, &session).await;
+    report.record("logout", started, logout);
+    report
+}
+
+async fn health(client: &Client, base: &str) -> Result<String, String> {
+    let (status, body) = send(client.get(format!("{base}/api/health"))).await?;
+    expect_status(status, StatusCode::OK, &body)?;
+    match body["status"].as_str() {
+        Some("ok") => Ok("ok".into()),
+        other => Err(format!("status {}", other.unwrap_or("missing"))),
+    }
+}
+
+async fn login(
+    client: &Client,
+    base: &str,
+    email: &str,
+    password: &str,
+) -> Result<Session, String> {
+    let res = client
+        .post(format!("{base}/api/login.json"))
+        .json(&json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    if res.status() != StatusCode::OK {
+        return Err(format!("expected 200, got {}", res.status()));
+    }
+    let mut pairs = Vec::new();
+    let mut csrf = None;
+    for value in res.headers().get_all(header::SET_COOKIE) {
+        let Some((name, value)) = value
+            .to_str()
+            .ok()
+            .and_then(|s| s.split(';').next())
+            .and_then(|pair| pair.split_once('='))
+        else {
+            continue;
+        };
+        if name == "csrf" || name == "__Host-csrf" {
+            csrf = Some(value.to_string());
+        }
+        pairs.push(format!("{name}={value}"));
+    }
+    match csrf {
+        Some(csrf) => Ok(Session {
+            cookie: pairs.join("; "),
+            csrf,
+        }),
+        None => Err("login response set no CSRF cookie".into()),
+    }
+}
+
+/// Log a 30 minute session on the first free day from [`SMOKE_DATE`] backwards.
+async fn create(
+    client: &Client,
+    base: &str,
+    session: &Session,
+) -> Result<(i64, NaiveDate), String> {
+    let mut free = None;
+    for date in (0..FREE_DAY_ATTEMPTS).map(|n| SMOKE_DATE - Days::new(n)) {
+        let req = authed(
+            client,
+            Method::GET,
+            &format!("{base}/api/sleep/date/{date}"),
+            session,
+        );
+        let (status, body) = send(req).await?;
+        expect_status(status, StatusCode::OK, &body)?;
+        if body.as_array().is_some_and(Vec::is_empty) {
+            free = Some(date);
+            break;
+        }
+    }
+    let Some(date) = free else {
+        return Err(format!(
+            "no free day in the {FREE_DAY_ATTEMPTS} days up to {SMOKE_DATE}"
+        ));
+    };
+    let req = authed(client, Method::POST, &format!("{base}/api/sleep"), session)
+        .header("X-Admin-Override", EDIT_WINDOW_OVERRIDE)
+        .json(&json!({
+            "date": date,
+            "bed_time": "01:00:00",
+            "wake_time": "01:30:00",
+            "latency_min": 0,
+            "awakenings": 0,
+            "quality": 3,
+        }));
+    let (status, body) = send(req).await?;
+    expect_status(status, StatusCode::CREATED, &body)?;
+    match body["id"].as_i64() {
+        Some(id) => Ok((id, date)),
+        None => Err(format!("response has no id: {body}")),
+    }
+}
+
+async fn read(
+    client: &Client,
+    base: &str,
+    session: &Session,
+    id: i64,
+    date: NaiveDate,
+) -> Result<String, String> {
+    let req = authed(
+        client,
+        Method::GET,
+        &format!("{base}/api/sleep/{id}"),
+        session,
+    );
+    let (status, body) = send(req).await?;
+    expect_status(status, StatusCode::OK, &body)?;
+    if body["date"] != json!(date) {
+        return Err(format!("expected date {date}, got {}", body["date"]));
+    }
+    Ok("session readable".into())
+}
+
+async fn trends(
+    client: &Client,
+    base: &str,
+    session: &Session,
+    date: NaiveDate,
+) -> Result<String, String> {
+    let url = format!("{base}/api/trends/summary?from={date}&to={date}");
+    let (status, body) = send(authed(client, Method::GET, &url, session)).await?;
+    expect_status(status, StatusCode::OK, &body)?;
+    match body["duration_by_bucket"][0]["avg_min"].as_f64() {
+        Some(30.0) => Ok("summary includes the session".into()),
+        _ => Err(format!(
+            "expected 30 minutes on {date}, got {}",
+            body["duration_by_bucket"]
+        )),
+    }
+}
+
+async fn delete(client: &Client, base: &str, session: &Session, id: i64) -> Result<String, String> {
+    let url = format!("{base}/api/sleep/{id}");
+    let req = authed(client, Method::DELETE, &url, session)
+        .header("X-Admin-Override", EDIT_WINDOW_OVERRIDE)
+        .json(&json!({ "reason": "test_data", "note": "sleepctl smoke" }));
+    let (status, body) = send(req).await?;
+    expect_status(status, StatusCode::NO_CONTENT, &body)?;
+    let (status, body) = send(authed(client, Method::GET, &url, session)).await?;
+    expect_status(status, StatusCode::NOT_FOUND, &body)?;
+    Ok("session removed".into())
+}
+
+async fn logout(client: &Client, base: &str, session: &Session) -> Result<String, String> {
+    let url = format!("{base}/api/logout");
+    let (status, body) = send(authed(client, Method::POST, &url, session)).await?;
+    expect_status(status, StatusCode::NO_CONTENT, &body)?;
+    Ok("logged out".into())
+}
+
+/// Request carrying the session cookies and the CSRF header.
+fn authed(client: &Client, method: Method, url: &str, session: &Session) -> RequestBuilder {
+    client
+        .request(method, url)
+        .header(header::COOKIE, &session.cookie)
+        .header("X-CSRF-Token", &session.csrf)
+}
+
+/// Send `req` and return its status with the JSON body (`null` when empty or not JSON).
+async fn send(req: RequestBuilder) -> Result<(StatusCode, Value), String> {
+    let res = req
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    let status = res.status();
+    let bytes = res
+        .bytes()
+        .await
+        .map_err(|e| format!("reading response failed: {e}"))?;
+    Ok((
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    ))
+}
+
+fn expect_status(actual: StatusCode, expected: StatusCode, body: &Value) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else if body.is_null() {
+        Err(format!("expected {expected}, got {actual}"))
+    } else {
+        Err(format!("expected {expected}, got {actual}: {body}"))
+    }
+}
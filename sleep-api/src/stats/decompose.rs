@@ -0,0 +1,231 @@
+#![doc = r#"Weekly seasonal decomposition
+
+Classical additive decomposition of a daily series, `value = trend + seasonal + residual`:
+
+1. **Trend** — centered 7-day moving average. A day gets a trend value when at least
+   [`MIN_WINDOW_VALUES`] of the 7 days around it are present, so isolated gaps do not break
+   the curve.
+2. **Seasonal** — mean of `value - trend` per weekday, re-centered so the 7 effects sum to zero.
+3. **Residual** — what is left after removing trend and seasonal effect.
+
+Missing days stay in the output with `value: None` so the series aligns with the calendar.
+"#]
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Window length of the trend moving average (one week).
+const WINDOW: usize = 7;
+
+/// Present values required inside a window before a trend value is produced.
+pub const MIN_WINDOW_VALUES: usize = 4;
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"One day of a decomposed series. Components are `None` where they cannot be computed."#]
+pub struct DecomposedPoint {
+    pub date: NaiveDate,
+    pub value: Option<f64>,
+    pub trend: Option<f64>,
+    pub seasonal: Option<f64>,
+    pub residual: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Average deviation from trend on one weekday (`Mon`..`Sun`)."#]
+pub struct WeekdayEffect {
+    pub weekday: String,
+    pub effect: Option<f64>,
+    pub samples: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"Result of [`decompose_weekly`].
+
+- `trend_slope_per_week`: least-squares slope of the trend component, in metric units per
+  week; `None` with fewer than two trend values. A slope near zero with strong weekday
+  effects means the apparent changes are weekly cycles rather than improvement.
+"#]
+pub struct Decomposition {
+    pub points: Vec<DecomposedPoint>,
+    pub weekday_effects: Vec<WeekdayEffect>,
+    pub trend_slope_per_week: Option<f64>,
+}
+
+#[doc = r#"Decompose a daily series starting at `start` (one entry per consecutive day).
+
+# Example
+
+```rust
+# use sleep_api::stats::decompose::decompose_weekly;
+# use chrono::NaiveDate;
+let start = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(); // a Monday
+// Flat 420 on weekdays, 480 on weekends, for four weeks.
+let values: Vec<Option<f64>> = (0..28)
+    .map(|i| Some(if i % 7 >= 5 { 480.0 } else { 420.0 }))
+    .collect();
+let d = decompose_weekly(start, &values);
+let slope = d.trend_slope_per_week.unwrap();
+assert!(slope.abs() < 1e-9);
+assert!(d.weekday_effects[5].effect.unwrap() > 0.0); // Saturday
+```
+"#]
+pub fn decompose_weekly(start: NaiveDate, values: &[Option<f64>]) -> Decomposition {
+    let n = values.len();
+    let half = WINDOW / 2;
+    let dates: Vec<NaiveDate> = (0..n).map(|i| start + Duration::days(i as i64)).collect();
+
+    let trend: Vec<Option<f64>> = (0..n)
+        .map(|i| {
+            if i < half || i + half >= n {
+                return None;
+            }
+            let window: Vec<f64> = values[i - half..=i + half]
+                .iter()
+                .flatten()
+                .copied()
+                .collect();
+            (window.len() >= MIN_WINDOW_VALUES)
+                .then(|| window.iter().sum::<f64>() / window.len() as f64)
+        })
+        .collect();
+
+    // Weekday index 0 = Monday.
+    let mut sums = [0.0f64; 7];
+    let mut counts = [0usize; 7];
+    for i in 0..n {
+        if let (Some(v), Some(t)) = (values[i], trend[i]) {
+            let wd = dates[i].weekday().num_days_from_monday() as usize;
+            sums[wd] += v - t;
+            counts[wd] += 1;
+        }
+    }
+    let raw: Vec<Option<f64>> = (0..7)
+        .map(|wd| (counts[wd] > 0).then(|| sums[wd] / counts[wd] as f64))
+        .collect();
+    let present: Vec<f64> = raw.iter().flatten().copied().collect();
+    let center = if present.is_empty() {
+        0.0
+    } else {
+        present.iter().sum::<f64>() / present.len() as f64
+    };
+    let effects: Vec<Option<f64>> = raw.iter().map(|e| e.map(|e| e - center)).collect();
+
+    let points = (0..n)
+        .map(|i| {
+            let seasonal = effects[dates[i].weekday().num_days_from_monday() as usize];
+            let residual = match (values[i], trend[i], seasonal) {
+                (Some(v), Some(t), Some(s)) => Some(v - t - s),
+                _ => None,
+            };
+            DecomposedPoint {
+                date: dates[i],
+                value: values[i],
+                trend: trend[i],
+                seasonal,
+                residual,
+            }
+        })
+        .collect();
+
+    let weekdays = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+    let weekday_effects = weekdays
+        .iter()
+        .enumerate()
+        .map(|(wd, day)| WeekdayEffect {
+            weekday: day.to_string(),
+            effect: effects[wd],
+            samples: counts[wd],
+        })
+        .collect();
+
+    let trend_points: Vec<(f64, f64)> = trend
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| t.map(|t| (i as f64, t)))
+        .collect();
+
+    Decomposition {
+        points,
+        weekday_effects,
+        trend_slope_per_week: slope(&trend_points).map(|per_day| per_day * 7.0),
+    }
+}
+
+/// Least-squares slope of `y` over `x`; `None` for fewer than two points or constant `x`.
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    Some(sxy / sxx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()
+    }
+
+    #[test]
+    fn linear_trend_with_weekly_cycle_is_separated() {
+        // +1 minute per day, plus +60 on weekends.
+        let values: Vec<Option<f64>> = (0..35)
+            .map(|i| Some(400.0 + i as f64 + if i % 7 >= 5 { 60.0 } else { 0.0 }))
+            .collect();
+        let d = decompose_weekly(monday(), &values);
+
+        let slope = d.trend_slope_per_week.unwrap();
+        assert!((slope - 7.0).abs() < 1e-9, "slope {slope}");
+
+        let sat = d.weekday_effects[5].effect.unwrap();
+        let mon = d.weekday_effects[0].effect.unwrap();
+        assert!((sat - mon - 60.0).abs() < 1e-9);
+        let total: f64 = d.weekday_effects.iter().filter_map(|e| e.effect).sum();
+        assert!(total.abs() < 1e-9);
+
+        for p in d.points.iter().filter(|p| p.residual.is_some()) {
+            assert!(p.residual.unwrap().abs() < 1e-9, "{p:?}");
+        }
+        // Edges lack a full centered window.
+        assert!(d.points[0].trend.is_none());
+        assert!(d.points[34].trend.is_none());
+    }
+
+    #[test]
+    fn sparse_windows_have_no_trend() {
+        let mut values = vec![Some(420.0); 14];
+        for v in values.iter_mut().take(10).skip(3) {
+            *v = None;
+        }
+        let d = decompose_weekly(monday(), &values);
+        // Day 6 sees only days 3..=9, all missing.
+        assert!(d.points[6].trend.is_none());
+        assert_eq!(d.points[6].value, None);
+        assert!(d.points[10].trend.is_some());
+    }
+
+    #[test]
+    fn short_series_has_no_slope() {
+        let d = decompose_weekly(monday(), &[Some(1.0), Some(2.0)]);
+        assert!(d.trend_slope_per_week.is_none());
+        assert_eq!(d.points.len(), 2);
+    }
+}
@@ -0,0 +1,413 @@
+#![doc = r#"Two-group inference and correlation
+
+Effect size, Welch t-test p-value, and a percentile bootstrap confidence interval for the
+difference of means between two groups of nights, plus plain-language caveats when the
+samples are too small to support the numbers. [`pearson`] gives the same treatment to the
+correlation between two paired series.
+
+The bootstrap uses a fixed seed so the same data always yields the same interval.
+"#]
+
+pub use crate::models::inference::{Correlation, Inference, MIN_RELIABLE_N, SignificanceHint};
+
+/// Bootstrap resamples drawn by [`compare_groups`].
+const BOOTSTRAP_RESAMPLES: usize = 2000;
+
+/// Fixed seed so bootstrap intervals are reproducible between requests.
+const BOOTSTRAP_SEED: u64 = 0x5EED_51EE_9000_0001;
+
+#[doc = r#"Compare two groups of values.
+
+# Example
+
+```rust
+# use sleep_api::stats::inference::compare_groups;
+let a = [7.5, 8.0, 7.0, 8.5, 7.8, 8.2, 7.9, 8.1, 7.6, 8.4];
+let b = [6.0, 6.5, 6.2, 5.8, 6.4, 6.1, 6.6, 5.9, 6.3, 6.0];
+let inf = compare_groups(&a, &b);
+assert!(inf.p_value.unwrap() < 0.001);
+assert_eq!(inf.effect_magnitude, Some("large"));
+assert!(inf.caveats.is_empty());
+```
+"#]
+pub fn compare_groups(a: &[f64], b: &[f64]) -> Inference {
+    let mut caveats = Vec::new();
+    if a.len() < 2 || b.len() < 2 {
+        caveats.push(format!(
+            "Not enough nights to estimate uncertainty ({} vs {}); at least 2 per group are needed.",
+            a.len(),
+            b.len()
+        ));
+        return Inference {
+            n_a: a.len(),
+            n_b: b.len(),
+            effect_size: None,
+            effect_magnitude: None,
+            p_value: None,
+            bootstrap_ci95_low: None,
+            bootstrap_ci95_high: None,
+            caveats,
+        };
+    }
+    if a.len() < MIN_RELIABLE_N || b.len() < MIN_RELIABLE_N {
+        caveats.push(format!(
+            "Small sample ({} vs {} nights): fewer than {MIN_RELIABLE_N} nights in a group makes the p-value and interval unreliable.",
+            a.len(),
+            b.len()
+        ));
+    }
+
+    let effect_size = hedges_g(a, b);
+    let p_value = welch_p_value(a, b);
+    let ci = bootstrap_diff_ci95(a, b);
+    if let Some(p) = p_value
+        && p >= 0.05
+    {
+        caveats.push(
+            "The difference is not statistically significant (p ≥ 0.05); it may be noise.".into(),
+        );
+    }
+
+    Inference {
+        n_a: a.len(),
+        n_b: b.len(),
+        effect_size,
+        effect_magnitude: effect_size.map(magnitude),
+        p_value,
+        bootstrap_ci95_low: ci.map(|c| c.0),
+        bootstrap_ci95_high: ci.map(|c| c.1),
+        caveats,
+    }
+}
+
+fn magnitude(g: f64) -> &'static str {
+    match g.abs() {
+        x if x < 0.2 => "negligible",
+        x if x < 0.5 => "small",
+        x if x < 0.8 => "medium",
+        _ => "large",
+    }
+}
+
+#[doc = r#"Hedges' g for `a - b`, or `None` with fewer than two values per group or zero spread."#]
+pub fn hedges_g(a: &[f64], b: &[f64]) -> Option<f64> {
+    let (Some(ma), Some(sa)) = super::mean_sd(a) else {
+        return None;
+    };
+    let (Some(mb), Some(sb)) = super::mean_sd(b) else {
+        return None;
+    };
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let pooled = (((na - 1.0) * sa * sa + (nb - 1.0) * sb * sb) / (na + nb - 2.0)).sqrt();
+    if pooled == 0.0 {
+        return None;
+    }
+    let correction = 1.0 - 3.0 / (4.0 * (na + nb) - 9.0);
+    Some((ma - mb) / pooled * correction)
+}
+
+#[doc = r#"Two-sided Welch t-test p-value, or `None` with fewer than two values per group.
+
+Identical constant groups give 1.0; distinct constant groups give 0.0.
+"#]
+pub fn welch_p_value(a: &[f64], b: &[f64]) -> Option<f64> {
+    let (Some(ma), Some(sa)) = super::mean_sd(a) else {
+        return None;
+    };
+    let (Some(mb), Some(sb)) = super::mean_sd(b) else {
+        return None;
+    };
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (va, vb) = (sa * sa / na, sb * sb / nb);
+    let se = (va + vb).sqrt();
+    if se == 0.0 {
+        return Some(if ma == mb { 1.0 } else { 0.0 });
+    }
+    let t = (ma - mb) / se;
+    let df = (va + vb).powi(2) / (va * va / (na - 1.0) + vb * vb / (nb - 1.0));
+    // Two-sided tail of Student's t: I_{df/(df+t²)}(df/2, 1/2).
+    Some(beta_inc(df / 2.0, 0.5, df / (df + t * t)).clamp(0.0, 1.0))
+}
+
+#[doc = r#"95% percentile bootstrap interval for the difference of means `a - b`.
+
+Resamples each group with replacement using a fixed seed. Returns `None` with fewer than
+two values per group.
+"#]
+pub fn bootstrap_diff_ci95(a: &[f64], b: &[f64]) -> Option<(f64, f64)> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let mut rng = SplitMix64(BOOTSTRAP_SEED);
+    let mut resample_mean = |values: &[f64]| {
+        let sum: f64 = (0..values.len())
+            .map(|_| values[rng.below(values.len())])
+            .sum();
+        sum / values.len() as f64
+    };
+    let mut diffs: Vec<f64> = (0..BOOTSTRAP_RESAMPLES)
+        .map(|_| resample_mean(a) - resample_mean(b))
+        .collect();
+    diffs.sort_by(f64::total_cmp);
+    let at = |q: f64| diffs[((diffs.len() - 1) as f64 * q).round() as usize];
+    Some((at(0.025), at(0.975)))
+}
+
+#[doc = r#"Pearson correlation of the paired series `x` and `y`.
+
+Pairs beyond the shorter series are ignored.
+
+# Example
+
+```rust
+# use sleep_api::stats::inference::pearson;
+let intensity = [0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 1.0];
+let quality = [3.0, 4.0, 5.0, 3.0, 4.0, 4.0, 2.0, 4.0, 5.0, 3.0];
+let c = pearson(&intensity, &quality);
+assert!(c.r.unwrap() > 0.8);
+assert!(c.p_value.unwrap() < 0.01);
+assert_eq!(c.strength, Some("strong"));
+```
+"#]
+pub fn pearson(x: &[f64], y: &[f64]) -> Correlation {
+    let n = x.len().min(y.len());
+    let (x, y) = (&x[..n], &y[..n]);
+    let mut caveats = Vec::new();
+    if n < 3 {
+        caveats.push(format!(
+            "Not enough nights to estimate a correlation ({n}); at least 3 are needed."
+        ));
+        return Correlation {
+            n,
+            r: None,
+            strength: None,
+            p_value: None,
+            caveats,
+        };
+    }
+    if n < MIN_RELIABLE_N {
+        caveats.push(format!(
+            "Small sample ({n} nights): fewer than {MIN_RELIABLE_N} nights makes the coefficient and p-value unreliable."
+        ));
+    }
+    let (mx, my) = (
+        x.iter().sum::<f64>() / n as f64,
+        y.iter().sum::<f64>() / n as f64,
+    );
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (a, b) in x.iter().zip(y) {
+        sxy += (a - mx) * (b - my);
+        sxx += (a - mx).powi(2);
+        syy += (b - my).powi(2);
+    }
+    if sxx == 0.0 || syy == 0.0 {
+        caveats.push("One of the series does not vary, so no correlation can be computed.".into());
+        return Correlation {
+            n,
+            r: None,
+            strength: None,
+            p_value: None,
+            caveats,
+        };
+    }
+    let r = (sxy / (sxx * syy).sqrt()).clamp(-1.0, 1.0);
+    let df = (n - 2) as f64;
+    let p_value = if r.abs() == 1.0 {
+        0.0
+    } else {
+        let t2 = r * r * df / (1.0 - r * r);
+        beta_inc(df / 2.0, 0.5, df / (df + t2)).clamp(0.0, 1.0)
+    };
+    if p_value >= 0.05 {
+        caveats.push(
+            "The correlation is not statistically significant (p ≥ 0.05); it may be noise.".into(),
+        );
+    }
+    Correlation {
+        n,
+        r: Some(r),
+        strength: Some(strength(r)),
+        p_value: Some(p_value),
+        caveats,
+    }
+}
+
+fn strength(r: f64) -> &'static str {
+    match r.abs() {
+        x if x < 0.1 => "negligible",
+        x if x < 0.3 => "weak",
+        x if x < 0.5 => "moderate",
+        _ => "strong",
+    }
+}
+
+/// Small deterministic PRNG; statistical quality is ample for resampling indices.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Natural log of the gamma function (Lanczos, g = 7).
+fn ln_gamma(x: f64) -> f64 {
+    const COEF: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEF[1..]
+        .iter()
+        .enumerate()
+        .fold(COEF[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`.
+fn beta_inc(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_cf(a, b, x) / a
+    } else {
+        1.0 - front * beta_cf(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz).
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=200 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for aa in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + aa * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + aa / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welch_p_value_matches_reference() {
+        // t = 2 with df = 8; reference 2 * scipy.stats.t.sf(2, 8).
+        let a = [5.0, 6.0, 7.0, 8.0, 9.0];
+        let b = [3.0, 4.0, 5.0, 6.0, 7.0];
+        let p = welch_p_value(&a, &b).unwrap();
+        assert!((p - 0.080_516).abs() < 1e-5, "p = {p}");
+    }
+
+    #[test]
+    fn hedges_g_is_bias_corrected() {
+        let a = [5.0, 6.0, 7.0, 8.0, 9.0];
+        let b = [3.0, 4.0, 5.0, 6.0, 7.0];
+        let g = hedges_g(&a, &b).unwrap();
+        // d = 2 / sqrt(2.5) = 1.2649; J = 1 - 3 / 31.
+        assert!((g - 1.264_911 * (1.0 - 3.0 / 31.0)).abs() < 1e-5);
+        assert_eq!(magnitude(g), "large");
+    }
+
+    #[test]
+    fn bootstrap_ci_is_deterministic_and_brackets_diff() {
+        let a = [7.0, 8.0, 6.5, 7.5, 8.5, 7.0, 6.0, 9.0];
+        let b = [6.0, 6.5, 5.5, 7.0, 6.0, 5.0, 6.5, 6.0];
+        let first = bootstrap_diff_ci95(&a, &b).unwrap();
+        assert_eq!(bootstrap_diff_ci95(&a, &b), Some(first));
+        let diff = 7.4375 - 6.0625;
+        assert!(first.0 < diff && diff < first.1);
+    }
+
+    #[test]
+    fn small_samples_carry_caveats() {
+        let inf = compare_groups(&[7.0], &[6.0, 5.0]);
+        assert_eq!(inf.p_value, None);
+        assert_eq!(inf.bootstrap_ci95_low, None);
+        assert_eq!(inf.caveats.len(), 1);
+
+        let inf = compare_groups(&[7.0, 8.0, 7.5], &[6.0, 5.0, 8.0]);
+        assert!(inf.p_value.is_some());
+        assert!(inf.caveats.iter().any(|c| c.starts_with("Small sample")));
+        assert!(inf.caveats.iter().any(|c| c.contains("not statistically")));
+    }
+
+    #[test]
+    fn pearson_matches_reference() {
+        // scipy.stats.pearsonr([1, 2, 3, 4, 5], [2, 4, 5, 4, 5]) = (0.7746, 0.1240).
+        let c = pearson(&[1.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 4.0, 5.0, 4.0, 5.0]);
+        assert!((c.r.unwrap() - 0.774_597).abs() < 1e-5);
+        assert!((c.p_value.unwrap() - 0.124_027).abs() < 1e-4, "{c:?}");
+        assert!(c.caveats.iter().any(|c| c.starts_with("Small sample")));
+
+        assert_eq!(pearson(&[1.0, 2.0], &[3.0, 4.0]).r, None);
+        let flat = pearson(&[1.0, 2.0, 3.0], &[4.0, 4.0, 4.0]);
+        assert_eq!((flat.r, flat.p_value), (None, None));
+    }
+
+    #[test]
+    fn hint_needs_reliable_samples() {
+        let a = [7.5, 8.0, 7.0, 8.5, 7.8, 8.2, 7.9, 8.1, 7.6, 8.4];
+        let b = [6.0, 6.5, 6.2, 5.8, 6.4, 6.1, 6.6, 5.9, 6.3, 6.0];
+        assert_eq!(compare_groups(&a, &b).hint(), SignificanceHint::Likely);
+        assert_eq!(
+            compare_groups(&a[..4], &b[..4]).hint(),
+            SignificanceHint::Possible
+        );
+        assert_eq!(compare_groups(&a, &a).hint(), SignificanceHint::Unlikely);
+        assert_eq!(
+            compare_groups(&a[..1], &b).hint(),
+            SignificanceHint::Insufficient
+        );
+    }
+}
@@ -0,0 +1,139 @@
+#![doc = r#"Statistics helpers
+
+Pure numeric routines used by the trends endpoints. Nothing here touches the database or
+HTTP; callers pass plain series and get serializable results back.
+
+Modules:
+- [`decompose`] — split a daily series into trend, weekly seasonality, and residual.
+- [`inference`] — effect size, p-value, and bootstrap CI for two-group comparisons; Pearson
+  correlation with a p-value.
+- [`reference`] — bundled age-bracket reference distributions for population context.
+
+Top-level helpers: [`normal_cdf`], [`mean_sd`], and [`welch_ci95`] for comparing two groups.
+
+[`decompose`]: crate::stats::decompose
+[`inference`]: crate::stats::inference
+[`reference`]: crate::stats::reference
+"#]
+
+pub mod decompose;
+pub mod inference;
+pub mod reference;
+
+#[doc = r#"Standard normal cumulative distribution function.
+
+Uses the Abramowitz–Stegun 7.1.26 approximation of `erf` (absolute error below 1.5e-7),
+which is plenty for reporting percentiles.
+
+# Example
+
+```rust
+# use sleep_api::stats::normal_cdf;
+assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+assert!((normal_cdf(1.96) - 0.975).abs() < 1e-3);
+```
+"#]
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[doc = r#"Return the mean and sample standard deviation of `values`.
+
+The mean is `None` for an empty slice; the standard deviation needs at least two values.
+"#]
+pub fn mean_sd(values: &[f64]) -> (Option<f64>, Option<f64>) {
+    if values.is_empty() {
+        return (None, None);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() < 2 {
+        return (Some(mean), None);
+    }
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (Some(mean), Some(var.sqrt()))
+}
+
+/// Two-sided 97.5% quantile of Student's t with `df` degrees of freedom (Cornish–Fisher).
+fn t_975(df: f64) -> f64 {
+    let z: f64 = 1.959_963_985;
+    let (z3, z5, z7) = (z.powi(3), z.powi(5), z.powi(7));
+    z + (z3 + z) / (4.0 * df)
+        + (5.0 * z5 + 16.0 * z3 + 3.0 * z) / (96.0 * df.powi(2))
+        + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * z) / (384.0 * df.powi(3))
+}
+
+#[doc = r#"Difference of means `a - b` with a 95% Welch t confidence interval.
+
+Returns `(diff, low, high)`, or `None` unless both groups have at least two values.
+
+# Example
+
+```rust
+# use sleep_api::stats::welch_ci95;
+let (diff, low, high) = welch_ci95(&[7.0, 8.0, 9.0], &[5.0, 6.0, 7.0]).unwrap();
+assert_eq!(diff, 2.0);
+assert!(low < diff && diff < high);
+```
+"#]
+pub fn welch_ci95(a: &[f64], b: &[f64]) -> Option<(f64, f64, f64)> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (Some(ma), Some(sa)) = mean_sd(a) else {
+        return None;
+    };
+    let (Some(mb), Some(sb)) = mean_sd(b) else {
+        return None;
+    };
+    let (va, vb) = (sa * sa / a.len() as f64, sb * sb / b.len() as f64);
+    let se = (va + vb).sqrt();
+    let diff = ma - mb;
+    if se == 0.0 {
+        return Some((diff, diff, diff));
+    }
+    let df =
+        (va + vb).powi(2) / (va * va / (a.len() as f64 - 1.0) + vb * vb / (b.len() as f64 - 1.0));
+    let half = t_975(df) * se;
+    Some((diff, diff - half, diff + half))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_sd_small_samples() {
+        assert_eq!(mean_sd(&[]), (None, None));
+        assert_eq!(mean_sd(&[3.0]), (Some(3.0), None));
+        let (m, sd) = mean_sd(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(m, Some(5.0));
+        assert!((sd.unwrap() - 2.138_089_935).abs() < 1e-6);
+    }
+
+    #[test]
+    fn t_quantile_matches_tables() {
+        // Tabulated t(0.975): df=5 -> 2.571, df=10 -> 2.228, df=30 -> 2.042.
+        assert!((t_975(5.0) - 2.571).abs() < 0.02);
+        assert!((t_975(10.0) - 2.228).abs() < 0.005);
+        assert!((t_975(30.0) - 2.042).abs() < 0.001);
+    }
+
+    #[test]
+    fn welch_interval_requires_two_per_group() {
+        assert!(welch_ci95(&[1.0], &[1.0, 2.0]).is_none());
+        let (diff, low, high) = welch_ci95(&[5.0, 5.0], &[5.0, 5.0]).unwrap();
+        assert_eq!((diff, low, high), (0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,193 @@
+#![doc = r#"Population reference data
+
+Static, bundled reference distributions used to give personal averages some context. Values
+are rounded approximations of published consensus guidance and survey data, not clinical
+norms:
+
+- **Typical range** — recommended nightly sleep per age bracket (consensus guidelines for
+  teens, adults, and older adults), and 10–20 minutes for sleep latency, where regularly
+  taking more than 30 minutes is a common insomnia marker.
+- **Distribution** — an approximate normal (`mean`, `sd`) of self-reported values per bracket,
+  used only to express a percentile.
+
+Everything is computed locally; no external service is contacted.
+"#]
+
+use super::normal_cdf;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[doc = r#"Reference values for one metric within an age bracket."#]
+pub struct MetricReference {
+    pub typical_low: f64,
+    pub typical_high: f64,
+    pub mean: f64,
+    pub sd: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[doc = r#"Reference data for an inclusive age bracket (`max_age: None` means open-ended)."#]
+pub struct AgeBracket {
+    pub label: &'static str,
+    pub min_age: u32,
+    pub max_age: Option<u32>,
+    pub duration_min: MetricReference,
+    pub latency_min: MetricReference,
+}
+
+const LATENCY: MetricReference = MetricReference {
+    typical_low: 10.0,
+    typical_high: 20.0,
+    mean: 18.0,
+    sd: 12.0,
+};
+
+#[doc = r#"Bundled age brackets, youngest first."#]
+pub const AGE_BRACKETS: &[AgeBracket] = &[
+    AgeBracket {
+        label: "14-17",
+        min_age: 14,
+        max_age: Some(17),
+        duration_min: MetricReference {
+            typical_low: 480.0,
+            typical_high: 600.0,
+            mean: 450.0,
+            sd: 70.0,
+        },
+        latency_min: LATENCY,
+    },
+    AgeBracket {
+        label: "18-25",
+        min_age: 18,
+        max_age: Some(25),
+        duration_min: MetricReference {
+            typical_low: 420.0,
+            typical_high: 540.0,
+            mean: 430.0,
+            sd: 70.0,
+        },
+        latency_min: LATENCY,
+    },
+    AgeBracket {
+        label: "26-64",
+        min_age: 26,
+        max_age: Some(64),
+        duration_min: MetricReference {
+            typical_low: 420.0,
+            typical_high: 540.0,
+            mean: 415.0,
+            sd: 65.0,
+        },
+        latency_min: LATENCY,
+    },
+    AgeBracket {
+        label: "65+",
+        min_age: 65,
+        max_age: None,
+        duration_min: MetricReference {
+            typical_low: 420.0,
+            typical_high: 480.0,
+            mean: 420.0,
+            sd: 70.0,
+        },
+        latency_min: MetricReference {
+            mean: 22.0,
+            sd: 14.0,
+            ..LATENCY
+        },
+    },
+];
+
+#[doc = r#"Return the bracket containing `age`, or `None` below the youngest bracket."#]
+pub fn bracket_for_age(age: u32) -> Option<&'static AgeBracket> {
+    AGE_BRACKETS
+        .iter()
+        .find(|b| age >= b.min_age && b.max_age.is_none_or(|max| age <= max))
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[doc = r#"Where a value falls relative to the typical range."#]
+pub enum RangePosition {
+    Below,
+    Within,
+    Above,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[doc = r#"A personal average placed within a reference distribution.
+
+- `population_percentile`: share of the reference population with a lower value, 1..=99.
+"#]
+pub struct MetricContext {
+    pub your_avg: f64,
+    pub typical_low: f64,
+    pub typical_high: f64,
+    pub population_percentile: f64,
+    pub position: RangePosition,
+}
+
+#[doc = r#"Place `value` within `reference`.
+
+# Example
+
+```rust
+# use sleep_api::stats::reference::{bracket_for_age, place, RangePosition};
+let adult = bracket_for_age(30).unwrap();
+let ctx = place(384.0, &adult.duration_min);
+assert_eq!(ctx.position, RangePosition::Below);
+assert!(ctx.population_percentile < 50.0);
+```
+"#]
+pub fn place(value: f64, reference: &MetricReference) -> MetricContext {
+    let position = if value < reference.typical_low {
+        RangePosition::Below
+    } else if value > reference.typical_high {
+        RangePosition::Above
+    } else {
+        RangePosition::Within
+    };
+    let pct = normal_cdf((value - reference.mean) / reference.sd) * 100.0;
+    MetricContext {
+        your_avg: value,
+        typical_low: reference.typical_low,
+        typical_high: reference.typical_high,
+        population_percentile: (pct.clamp(1.0, 99.0) * 10.0).round() / 10.0,
+        position,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brackets_cover_ages_without_gaps() {
+        assert!(bracket_for_age(13).is_none());
+        assert_eq!(bracket_for_age(14).unwrap().label, "14-17");
+        assert_eq!(bracket_for_age(25).unwrap().label, "18-25");
+        assert_eq!(bracket_for_age(26).unwrap().label, "26-64");
+        assert_eq!(bracket_for_age(90).unwrap().label, "65+");
+        for pair in AGE_BRACKETS.windows(2) {
+            assert_eq!(pair[0].max_age.unwrap() + 1, pair[1].min_age);
+        }
+    }
+
+    #[test]
+    fn place_reports_position_and_clamped_percentile() {
+        let adult = bracket_for_age(40).unwrap();
+        let mean = place(adult.duration_min.mean, &adult.duration_min);
+        assert_eq!(mean.population_percentile, 50.0);
+
+        assert_eq!(
+            place(480.0, &adult.duration_min).position,
+            RangePosition::Within
+        );
+        assert_eq!(
+            place(600.0, &adult.duration_min).position,
+            RangePosition::Above
+        );
+        assert_eq!(place(60.0, &adult.duration_min).population_percentile, 1.0);
+    }
+}
@@ -0,0 +1,322 @@
+#![doc = r#"Multi-tenant mode
+
+Optional deployment mode where one process serves several isolated SQLite databases, one
+file per tenant. Enabled with `TENANT_MODE` (see [`config::tenant_mode`]); single-tenant
+deployments are unaffected.
+
+How a request is served:
+1. [`TenantRegistry::resolve`] picks the tenant from the `Host` subdomain
+   (`alice.sleep.example.com`) or a `/t/{tenant}` path prefix, which is stripped.
+2. Unknown tenants (not listed in `TENANTS`) get `404` before any file is touched.
+3. On first use the tenant's database file is created, integrity-checked, migrated, and its
+   maintenance scheduler started (a corrupt file is served read-only instead, see
+   [`crate::integrity`]); the pool and a per-tenant router are cached.
+4. The request runs inside [`current`]'s task-local scope so login checks the tenant's
+   own credentials (`TENANT_<NAME>_ADMIN_EMAIL` / `TENANT_<NAME>_ADMIN_PASSWORD_HASH`).
+
+Session cookies are encrypted with a key derived per tenant, so a cookie issued for one
+tenant never authenticates against another.
+
+[`config::tenant_mode`]: crate::config::tenant_mode
+"#]
+
+use crate::db;
+use crate::reload::SessionKey;
+use axum::{
+    Json, Router,
+    extract::{Request, State},
+    http::{StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+tokio::task_local! {
+    static CURRENT_TENANT: String;
+}
+
+#[doc = r#"Return the tenant serving the current request, or `None` in single-tenant mode."#]
+pub fn current() -> Option<String> {
+    CURRENT_TENANT.try_with(|t| t.clone()).ok()
+}
+
+#[doc = r#"Environment variable name for a per-tenant setting, e.g. `TENANT_ALICE_ADMIN_EMAIL`."#]
+pub fn env_var_name(tenant: &str, suffix: &str) -> String {
+    format!(
+        "TENANT_{}_{suffix}",
+        tenant.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[doc = r#"How the tenant is resolved from a request."#]
+pub enum TenantMode {
+    /// First label of the `Host` header.
+    Subdomain,
+    /// `/t/{tenant}/...` path prefix.
+    PathPrefix,
+}
+
+impl std::str::FromStr for TenantMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "subdomain" => Ok(TenantMode::Subdomain),
+            "path" => Ok(TenantMode::PathPrefix),
+            other => Err(format!("unknown tenant mode {other:?}")),
+        }
+    }
+}
+
+#[doc = r#"Whether `name` is a valid tenant name: 1–32 of `a-z`, `0-9`, `-`, not starting with `-`."#]
+pub fn is_valid_tenant_name(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && !name.starts_with('-')
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+struct Inner {
+    mode: TenantMode,
+    data_dir: PathBuf,
+    allowed: BTreeSet<String>,
+    master_key: SessionKey,
+    /// Tenant → router; each router's state owns that tenant's pool.
+    tenants: Mutex<HashMap<String, Router>>,
+}
+
+#[derive(Clone)]
+#[doc = r#"Tenant → pool map with lazy pool creation.
+
+Cheap to clone; all clones share the same cache.
+
+# Example
+
+```rust,no_run
+# use sleep_api::tenant::{TenantMode, TenantRegistry};
+# async fn demo() {
+let registry = TenantRegistry::new(
+    TenantMode::Subdomain,
+    "/var/lib/sleep/tenants".into(),
+    ["alice".to_string(), "bob".to_string()],
+    sleep_api::config::session_key(),
+);
+let app = sleep_api::tenant::router(registry);
+// axum::serve(listener, app).await?;
+# }
+```
+"#]
+pub struct TenantRegistry {
+    inner: Arc<Inner>,
+}
+
+impl TenantRegistry {
+    #[doc = r#"Create a registry serving `tenants` from `{data_dir}/{tenant}.sqlite`.
+
+Invalid tenant names are dropped with a warning.
+"#]
+    pub fn new(
+        mode: TenantMode,
+        data_dir: PathBuf,
+        tenants: impl IntoIterator<Item = String>,
+        master_key: impl Into<SessionKey>,
+    ) -> Self {
+        let allowed = tenants
+            .into_iter()
+            .filter(|t| {
+                let ok = is_valid_tenant_name(t);
+                if !ok {
+                    tracing::warn!(tenant = %t, "ignoring invalid tenant name");
+                }
+                ok
+            })
+            .collect();
+        TenantRegistry {
+            inner: Arc::new(Inner {
+                mode,
+                data_dir,
+                allowed,
+                master_key: master_key.into(),
+                tenants: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    #[doc = r#"Resolve the tenant for `req`, stripping the `/t/{tenant}` prefix in path mode.
+
+Returns `None` when no known tenant matches.
+"#]
+    pub fn resolve(&self, mut req: Request) -> Option<(String, Request)> {
+        let tenant = match self.inner.mode {
+            TenantMode::Subdomain => {
+                let host = req
+                    .headers()
+                    .get(header::HOST)
+                    .and_then(|h| h.to_str().ok())?;
+                let host = host.split(':').next().unwrap_or(host);
+                let (label, rest) = host.split_once('.')?;
+                if rest.is_empty() {
+                    return None;
+                }
+                label.to_ascii_lowercase()
+            }
+            TenantMode::PathPrefix => {
+                let path = req.uri().path().strip_prefix("/t/")?;
+                let (tenant, rest) = path.split_once('/').unwrap_or((path, ""));
+                let tenant = tenant.to_string();
+                let stripped = match req.uri().query() {
+                    Some(q) => format!("/{rest}?{q}"),
+                    None => format!("/{rest}"),
+                };
+                *req.uri_mut() = stripped.parse::<Uri>().ok()?;
+                tenant
+            }
+        };
+        self.inner
+            .allowed
+            .contains(&tenant)
+            .then_some((tenant, req))
+    }
+
+    /// Return the cached tenant, creating and migrating its database on first use.
+    async fn tenant(&self, name: &str) -> Result<Router, crate::error::Error> {
+        let mut tenants = self.inner.tenants.lock().await;
+        if let Some(t) = tenants.get(name) {
+            return Ok(t.clone());
+        }
+        std::fs::create_dir_all(&self.inner.data_dir).map_err(sqlx::Error::Io)?;
+        let path = self.inner.data_dir.join(format!("{name}.sqlite"));
+        let db = db::connect_file(&path).await?;
+        let now = crate::config::clock().now_utc();
+        let integrity = crate::integrity::startup_check(&db, now).await;
+        if !integrity.degraded() {
+            sqlx::migrate!("../migrations").run(&db).await?;
+            crate::jobs::spawn_scheduler(db.clone(), crate::config::clock());
+        }
+        tracing::info!(tenant = %name, path = %path.display(), "opened tenant database");
+
+        let router = crate::app::router_with_state(crate::app::AppState {
+            integrity,
+            ..crate::app::AppState::new(db, self.tenant_key(name))
+        });
+        tenants.insert(name.to_string(), router.clone());
+        Ok(router)
+    }
+
+    fn tenant_key(&self, name: &str) -> SessionKey {
+        self.inner
+            .master_key
+            .derive(&[b"tenant:".as_slice(), name.as_bytes()].concat())
+    }
+}
+
+#[doc = r#"Build the front router that dispatches every request to its tenant.
+
+Responses:
+- `404` `{code:"unknown_tenant"}` when the request names no configured tenant
+- `503` `{code:"tenant_unavailable"}` when the tenant database cannot be opened
+"#]
+pub fn router(registry: TenantRegistry) -> Router {
+    Router::new().fallback(dispatch).with_state(registry)
+}
+
+async fn dispatch(State(registry): State<TenantRegistry>, req: Request) -> Response {
+    let Some((name, req)) = registry.resolve(req) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"code": "unknown_tenant", "message": "Unknown tenant"})),
+        )
+            .into_response();
+    };
+    let router = match registry.tenant(&name).await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!(error = ?e, tenant = %name, "failed to open tenant database");
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "code": "tenant_unavailable",
+                    "message": "Tenant database unavailable"
+                })),
+            )
+                .into_response();
+        }
+    };
+    match CURRENT_TENANT.scope(name, router.oneshot(req)).await {
+        Ok(res) => res,
+        Err(never) => match never {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum_extra::extract::cookie::Key;
+
+    fn registry(mode: TenantMode) -> TenantRegistry {
+        TenantRegistry::new(
+            mode,
+            PathBuf::from("unused"),
+            ["alice".to_string(), "Bad Name".to_string()],
+            Key::generate(),
+        )
+    }
+
+    #[test]
+    fn resolves_subdomain() {
+        let reg = registry(TenantMode::Subdomain);
+        let req = Request::builder()
+            .uri("/api/health")
+            .header("Host", "Alice.sleep.example.com:8080")
+            .body(Body::empty())
+            .unwrap();
+        let (tenant, req) = reg.resolve(req).unwrap();
+        assert_eq!(tenant, "alice");
+        assert_eq!(req.uri().path(), "/api/health");
+
+        let req = Request::builder()
+            .uri("/")
+            .header("Host", "mallory.sleep.example.com")
+            .body(Body::empty())
+            .unwrap();
+        assert!(reg.resolve(req).is_none());
+    }
+
+    #[test]
+    fn resolves_and_strips_path_prefix() {
+        let reg = registry(TenantMode::PathPrefix);
+        let req = Request::builder()
+            .uri("/t/alice/api/sleep/range?from=2025-01-01&to=2025-01-07")
+            .body(Body::empty())
+            .unwrap();
+        let (tenant, req) = reg.resolve(req).unwrap();
+        assert_eq!(tenant, "alice");
+        assert_eq!(
+            req.uri().to_string(),
+            "/api/sleep/range?from=2025-01-01&to=2025-01-07"
+        );
+
+        let req = Request::builder()
+            .uri("/api/health")
+            .body(Body::empty())
+            .unwrap();
+        assert!(reg.resolve(req).is_none());
+    }
+
+    #[test]
+    fn tenant_names_and_env_vars() {
+        assert!(is_valid_tenant_name("bob-2"));
+        assert!(!is_valid_tenant_name("-bob"));
+        assert!(!is_valid_tenant_name("../etc"));
+        assert_eq!(
+            env_var_name("bob-2", "ADMIN_EMAIL"),
+            "TENANT_BOB_2_ADMIN_EMAIL"
+        );
+    }
+}
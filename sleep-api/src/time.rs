@@ -1,153 +0,0 @@
-#![doc = r#"Time handling utilities
-
-Provides DST-aware resolution and helpers for computing sleep durations
-using "wake-date" semantics. See [`compute_duration_min`].
-
-[`compute_duration_min`]: crate::time::compute_duration_min
-"#]
-
-use crate::domain::DomainError;
-use chrono::{
-    DateTime, Duration as ChronoDuration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
-    TimeZone, Utc,
-};
-use chrono_tz::Tz;
-
-/// Maximum minutes to scan forward to bridge DST "spring forward" gaps
-const MAX_DST_GAP_MINUTES: usize = 3 * 60;
-
-#[derive(Copy, Clone, Debug)]
-enum AmbiguousTimeChoice {
-    Earliest,
-    Latest,
-}
-
-/// Resolve a local naive datetime in a timezone to a concrete instant.
-///
-/// Handling of DST gaps/overlaps:
-/// - Ambiguous: pick the earliest or latest instant based on `choice`.
-/// - Non-existent (spring-forward gap): advance in 1-minute steps until valid (up to MAX_DST_GAP_MINUTES).
-///
-/// Fallback: If no valid local time can be resolved, falls back to interpreting the naive datetime as UTC with a warning. This may affect duration calculations across DST transitions.
-fn resolve_local(tz: Tz, ndt: NaiveDateTime, choice: AmbiguousTimeChoice) -> DateTime<Tz> {
-    match tz.from_local_datetime(&ndt) {
-        LocalResult::Single(dt) => dt,
-        LocalResult::Ambiguous(earliest, latest) => match choice {
-            AmbiguousTimeChoice::Earliest => earliest,
-            AmbiguousTimeChoice::Latest => latest,
-        },
-        LocalResult::None => {
-            // advance forward until it becomes valid
-            let mut cur = ndt;
-            for _ in 0..MAX_DST_GAP_MINUTES {
-                match tz.from_local_datetime(&cur) {
-                    LocalResult::Single(dt) => return dt,
-                    LocalResult::Ambiguous(earliest, latest) => {
-                        return match choice {
-                            AmbiguousTimeChoice::Earliest => earliest,
-                            AmbiguousTimeChoice::Latest => latest,
-                        };
-                    }
-                    LocalResult::None => {
-                        cur += ChronoDuration::minutes(1);
-                    }
-                }
-            }
-            // Fallback: interpret as UTC wall time then project to tz (best-effort)
-            tracing::warn!(
-                "resolve_local fallback: projecting naive datetime as UTC; tz={:?}, ndt={}",
-                tz,
-                ndt
-            );
-            tz.from_utc_datetime(&ndt)
-        }
-    }
-}
-
-#[doc = r#"Compute duration minutes using wake-date semantics in the provided timezone.
-
-Given a target `wake_date`, `bed_time` and `wake_time`, the bed datetime may belong to the
-previous calendar day when `bed_time > wake_time` (crossing midnight).
-
-DST handling:
-- Ambiguous local times (fall back) choose the earliest instant for bed times and the latest instant for wake times.
-- Non-existent times (spring forward gap) advance minute-by-minute until valid.
-
-# Example
-
-```rust
-# use sleep_api::domain::DomainError;
-# use chrono::{NaiveDate, NaiveTime};
-# use chrono_tz::Asia::Tokyo;
-# fn main() -> Result<(), DomainError> {
-// Cross-midnight: bed 23:00, wake 07:00 next day
-let mins = sleep_api::time::compute_duration_min(
-    NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
-    NaiveTime::from_hms_opt(23, 0, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
-    NaiveTime::from_hms_opt(7, 0, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
-    Tokyo,
-)?;
-assert_eq!(mins, 8 * 60);
-# Ok(()) }
-```
-
-# Errors
-
-Returns [`DomainError::InvalidInput`] if the computed duration is non-positive,
-exceeds `i32::MAX`, or if the computed bed date would underflow.
-
-[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
-"#]
-pub fn compute_duration_min(
-    wake_date: NaiveDate,
-    bed_time: NaiveTime,
-    wake_time: NaiveTime,
-    tz: Tz,
-) -> Result<i32, DomainError> {
-    let (bed_ndt, wake_ndt) = sleep_window_bounds(wake_date, bed_time, wake_time)?;
-
-    let bed_local = resolve_local(tz, bed_ndt, AmbiguousTimeChoice::Earliest);
-    let wake_local = resolve_local(tz, wake_ndt, AmbiguousTimeChoice::Latest);
-
-    let bed_utc = bed_local.with_timezone(&Utc);
-    let wake_utc = wake_local.with_timezone(&Utc);
-
-    let mins = (wake_utc - bed_utc).num_minutes();
-    if mins <= 0 {
-        return Err(DomainError::InvalidInput(
-            "Duration must be positive".into(),
-        ));
-    }
-    if mins > i32::MAX as i64 {
-        return Err(DomainError::InvalidInput("Duration too large".into()));
-    }
-    Ok(mins as i32)
-}
-
-#[doc = r#"Return the local bed/wake datetime bounds for a sleep session.
-
-Uses wake-date semantics: if `bed_time > wake_time`, the bed datetime is
-considered to be on the previous calendar day relative to `wake_date`.
-
-# Errors
-
-Returns [`DomainError::InvalidInput`] if the computed bed date would underflow.
-"#]
-pub fn sleep_window_bounds(
-    wake_date: NaiveDate,
-    bed_time: NaiveTime,
-    wake_time: NaiveTime,
-) -> Result<(NaiveDateTime, NaiveDateTime), DomainError> {
-    let bed_date = if bed_time > wake_time {
-        wake_date
-            .pred_opt()
-            .ok_or_else(|| DomainError::InvalidInput("invalid date (underflow)".into()))?
-    } else {
-        wake_date
-    };
-
-    Ok((
-        NaiveDateTime::new(bed_date, bed_time),
-        NaiveDateTime::new(wake_date, wake_time),
-    ))
-}
@@ -5,17 +5,37 @@ Aggregations over recorded sleep data, exposed as Axum handlers.
 Endpoints:
 - `GET /api/trends/sleep-bars`
 - `GET /api/trends/summary`
+- `GET /api/trends/personalization`
+- `GET /api/trends/routine`
+- `GET /api/trends/aids`
+- `GET /api/trends/tags`
+- `GET /api/trends/awakenings`
+- `GET /api/trends/correlation`
+- `GET /api/trends/compare`
+- `GET /api/trends/period-compare`
+- `GET /api/trends/decompose`
+- `GET /api/trends/context`
+
+Every endpoint except `personalization` also renders as CSV for `Accept: text/csv` or
+`?format=csv` (see [`crate::negotiate`]); the JSON body is flattened to one table.
 
 For HTTP examples, see `docs/api_examples.md` and the OpenAPI spec.
 "#]
 
+use crate::extract::DateRange;
+use crate::i18n::{DurationUnit, Lang, Locale, Rounding, Units, duration_hours, tr};
 use crate::middleware::auth_layer::RequireSessionJson;
-use crate::{db::Db, error::ApiError};
+use crate::models::{BodyMetric, Intensity, IntensityLevels};
+use crate::negotiate::{CsvTable, Negotiated, ResponseFormat, cell};
+use crate::stats::inference::{Correlation, Inference, SignificanceHint, compare_groups, pearson};
+use crate::time::SharedClock;
+use crate::{calendar, db::Db, error::ApiError};
 use axum::{
     Json,
     extract::{Query, State},
 };
-use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, Timelike, Weekday};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Sqlite};
 use std::collections::{BTreeMap, HashSet};
@@ -26,29 +46,40 @@ fn parse_date_field(s: &str, field: &str) -> Result<NaiveDate, ApiError> {
         .map_err(|_| ApiError::InvalidInput(format!("invalid {field} date")))
 }
 
-/// Helper to parse and validate a from/to date range (YYYY-MM-DD)
-fn parse_and_validate_date_range(from: &str, to: &str) -> Result<(NaiveDate, NaiveDate), ApiError> {
-    let from_date = parse_date_field(from, "from")?;
-    let to_date = parse_date_field(to, "to")?;
-    if to_date < from_date {
-        return Err(ApiError::InvalidInput("to must be >= from".into()));
-    }
-    Ok((from_date, to_date))
-}
-
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[doc = r#"Query parameters for trends endpoints.
 
-- `from`, `to`: inclusive date range `YYYY-MM-DD`.
+- `from`, `to`: inclusive date range `YYYY-MM-DD`, extracted separately as [`DateRange`].
 - `bucket`: optional `"day"` or `"week"` (summary only). Defaults to `"day"`.
+- `per`: optional `"day"` or `"segment"` (sleep-bars and summary only). Defaults to `"day"`.
+  With `"day"`, all segments waking on a date are combined (total sleep time); with
+  `"segment"`, each session is its own sample, so biphasic naps do not inflate nightly values.
 "#]
 pub struct RangeQuery {
-    pub from: String,
-    pub to: String,
     pub bucket: Option<String>, // day|week (for summary)
+    pub per: Option<String>,    // day|segment (for sleep-bars and summary)
+}
+
+/// Helper to validate the `per` aggregation toggle, returning `true` for per-segment samples.
+fn parse_per_segment(per: Option<&str>) -> Result<bool, ApiError> {
+    match per.unwrap_or("day") {
+        "day" => Ok(false),
+        "segment" => Ok(true),
+        _ => Err(ApiError::InvalidInput("per must be day or segment".into())),
+    }
 }
 
-#[derive(Serialize)]
+/// Per-session rows keyed by wake date, with the same column names as `v_daily_sleep`.
+const SEGMENTS_SQL: &str = r#"
+    SELECT COALESCE(s.session_date, s.date) AS wake_date, s.bed_time, s.wake_time,
+           m.latency_min, m.quality, m.duration_min, m.wake_feeling, m.sleep_inertia_min
+    FROM sleep_sessions s
+    JOIN sleep_metrics m ON m.session_id = s.id
+    WHERE COALESCE(s.session_date, s.date) BETWEEN ? AND ?
+    ORDER BY wake_date ASC, CASE WHEN s.bed_time > s.wake_time THEN 0 ELSE 1 END, s.bed_time ASC
+"#;
+
+#[derive(Serialize, JsonSchema)]
 #[doc = r#"Bar data point for per-day sleep: local bed/wake times, optional quality/duration."#]
 pub struct SleepBar {
     pub date: NaiveDate, // wake date
@@ -56,6 +87,8 @@ pub struct SleepBar {
     pub wake_time: NaiveTime,
     pub quality: Option<i32>,      // optional for coloring
     pub duration_min: Option<i32>, // optional
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_hours: Option<f64>, // when units are hours
 }
 
 #[derive(FromRow)]
@@ -69,7 +102,9 @@ struct SleepBarRow {
 
 #[doc = r#"Return per-day sleep bars over a date range.
 
-Validates the date range and fetches rows from the `v_daily_sleep` view.
+Validates the date range and fetches rows from the `v_daily_sleep` view. With `per=segment`,
+returns one bar per session instead (several bars may share a `date`). Bed and wake times are
+rounded to the time rounding preference.
 
 Examples:
 - HTTP usage: see `docs/api_examples.md` and the OpenAPI spec.
@@ -81,67 +116,164 @@ Errors:
 pub async fn sleep_bars(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Units(unit): Units,
+    Rounding(rounding): Rounding,
+    range: DateRange,
     Query(q): Query<RangeQuery>,
-) -> Result<Json<Vec<SleepBar>>, ApiError> {
-    let (from, to) = parse_and_validate_date_range(&q.from, &q.to)?;
+    format: ResponseFormat,
+) -> Result<Negotiated<Vec<SleepBar>>, ApiError> {
+    let DateRange { from, to } = range;
+    let per_segment = parse_per_segment(q.per.as_deref())?;
 
     // Pull from view; rely on server-computed duration_min
-    let rows = sqlx::query_as::<Sqlite, SleepBarRow>(
+    let sql = if per_segment {
+        SEGMENTS_SQL
+    } else {
         r#"
         SELECT wake_date, bed_time, wake_time, quality, duration_min
         FROM v_daily_sleep
         WHERE wake_date BETWEEN ? AND ?
         ORDER BY wake_date ASC
-        "#,
-    )
-    .bind(from)
-    .bind(to)
-    .fetch_all(&db)
-    .await?;
+        "#
+    };
+    let rows = sqlx::query_as::<Sqlite, SleepBarRow>(sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&db)
+        .await?;
 
     let out = rows
         .into_iter()
         .map(|r| SleepBar {
             date: r.wake_date,
-            bed_time: r.bed_time,
-            wake_time: r.wake_time,
+            bed_time: rounding.round(r.bed_time),
+            wake_time: rounding.round(r.wake_time),
             quality: r.quality,
             duration_min: r.duration_min,
+            duration_hours: duration_hours(unit, r.duration_min),
         })
         .collect();
 
-    Ok(Json(out))
+    Ok(format.render(out))
 }
 
-#[derive(Serialize, Clone)]
-#[doc = r#"Aggregated duration statistics per bucket (`bucket` is a date or ISO week)."#]
+#[derive(Serialize, Clone, JsonSchema)]
+#[doc = r#"Aggregated duration statistics per bucket (`bucket` is a date or ISO week).
+
+`p25_min`/`p75_min` are linearly interpolated quartiles; `sd_min` is the population standard
+deviation, `None` with fewer than two samples.
+"#]
 pub struct DurationBucket {
     pub bucket: String,
     pub avg_min: f64,
     pub min_min: i32,
     pub max_min: i32,
+    pub p25_min: f64,
+    pub p75_min: f64,
+    pub sd_min: Option<f64>,
 }
 
-#[derive(Serialize, Clone)]
-#[doc = r#"Average quality per bucket."#]
+#[derive(Serialize, Clone, JsonSchema)]
+#[doc = r#"Average quality per bucket; `counts[i]` is the number of samples with quality `i + 1`."#]
 pub struct QualityBucket {
     pub bucket: String,
     pub avg: f64,
+    pub counts: [usize; 5],
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 #[doc = r#"Median latency per bucket (computed via selection)."#]
 pub struct LatencyBucket {
     pub bucket: String,
     pub median: f64,
 }
 
-#[derive(Serialize)]
-#[doc = r#"Aggregated trends response combining duration, quality, and latency buckets."#]
+#[derive(Serialize, Clone, JsonSchema)]
+#[doc = r#"Average wake feeling and sleep inertia per bucket.
+
+Averages cover only days that reported the field; `days_reported` counts days with a
+`wake_feeling`. Both averages are `None` when no day in the bucket reported them.
+"#]
+pub struct WakeFeelingBucket {
+    pub bucket: String,
+    pub avg_feeling: Option<f64>,
+    pub avg_inertia_min: Option<f64>,
+    pub days_reported: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Split-sleep statistics per bucket, always computed per wake date.
+
+- `avg_segments`: sessions per day.
+- `avg_total_min`: total sleep time per day (sum of segments).
+- `avg_longest_min`: longest single segment per day.
+- `split_days`: days with more than one segment.
+"#]
+pub struct SegmentBucket {
+    pub bucket: String,
+    pub avg_segments: f64,
+    pub avg_total_min: f64,
+    pub avg_longest_min: f64,
+    pub split_days: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Nights in a bucket on which a medication was logged.
+
+A night counts once per medication however many intakes were logged for it.
+"#]
+pub struct MedicationBucket {
+    pub bucket: String,
+    pub medication: String,
+    pub nights: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Average daytime mood per bucket; `days` counts the rated days it covers."#]
+pub struct MoodBucket {
+    pub bucket: String,
+    pub avg_mood: f64,
+    pub days: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Sleep efficiency and wake after sleep onset per bucket, always per wake date.
+
+`avg_efficiency` weights each day's efficiency by its time in bed, like the daily value
+weights sessions. `avg_waso_min` averages the days that reported `waso_min` (`None` when none
+did). `days` counts the days with an efficiency.
+"#]
+pub struct EfficiencyBucket {
+    pub bucket: String,
+    pub avg_efficiency: f64,
+    pub avg_waso_min: Option<f64>,
+    pub days: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Aggregated trends response combining duration, quality, latency, wake feeling, and segment buckets.
+
+`per` echoes the sample unit used for the duration/quality/latency/wake feeling buckets.
+`goal_adherence_pct` is the share of logged days in the range whose total sleep met the sleep
+goal's `target_duration_min`, in percent (`None` without logged days); it is JSON-only.
+`medication_by_bucket` overlays logged medication use on the same buckets, ordered by bucket
+then medication name; it is JSON-only as well.
+`mood_by_bucket` averages the mood rated on the buckets' days (`POST /api/mood`); buckets
+without a rating are omitted. It is JSON-only too.
+`efficiency_by_bucket` carries sleep efficiency and WASO per bucket; buckets without an
+efficiency are omitted. It is JSON-only as well.
+"#]
 pub struct SummaryResponse {
+    pub per: &'static str,
+    pub goal_adherence_pct: Option<f64>,
     pub duration_by_bucket: Vec<DurationBucket>,
     pub quality_by_bucket: Vec<QualityBucket>,
     pub latency_by_bucket: Vec<LatencyBucket>,
+    pub wake_feeling_by_bucket: Vec<WakeFeelingBucket>,
+    pub segments_by_bucket: Vec<SegmentBucket>,
+    pub medication_by_bucket: Vec<MedicationBucket>,
+    pub mood_by_bucket: Vec<MoodBucket>,
+    pub efficiency_by_bucket: Vec<EfficiencyBucket>,
 }
 
 #[derive(FromRow)]
@@ -150,12 +282,147 @@ struct SummaryRow {
     duration_min: i32,
     quality: i32,
     latency_min: i32,
+    wake_feeling: Option<i32>,
+    sleep_inertia_min: Option<i32>,
+}
+
+type SummaryValues = (i32, i32, i32, Option<i32>, Option<i32>);
+
+#[derive(FromRow)]
+struct SegmentDayRow {
+    wake_date: NaiveDate,
+    duration_min: i32,
+    session_count: i32,
+    longest_segment_min: i32,
+}
+
+/// Helper to key a wake date by summary bucket (`day` → `YYYY-MM-DD`, `week` → `YYYY-Www`).
+fn bucket_key(date: NaiveDate, bucket: &str) -> String {
+    if bucket == "day" {
+        date.format("%Y-%m-%d").to_string()
+    } else {
+        // week: ISO week keyed to Monday; format "YYYY-Www"
+        let iw = date.iso_week();
+        format!("{:04}-W{:02}", iw.year(), iw.week())
+    }
+}
+
+fn segment_buckets(rows: &[SegmentDayRow], bucket: &str) -> Vec<SegmentBucket> {
+    let mut by_bucket: BTreeMap<String, Vec<&SegmentDayRow>> = BTreeMap::new();
+    for r in rows {
+        by_bucket
+            .entry(bucket_key(r.wake_date, bucket))
+            .or_default()
+            .push(r);
+    }
+    by_bucket
+        .into_iter()
+        .map(|(bucket, days)| {
+            let n = days.len() as f64;
+            let avg = |f: fn(&SegmentDayRow) -> i32| {
+                days.iter().map(|d| f64::from(f(d))).sum::<f64>() / n
+            };
+            SegmentBucket {
+                bucket,
+                avg_segments: avg(|d| d.session_count),
+                avg_total_min: avg(|d| d.duration_min),
+                avg_longest_min: avg(|d| d.longest_segment_min),
+                split_days: days.iter().filter(|d| d.session_count > 1).count(),
+            }
+        })
+        .collect()
+}
+
+fn medication_buckets(nights: &[(NaiveDate, String)], bucket: &str) -> Vec<MedicationBucket> {
+    let mut counts: BTreeMap<(String, &str), usize> = BTreeMap::new();
+    for (date, name) in nights {
+        *counts
+            .entry((bucket_key(*date, bucket), name.as_str()))
+            .or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|((bucket, medication), nights)| MedicationBucket {
+            bucket,
+            medication: medication.to_string(),
+            nights,
+        })
+        .collect()
+}
+
+fn mood_buckets(entries: &[crate::models::MoodEntry], bucket: &str) -> Vec<MoodBucket> {
+    let mut by_bucket: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+    for e in entries {
+        by_bucket
+            .entry(bucket_key(e.date, bucket))
+            .or_default()
+            .push(e.mood);
+    }
+    by_bucket
+        .into_iter()
+        .map(|(bucket, moods)| MoodBucket {
+            bucket,
+            avg_mood: moods.iter().map(|m| f64::from(*m)).sum::<f64>() / moods.len() as f64,
+            days: moods.len(),
+        })
+        .collect()
+}
+
+#[derive(FromRow)]
+struct EfficiencyDayRow {
+    wake_date: NaiveDate,
+    duration_min: i32,
+    efficiency: f64,
+    waso_min: Option<i32>,
+}
+
+fn efficiency_buckets(rows: &[EfficiencyDayRow], bucket: &str) -> Vec<EfficiencyBucket> {
+    let mut by_bucket: BTreeMap<String, Vec<&EfficiencyDayRow>> = BTreeMap::new();
+    for r in rows {
+        by_bucket
+            .entry(bucket_key(r.wake_date, bucket))
+            .or_default()
+            .push(r);
+    }
+    by_bucket
+        .into_iter()
+        .map(|(bucket, days)| {
+            let in_bed: f64 = days.iter().map(|d| f64::from(d.duration_min)).sum();
+            let asleep: f64 = days
+                .iter()
+                .map(|d| d.efficiency * f64::from(d.duration_min))
+                .sum();
+            EfficiencyBucket {
+                bucket,
+                avg_efficiency: (asleep / in_bed * 1000.0).round() / 1000.0,
+                avg_waso_min: mean_of_present(days.iter().map(|d| d.waso_min)).0,
+                days: days.len(),
+            }
+        })
+        .collect()
+}
+
+fn mean_of_present(values: impl Iterator<Item = Option<i32>>) -> (Option<f64>, usize) {
+    let present: Vec<i32> = values.flatten().collect();
+    if present.is_empty() {
+        return (None, 0);
+    }
+    let sum: i64 = present.iter().map(|v| *v as i64).sum();
+    (Some(sum as f64 / present.len() as f64), present.len())
 }
 
 #[doc = r#"Return aggregated summary statistics over a date range.
 
 When `bucket` is `"day"` (default), groups by date; when `"week"`, groups by ISO week (YYYY-Www).
 
+With `per=day` (default) each wake date is one sample: duration is total sleep time across
+segments, quality is the average. With `per=segment` each session is a sample. The
+`segments_by_bucket` series (segment count, total, longest segment) is per day in both modes.
+
+The aggregation runs in Rust by default; `TRENDS_SUMMARY_AGGREGATION` switches to the SQL-side
+rewrite or to shadow mode, which serves the Rust result and compares it against the SQL path on
+a sample of requests (see [`SummaryAggregation`]).
+
 Examples:
 - HTTP usage: see `docs/api_examples.md` and the OpenAPI spec.
 
@@ -166,19 +433,97 @@ Errors:
 pub async fn summary(
     State(db): State<Db>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange,
     Query(q): Query<RangeQuery>,
-) -> Result<Json<SummaryResponse>, ApiError> {
-    let (from, to) = parse_and_validate_date_range(&q.from, &q.to)?;
+    format: ResponseFormat,
+) -> Result<Negotiated<SummaryResponse>, ApiError> {
+    let DateRange { from, to } = range;
+
+    let bucket = match q.bucket.as_deref().unwrap_or("day") {
+        "day" => "day",
+        "week" => "week",
+        _ => return Err(ApiError::InvalidInput("bucket must be day or week".into())),
+    };
+    let per_segment = parse_per_segment(q.per.as_deref())?;
+
+    let mut response = match crate::config::trends_summary_aggregation() {
+        SummaryAggregation::Rust => summary_in_rust(&db, from, to, bucket, per_segment).await?,
+        SummaryAggregation::Sql => summary_in_sql(&db, from, to, bucket, per_segment).await?,
+        SummaryAggregation::Shadow => {
+            let primary = summary_in_rust(&db, from, to, bucket, per_segment).await?;
+            if shadow_sampled(crate::config::trends_summary_shadow_pct()) {
+                spawn_summary_shadow(db.clone(), from, to, bucket, per_segment, &primary);
+            }
+            primary
+        }
+    };
+    let goal = crate::repository::get_sleep_goal(&db).await;
+    response.goal_adherence_pct =
+        goal_adherence_pct(&db, from, to, goal.target_duration_min).await?;
+    let medication_nights = crate::repository::list_medication_nights_range(&db, from, to).await?;
+    response.medication_by_bucket = medication_buckets(&medication_nights, bucket);
+    let moods = crate::repository::list_mood_range(&db, from, to).await?;
+    response.mood_by_bucket = mood_buckets(&moods, bucket);
+    let efficiency_days = sqlx::query_as::<Sqlite, EfficiencyDayRow>(
+        "SELECT wake_date, duration_min, efficiency, waso_min FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? AND efficiency IS NOT NULL AND duration_min > 0 \
+         ORDER BY wake_date",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+    response.efficiency_by_bucket = efficiency_buckets(&efficiency_days, bucket);
+    Ok(format.render(response))
+}
 
-    let bucket = q.bucket.as_deref().unwrap_or("day");
-    if bucket != "day" && bucket != "week" {
-        return Err(ApiError::InvalidInput("bucket must be day or week".into()));
-    }
+/// Percent of logged wake dates in the range with at least `target_min` of total sleep.
+async fn goal_adherence_pct(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    target_min: i32,
+) -> Result<Option<f64>, ApiError> {
+    let (logged, met) = sqlx::query_as::<Sqlite, (i64, i64)>(
+        "SELECT COUNT(*), COALESCE(SUM(duration_min >= ?), 0) FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ?",
+    )
+    .bind(target_min)
+    .bind(from)
+    .bind(to)
+    .fetch_one(db)
+    .await?;
+    Ok((logged > 0).then(|| (met as f64 * 1000.0 / logged as f64).round() / 10.0))
+}
+
+/// Summary aggregated in Rust over per-day (or per-segment) rows.
+async fn summary_in_rust(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    bucket: &str,
+    per_segment: bool,
+) -> Result<SummaryResponse, sqlx::Error> {
+    // Pull per-day (or per-segment) rows; aggregate in Rust for day/week.
+    let sql = if per_segment {
+        SEGMENTS_SQL
+    } else {
+        r#"
+        SELECT wake_date, duration_min, quality, latency_min, wake_feeling, sleep_inertia_min
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#
+    };
+    let rows = sqlx::query_as::<Sqlite, SummaryRow>(sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(db)
+        .await?;
 
-    // Pull per-day rows; aggregate in Rust for day/week.
-    let rows = sqlx::query_as::<Sqlite, SummaryRow>(
+    let segment_days = sqlx::query_as::<Sqlite, SegmentDayRow>(
         r#"
-        SELECT wake_date, duration_min, quality, latency_min
+        SELECT wake_date, duration_min, session_count, longest_segment_min
         FROM v_daily_sleep
         WHERE wake_date BETWEEN ? AND ?
         ORDER BY wake_date ASC
@@ -186,47 +531,53 @@ pub async fn summary(
     )
     .bind(from)
     .bind(to)
-    .fetch_all(&db)
+    .fetch_all(db)
     .await?;
 
     // Group by bucket key
-    let mut by_bucket: BTreeMap<String, Vec<(i32, i32, i32)>> = BTreeMap::new();
+    let mut by_bucket: BTreeMap<String, Vec<SummaryValues>> = BTreeMap::new();
     for r in rows {
-        let key = if bucket == "day" {
-            r.wake_date.format("%Y-%m-%d").to_string()
-        } else {
-            // week: ISO week keyed to Monday; format "YYYY-Www"
-            let iw = r.wake_date.iso_week();
-            format!("{:04}-W{:02}", iw.year(), iw.week())
-        };
-        by_bucket
-            .entry(key)
-            .or_default()
-            .push((r.duration_min, r.quality, r.latency_min));
+        let key = bucket_key(r.wake_date, bucket);
+        by_bucket.entry(key).or_default().push((
+            r.duration_min,
+            r.quality,
+            r.latency_min,
+            r.wake_feeling,
+            r.sleep_inertia_min,
+        ));
     }
 
     let mut duration_buckets = Vec::new();
     let mut quality_buckets = Vec::new();
     let mut latency_buckets = Vec::new();
+    let mut wake_feeling_buckets = Vec::new();
 
     for (bucket_key, vals) in by_bucket {
         if vals.is_empty() {
             continue;
         }
         let count = vals.len();
+        let (avg_feeling, days_reported) = mean_of_present(vals.iter().map(|v| v.3));
+        let (avg_inertia_min, _) = mean_of_present(vals.iter().map(|v| v.4));
         let mut sum_dur = 0i64;
         let mut min_dur = i32::MAX;
         let mut max_dur = i32::MIN;
 
         let mut sum_quality = 0i64;
+        let mut quality_counts = [0usize; 5];
+        let mut durations = Vec::with_capacity(vals.len());
         let mut latencies = Vec::with_capacity(vals.len());
 
-        for (dur, qual, lat) in vals {
+        for (dur, qual, lat, _, _) in vals {
             sum_dur += dur as i64;
             min_dur = min_dur.min(dur);
             max_dur = max_dur.max(dur);
+            durations.push(dur as f64);
 
             sum_quality += qual as i64;
+            if let Some(c) = quality_counts.get_mut((qual - 1) as usize) {
+                *c += 1;
+            }
             latencies.push(lat);
         }
 
@@ -265,10 +616,20 @@ pub async fn summary(
             avg_min,
             min_min: min_dur,
             max_min: max_dur,
+            p25_min: percentile_linear(&durations, 0.25).unwrap_or(avg_min),
+            p75_min: percentile_linear(&durations, 0.75).unwrap_or(avg_min),
+            sd_min: std_dev(&durations),
         });
         quality_buckets.push(QualityBucket {
             bucket: bucket_key.clone(),
             avg: avg_quality,
+            counts: quality_counts,
+        });
+        wake_feeling_buckets.push(WakeFeelingBucket {
+            bucket: bucket_key.clone(),
+            avg_feeling,
+            avg_inertia_min,
+            days_reported,
         });
         latency_buckets.push(LatencyBucket {
             bucket: bucket_key,
@@ -276,14 +637,346 @@ pub async fn summary(
         });
     }
 
-    Ok(Json(SummaryResponse {
+    Ok(SummaryResponse {
+        per: if per_segment { "segment" } else { "day" },
+        goal_adherence_pct: None,
         duration_by_bucket: duration_buckets,
         quality_by_bucket: quality_buckets,
         latency_by_bucket: latency_buckets,
-    }))
+        wake_feeling_by_bucket: wake_feeling_buckets,
+        segments_by_bucket: segment_buckets(&segment_days, bucket),
+        medication_by_bucket: Vec::new(),
+        mood_by_bucket: Vec::new(),
+        efficiency_by_bucket: Vec::new(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = r#"Which implementation serves `GET /api/trends/summary` while the SQL-side aggregation
+rolls out (`TRENDS_SUMMARY_AGGREGATION`).
+
+- `rust` (default): aggregate per-day rows in Rust.
+- `sql`: aggregate in SQLite (`GROUP BY` bucket, window-function median).
+- `shadow`: serve the Rust result; on a sample of requests (`TRENDS_SUMMARY_SHADOW_PCT`) also
+  run the SQL path in the background and log any discrepancy at `warn`.
+
+Temporary: remove together with the Rust path once the SQL path has been verified.
+"#]
+pub enum SummaryAggregation {
+    Rust,
+    Sql,
+    Shadow,
+}
+
+impl std::str::FromStr for SummaryAggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "rust" => Ok(SummaryAggregation::Rust),
+            "sql" => Ok(SummaryAggregation::Sql),
+            "shadow" => Ok(SummaryAggregation::Shadow),
+            other => Err(format!("unknown summary aggregation {other:?}")),
+        }
+    }
+}
+
+/// Thursday of the ISO week containing `wake_date`; its year and ordinal give the ISO week.
+const ISO_THURSDAY_SQL: &str = "date(wake_date, '-' || ((CAST(strftime('%w', wake_date) AS INTEGER) + 6) % 7) || ' days', '+3 days')";
+
+/// SQL counterpart of [`bucket_key`], over `wake_date` and its ISO Thursday `th`.
+fn bucket_sql(bucket: &str) -> &'static str {
+    if bucket == "day" {
+        "wake_date"
+    } else {
+        "strftime('%Y', th) || '-W' || printf('%02d', (CAST(strftime('%j', th) AS INTEGER) - 1) / 7 + 1)"
+    }
+}
+
+#[derive(FromRow)]
+struct SqlSummaryBucket {
+    bucket: String,
+    avg_min: f64,
+    min_min: i32,
+    max_min: i32,
+    p25_min: f64,
+    p75_min: f64,
+    var_min: Option<f64>,
+    avg_quality: f64,
+    quality_1: i64,
+    quality_2: i64,
+    quality_3: i64,
+    quality_4: i64,
+    quality_5: i64,
+    median_latency: f64,
+    avg_feeling: Option<f64>,
+    avg_inertia_min: Option<f64>,
+    days_reported: i64,
+}
+
+#[derive(FromRow)]
+struct SqlSegmentBucket {
+    bucket: String,
+    avg_segments: f64,
+    avg_total_min: f64,
+    avg_longest_min: f64,
+    split_days: i64,
+}
+
+/// Summary aggregated by SQLite; must match [`summary_in_rust`].
+async fn summary_in_sql(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    bucket: &str,
+    per_segment: bool,
+) -> Result<SummaryResponse, sqlx::Error> {
+    let source = if per_segment {
+        SEGMENTS_SQL
+    } else {
+        r#"
+        SELECT wake_date, duration_min, quality, latency_min, wake_feeling, sleep_inertia_min
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        "#
+    };
+    let key = bucket_sql(bucket);
+    let sql = format!(
+        r#"
+        WITH src AS ({source}),
+        keyed AS (
+            SELECT {key} AS bucket, duration_min, quality, latency_min, wake_feeling,
+                   sleep_inertia_min
+            FROM (SELECT src.*, {ISO_THURSDAY_SQL} AS th FROM src)
+        ),
+        ranked AS (
+            SELECT bucket, latency_min,
+                   ROW_NUMBER() OVER (PARTITION BY bucket ORDER BY latency_min) AS rn,
+                   COUNT(*) OVER (PARTITION BY bucket) AS n
+            FROM keyed
+        ),
+        medians AS (
+            SELECT bucket, AVG(latency_min) AS median
+            FROM ranked
+            WHERE rn IN ((n + 1) / 2, (n + 2) / 2)
+            GROUP BY bucket
+        ),
+        durations AS (
+            SELECT bucket, duration_min,
+                   ROW_NUMBER() OVER (PARTITION BY bucket ORDER BY duration_min) - 1 AS i,
+                   COUNT(*) OVER (PARTITION BY bucket) AS n,
+                   AVG(duration_min) OVER (PARTITION BY bucket) AS mean
+            FROM keyed
+        ),
+        spread AS (
+            -- Quartiles interpolate between sorted positions (n - 1) * p and the next one.
+            SELECT bucket,
+                   SUM(CASE i WHEN (n - 1) / 4 THEN duration_min * (1 - ((n - 1) % 4) / 4.0)
+                              WHEN (n - 1) / 4 + 1 THEN duration_min * (((n - 1) % 4) / 4.0)
+                              ELSE 0 END) AS p25,
+                   SUM(CASE i WHEN 3 * (n - 1) / 4 THEN duration_min * (1 - (3 * (n - 1) % 4) / 4.0)
+                              WHEN 3 * (n - 1) / 4 + 1 THEN duration_min * ((3 * (n - 1) % 4) / 4.0)
+                              ELSE 0 END) AS p75,
+                   CASE WHEN COUNT(*) > 1
+                        THEN AVG((duration_min - mean) * (duration_min - mean)) END AS variance
+            FROM durations
+            GROUP BY bucket
+        )
+        SELECT k.bucket,
+               AVG(k.duration_min) AS avg_min,
+               MIN(k.duration_min) AS min_min,
+               MAX(k.duration_min) AS max_min,
+               s.p25 AS p25_min,
+               s.p75 AS p75_min,
+               s.variance AS var_min,
+               AVG(k.quality) AS avg_quality,
+               SUM(k.quality = 1) AS quality_1,
+               SUM(k.quality = 2) AS quality_2,
+               SUM(k.quality = 3) AS quality_3,
+               SUM(k.quality = 4) AS quality_4,
+               SUM(k.quality = 5) AS quality_5,
+               m.median AS median_latency,
+               AVG(k.wake_feeling) AS avg_feeling,
+               AVG(k.sleep_inertia_min) AS avg_inertia_min,
+               COUNT(k.wake_feeling) AS days_reported
+        FROM keyed k
+        JOIN medians m ON m.bucket = k.bucket
+        JOIN spread s ON s.bucket = k.bucket
+        GROUP BY k.bucket
+        ORDER BY k.bucket
+        "#
+    );
+    let rows = sqlx::query_as::<Sqlite, SqlSummaryBucket>(&sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(db)
+        .await?;
+
+    let segments_sql = format!(
+        r#"
+        WITH src AS (
+            SELECT wake_date, duration_min, session_count, longest_segment_min
+            FROM v_daily_sleep
+            WHERE wake_date BETWEEN ? AND ?
+        )
+        SELECT {key} AS bucket,
+               AVG(session_count) AS avg_segments,
+               AVG(duration_min) AS avg_total_min,
+               AVG(longest_segment_min) AS avg_longest_min,
+               SUM(session_count > 1) AS split_days
+        FROM (SELECT src.*, {ISO_THURSDAY_SQL} AS th FROM src)
+        GROUP BY bucket
+        ORDER BY bucket
+        "#
+    );
+    let segments = sqlx::query_as::<Sqlite, SqlSegmentBucket>(&segments_sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(db)
+        .await?;
+
+    let mut response = SummaryResponse {
+        per: if per_segment { "segment" } else { "day" },
+        goal_adherence_pct: None,
+        duration_by_bucket: Vec::with_capacity(rows.len()),
+        quality_by_bucket: Vec::with_capacity(rows.len()),
+        latency_by_bucket: Vec::with_capacity(rows.len()),
+        wake_feeling_by_bucket: Vec::with_capacity(rows.len()),
+        segments_by_bucket: segments
+            .into_iter()
+            .map(|r| SegmentBucket {
+                bucket: r.bucket,
+                avg_segments: r.avg_segments,
+                avg_total_min: r.avg_total_min,
+                avg_longest_min: r.avg_longest_min,
+                split_days: r.split_days as usize,
+            })
+            .collect(),
+        medication_by_bucket: Vec::new(),
+        mood_by_bucket: Vec::new(),
+        efficiency_by_bucket: Vec::new(),
+    };
+    for r in rows {
+        response.duration_by_bucket.push(DurationBucket {
+            bucket: r.bucket.clone(),
+            avg_min: r.avg_min,
+            min_min: r.min_min,
+            max_min: r.max_min,
+            p25_min: r.p25_min,
+            p75_min: r.p75_min,
+            sd_min: r.var_min.map(f64::sqrt),
+        });
+        response.quality_by_bucket.push(QualityBucket {
+            bucket: r.bucket.clone(),
+            avg: r.avg_quality,
+            counts: [
+                r.quality_1,
+                r.quality_2,
+                r.quality_3,
+                r.quality_4,
+                r.quality_5,
+            ]
+            .map(|c| c as usize),
+        });
+        response.wake_feeling_by_bucket.push(WakeFeelingBucket {
+            bucket: r.bucket.clone(),
+            avg_feeling: r.avg_feeling,
+            avg_inertia_min: r.avg_inertia_min,
+            days_reported: r.days_reported as usize,
+        });
+        response.latency_by_bucket.push(LatencyBucket {
+            bucket: r.bucket,
+            median: r.median_latency,
+        });
+    }
+    Ok(response)
+}
+
+static SHADOW_REQUESTS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Whether this request is in the shadowed `pct` percent (every request counts, in order).
+fn shadow_sampled(pct: u8) -> bool {
+    let n = SHADOW_REQUESTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    n % 100 < u64::from(pct)
+}
+
+/// Run the SQL path in the background and log how it differs from the served `primary`.
+fn spawn_summary_shadow(
+    db: Db,
+    from: NaiveDate,
+    to: NaiveDate,
+    bucket: &'static str,
+    per_segment: bool,
+    primary: &SummaryResponse,
+) {
+    let Ok(expected) = serde_json::to_value(primary) else {
+        return;
+    };
+    tokio::spawn(async move {
+        let candidate = match summary_in_sql(&db, from, to, bucket, per_segment).await {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!(error = %e, %from, %to, bucket, per_segment, "summary shadow query failed");
+                return;
+            }
+        };
+        let Ok(actual) = serde_json::to_value(&candidate) else {
+            return;
+        };
+        let mut diffs = Vec::new();
+        json_discrepancies("$", &expected, &actual, &mut diffs);
+        if diffs.is_empty() {
+            tracing::debug!(%from, %to, bucket, per_segment, "summary shadow matched");
+        } else {
+            tracing::warn!(%from, %to, bucket, per_segment, ?diffs, "summary shadow mismatch");
+        }
+    });
+}
+
+/// Collect paths where `actual` differs from `expected`; numbers match within a relative 1e-9.
+fn json_discrepancies(
+    path: &str,
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    out: &mut Vec<String>,
+) {
+    use serde_json::Value;
+    match (expected, actual) {
+        (Value::Number(a), Value::Number(b)) => {
+            let close = match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0),
+                _ => a == b,
+            };
+            if !close {
+                out.push(format!("{path}: {a} != {b}"));
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            if a.len() != b.len() {
+                out.push(format!("{path}: length {} != {}", a.len(), b.len()));
+                return;
+            }
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                json_discrepancies(&format!("{path}[{i}]"), x, y, out);
+            }
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: std::collections::BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                json_discrepancies(
+                    &format!("{path}.{key}"),
+                    a.get(key).unwrap_or(&Value::Null),
+                    b.get(key).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        _ if expected != actual => out.push(format!("{path}: {expected} != {actual}")),
+        _ => {}
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[doc = r#"Query parameters for personalization trends endpoint.
 
 - `window_days`: optional rolling window size in days. Defaults to 28.
@@ -294,7 +987,7 @@ pub struct PersonalizationQuery {
     pub to: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PersonalizationWindow {
     pub from: NaiveDate,
     pub to: NaiveDate,
@@ -303,7 +996,7 @@ pub struct PersonalizationWindow {
     pub missing_days_pct: f64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct DurationBaselineMetric {
     pub eligible: bool,
     pub sample_days: usize,
@@ -314,7 +1007,7 @@ pub struct DurationBaselineMetric {
     pub recent_out_of_range_incidence_pct: Option<f64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct DayTypeTimingBaselineMetric {
     pub eligible: bool,
     pub weekday_sample_days: usize,
@@ -327,7 +1020,7 @@ pub struct DayTypeTimingBaselineMetric {
     pub recent_14_day_diverges_from_baseline: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SocialJetlagMetric {
     pub eligible: bool,
     pub weekend_sample_days: usize,
@@ -336,7 +1029,7 @@ pub struct SocialJetlagMetric {
     pub sustained_two_windows: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ScheduleVariabilityMetric {
     pub eligible: bool,
     pub current_variability_min: Option<f64>,
@@ -345,13 +1038,13 @@ pub struct ScheduleVariabilityMetric {
     pub high_data_gap: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RankedQualityFactor {
     pub factor: String,
     pub effect: f64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct QualityFactorRankingMetric {
     pub eligible: bool,
     pub sessions_with_quality: usize,
@@ -360,7 +1053,7 @@ pub struct QualityFactorRankingMetric {
     pub ranked_factors: Vec<RankedQualityFactor>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PersonalizationMetrics {
     pub duration_baseline: DurationBaselineMetric,
     pub day_type_timing_baseline: DayTypeTimingBaselineMetric,
@@ -369,14 +1062,14 @@ pub struct PersonalizationMetrics {
     pub quality_factor_ranking: QualityFactorRankingMetric,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum RecommendationStatus {
     Recommended,
     Suppressed,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Confidence {
     High,
@@ -384,7 +1077,7 @@ pub enum Confidence {
     Low,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ActionRecommendation {
     pub action_key: String,
     pub status: RecommendationStatus,
@@ -393,7 +1086,7 @@ pub struct ActionRecommendation {
     pub suppression_reasons: Vec<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PersonalizationResponse {
     pub as_of: NaiveDate,
     pub window_days: i64,
@@ -444,6 +1137,7 @@ Errors:
 "#]
 pub async fn personalization(
     State(db): State<Db>,
+    State(clock): State<SharedClock>,
     RequireSessionJson { _user_id: _ }: RequireSessionJson,
     Query(q): Query<PersonalizationQuery>,
 ) -> Result<Json<PersonalizationResponse>, ApiError> {
@@ -456,9 +1150,25 @@ pub async fn personalization(
 
     let as_of = match q.to.as_deref() {
         Some(s) => parse_date_field(s, "to")?,
-        None => Utc::now().date_naive(),
+        None => clock.today_in_tz(chrono_tz::UTC),
     };
 
+    Ok(Json(personalization_as_of(&db, as_of, window_days).await?))
+}
+
+#[doc = r#"Compute the personalization response for the window ending at `as_of`.
+
+`window_days` must already be validated (1..=365).
+
+Errors:
+- Returns an API error when the windows fall outside the supported date range.
+- Returns an API error on database failures.
+"#]
+pub async fn personalization_as_of(
+    db: &Db,
+    as_of: NaiveDate,
+    window_days: i64,
+) -> Result<PersonalizationResponse, ApiError> {
     let current_from = as_of
         .checked_sub_signed(ChronoDuration::days(window_days - 1))
         .ok_or_else(|| ApiError::InvalidInput("invalid date range".into()))?;
@@ -479,7 +1189,7 @@ pub async fn personalization(
     )
     .bind(prior_from)
     .bind(as_of)
-    .fetch_all(&db)
+    .fetch_all(db)
     .await?;
 
     let samples: Vec<DaySample> = rows.into_iter().map(to_day_sample).collect();
@@ -505,14 +1215,14 @@ pub async fn personalization(
         window_days,
     );
 
-    Ok(Json(PersonalizationResponse {
+    Ok(PersonalizationResponse {
         as_of,
         window_days,
         current_window: calc.current_window,
         prior_window: calc.prior_window,
         metrics: calc.metrics,
         recommendations: calc.recommendations,
-    }))
+    })
 }
 
 fn to_day_sample(row: PersonalizationDailyRow) -> DaySample {
@@ -584,7 +1294,7 @@ fn median(values: &[f64]) -> Option<f64> {
     percentile_linear(values, 0.5)
 }
 
-fn std_dev(values: &[f64]) -> Option<f64> {
+pub(crate) fn std_dev(values: &[f64]) -> Option<f64> {
     if values.len() < 2 {
         return None;
     }
@@ -1195,47 +1905,2551 @@ fn build_recommendations(
     out
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Sleep outcomes for the nights following evenings in one group (item done / not done).
 
-    fn make_day_sample(
-        wake_date: NaiveDate,
-        weekend: bool,
-        midpoint_clock_min: f64,
-        quality: Option<f64>,
-    ) -> DaySample {
-        DaySample {
-            wake_date,
-            bed_clock_min: 23.0 * 60.0,
-            wake_clock_min: 7.0 * 60.0,
-            bed_relative_min: -60.0,
-            wake_relative_min: 7.0 * 60.0,
-            midpoint_clock_min: normalize_minutes(midpoint_clock_min),
-            duration_min: 8.0 * 60.0,
-            quality,
-            weekend,
-        }
-    }
+Averages come from `v_daily_sleep` for the wake date after each evening; `nights` counts
+evenings with a matching sleep entry.
+"#]
+pub struct RoutineOutcome {
+    pub nights: usize,
+    pub avg_quality: Option<f64>,
+    pub avg_duration_min: Option<f64>,
+    pub avg_wake_feeling: Option<f64>,
+}
 
-    #[test]
-    fn circular_midpoint_diff_wraps_across_midnight() {
-        let left = 23.0 * 60.0 + 55.0;
-        let right = 5.0;
-        assert_eq!(circular_minutes_diff(left, right), 10.0);
-        assert!(within_clock_minute_band(left, right, 15.0));
-        assert!(!within_clock_minute_band(left, right, 5.0));
-    }
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Adherence and outcome comparison for one checklist item."#]
+pub struct RoutineItemAdherence {
+    pub id: String,
+    pub label: String,
+    pub nights_recorded: usize,
+    pub nights_done: usize,
+    pub adherence_pct: Option<f64>,
+    pub done: RoutineOutcome,
+    pub not_done: RoutineOutcome,
+}
 
-    #[test]
-    fn midpoint_stability_treats_midnight_neighbors_as_stable() {
-        let prior_from = NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid date");
-        let prior_to = NaiveDate::from_ymd_opt(2025, 1, 28).expect("valid date");
-        let current_from = NaiveDate::from_ymd_opt(2025, 1, 29).expect("valid date");
-        let current_to = NaiveDate::from_ymd_opt(2025, 2, 25).expect("valid date");
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Routine adherence response for an inclusive range of evenings."#]
+pub struct RoutineTrendsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub items: Vec<RoutineItemAdherence>,
+}
 
-        let mut prior_samples = Vec::new();
-        let mut current_samples = Vec::new();
+#[derive(FromRow, Clone)]
+struct RoutineRow {
+    item_id: String,
+    done: bool,
+    quality: Option<i32>,
+    duration_min: Option<i32>,
+    wake_feeling: Option<i32>,
+}
+
+#[doc = r#"Return per-item routine adherence joined with the following night's sleep.
+
+`from`/`to` select evenings (the dates routines were recorded for). Only items on the current
+checklist are reported, in checklist order.
+
+Errors:
+- Returns an API error for invalid dates.
+- Returns an API error on database failures.
+"#]
+pub async fn routine(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange,
+    format: ResponseFormat,
+) -> Result<Negotiated<RoutineTrendsResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let checklist = crate::repository::get_routine_checklist(&db).await;
+
+    let rows = sqlx::query_as::<Sqlite, RoutineRow>(
+        r#"
+        SELECT r.item_id, r.done, v.quality, v.duration_min, v.wake_feeling
+        FROM routine_entries r
+        LEFT JOIN v_daily_sleep v ON v.wake_date = date(r.date, '+1 day')
+        WHERE r.date BETWEEN ? AND ?
+        ORDER BY r.date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    Ok(format.render(RoutineTrendsResponse {
+        from,
+        to,
+        items: routine_adherence(&checklist.items, &rows),
+    }))
+}
+
+fn routine_outcome<'a>(rows: impl Iterator<Item = &'a RoutineRow> + Clone) -> RoutineOutcome {
+    let avg = |values: Vec<f64>| {
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().sum::<f64>() / values.len() as f64)
+        }
+    };
+    RoutineOutcome {
+        nights: rows
+            .clone()
+            .filter(|r| r.quality.is_some() || r.duration_min.is_some())
+            .count(),
+        avg_quality: avg(rows
+            .clone()
+            .filter_map(|r| r.quality.map(f64::from))
+            .collect()),
+        avg_duration_min: avg(rows
+            .clone()
+            .filter_map(|r| r.duration_min.map(f64::from))
+            .collect()),
+        avg_wake_feeling: avg(rows.filter_map(|r| r.wake_feeling.map(f64::from)).collect()),
+    }
+}
+
+fn routine_adherence(
+    items: &[crate::models::RoutineItem],
+    rows: &[RoutineRow],
+) -> Vec<RoutineItemAdherence> {
+    items
+        .iter()
+        .map(|item| {
+            let item_rows = rows.iter().filter(|r| r.item_id == item.id);
+            let nights_recorded = item_rows.clone().count();
+            let nights_done = item_rows.clone().filter(|r| r.done).count();
+            RoutineItemAdherence {
+                id: item.id.clone(),
+                label: item.label.clone(),
+                nights_recorded,
+                nights_done,
+                adherence_pct: pct(nights_done, nights_recorded),
+                done: routine_outcome(item_rows.clone().filter(|r| r.done)),
+                not_done: routine_outcome(item_rows.filter(|r| !r.done)),
+            }
+        })
+        .collect()
+}
+
+/// Default minimum nights required in each group before an aid comparison is reported.
+const DEFAULT_AID_MIN_SAMPLES: usize = 5;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/aids`.
+
+- `from`, `to`: inclusive wake-date range `YYYY-MM-DD`, extracted separately as [`DateRange`].
+- `min_samples`: nights required in both the with-aid and without-aid groups before
+  differences are reported. Defaults to 5; must be at least 2.
+"#]
+pub struct AidsQuery {
+    pub min_samples: Option<usize>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Averages over a group of nights; fields are `None` when no night reported them."#]
+pub struct NightGroupStats {
+    pub nights: usize,
+    pub avg_quality: Option<f64>,
+    pub avg_duration_min: Option<f64>,
+    pub avg_latency_min: Option<f64>,
+    pub avg_wake_feeling: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"With-vs-without comparison for one sleep aid.
+
+`sufficient_sample` is `false` when either group has fewer than `min_samples` nights; the
+`*_diff` fields (with minus without) are then `None` so small samples are not over-read.
+`inference` holds effect size, p-value, bootstrap interval, and caveats per metric
+(`quality`, `duration_min`, `latency_min`, `wake_feeling`) regardless of `min_samples`.
+"#]
+pub struct AidEffect {
+    pub aid: String,
+    pub with_aid: NightGroupStats,
+    pub without_aid: NightGroupStats,
+    pub sufficient_sample: bool,
+    pub quality_diff: Option<f64>,
+    pub duration_diff_min: Option<f64>,
+    pub latency_diff_min: Option<f64>,
+    pub wake_feeling_diff: Option<f64>,
+    pub inference: BTreeMap<&'static str, Inference>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Sleep aid effectiveness response."#]
+pub struct AidsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub min_samples: usize,
+    pub aids: Vec<AidEffect>,
+}
+
+#[derive(FromRow, Clone)]
+struct AidNightRow {
+    wake_date: NaiveDate,
+    quality: Option<i32>,
+    duration_min: Option<i32>,
+    latency_min: Option<i32>,
+    wake_feeling: Option<i32>,
+}
+
+#[derive(FromRow)]
+struct AidUseRow {
+    wake_date: NaiveDate,
+    aid: String,
+}
+
+#[doc = r#"Compare nights with vs without each sleep aid over a wake-date range.
+
+A night counts as "with" an aid when any session waking that date used it. Aids are listed
+alphabetically and only those used at least once in the range are reported.
+
+Errors:
+- Returns an API error for invalid dates or `min_samples` below 2.
+- Returns an API error on database failures.
+"#]
+pub async fn aids(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange,
+    Query(q): Query<AidsQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<AidsResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let min_samples = q.min_samples.unwrap_or(DEFAULT_AID_MIN_SAMPLES);
+    if min_samples < 2 {
+        return Err(ApiError::InvalidInput(
+            "min_samples must be at least 2".into(),
+        ));
+    }
+
+    let nights = sqlx::query_as::<Sqlite, AidNightRow>(
+        r#"
+        SELECT wake_date, quality, duration_min, latency_min, wake_feeling
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let uses = sqlx::query_as::<Sqlite, AidUseRow>(
+        r#"
+        SELECT DISTINCT COALESCE(s.session_date, s.date) AS wake_date, a.aid
+        FROM sleep_aids a
+        JOIN sleep_sessions s ON s.id = a.session_id
+        WHERE COALESCE(s.session_date, s.date) BETWEEN ? AND ?
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let mut by_aid: BTreeMap<String, HashSet<NaiveDate>> = BTreeMap::new();
+    for u in uses {
+        by_aid.entry(u.aid).or_default().insert(u.wake_date);
+    }
+
+    Ok(format.render(AidsResponse {
+        from,
+        to,
+        min_samples,
+        aids: aid_effects(&nights, &by_aid, min_samples),
+    }))
+}
+
+fn night_group_stats<'a>(rows: impl Iterator<Item = &'a AidNightRow> + Clone) -> NightGroupStats {
+    let avg = |values: Vec<f64>| {
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().sum::<f64>() / values.len() as f64)
+        }
+    };
+    NightGroupStats {
+        nights: rows.clone().count(),
+        avg_quality: avg(rows
+            .clone()
+            .filter_map(|r| r.quality.map(f64::from))
+            .collect()),
+        avg_duration_min: avg(rows
+            .clone()
+            .filter_map(|r| r.duration_min.map(f64::from))
+            .collect()),
+        avg_latency_min: avg(rows
+            .clone()
+            .filter_map(|r| r.latency_min.map(f64::from))
+            .collect()),
+        avg_wake_feeling: avg(rows.filter_map(|r| r.wake_feeling.map(f64::from)).collect()),
+    }
+}
+
+/// Accessor for one metric on an aid night row.
+type AidMetric = fn(&AidNightRow) -> Option<i32>;
+
+/// Metrics with inference in [`AidEffect::inference`].
+const AID_METRICS: [(&str, AidMetric); 4] = [
+    ("quality", |r| r.quality),
+    ("duration_min", |r| r.duration_min),
+    ("latency_min", |r| r.latency_min),
+    ("wake_feeling", |r| r.wake_feeling),
+];
+
+fn aid_effects(
+    nights: &[AidNightRow],
+    by_aid: &BTreeMap<String, HashSet<NaiveDate>>,
+    min_samples: usize,
+) -> Vec<AidEffect> {
+    by_aid
+        .iter()
+        .map(|(aid, dates)| {
+            let with_aid =
+                night_group_stats(nights.iter().filter(|n| dates.contains(&n.wake_date)));
+            let without_aid =
+                night_group_stats(nights.iter().filter(|n| !dates.contains(&n.wake_date)));
+            let sufficient_sample =
+                with_aid.nights >= min_samples && without_aid.nights >= min_samples;
+            let diff = |a: Option<f64>, b: Option<f64>| match (sufficient_sample, a, b) {
+                (true, Some(a), Some(b)) => Some(a - b),
+                _ => None,
+            };
+            let (with_rows, without_rows): (Vec<&AidNightRow>, Vec<&AidNightRow>) =
+                nights.iter().partition(|n| dates.contains(&n.wake_date));
+            let inference = AID_METRICS
+                .iter()
+                .map(|(metric, get)| {
+                    let values = |rows: &[&AidNightRow]| -> Vec<f64> {
+                        rows.iter().filter_map(|r| get(r)).map(f64::from).collect()
+                    };
+                    (
+                        *metric,
+                        compare_groups(&values(&with_rows), &values(&without_rows)),
+                    )
+                })
+                .collect();
+            AidEffect {
+                aid: aid.clone(),
+                inference,
+                quality_diff: diff(with_aid.avg_quality, without_aid.avg_quality),
+                duration_diff_min: diff(with_aid.avg_duration_min, without_aid.avg_duration_min),
+                latency_diff_min: diff(with_aid.avg_latency_min, without_aid.avg_latency_min),
+                wake_feeling_diff: diff(with_aid.avg_wake_feeling, without_aid.avg_wake_feeling),
+                with_aid,
+                without_aid,
+                sufficient_sample,
+            }
+        })
+        .collect()
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/tags`.
+
+- `from`, `to`: inclusive wake-date range `YYYY-MM-DD`, extracted separately as [`DateRange`].
+- `min_samples`: nights required in both the tagged and untagged groups before differences
+  are reported. Defaults to 5; must be at least 2.
+"#]
+pub struct TagsQuery {
+    pub min_samples: Option<usize>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Tagged-vs-untagged comparison for one tag; fields as in [`AidEffect`]."#]
+pub struct TagEffect {
+    pub tag: String,
+    pub with_tag: NightGroupStats,
+    pub without_tag: NightGroupStats,
+    pub sufficient_sample: bool,
+    pub quality_diff: Option<f64>,
+    pub duration_diff_min: Option<f64>,
+    pub latency_diff_min: Option<f64>,
+    pub wake_feeling_diff: Option<f64>,
+    pub inference: BTreeMap<&'static str, Inference>,
+}
+
+impl From<AidEffect> for TagEffect {
+    fn from(e: AidEffect) -> Self {
+        TagEffect {
+            tag: e.aid,
+            with_tag: e.with_aid,
+            without_tag: e.without_aid,
+            sufficient_sample: e.sufficient_sample,
+            quality_diff: e.quality_diff,
+            duration_diff_min: e.duration_diff_min,
+            latency_diff_min: e.latency_diff_min,
+            wake_feeling_diff: e.wake_feeling_diff,
+            inference: e.inference,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Tag comparison response."#]
+pub struct TagsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub min_samples: usize,
+    pub tags: Vec<TagEffect>,
+}
+
+#[derive(FromRow)]
+struct TagUseRow {
+    wake_date: NaiveDate,
+    tag: String,
+}
+
+#[doc = r#"Compare nights with vs without each tag over a wake-date range.
+
+A night counts as tagged when any session waking that date, or any note dated that day,
+carries the tag. Tags are listed alphabetically and only those used in the range are
+reported. The split is computed like [`aids`].
+
+Errors:
+- Returns an API error for invalid dates or `min_samples` below 2.
+- Returns an API error on database failures.
+"#]
+pub async fn tags(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange,
+    Query(q): Query<TagsQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<TagsResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let min_samples = q.min_samples.unwrap_or(DEFAULT_AID_MIN_SAMPLES);
+    if min_samples < 2 {
+        return Err(ApiError::InvalidInput(
+            "min_samples must be at least 2".into(),
+        ));
+    }
+
+    let nights = sqlx::query_as::<Sqlite, AidNightRow>(
+        r#"
+        SELECT wake_date, quality, duration_min, latency_min, wake_feeling
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let uses = sqlx::query_as::<Sqlite, TagUseRow>(
+        r#"
+        SELECT COALESCE(s.session_date, s.date) AS wake_date, t.name AS tag
+        FROM sleep_session_tags st
+        JOIN sleep_sessions s ON s.id = st.session_id
+        JOIN tags t ON t.id = st.tag_id
+        WHERE COALESCE(s.session_date, s.date) BETWEEN ? AND ?
+        UNION
+        SELECT n.date AS wake_date, t.name AS tag
+        FROM note_tags nt
+        JOIN notes n ON n.id = nt.note_id
+        JOIN tags t ON t.id = nt.tag_id
+        WHERE n.date BETWEEN ? AND ?
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let mut by_tag: BTreeMap<String, HashSet<NaiveDate>> = BTreeMap::new();
+    for u in uses {
+        by_tag.entry(u.tag).or_default().insert(u.wake_date);
+    }
+
+    Ok(format.render(TagsResponse {
+        from,
+        to,
+        min_samples,
+        tags: aid_effects(&nights, &by_tag, min_samples)
+            .into_iter()
+            .map(TagEffect::from)
+            .collect(),
+    }))
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Awakenings and quality averaged over a group of nights."#]
+pub struct AwakeningsGroup {
+    pub nights: usize,
+    pub avg_awakenings: Option<f64>,
+    pub avg_quality: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Awakenings on nights with at least one disturbance of a given type.
+
+- `events` / `total_minutes`: disturbances of this type logged on recorded nights.
+"#]
+pub struct DisturbanceTypeAwakenings {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub nights: usize,
+    pub events: usize,
+    pub total_minutes: i64,
+    pub avg_awakenings: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Awakenings analysis split by external disturbances."#]
+pub struct AwakeningsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub disturbed: AwakeningsGroup,
+    pub undisturbed: AwakeningsGroup,
+    pub by_type: Vec<DisturbanceTypeAwakenings>,
+}
+
+#[derive(FromRow)]
+struct AwakeningsNightRow {
+    wake_date: NaiveDate,
+    awakenings: Option<i32>,
+    quality: Option<i32>,
+}
+
+#[derive(FromRow)]
+struct DisturbanceRow {
+    date: NaiveDate,
+    kind: String,
+    duration_min: i32,
+}
+
+#[doc = r#"Compare awakenings on nights with vs without logged disturbances.
+
+Only nights with recorded sleep are counted; disturbances logged for dates without sleep are
+ignored. `by_type` lists every disturbance type in a fixed order, including unused ones.
+
+Errors:
+- Returns an API error for invalid dates.
+- Returns an API error on database failures.
+"#]
+pub async fn awakenings(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange,
+    format: ResponseFormat,
+) -> Result<Negotiated<AwakeningsResponse>, ApiError> {
+    let DateRange { from, to } = range;
+
+    let nights = sqlx::query_as::<Sqlite, AwakeningsNightRow>(
+        r#"
+        SELECT wake_date, awakenings, quality
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let disturbances = sqlx::query_as::<Sqlite, DisturbanceRow>(
+        "SELECT date, kind, duration_min FROM disturbances WHERE date BETWEEN ? AND ?",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let (disturbed, undisturbed, by_type) = awakenings_by_disturbance(&nights, &disturbances);
+    Ok(format.render(AwakeningsResponse {
+        from,
+        to,
+        disturbed,
+        undisturbed,
+        by_type,
+    }))
+}
+
+fn awakenings_group<'a>(rows: impl Iterator<Item = &'a AwakeningsNightRow>) -> AwakeningsGroup {
+    let rows: Vec<_> = rows.collect();
+    AwakeningsGroup {
+        nights: rows.len(),
+        avg_awakenings: mean_of_present(rows.iter().map(|r| r.awakenings)).0,
+        avg_quality: mean_of_present(rows.iter().map(|r| r.quality)).0,
+    }
+}
+
+fn awakenings_by_disturbance(
+    nights: &[AwakeningsNightRow],
+    disturbances: &[DisturbanceRow],
+) -> (
+    AwakeningsGroup,
+    AwakeningsGroup,
+    Vec<DisturbanceTypeAwakenings>,
+) {
+    let recorded: HashSet<NaiveDate> = nights.iter().map(|n| n.wake_date).collect();
+    let disturbances: Vec<&DisturbanceRow> = disturbances
+        .iter()
+        .filter(|d| recorded.contains(&d.date))
+        .collect();
+    let disturbed_dates: HashSet<NaiveDate> = disturbances.iter().map(|d| d.date).collect();
+
+    let by_type = crate::models::DisturbanceKind::ALL
+        .iter()
+        .map(|kind| {
+            let kind = kind.to_string();
+            let events: Vec<_> = disturbances.iter().filter(|d| d.kind == kind).collect();
+            let dates: HashSet<NaiveDate> = events.iter().map(|d| d.date).collect();
+            let group = awakenings_group(nights.iter().filter(|n| dates.contains(&n.wake_date)));
+            DisturbanceTypeAwakenings {
+                kind,
+                nights: group.nights,
+                events: events.len(),
+                total_minutes: events.iter().map(|d| i64::from(d.duration_min)).sum(),
+                avg_awakenings: group.avg_awakenings,
+            }
+        })
+        .collect();
+
+    (
+        awakenings_group(
+            nights
+                .iter()
+                .filter(|n| disturbed_dates.contains(&n.wake_date)),
+        ),
+        awakenings_group(
+            nights
+                .iter()
+                .filter(|n| !disturbed_dates.contains(&n.wake_date)),
+        ),
+        by_type,
+    )
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/correlation`.
+
+- `from`, `to`: inclusive wake-date range `YYYY-MM-DD`, extracted separately as [`DateRange`].
+- `mode`: `"same_day"` (default) pairs each night with the exercise logged on its wake date, as
+  the UI logs them together; `"previous_day"` pairs it with the exercise of the day before,
+  i.e. the day that led into the night.
+- `body_metrics`: `true` adds the optional [`BodyMetricsCorrelation`] series.
+"#]
+pub struct CorrelationQuery {
+    pub mode: Option<String>,
+    pub body_metrics: Option<bool>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Averages over the nights paired with one exercise intensity.
+
+`rank` is the level's position in the configured [`IntensityLevels`] (0 = lowest), or `None`
+for a level that is no longer configured. `inference` compares these nights against every
+other paired night per metric (`quality`, `duration_min`, `wake_feeling`).
+"#]
+pub struct IntensityGroup {
+    pub intensity: String,
+    pub rank: Option<usize>,
+    pub nights: usize,
+    pub avg_quality: Option<f64>,
+    pub avg_duration_min: Option<f64>,
+    pub avg_wake_feeling: Option<f64>,
+    pub inference: BTreeMap<&'static str, Inference>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Body metrics versus sleep, over nights with a body reading dated their wake date.
+
+Readings are usually taken in the morning, so each one is paired with the night that ended
+that day. Nights without exercise count here too.
+"#]
+pub struct BodyMetricsCorrelation {
+    pub nights: usize,
+    pub avg_weight_kg: Option<f64>,
+    pub weight_quality: Correlation,
+    pub weight_duration_min: Correlation,
+    pub body_fat_quality: Correlation,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Exercise intensity versus sleep.
+
+- `nights`: nights in the range with a paired exercise intensity.
+- `groups`: one entry per intensity, lowest rank first.
+- `quality` / `duration_min` / `wake_feeling`: Pearson correlation between the intensity rank
+  and the night's quality, total sleep minutes or wake feeling, over the nights whose level is
+  ranked.
+- `body_metrics`: only with `?body_metrics=true`.
+"#]
+pub struct CorrelationResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub mode: &'static str,
+    pub nights: usize,
+    pub groups: Vec<IntensityGroup>,
+    pub quality: Correlation,
+    pub duration_min: Correlation,
+    pub wake_feeling: Correlation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_metrics: Option<BodyMetricsCorrelation>,
+}
+
+#[derive(FromRow)]
+struct CorrelationNightRow {
+    wake_date: NaiveDate,
+    quality: Option<i32>,
+    duration_min: Option<i32>,
+    wake_feeling: Option<i32>,
+}
+
+/// A night paired with the intensity of its exercise day.
+struct PairedNight<'a> {
+    intensity: &'a str,
+    rank: Option<usize>,
+    quality: Option<i32>,
+    duration_min: Option<i32>,
+    wake_feeling: Option<i32>,
+}
+
+/// Accessor for one metric on a paired night.
+type PairedMetric = fn(&PairedNight) -> Option<i32>;
+
+#[doc = r#"Correlate exercise intensity with the sleep that follows.
+
+Joins `v_daily_sleep` with the highest exercise intensity of each day (see
+[`repository::list_exercise_intensity`](crate::repository::list_exercise_intensity)) and
+groups nights by that intensity. Nights without logged exercise on the paired day are left
+out.
+
+Errors:
+- Returns an API error for invalid dates or an unknown `mode`.
+- Returns an API error on database failures.
+"#]
+pub async fn correlation(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange,
+    Query(q): Query<CorrelationQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<CorrelationResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let (mode, offset) = match q.mode.as_deref().unwrap_or("same_day") {
+        "same_day" => ("same_day", 0),
+        "previous_day" => ("previous_day", 1),
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "mode must be same_day or previous_day".into(),
+            ));
+        }
+    };
+
+    let nights = sqlx::query_as::<Sqlite, CorrelationNightRow>(
+        r#"
+        SELECT wake_date, quality, duration_min, wake_feeling
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+    let levels = crate::repository::get_intensity_levels(&db).await;
+    let exercise: BTreeMap<NaiveDate, String> = crate::repository::list_exercise_intensity(
+        &db,
+        from - ChronoDuration::days(offset),
+        to,
+        &levels,
+    )
+    .await?
+    .into_iter()
+    .map(|d| (d.date, d.intensity))
+    .collect();
+
+    let paired: Vec<PairedNight> = nights
+        .iter()
+        .filter_map(|n| {
+            let intensity = exercise.get(&(n.wake_date - ChronoDuration::days(offset)))?;
+            Some(PairedNight {
+                intensity,
+                rank: intensity_rank(&levels, intensity),
+                quality: n.quality,
+                duration_min: n.duration_min,
+                wake_feeling: n.wake_feeling,
+            })
+        })
+        .collect();
+
+    let body_metrics = if q.body_metrics.unwrap_or(false) {
+        let readings = crate::repository::list_body_metrics_range(&db, from, to).await?;
+        Some(body_metrics_correlation(&nights, &readings))
+    } else {
+        None
+    };
+
+    let metric = |get: PairedMetric| {
+        let (x, y): (Vec<f64>, Vec<f64>) = paired
+            .iter()
+            .filter_map(|p| Some((p.rank? as f64, f64::from(get(p)?))))
+            .unzip();
+        pearson(&x, &y)
+    };
+    Ok(format.render(CorrelationResponse {
+        from,
+        to,
+        mode,
+        nights: paired.len(),
+        quality: metric(|p| p.quality),
+        duration_min: metric(|p| p.duration_min),
+        wake_feeling: metric(|p| p.wake_feeling),
+        groups: intensity_groups(&paired),
+        body_metrics,
+    }))
+}
+
+fn body_metrics_correlation(
+    nights: &[CorrelationNightRow],
+    readings: &[BodyMetric],
+) -> BodyMetricsCorrelation {
+    let by_date: BTreeMap<NaiveDate, &BodyMetric> = readings.iter().map(|r| (r.date, r)).collect();
+    let paired: Vec<(&CorrelationNightRow, &BodyMetric)> = nights
+        .iter()
+        .filter_map(|n| Some((n, *by_date.get(&n.wake_date)?)))
+        .collect();
+    let series = |x: fn(&BodyMetric) -> Option<f64>, y: fn(&CorrelationNightRow) -> Option<i32>| {
+        let (x, y): (Vec<f64>, Vec<f64>) = paired
+            .iter()
+            .filter_map(|(n, b)| Some((x(b)?, f64::from(y(n)?))))
+            .unzip();
+        pearson(&x, &y)
+    };
+    let weights: Vec<f64> = paired.iter().filter_map(|(_, b)| b.weight_kg).collect();
+    BodyMetricsCorrelation {
+        nights: paired.len(),
+        avg_weight_kg: (!weights.is_empty())
+            .then(|| weights.iter().sum::<f64>() / weights.len() as f64),
+        weight_quality: series(|b| b.weight_kg, |n| n.quality),
+        weight_duration_min: series(|b| b.weight_kg, |n| n.duration_min),
+        body_fat_quality: series(|b| b.body_fat_pct, |n| n.quality),
+    }
+}
+
+fn intensity_rank(levels: &IntensityLevels, intensity: &str) -> Option<usize> {
+    levels.rank(&intensity.parse::<Intensity>().ok()?)
+}
+
+fn intensity_groups(paired: &[PairedNight]) -> Vec<IntensityGroup> {
+    let mut by_level: BTreeMap<(usize, &str), Vec<&PairedNight>> = BTreeMap::new();
+    for p in paired {
+        // Unconfigured levels sort after every ranked one.
+        let key = (p.rank.unwrap_or(usize::MAX), p.intensity);
+        by_level.entry(key).or_default().push(p);
+    }
+    let metrics: [(&'static str, PairedMetric); 3] = [
+        ("quality", |p| p.quality),
+        ("duration_min", |p| p.duration_min),
+        ("wake_feeling", |p| p.wake_feeling),
+    ];
+    by_level
+        .into_iter()
+        .map(|((_, intensity), nights)| {
+            let others: Vec<&PairedNight> =
+                paired.iter().filter(|p| p.intensity != intensity).collect();
+            let inference = metrics
+                .iter()
+                .map(|(metric, get)| {
+                    let values = |rows: &[&PairedNight]| -> Vec<f64> {
+                        rows.iter().filter_map(|r| get(r)).map(f64::from).collect()
+                    };
+                    (*metric, compare_groups(&values(&nights), &values(&others)))
+                })
+                .collect();
+            IntensityGroup {
+                intensity: intensity.to_string(),
+                rank: nights[0].rank,
+                nights: nights.len(),
+                avg_quality: mean_of_present(nights.iter().map(|n| n.quality)).0,
+                avg_duration_min: mean_of_present(nights.iter().map(|n| n.duration_min)).0,
+                avg_wake_feeling: mean_of_present(nights.iter().map(|n| n.wake_feeling)).0,
+                inference,
+            }
+        })
+        .collect()
+}
+
+/// Logged nights below which a compared period is flagged as a small sample.
+const MIN_COMPARE_NIGHTS: usize = 7;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/compare`.
+
+- `period`: `"month"` or `"year"`.
+- `anchor`: the current period, `YYYY-MM` for months or `YYYY` for years.
+"#]
+pub struct CompareQuery {
+    pub period: String,
+    pub anchor: String,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Averages over the logged nights of one period (`None` when nothing was reported)."#]
+pub struct PeriodStats {
+    pub label: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub nights: usize,
+    pub avg_duration_min: Option<f64>,
+    pub avg_quality: Option<f64>,
+    pub avg_latency_min: Option<f64>,
+    pub avg_awakenings: Option<f64>,
+    pub avg_wake_feeling: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"Change of one metric: current minus previous period, and current minus a year earlier."#]
+pub struct MetricDelta {
+    pub metric: &'static str,
+    pub current: Option<f64>,
+    pub vs_previous: Option<f64>,
+    pub vs_year_ago: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Period comparison response.
+
+`year_ago` is only present for `period=month` (for years it equals `previous`). `warnings`
+lists periods with fewer than 7 logged nights, whose deltas should be read with care.
+"#]
+pub struct CompareResponse {
+    pub period: String,
+    pub current: PeriodStats,
+    pub previous: PeriodStats,
+    pub year_ago: Option<PeriodStats>,
+    pub deltas: Vec<MetricDelta>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(FromRow)]
+struct CompareRow {
+    wake_date: NaiveDate,
+    duration_min: Option<i32>,
+    quality: Option<i32>,
+    latency_min: Option<i32>,
+    awakenings: Option<i32>,
+    wake_feeling: Option<i32>,
+}
+
+/// A labelled inclusive date range.
+type PeriodBounds = (String, NaiveDate, NaiveDate);
+
+/// Accessor for one metric on a daily row.
+type RowMetric = fn(&CompareRow) -> Option<i32>;
+
+/// Accessor for one compared metric.
+type PeriodMetric = fn(&PeriodStats) -> Option<f64>;
+
+fn month_bounds(year: i32, month: u32) -> Option<PeriodBounds> {
+    let from = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    Some((format!("{year:04}-{month:02}"), from, next.pred_opt()?))
+}
+
+fn year_bounds(year: i32) -> Option<PeriodBounds> {
+    Some((
+        format!("{year:04}"),
+        NaiveDate::from_ymd_opt(year, 1, 1)?,
+        NaiveDate::from_ymd_opt(year, 12, 31)?,
+    ))
+}
+
+async fn compare_rows(
+    db: &Db,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<CompareRow>, ApiError> {
+    Ok(sqlx::query_as::<Sqlite, CompareRow>(
+        r#"
+        SELECT wake_date, duration_min, quality, latency_min, awakenings, wake_feeling
+        FROM v_daily_sleep
+        WHERE wake_date BETWEEN ? AND ?
+        ORDER BY wake_date ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Current, previous, and (for months) year-ago bounds for `period`/`anchor`.
+fn compare_bounds(
+    period: &str,
+    anchor: &str,
+) -> Result<(PeriodBounds, PeriodBounds, Option<PeriodBounds>), ApiError> {
+    let invalid = || ApiError::InvalidInput(format!("invalid anchor for period {period}"));
+    match period {
+        "month" => {
+            let (y, m) = anchor.split_once('-').ok_or_else(invalid)?;
+            let year: i32 = y.parse().map_err(|_| invalid())?;
+            let month: u32 = m.parse().map_err(|_| invalid())?;
+            let current = month_bounds(year, month).ok_or_else(invalid)?;
+            let previous = if month == 1 {
+                month_bounds(year - 1, 12)
+            } else {
+                month_bounds(year, month - 1)
+            }
+            .ok_or_else(invalid)?;
+            let year_ago = month_bounds(year - 1, month).ok_or_else(invalid)?;
+            Ok((current, previous, Some(year_ago)))
+        }
+        "year" => {
+            let year: i32 = anchor.parse().map_err(|_| invalid())?;
+            let current = year_bounds(year).ok_or_else(invalid)?;
+            let previous = year_bounds(year - 1).ok_or_else(invalid)?;
+            Ok((current, previous, None))
+        }
+        _ => Err(ApiError::InvalidInput(
+            "period must be month or year".into(),
+        )),
+    }
+}
+
+fn period_stats(rows: &[CompareRow], (label, from, to): PeriodBounds) -> PeriodStats {
+    let rows: Vec<_> = rows
+        .iter()
+        .filter(|r| r.wake_date >= from && r.wake_date <= to)
+        .collect();
+    PeriodStats {
+        label,
+        from,
+        to,
+        nights: rows.len(),
+        avg_duration_min: mean_of_present(rows.iter().map(|r| r.duration_min)).0,
+        avg_quality: mean_of_present(rows.iter().map(|r| r.quality)).0,
+        avg_latency_min: mean_of_present(rows.iter().map(|r| r.latency_min)).0,
+        avg_awakenings: mean_of_present(rows.iter().map(|r| r.awakenings)).0,
+        avg_wake_feeling: mean_of_present(rows.iter().map(|r| r.wake_feeling)).0,
+    }
+}
+
+fn metric_deltas(
+    current: &PeriodStats,
+    previous: &PeriodStats,
+    year_ago: Option<&PeriodStats>,
+) -> Vec<MetricDelta> {
+    let metrics: [(&'static str, PeriodMetric); 5] = [
+        ("duration_min", |p| p.avg_duration_min),
+        ("quality", |p| p.avg_quality),
+        ("latency_min", |p| p.avg_latency_min),
+        ("awakenings", |p| p.avg_awakenings),
+        ("wake_feeling", |p| p.avg_wake_feeling),
+    ];
+    let diff = |a: Option<f64>, b: Option<f64>| Some(a? - b?);
+    metrics
+        .into_iter()
+        .map(|(metric, get)| MetricDelta {
+            metric,
+            current: get(current),
+            vs_previous: diff(get(current), get(previous)),
+            vs_year_ago: year_ago.and_then(|y| diff(get(current), get(y))),
+        })
+        .collect()
+}
+
+#[doc = r#"Compare a month or year with the previous one and, for months, the same month a year earlier.
+
+Errors:
+- Returns an API error for an unknown `period` or malformed `anchor`.
+- Returns an API error on database failures.
+"#]
+pub async fn compare(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Lang(locale): Lang,
+    Query(q): Query<CompareQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<CompareResponse>, ApiError> {
+    let (current, previous, year_ago) = compare_bounds(&q.period, &q.anchor)?;
+    let from = year_ago.as_ref().map_or(previous.1, |y| y.1);
+
+    let rows = compare_rows(&db, from, current.2).await?;
+
+    let current = period_stats(&rows, current);
+    let previous = period_stats(&rows, previous);
+    let year_ago = year_ago.map(|b| period_stats(&rows, b));
+
+    let warnings = std::iter::once(&current)
+        .chain(std::iter::once(&previous))
+        .chain(year_ago.as_ref())
+        .filter(|p| p.nights < MIN_COMPARE_NIGHTS)
+        .map(|p| {
+            tr(
+                locale,
+                "compare-few-nights",
+                &[("label", p.label.clone()), ("nights", p.nights.to_string())],
+            )
+        })
+        .collect();
+
+    Ok(format.render(CompareResponse {
+        period: q.period,
+        deltas: metric_deltas(&current, &previous, year_ago.as_ref()),
+        current,
+        previous,
+        year_ago,
+        warnings,
+    }))
+}
+
+/// Longest period accepted by `GET /api/trends/period-compare`.
+const MAX_PERIOD_COMPARE_DAYS: i64 = 730;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/period-compare`.
+
+- `a_from`, `a_to`: inclusive wake-date range of period A (`YYYY-MM-DD`), e.g. before a change.
+- `b_from`, `b_to`: inclusive wake-date range of period B, e.g. after it.
+
+Each period spans at most 730 days; the periods may have different lengths.
+"#]
+pub struct PeriodCompareQuery {
+    pub a_from: Option<String>,
+    pub a_to: Option<String>,
+    pub b_from: Option<String>,
+    pub b_to: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"One metric compared between two periods.
+
+`a` / `b` are the period averages and `diff` is `b - a`. `inference` tests the same
+difference (its `n_a` counts the nights of period B, `n_b` those of period A) and `hint`
+summarizes it (see [`SignificanceHint`]).
+"#]
+pub struct PeriodMetricComparison {
+    pub metric: &'static str,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+    pub diff: Option<f64>,
+    pub hint: SignificanceHint,
+    pub inference: Inference,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Response of `GET /api/trends/period-compare`.
+
+`warnings` lists periods with fewer than 7 logged nights and notes overlapping periods, whose
+shared nights count on both sides.
+"#]
+pub struct PeriodCompareResponse {
+    pub a: PeriodStats,
+    pub b: PeriodStats,
+    pub metrics: Vec<PeriodMetricComparison>,
+    pub warnings: Vec<String>,
+}
+
+/// Parse one period of `GET /api/trends/period-compare`, naming it in errors.
+fn parse_period(
+    name: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<PeriodBounds, ApiError> {
+    let DateRange { from, to } =
+        DateRange::<MAX_PERIOD_COMPARE_DAYS>::parse(from, to).map_err(|e| match e {
+            ApiError::InvalidInput(msg) => ApiError::InvalidInput(format!("period {name}: {msg}")),
+            other => other,
+        })?;
+    Ok((format!("{from}..{to}"), from, to))
+}
+
+fn period_metric_comparisons(a: &[CompareRow], b: &[CompareRow]) -> Vec<PeriodMetricComparison> {
+    let metrics: [(&'static str, RowMetric); 5] = [
+        ("duration_min", |r| r.duration_min),
+        ("quality", |r| r.quality),
+        ("latency_min", |r| r.latency_min),
+        ("awakenings", |r| r.awakenings),
+        ("wake_feeling", |r| r.wake_feeling),
+    ];
+    metrics
+        .into_iter()
+        .map(|(metric, get)| {
+            let values = |rows: &[CompareRow]| -> Vec<f64> {
+                rows.iter().filter_map(get).map(f64::from).collect()
+            };
+            let (va, vb) = (values(a), values(b));
+            let (a, _) = crate::stats::mean_sd(&va);
+            let (b, _) = crate::stats::mean_sd(&vb);
+            let inference = compare_groups(&vb, &va);
+            PeriodMetricComparison {
+                metric,
+                a,
+                b,
+                diff: b.zip(a).map(|(b, a)| b - a),
+                hint: inference.hint(),
+                inference,
+            }
+        })
+        .collect()
+}
+
+#[doc = r#"Compare two arbitrary wake-date periods side by side, e.g. before and after a new
+mattress or job, when the change does not line up with calendar months.
+
+Errors:
+- Returns an API error for missing or malformed dates, `from > to`, or a period longer than
+  730 days.
+- Returns an API error on database failures.
+"#]
+pub async fn period_compare(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Lang(locale): Lang,
+    Query(q): Query<PeriodCompareQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<PeriodCompareResponse>, ApiError> {
+    let a = parse_period("a", q.a_from.as_deref(), q.a_to.as_deref())?;
+    let b = parse_period("b", q.b_from.as_deref(), q.b_to.as_deref())?;
+    let overlap = a.1 <= b.2 && b.1 <= a.2;
+
+    let a_rows = compare_rows(&db, a.1, a.2).await?;
+    let b_rows = compare_rows(&db, b.1, b.2).await?;
+    let metrics = period_metric_comparisons(&a_rows, &b_rows);
+    let a = period_stats(&a_rows, a);
+    let b = period_stats(&b_rows, b);
+
+    let mut warnings: Vec<String> = [&a, &b]
+        .into_iter()
+        .filter(|p| p.nights < MIN_COMPARE_NIGHTS)
+        .map(|p| {
+            tr(
+                locale,
+                "compare-few-nights",
+                &[("label", p.label.clone()), ("nights", p.nights.to_string())],
+            )
+        })
+        .collect();
+    if overlap {
+        warnings.push(tr(locale, "compare-periods-overlap", &[]));
+    }
+
+    Ok(format.render(PeriodCompareResponse {
+        a,
+        b,
+        metrics,
+        warnings,
+    }))
+}
+
+/// Shortest range accepted by `GET /api/trends/decompose` (two full weeks).
+const MIN_DECOMPOSE_DAYS: i64 = 14;
+
+/// Longest range accepted by `GET /api/trends/decompose`.
+const MAX_DECOMPOSE_DAYS: i64 = 730;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/decompose`.
+
+- `metric`: `duration` | `quality` | `latency` | `awakenings` | `wake_feeling`.
+- `from`, `to`: inclusive wake-date range `YYYY-MM-DD`, 14..=730 days, extracted separately as
+  [`DateRange`].
+"#]
+pub struct DecomposeQuery {
+    pub metric: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Decomposition of one daily metric into trend, weekly seasonality, and residual.
+
+See [`crate::stats::decompose`] for the method.
+"#]
+pub struct DecomposeResponse {
+    pub metric: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(flatten)]
+    pub decomposition: crate::stats::decompose::Decomposition,
+}
+
+#[derive(FromRow)]
+struct DecomposeRow {
+    wake_date: NaiveDate,
+    value: Option<f64>,
+}
+
+#[doc = r#"Split a daily metric series into trend, weekly seasonality, and residual.
+
+Days without recorded sleep are kept in the series with `value: null`.
+
+Errors:
+- Returns an API error for invalid dates, an unknown `metric`, or a range outside 14..=730 days.
+- Returns an API error on database failures.
+"#]
+pub async fn decompose(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_DECOMPOSE_DAYS>,
+    Query(q): Query<DecomposeQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<DecomposeResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    // Column names come from this fixed list, never from user input.
+    let column = match q.metric.as_str() {
+        "duration" => "duration_min",
+        "quality" => "quality",
+        "latency" => "latency_min",
+        "awakenings" => "awakenings",
+        "wake_feeling" => "wake_feeling",
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "metric must be duration, quality, latency, awakenings, or wake_feeling".into(),
+            ));
+        }
+    };
+    let days = range.days();
+    if days < MIN_DECOMPOSE_DAYS {
+        return Err(ApiError::InvalidInput(format!(
+            "range must be >= {MIN_DECOMPOSE_DAYS} days"
+        )));
+    }
+
+    let sql = format!(
+        "SELECT wake_date, CAST({column} AS REAL) AS value FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC"
+    );
+    let rows = sqlx::query_as::<Sqlite, DecomposeRow>(&sql)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&db)
+        .await?;
+
+    let values: Vec<Option<f64>> =
+        calendar::fill_days(from, to, rows.into_iter().map(|r| (r.wake_date, r.value)))
+            .into_iter()
+            .map(|(_, value)| value.flatten())
+            .collect();
+
+    Ok(format.render(DecomposeResponse {
+        metric: q.metric,
+        from,
+        to,
+        decomposition: crate::stats::decompose::decompose_weekly(from, &values),
+    }))
+}
+
+/// Longest range accepted by `GET /api/trends/sleep-debt`.
+const MAX_SLEEP_DEBT_DAYS: i64 = 366;
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"One day of `GET /api/trends/sleep-debt`.
+
+`duration_min` and `delta_min` (duration minus target) are `None` for days without logged sleep;
+`balance_min` is the running sum of `delta_min` up to and including the day.
+"#]
+pub struct SleepDebtDay {
+    pub date: NaiveDate,
+    pub duration_min: Option<i64>,
+    pub delta_min: Option<i64>,
+    pub balance_min: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Cumulative sleep deficit and surplus against the sleep goal over a range.
+
+- `deficit_min`: summed shortfall of nights below `target_duration_min`.
+- `surplus_min`: summed excess of nights above it.
+- `balance_min`: `surplus_min - deficit_min`; negative means sleep debt.
+"#]
+pub struct SleepDebtResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub target_duration_min: i32,
+    pub nights_logged: usize,
+    pub deficit_min: i64,
+    pub surplus_min: i64,
+    pub balance_min: i64,
+    pub days: Vec<SleepDebtDay>,
+}
+
+#[doc = r#"Return the day-by-day sleep balance against the current sleep goal.
+
+Each logged wake date contributes its total sleep minus `target_duration_min`; days without
+logged sleep are listed with `null` values and leave the balance unchanged, so gaps in logging
+do not count as debt.
+
+Errors:
+- Returns an API error for invalid dates or a range longer than 366 days.
+- Returns an API error on database failures.
+"#]
+pub async fn sleep_debt(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    range: DateRange<MAX_SLEEP_DEBT_DAYS>,
+    format: ResponseFormat,
+) -> Result<Negotiated<SleepDebtResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let target = crate::repository::get_sleep_goal(&db)
+        .await
+        .target_duration_min;
+    let rows = sqlx::query_as::<Sqlite, (NaiveDate, i64)>(
+        "SELECT wake_date, duration_min FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+    let nights_logged = rows.len();
+    let days = debt_series(calendar::fill_days(from, to, rows), target);
+    let (deficit_min, surplus_min) = days
+        .iter()
+        .filter_map(|d| d.delta_min)
+        .fold((0, 0), |(deficit, surplus), delta| {
+            (deficit + (-delta).max(0), surplus + delta.max(0))
+        });
+
+    Ok(format.render(SleepDebtResponse {
+        from,
+        to,
+        target_duration_min: target,
+        nights_logged,
+        deficit_min,
+        surplus_min,
+        balance_min: surplus_min - deficit_min,
+        days,
+    }))
+}
+
+fn debt_series(days: Vec<(NaiveDate, Option<i64>)>, target_min: i32) -> Vec<SleepDebtDay> {
+    let mut balance_min = 0;
+    days.into_iter()
+        .map(|(date, duration_min)| {
+            let delta_min = duration_min.map(|d| d - i64::from(target_min));
+            balance_min += delta_min.unwrap_or(0);
+            SleepDebtDay {
+                date,
+                duration_min,
+                delta_min,
+                balance_min,
+            }
+        })
+        .collect()
+}
+
+/// Longest range accepted by `GET /api/trends/regularity`.
+const MAX_REGULARITY_DAYS: i64 = 366;
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Bedtime and wake time regularity of one ISO week (`YYYY-Www`).
+
+Fields are as in [`RegularityResponse`], over the week's logged nights."#]
+pub struct RegularityWeek {
+    pub week: String,
+    pub nights_logged: usize,
+    pub bedtime_mean: Option<NaiveTime>,
+    pub bedtime_sd_min: Option<f64>,
+    pub waketime_mean: Option<NaiveTime>,
+    pub waketime_sd_min: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"How regular bedtimes and wake times are over a range and per ISO week.
+
+Clock times are circular, so 23:30 and 00:30 are an hour apart rather than 23 hours:
+- `bedtime_mean` / `waketime_mean`: circular mean, to the minute; `None` without logged nights
+  or when the times are spread evenly around the clock.
+- `bedtime_sd_min` / `waketime_sd_min`: circular standard deviation in minutes (lower is more
+  regular); `None` with fewer than two nights.
+
+Each wake date counts once, with its earliest bed time and latest wake time. Those times and
+the means are rounded to the time rounding preference.
+"#]
+pub struct RegularityResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub nights_logged: usize,
+    pub bedtime_mean: Option<NaiveTime>,
+    pub bedtime_sd_min: Option<f64>,
+    pub waketime_mean: Option<NaiveTime>,
+    pub waketime_sd_min: Option<f64>,
+    pub weeks: Vec<RegularityWeek>,
+}
+
+#[doc = r#"Return bedtime and wake time regularity (circular mean and standard deviation).
+
+Weeks without logged nights are omitted from `weeks`.
+
+Errors:
+- Returns an API error for invalid dates or a range longer than 366 days.
+- Returns an API error on database failures.
+"#]
+pub async fn regularity(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Rounding(rounding): Rounding,
+    range: DateRange<MAX_REGULARITY_DAYS>,
+    format: ResponseFormat,
+) -> Result<Negotiated<RegularityResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let rows = sqlx::query_as::<Sqlite, (NaiveDate, NaiveTime, NaiveTime)>(
+        "SELECT wake_date, bed_time, wake_time FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let mut by_week: BTreeMap<String, Vec<(NaiveTime, NaiveTime)>> = BTreeMap::new();
+    for (date, bed, wake) in &rows {
+        by_week
+            .entry(bucket_key(*date, "week"))
+            .or_default()
+            .push((rounding.round(*bed), rounding.round(*wake)));
+    }
+    let weeks = by_week
+        .into_iter()
+        .map(|(week, nights)| {
+            let (bed, wake) = clock_regularity(&nights);
+            RegularityWeek {
+                week,
+                nights_logged: nights.len(),
+                bedtime_mean: bed.mean.map(|t| rounding.round(t)),
+                bedtime_sd_min: bed.sd_min,
+                waketime_mean: wake.mean.map(|t| rounding.round(t)),
+                waketime_sd_min: wake.sd_min,
+            }
+        })
+        .collect();
+    let nights: Vec<(NaiveTime, NaiveTime)> = rows
+        .iter()
+        .map(|r| (rounding.round(r.1), rounding.round(r.2)))
+        .collect();
+    let (bed, wake) = clock_regularity(&nights);
+
+    Ok(format.render(RegularityResponse {
+        from,
+        to,
+        nights_logged: nights.len(),
+        bedtime_mean: bed.mean.map(|t| rounding.round(t)),
+        bedtime_sd_min: bed.sd_min,
+        waketime_mean: wake.mean.map(|t| rounding.round(t)),
+        waketime_sd_min: wake.sd_min,
+        weeks,
+    }))
+}
+
+/// Circular mean and standard deviation of a set of clock times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ClockSpread {
+    mean: Option<NaiveTime>,
+    sd_min: Option<f64>,
+}
+
+/// Spread of the bed times and of the wake times of `nights`.
+fn clock_regularity(nights: &[(NaiveTime, NaiveTime)]) -> (ClockSpread, ClockSpread) {
+    let bed: Vec<f64> = nights.iter().map(|n| minutes_of_day(n.0) as f64).collect();
+    let wake: Vec<f64> = nights.iter().map(|n| minutes_of_day(n.1) as f64).collect();
+    (clock_spread(&bed), clock_spread(&wake))
+}
+
+/// Treat each time as an angle on a 24 hour clock: the mean is the direction of the summed unit
+/// vectors and the standard deviation is `sqrt(-2 ln R)` for the mean resultant length `R`,
+/// converted back to minutes. For tight clusters this matches the ordinary standard deviation.
+fn clock_spread(minutes: &[f64]) -> ClockSpread {
+    use std::f64::consts::TAU;
+    let day = 24.0 * 60.0;
+    let n = minutes.len() as f64;
+    let (sin, cos) = minutes.iter().fold((0.0, 0.0), |(s, c), m| {
+        let angle = m / day * TAU;
+        (s + angle.sin(), c + angle.cos())
+    });
+    let r = (sin / n).hypot(cos / n);
+    // Below this the times cancel out and there is no meaningful mean direction.
+    if minutes.is_empty() || r < 1e-9 {
+        return ClockSpread {
+            mean: None,
+            sd_min: None,
+        };
+    }
+    let mean_min = normalize_minutes((sin.atan2(cos) / TAU * day).round()) as u32;
+    let sd_min = (minutes.len() >= 2).then(|| {
+        // `abs` turns the -0.0 of identical times into 0.0.
+        let sd = (-2.0 * r.min(1.0).ln()).abs().sqrt() / TAU * day;
+        (sd * 10.0).round() / 10.0
+    });
+    ClockSpread {
+        mean: NaiveTime::from_hms_opt(mean_min / 60, mean_min % 60, 0),
+        sd_min,
+    }
+}
+
+/// Longest range accepted by `GET /api/trends/histogram`.
+const MAX_HISTOGRAM_DAYS: i64 = 366;
+
+/// Bin width used by `GET /api/trends/histogram` when no `bin_min` is given.
+const DEFAULT_HISTOGRAM_BIN_MIN: u32 = 30;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/histogram`.
+
+- `field`: `bed_time` | `wake_time` | `duration`.
+- `bin_min`: bin width in minutes, 5..=240 and dividing 24 hours evenly; defaults to 30.
+- `from`, `to`: inclusive wake-date range `YYYY-MM-DD`, up to 366 days, extracted separately as
+  [`DateRange`].
+"#]
+pub struct HistogramQuery {
+    pub field: String,
+    pub bin_min: Option<u32>,
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+#[doc = r#"One histogram bin covering `[start_min, end_min)`.
+
+For clock times the bounds are minutes after midnight and `label` reads `HH:MM-HH:MM`; the last
+bin before midnight ends at 1440. For durations they are minutes of sleep and `label` reads
+`start-end`."#]
+pub struct HistogramBin {
+    pub start_min: u32,
+    pub end_min: u32,
+    pub label: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Distribution of bed times, wake times, or durations over a range.
+
+Each wake date counts once, with its earliest bed time, latest wake time and total duration.
+`bins` is empty when no night is logged. Clock time histograms cover the whole day but start
+twelve hours away from the circular mean (at midnight when there is none), so a cluster around
+midnight stays in one piece; duration histograms run from the shortest to the longest night,
+keeping empty bins in between. Clock times are rounded to the time rounding preference before
+they are binned.
+"#]
+pub struct HistogramResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub field: String,
+    pub bin_min: u32,
+    pub nights_logged: usize,
+    pub bins: Vec<HistogramBin>,
+}
+
+#[doc = r#"Return binned counts of bed times, wake times, or durations.
+
+Errors:
+- Returns an API error for invalid dates, a range longer than 366 days, an unknown `field`, or
+  a `bin_min` outside 5..=240 or not dividing 24 hours.
+- Returns an API error on database failures.
+"#]
+pub async fn histogram(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Rounding(rounding): Rounding,
+    range: DateRange<MAX_HISTOGRAM_DAYS>,
+    Query(q): Query<HistogramQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<HistogramResponse>, ApiError> {
+    let DateRange { from, to } = range;
+    let bin_min = q.bin_min.unwrap_or(DEFAULT_HISTOGRAM_BIN_MIN);
+    if !(5..=240).contains(&bin_min) || 1440 % bin_min != 0 {
+        return Err(ApiError::InvalidInput(
+            "bin_min must be between 5 and 240 and divide 1440".into(),
+        ));
+    }
+    // Column names come from this fixed list, never from user input.
+    let column = match q.field.as_str() {
+        "bed_time" => "bed_time",
+        "wake_time" => "wake_time",
+        "duration" => "duration_min",
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "field must be bed_time, wake_time, or duration".into(),
+            ));
+        }
+    };
+
+    let sql = format!(
+        "SELECT {column} FROM v_daily_sleep \
+         WHERE wake_date BETWEEN ? AND ? ORDER BY wake_date ASC"
+    );
+    let (nights_logged, bins) = if column == "duration_min" {
+        let minutes = sqlx::query_scalar::<Sqlite, i64>(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&db)
+            .await?;
+        (minutes.len(), duration_bins(&minutes, bin_min))
+    } else {
+        let times = sqlx::query_scalar::<Sqlite, NaiveTime>(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&db)
+            .await?;
+        let minutes: Vec<u32> = times
+            .iter()
+            .map(|t| minutes_of_day(rounding.round(*t)) as u32)
+            .collect();
+        (minutes.len(), clock_bins(&minutes, bin_min))
+    };
+
+    Ok(format.render(HistogramResponse {
+        from,
+        to,
+        field: q.field,
+        bin_min,
+        nights_logged,
+        bins,
+    }))
+}
+
+/// Bin clock times (minutes after midnight) over the whole day, starting the axis opposite the
+/// circular mean so times around midnight land next to each other.
+fn clock_bins(minutes: &[u32], bin_min: u32) -> Vec<HistogramBin> {
+    if minutes.is_empty() {
+        return Vec::new();
+    }
+    let n_bins = 1440 / bin_min;
+    let mut counts = vec![0usize; n_bins as usize];
+    for m in minutes {
+        counts[(m % 1440 / bin_min) as usize] += 1;
+    }
+    let as_f64: Vec<f64> = minutes.iter().map(|m| f64::from(*m)).collect();
+    let first = clock_spread(&as_f64)
+        .mean
+        .map(|mean| (minutes_of_day(mean) as u32 + 720) % 1440 / bin_min)
+        .unwrap_or(0);
+    let clock = |m: u32| format!("{:02}:{:02}", m / 60 % 24, m % 60);
+    (0..n_bins)
+        .map(|i| {
+            let bin = (first + i) % n_bins;
+            let start_min = bin * bin_min;
+            let end_min = start_min + bin_min;
+            HistogramBin {
+                start_min,
+                end_min,
+                label: format!("{}-{}", clock(start_min), clock(end_min)),
+                count: counts[bin as usize],
+            }
+        })
+        .collect()
+}
+
+/// Bin durations from the bin holding the shortest to the one holding the longest.
+fn duration_bins(minutes: &[i64], bin_min: u32) -> Vec<HistogramBin> {
+    let (Some(min), Some(max)) = (minutes.iter().min(), minutes.iter().max()) else {
+        return Vec::new();
+    };
+    let width = i64::from(bin_min);
+    let first = min.max(&0) / width;
+    let last = max.max(&0) / width;
+    let mut counts = vec![0usize; (last - first + 1) as usize];
+    for m in minutes {
+        counts[(m.max(&0) / width - first) as usize] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let start_min = ((first + i as i64) * width) as u32;
+            let end_min = start_min + bin_min;
+            HistogramBin {
+                start_min,
+                end_min,
+                label: format!("{start_min}-{end_min}"),
+                count,
+            }
+        })
+        .collect()
+}
+
+/// Age bracket used by `GET /api/trends/context` when no `age` is given.
+const DEFAULT_CONTEXT_AGE: u32 = 30;
+
+#[derive(Deserialize, JsonSchema)]
+#[doc = r#"Query parameters for `GET /api/trends/context`.
+
+- `from`, `to`: inclusive wake-date range `YYYY-MM-DD`, extracted separately as [`DateRange`].
+- `age`: optional age in years (14 or older) selecting the reference bracket; defaults to the
+  adult 26–64 bracket.
+"#]
+pub struct ContextQuery {
+    pub age: Option<u32>,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[doc = r#"A metric placed in population context with a human-readable summary."#]
+pub struct ContextMetric {
+    #[serde(flatten)]
+    pub context: crate::stats::reference::MetricContext,
+    pub message: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[doc = r#"Population context for the average duration and latency over a range.
+
+Metrics are `None` when no night in the range was logged. Reference data is bundled and
+approximate; see [`crate::stats::reference`].
+"#]
+pub struct ContextResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub age_bracket: &'static str,
+    pub nights: usize,
+    pub duration: Option<ContextMetric>,
+    pub latency: Option<ContextMetric>,
+}
+
+#[derive(FromRow)]
+struct ContextRow {
+    duration_min: Option<i32>,
+    latency_min: Option<i32>,
+}
+
+/// Format hours with at most one decimal, dropping a trailing `.0` (`7`, `6.4`).
+fn fmt_hours(min: f64) -> String {
+    let h = (min / 60.0 * 10.0).round() / 10.0;
+    if h.fract() == 0.0 {
+        format!("{h:.0}")
+    } else {
+        format!("{h:.1}")
+    }
+}
+
+fn context_message(
+    locale: Locale,
+    unit: DurationUnit,
+    metric: &str,
+    ctx: &crate::stats::reference::MetricContext,
+    bracket: &str,
+) -> String {
+    use crate::stats::reference::RangePosition;
+    let position = match ctx.position {
+        RangePosition::Below => "below",
+        RangePosition::Within => "within",
+        RangePosition::Above => "above",
+    };
+    match metric {
+        "duration" => {
+            let (fmt, unit_id): (fn(f64) -> String, _) = match unit {
+                DurationUnit::Hours => (fmt_hours, "unit-hours"),
+                DurationUnit::Minutes => (|min| format!("{min:.0}"), "unit-minutes"),
+            };
+            tr(
+                locale,
+                "context-duration",
+                &[
+                    ("avg", fmt(ctx.your_avg)),
+                    ("unit", tr(locale, unit_id, &[])),
+                    ("position", position.into()),
+                    ("low", fmt(ctx.typical_low)),
+                    ("high", fmt(ctx.typical_high)),
+                    ("bracket", bracket.into()),
+                ],
+            )
+        }
+        _ => tr(
+            locale,
+            "context-latency",
+            &[
+                ("avg", format!("{:.0}", ctx.your_avg)),
+                ("position", position.into()),
+                ("low", format!("{:.0}", ctx.typical_low)),
+                ("high", format!("{:.0}", ctx.typical_high)),
+            ],
+        ),
+    }
+}
+
+#[doc = r#"Place average duration and latency within bundled age-bracket reference data.
+
+Errors:
+- Returns an API error for invalid dates or an `age` below the youngest bracket (14).
+- Returns an API error on database failures.
+"#]
+pub async fn context(
+    State(db): State<Db>,
+    RequireSessionJson { _user_id: _ }: RequireSessionJson,
+    Lang(locale): Lang,
+    Units(unit): Units,
+    range: DateRange,
+    Query(q): Query<ContextQuery>,
+    format: ResponseFormat,
+) -> Result<Negotiated<ContextResponse>, ApiError> {
+    use crate::stats::reference::{bracket_for_age, place};
+
+    let DateRange { from, to } = range;
+    let bracket = bracket_for_age(q.age.unwrap_or(DEFAULT_CONTEXT_AGE))
+        .ok_or_else(|| ApiError::InvalidInput("age must be 14 or older".into()))?;
+
+    let rows = sqlx::query_as::<Sqlite, ContextRow>(
+        "SELECT duration_min, latency_min FROM v_daily_sleep WHERE wake_date BETWEEN ? AND ?",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db)
+    .await?;
+
+    let metric = |name: &str, avg: Option<f64>, reference| {
+        avg.map(|avg| {
+            let context = place(avg, reference);
+            ContextMetric {
+                message: context_message(locale, unit, name, &context, bracket.label),
+                context,
+            }
+        })
+    };
+    let (avg_duration, _) = mean_of_present(rows.iter().map(|r| r.duration_min));
+    let (avg_latency, _) = mean_of_present(rows.iter().map(|r| r.latency_min));
+
+    Ok(format.render(ContextResponse {
+        from,
+        to,
+        age_bracket: bracket.label,
+        nights: rows.len(),
+        duration: metric("duration", avg_duration, &bracket.duration_min),
+        latency: metric("latency", avg_latency, &bracket.latency_min),
+    }))
+}
+
+// CSV renderings for `?format=csv` / `Accept: text/csv` (see [`crate::negotiate`]).
+// Each response is flattened to one table; nested statistics become prefixed columns.
+
+impl CsvTable for Vec<SleepBar> {
+    const HEADER: &'static [&'static str] = &[
+        "date",
+        "bed_time",
+        "wake_time",
+        "quality",
+        "duration_min",
+        "duration_hours",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.iter()
+            .map(|b| {
+                vec![
+                    b.date.to_string(),
+                    b.bed_time.to_string(),
+                    b.wake_time.to_string(),
+                    cell(b.quality),
+                    cell(b.duration_min),
+                    cell(b.duration_hours),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One row per bucket, joining every summary series on its bucket key.
+impl CsvTable for SummaryResponse {
+    const HEADER: &'static [&'static str] = &[
+        "bucket",
+        "avg_duration_min",
+        "min_duration_min",
+        "max_duration_min",
+        "avg_quality",
+        "median_latency_min",
+        "avg_wake_feeling",
+        "avg_inertia_min",
+        "wake_feeling_days",
+        "avg_segments",
+        "avg_total_min",
+        "avg_longest_min",
+        "split_days",
+        "p25_duration_min",
+        "p75_duration_min",
+        "sd_duration_min",
+        "quality_1",
+        "quality_2",
+        "quality_3",
+        "quality_4",
+        "quality_5",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut set = |bucket: &str, cells: &[(usize, String)]| {
+            let r = rows
+                .entry(bucket.to_string())
+                .or_insert_with(|| vec![String::new(); Self::HEADER.len() - 1]);
+            for (i, v) in cells {
+                r[*i] = v.clone();
+            }
+        };
+        for d in &self.duration_by_bucket {
+            set(
+                &d.bucket,
+                &[
+                    (0, d.avg_min.to_string()),
+                    (1, d.min_min.to_string()),
+                    (2, d.max_min.to_string()),
+                    (12, d.p25_min.to_string()),
+                    (13, d.p75_min.to_string()),
+                    (14, cell(d.sd_min)),
+                ],
+            );
+        }
+        for q in &self.quality_by_bucket {
+            let mut cells = vec![(3, q.avg.to_string())];
+            cells.extend(
+                q.counts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (15 + i, c.to_string())),
+            );
+            set(&q.bucket, &cells);
+        }
+        for l in &self.latency_by_bucket {
+            set(&l.bucket, &[(4, l.median.to_string())]);
+        }
+        for w in &self.wake_feeling_by_bucket {
+            set(
+                &w.bucket,
+                &[
+                    (5, cell(w.avg_feeling)),
+                    (6, cell(w.avg_inertia_min)),
+                    (7, w.days_reported.to_string()),
+                ],
+            );
+        }
+        for g in &self.segments_by_bucket {
+            set(
+                &g.bucket,
+                &[
+                    (8, g.avg_segments.to_string()),
+                    (9, g.avg_total_min.to_string()),
+                    (10, g.avg_longest_min.to_string()),
+                    (11, g.split_days.to_string()),
+                ],
+            );
+        }
+        rows.into_iter()
+            .map(|(bucket, cells)| std::iter::once(bucket).chain(cells).collect())
+            .collect()
+    }
+}
+
+impl CsvTable for RoutineTrendsResponse {
+    const HEADER: &'static [&'static str] = &[
+        "id",
+        "label",
+        "nights_recorded",
+        "nights_done",
+        "adherence_pct",
+        "done_nights",
+        "done_avg_quality",
+        "done_avg_duration_min",
+        "done_avg_wake_feeling",
+        "not_done_nights",
+        "not_done_avg_quality",
+        "not_done_avg_duration_min",
+        "not_done_avg_wake_feeling",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let outcome = |o: &RoutineOutcome| {
+            [
+                o.nights.to_string(),
+                cell(o.avg_quality),
+                cell(o.avg_duration_min),
+                cell(o.avg_wake_feeling),
+            ]
+        };
+        self.items
+            .iter()
+            .map(|i| {
+                [
+                    i.id.clone(),
+                    i.label.clone(),
+                    i.nights_recorded.to_string(),
+                    i.nights_done.to_string(),
+                    cell(i.adherence_pct),
+                ]
+                .into_iter()
+                .chain(outcome(&i.done))
+                .chain(outcome(&i.not_done))
+                .collect()
+            })
+            .collect()
+    }
+}
+
+/// Per-metric `inference` details are JSON-only.
+impl CsvTable for AidsResponse {
+    const HEADER: &'static [&'static str] = &[
+        "aid",
+        "with_nights",
+        "with_avg_quality",
+        "with_avg_duration_min",
+        "with_avg_latency_min",
+        "with_avg_wake_feeling",
+        "without_nights",
+        "without_avg_quality",
+        "without_avg_duration_min",
+        "without_avg_latency_min",
+        "without_avg_wake_feeling",
+        "sufficient_sample",
+        "quality_diff",
+        "duration_diff_min",
+        "latency_diff_min",
+        "wake_feeling_diff",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let group = |g: &NightGroupStats| {
+            [
+                g.nights.to_string(),
+                cell(g.avg_quality),
+                cell(g.avg_duration_min),
+                cell(g.avg_latency_min),
+                cell(g.avg_wake_feeling),
+            ]
+        };
+        self.aids
+            .iter()
+            .map(|a| {
+                std::iter::once(a.aid.clone())
+                    .chain(group(&a.with_aid))
+                    .chain(group(&a.without_aid))
+                    .chain([
+                        a.sufficient_sample.to_string(),
+                        cell(a.quality_diff),
+                        cell(a.duration_diff_min),
+                        cell(a.latency_diff_min),
+                        cell(a.wake_feeling_diff),
+                    ])
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Per-metric `inference` details are JSON-only.
+impl CsvTable for TagsResponse {
+    const HEADER: &'static [&'static str] = &[
+        "tag",
+        "with_nights",
+        "with_avg_quality",
+        "with_avg_duration_min",
+        "with_avg_latency_min",
+        "with_avg_wake_feeling",
+        "without_nights",
+        "without_avg_quality",
+        "without_avg_duration_min",
+        "without_avg_latency_min",
+        "without_avg_wake_feeling",
+        "sufficient_sample",
+        "quality_diff",
+        "duration_diff_min",
+        "latency_diff_min",
+        "wake_feeling_diff",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let group = |g: &NightGroupStats| {
+            [
+                g.nights.to_string(),
+                cell(g.avg_quality),
+                cell(g.avg_duration_min),
+                cell(g.avg_latency_min),
+                cell(g.avg_wake_feeling),
+            ]
+        };
+        self.tags
+            .iter()
+            .map(|t| {
+                std::iter::once(t.tag.clone())
+                    .chain(group(&t.with_tag))
+                    .chain(group(&t.without_tag))
+                    .chain([
+                        t.sufficient_sample.to_string(),
+                        cell(t.quality_diff),
+                        cell(t.duration_diff_min),
+                        cell(t.latency_diff_min),
+                        cell(t.wake_feeling_diff),
+                    ])
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// `disturbed` and `undisturbed` rows first, then one row per disturbance type.
+impl CsvTable for AwakeningsResponse {
+    const HEADER: &'static [&'static str] = &[
+        "group",
+        "nights",
+        "events",
+        "total_minutes",
+        "avg_awakenings",
+        "avg_quality",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let group = |name: &str, g: &AwakeningsGroup| {
+            vec![
+                name.to_string(),
+                g.nights.to_string(),
+                String::new(),
+                String::new(),
+                cell(g.avg_awakenings),
+                cell(g.avg_quality),
+            ]
+        };
+        [
+            group("disturbed", &self.disturbed),
+            group("undisturbed", &self.undisturbed),
+        ]
+        .into_iter()
+        .chain(self.by_type.iter().map(|t| {
+            vec![
+                t.kind.clone(),
+                t.nights.to_string(),
+                t.events.to_string(),
+                t.total_minutes.to_string(),
+                cell(t.avg_awakenings),
+                String::new(),
+            ]
+        }))
+        .collect()
+    }
+}
+
+/// One row per period; `deltas` and `warnings` are JSON-only.
+impl CsvTable for CompareResponse {
+    const HEADER: &'static [&'static str] = &[
+        "period",
+        "label",
+        "from",
+        "to",
+        "nights",
+        "avg_duration_min",
+        "avg_quality",
+        "avg_latency_min",
+        "avg_awakenings",
+        "avg_wake_feeling",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        [
+            ("current", Some(&self.current)),
+            ("previous", Some(&self.previous)),
+            ("year_ago", self.year_ago.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(period, stats)| {
+            let p = stats?;
+            Some(vec![
+                period.to_string(),
+                p.label.clone(),
+                p.from.to_string(),
+                p.to.to_string(),
+                p.nights.to_string(),
+                cell(p.avg_duration_min),
+                cell(p.avg_quality),
+                cell(p.avg_latency_min),
+                cell(p.avg_awakenings),
+                cell(p.avg_wake_feeling),
+            ])
+        })
+        .collect()
+    }
+}
+
+/// One row per metric; the period averages and warnings are JSON-only.
+impl CsvTable for PeriodCompareResponse {
+    const HEADER: &'static [&'static str] =
+        &["metric", "a", "b", "diff", "effect_size", "p_value", "hint"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.metrics
+            .iter()
+            .map(|m| {
+                vec![
+                    m.metric.to_string(),
+                    cell(m.a),
+                    cell(m.b),
+                    cell(m.diff),
+                    cell(m.inference.effect_size),
+                    cell(m.inference.p_value),
+                    m.hint.as_str().to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One row per day; `weekday_effects` and the trend slope are JSON-only.
+impl CsvTable for DecomposeResponse {
+    const HEADER: &'static [&'static str] = &["date", "value", "trend", "seasonal", "residual"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.decomposition
+            .points
+            .iter()
+            .map(|p| {
+                vec![
+                    p.date.to_string(),
+                    cell(p.value),
+                    cell(p.trend),
+                    cell(p.seasonal),
+                    cell(p.residual),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One row per day; the range totals are JSON-only.
+impl CsvTable for SleepDebtResponse {
+    const HEADER: &'static [&'static str] = &["date", "duration_min", "delta_min", "balance_min"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.days
+            .iter()
+            .map(|d| {
+                vec![
+                    d.date.to_string(),
+                    cell(d.duration_min),
+                    cell(d.delta_min),
+                    d.balance_min.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One row per intensity group; the correlations are JSON-only.
+impl CsvTable for CorrelationResponse {
+    const HEADER: &'static [&'static str] = &[
+        "intensity",
+        "rank",
+        "nights",
+        "avg_quality",
+        "avg_duration_min",
+        "avg_wake_feeling",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.groups
+            .iter()
+            .map(|g| {
+                vec![
+                    g.intensity.clone(),
+                    cell(g.rank),
+                    g.nights.to_string(),
+                    cell(g.avg_quality),
+                    cell(g.avg_duration_min),
+                    cell(g.avg_wake_feeling),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl CsvTable for RegularityResponse {
+    const HEADER: &'static [&'static str] = &[
+        "week",
+        "nights_logged",
+        "bedtime_mean",
+        "bedtime_sd_min",
+        "waketime_mean",
+        "waketime_sd_min",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.weeks
+            .iter()
+            .map(|w| {
+                vec![
+                    w.week.clone(),
+                    w.nights_logged.to_string(),
+                    cell(w.bedtime_mean),
+                    cell(w.bedtime_sd_min),
+                    cell(w.waketime_mean),
+                    cell(w.waketime_sd_min),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl CsvTable for HistogramResponse {
+    const HEADER: &'static [&'static str] = &["start_min", "end_min", "label", "count"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.bins
+            .iter()
+            .map(|b| {
+                vec![
+                    b.start_min.to_string(),
+                    b.end_min.to_string(),
+                    b.label.clone(),
+                    b.count.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One row per metric with a logged average.
+impl CsvTable for ContextResponse {
+    const HEADER: &'static [&'static str] = &[
+        "metric",
+        "age_bracket",
+        "nights",
+        "your_avg",
+        "typical_low",
+        "typical_high",
+        "population_percentile",
+        "position",
+        "message",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        use crate::stats::reference::RangePosition;
+        [("duration", &self.duration), ("latency", &self.latency)]
+            .into_iter()
+            .filter_map(|(metric, m)| {
+                let m = m.as_ref()?;
+                let position = match m.context.position {
+                    RangePosition::Below => "below",
+                    RangePosition::Within => "within",
+                    RangePosition::Above => "above",
+                };
+                Some(vec![
+                    metric.to_string(),
+                    self.age_bracket.to_string(),
+                    self.nights.to_string(),
+                    m.context.your_avg.to_string(),
+                    m.context.typical_low.to_string(),
+                    m.context.typical_high.to_string(),
+                    m.context.population_percentile.to_string(),
+                    position.to_string(),
+                    m.message.clone(),
+                ])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_spread_wraps_across_midnight() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let minutes = |times: &[NaiveTime]| -> Vec<f64> {
+            times.iter().map(|t| minutes_of_day(*t) as f64).collect()
+        };
+
+        // 23:30 and 00:30 are an hour apart, centred on midnight, not on noon.
+        let across = clock_spread(&minutes(&[t(23, 30), t(0, 30)]));
+        assert_eq!(across.mean, Some(t(0, 0)));
+        let sd = across.sd_min.unwrap();
+        assert!((sd - 30.0).abs() < 0.2, "sd {sd}");
+        // The same spread away from midnight gives the same deviation.
+        let before = clock_spread(&minutes(&[t(22, 30), t(23, 30)]));
+        assert_eq!(before.mean, Some(t(23, 0)));
+        assert_eq!(before.sd_min, across.sd_min);
+
+        let skewed = clock_spread(&minutes(&[t(23, 50), t(0, 10), t(0, 30)]));
+        assert_eq!(skewed.mean, Some(t(0, 10)));
+
+        let steady = clock_spread(&minutes(&[t(1, 15), t(1, 15)]));
+        assert_eq!(steady.sd_min, Some(0.0));
+        assert_eq!(clock_spread(&minutes(&[t(23, 0)])).sd_min, None);
+        // Opposite times have no mean direction.
+        let opposite = clock_spread(&minutes(&[t(0, 0), t(12, 0)]));
+        assert_eq!((opposite.mean, opposite.sd_min), (None, None));
+        assert_eq!(clock_spread(&[]).mean, None);
+    }
+
+    #[test]
+    fn clock_bins_start_opposite_the_mean() {
+        // Bedtimes around midnight: the axis starts at noon, so 23:30 and 00:00 are neighbours.
+        let bins = clock_bins(&[23 * 60 + 40, 10, 20], 30);
+        assert_eq!(bins.len(), 48);
+        assert_eq!(bins[0].start_min, 12 * 60);
+        assert_eq!(bins[0].label, "12:00-12:30");
+        let (before, after) = (&bins[23], &bins[24]);
+        assert_eq!((before.label.as_str(), before.count), ("23:30-00:00", 1));
+        assert_eq!((after.start_min, after.count), (0, 2));
+        assert_eq!(before.end_min, 1440);
+        assert_eq!(bins.iter().map(|b| b.count).sum::<usize>(), 3);
+        assert!(clock_bins(&[], 30).is_empty());
+    }
+
+    #[test]
+    fn duration_bins_keep_empty_bins_between_extremes() {
+        let bins = duration_bins(&[400, 419, 505], 60);
+        let counts: Vec<(u32, usize)> = bins.iter().map(|b| (b.start_min, b.count)).collect();
+        assert_eq!(counts, [(360, 2), (420, 0), (480, 1)]);
+        assert_eq!(bins[0].label, "360-420");
+    }
+
+    #[test]
+    fn debt_balance_skips_unlogged_days() {
+        let d = |day| NaiveDate::from_ymd_opt(2025, 6, day).unwrap();
+        let series = debt_series(
+            vec![(d(1), Some(420)), (d(2), None), (d(3), Some(510))],
+            480,
+        );
+        let balances: Vec<(Option<i64>, i64)> = series
+            .iter()
+            .map(|s| (s.delta_min, s.balance_min))
+            .collect();
+        assert_eq!(balances, [(Some(-60), -60), (None, -60), (Some(30), -30)]);
+    }
+
+    fn make_day_sample(
+        wake_date: NaiveDate,
+        weekend: bool,
+        midpoint_clock_min: f64,
+        quality: Option<f64>,
+    ) -> DaySample {
+        DaySample {
+            wake_date,
+            bed_clock_min: 23.0 * 60.0,
+            wake_clock_min: 7.0 * 60.0,
+            bed_relative_min: -60.0,
+            wake_relative_min: 7.0 * 60.0,
+            midpoint_clock_min: normalize_minutes(midpoint_clock_min),
+            duration_min: 8.0 * 60.0,
+            quality,
+            weekend,
+        }
+    }
+
+    #[test]
+    fn circular_midpoint_diff_wraps_across_midnight() {
+        let left = 23.0 * 60.0 + 55.0;
+        let right = 5.0;
+        assert_eq!(circular_minutes_diff(left, right), 10.0);
+        assert!(within_clock_minute_band(left, right, 15.0));
+        assert!(!within_clock_minute_band(left, right, 5.0));
+    }
+
+    #[test]
+    fn midpoint_stability_treats_midnight_neighbors_as_stable() {
+        let prior_from = NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid date");
+        let prior_to = NaiveDate::from_ymd_opt(2025, 1, 28).expect("valid date");
+        let current_from = NaiveDate::from_ymd_opt(2025, 1, 29).expect("valid date");
+        let current_to = NaiveDate::from_ymd_opt(2025, 2, 25).expect("valid date");
+
+        let mut prior_samples = Vec::new();
+        let mut current_samples = Vec::new();
 
         for i in 0..8 {
             let d = prior_from + ChronoDuration::days(i);
@@ -1291,4 +4505,301 @@ mod tests {
         assert_eq!(weekend_n, 4);
         assert_eq!(delta, Some(10.0));
     }
+
+    #[test]
+    fn routine_adherence_splits_outcomes_by_done() {
+        let items = crate::models::RoutineChecklist::default().items;
+        let row = |item_id: &str, done: bool, quality: Option<i32>| RoutineRow {
+            item_id: item_id.into(),
+            done,
+            quality,
+            duration_min: quality.map(|q| q * 100),
+            wake_feeling: None,
+        };
+        let rows = vec![
+            row("no_screens_after_22", true, Some(4)),
+            row("no_screens_after_22", true, Some(5)),
+            row("no_screens_after_22", false, Some(2)),
+            row("no_screens_after_22", false, None),
+            row("removed_item", true, Some(5)),
+        ];
+
+        let out = routine_adherence(&items, &rows);
+        assert_eq!(out.len(), 3);
+        let screens = &out[0];
+        assert_eq!(screens.nights_recorded, 4);
+        assert_eq!(screens.nights_done, 2);
+        assert_eq!(screens.adherence_pct, Some(50.0));
+        assert_eq!(screens.done.nights, 2);
+        assert_eq!(screens.done.avg_quality, Some(4.5));
+        assert_eq!(screens.done.avg_duration_min, Some(450.0));
+        assert_eq!(screens.done.avg_wake_feeling, None);
+        assert_eq!(screens.not_done.nights, 1);
+        assert_eq!(screens.not_done.avg_quality, Some(2.0));
+
+        assert_eq!(out[1].nights_recorded, 0);
+        assert_eq!(out[1].adherence_pct, None);
+    }
+
+    #[test]
+    fn aid_effects_guard_small_samples() {
+        let night = |day: u32, quality: i32| AidNightRow {
+            wake_date: NaiveDate::from_ymd_opt(2025, 6, day).unwrap(),
+            quality: Some(quality),
+            duration_min: Some(420),
+            latency_min: Some(10),
+            wake_feeling: None,
+        };
+        let nights: Vec<AidNightRow> = (1..=6)
+            .map(|d| night(d, 4))
+            .chain((7..=12).map(|d| night(d, 2)))
+            .collect();
+        let mut by_aid = BTreeMap::new();
+        by_aid.insert(
+            "earplugs".to_string(),
+            (1..=6)
+                .map(|d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap())
+                .collect::<HashSet<_>>(),
+        );
+        by_aid.insert(
+            "melatonin".to_string(),
+            HashSet::from([NaiveDate::from_ymd_opt(2025, 6, 7).unwrap()]),
+        );
+
+        let out = aid_effects(&nights, &by_aid, 5);
+        assert_eq!(out.len(), 2);
+        let earplugs = &out[0];
+        assert!(earplugs.sufficient_sample);
+        assert_eq!(earplugs.with_aid.nights, 6);
+        assert_eq!(earplugs.quality_diff, Some(2.0));
+        assert_eq!(earplugs.duration_diff_min, Some(0.0));
+        assert_eq!(earplugs.wake_feeling_diff, None);
+        assert_eq!(earplugs.inference["quality"].p_value, Some(0.0));
+        assert_eq!(earplugs.inference["wake_feeling"].n_a, 0);
+
+        let melatonin = &out[1];
+        assert!(!melatonin.sufficient_sample);
+        assert_eq!(melatonin.with_aid.nights, 1);
+        assert_eq!(melatonin.quality_diff, None);
+        assert!(!melatonin.inference["quality"].caveats.is_empty());
+    }
+
+    #[test]
+    fn awakenings_split_by_disturbance() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2025, 6, d).unwrap();
+        let nights = vec![
+            AwakeningsNightRow {
+                wake_date: date(1),
+                awakenings: Some(4),
+                quality: Some(2),
+            },
+            AwakeningsNightRow {
+                wake_date: date(2),
+                awakenings: Some(2),
+                quality: Some(3),
+            },
+            AwakeningsNightRow {
+                wake_date: date(3),
+                awakenings: Some(0),
+                quality: Some(5),
+            },
+        ];
+        let disturbance = |d: u32, kind: &str, duration_min: i32| DisturbanceRow {
+            date: date(d),
+            kind: kind.to_string(),
+            duration_min,
+        };
+        let disturbances = vec![
+            disturbance(1, "pet", 5),
+            disturbance(1, "pet", 10),
+            disturbance(2, "noise", 20),
+            // No sleep recorded for this date; ignored.
+            disturbance(9, "child", 30),
+        ];
+
+        let (disturbed, undisturbed, by_type) = awakenings_by_disturbance(&nights, &disturbances);
+        assert_eq!(disturbed.nights, 2);
+        assert_eq!(disturbed.avg_awakenings, Some(3.0));
+        assert_eq!(undisturbed.nights, 1);
+        assert_eq!(undisturbed.avg_quality, Some(5.0));
+
+        let kinds: Vec<&str> = by_type.iter().map(|t| t.kind.as_str()).collect();
+        assert_eq!(kinds, ["noise", "partner", "pet", "child"]);
+        let pet = &by_type[2];
+        assert_eq!((pet.nights, pet.events, pet.total_minutes), (1, 2, 15));
+        assert_eq!(pet.avg_awakenings, Some(4.0));
+        assert_eq!(by_type[3].events, 0);
+        assert_eq!(by_type[3].avg_awakenings, None);
+    }
+
+    #[test]
+    fn segment_buckets_aggregate_split_days() {
+        let day = |d: u32, total: i32, segments: i32, longest: i32| SegmentDayRow {
+            wake_date: NaiveDate::from_ymd_opt(2025, 6, d).unwrap(),
+            duration_min: total,
+            session_count: segments,
+            longest_segment_min: longest,
+        };
+        // 2025-06-02 (Mon) .. 2025-06-04 fall in one ISO week.
+        let rows = vec![
+            day(2, 420, 1, 420),
+            day(3, 450, 2, 330),
+            day(4, 480, 3, 300),
+        ];
+
+        let daily = segment_buckets(&rows, "day");
+        assert_eq!(daily.len(), 3);
+        assert_eq!(daily[1].bucket, "2025-06-03");
+        assert_eq!(daily[1].avg_longest_min, 330.0);
+        assert_eq!(daily[1].split_days, 1);
+
+        let weekly = segment_buckets(&rows, "week");
+        assert_eq!(weekly.len(), 1);
+        assert_eq!(weekly[0].bucket, "2025-W23");
+        assert_eq!(weekly[0].avg_segments, 2.0);
+        assert_eq!(weekly[0].avg_total_min, 450.0);
+        assert_eq!(weekly[0].avg_longest_min, 350.0);
+        assert_eq!(weekly[0].split_days, 2);
+    }
+
+    #[test]
+    fn compare_bounds_month_and_year() {
+        let d = |y, m, day| NaiveDate::from_ymd_opt(y, m, day).unwrap();
+        let (cur, prev, ago) = compare_bounds("month", "2025-03").unwrap();
+        assert_eq!(cur, ("2025-03".to_string(), d(2025, 3, 1), d(2025, 3, 31)));
+        assert_eq!(prev, ("2025-02".to_string(), d(2025, 2, 1), d(2025, 2, 28)));
+        assert_eq!(
+            ago,
+            Some(("2024-03".to_string(), d(2024, 3, 1), d(2024, 3, 31)))
+        );
+
+        let (_, prev, _) = compare_bounds("month", "2025-01").unwrap();
+        assert_eq!(prev.0, "2024-12");
+
+        let (cur, prev, ago) = compare_bounds("year", "2024").unwrap();
+        assert_eq!(cur.2, d(2024, 12, 31));
+        assert_eq!(prev.0, "2023");
+        assert!(ago.is_none());
+
+        assert!(compare_bounds("month", "2025-13").is_err());
+        assert!(compare_bounds("week", "2025-01").is_err());
+    }
+
+    #[test]
+    fn metric_deltas_skip_missing_values() {
+        let row = |m: u32, day: u32, duration: i32, quality: i32| CompareRow {
+            wake_date: NaiveDate::from_ymd_opt(2025, m, day).unwrap(),
+            duration_min: Some(duration),
+            quality: Some(quality),
+            latency_min: None,
+            awakenings: Some(1),
+            wake_feeling: None,
+        };
+        let rows = vec![row(5, 1, 400, 3), row(6, 1, 420, 4), row(6, 2, 440, 4)];
+        let (cur, prev, _) = compare_bounds("month", "2025-06").unwrap();
+        let current = period_stats(&rows, cur);
+        let previous = period_stats(&rows, prev);
+        assert_eq!(current.nights, 2);
+
+        let deltas = metric_deltas(&current, &previous, None);
+        assert_eq!(deltas[0].metric, "duration_min");
+        assert_eq!(deltas[0].vs_previous, Some(30.0));
+        assert_eq!(deltas[1].vs_previous, Some(1.0));
+        assert_eq!(deltas[2].vs_previous, None);
+        assert_eq!(deltas[0].vs_year_ago, None);
+    }
+
+    #[test]
+    fn context_messages_read_naturally() {
+        use crate::stats::reference::{bracket_for_age, place};
+        let adult = bracket_for_age(30).unwrap();
+        let duration = place(384.0, &adult.duration_min);
+        assert_eq!(
+            context_message(
+                Locale::En,
+                DurationUnit::Hours,
+                "duration",
+                &duration,
+                adult.label
+            ),
+            "Your 6.4 h average is below the typical 7\u{2013}9 h range for ages 26-64."
+        );
+        let latency = place(25.0, &adult.latency_min);
+        assert_eq!(
+            context_message(
+                Locale::En,
+                DurationUnit::Hours,
+                "latency",
+                &latency,
+                adult.label
+            ),
+            "Your 25 min average time to fall asleep is above the typical 10\u{2013}20 min range."
+        );
+        assert_eq!(
+            context_message(
+                Locale::Ja,
+                DurationUnit::Hours,
+                "latency",
+                &latency,
+                adult.label
+            ),
+            "平均入眠時間25分は、一般的な範囲（10〜20分）を上回っています。"
+        );
+        assert_eq!(
+            context_message(
+                Locale::En,
+                DurationUnit::Minutes,
+                "duration",
+                &duration,
+                adult.label
+            ),
+            "Your 384 min average is below the typical 420\u{2013}540 min range for ages 26-64."
+        );
+    }
+
+    #[test]
+    fn json_discrepancies_tolerates_float_noise() {
+        let expected = serde_json::json!({"a": [1.0, 2.5], "b": "x", "c": null});
+        let noisy = serde_json::json!({"a": [1.0000000000001, 2.5], "b": "x", "c": null});
+        let mut diffs = Vec::new();
+        json_discrepancies("$", &expected, &noisy, &mut diffs);
+        assert!(diffs.is_empty(), "{diffs:?}");
+
+        let off = serde_json::json!({"a": [1.0], "b": "y", "c": 3});
+        json_discrepancies("$", &expected, &off, &mut diffs);
+        assert_eq!(
+            diffs,
+            vec![
+                "$.a: length 2 != 1".to_string(),
+                "$.b: \"x\" != \"y\"".to_string(),
+                "$.c: null != 3".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn week_bucket_sql_matches_iso_week() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let sql = format!(
+            "SELECT {} FROM (SELECT wake_date, {ISO_THURSDAY_SQL} AS th FROM (SELECT ? AS wake_date))",
+            bucket_sql("week")
+        );
+        for (y, m, d) in [
+            (2020, 12, 31),
+            (2021, 1, 3),
+            (2021, 1, 4),
+            (2024, 12, 30),
+            (2025, 6, 1),
+            (2025, 6, 2),
+            (2026, 12, 28),
+        ] {
+            let date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            let key: String = sqlx::query_scalar(&sql)
+                .bind(date)
+                .fetch_one(&db)
+                .await
+                .unwrap();
+            assert_eq!(key, bucket_key(date, "week"), "{date}");
+        }
+    }
 }
@@ -0,0 +1,435 @@
+#![doc = r#"TypeScript type generation
+
+Renders the JSON Schemas of the API's request/response types (derived with
+[`schemars::JsonSchema`]) as TypeScript declarations for the SvelteKit UI, so the client
+types cannot drift from the Rust models.
+
+Regenerate with:
+```text
+cargo run -p sleep-api --bin sleepctl -- gen-types
+```
+
+The checked-in output lives at `sleep-ui/src/lib/api-types.gen.ts`; the `ts_types`
+integration test fails when it is stale.
+
+The same schemas are served by `GET /api/schema/{type}` (see [`json_schema`]) for external
+form builders and validators. Field constraints mirror the models' `validate` rules
+(ranges, lengths, patterns).
+
+Mapping:
+- structs become `export interface`; fields that may be absent (`Option` with
+  `skip_serializing_if`, `#[serde(default)]`) become optional (`field?:`).
+- nullable values become `T | null`; dates, times and timestamps are `string`.
+- unit enums become string-literal unions; tagged enums become unions of object types.
+"#]
+
+use schemars::{JsonSchema, SchemaGenerator, generate::SchemaSettings};
+use serde_json::{Map, Value, json};
+
+/// Header written at the top of the generated file.
+// Used by `sleepctl` and the drift test; unused by the server binary.
+#[allow(dead_code)]
+pub const HEADER: &str =
+    "// Generated by `sleepctl gen-types` from the sleep-api Rust models. Do not edit.\n";
+
+macro_rules! register {
+    ($generator:ident: $($ty:ty),* $(,)?) => {
+        $( $generator.subschema_for::<$ty>(); )*
+    };
+}
+
+/// JSON Schema definitions for every exported type, keyed by type name.
+///
+/// Types referenced from the registered roots (nested structs, enums) are included.
+pub fn schemas() -> Map<String, Value> {
+    use crate::{
+        admin_query, completeness, csp_reports, dashboard, deprecation, events, export, features,
+        handlers, i18n, importers, integrity, models, now, plan, public, reports, schema_change,
+        trends,
+    };
+
+    let mut generator = SchemaGenerator::new(SchemaSettings::draft2020_12());
+    register!(generator:
+        models::SleepInput,
+        models::SleepPatch,
+        models::SleepSession,
+        models::SleepListItem,
+        models::ActiveSleep,
+        models::SleepTimerStop,
+        models::SleepGoal,
+        models::DayBoundary,
+        models::TimeRounding,
+        models::PublicSummarySettings,
+        models::Audience,
+        models::RedactionSettings,
+        models::AlertRules,
+        models::KnownDevice,
+        models::EmailChangeInput,
+        models::PendingEmailChange,
+        models::Starred,
+        models::Attachment,
+        models::AttachmentUpload,
+        models::ExternalRef,
+        models::ApiScope,
+        models::ApiTokenInput,
+        models::ApiToken,
+        models::CreatedApiToken,
+        models::ShareLinkInput,
+        models::ShareLink,
+        models::CreatedShareLink,
+        models::ShareLinkStats,
+        models::ShareLinkVisitor,
+        models::SharedView,
+        models::AlertEvent,
+        models::AlertHistoryQuery,
+        models::ExerciseInput,
+        models::ExerciseEvent,
+        models::HrZoneMinutes,
+        models::ExerciseZoneDay,
+        models::DateIntensity,
+        models::IntensityLevels,
+        models::NoteInput,
+        models::BodyMetricInput,
+        models::BodyMetric,
+        models::DisturbanceInput,
+        models::Disturbance,
+        models::MedicationInput,
+        models::Medication,
+        models::MedicationEventInput,
+        models::MedicationEvent,
+        models::MoodInput,
+        models::MoodEntry,
+        models::ExperimentInput,
+        models::Experiment,
+        models::ExperimentResults,
+        models::RoutineChecklist,
+        models::RoutineInput,
+        models::RoutineEntry,
+        models::FrictionTelemetryInput,
+        models::FrictionTelemetryEvent,
+        models::FrictionWindowAggregate,
+        models::JobRun,
+        models::AuditReason,
+        models::AuditEntry,
+        models::AuditQuery,
+        models::AuditPage,
+        models::SchemaDescription,
+        trends::SleepBar,
+        trends::SummaryResponse,
+        trends::PersonalizationQuery,
+        trends::PersonalizationResponse,
+        trends::RoutineTrendsResponse,
+        trends::AidsResponse,
+        trends::TagsResponse,
+        trends::AwakeningsResponse,
+        trends::CorrelationResponse,
+        trends::CompareQuery,
+        trends::CompareResponse,
+        trends::PeriodCompareQuery,
+        trends::PeriodCompareResponse,
+        trends::DecomposeResponse,
+        trends::ContextResponse,
+        trends::SleepDebtResponse,
+        trends::RegularityResponse,
+        trends::HistogramResponse,
+        now::NowStatus,
+        now::BedtimeStatus,
+        now::TodayStatus,
+        dashboard::Dashboard,
+        reports::WeeklyReportResponse,
+        plan::WeekPlan,
+        public::PublicSummary,
+        completeness::CompletenessResponse,
+        csp_reports::CspReportSummary,
+        deprecation::DeprecationReport,
+        handlers::BodyMetricsImportSummary,
+        handlers::IngestSummary,
+        importers::MappingImportRequest,
+        handlers::MappingPreview,
+        handlers::MappingImportSummary,
+        handlers::SleepImportReport,
+        handlers::ExerciseZones,
+        handlers::JobsOverview,
+        handlers::FrictionBacklogResponse,
+        i18n::Locale,
+        i18n::DurationUnit,
+        events::DomainEvent,
+        admin_query::QueryRequest,
+        admin_query::QueryResult,
+        features::VersionInfo,
+        schema_change::SchemaChangeStatus,
+        integrity::IntegrityCheck,
+        integrity::IntegrityReport,
+        integrity::DailySleepVerification,
+        export::ExportSummary,
+        export::Manifest,
+        export::CompareReport,
+    );
+    generator.definitions().clone()
+}
+
+/// Standalone JSON Schema (draft 2020-12) for the exported type `name`.
+///
+/// The definitions it references are embedded under `$defs`. Returns `None` when `name`
+/// is not an exported type name (case-sensitive, e.g. `SleepInput`).
+pub fn json_schema(name: &str) -> Option<Value> {
+    let definitions = schemas();
+    let root = definitions.get(name)?;
+
+    let mut defs = Map::new();
+    let mut pending = Vec::new();
+    collect_refs(root, &mut pending);
+    while let Some(referenced) = pending.pop() {
+        if referenced == name || defs.contains_key(&referenced) {
+            continue;
+        }
+        if let Some(def) = definitions.get(&referenced) {
+            collect_refs(def, &mut pending);
+            defs.insert(referenced, def.clone());
+        }
+    }
+
+    let mut schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": name,
+    });
+    if let (Some(out), Some(root)) = (schema.as_object_mut(), root.as_object()) {
+        out.extend(root.clone());
+        if !defs.is_empty() {
+            out.insert("$defs".into(), Value::Object(defs));
+        }
+    }
+    Some(schema)
+}
+
+fn collect_refs(schema: &Value, out: &mut Vec<String>) {
+    match schema {
+        Value::Object(obj) => {
+            if let Some(reference) = obj.get("$ref").and_then(Value::as_str)
+                && let Some(name) = reference.strip_prefix("#/$defs/")
+            {
+                out.push(name.to_string());
+            }
+            obj.values().for_each(|v| collect_refs(v, out));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_refs(v, out)),
+        _ => {}
+    }
+}
+
+/// Render [`schemas`] as a TypeScript module (deterministic, sorted by type name).
+#[allow(dead_code)]
+pub fn typescript() -> String {
+    let mut out = String::from(HEADER);
+    for (name, schema) in schemas() {
+        out.push('\n');
+        declaration(&mut out, &name, &schema);
+    }
+    out
+}
+
+/// Render one type's schema as a TypeScript declaration.
+#[allow(dead_code)]
+pub fn declaration_for<T: JsonSchema>() -> String {
+    let mut generator = SchemaGenerator::new(SchemaSettings::draft2020_12());
+    let schema = generator.root_schema_for::<T>();
+    let mut out = String::new();
+    declaration(&mut out, &T::schema_name(), schema.as_value());
+    out
+}
+
+fn declaration(out: &mut String, name: &str, schema: &Value) {
+    doc_comment(out, "", schema);
+    if let Some(props) = schema.get("properties").and_then(Value::as_object)
+        && schema.get("type").and_then(Value::as_str) == Some("object")
+    {
+        out.push_str(&format!("export interface {name} {{\n"));
+        fields(out, "  ", props, &required(schema));
+        out.push_str("}\n");
+    } else {
+        out.push_str(&format!("export type {name} = {};\n", ts_type(schema)));
+    }
+}
+
+fn doc_comment(out: &mut String, indent: &str, schema: &Value) {
+    let summary = schema
+        .get("description")
+        .and_then(Value::as_str)
+        .and_then(|d| d.lines().map(str::trim).find(|l| !l.is_empty()));
+    if let Some(summary) = summary {
+        out.push_str(&format!(
+            "{indent}/** {} */\n",
+            summary.replace("*/", "*\\/")
+        ));
+    }
+}
+
+fn required(schema: &Value) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn fields(out: &mut String, indent: &str, props: &Map<String, Value>, required: &[&str]) {
+    for (field, schema) in props {
+        doc_comment(out, indent, schema);
+        let optional = if required.contains(&field.as_str()) {
+            ""
+        } else {
+            "?"
+        };
+        out.push_str(&format!(
+            "{indent}{}{optional}: {};\n",
+            property_name(field),
+            ts_type(schema)
+        ));
+    }
+}
+
+fn property_name(name: &str) -> String {
+    let ident = name
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+    if ident {
+        name.to_string()
+    } else {
+        Value::from(name).to_string()
+    }
+}
+
+fn union(types: impl IntoIterator<Item = String>) -> String {
+    let mut seen: Vec<String> = Vec::new();
+    for t in types {
+        if !seen.contains(&t) {
+            seen.push(t);
+        }
+    }
+    seen.join(" | ")
+}
+
+fn ts_type(schema: &Value) -> String {
+    let Some(obj) = schema.as_object() else {
+        return "unknown".into();
+    };
+    if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+    if let Some(value) = obj.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = obj.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(Value::to_string));
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(variants) = obj.get(key).and_then(Value::as_array) {
+            return union(variants.iter().map(ts_type));
+        }
+    }
+    if let Some(parts) = obj.get("allOf").and_then(Value::as_array) {
+        return parts.iter().map(ts_type).collect::<Vec<_>>().join(" & ");
+    }
+    match obj.get("type") {
+        Some(Value::String(t)) => primitive(t, obj),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|t| primitive(t, obj)),
+        ),
+        _ => "unknown".into(),
+    }
+}
+
+fn primitive(ty: &str, obj: &Map<String, Value>) -> String {
+    match ty {
+        "string" => "string".into(),
+        "integer" | "number" => "number".into(),
+        "boolean" => "boolean".into(),
+        "null" => "null".into(),
+        "array" => {
+            if let Some(items) = obj.get("prefixItems").and_then(Value::as_array) {
+                let items: Vec<_> = items.iter().map(ts_type).collect();
+                return format!("[{}]", items.join(", "));
+            }
+            let item = obj.get("items").map(ts_type).unwrap_or("unknown".into());
+            if item.contains(' ') {
+                format!("({item})[]")
+            } else {
+                format!("{item}[]")
+            }
+        }
+        "object" => {
+            if let Some(props) = obj.get("properties").and_then(Value::as_object) {
+                let mut inline = String::from("{\n");
+                fields(
+                    &mut inline,
+                    "  ",
+                    props,
+                    &required(&Value::Object(obj.clone())),
+                );
+                inline.push('}');
+                return inline;
+            }
+            match obj.get("additionalProperties") {
+                Some(Value::Object(_)) => {
+                    format!("Record<string, {}>", ts_type(&obj["additionalProperties"]))
+                }
+                _ => "Record<string, unknown>".into(),
+            }
+        }
+        _ => "unknown".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn maps_nullable_arrays_and_literals() {
+        assert_eq!(
+            ts_type(&json!({"type": ["integer", "null"]})),
+            "number | null"
+        );
+        assert_eq!(
+            ts_type(&json!({"type": "array", "items": {"$ref": "#/$defs/SleepBar"}})),
+            "SleepBar[]"
+        );
+        assert_eq!(
+            ts_type(&json!({"type": "array", "items": {"type": ["string", "null"]}})),
+            "(string | null)[]"
+        );
+        assert_eq!(ts_type(&json!({"enum": ["en", "ja"]})), "\"en\" | \"ja\"");
+        assert_eq!(
+            ts_type(&json!({"type": "object", "additionalProperties": {"type": "number"}})),
+            "Record<string, number>"
+        );
+    }
+
+    #[test]
+    fn json_schema_embeds_referenced_definitions() {
+        let schema = json_schema("SleepInput").unwrap();
+        assert_eq!(schema["title"], "SleepInput");
+        assert_eq!(schema["properties"]["quality"]["$ref"], "#/$defs/Quality");
+        assert_eq!(schema["$defs"]["Quality"]["maximum"], 5);
+        assert!(json_schema("sleepinput").is_none());
+        assert!(json_schema("SleepBar").unwrap().get("$defs").is_none());
+    }
+
+    #[test]
+    fn optional_fields_follow_required() {
+        let out = declaration_for::<crate::models::SleepListItem>();
+        assert!(out.starts_with("/**"), "{out}");
+        assert!(out.contains("export interface SleepListItem {"), "{out}");
+        assert!(out.contains("  date: string;\n"), "{out}");
+        assert!(out.contains("  duration_hours?: number | null;\n"), "{out}");
+    }
+}
@@ -0,0 +1,220 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+type Bodies = std::sync::Arc<std::sync::Mutex<Vec<String>>>;
+
+/// Local webhook receiver recording the body of every POST.
+async fn spawn_receiver() -> (std::net::SocketAddr, Bodies, tokio::task::JoinHandle<()>) {
+    let received = Bodies::default();
+    let store = received.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |body: String| {
+            let store = store.clone();
+            async move {
+                store.lock().unwrap().push(body);
+                axum::http::StatusCode::NO_CONTENT
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (addr, received, handle)
+}
+
+async fn login_status(client: &Client, addr: &str, email: &str, password: &str) -> u16 {
+    client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn test_email_change_requires_confirmation() {
+    let (hook_addr, received, receiver) = spawn_receiver().await;
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("NOTIFY_WEBHOOK_URL", format!("http://{hook_addr}/hook"));
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr).await;
+    let (csrf, _) = login_and_get_auth(&client, &addr, "admin@example.com", "password123").await;
+
+    let request = |password: &str, email: &str| {
+        client
+            .post(format!("http://{addr}/api/account/email"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "current_password": password, "new_email": email }))
+            .send()
+    };
+    assert_eq!(
+        request("wrong", "new@example.com").await.unwrap().status(),
+        403
+    );
+    assert_eq!(
+        request("password123", "not-an-email")
+            .await
+            .unwrap()
+            .status(),
+        400
+    );
+    assert_eq!(
+        request("password123", "ADMIN@example.com")
+            .await
+            .unwrap()
+            .status(),
+        400
+    );
+    assert!(received.lock().unwrap().is_empty());
+
+    let res = request("password123", " new@example.com ").await.unwrap();
+    assert_eq!(res.status(), 202);
+    let pending: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(pending["new_email"], "new@example.com");
+
+    let mut body = None;
+    for _ in 0..50 {
+        body = received.lock().unwrap().first().cloned();
+        if body.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let body: serde_json::Value = serde_json::from_str(&body.expect("no notification")).unwrap();
+    assert_eq!(body["kind"], "email_change");
+    let text = body["body"].as_str().unwrap();
+    let start = text.find("sle_").expect("no confirmation secret");
+    let secret = &text[start..start + 4 + 64];
+
+    // Nothing changes before the link is opened.
+    assert_eq!(
+        login_status(&client, &addr, "new@example.com", "password123").await,
+        401
+    );
+    let confirm = |secret: &str| {
+        client
+            .get(format!("http://{addr}/api/account/email/confirm/{secret}"))
+            .send()
+    };
+    assert_eq!(confirm("sle_bogus").await.unwrap().status(), 404);
+
+    let res = confirm(secret).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let confirmed: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(confirmed["email"], "new@example.com");
+    assert_eq!(confirm(secret).await.unwrap().status(), 404);
+
+    assert_eq!(
+        login_status(&client, &addr, "admin@example.com", "password123").await,
+        401
+    );
+    assert_eq!(
+        login_status(&client, &addr, "new@example.com", "password123").await,
+        200
+    );
+
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_log WHERE entity = 'account_email' ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(actions, ["request", "confirm"]);
+
+    server.abort();
+    receiver.abort();
+}
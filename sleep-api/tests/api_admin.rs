@@ -0,0 +1,595 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_admin_schema_describes_live_db() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let _ = (&csrf, &session_cookie);
+
+    let res = client
+        .get(format!("http://{addr}/api/admin/schema"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["schema_version"].as_i64().unwrap() >= 6);
+
+    let objects = body["objects"].as_array().unwrap();
+    assert!(
+        objects
+            .iter()
+            .all(|o| o["name"] != "_sqlx_migrations" && o["name"] != "sqlite_sequence")
+    );
+
+    let sessions = objects
+        .iter()
+        .find(|o| o["name"] == "sleep_sessions")
+        .expect("sleep_sessions table");
+    assert_eq!(sessions["kind"], "table");
+    let id_col = sessions["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "id")
+        .unwrap();
+    assert_eq!(id_col["data_type"], "INTEGER");
+    assert_eq!(id_col["primary_key"], 1);
+
+    let view = objects
+        .iter()
+        .find(|o| o["name"] == "v_daily_sleep")
+        .expect("v_daily_sleep view");
+    assert_eq!(view["kind"], "view");
+    assert!(view["sql"].as_str().unwrap().contains("CREATE VIEW"));
+    assert!(
+        view["columns"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|c| c["name"] == "duration_min")
+    );
+
+    // Unauthenticated access is rejected
+    let anon = Client::new();
+    let res = anon
+        .get(format!("http://{addr}/api/admin/schema"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_admin_query_is_read_only_and_limited() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let query = |sql: &'static str| {
+        client
+            .post(format!("http://{addr}/api/admin/query"))
+            .header("Cookie", format!("session={session_cookie}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "sql": sql }))
+            .send()
+    };
+
+    // Disabled by default
+    let res = query("SELECT 1").await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    unsafe {
+        std::env::set_var("ADMIN_QUERY_ENABLED", "1");
+        std::env::set_var("ADMIN_QUERY_MAX_ROWS", "3");
+        std::env::set_var("ADMIN_QUERY_TIMEOUT_MS", "200");
+    };
+
+    let res = query("SELECT name, type FROM sqlite_master WHERE name = 'sleep_sessions';")
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["columns"], serde_json::json!(["name", "type"]));
+    assert_eq!(
+        body["rows"],
+        serde_json::json!([["sleep_sessions", "table"]])
+    );
+    assert_eq!(body["truncated"], false);
+
+    // Row limit
+    let res = query("WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 10) SELECT x, x * 0.5 AS half, NULL AS missing FROM n")
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["rows"].as_array().unwrap().len(), 3);
+    assert_eq!(body["rows"][0], serde_json::json!([1, 0.5, null]));
+    assert_eq!(body["truncated"], true);
+
+    // Empty results still report columns
+    let res = query("SELECT id, date FROM sleep_sessions").await.unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["columns"], serde_json::json!(["id", "date"]));
+    assert_eq!(body["rows"], serde_json::json!([]));
+
+    // Writes and multiple statements are rejected
+    for sql in [
+        "DELETE FROM sleep_sessions",
+        "SELECT 1; DELETE FROM sleep_sessions",
+        "WITH x AS (SELECT 1) DELETE FROM sleep_sessions",
+        "PRAGMA query_only = OFF",
+    ] {
+        let res = query(sql).await.unwrap();
+        assert_eq!(res.status(), 400, "{sql}");
+    }
+
+    // Time limit interrupts runaway statements
+    let res = query(
+        "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT count(*) FROM n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["message"].as_str().unwrap().contains("interrupt"));
+
+    // Pooled connections are writable again afterwards
+    let res = client
+        .post(format!("http://{addr}/api/body-metrics"))
+        .header("Cookie", format!("session={session_cookie}; csrf={csrf}"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "date": "2025-06-01", "weight_kg": 70.0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    unsafe {
+        std::env::remove_var("ADMIN_QUERY_ENABLED");
+        std::env::remove_var("ADMIN_QUERY_MAX_ROWS");
+        std::env::remove_var("ADMIN_QUERY_TIMEOUT_MS");
+    };
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_admin_jobs_run_and_report_maintenance() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let res = client
+        .post(format!(
+            "http://{addr}/api/admin/jobs/sqlite_maintenance/run"
+        ))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let run: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(run["job"], "sqlite_maintenance");
+    assert_eq!(run["status"], "ok");
+    assert!(!run["finished_at"].is_null());
+    let detail = run["detail"].as_str().unwrap();
+    assert!(detail.starts_with("optimize, analyze, vacuum"), "{detail}");
+
+    // A second run within the vacuum interval skips VACUUM
+    let res = client
+        .post(format!(
+            "http://{addr}/api/admin/jobs/sqlite_maintenance/run"
+        ))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    let run: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(run["detail"], "optimize, analyze");
+
+    let res = client
+        .post(format!("http://{addr}/api/admin/jobs/nope/run"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("http://{addr}/api/admin/jobs"))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body["jobs"],
+        serde_json::json!([
+            "schema_backfill",
+            "telemetry_archive",
+            "sqlite_maintenance",
+            "alert_evaluation"
+        ])
+    );
+    let runs = body["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0]["detail"], "optimize, analyze");
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_telemetry_archive_moves_old_rows_into_yearly_tables() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    for recorded_at in [
+        "2023-03-01 08:00:00",
+        "2024-01-15 08:00:00",
+        "2024-11-30 08:00:00",
+    ] {
+        sqlx::query(
+            "INSERT INTO personalization_friction_events(recorded_at, form_time_ms) VALUES (?, 1000)",
+        )
+        .bind(recorded_at)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query("INSERT INTO personalization_friction_events(form_time_ms) VALUES (500)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let res = client
+        .post(format!(
+            "http://{addr}/api/admin/jobs/telemetry_archive/run"
+        ))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let run: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(run["status"], "ok");
+    assert_eq!(
+        run["detail"],
+        "moved personalization_friction_events_archive_2023: 1, personalization_friction_events_archive_2024: 2"
+    );
+
+    let hot: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM personalization_friction_events")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(hot, 1);
+    let archived: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM personalization_friction_events_archive_2024")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(archived, 2);
+
+    // Outside an archive move the hot table stays append-only
+    let deleted = sqlx::query("DELETE FROM personalization_friction_events")
+        .execute(&pool)
+        .await;
+    assert!(deleted.is_err());
+
+    // Nothing left to move on a second run
+    let res = client
+        .post(format!(
+            "http://{addr}/api/admin/jobs/telemetry_archive/run"
+        ))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    let run: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(run["detail"], "nothing to archive");
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_schema_change_backfill_then_switch() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("SCHEMA_BACKFILL_BATCH", "1");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // Rows written before the dual-write shim only have the old column.
+    for date in ["2024-01-01", "2024-01-02", "2024-01-03"] {
+        sqlx::query(
+            "INSERT INTO sleep_sessions(date, bed_time, wake_time) VALUES (?, '23:00:00', '07:00:00')",
+        )
+        .bind(date)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let status = || async {
+        let res = client
+            .get(format!("http://{addr}/api/admin/schema-changes"))
+            .header("Cookie", &auth)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let body: serde_json::Value = res.json().await.unwrap();
+        body[0].clone()
+    };
+    let switch = |name: &'static str| {
+        client
+            .post(format!(
+                "http://{addr}/api/admin/schema-changes/{name}/switch"
+            ))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .send()
+    };
+    let backfill = || {
+        client
+            .post(format!("http://{addr}/api/admin/jobs/schema_backfill/run"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .send()
+    };
+
+    let before = status().await;
+    assert_eq!(before["name"], "session_date");
+    assert_eq!(before["phase"], "dual_write");
+    assert_eq!(before["pending_rows"], 3);
+
+    // Reads cannot switch before the backfill has finished
+    assert_eq!(switch("session_date").await.unwrap().status(), 400);
+
+    let run: serde_json::Value = backfill().await.unwrap().json().await.unwrap();
+    assert_eq!(run["status"], "ok");
+    assert_eq!(run["detail"], "session_date: 3 rows, done");
+    let after = status().await;
+    assert_eq!(after["phase"], "backfilled");
+    assert_eq!(after["pending_rows"], 0);
+
+    let res = switch("session_date").await.unwrap();
+    assert_eq!(res.status(), 200);
+    let switched: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(switched["phase"], "read_new");
+    assert_eq!(switch("session_date").await.unwrap().status(), 200);
+    assert_eq!(switch("nope").await.unwrap().status(), 404);
+
+    let run: serde_json::Value = backfill().await.unwrap().json().await.unwrap();
+    assert_eq!(run["detail"], "nothing to backfill");
+
+    let migrated: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sleep_sessions WHERE session_date = date")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(migrated, 3);
+
+    server.abort();
+}
@@ -0,0 +1,287 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+/// Signature header (if any) and body of one webhook delivery.
+type Delivery = (Option<String>, String);
+
+#[derive(Clone, Default)]
+struct Received(std::sync::Arc<std::sync::Mutex<Vec<Delivery>>>);
+
+/// Local webhook receiver recording the signature header and body of every POST.
+async fn spawn_receiver() -> (std::net::SocketAddr, Received, tokio::task::JoinHandle<()>) {
+    let received = Received::default();
+    let store = received.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |headers: axum::http::HeaderMap, body: String| {
+            let store = store.clone();
+            async move {
+                let sig = headers
+                    .get("x-signature-256")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                store.0.lock().unwrap().push((sig, body));
+                axum::http::StatusCode::NO_CONTENT
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (addr, received, handle)
+}
+
+#[tokio::test]
+async fn test_alert_rules_fire_once_and_notify() {
+    let (hook_addr, received, receiver) = spawn_receiver().await;
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("NOTIFY_WEBHOOK_URL", format!("http://{hook_addr}/hook"));
+        std::env::set_var("NOTIFY_WEBHOOK_SECRET", "hook-secret");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // 03:30 on June 11th in Tokyo: the last completed day is June 10th.
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-06-11T03:30:00+09:00")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let url = format!("http://{addr}/api/settings/alerts");
+    let empty: serde_json::Value = client.get(&url).send().await.unwrap().json().await.unwrap();
+    assert_eq!(empty, serde_json::json!({"rules": []}));
+
+    let rule =
+        |id: &str, metric: &str, comparison: &str, threshold: f64, condition: &str, nights: u32| {
+            serde_json::json!({
+                "id": id,
+                "metric": metric,
+                "comparison": comparison,
+                "threshold": threshold,
+                "condition": condition,
+                "nights": nights,
+            })
+        };
+    let res = client
+        .put(&url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"rules": [
+            rule("dup", "duration_min", "below", 360.0, "average", 7),
+            rule("dup", "latency_min", "above", 45.0, "consecutive", 3),
+        ]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .put(&url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"rules": [
+            rule("short_nights", "duration_min", "below", 360.0, "average", 4),
+            rule("slow_to_sleep", "latency_min", "above", 45.0, "consecutive", 3),
+            rule("poor_quality", "quality", "below", 2.0, "average", 3),
+        ]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let saved: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(saved["rules"][0]["enabled"], true);
+
+    // Wake dates June 8th..=10th: 5 hours each, 50 minutes to fall asleep.
+    for day in 8..=10 {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": format!("2025-06-{day:02}"),
+                "bed_time": "01:00:00",
+                "wake_time": "06:00:00",
+                "latency_min": 50,
+                "awakenings": 0,
+                "quality": 3,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201, "day {day}");
+    }
+
+    let run_url = format!("http://{addr}/api/admin/jobs/alert_evaluation/run");
+    let run: serde_json::Value = client
+        .post(&run_url)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(run["status"], "ok");
+    assert_eq!(
+        run["detail"],
+        "2025-06-10: 3 rules, fired [short_nights, slow_to_sleep]"
+    );
+
+    // Re-running the same night does not fire or deliver again.
+    let run: serde_json::Value = client
+        .post(&run_url)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(run["detail"], "2025-06-10: 3 rules, fired []");
+
+    let history: serde_json::Value = client
+        .get(format!("http://{addr}/api/alerts/history"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let history = history.as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    let latency = history
+        .iter()
+        .find(|a| a["rule_id"] == "slow_to_sleep")
+        .unwrap();
+    assert_eq!(latency["as_of"], "2025-06-10");
+    assert_eq!(latency["value"], 50.0);
+    assert_eq!(latency["delivered_via"], "log,webhook");
+    assert_eq!(
+        latency["message"],
+        "slow_to_sleep: latency_min above 45 for 3 consecutive nights (average 50)"
+    );
+
+    let received = received.0.lock().unwrap().clone();
+    assert_eq!(received.len(), 2);
+    for (sig, body) in &received {
+        let sig = sig.as_deref().expect("signed webhook");
+        assert!(sleep_api::security::signature::verify(
+            b"hook-secret",
+            body.as_bytes(),
+            sig
+        ));
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["kind"], "alert");
+    }
+
+    let res = client
+        .get(format!("http://{addr}/api/alerts/history?limit=0"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+    receiver.abort();
+}
@@ -0,0 +1,254 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+/// A mono 16-bit PCM WAV file of `ms` milliseconds of silence at 8 kHz.
+fn wav(ms: u32) -> Vec<u8> {
+    let data_len = 8 * ms * 2;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&8000u32.to_le_bytes());
+    out.extend_from_slice(&16000u32.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.resize(out.len() + data_len as usize, 0);
+    out
+}
+
+#[tokio::test]
+async fn test_attachments_thumbnails_and_durations() {
+    let dir = std::env::temp_dir().join(format!("sleep-attachments-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("ATTACHMENT_DIR", &dir);
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let upload = |filename: &str, content_type: &str, body: Vec<u8>| {
+        client
+            .post(format!(
+                "http://{addr}/api/attachments?date=2025-06-01&filename={filename}"
+            ))
+            .header("X-CSRF-Token", &csrf)
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+    };
+
+    // Audio memos get their duration
+    let memo = wav(1500);
+    let res = upload("memo.wav", "audio/wav", memo.clone()).await.unwrap();
+    assert_eq!(res.status(), 201);
+    let audio: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(audio["duration_ms"], 1500);
+    assert_eq!(audio["has_thumb"], false);
+    let audio_id = audio["id"].as_i64().unwrap();
+
+    let res = client
+        .get(format!("http://{addr}/api/attachment/{audio_id}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "audio/wav");
+    assert_eq!(res.bytes().await.unwrap(), memo);
+
+    let res = client
+        .get(format!("http://{addr}/api/attachment/{audio_id}/thumb"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // Undecodable files are stored without metadata
+    let res = upload("notes.txt", "text/plain", b"slept badly".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let text: serde_json::Value = res.json().await.unwrap();
+    assert!(text["duration_ms"].is_null());
+    assert!(text["width"].is_null());
+
+    let res = upload("empty.bin", "application/octet-stream", Vec::new())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = upload("a%2Fb.txt", "text/plain", b"x".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Uploads need the CSRF header
+    let res = client
+        .post(format!(
+            "http://{addr}/api/attachments?date=2025-06-01&filename=x.txt"
+        ))
+        .body("x")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    #[cfg(feature = "image")]
+    {
+        let img = image::RgbImage::from_pixel(800, 400, image::Rgb([20, 40, 200]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let res = upload("sky.png", "image/png", png).await.unwrap();
+        assert_eq!(res.status(), 201);
+        let photo: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(photo["width"], 800);
+        assert_eq!(photo["height"], 400);
+        assert_eq!(photo["has_thumb"], true);
+        let photo_id = photo["id"].as_i64().unwrap();
+
+        let url = format!("http://{addr}/api/attachment/{photo_id}/thumb");
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "image/jpeg");
+        let etag = res.headers()["etag"].to_str().unwrap().to_string();
+        let thumb = image::load_from_memory(&res.bytes().await.unwrap()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (256, 128));
+
+        let res = client
+            .get(&url)
+            .header("If-None-Match", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 304);
+    }
+
+    let listed: Vec<serde_json::Value> = client
+        .get(format!("http://{addr}/api/attachments?date=2025-06-01"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(listed[0]["filename"], "memo.wav");
+    assert_eq!(listed[1]["filename"], "notes.txt");
+    let empty: Vec<serde_json::Value> = client
+        .get(format!("http://{addr}/api/attachments?date=2025-06-02"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(empty.is_empty());
+
+    let res = client
+        .get(format!("http://{addr}/api/attachment/9999/thumb"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    server.abort();
+    let _ = std::fs::remove_dir_all(&dir);
+}
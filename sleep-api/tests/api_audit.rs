@@ -0,0 +1,337 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{
+    app, db,
+    models::{AuditEntry, AuditQuery, AuditReason, DisturbanceInput, DisturbanceKind},
+    repository,
+};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+#[tokio::test]
+async fn test_deletes_require_and_record_reason() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("AUDIT_REASON_REQUIRED", "1");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let input = DisturbanceInput {
+        date: "2025-06-01".parse().unwrap(),
+        time: "02:00:00".parse().unwrap(),
+        kind: DisturbanceKind::Noise,
+        duration_min: 5,
+    };
+    let id = repository::insert_disturbance(&pool, &input).await.unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({"email": "admin@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    let csrf = parse_cookie(headers.iter(), "csrf=").expect("missing CSRF cookie");
+    let session = parse_cookie(headers.iter(), "session=").expect("missing session cookie");
+
+    let delete = |body: Option<serde_json::Value>| {
+        let mut req = client
+            .delete(format!("http://{addr}/api/disturbances/{id}"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf);
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        req.send()
+    };
+
+    // No body, a note without a reason, and a malformed reason code are all rejected.
+    let res = delete(None).await.unwrap();
+    assert_eq!(res.status(), 400);
+    let res = delete(Some(serde_json::json!({"note": "oops"})))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = delete(Some(serde_json::json!({"reason": "Bad Import"})))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(
+        repository::find_disturbance_date(&pool, id)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let res = delete(Some(
+        serde_json::json!({"reason": "duplicate", "note": "logged twice"}),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 204);
+
+    let res = client
+        .get(format!("http://{addr}/api/admin/audit"))
+        .header("Cookie", format!("session={session}; csrf={csrf}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let page: serde_json::Value = res.json().await.unwrap();
+    assert!(page["next_cursor"].is_null());
+    let entries = page["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["action"], "delete");
+    assert_eq!(entries[0]["entity"], "disturbance");
+    assert_eq!(entries[0]["entity_id"], id);
+    assert_eq!(entries[0]["reason"], "duplicate");
+    assert_eq!(entries[0]["note"], "logged twice");
+
+    // Deleting an already-absent row succeeds without a new audit entry.
+    let res = delete(Some(serde_json::json!({"reason": "duplicate"})))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    assert_eq!(
+        repository::list_audit_entries(&pool, &AuditQuery::default(), 10)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_audit_log_pagination_filters_and_csv() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let reason = AuditReason {
+        reason: Some("cleanup".into()),
+        note: None,
+    };
+    for id in 1..=5 {
+        repository::insert_audit_entry(&pool, "delete", "sleep_session", Some(id), &reason)
+            .await
+            .unwrap();
+        repository::insert_audit_entry(&pool, "delete", "disturbance", Some(id), &reason)
+            .await
+            .unwrap();
+    }
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({"email": "admin@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    let csrf = parse_cookie(headers.iter(), "csrf=").expect("missing CSRF cookie");
+    let session = parse_cookie(headers.iter(), "session=").expect("missing session cookie");
+    let get = |query: String| {
+        client
+            .get(format!("http://{addr}/api/admin/audit?{query}"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .send()
+    };
+
+    // Walk the sleep_session entries two at a time, newest first.
+    let mut seen = Vec::new();
+    let mut cursor: Option<i64> = None;
+    loop {
+        let mut query = "entity=sleep_session&limit=2".to_string();
+        if let Some(c) = cursor {
+            query.push_str(&format!("&cursor={c}"));
+        }
+        let res = get(query).await.unwrap();
+        assert_eq!(res.status(), 200);
+        let page: serde_json::Value = res.json().await.unwrap();
+        for e in page["entries"].as_array().unwrap() {
+            assert_eq!(e["entity"], "sleep_session");
+            seen.push(e["entity_id"].as_i64().unwrap());
+        }
+        match page["next_cursor"].as_i64() {
+            Some(c) => cursor = Some(c),
+            None => break,
+        }
+    }
+    assert_eq!(seen, [5, 4, 3, 2, 1]);
+
+    // Dates bound recorded_at; everything was recorded today (UTC).
+    let res = get("from=2000-01-01&to=2000-12-31".into()).await.unwrap();
+    let page: serde_json::Value = res.json().await.unwrap();
+    assert!(page["entries"].as_array().unwrap().is_empty());
+
+    let res = get("action=delete&limit=3&format=csv".into())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().contains_key("x-next-cursor"));
+    let csv = res.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0],
+        "id,recorded_at,action,entity,entity_id,reason,note"
+    );
+    assert!(lines[1].ends_with(",delete,disturbance,5,cleanup,"));
+
+    assert_eq!(get("limit=0".into()).await.unwrap().status(), 400);
+    assert_eq!(
+        get("from=2025-06-02&to=2025-06-01".into())
+            .await
+            .unwrap()
+            .status(),
+        400
+    );
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_audit_log_lists_archived_entries() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+    }
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    for (recorded_at, entity_id) in [
+        ("2023-03-01 10:00:00", 1),
+        ("2024-03-01 10:00:00", 2),
+        ("2030-03-01 10:00:00", 3),
+    ] {
+        sqlx::query(
+            "INSERT INTO audit_log(recorded_at, action, entity, entity_id, reason) \
+             VALUES (?, 'delete', 'note', ?, 'cleanup')",
+        )
+        .bind(recorded_at)
+        .bind(entity_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    let cutoff = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let moved = repository::archive_rows_before(&pool, "audit_log", "recorded_at", cutoff)
+        .await
+        .unwrap();
+    assert_eq!(moved, [(2023, 1), (2024, 1)]);
+
+    let ids = |entries: Vec<AuditEntry>| {
+        entries
+            .iter()
+            .map(|e| e.entity_id.unwrap())
+            .collect::<Vec<_>>()
+    };
+    let all = repository::list_audit_entries(&pool, &AuditQuery::default(), 10)
+        .await
+        .unwrap();
+    assert_eq!(ids(all), [3, 2, 1]);
+    let query = AuditQuery {
+        from: chrono::NaiveDate::from_ymd_opt(2024, 1, 1),
+        ..Default::default()
+    };
+    let since = repository::list_audit_entries(&pool, &query, 1)
+        .await
+        .unwrap();
+    assert_eq!(ids(since.clone()), [3]);
+    let query = AuditQuery {
+        cursor: Some(since[0].id),
+        ..query
+    };
+    let next = repository::list_audit_entries(&pool, &query, 10)
+        .await
+        .unwrap();
+    assert_eq!(ids(next), [2]);
+}
@@ -0,0 +1,202 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_body_metrics_crud_and_import() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // Create
+    let res = client
+        .post(format!("http://{addr}/api/body-metrics"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-10", "weight_kg": 72.5, "body_fat_pct": null}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let id = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    // Invalid reading
+    let res = client
+        .post(format!("http://{addr}/api/body-metrics"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-10", "weight_kg": null, "body_fat_pct": null}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Update
+    let res = client
+        .put(format!("http://{addr}/api/body-metrics/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-10", "weight_kg": 72.0, "body_fat_pct": 19.5}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+
+    // Import Withings CSV: overwrites 2025-06-10 and adds 2025-06-11
+    let csv = "Date,\"Weight (kg)\",\"Fat mass (kg)\",Comments\n\
+               \"2025-06-11 07:00:00\",71.8,,\n\
+               \"2025-06-10 07:00:00\",72.2,14.44,\n";
+    let res = client
+        .post(format!("http://{addr}/api/body-metrics/import/withings"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["imported"], 2);
+
+    let res = client
+        .post(format!("http://{addr}/api/body-metrics/import/garmin"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .body("{}")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // List
+    let res = client
+        .get(format!(
+            "http://{addr}/api/body-metrics?from=2025-06-01&to=2025-06-30"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items: Vec<serde_json::Value> = res.json().await.unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["date"], "2025-06-10");
+    assert_eq!(items[0]["source"], "withings");
+    assert!((items[0]["body_fat_pct"].as_f64().unwrap() - 20.0).abs() < 1e-6);
+    assert_eq!(items[1]["weight_kg"], 71.8);
+
+    // Delete (idempotent)
+    for _ in 0..2 {
+        let res = client
+            .delete(format!("http://{addr}/api/body-metrics/{id}"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 204);
+    }
+
+    server.abort();
+}
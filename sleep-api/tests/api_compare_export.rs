@@ -0,0 +1,196 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+async fn create_sleep(client: &Client, addr: &str, csrf: &str, date: &str) -> i64 {
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", csrf)
+        .json(&serde_json::json!({
+            "date": date,
+            "bed_time": "23:00:00",
+            "wake_time": "07:00:00",
+            "latency_min": 10,
+            "awakenings": 1,
+            "quality": 3
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201, "create sleep failed");
+    let body: serde_json::Value = res.json().await.unwrap();
+    body["id"].as_i64().unwrap()
+}
+
+#[tokio::test]
+async fn test_compare_export_reports_changed_and_missing_sessions() {
+    let dir = std::env::temp_dir().join(format!("sleep-compare-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    unsafe {
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("BACKUP_DIR", dir.join("backups"));
+        std::env::remove_var("EXPORT_SIGNING_KEY");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect_file(&dir.join("sleep.db")).await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr).await;
+    let (csrf, _session) =
+        login_and_get_auth(&client, &addr, "admin@example.com", "password123").await;
+
+    create_sleep(&client, &addr, &csrf, "2025-06-01").await;
+    let edited = create_sleep(&client, &addr, &csrf, "2025-06-02").await;
+    let deleted = create_sleep(&client, &addr, &csrf, "2025-06-03").await;
+
+    let res = client
+        .post(format!("http://{addr}/api/admin/backups"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let created: serde_json::Value = res.json().await.unwrap();
+    let name = created["name"].as_str().unwrap();
+    let manifest: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(dir.join("backups").join(name).join("manifest.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(manifest["version"], 2);
+    assert_eq!(manifest["records"].as_array().unwrap().len(), 3);
+
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{edited}"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "quality": 5 }))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success(), "patch failed: {}", res.status());
+    let res = client
+        .delete(format!("http://{addr}/api/sleep/{deleted}"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    create_sleep(&client, &addr, &csrf, "2025-06-04").await;
+
+    let res = client
+        .post(format!("http://{addr}/api/admin/compare-export"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&manifest)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let report: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(report["signature"], "unsigned");
+    assert_eq!(report["unchanged"], 1);
+    assert_eq!(report["added"], 1);
+    assert_eq!(report["changed"][0]["id"], edited);
+    assert_eq!(report["changed"][0]["date"], "2025-06-02");
+    assert_eq!(report["changed"].as_array().unwrap().len(), 1);
+    assert_eq!(report["missing"][0]["id"], deleted);
+    assert_eq!(report["missing"].as_array().unwrap().len(), 1);
+
+    // Version 1 manifests only hash whole files, so there is nothing to compare against.
+    let mut old = manifest.clone();
+    old["version"] = 1.into();
+    old["records"] = serde_json::json!([]);
+    let res = client
+        .post(format!("http://{addr}/api/admin/compare-export"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&old)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+    pool.close().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,270 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_csp_report_collection_and_summary() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("CSP_REPORT_URI", "/api/csp-report");
+        std::env::set_var("CSP_REPORT_ONLY", "1");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-06-18T12:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    let browser = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+    let report_url = format!("http://{addr}/api/csp-report");
+
+    // Every response advertises the endpoint and reports against the strict policy.
+    let res = browser
+        .get(format!("http://{addr}/api/health"))
+        .send()
+        .await
+        .unwrap();
+    let header = |name: &str| {
+        res.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    assert!(header("content-security-policy").contains("'unsafe-inline'"));
+    assert!(
+        header("content-security-policy")
+            .ends_with("; report-uri /api/csp-report; report-to csp-endpoint")
+    );
+    let report_only = header("content-security-policy-report-only");
+    assert!(report_only.starts_with(sleep_api::security::headers::STRICT_CSP));
+    assert!(!report_only.contains("'unsafe-inline'"));
+    assert_eq!(
+        header("reporting-endpoints"),
+        "csp-endpoint=\"/api/csp-report\""
+    );
+
+    // report-uri format, with a token in the page URL that must not be stored.
+    let legacy = serde_json::json!({"csp-report": {
+        "document-uri": "http://localhost/api/feeds/reports.xml?token=slt_secret",
+        "violated-directive": "script-src-elem",
+        "effective-directive": "script-src-elem",
+        "blocked-uri": "inline",
+        "line-number": 12,
+        "disposition": "report"
+    }});
+    let res = browser
+        .post(&report_url)
+        .header("Content-Type", "application/csp-report")
+        .body(legacy.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+
+    // Reporting API format; entries of other types are ignored.
+    let batch = serde_json::json!([
+        {"type": "csp-violation", "body": {
+            "documentURL": "http://localhost/trends",
+            "effectiveDirective": "img-src",
+            "blockedURL": "https://tiles.example/a.png?k=1",
+            "disposition": "enforce"
+        }},
+        {"type": "deprecation", "body": {"id": "x"}}
+    ]);
+    let res = browser
+        .post(&report_url)
+        .header("Content-Type", "application/reports+json")
+        .body(batch.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+
+    let res = browser
+        .post(&report_url)
+        .body("{\"hello\":1}")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = browser
+        .post(&report_url)
+        .body(vec![b' '; sleep_api::csp_reports::MAX_REPORT_BYTES + 1])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 413);
+
+    // The per-minute budget: 2 stored so far, a batch fills the rest, the next is refused.
+    let flood: Vec<_> = (0..100)
+        .map(|i| {
+            serde_json::json!({"type": "csp-violation", "body": {
+                "documentURL": "http://localhost/",
+                "effectiveDirective": "style-src-attr",
+                "blockedURL": "inline",
+                "lineNumber": i
+            }})
+        })
+        .collect();
+    let res = browser
+        .post(&report_url)
+        .body(serde_json::Value::from(flood.clone()).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = browser
+        .post(&report_url)
+        .body(serde_json::Value::from(flood).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 429);
+
+    // Admin summary.
+    let summary_url = format!("http://{addr}/api/admin/csp-reports");
+    let res = browser.get(&summary_url).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+    login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let res = client.get(&summary_url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["since"], "2025-06-11T12:00:00");
+    assert_eq!(
+        summary["total"],
+        sleep_api::csp_reports::REPORTS_PER_MIN,
+        "{summary}"
+    );
+    let groups = summary["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 3, "{summary}");
+    assert_eq!(groups[0]["violated_directive"], "style-src-attr");
+    assert_eq!(
+        groups[0]["count"],
+        sleep_api::csp_reports::REPORTS_PER_MIN - 2
+    );
+    let inline_script = groups
+        .iter()
+        .find(|g| g["violated_directive"] == "script-src-elem")
+        .unwrap();
+    assert_eq!(
+        inline_script["document_uri"],
+        "http://localhost/api/feeds/reports.xml"
+    );
+    assert_eq!(inline_script["disposition"], "report");
+    let image = groups
+        .iter()
+        .find(|g| g["violated_directive"] == "img-src")
+        .unwrap();
+    assert_eq!(image["blocked_uri"], "https://tiles.example/a.png");
+
+    let res = client
+        .get(&summary_url)
+        .query(&[("days", "0")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
@@ -0,0 +1,202 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_verify_reports_drift_between_table_and_sessions() {
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let url = format!("http://{addr}/api/admin/daily-sleep/verify?from=2025-06-01&to=2025-06-30");
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    for date in ["2025-06-02", "2025-06-03", "2025-06-04"] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date,
+                "bed_time": "23:00:00",
+                "wake_time": "07:00:00",
+                "latency_min": 5,
+                "awakenings": 0,
+                "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["ok"], true);
+    assert_eq!(body["nights"], 3);
+    assert_eq!(body["discrepancies"], serde_json::json!([]));
+
+    // Drift the table behind the triggers' back.
+    sqlx::raw_sql(
+        "UPDATE daily_sleep SET quality = 1, awakenings = 4 WHERE wake_date = '2025-06-02';
+         DELETE FROM daily_sleep WHERE wake_date = '2025-06-03';
+         INSERT INTO daily_sleep (wake_date, id, bed_time, wake_time, session_count)
+             VALUES ('2025-06-20', 999, '22:00:00', '06:00:00', 1);",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["ok"], false);
+    assert_eq!(body["nights"], 4);
+    let found: Vec<(&str, &str)> = body["discrepancies"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| {
+            (
+                d["wake_date"].as_str().unwrap(),
+                d["kind"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("2025-06-02", "mismatch"),
+            ("2025-06-03", "missing"),
+            ("2025-06-20", "orphaned"),
+        ]
+    );
+    let mismatch = &body["discrepancies"][0];
+    assert_eq!(
+        mismatch["fields"],
+        serde_json::json!(["awakenings", "quality"])
+    );
+    assert_eq!(mismatch["stored"]["quality"], 1);
+    assert_eq!(mismatch["computed"]["quality"], 3);
+    assert_eq!(body["discrepancies"][1]["stored"], serde_json::Value::Null);
+    assert_eq!(
+        body["discrepancies"][2]["computed"],
+        serde_json::Value::Null
+    );
+
+    // A range outside the drift is clean.
+    let res = client
+        .get(format!(
+            "http://{addr}/api/admin/daily-sleep/verify?from=2025-06-04&to=2025-06-10"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["ok"], true);
+    assert_eq!(body["nights"], 1);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/admin/daily-sleep/verify?from=2025-06-10&to=2025-06-01"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
@@ -0,0 +1,181 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_dashboard_payload() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // 21:40 on June 10th in Tokyo (the default timezone), 80 minutes before the default bedtime.
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-06-10T21:40:00+09:00")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let url = format!("http://{addr}/api/dashboard");
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let empty: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(empty["as_of"], "2025-06-10");
+    assert_eq!(empty["days"], 30);
+    assert!(empty["last_night"].is_null());
+    assert_eq!(empty["week"]["nights_logged"], 0);
+    assert!(empty["week"]["avg_duration_min"].is_null());
+    assert_eq!(empty["reminders"][0]["kind"], "log_sleep");
+    assert_eq!(empty["reminders"][1]["kind"], "bedtime");
+    assert_eq!(empty["reminders"][1]["minutes_until"], 80);
+
+    // Wake dates June 1st (outside the week) and June 8th..=10th, 6 hours each.
+    for (day, quality, feeling) in [(1, 2, None), (8, 3, None), (9, 4, None), (10, 5, Some(3))] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": format!("2025-06-{day:02}"),
+                "bed_time": "00:00:00",
+                "wake_time": "06:00:00",
+                "latency_min": 0,
+                "awakenings": 0,
+                "quality": quality,
+                "wake_feeling": feeling,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201, "day {day}");
+    }
+
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let dash: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(dash["last_night"]["wake_date"], "2025-06-10");
+    assert_eq!(dash["last_night"]["duration_min"], 360);
+    assert_eq!(dash["week"]["nights_logged"], 3);
+    assert_eq!(dash["week"]["avg_quality"], 4.0);
+    assert_eq!(dash["period"]["nights_logged"], 4);
+    assert_eq!(dash["period"]["avg_quality"], 3.5);
+    assert_eq!(dash["streaks"]["logging"], 3);
+    // Default goal is 480 minutes: 120 short on each logged night of the week.
+    assert_eq!(dash["sleep_debt_min"], 360);
+    assert_eq!(dash["reminders"].as_array().unwrap().len(), 1);
+    assert_eq!(dash["reminders"][0]["kind"], "bedtime");
+
+    let res = client.get(format!("{url}?days=7")).send().await.unwrap();
+    let week: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(week["period"]["nights_logged"], 3);
+
+    for bad in ["0", "366", "x"] {
+        let res = client
+            .get(format!("{url}?days={bad}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "days={bad}");
+    }
+
+    server.abort();
+}
@@ -0,0 +1,209 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+const IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1";
+
+type Bodies = std::sync::Arc<std::sync::Mutex<Vec<String>>>;
+
+/// Local webhook receiver recording the body of every POST.
+async fn spawn_receiver() -> (std::net::SocketAddr, Bodies, tokio::task::JoinHandle<()>) {
+    let received = Bodies::default();
+    let store = received.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |body: String| {
+            let store = store.clone();
+            async move {
+                store.lock().unwrap().push(body);
+                axum::http::StatusCode::NO_CONTENT
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (addr, received, handle)
+}
+
+#[tokio::test]
+async fn test_login_devices_are_recorded_and_new_ones_notify() {
+    let (hook_addr, received, receiver) = spawn_receiver().await;
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("NOTIFY_WEBHOOK_URL", format!("http://{hook_addr}/hook"));
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let laptop = Client::builder()
+        .cookie_store(true)
+        .user_agent(FIREFOX)
+        .build()
+        .unwrap();
+    wait_ready(&laptop, &addr.to_string()).await;
+    // The first device ever seen, then a repeat login from it: no notifications.
+    for _ in 0..2 {
+        login_and_get_auth(
+            &laptop,
+            &addr.to_string(),
+            "admin@example.com",
+            "password123",
+        )
+        .await;
+    }
+    let url = format!("http://{addr}/api/account/devices");
+    let devices: serde_json::Value = laptop.get(&url).send().await.unwrap().json().await.unwrap();
+    assert_eq!(devices.as_array().unwrap().len(), 1);
+    assert_eq!(devices[0]["label"], "Firefox on Linux");
+    assert_eq!(devices[0]["login_count"], 2);
+    assert_eq!(devices[0]["current"], true);
+
+    let phone = Client::builder()
+        .cookie_store(true)
+        .user_agent(IPHONE)
+        .build()
+        .unwrap();
+    let (phone_csrf, _) = login_and_get_auth(
+        &phone,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // Delivery runs in the background.
+    for _ in 0..50 {
+        if !received.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let bodies = received.lock().unwrap().clone();
+    assert_eq!(bodies.len(), 1);
+    let payload: serde_json::Value = serde_json::from_str(&bodies[0]).unwrap();
+    assert_eq!(payload["kind"], "new_device");
+    assert_eq!(
+        payload["body"],
+        "Signed in from a new device: Safari on iOS"
+    );
+
+    let devices: serde_json::Value = phone.get(&url).send().await.unwrap().json().await.unwrap();
+    let devices = devices.as_array().unwrap();
+    assert_eq!(devices.len(), 2);
+    let laptop_device = devices
+        .iter()
+        .find(|d| d["label"] == "Firefox on Linux")
+        .unwrap();
+    assert_eq!(laptop_device["current"], false);
+    let phone_device = devices
+        .iter()
+        .find(|d| d["label"] == "Safari on iOS")
+        .unwrap();
+    assert_eq!(phone_device["current"], true);
+
+    let res = phone
+        .delete(format!(
+            "http://{addr}/api/account/devices/{}",
+            laptop_device["id"]
+        ))
+        .header("X-CSRF-Token", &phone_csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let devices: serde_json::Value = phone.get(&url).send().await.unwrap().json().await.unwrap();
+    assert_eq!(devices.as_array().unwrap().len(), 1);
+
+    let res = laptop.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 200, "forgetting a device keeps its session");
+
+    server.abort();
+    receiver.abort();
+}
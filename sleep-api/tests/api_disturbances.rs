@@ -0,0 +1,242 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_disturbances_crud_and_awakenings() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let post = |body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}/api/disturbances"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+
+    // Invalid type and duration
+    let res = post(serde_json::json!({
+        "date": "2025-06-01", "time": "02:00:00", "type": "alarm", "duration_min": 5
+    }))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 422);
+    let res = post(serde_json::json!({
+        "date": "2025-06-01", "time": "02:00:00", "type": "pet", "duration_min": 800
+    }))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = post(serde_json::json!({
+        "date": "2025-06-01", "time": "02:00:00", "type": "pet", "duration_min": 5
+    }))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+    let id = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    let res = post(serde_json::json!({
+        "date": "2025-06-01", "time": "01:15:00", "type": "partner", "duration_min": 15
+    }))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+
+    // Update
+    let res = client
+        .put(format!("http://{addr}/api/disturbances/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-01", "time": "03:00:00", "type": "noise", "duration_min": 20
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .put(format!("http://{addr}/api/disturbances/9999"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-01", "time": "03:00:00", "type": "noise", "duration_min": 20
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // Day view listing is ordered by time
+    let res = client
+        .get(format!(
+            "http://{addr}/api/disturbances?from=2025-06-01&to=2025-06-01"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items: serde_json::Value = res.json().await.unwrap();
+    let items = items.as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["type"], "partner");
+    assert_eq!(items[1]["type"], "noise");
+    assert_eq!(items[1]["time"], "03:00:00");
+    assert_eq!(items[1]["duration_min"], 20);
+
+    // Two nights of sleep: disturbed (3 awakenings) and undisturbed (1)
+    for (date, awakenings) in [("2025-06-01", 3), ("2025-06-02", 1)] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:00:00", "wake_time": "07:00:00",
+                "latency_min": 10, "awakenings": awakenings, "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/awakenings?from=2025-06-01&to=2025-06-02"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["disturbed"]["nights"], 1);
+    assert_eq!(body["disturbed"]["avg_awakenings"], 3.0);
+    assert_eq!(body["undisturbed"]["avg_awakenings"], 1.0);
+    assert_eq!(body["by_type"][0]["type"], "noise");
+    assert_eq!(body["by_type"][0]["total_minutes"], 20);
+    assert_eq!(body["by_type"][1]["type"], "partner");
+    assert_eq!(body["by_type"][1]["events"], 1);
+
+    // Delete
+    let res = client
+        .delete(format!("http://{addr}/api/disturbances/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .get(format!(
+            "http://{addr}/api/disturbances?from=2025-06-01&to=2025-06-01"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let items: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(items.as_array().unwrap().len(), 1);
+
+    server.abort();
+}
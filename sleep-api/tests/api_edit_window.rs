@@ -0,0 +1,117 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+#[tokio::test]
+async fn test_edit_window_blocks_old_entries_unless_overridden() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("FROZEN_TIME", "2025-06-30T12:00:00+09:00");
+        std::env::set_var("EDIT_WINDOW_DAYS", "30");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({"email": "admin@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    let csrf = parse_cookie(headers.iter(), "csrf=").expect("missing CSRF cookie");
+    let session = parse_cookie(headers.iter(), "session=").expect("missing session cookie");
+
+    let post_note = |date: &'static str, override_header: Option<&'static str>| {
+        let mut req = client
+            .post(format!("http://{addr}/api/note"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({"date": date, "body": "late entry"}));
+        if let Some(value) = override_header {
+            req = req.header("X-Admin-Override", value);
+        }
+        req.send()
+    };
+
+    // Cutoff is 2025-05-31 (30 days before the frozen "today").
+    let res = post_note("2025-05-30", None).await.unwrap();
+    assert_eq!(res.status(), 403);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "forbidden");
+
+    let res = post_note("2025-05-31", None).await.unwrap();
+    assert_eq!(res.status(), 201);
+
+    let res = post_note("2025-05-30", Some("something-else"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = post_note("2025-05-30", Some("edit-window")).await.unwrap();
+    assert_eq!(res.status(), 201);
+
+    server.abort();
+}
@@ -0,0 +1,246 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_exercise_list_update_delete() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let mut ids = Vec::new();
+    for input in [
+        serde_json::json!({ "date": "2025-06-02", "intensity": "hard",
+            "start_time": "18:00:00", "duration_min": 45 }),
+        serde_json::json!({ "date": "2025-06-01", "intensity": "light" }),
+        serde_json::json!({ "date": "2025-06-02", "intensity": "light" }),
+        serde_json::json!({ "date": "2025-07-01", "intensity": "hard" }),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/exercise"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&input)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+        let created: serde_json::Value = res.json().await.unwrap();
+        ids.push(created["id"].as_i64().unwrap());
+    }
+
+    let list_url = format!("http://{addr}/api/exercise?from=2025-06-01&to=2025-06-30");
+    let events: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        events,
+        serde_json::json!([
+            { "id": ids[1], "date": "2025-06-01", "intensity": "light",
+              "start_time": null, "duration_min": null },
+            { "id": ids[0], "date": "2025-06-02", "intensity": "hard",
+              "start_time": "18:00:00", "duration_min": 45 },
+            { "id": ids[2], "date": "2025-06-02", "intensity": "light",
+              "start_time": null, "duration_min": null },
+        ])
+    );
+
+    let exercise_url = format!("http://{addr}/api/exercise/{}", ids[0]);
+    let update = serde_json::json!({ "date": "2025-06-03", "intensity": "light",
+        "start_time": "07:30:00", "duration_min": 30 });
+    let res = client
+        .put(&exercise_url)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403, "update without CSRF header");
+    let res = client
+        .put(&exercise_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let events: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(events[2]["id"], ids[0]);
+    assert_eq!(events[2]["date"], "2025-06-03");
+    assert_eq!(events[2]["start_time"], "07:30:00");
+
+    // A date keeps a single daily intensity entry.
+    let res = client
+        .put(&exercise_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "date": "2025-06-01", "intensity": "hard" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = client
+        .put(&exercise_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "date": "2025-06-03", "intensity": "extreme" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = client
+        .put(format!("http://{addr}/api/exercise/999999"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .delete(&exercise_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "reason": "duplicate" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    // Deleting again is idempotent.
+    let res = client
+        .delete(&exercise_url)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let events: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(events.as_array().unwrap().len(), 2);
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_log WHERE entity = 'exercise' AND action = 'delete'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, 1);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/exercise?from=2025-01-01&to=2025-06-30"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
@@ -35,14 +35,13 @@ fn parse_cookie<'a>(
     name_with_eq: &str,
 ) -> Option<String> {
     for hv in headers {
-        if let Ok(s) = hv.to_str() {
-            if s.starts_with(name_with_eq) {
-                if let Some(eq_idx) = s.find('=') {
-                    let rest = &s[eq_idx + 1..];
-                    let end = rest.find(';').unwrap_or(rest.len());
-                    return Some(rest[..end].to_string());
-                }
-            }
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
         }
     }
     None
@@ -76,6 +75,8 @@ async fn login_and_get_auth(
 struct DateIntensity {
     date: chrono::NaiveDate,
     intensity: String, // "none" | "light" | "hard"
+    sessions: i64,
+    total_min: i64,
 }
 
 #[tokio::test]
@@ -167,6 +168,7 @@ async fn test_exercise_intensity_range_endpoint() {
         chrono::NaiveDate::from_ymd_opt(2025, 6, 12).unwrap()
     );
     assert_eq!(items[2].intensity, "hard");
+    assert_eq!((items[2].sessions, items[2].total_min), (1, 0));
 
     // Invalid range: from > to => 400
     let res = client
@@ -180,3 +182,121 @@ async fn test_exercise_intensity_range_endpoint() {
 
     server.abort();
 }
+
+#[tokio::test]
+async fn test_custom_intensity_levels() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let exercise = |date: &str, intensity: &str, start: &str| {
+        client
+            .post(format!("http://{addr}/api/exercise"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "intensity": intensity,
+                "start_time": start, "duration_min": 30
+            }))
+            .send()
+    };
+    let set_levels = |levels: serde_json::Value| {
+        client
+            .post(format!("http://{addr}/api/settings/intensity-levels"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "levels": levels }))
+            .send()
+    };
+
+    // Unknown until configured
+    let res = exercise("2025-06-10", "moderate", "07:00:00")
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Built-in levels must stay, in order
+    let res = set_levels(serde_json::json!(["none", "light", "moderate"]))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = set_levels(serde_json::json!(["none", "hard", "light"]))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = set_levels(serde_json::json!(["none", "light", "Moderate!", "hard"]))
+        .await
+        .unwrap();
+    assert!(res.status().is_client_error());
+
+    let res = set_levels(serde_json::json!(["none", "light", "moderate", "hard"]))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = client
+        .get(format!("http://{addr}/api/settings/intensity-levels"))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body["levels"],
+        serde_json::json!(["none", "light", "moderate", "hard"])
+    );
+
+    // moderate ranks between light and hard for the per-day maximum
+    for (date, intensity, start) in [
+        ("2025-06-10", "light", "07:00:00"),
+        ("2025-06-10", "moderate", "18:00:00"),
+        ("2025-06-11", "moderate", "07:00:00"),
+        ("2025-06-11", "hard", "18:00:00"),
+    ] {
+        let res = exercise(date, intensity, start).await.unwrap();
+        assert_eq!(res.status(), 201, "{date} {intensity}");
+    }
+    let res = client
+        .get(format!(
+            "http://{addr}/api/exercise/intensity?from=2025-06-10&to=2025-06-11"
+        ))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    let items: Vec<DateIntensity> = res.json().await.unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].intensity, "moderate");
+    assert_eq!(items[1].intensity, "hard");
+    assert_eq!((items[1].sessions, items[1].total_min), (2, 60));
+
+    server.abort();
+}
@@ -0,0 +1,201 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::security::signature::sign;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_exercise_zones_from_strava_and_garmin() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("INGEST_SECRET_STRAVA", "strava-secret");
+        std::env::set_var("INGEST_SECRET_GARMIN", "garmin-secret");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let push = |source: &'static str, secret: &'static [u8], body: serde_json::Value| {
+        let body = body.to_string();
+        client
+            .post(format!("http://{addr}/api/ingest/{source}"))
+            .header("X-Signature-256", sign(secret, body.as_bytes()))
+            .body(body)
+            .send()
+    };
+
+    // 10/20/15/10/5 minutes: a quarter in Z4–Z5, so hard.
+    let strava = serde_json::json!([{
+        "id": 111, "type": "Run", "start_date_local": "2025-06-01T18:30:00Z",
+        "elapsed_time": 3600, "moving_time": 3500,
+        "zones": [
+            {"type": "power", "distribution_buckets": []},
+            {"type": "heartrate", "distribution_buckets": [
+                {"min": 0, "max": 120, "time": 600}, {"min": 120, "max": 140, "time": 1200},
+                {"min": 140, "max": 155, "time": 900}, {"min": 155, "max": 170, "time": 600},
+                {"min": 170, "max": -1, "time": 300}]}
+        ]
+    }]);
+    let res = push("strava", b"strava-secret", strava).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["exercise_imported"], 1);
+
+    // 5/15/10/0/0 minutes, and a walk recorded without a heart-rate sensor.
+    let garmin = serde_json::json!([
+        {"activityId": 222, "startTimeLocal": "2025-06-01 07:00:00", "duration": 1800.0,
+         "hrTimeInZone_1": 300.0, "hrTimeInZone_2": 900.0, "hrTimeInZone_3": 600.0,
+         "hrTimeInZone_4": 0.0, "hrTimeInZone_5": 0.0},
+        {"activityId": 223, "startTimeLocal": "2025-06-02 12:00:00", "duration": 1200.0}
+    ]);
+    let res = push("garmin", b"garmin-secret", garmin).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["exercise_imported"], 2);
+
+    let intensities: Vec<(String, String)> = sqlx::query_as(
+        "SELECT r.source, e.intensity FROM exercise_events e \
+         JOIN external_refs r ON r.exercise_id = e.id ORDER BY r.external_id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        intensities,
+        vec![
+            ("strava".to_string(), "hard".to_string()),
+            ("garmin".to_string(), "light".to_string()),
+            ("garmin".to_string(), "light".to_string()),
+        ]
+    );
+
+    let zones_url = format!("http://{addr}/api/exercise/zones?from=2025-06-01&to=2025-06-07");
+    let res = client.get(&zones_url).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let res = client.get(&zones_url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let zones: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        zones,
+        serde_json::json!({
+            "from": "2025-06-01",
+            "to": "2025-06-07",
+            "sessions": 2,
+            "totals": {"z1": 15, "z2": 35, "z3": 25, "z4": 10, "z5": 5},
+            "days": [{"date": "2025-06-01", "sessions": 2,
+                      "zones": {"z1": 15, "z2": 35, "z3": 25, "z4": 10, "z5": 5}}]
+        })
+    );
+
+    // Zones may also be entered by hand, but only with a duration.
+    let zones = serde_json::json!({"z1": 0, "z2": 30, "z3": 10, "z4": 0, "z5": 0});
+    for (duration, status) in [(None, 400), (Some(40), 201)] {
+        let res = client
+            .post(format!("http://{addr}/api/exercise"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": "2025-06-03", "intensity": "light", "start_time": "07:00:00",
+                "duration_min": duration, "hr_zones": zones,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status);
+    }
+    let res = client.get(&zones_url).send().await.unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["sessions"], 3);
+    assert_eq!(body["totals"]["z2"], 65);
+
+    server.abort();
+}
@@ -0,0 +1,235 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_experiments_crud_and_results() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // Baseline 2025-05-25..31: ~7h; experiment 2025-06-01..07: ~8h.
+    for day in 0..14 {
+        let date =
+            chrono::NaiveDate::from_ymd_opt(2025, 5, 25).unwrap() + chrono::Duration::days(day);
+        let wake = match (day >= 7, day % 2) {
+            (false, 0) => "05:50:00",
+            (false, _) => "06:10:00",
+            (true, 0) => "06:50:00",
+            (true, _) => "07:10:00",
+        };
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:00:00", "wake_time": wake,
+                "latency_min": 10, "awakenings": 1, "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let post = |body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}/api/experiments"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+    let res = post(serde_json::json!({
+        "name": "Backwards", "start_date": "2025-06-07", "end_date": "2025-06-01"
+    }))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = post(serde_json::json!({
+        "name": "  No caffeine  ", "start_date": "2025-06-01", "end_date": "2025-06-07",
+        "description": "No coffee after waking"
+    }))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+    let id = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{addr}/api/experiments"))
+        .send()
+        .await
+        .unwrap();
+    let list: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(list[0]["name"], "No caffeine");
+    assert_eq!(list[0]["end_date"], "2025-06-07");
+
+    let res = client
+        .get(format!("http://{addr}/api/experiments/{id}/results"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["baseline"], "before");
+    assert_eq!(body["period_from"], "2025-06-01");
+    let duration = &body["metrics"][0];
+    assert_eq!(duration["metric"], "duration_min");
+    assert_eq!(duration["during"]["n"], 7);
+    assert_eq!(duration["baseline"]["n"], 7);
+    let diff = duration["diff"].as_f64().unwrap();
+    assert!((diff - 60.0).abs() < 5.0, "diff {diff}");
+    let (low, high) = (
+        duration["ci95_low"].as_f64().unwrap(),
+        duration["ci95_high"].as_f64().unwrap(),
+    );
+    assert!(low > 0.0 && low < diff && diff < high);
+    assert!(duration["p_value"].as_f64().unwrap() < 0.05);
+    assert!(duration["bootstrap_ci95_low"].as_f64().unwrap() > 0.0);
+    assert!(
+        duration["caveats"][0]
+            .as_str()
+            .unwrap()
+            .starts_with("Small sample")
+    );
+    // Identical awakenings: zero-width interval.
+    assert_eq!(body["metrics"][3]["diff"], 0.0);
+    assert!(body["metrics"][4]["ci95_low"].is_null());
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/experiments/{id}/results?baseline=outside"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["metrics"][0]["baseline"]["n"], 7);
+
+    // Update, then delete
+    let res = client
+        .put(format!("http://{addr}/api/experiments/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"name": "No caffeine", "start_date": "2025-06-01"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .delete(format!("http://{addr}/api/experiments/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .get(format!("http://{addr}/api/experiments/{id}/results"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    server.abort();
+}
@@ -0,0 +1,253 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_export_streams_all_records() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // More nights than one export page, so the body spans several chunks.
+    let start = chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+    let nights = sleep_api::data_export::EXPORT_PAGE_ROWS as usize + 120;
+    for day in 0..nights {
+        let input = sleep_api::models::SleepInput {
+            date: start + chrono::Duration::days(day as i64),
+            bed_time: chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            wake_time: chrono::NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            latency_min: 10,
+            awakenings: 1,
+            quality: sleep_api::models::Quality(4),
+            wake_feeling: None,
+            sleep_inertia_min: None,
+            waso_min: None,
+            aids: Vec::new(),
+            tags: Vec::new(),
+            timezone: None,
+        };
+        sleep_api::repository::insert_sleep(&pool, &input, 470)
+            .await
+            .unwrap();
+    }
+    sqlx::query(
+        "INSERT INTO exercise_events(date, intensity, start_time, duration_min) \
+         VALUES ('2023-01-02', 'light', '18:30:00', 45)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO notes(date, body) VALUES ('2023-01-03', 'late coffee, \"oops\"')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let res = client
+        .get(format!("http://{addr}/api/export"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let res = client
+        .get(format!("http://{addr}/api/export"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(res.headers()["accept-ranges"], "bytes");
+    let etag = res.headers()["etag"].to_str().unwrap().to_string();
+    let whole = res.bytes().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&whole).unwrap();
+    let sleep = body["sleep"].as_array().unwrap();
+    assert_eq!(sleep.len(), nights);
+    assert_eq!(sleep[0]["date"], "2023-01-01");
+    assert!(
+        sleep
+            .windows(2)
+            .all(|w| w[0]["date"].as_str() < w[1]["date"].as_str())
+    );
+    assert_eq!(sleep[0]["duration_min"], 470);
+    assert_eq!(body["exercise"][0]["intensity"], "light");
+    assert_eq!(body["exercise"][0]["duration_min"], 45);
+    assert_eq!(body["notes"][0]["body"], "late coffee, \"oops\"");
+
+    // Resuming: the same data gives the same ETag, so If-Range honours the range.
+    let res = client
+        .get(format!("http://{addr}/api/export"))
+        .header("Range", "bytes=100-")
+        .header("If-Range", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 206);
+    assert_eq!(
+        res.headers()["content-range"],
+        format!("bytes 100-{}/{}", whole.len() - 1, whole.len()).as_str()
+    );
+    assert_eq!(res.headers()["etag"], etag.as_str());
+    assert_eq!(res.bytes().await.unwrap(), whole.slice(100..));
+    let res = client
+        .get(format!("http://{addr}/api/export"))
+        .header("Range", "bytes=100-")
+        .header("If-Range", "\"stale\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.bytes().await.unwrap(), whole);
+    let res = client
+        .get(format!("http://{addr}/api/export"))
+        .header("Range", format!("bytes={}-", whole.len()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 416);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/export?format=csv&from=2023-01-02&to=2023-01-03"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "text/csv; charset=utf-8");
+    assert!(
+        res.headers()["content-disposition"]
+            .to_str()
+            .unwrap()
+            .contains("sleep-export.csv")
+    );
+    let csv = res.text().await.unwrap();
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    assert_eq!(
+        reader.headers().unwrap(),
+        sleep_api::data_export::CSV_HEADER
+    );
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    let kinds: Vec<&str> = rows.iter().map(|r| &r[0]).collect();
+    assert_eq!(kinds, vec!["sleep", "sleep", "exercise", "note"]);
+    assert_eq!(&rows[2][11], "light");
+    assert_eq!(&rows[3][13], "late coffee, \"oops\"");
+
+    // Open-ended ranges and validation
+    let body: serde_json::Value = client
+        .get(format!("http://{addr}/api/export?from=2024-09-01&to="))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let since =
+        nights - (chrono::NaiveDate::from_ymd_opt(2024, 9, 1).unwrap() - start).num_days() as usize;
+    assert_eq!(body["sleep"].as_array().unwrap().len(), since);
+    assert!(body["notes"].as_array().unwrap().is_empty());
+    for query in ["format=xml", "from=2024-02-01&to=2024-01-01", "to=tomorrow"] {
+        let res = client
+            .get(format!("http://{addr}/api/export?{query}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "{query}");
+    }
+
+    server.abort();
+}
@@ -0,0 +1,70 @@
+use reqwest::Client;
+use sleep_api::{app, db};
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+#[tokio::test]
+async fn test_unknown_routes_and_methods_answer_problem_json() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+
+    let pool = db::connect().await.unwrap();
+    let app = app::router(pool);
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let res = client
+        .get(format!("http://{addr}/api/no-such-route"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.headers()["content-type"], "application/problem+json");
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "route_not_found");
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["path"], "/api/no-such-route");
+
+    let res = client
+        .delete(format!("http://{addr}/api/health"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 405);
+    assert_eq!(res.headers()["content-type"], "application/problem+json");
+    let allow = res.headers()["allow"].to_str().unwrap().to_string();
+    let mut methods: Vec<&str> = allow.split(',').map(str::trim).collect();
+    methods.sort_unstable();
+    assert_eq!(methods, ["GET", "HEAD"]);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "method_not_allowed");
+    assert_eq!(body["method"], "DELETE");
+    assert_eq!(body["path"], "/api/health");
+
+    // A write-only route does not advertise GET.
+    let res = client
+        .get(format!("http://{addr}/api/sleep"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 405);
+    assert_eq!(res.headers()["allow"], "POST");
+
+    server.abort();
+}
@@ -0,0 +1,228 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_weekly_report_feed() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // Wednesday June 18th, 12:00 in Tokyo (the default timezone): W24 is the newest full week.
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-06-18T12:00:00+09:00")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    let reader = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+    let feed_url = format!("http://{addr}/api/feeds/reports.xml");
+
+    let res = reader.get(&feed_url).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+    let res = reader
+        .get(&feed_url)
+        .query(&[("token", "slt_unknown")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    // W23: 8h (quality 4) and 7h (quality 3); W24: 8h (quality 5); June 17th is this week.
+    for (day, bed, wake, quality) in [
+        ("2025-06-03", "23:00:00", "07:00:00", 4),
+        ("2025-06-05", "23:00:00", "06:00:00", 3),
+        ("2025-06-10", "22:00:00", "06:00:00", 5),
+        ("2025-06-17", "22:00:00", "06:00:00", 1),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": day,
+                "bed_time": bed,
+                "wake_time": wake,
+                "latency_min": 0,
+                "awakenings": 0,
+                "quality": quality,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201, "{day}");
+    }
+    let res = client
+        .post(format!("http://{addr}/api/tokens"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"name": "feed reader", "scope": "read"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let created: serde_json::Value = res.json().await.unwrap();
+    let secret = created["secret"].as_str().unwrap().to_string();
+
+    let res = reader
+        .get(&feed_url)
+        .query(&[("token", &secret)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok()),
+        Some("application/atom+xml; charset=utf-8")
+    );
+    let xml = res.text().await.unwrap();
+    assert!(
+        xml.contains("<updated>2025-06-16T00:00:00+09:00</updated>"),
+        "{xml}"
+    );
+    assert_eq!(xml.matches("<entry>").count(), 2, "{xml}");
+    let w24 = xml.find("Week 2025-W24 (2025-06-09 – 2025-06-15)").unwrap();
+    let w23 = xml.find("Week 2025-W23 (2025-06-02 – 2025-06-08)").unwrap();
+    assert!(w24 < w23, "newest first: {xml}");
+    assert!(xml.contains("Average sleep: 7h 30m"), "{xml}");
+    assert!(xml.contains("Average quality: 3.5 / 5"), "{xml}");
+    assert!(
+        xml.contains("+30m average sleep vs. the previous week"),
+        "{xml}"
+    );
+    assert!(
+        !xml.contains("W25"),
+        "the current week is not reported: {xml}"
+    );
+
+    // Bearer works too, and the text follows Accept-Language.
+    let res = reader
+        .get(&feed_url)
+        .bearer_auth(&secret)
+        .header("Accept-Language", "ja")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let xml = res.text().await.unwrap();
+    assert!(xml.contains("記録した夜: 1"), "{xml}");
+
+    // Revoking the token turns the feed off.
+    let id = created["token"]["id"].as_i64().unwrap();
+    let res = client
+        .delete(format!("http://{addr}/api/tokens/{id}"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let res = reader
+        .get(&feed_url)
+        .query(&[("token", &secret)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    server.abort();
+}
@@ -0,0 +1,226 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_goals_and_sleep_debt() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let goal: serde_json::Value = client
+        .get(format!("http://{addr}/api/goals"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(goal["target_duration_min"], 480);
+
+    let put = |duration: i64| {
+        client
+            .put(format!("http://{addr}/api/goals"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "target_bedtime": "22:30:00", "target_duration_min": duration }))
+            .send()
+    };
+    assert_eq!(put(60).await.unwrap().status(), 400);
+    assert_eq!(put(470).await.unwrap().status(), 200);
+    // The settings route reads the same goal.
+    let goal: serde_json::Value = client
+        .get(format!("http://{addr}/api/settings/sleep-goal"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        goal,
+        serde_json::json!({"target_bedtime": "22:30:00", "target_duration_min": 470})
+    );
+
+    // 460 minutes (night plus nap) on the 2nd, 480 on the 4th.
+    for (date, bed, wake) in [
+        ("2025-06-02", "23:00:00", "06:00:00"),
+        ("2025-06-02", "13:00:00", "13:40:00"),
+        ("2025-06-04", "23:00:00", "07:00:00"),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date,
+                "bed_time": bed,
+                "wake_time": wake,
+                "latency_min": 5,
+                "awakenings": 0,
+                "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/sleep-debt?from=2025-06-01&to=2025-06-04"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let debt: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(debt["target_duration_min"], 470);
+    assert_eq!(debt["nights_logged"], 2);
+    assert_eq!(debt["deficit_min"], 10);
+    assert_eq!(debt["surplus_min"], 10);
+    assert_eq!(debt["balance_min"], 0);
+    let balances: Vec<(serde_json::Value, i64)> = debt["days"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| (d["delta_min"].clone(), d["balance_min"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(
+        balances,
+        [
+            (serde_json::Value::Null, 0),
+            (serde_json::json!(-10), -10),
+            (serde_json::Value::Null, -10),
+            (serde_json::json!(10), 0),
+        ]
+    );
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/sleep-debt?from=2024-01-01&to=2025-06-04"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let summary: serde_json::Value = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-06-01&to=2025-06-04"
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(summary["goal_adherence_pct"], 50.0);
+
+    let summary: serde_json::Value = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-07-01&to=2025-07-04"
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(summary["goal_adherence_pct"].is_null());
+
+    server.abort();
+}
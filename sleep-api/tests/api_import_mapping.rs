@@ -0,0 +1,278 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_mapping_preview_and_import() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let mapping = serde_json::json!({
+        "date": "Night of",
+        "bed_time": "In bed",
+        "wake_time": "Up",
+        "quality": "Rating",
+    });
+    let bad_csv = "Night of,In bed,Up,Rating\n\
+                   2025-06-01,23:10,6:45,4\n\
+                   2025-06-02,late,7:00,3\n";
+    let good_csv = "Night of,In bed,Up,Rating\n\
+                    2025-06-01,23:10,6:45,4\n\
+                    2025-06-02,23:30,7:00,\n";
+
+    let post = |path: &'static str, csv: &'static str| {
+        let client = client.clone();
+        let csrf = csrf.clone();
+        let mapping = mapping.clone();
+        async move {
+            client
+                .post(format!("http://{addr}/api/import/{path}"))
+                .header("X-CSRF-Token", &csrf)
+                .json(&serde_json::json!({ "csv": csv, "mapping": mapping }))
+                .send()
+                .await
+                .unwrap()
+        }
+    };
+
+    let res = post("mapping-preview", bad_csv).await;
+    assert_eq!(res.status(), 200);
+    let preview: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(preview["total_rows"], 2);
+    assert_eq!(preview["valid_rows"], 1);
+    assert_eq!(preview["rows"][0]["row"], 2);
+    assert_eq!(preview["rows"][0]["input"]["bed_time"], "23:10:00");
+    assert_eq!(preview["issues"][0]["row"], 3);
+    assert_eq!(preview["issues"][0]["column"], "In bed");
+
+    // Invalid rows block the whole import.
+    let res = post("with-mapping", bad_csv).await;
+    assert_eq!(res.status(), 400);
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sleep_sessions")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+
+    let res = post("with-mapping", good_csv).await;
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary, serde_json::json!({"imported": 2, "skipped": 0}));
+
+    // Re-running the same import skips the recorded nights.
+    let res = post("with-mapping", good_csv).await;
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary, serde_json::json!({"imported": 0, "skipped": 2}));
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_sleep_csv_upload() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let mapping =
+        r#"{"date": "Night of", "bed_time": "In bed", "wake_time": "Up", "quality": "Rating"}"#;
+    let bad_csv = "Night of,In bed,Up,Rating\n\
+                   2025-06-01,23:10,6:45,4\n\
+                   2025-06-02,late,7:00,3\n\
+                   2025-06-03,23:00,7:00,9\n";
+    // The third row repeats the first night and is skipped as an overlap.
+    let good_csv = "\u{feff}Night of,In bed,Up,Rating\n\
+                    2025-06-01,23:10,6:45,4\n\
+                    2025-06-02,23:30,7:00,\n\
+                    2025-06-01,23:10,6:45,4\n";
+    let upload = |query: &'static str, csv: &'static str, with_mapping: bool| {
+        let client = client.clone();
+        let csrf = csrf.clone();
+        async move {
+            let mut form = reqwest::multipart::Form::new().part(
+                "file",
+                reqwest::multipart::Part::text(csv)
+                    .file_name("sleep.csv")
+                    .mime_str("text/csv")
+                    .unwrap(),
+            );
+            if with_mapping {
+                form = form.text("mapping", mapping);
+            }
+            client
+                .post(format!("http://{addr}/api/import/sleep{query}"))
+                .header("X-CSRF-Token", &csrf)
+                .multipart(form)
+                .send()
+                .await
+                .unwrap()
+        }
+    };
+    let count = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM sleep_sessions")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    // Every rejected row is reported and nothing is written.
+    let res = upload("", bad_csv, true).await;
+    assert_eq!(res.status(), 422);
+    let report: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(report["total_rows"], 3);
+    assert_eq!(report["imported"], 0);
+    let rows: Vec<_> = report["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["row"].as_u64().unwrap())
+        .collect();
+    assert_eq!(rows, vec![3, 4]);
+    assert_eq!(count().await, 0);
+
+    // A dry run reports the outcome of a real run without writing.
+    let res = upload("?dry_run=true", good_csv, true).await;
+    assert_eq!(res.status(), 200);
+    let report: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["imported"], 2);
+    assert_eq!(report["skipped"], 1);
+    assert_eq!(count().await, 0);
+
+    let res = upload("", good_csv, true).await;
+    assert_eq!(res.status(), 200);
+    let report: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(report["dry_run"], false);
+    assert_eq!(report["imported"], 2);
+    assert_eq!(report["skipped"], 1);
+    assert!(report["errors"].as_array().unwrap().is_empty());
+    assert_eq!(count().await, 2);
+
+    let res = upload("", good_csv, false).await;
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
@@ -0,0 +1,210 @@
+use reqwest::Client;
+use sleep_api::security::signature::sign;
+use sleep_api::{app, db};
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+#[tokio::test]
+async fn test_ingest_requires_signature_and_skips_repeats() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("INGEST_SECRET_TASKER", "tasker-secret");
+        std::env::remove_var("INGEST_SECRET_HEALTH_AUTO_EXPORT");
+    };
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let body = serde_json::json!({
+        "sleep": [{"date": "2025-06-02", "bed_time": "23:00", "wake_time": "07:00",
+                   "latency_min": 10, "awakenings": 1, "quality": 4}],
+        "exercise": [{"date": "2025-06-01", "intensity": "hard",
+                      "start_time": "18:00:00", "duration_min": 45}]
+    })
+    .to_string();
+    let url = format!("http://{addr}/api/ingest/tasker");
+
+    // Unsigned and wrongly signed pushes are rejected.
+    let res = client.post(&url).body(body.clone()).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+    let res = client
+        .post(&url)
+        .header("X-Signature-256", sign(b"wrong", body.as_bytes()))
+        .body(body.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let signed = sign(b"tasker-secret", body.as_bytes());
+    let res = client
+        .post(&url)
+        .header("X-Signature-256", &signed)
+        .body(body.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["sleep_imported"], 1);
+    assert_eq!(summary["exercise_imported"], 1);
+
+    // A retried push records nothing new.
+    let res = client
+        .post(&url)
+        .header("X-Signature-256", &signed)
+        .body(body.clone())
+        .send()
+        .await
+        .unwrap();
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["sleep_skipped"], 1);
+    assert_eq!(summary["exercise_skipped"], 1);
+    let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sleep_sessions")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(sessions, 1);
+
+    // Invalid entries reject the whole push.
+    let bad = r#"{"exercise":[{"date":"2025-06-03","intensity":"light","start_time":"07:00:00","duration_min":0}]}"#;
+    let res = client
+        .post(&url)
+        .header("X-Signature-256", sign(b"tasker-secret", bad.as_bytes()))
+        .body(bad)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Sources without a configured secret are disabled.
+    let res = client
+        .post(format!("http://{addr}/api/ingest/health-auto-export"))
+        .header("X-Signature-256", sign(b"", b"{}"))
+        .body("{}")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_ingest_links_external_refs_and_resyncs_idempotently() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("INGEST_SECRET_TASKER", "tasker-secret");
+    };
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+    let url = format!("http://{addr}/api/ingest/tasker");
+    let push = |body: String| {
+        client
+            .post(&url)
+            .header("X-Signature-256", sign(b"tasker-secret", body.as_bytes()))
+            .body(body)
+            .send()
+    };
+
+    let payload = |wake: &str, start: &str| {
+        serde_json::json!({
+            "sleep": [{"date": "2025-06-02", "bed_time": "23:00", "wake_time": wake,
+                       "latency_min": 10, "awakenings": 1, "quality": 4,
+                       "external_ref": {"source": "fitbit", "external_id": "28811",
+                                        "url": "https://www.fitbit.com/sleep/2025-06-02"}}],
+            "exercise": [{"date": "2025-06-01", "intensity": "hard",
+                          "start_time": start, "duration_min": 45,
+                          "external_ref": {"source": "strava", "external_id": "99001"}}]
+        })
+        .to_string()
+    };
+    let res = push(payload("07:00", "18:00:00")).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["sleep_imported"], 1);
+    assert_eq!(summary["exercise_imported"], 1);
+
+    let date = chrono::NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+    let sessions = sleep_api::repository::find_sleep_by_date(&pool, date)
+        .await
+        .unwrap();
+    assert_eq!(sessions.len(), 1);
+    let refs = &sessions[0].external_refs;
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].source, "fitbit");
+    assert_eq!(refs[0].external_id, "28811");
+    assert_eq!(
+        refs[0].url.as_deref(),
+        Some("https://www.fitbit.com/sleep/2025-06-02")
+    );
+
+    // Re-syncing entries edited at the source (different times) recognizes them by reference.
+    let res = push(payload("07:30", "18:15:00")).await.unwrap();
+    let summary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(summary["sleep_skipped"], 1);
+    assert_eq!(summary["exercise_skipped"], 1);
+    let exercise: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM exercise_events")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(exercise, 1);
+
+    // Deleting the session drops its reference.
+    sleep_api::repository::delete_sleep(&pool, sessions[0].id)
+        .await
+        .unwrap();
+    let refs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM external_refs")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(refs, 1);
+
+    // Malformed references reject the push.
+    let bad = serde_json::json!({
+        "exercise": [{"date": "2025-06-03", "intensity": "light", "start_time": "07:00:00",
+                      "duration_min": 20,
+                      "external_ref": {"source": "strava", "external_id": "1",
+                                       "url": "javascript:alert(1)"}}]
+    })
+    .to_string();
+    assert_eq!(push(bad).await.unwrap().status(), 400);
+
+    server.abort();
+}
@@ -0,0 +1,258 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{
+    app, db,
+    events::EventBus,
+    handlers::{self, EditLock},
+    integrity,
+};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_admin_integrity_check() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let res = client
+        .get(format!("http://{addr}/api/admin/integrity"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    for (query, check) in [("", "quick"), ("?check=full", "full")] {
+        let res = client
+            .get(format!("http://{addr}/api/admin/integrity{query}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let report: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(report["ok"], true);
+        assert_eq!(report["check"], check);
+        assert_eq!(report["problems"], serde_json::json!([]));
+    }
+    let res = client
+        .get(format!("http://{addr}/api/admin/integrity?check=deep"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let health: serde_json::Value = client
+        .get(format!("http://{addr}/api/health"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(health["status"], "ok");
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_corrupt_database_is_degraded_and_salvaged() {
+    unsafe {
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    let dir = std::env::temp_dir().join(format!("sleep-integrity-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("sleep.db");
+
+    // Enough nights to span many pages; VACUUM leaves no free pages to corrupt harmlessly.
+    let pool = db::connect_file(&path).await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let time = handlers::TimeContext::fixed(chrono::Utc::now(), chrono_tz::Asia::Tokyo);
+    let first = chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
+    for day in 0..1000 {
+        let input = serde_json::from_value(serde_json::json!({
+            "date": first + chrono::Duration::days(day),
+            "bed_time": "23:00", "wake_time": "07:00",
+            "latency_min": 10, "awakenings": 1, "quality": 4
+        }))
+        .unwrap();
+        handlers::create_sleep(&pool, &EventBus::new(), &time, &EditLock::none(), input)
+            .await
+            .unwrap();
+    }
+    sqlx::query("VACUUM").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    // Overwrite one page in the middle of the file with garbage.
+    let mut bytes = std::fs::read(&path).unwrap();
+    let page_size = u16::from_be_bytes([bytes[16], bytes[17]]) as usize;
+    let page = bytes.len() / page_size / 2;
+    bytes[page * page_size..(page + 1) * page_size].fill(0xA5);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let pool = db::connect_file(&path).await.unwrap();
+    let integrity = integrity::startup_check(&pool, chrono::Utc::now()).await;
+    assert!(integrity.degraded());
+    let report = integrity.last().unwrap();
+    assert!(!report.ok);
+    assert!(!report.problems.is_empty());
+
+    let app = app::router_with_state(app::AppState {
+        integrity,
+        ..app::AppState::new(pool.clone(), sleep_api::config::session_key())
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let health: serde_json::Value = client
+        .get(format!("http://{addr}/api/health"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(health["status"], "degraded");
+    assert_eq!(health["integrity"]["ok"], false);
+
+    let res = client
+        .post(format!("http://{addr}/api/note"))
+        .json(&serde_json::json!({"date": "2025-01-01", "body": "x"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 503);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "degraded");
+
+    server.abort();
+    pool.close().await;
+
+    let dest = dir.join("salvaged.db");
+    let salvaged = integrity::salvage(&path, &dest).await.unwrap();
+    assert!(salvaged.integrity.ok, "{:?}", salvaged.integrity.problems);
+    let sleeps = salvaged
+        .tables
+        .iter()
+        .find(|t| t.table == "sleep_sessions")
+        .unwrap();
+    assert!(sleeps.copied > 0);
+    assert!(sleeps.copied <= 1000);
+
+    // The salvaged file opens healthy, and salvage never overwrites.
+    let recovered = db::connect_file(&dest).await.unwrap();
+    let check = integrity::check(
+        &recovered,
+        integrity::IntegrityCheck::Full,
+        chrono::Utc::now(),
+    )
+    .await
+    .unwrap();
+    assert!(check.ok);
+    recovered.close().await;
+    assert!(integrity::salvage(&path, &dest).await.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,164 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+use chrono::{Duration, TimeZone, Utc};
+use sleep_api::security::lockout::{self, LockoutPolicy};
+
+async fn migrated_pool() -> sleep_api::db::Db {
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    pool
+}
+
+#[tokio::test]
+async fn test_login_locks_after_repeated_failures() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("RATE_LIMIT_LOGIN_PER_MIN", "0");
+        std::env::set_var("RATE_LIMIT_LOGIN_ACCOUNT_PER_MIN", "0");
+        std::env::set_var("LOGIN_LOCKOUT_THRESHOLD", "3");
+        std::env::set_var("LOGIN_LOCKOUT_BASE_SECS", "60");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = migrated_pool().await;
+    let frozen = Utc.with_ymd_and_hms(2025, 6, 10, 8, 0, 0).unwrap();
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+    let login = |password: &'static str| {
+        client
+            .post(format!("http://{addr}/api/login.json"))
+            .json(&serde_json::json!({"email": "Admin@example.com", "password": password}))
+            .send()
+    };
+
+    for _ in 0..2 {
+        assert_eq!(login("wrong").await.unwrap().status(), 401);
+    }
+    let res = login("wrong").await.unwrap();
+    assert_eq!(res.status(), 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({"error": "locked", "retry_after_s": 60})
+    );
+
+    // While locked, even the right password is refused, without being checked.
+    let res = login("password123").await.unwrap();
+    assert_eq!(res.status(), 429);
+
+    // The form login shares the lock.
+    let res = client
+        .post(format!("http://{addr}/api/login"))
+        .form(&[("email", "admin@example.com"), ("password", "password123")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 429);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_lockout_backs_off_exponentially_and_resets() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+    };
+    let pool = migrated_pool().await;
+    let policy = LockoutPolicy {
+        threshold: 2,
+        base_secs: 60,
+        max_secs: 200,
+    };
+    let keys = lockout::keys("admin@example.com", "203.0.113.7".parse().ok());
+    let t0 = Utc
+        .with_ymd_and_hms(2025, 6, 10, 8, 0, 0)
+        .unwrap()
+        .naive_utc();
+
+    let fail = |now| lockout::record_failure(&pool, &policy, &keys, now);
+    assert_eq!(fail(t0).await.unwrap(), None);
+    assert_eq!(fail(t0).await.unwrap(), Some(60));
+    assert_eq!(
+        lockout::locked_for(&pool, &keys, t0 + Duration::seconds(15))
+            .await
+            .unwrap(),
+        Some(45)
+    );
+    // The address alone is locked too.
+    let other_account = lockout::keys("someone@example.com", "203.0.113.7".parse().ok());
+    assert!(
+        lockout::locked_for(&pool, &other_account, t0)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let t1 = t0 + Duration::seconds(61);
+    assert_eq!(lockout::locked_for(&pool, &keys, t1).await.unwrap(), None);
+    fail(t1).await.unwrap();
+    assert_eq!(fail(t1).await.unwrap(), Some(120));
+    let t2 = t1 + Duration::seconds(121);
+    fail(t2).await.unwrap();
+    assert_eq!(fail(t2).await.unwrap(), Some(200), "capped at max_secs");
+
+    // A day without failures starts over.
+    let t3 = t2 + Duration::hours(25);
+    fail(t3).await.unwrap();
+    assert_eq!(fail(t3).await.unwrap(), Some(60));
+
+    // A successful login clears the history.
+    lockout::record_success(&pool, &keys).await.unwrap();
+    assert_eq!(lockout::locked_for(&pool, &keys, t3).await.unwrap(), None);
+    assert_eq!(fail(t3).await.unwrap(), None);
+}
@@ -0,0 +1,292 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_medications_crud_and_summary_overlay() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let post = |path: &str, body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}{path}"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+
+    // Medications: blank name rejected, duplicate name rejected
+    let res = post("/api/medications", serde_json::json!({"name": "  "}))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = post(
+        "/api/medications",
+        serde_json::json!({"name": " Melatonin ", "dose": "3 mg"}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+    let melatonin = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    let res = post("/api/medications", serde_json::json!({"name": "Melatonin"}))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = post(
+        "/api/medications",
+        serde_json::json!({"name": "Zolpidem", "prescription": true}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+    let zolpidem = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    let res = client
+        .put(format!("http://{addr}/api/medications/{zolpidem}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"name": "Zolpidem", "dose": "5 mg", "prescription": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .put(format!("http://{addr}/api/medications/9999"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"name": "Other"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("http://{addr}/api/medications"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let meds: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(meds[0]["name"], "Melatonin");
+    assert_eq!(meds[0]["prescription"], false);
+    assert_eq!(meds[1]["dose"], "5 mg");
+    assert_eq!(meds[1]["prescription"], true);
+
+    // Events: unknown medication rejected; two melatonin nights and one zolpidem night
+    let res = post(
+        "/api/medication-events",
+        serde_json::json!({"medication_id": 9999, "date": "2025-06-02"}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = post(
+        "/api/medication-events",
+        serde_json::json!({"medication_id": melatonin, "date": "2025-06-02", "time": "22:30"}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+    let event = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    for body in [
+        serde_json::json!({"medication_id": melatonin, "date": "2025-06-02", "dose": "1 mg"}),
+        serde_json::json!({"medication_id": melatonin, "date": "2025-06-03"}),
+        serde_json::json!({"medication_id": zolpidem, "date": "2025-06-10"}),
+    ] {
+        let res = post("/api/medication-events", body).await.unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .put(format!("http://{addr}/api/medication-events/{event}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "medication_id": melatonin, "date": "2025-06-02", "time": "23:00:00", "dose": "5 mg"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/medication-events?from=2025-06-02&to=2025-06-02"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items: serde_json::Value = res.json().await.unwrap();
+    let items = items.as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    // Untimed events sort first; a missing dose falls back to the medication's usual one.
+    assert_eq!(items[0]["dose"], "1 mg");
+    assert_eq!(items[1]["name"], "Melatonin");
+    assert_eq!(items[1]["time"], "23:00:00");
+    assert_eq!(items[1]["dose"], "5 mg");
+    let res = client
+        .get(format!(
+            "http://{addr}/api/medication-events?from=2025-06-03&to=2025-06-03"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let items: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(items[0]["dose"], "3 mg");
+
+    // Summary overlay counts nights per medication and bucket
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-06-01&to=2025-06-14&bucket=week"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body["medication_by_bucket"],
+        serde_json::json!([
+            {"bucket": "2025-W23", "medication": "Melatonin", "nights": 2},
+            {"bucket": "2025-W24", "medication": "Zolpidem", "nights": 1}
+        ])
+    );
+
+    // Deleting an event, then a medication with its events
+    let res = client
+        .delete(format!("http://{addr}/api/medication-events/{event}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .delete(format!("http://{addr}/api/medications/{zolpidem}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .get(format!(
+            "http://{addr}/api/medication-events?from=2025-06-01&to=2025-06-14"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let items: serde_json::Value = res.json().await.unwrap();
+    let items = items.as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().all(|e| e["name"] == "Melatonin"));
+
+    server.abort();
+}
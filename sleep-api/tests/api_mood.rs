@@ -0,0 +1,174 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_mood_record_list_and_summary() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let post = |body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}/api/mood"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+
+    for mood in [0, 6] {
+        let res = post(serde_json::json!({"date": "2025-06-02", "mood": mood}))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "mood {mood}");
+    }
+    // Re-posting a date replaces its rating
+    for (date, mood) in [
+        ("2025-06-02", 1),
+        ("2025-06-02", 4),
+        ("2025-06-03", 3),
+        ("2025-06-10", 5),
+    ] {
+        let res = post(serde_json::json!({"date": date, "mood": mood}))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 204, "{date}");
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/mood?from=2025-06-01&to=2025-06-07"
+        ))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        items,
+        serde_json::json!([
+            {"date": "2025-06-02", "mood": 4},
+            {"date": "2025-06-03", "mood": 3}
+        ])
+    );
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-06-01&to=2025-06-14&bucket=week"
+        ))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body["mood_by_bucket"],
+        serde_json::json!([
+            {"bucket": "2025-W23", "avg_mood": 3.5, "days": 2},
+            {"bucket": "2025-W24", "avg_mood": 5.0, "days": 1}
+        ])
+    );
+
+    server.abort();
+}
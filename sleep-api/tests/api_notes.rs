@@ -0,0 +1,387 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_notes_list_update_delete() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let mut ids = Vec::new();
+    for (date, body) in [
+        ("2025-06-02", "late coffee"),
+        ("2025-06-01", "slept well"),
+        ("2025-07-01", "outside the range"),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/note"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "date": date, "body": body }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+        let created: serde_json::Value = res.json().await.unwrap();
+        ids.push(created["id"].as_i64().unwrap());
+    }
+
+    let list_url = format!("http://{addr}/api/notes?from=2025-06-01&to=2025-06-30");
+    let notes: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let bodies: Vec<&str> = notes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["body"].as_str().unwrap())
+        .collect();
+    assert_eq!(bodies, ["slept well", "late coffee"]);
+
+    // Updating keeps the star.
+    let note_url = format!("http://{addr}/api/note/{}", ids[0]);
+    let res = client
+        .post(format!("{note_url}/star"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let update = serde_json::json!({ "date": "2025-06-03", "body": "coffee at 4pm" });
+    let res = client.put(&note_url).json(&update).send().await.unwrap();
+    assert_eq!(res.status(), 403, "update without CSRF header");
+    let res = client
+        .put(&note_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let starred: serde_json::Value = client
+        .get(format!("http://{addr}/api/starred"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(starred["notes"][0]["body"], "coffee at 4pm");
+    assert_eq!(starred["notes"][0]["date"], "2025-06-03");
+
+    let res = client
+        .put(format!("http://{addr}/api/note/999999"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let too_long = serde_json::json!({ "date": "2025-06-03", "body": "x".repeat(1001) });
+    let res = client
+        .put(&note_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&too_long)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .delete(&note_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "reason": "duplicate" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    // Deleting again is idempotent.
+    let res = client
+        .delete(&note_url)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let notes: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(notes.as_array().unwrap().len(), 1);
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_log WHERE entity = 'note' AND action = 'delete'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, 1);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/notes?from=2025-01-01&to=2025-06-30"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_note_linked_to_session() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let mut sleep = serde_json::json!({
+        "date": "2025-06-10",
+        "bed_time": "23:00:00",
+        "wake_time": "07:00:00",
+        "latency_min": 10,
+        "awakenings": 1,
+        "quality": 4
+    });
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&sleep)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let created: serde_json::Value = res.json().await.unwrap();
+    let session_id = created["id"].as_i64().unwrap();
+    let sleep_url = format!("http://{addr}/api/sleep/{session_id}");
+
+    // The note's date must be the session's wake date, and the session must exist.
+    for (body, status) in [
+        (
+            serde_json::json!({ "date": "2025-06-09", "body": "noisy", "session_id": session_id }),
+            400,
+        ),
+        (
+            serde_json::json!({ "date": "2025-06-10", "body": "noisy", "session_id": 999999 }),
+            400,
+        ),
+        (
+            serde_json::json!({ "date": "2025-06-10", "body": "noisy neighbours", "session_id": session_id }),
+            201,
+        ),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/note"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status, "{body}");
+    }
+    let res = client
+        .post(format!("http://{addr}/api/note"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "date": "2025-06-10", "body": "unlinked" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    let session: serde_json::Value = client
+        .get(&sleep_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let notes = session["notes"].as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["body"], "noisy neighbours");
+    assert_eq!(notes[0]["session_id"], session_id);
+
+    // Moving the session moves its linked note, but not the unlinked one.
+    sleep["date"] = serde_json::json!("2025-06-11");
+    let res = client
+        .put(&sleep_url)
+        .header("X-CSRF-Token", &csrf)
+        .json(&sleep)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let list_url = format!("http://{addr}/api/notes?from=2025-06-01&to=2025-06-30");
+    let notes: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let dated: Vec<(&str, &str)> = notes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| (n["date"].as_str().unwrap(), n["body"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        dated,
+        [
+            ("2025-06-10", "unlinked"),
+            ("2025-06-11", "noisy neighbours")
+        ]
+    );
+
+    // Deleting the session keeps the note and clears the link.
+    let res = client
+        .delete(&sleep_url)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let notes: serde_json::Value = client
+        .get(&list_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let notes = notes.as_array().unwrap();
+    assert_eq!(notes.len(), 2);
+    assert!(notes.iter().all(|n| n["session_id"].is_null()));
+
+    server.abort();
+}
@@ -0,0 +1,467 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_sleep_goal_and_bedtime_status() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let res = client
+        .post(format!("http://{addr}/api/settings/timezone"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "timezone": "Asia/Tokyo" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    // Default goal, then an invalid and a valid update
+    let res = client
+        .get(format!("http://{addr}/api/settings/sleep-goal"))
+        .send()
+        .await
+        .unwrap();
+    let goal: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        goal,
+        serde_json::json!({"target_bedtime": "23:00:00", "target_duration_min": 480})
+    );
+    for (duration, status) in [(60, 400), (450, 200)] {
+        let res = client
+            .post(format!("http://{addr}/api/settings/sleep-goal"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "target_bedtime": "22:30:00", "target_duration_min": duration
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status);
+    }
+
+    // Two short nights in the debt window: 360 and 420 minutes against a 450 target.
+    for (date, bed) in [("2025-06-01", "00:00:00"), ("2025-06-02", "23:00:00")] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": bed, "wake_time": "06:00:00",
+                "latency_min": 10, "awakenings": 0, "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    // 20:00 in Tokyo, sent by a client in UTC.
+    let res = client
+        .get(format!(
+            "http://{addr}/api/now/bedtime-status?now=2025-06-02T11:00:00Z"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let status: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(status["timezone"], "Asia/Tokyo");
+    assert_eq!(status["local_time"], "2025-06-02T20:00:00");
+    assert_eq!(status["bedtime_at"], "2025-06-02T22:30:00");
+    assert_eq!(status["minutes_until_bedtime"], 150);
+    assert_eq!(status["sleep_debt_min"], 120);
+    assert_eq!(status["nights_logged"], 2);
+    assert_eq!(
+        status["recommendation"],
+        "Sleep debt is 2h 00m; aim for bed 1h 00m early, in 1h 30m."
+    );
+
+    // Accept-Language wins; otherwise the saved locale applies.
+    let status_url = format!("http://{addr}/api/now/bedtime-status?now=2025-06-02T11:00:00Z");
+    let recommendation = |lang: Option<&'static str>| {
+        let mut req = client.get(&status_url);
+        if let Some(lang) = lang {
+            req = req.header("Accept-Language", lang);
+        }
+        async move {
+            let body: serde_json::Value = req.send().await.unwrap().json().await.unwrap();
+            body["recommendation"].as_str().unwrap().to_string()
+        }
+    };
+    let ja =
+        "睡眠負債は2時間00分です。いつもより1時間00分早く、1時間30分後の就寝を目指しましょう。";
+    assert_eq!(recommendation(Some("ja-JP,en;q=0.5")).await, ja);
+    for (locale, status) in [("fr", 400), ("ja", 204)] {
+        let res = client
+            .post(format!("http://{addr}/api/settings/locale"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "locale": locale }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status);
+    }
+    let res = client
+        .get(format!("http://{addr}/api/settings/locale"))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["locale"], "ja");
+    assert_eq!(recommendation(None).await, ja);
+    assert_eq!(recommendation(Some("fr")).await, ja);
+    assert!(
+        recommendation(Some("en-US"))
+            .await
+            .starts_with("Sleep debt")
+    );
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/now/bedtime-status?now=yesterday"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_frozen_clock_drives_default_now() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // 21:40 in Tokyo (the default timezone), 80 minutes before the default 23:00 bedtime.
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-06-02T21:40:00+09:00")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    for _ in 0..2 {
+        let res = client
+            .get(format!("http://{addr}/api/now/bedtime-status"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let status: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(status["local_time"], "2025-06-02T21:40:00");
+        assert_eq!(status["minutes_until_bedtime"], 80);
+    }
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_day_boundary_sets_logical_today() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let today_at = |now: &'static str| {
+        let client = client.clone();
+        async move {
+            let res = client
+                .get(format!("http://{addr}/api/now/today?now={now}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 200);
+            let today: serde_json::Value = res.json().await.unwrap();
+            today["date"].as_str().unwrap().to_string()
+        }
+    };
+
+    // 01:30 on June 2nd in Tokyo (the default timezone).
+    let late = "2025-06-01T16:30:00Z";
+    // 19:00 on June 1st in Tokyo.
+    let evening = "2025-06-01T10:00:00Z";
+
+    let res = client
+        .get(format!("http://{addr}/api/settings/day-boundary"))
+        .send()
+        .await
+        .unwrap();
+    let boundary: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(boundary, serde_json::json!({"day_start": "00:00:00"}));
+    assert_eq!(today_at(late).await, "2025-06-02");
+
+    for (day_start, status) in [("04:00:30", 400), ("04:00:00", 200)] {
+        let res = client
+            .post(format!("http://{addr}/api/settings/day-boundary"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "day_start": day_start }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status, "day_start {day_start}");
+    }
+    assert_eq!(today_at(late).await, "2025-06-01");
+    assert_eq!(today_at(evening).await, "2025-06-01");
+
+    let res = client
+        .post(format!("http://{addr}/api/settings/day-boundary"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "day_start": "18:00:00" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(today_at(evening).await, "2025-06-02");
+    assert_eq!(today_at(late).await, "2025-06-02");
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_now_reports_server_time_wake_date_and_logged_sleep() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // 23:30 in New York on the evening clocks sprang forward (EDT, UTC-4).
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-03-10T03:30:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    let res = client
+        .post(format!("http://{addr}/api/settings/timezone"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "timezone": "America/New_York" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let now = || async {
+        let res = client
+            .get(format!("http://{addr}/api/now"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        res.json::<serde_json::Value>().await.unwrap()
+    };
+    let status = now().await;
+    assert_eq!(status["now"], "2025-03-10T03:30:00Z");
+    assert_eq!(status["timezone"], "America/New_York");
+    assert_eq!(status["utc_offset_min"], -240);
+    assert_eq!(status["local_time"], "2025-03-09T23:30:00");
+    assert_eq!(status["wake_date"], "2025-03-09");
+    assert_eq!(status["sleep_logged"], false);
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-03-09", "bed_time": "23:00:00", "wake_time": "07:00:00",
+            "latency_min": 10, "awakenings": 0, "quality": 3
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    assert_eq!(now().await["sleep_logged"], true);
+
+    server.abort();
+}
@@ -0,0 +1,90 @@
+use argon2::password_hash::PasswordHash;
+use reqwest::Client;
+use sleep_api::security::password::HashPolicy;
+use sleep_api::{app, db};
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+async fn login_status(client: &Client, addr: &str, password: &str) -> u16 {
+    client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": "admin@example.com", "password": password }))
+        .send()
+        .await
+        .expect("login request failed")
+        .status()
+        .as_u16()
+}
+
+async fn stored_hash(pool: &db::Db) -> Option<String> {
+    sqlx::query_scalar("SELECT hash FROM password_hashes WHERE account = 'admin@example.com'")
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_weak_hash_is_upgraded_on_login() {
+    let weak = HashPolicy {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    };
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("ADMIN_EMAIL", "admin@example.com");
+        std::env::set_var("ADMIN_PASSWORD_HASH", weak.hash(b"password123").unwrap());
+        std::env::set_var("ARGON2_MEMORY_KIB", "4096");
+        std::env::set_var("ARGON2_ITERATIONS", "2");
+    };
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::builder().build().unwrap();
+    wait_ready(&client, &addr).await;
+
+    // A failed login does not rehash.
+    assert_eq!(login_status(&client, &addr, "wrong").await, 401);
+    assert!(stored_hash(&pool).await.is_none());
+
+    assert_eq!(login_status(&client, &addr, "password123").await, 200);
+    let upgraded = stored_hash(&pool).await.expect("hash was not upgraded");
+    let policy = sleep_api::config::password_hash_policy();
+    assert!(policy.is_met_by(&PasswordHash::new(&upgraded).unwrap()));
+
+    // The upgraded hash is used from now on and is not replaced again.
+    assert_eq!(login_status(&client, &addr, "password123").await, 200);
+    assert_eq!(stored_hash(&pool).await.as_deref(), Some(upgraded.as_str()));
+    assert_eq!(login_status(&client, &addr, "wrong").await, 401);
+
+    // Changing ADMIN_PASSWORD_HASH takes effect despite the stored hash.
+    unsafe {
+        std::env::set_var("ADMIN_PASSWORD_HASH", policy.hash(b"rotated456").unwrap());
+    }
+    assert_eq!(login_status(&client, &addr, "password123").await, 401);
+    assert_eq!(login_status(&client, &addr, "rotated456").await, 200);
+    assert_eq!(stored_hash(&pool).await.as_deref(), Some(upgraded.as_str()));
+
+    server.abort();
+}
@@ -0,0 +1,159 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_plan_week_around_busy_times() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    // Monday June 2nd, 12:00 in Tokyo (the default timezone).
+    let frozen = chrono::DateTime::parse_from_rfc3339("2025-06-02T12:00:00+09:00")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // An early flight on Wednesday and a long night out before Friday.
+    let busy = "2025-06-04T06:00/2025-06-04T09:00,2025-06-05T19:00/2025-06-06T02:00";
+    let res = client
+        .get(format!("http://{addr}/api/plan/week?busy={busy}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let plan: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(plan["as_of"], "2025-06-02");
+    let nights = plan["nights"].as_array().unwrap();
+    assert_eq!(nights.len(), 7);
+    assert_eq!(nights[0]["wake_date"], "2025-06-03");
+    assert_eq!(nights[0]["bed_at"], "2025-06-02T23:00:00");
+    assert_eq!(nights[0]["attainable"], true);
+
+    assert_eq!(nights[1]["wake_date"], "2025-06-04");
+    assert_eq!(nights[1]["bed_at"], "2025-06-03T22:00:00");
+    assert_eq!(nights[1]["wake_at"], "2025-06-04T06:00:00");
+    assert_eq!(nights[1]["shift_min"], -60);
+
+    assert_eq!(nights[3]["wake_date"], "2025-06-06");
+    assert_eq!(nights[3]["attainable"], false);
+    assert_eq!(nights[3]["bed_at"], "2025-06-06T02:00:00");
+    assert_eq!(nights[3]["planned_duration_min"], 420);
+
+    for bad in [
+        "2025-06-04T06:00",
+        "2025-06-04T09:00/2025-06-04T06:00",
+        "soon/later",
+    ] {
+        let res = client
+            .get(format!("http://{addr}/api/plan/week?busy={bad}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "busy={bad}");
+    }
+
+    server.abort();
+}
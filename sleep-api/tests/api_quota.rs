@@ -0,0 +1,227 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("server did not become ready");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie");
+    (csrf, session)
+}
+
+async fn serve(pool: sqlx::SqlitePool) -> String {
+    let app = app::router(pool);
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr.to_string()
+}
+
+#[tokio::test]
+async fn test_quotas_limit_entries_body_size_and_rate() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("QUOTA_SESSIONS_PER_DAY", "1");
+        std::env::set_var("QUOTA_NOTES_PER_DAY", "2");
+        std::env::set_var("QUOTA_MAX_BODY_BYTES", "4096");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let addr = serve(pool.clone()).await;
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr).await;
+    let (csrf, session) =
+        login_and_get_auth(&client, &addr, "admin@example.com", "password123").await;
+    let auth = format!("session={session}; csrf={csrf}");
+
+    let post = |path: &'static str, body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}{path}"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+    let sleep = |date: &str| {
+        serde_json::json!({
+            "date": date, "bed_time": "23:00:00", "wake_time": "07:00:00",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        })
+    };
+
+    assert_eq!(
+        post("/api/sleep", sleep("2025-06-01"))
+            .await
+            .unwrap()
+            .status(),
+        201
+    );
+    let res = post(
+        "/api/sleep",
+        serde_json::json!({
+            "date": "2025-06-01", "bed_time": "13:00:00", "wake_time": "14:00:00",
+            "latency_min": 5, "awakenings": 0, "quality": 3
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 429);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "quota_exceeded");
+    // Other days are unaffected
+    assert_eq!(
+        post("/api/sleep", sleep("2025-06-02"))
+            .await
+            .unwrap()
+            .status(),
+        201
+    );
+
+    for expected in [201, 201, 429] {
+        let res = post(
+            "/api/note",
+            serde_json::json!({"date": "2025-06-01", "body": "ok"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), expected);
+    }
+
+    let res = post(
+        "/api/note",
+        serde_json::json!({"date": "2025-06-03", "body": "x".repeat(5000)}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 413);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "payload_too_large");
+
+    // Rate limit: a router built with QUOTA_API_CALLS_PER_MIN counts calls per user
+    unsafe { std::env::set_var("QUOTA_API_CALLS_PER_MIN", "3") };
+    let limited = serve(pool.clone()).await;
+    unsafe { std::env::remove_var("QUOTA_API_CALLS_PER_MIN") };
+    wait_ready(&client, &limited).await;
+    // Each router has its own session key
+    let (csrf, session) =
+        login_and_get_auth(&client, &limited, "admin@example.com", "password123").await;
+    let auth = format!("session={session}; csrf={csrf}");
+    let mut statuses = Vec::new();
+    for _ in 0..4 {
+        let res = client
+            .get(format!("http://{limited}/api/sleep/recent"))
+            .header("Cookie", &auth)
+            .send()
+            .await
+            .unwrap();
+        statuses.push(res.status().as_u16());
+        if res.status() == 429 {
+            let retry: u64 = res.headers()["retry-after"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((1..=60).contains(&retry));
+        }
+    }
+    assert_eq!(statuses, vec![200, 200, 200, 429]);
+    // Logins are never counted: anonymous calls cannot lock the owner out
+    let anonymous = Client::new();
+    let mut statuses = Vec::new();
+    for _ in 0..4 {
+        let res = anonymous
+            .get(format!("http://{limited}/api/sleep/recent"))
+            .send()
+            .await
+            .unwrap();
+        statuses.push(res.status().as_u16());
+    }
+    assert_eq!(statuses, vec![401, 401, 401, 429]);
+    let res = anonymous
+        .post(format!("http://{limited}/api/login.json"))
+        .json(&serde_json::json!({"email": "admin@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    // Health checks are never limited
+    let res = client
+        .get(format!("http://{limited}/api/health"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+}
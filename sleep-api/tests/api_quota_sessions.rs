@@ -0,0 +1,209 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use chrono::TimeZone;
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+async fn serve_at(pool: sqlx::SqlitePool, frozen: chrono::DateTime<chrono::Utc>) -> String {
+    let app = app::router_with_state(app::AppState {
+        db: pool,
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr.to_string()
+}
+
+#[tokio::test]
+async fn test_sessions_per_day_counts_inferred_dates() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("QUOTA_SESSIONS_PER_DAY", "1");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    // 02:30 UTC on June 10: a wake time later in the day belongs to June 9.
+    let frozen = chrono::Utc.with_ymd_and_hms(2025, 6, 10, 2, 30, 0).unwrap();
+    let addr = serve_at(pool.clone(), frozen).await;
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr).await;
+    let (csrf, _) = login_and_get_auth(&client, &addr, "admin@example.com", "password123").await;
+
+    let post = |path: &'static str, body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}{path}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+    let res = post(
+        "/api/sleep",
+        serde_json::json!({
+            "date": "2025-06-09", "bed_time": "22:00:00", "wake_time": "01:00:00",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 201);
+
+    // No date in the body: the quota applies to the inferred one.
+    let res = post(
+        "/api/sleep?infer_date=true",
+        serde_json::json!({
+            "bed_time": "13:00", "wake_time": "14:00",
+            "latency_min": 5, "awakenings": 0, "quality": 3
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 429);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "quota_exceeded");
+    assert!(
+        body["message"].as_str().unwrap().contains("2025-06-09"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn test_sessions_per_day_counts_timer_stops() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("QUOTA_SESSIONS_PER_DAY", "1");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let frozen = chrono::Utc.with_ymd_and_hms(2025, 6, 10, 2, 30, 0).unwrap();
+    let addr = serve_at(pool.clone(), frozen).await;
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr).await;
+    let (csrf, _) = login_and_get_auth(&client, &addr, "admin@example.com", "password123").await;
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-10", "bed_time": "22:00:00", "wake_time": "01:00:00",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    // A timer started at 01:30 stops at 02:30 on the same, already full, wake date.
+    sleep_api::repository::start_sleep_timer(
+        &pool,
+        frozen.naive_utc() - chrono::Duration::hours(1),
+    )
+    .await
+    .unwrap();
+    let res = client
+        .post(format!("http://{addr}/api/sleep/stop"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 429);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "quota_exceeded");
+    let res = client
+        .get(format!("http://{addr}/api/sleep/active"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+}
@@ -0,0 +1,266 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_login_and_write_rate_limits() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("RATE_LIMIT_LOGIN_PER_MIN", "4");
+        std::env::set_var("RATE_LIMIT_LOGIN_ACCOUNT_PER_MIN", "2");
+        std::env::set_var("RATE_LIMIT_WRITES_PER_MIN", "3");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let login_url = format!("http://{addr}/api/login.json");
+    let attempt = |email: &'static str, password: &'static str| {
+        client
+            .post(&login_url)
+            .json(&serde_json::json!({ "email": email, "password": password }))
+            .send()
+    };
+
+    // Two failures lock the account, whatever the case of the email or the password.
+    assert_eq!(
+        attempt("admin@example.com", "nope").await.unwrap().status(),
+        401
+    );
+    assert_eq!(
+        attempt("Admin@Example.com", "nope").await.unwrap().status(),
+        401
+    );
+    let res = attempt("admin@example.com", "password123").await.unwrap();
+    assert_eq!(res.status(), 429);
+    let retry: u64 = res.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry), "{retry}");
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "rate_limited");
+
+    // Form logins share the account bucket.
+    let res = client
+        .post(format!("http://{addr}/api/login"))
+        .form(&[("email", "admin@example.com"), ("password", "password123")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 429);
+
+    // Other accounts still fail normally until the address has used its 4 failures.
+    assert_eq!(attempt("a@example.com", "x").await.unwrap().status(), 401);
+    assert_eq!(attempt("b@example.com", "x").await.unwrap().status(), 401);
+    assert_eq!(attempt("c@example.com", "x").await.unwrap().status(), 429);
+
+    server.abort();
+
+    // A fresh router: successful logins are not counted, writes are.
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+    wait_ready(&client, &addr.to_string()).await;
+    for _ in 0..3 {
+        login_and_get_auth(
+            &client,
+            &addr.to_string(),
+            "admin@example.com",
+            "password123",
+        )
+        .await;
+    }
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let mut statuses = Vec::new();
+    for date in ["2025-06-01", "2025-06-02", "2025-06-03", "2025-06-04"] {
+        let res = client
+            .post(format!("http://{addr}/api/exercise"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "date": date, "intensity": "light" }))
+            .send()
+            .await
+            .unwrap();
+        statuses.push(res.status().as_u16());
+    }
+    assert_eq!(&statuses[..3], &[201, 201, 201], "{statuses:?}");
+    assert_eq!(statuses[3], 429);
+    // Reads are not limited.
+    let res = client
+        .get(format!(
+            "http://{addr}/api/exercise/intensity?from=2025-06-01&to=2025-06-04"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_concurrent_failed_logins_stay_within_limit() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("RATE_LIMIT_LOGIN_PER_MIN", "4");
+        std::env::set_var("RATE_LIMIT_LOGIN_ACCOUNT_PER_MIN", "2");
+        std::env::set_var("RATE_LIMIT_WRITES_PER_MIN", "3");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+
+    // Ten wrong passwords at once: only the account's 2 may reach the handler and get a 401.
+    let mut attempts = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let client = client.clone();
+        let url = format!("http://{addr}/api/login.json");
+        attempts.spawn(async move {
+            let res = client
+                .post(url)
+                .json(&serde_json::json!({ "email": "admin@example.com", "password": "nope" }))
+                .send()
+                .await
+                .unwrap();
+            let status = res.status().as_u16();
+            let body: serde_json::Value = res.json().await.unwrap_or_default();
+            (status, body["code"].as_str().map(str::to_string))
+        });
+    }
+    let mut reached = 0;
+    while let Some(result) = attempts.join_next().await {
+        match result.unwrap() {
+            (401, _) => reached += 1,
+            (429, Some(code)) if code == "rate_limited" => {}
+            other => panic!("unexpected response {other:?}"),
+        }
+    }
+    assert_eq!(reached, 2);
+
+    server.abort();
+}
@@ -0,0 +1,258 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_share_links_and_anonymized_export_are_redacted() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let addr = addr.to_string();
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-10",
+            "bed_time": "23:07:00",
+            "wake_time": "06:53:00",
+            "latency_min": 10,
+            "awakenings": 1,
+            "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let res = client
+        .post(format!("http://{addr}/api/note"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-10", "body": "argued with J."}))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    // Invalid ranges are rejected.
+    let res = client
+        .post(format!("http://{addr}/api/share-links"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"from": "2025-06-30", "to": "2025-06-01"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("http://{addr}/api/share-links"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"from": "2025-06-01", "to": "2025-06-30"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let created: serde_json::Value = res.json().await.unwrap();
+    let id = created["link"]["id"].as_i64().unwrap();
+    let secret = created["secret"].as_str().unwrap().to_string();
+    assert!(secret.starts_with("sls_"));
+
+    // Default share policy: no note bodies, times rounded to 15 minutes.
+    let anonymous = Client::new();
+    let res = anonymous
+        .get(format!("http://{addr}/api/shared/{secret}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["cache-control"], "no-store");
+    let view: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(view["sleep"][0]["bed_time"], "23:00:00");
+    assert_eq!(view["sleep"][0]["wake_time"], "07:00:00");
+    assert_eq!(view["sleep"][0]["duration_min"], 465);
+    assert_eq!(view["notes"][0]["date"], "2025-06-10");
+    assert!(view["notes"][0]["body"].is_null());
+
+    let res = client
+        .get(format!("http://{addr}/api/share-links"))
+        .send()
+        .await
+        .unwrap();
+    let links: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(links[0]["id"], id);
+    assert!(!links[0]["last_used_at"].is_null());
+
+    // Policies are validated, and a saved policy applies to the next view.
+    let mut settings: serde_json::Value = client
+        .get(format!("http://{addr}/api/settings/redaction"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(settings["public"]["time_rounding_min"], 5);
+    settings["share"]["time_rounding_min"] = 7.into();
+    let res = client
+        .post(format!("http://{addr}/api/settings/redaction"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&settings)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    settings["share"] =
+        serde_json::json!({"note_bodies": true, "time_rounding_min": 1, "check_in": true});
+    let res = client
+        .post(format!("http://{addr}/api/settings/redaction"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&settings)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let view: serde_json::Value = anonymous
+        .get(format!("http://{addr}/api/shared/{secret}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(view["sleep"][0]["bed_time"], "23:07:00");
+    assert_eq!(view["notes"][0]["body"], "argued with J.");
+
+    // The export policy is independent of the share policy.
+    let export: serde_json::Value = client
+        .get(format!("http://{addr}/api/export?anonymize=true"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(export["sleep"][0]["wake_time"], "07:00:00");
+    assert!(export["notes"][0]["body"].is_null());
+    let export: serde_json::Value = client
+        .get(format!("http://{addr}/api/export"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(export["sleep"][0]["wake_time"], "06:53:00");
+
+    // Revoked and unknown links look the same.
+    let res = client
+        .delete(format!("http://{addr}/api/share-links/{id}"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    for path in [secret.as_str(), "sls_unknown"] {
+        let res = anonymous
+            .get(format!("http://{addr}/api/shared/{path}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 404);
+    }
+
+    server.abort();
+}
@@ -0,0 +1,231 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_weekly_report_compares_with_prior_week() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let post = |path: &str, body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}{path}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+
+    for (date, bed, wake, latency, quality) in [
+        ("2025-06-10", "23:00:00", "06:00:00", 30, 3),
+        ("2025-06-16", "23:00:00", "07:00:00", 10, 4),
+        ("2025-06-17", "00:00:00", "07:00:00", 20, 2),
+    ] {
+        let res = post(
+            "/api/sleep",
+            serde_json::json!({
+                "date": date,
+                "bed_time": bed,
+                "wake_time": wake,
+                "latency_min": latency,
+                "awakenings": 0,
+                "quality": quality
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+    for (date, intensity) in [
+        ("2025-06-16", "hard"),
+        ("2025-06-16", "light"),
+        ("2025-06-18", "light"),
+        ("2025-06-19", "none"),
+    ] {
+        let res = post(
+            "/api/exercise",
+            serde_json::json!({"date": date, "intensity": intensity}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+    let mut note_ids = Vec::new();
+    for (date, body) in [
+        ("2025-06-10", "last week"),
+        ("2025-06-17", "ok night"),
+        ("2025-06-18", "worth remembering"),
+        ("2025-06-20", "latest"),
+        ("2025-06-21", "  "),
+    ] {
+        let res = post("/api/note", serde_json::json!({"date": date, "body": body}))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+        let body: serde_json::Value = res.json().await.unwrap();
+        note_ids.push(body["id"].as_i64().unwrap());
+    }
+    let res = post(
+        &format!("/api/note/{}/star", note_ids[2]),
+        serde_json::json!({}),
+    )
+    .await
+    .unwrap();
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("http://{addr}/api/reports/weekly?week=2025-W25"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let report: serde_json::Value = res.json().await.unwrap();
+    let current = &report["current"];
+    assert_eq!(current["week"], "2025-W25");
+    assert_eq!(current["from"], "2025-06-16");
+    assert_eq!(current["to"], "2025-06-22");
+    assert_eq!(current["nights_logged"], 2);
+    assert_eq!(current["avg_duration_min"], 450.0);
+    assert_eq!(current["avg_quality"], 3.0);
+    assert_eq!(current["avg_latency_min"], 15.0);
+    assert_eq!(current["bedtime_sd_min"], 30.0);
+    assert_eq!(current["exercise_days"], 2);
+
+    let prior = &report["prior"];
+    assert_eq!(prior["week"], "2025-W24");
+    assert_eq!(prior["nights_logged"], 1);
+    assert!(prior["bedtime_sd_min"].is_null());
+
+    let change = &report["change"];
+    assert_eq!(change["nights_logged"], 1);
+    assert_eq!(change["avg_duration_min"], 30.0);
+    assert_eq!(change["avg_latency_min"], -15.0);
+    assert!(change["bedtime_sd_min"].is_null());
+    assert_eq!(change["exercise_days"], 2);
+
+    let highlights: Vec<(&str, &str, bool)> = report["highlights"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| {
+            (
+                h["date"].as_str().unwrap(),
+                h["excerpt"].as_str().unwrap(),
+                h["starred"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        highlights,
+        [
+            ("2025-06-18", "worth remembering", true),
+            ("2025-06-20", "latest", false),
+            ("2025-06-17", "ok night", false),
+        ]
+    );
+
+    for week in ["2025-25", "2025-W54"] {
+        let res = client
+            .get(format!("http://{addr}/api/reports/weekly?week={week}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "{week}");
+    }
+
+    server.abort();
+}
@@ -0,0 +1,233 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_routine_checklist_recording_and_adherence() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // Default checklist
+    let res = client
+        .get(format!("http://{addr}/api/settings/routine"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let checklist: serde_json::Value = res.json().await.unwrap();
+    let ids: Vec<&str> = checklist["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        ["no_screens_after_22", "no_caffeine_after_14", "read_20_min"]
+    );
+
+    // Invalid checklist (duplicate ids)
+    let res = client
+        .post(format!("http://{addr}/api/settings/routine"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"items": [
+            {"id": "stretch", "label": "Stretch"},
+            {"id": "stretch", "label": "Stretch again"}
+        ]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Custom checklist
+    let res = client
+        .post(format!("http://{addr}/api/settings/routine"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"items": [
+            {"id": "no_screens_after_22", "label": "No screens after 22:00"},
+            {"id": "stretch", "label": "  Stretch  "}
+        ]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let saved: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(saved["items"][1]["label"], "Stretch");
+
+    // Record two evenings
+    for (date, done) in [
+        ("2025-06-01", serde_json::json!(["no_screens_after_22"])),
+        ("2025-06-02", serde_json::json!(["stretch"])),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/routine/{date}"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "done": done }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 204);
+    }
+
+    let res = client
+        .post(format!("http://{addr}/api/routine/2025-06-03"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({ "done": ["read_20_min"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .get(format!("http://{addr}/api/routine/2025-06-01"))
+        .send()
+        .await
+        .unwrap();
+    let entries: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        entries,
+        serde_json::json!([
+            {"item_id": "no_screens_after_22", "done": true},
+            {"item_id": "stretch", "done": false}
+        ])
+    );
+
+    // Sleep waking the morning after each evening
+    for (date, quality) in [("2025-06-02", 5), ("2025-06-03", 2)] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:00:00", "wake_time": "07:00:00",
+                "latency_min": 10, "awakenings": 0, "quality": quality
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/routine?from=2025-06-01&to=2025-06-02"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let screens = &body["items"][0];
+    assert_eq!(screens["id"], "no_screens_after_22");
+    assert_eq!(screens["nights_recorded"], 2);
+    assert_eq!(screens["nights_done"], 1);
+    assert_eq!(screens["adherence_pct"], 50.0);
+    assert_eq!(screens["done"]["avg_quality"], 5.0);
+    assert_eq!(screens["not_done"]["avg_quality"], 2.0);
+    assert_eq!(screens["done"]["avg_duration_min"], 480.0);
+
+    server.abort();
+}
@@ -0,0 +1,76 @@
+use reqwest::Client;
+use sleep_api::{app, db};
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+#[tokio::test]
+async fn test_schema_endpoint_mirrors_validation() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    let pool = db::connect().await.unwrap();
+    let app = app::router(pool);
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+
+    // Public: no session needed.
+    let res = client
+        .get(format!("http://{addr}/api/schema/SleepInput"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.headers()["content-type"].to_str().unwrap(),
+        "application/schema+json"
+    );
+    let schema: serde_json::Value = res.json().await.unwrap();
+    let required = schema["required"].as_array().unwrap();
+    for field in ["date", "bed_time", "wake_time", "latency_min", "quality"] {
+        assert!(required.iter().any(|r| r == field), "{field} not required");
+    }
+    assert!(!required.iter().any(|r| r == "aids"));
+    let latency = &schema["properties"]["latency_min"];
+    assert_eq!(
+        (&latency["minimum"], &latency["maximum"]),
+        (&0.into(), &180.into())
+    );
+    assert_eq!(schema["properties"]["aids"]["maxItems"], 10);
+    assert_eq!(schema["$defs"]["Quality"]["minimum"], 1);
+
+    let res = client
+        .get(format!("http://{addr}/api/schema/RoutineChecklist"))
+        .send()
+        .await
+        .unwrap();
+    let schema: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        schema["$defs"]["RoutineItem"]["properties"]["id"]["pattern"],
+        "^[a-z0-9_]+$"
+    );
+
+    let res = client
+        .get(format!("http://{addr}/api/schema/NoSuchType"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    server.abort();
+}
@@ -0,0 +1,190 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_share_link_use_limit_and_stats() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("TRUST_PROXY_HEADERS", "1");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr).await;
+    let (csrf, _) = login_and_get_auth(&client, &addr, "admin@example.com", "password123").await;
+
+    let create = |max_uses: i64| {
+        client
+            .post(format!("http://{addr}/api/share-links"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({"from": "2025-06-01", "to": "2025-06-30", "max_uses": max_uses}))
+            .send()
+    };
+    assert_eq!(create(0).await.unwrap().status(), 400);
+    let res = create(3).await.unwrap();
+    assert_eq!(res.status(), 201);
+    let created: serde_json::Value = res.json().await.unwrap();
+    let id = created["link"]["id"].as_i64().unwrap();
+    let secret = created["secret"].as_str().unwrap().to_string();
+    assert_eq!(created["link"]["use_count"], 0);
+    assert_eq!(created["link"]["max_uses"], 3);
+
+    let anonymous = Client::new();
+    let open = |ip: &'static str| {
+        anonymous
+            .get(format!("http://{addr}/api/shared/{secret}"))
+            .header("X-Forwarded-For", ip)
+            .send()
+    };
+    for ip in ["203.0.113.5", "203.0.113.5", "198.51.100.7"] {
+        assert_eq!(open(ip).await.unwrap().status(), 200);
+    }
+    // The fourth view exceeds max_uses and is neither served nor recorded.
+    assert_eq!(open("192.0.2.1").await.unwrap().status(), 404);
+
+    let res = client
+        .get(format!("http://{addr}/api/shares/{id}/stats"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let stats: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(stats["use_count"], 3);
+    assert_eq!(stats["max_uses"], 3);
+    assert_eq!(stats["distinct_ips"], 2);
+    let mut visitors: Vec<(String, i64)> = stats["visitors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| {
+            (
+                v["ip"].as_str().unwrap().to_string(),
+                v["use_count"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    visitors.sort();
+    assert_eq!(
+        visitors,
+        [
+            ("198.51.100.7".to_string(), 1),
+            ("203.0.113.5".to_string(), 2)
+        ]
+    );
+
+    let links: serde_json::Value = client
+        .get(format!("http://{addr}/api/share-links"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(links[0]["expired"], true);
+
+    let res = client
+        .delete(format!("http://{addr}/api/share-links/{id}"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .get(format!("http://{addr}/api/shares/{id}/stats"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let visitors: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM share_link_visitors")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(visitors, 0);
+
+    server.abort();
+}
@@ -37,14 +37,13 @@ fn parse_cookie<'a>(
     name_with_eq: &str,
 ) -> Option<String> {
     for hv in headers {
-        if let Ok(s) = hv.to_str() {
-            if s.starts_with(name_with_eq) {
-                if let Some(eq_idx) = s.find('=') {
-                    let rest = &s[eq_idx + 1..];
-                    let end = rest.find(';').unwrap_or(rest.len());
-                    return Some(rest[..end].to_string());
-                }
-            }
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
         }
     }
     None
@@ -135,6 +134,12 @@ async fn test_sleep_flow() {
         latency_min: 10,
         awakenings: 1,
         quality: Quality(4),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let id = create_sleep_session(&client, &addr.to_string(), &csrf, &session_cookie, &input).await;
 
@@ -271,6 +276,12 @@ async fn test_sleep_multi_sessions_and_wake_date_lookup() {
         latency_min: 15,
         awakenings: 1,
         quality: Quality(4),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let nap = SleepInput {
         date: wake_date,
@@ -279,6 +290,12 @@ async fn test_sleep_multi_sessions_and_wake_date_lookup() {
         latency_min: 5,
         awakenings: 0,
         quality: Quality(3),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
 
     create_sleep_session(
@@ -358,6 +375,12 @@ async fn test_sleep_overlap_rejection_inclusive() {
         latency_min: 10,
         awakenings: 0,
         quality: Quality(4),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     create_sleep_session(
         &client,
@@ -375,6 +398,12 @@ async fn test_sleep_overlap_rejection_inclusive() {
         latency_min: 5,
         awakenings: 0,
         quality: Quality(3),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let res = client
         .post(format!("http://{addr}/api/sleep"))
@@ -393,6 +422,12 @@ async fn test_sleep_overlap_rejection_inclusive() {
         latency_min: 5,
         awakenings: 0,
         quality: Quality(3),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let res = client
         .post(format!("http://{addr}/api/sleep"))
@@ -446,6 +481,7 @@ async fn test_exercise_and_note() {
         intensity: sleep_api::models::intensity::Intensity::Light,
         start_time: Some(chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap()),
         duration_min: Some(30),
+        hr_zones: None,
     };
     let res = client
         .post(format!("http://{addr}/api/exercise"))
@@ -472,6 +508,8 @@ async fn test_exercise_and_note() {
     let note = sleep_api::models::NoteInput {
         date: exercise.date,
         body: Some("Great workout".to_string()),
+        tags: Vec::new(),
+        session_id: None,
     };
     let res = client
         .post(format!("http://{addr}/api/note"))
@@ -495,3 +533,523 @@ async fn test_exercise_and_note() {
 
     server.abort();
 }
+
+#[tokio::test]
+async fn test_wake_feeling_roundtrip_and_summary() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let input = SleepInput {
+        date: chrono::NaiveDate::from_ymd_opt(2025, 6, 20).unwrap(),
+        bed_time: chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+        wake_time: chrono::NaiveTime::from_hms_opt(6, 30, 0).unwrap(),
+        latency_min: 15,
+        awakenings: 0,
+        quality: Quality(3),
+        wake_feeling: Some(2),
+        sleep_inertia_min: Some(45),
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
+    };
+    let id = create_sleep_session(&client, &addr.to_string(), &csrf, &session_cookie, &input).await;
+    let second = SleepInput {
+        date: chrono::NaiveDate::from_ymd_opt(2025, 6, 21).unwrap(),
+        wake_feeling: Some(4),
+        sleep_inertia_min: None,
+        ..input.clone()
+    };
+    create_sleep_session(&client, &addr.to_string(), &csrf, &session_cookie, &second).await;
+
+    let res = client
+        .get(format!("http://{addr}/api/sleep/{id}"))
+        .send()
+        .await
+        .unwrap();
+    let session: SleepSession = res.json().await.unwrap();
+    assert_eq!(session.wake_feeling, Some(2));
+    assert_eq!(session.sleep_inertia_min, Some(45));
+
+    // Fields are optional on the wire
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("Cookie", format!("session={session_cookie}; csrf={csrf}"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-22", "bed_time": "23:00:00", "wake_time": "06:30:00",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    for (feeling, inertia) in [(Some(0), None), (Some(6), None), (None, Some(-1))] {
+        let bad = SleepInput {
+            date: chrono::NaiveDate::from_ymd_opt(2025, 6, 25).unwrap(),
+            wake_feeling: feeling,
+            sleep_inertia_min: inertia,
+            ..input.clone()
+        };
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", format!("session={session_cookie}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&bad)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-06-20&to=2025-06-22&bucket=week"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let buckets = body["wake_feeling_by_bucket"].as_array().unwrap();
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0]["avg_feeling"], 3.0);
+    assert_eq!(buckets[0]["avg_inertia_min"], 45.0);
+    assert_eq!(buckets[0]["days_reported"], 2);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_invalid_path_params_return_problem_json() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let res = client
+        .get(format!("http://{addr}/api/sleep/date/2025-13-01"))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.headers()[reqwest::header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["status"], 400);
+    assert_eq!(body["code"], "invalid_path_param");
+    assert_eq!(body["param"], "date");
+    assert_eq!(body["value"], "2025-13-01");
+    assert_eq!(body["expected"], "YYYY-MM-DD");
+
+    let res = client
+        .delete(format!("http://{addr}/api/sleep/abc"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["param"], "id");
+    assert_eq!(body["value"], "abc");
+    assert_eq!(body["expected"], "integer");
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_flexible_time_formats() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-10", "bed_time": "10:30 pm", "wake_time": "6:45",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let id = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{addr}/api/sleep/{id}"))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["bed_time"], "22:30:00");
+    assert_eq!(body["wake_time"], "06:45:00");
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-11", "bed_time": "23:00", "wake_time": "13:00 PM",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let text = res.text().await.unwrap();
+    assert!(text.contains("wake_time"), "{text}");
+    assert!(
+        text.contains("expected HH:MM, HH:MM:SS, or h:mm AM/PM"),
+        "{text}"
+    );
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_sleep_patch() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    let input = SleepInput {
+        date: chrono::NaiveDate::from_ymd_opt(2025, 6, 17).unwrap(),
+        bed_time: chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+        wake_time: chrono::NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        latency_min: 10,
+        awakenings: 1,
+        quality: Quality(3),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: vec!["earplugs".into()],
+        tags: Vec::new(),
+        timezone: None,
+    };
+    let id = create_sleep_session(&client, &addr.to_string(), &csrf, &session_cookie, &input).await;
+
+    // Only quality changes; times, aids and duration are kept
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"quality": 5}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["quality"], 5);
+    assert_eq!(body["bed_time"], "23:00:00");
+    assert_eq!(body["latency_min"], 10);
+    assert_eq!(body["aids"], serde_json::json!(["earplugs"]));
+    let row = sqlx::query("SELECT duration_min FROM sleep_metrics WHERE session_id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<Option<i32>, _>("duration_min"), Some(480));
+
+    // Changing wake_time recomputes the duration
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"wake_time": "08:00"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["wake_time"], "08:00:00");
+    assert_eq!(body["quality"], 5);
+    let row = sqlx::query("SELECT duration_min FROM sleep_metrics WHERE session_id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<Option<i32>, _>("duration_min"), Some(540));
+
+    // The merged record is validated
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"latency_min": 500}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Unknown id
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/999999"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"quality": 2}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // PATCH requires the CSRF header
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{id}"))
+        .header("Cookie", &auth)
+        .json(&serde_json::json!({"quality": 2}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_sleep_efficiency_and_waso() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // 480 min in bed, 20 min to fall asleep, 40 min awake: 420 / 480 asleep
+    let night = SleepInput {
+        date: chrono::NaiveDate::from_ymd_opt(2025, 6, 20).unwrap(),
+        bed_time: chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+        wake_time: chrono::NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        latency_min: 20,
+        awakenings: 2,
+        quality: Quality(3),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: Some(40),
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
+    };
+    let id = create_sleep_session(&client, &addr.to_string(), &csrf, &session_cookie, &night).await;
+    // A 60 min nap without latency or WASO is fully efficient
+    let nap = SleepInput {
+        bed_time: chrono::NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+        wake_time: chrono::NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+        latency_min: 0,
+        awakenings: 0,
+        waso_min: None,
+        ..night.clone()
+    };
+    create_sleep_session(&client, &addr.to_string(), &csrf, &session_cookie, &nap).await;
+
+    let session: SleepSession = client
+        .get(format!("http://{addr}/api/sleep/{id}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(session.waso_min, Some(40));
+    assert_eq!(session.efficiency, Some(0.875));
+
+    // The day weights sessions by time in bed: (420 + 60) / 540
+    let days: Vec<serde_json::Value> = client
+        .get(format!("http://{addr}/api/sleep/recent?days=7"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(days[0]["waso_min"], 40);
+    assert_eq!(days[0]["efficiency"], 0.889);
+
+    let summary: serde_json::Value = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-06-16&to=2025-06-22&bucket=week"
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        summary["efficiency_by_bucket"],
+        serde_json::json!([{
+            "bucket": "2025-W25", "avg_efficiency": 0.889, "avg_waso_min": 40.0, "days": 1
+        }])
+    );
+
+    // Patching WASO recomputes the efficiency; it cannot be set by clients
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{id}"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"waso_min": 0, "efficiency": 0.1}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["waso_min"], 0);
+    assert_eq!(body["efficiency"], 0.958);
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&SleepInput {
+            date: chrono::NaiveDate::from_ymd_opt(2025, 6, 21).unwrap(),
+            waso_min: Some(601),
+            ..night.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
@@ -0,0 +1,193 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_sleep_aids_roundtrip_and_trends() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // Invalid aid (empty after trimming)
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-01", "bed_time": "23:00:00", "wake_time": "07:00:00",
+            "latency_min": 10, "awakenings": 0, "quality": 3, "aids": ["  "]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Nights 1..=3 with earplugs (quality 5), 4..=6 without (quality 3); melatonin once
+    let mut first_id = 0;
+    for day in 1..=6 {
+        let aids = match day {
+            1 => serde_json::json!([" Earplugs ", "melatonin", "earplugs"]),
+            2 | 3 => serde_json::json!(["earplugs"]),
+            _ => serde_json::json!([]),
+        };
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": format!("2025-06-0{day}"), "bed_time": "23:00:00",
+                "wake_time": "07:00:00", "latency_min": 10, "awakenings": 0,
+                "quality": if day <= 3 { 5 } else { 3 }, "aids": aids
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+        let created: serde_json::Value = res.json().await.unwrap();
+        if day == 1 {
+            first_id = created["id"].as_i64().unwrap();
+        }
+    }
+
+    let res = client
+        .get(format!("http://{addr}/api/sleep/{first_id}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let session: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        session["aids"],
+        serde_json::json!(["earplugs", "melatonin"])
+    );
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/aids?from=2025-06-01&to=2025-06-06&min_samples=3"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["min_samples"], 3);
+    let earplugs = &body["aids"][0];
+    assert_eq!(earplugs["aid"], "earplugs");
+    assert_eq!(earplugs["with_aid"]["nights"], 3);
+    assert_eq!(earplugs["without_aid"]["nights"], 3);
+    assert_eq!(earplugs["sufficient_sample"], true);
+    assert_eq!(earplugs["quality_diff"], 2.0);
+    let melatonin = &body["aids"][1];
+    assert_eq!(melatonin["aid"], "melatonin");
+    assert_eq!(melatonin["sufficient_sample"], false);
+    assert!(melatonin["quality_diff"].is_null());
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/aids?from=2025-06-01&to=2025-06-06&min_samples=1"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
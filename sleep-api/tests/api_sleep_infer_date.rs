@@ -0,0 +1,284 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use chrono::TimeZone;
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_create_sleep_infers_wake_date() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    // 11:30 in Tokyo: a late-morning quick log.
+    let frozen = chrono::Utc.with_ymd_and_hms(2025, 6, 10, 2, 30, 0).unwrap();
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let addr = addr.to_string();
+    let res = client
+        .post(format!("http://{addr}/api/settings/timezone"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"timezone": "Asia/Tokyo"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let post = |body: serde_json::Value| {
+        client
+            .post(format!("http://{addr}/api/sleep?infer_date=true"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+    };
+    let night = |bed: &str, wake: &str| {
+        serde_json::json!({
+            "bed_time": bed,
+            "wake_time": wake,
+            "latency_min": 10,
+            "awakenings": 0,
+            "quality": 4
+        })
+    };
+
+    // Without inference the date stays required.
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&night("23:00", "07:00"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+
+    let res = post(night("23:00", "07:00")).await.unwrap();
+    assert_eq!(res.status(), 201);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["date"], "2025-06-10");
+    assert_eq!(body["date_inferred"], true);
+
+    // A second log of the same morning is refused, even when it does not overlap.
+    let res = post(night("07:40", "08:20")).await.unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(
+        body["message"].as_str().unwrap().contains("already logged"),
+        "{body}"
+    );
+
+    // A nap well after the morning is fine.
+    let res = post(night("09:00", "10:00")).await.unwrap();
+    assert_eq!(res.status(), 201);
+
+    // A wake time later than now belongs to yesterday.
+    let res = post(night("04:00", "12:30")).await.unwrap();
+    assert_eq!(res.status(), 201);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["date"], "2025-06-09");
+
+    // An explicit date is used as sent, without the duplicate check.
+    let mut explicit = night("07:40", "08:20");
+    explicit["date"] = "2025-06-10".into();
+    let res = post(explicit).await.unwrap();
+    assert_eq!(res.status(), 201);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body.get("date_inferred").is_none());
+
+    let res = post(serde_json::json!({"bed_time": "23:00", "latency_min": 0}))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_inferred_date_follows_day_boundary() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    // 02:00 on June 10 in Tokyo: before a 04:00 day start, so still June 9.
+    let frozen = chrono::Utc.with_ymd_and_hms(2025, 6, 9, 17, 0, 0).unwrap();
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: std::sync::Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let addr = addr.to_string();
+    for (path, body) in [
+        (
+            "/api/settings/timezone",
+            serde_json::json!({"timezone": "Asia/Tokyo"}),
+        ),
+        (
+            "/api/settings/day-boundary",
+            serde_json::json!({"day_start": "04:00:00"}),
+        ),
+    ] {
+        let res = client
+            .post(format!("http://{addr}{path}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_success(), "{path}");
+    }
+
+    let today: serde_json::Value = client
+        .get(format!("http://{addr}/api/now/today"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(today["date"], "2025-06-09");
+
+    let res = client
+        .post(format!("http://{addr}/api/sleep?infer_date=true"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "bed_time": "23:00",
+            "wake_time": "01:50",
+            "latency_min": 10,
+            "awakenings": 0,
+            "quality": 3
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["date"], today["date"]);
+
+    server.abort();
+}
@@ -36,14 +36,13 @@ fn parse_cookie<'a>(
     name_with_eq: &str,
 ) -> Option<String> {
     for hv in headers {
-        if let Ok(s) = hv.to_str() {
-            if s.starts_with(name_with_eq) {
-                if let Some(eq_idx) = s.find('=') {
-                    let rest = &s[eq_idx + 1..];
-                    let end = rest.find(';').unwrap_or(rest.len());
-                    return Some(rest[..end].to_string());
-                }
-            }
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
         }
     }
     None
@@ -73,6 +72,7 @@ async fn login_and_get_auth(
     (csrf, session)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn seed_sleep(
     client: &Client,
     addr: &str,
@@ -90,6 +90,12 @@ async fn seed_sleep(
         latency_min: 10,
         awakenings: 1,
         quality: Quality(quality as u8),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let res = client
         .post(format!("http://{addr}/api/sleep"))
@@ -217,6 +223,37 @@ async fn test_sleep_list_recent_and_range() {
         .filter(|item| item.date == chrono::NaiveDate::from_ymd_opt(2025, 6, 15).unwrap())
         .collect();
     assert_eq!(sessions_on_15.len(), 2, "sessions on 2025-06-15");
+    // Units default to hours, so duration_hours accompanies duration_min.
+    assert_eq!(range[0].duration_min, Some(480));
+    assert_eq!(range[0].duration_hours, Some(8.0));
+
+    // Switching to minutes drops duration_hours; unknown units are rejected.
+    for (units, status) in [("days", 400), ("minutes", 204)] {
+        let res = client
+            .post(format!("http://{addr}/api/settings/units"))
+            .header("Cookie", format!("session={session_cookie}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "units": units }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status, "units {units}");
+    }
+    let res = client
+        .get(format!("http://{addr}/api/settings/units"))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["units"], "minutes");
+    let res = client
+        .get(format!("http://{addr}/api/sleep/recent?days=7"))
+        .send()
+        .await
+        .unwrap();
+    let recent: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(recent[0]["duration_min"], 480);
+    assert!(recent[0].get("duration_hours").is_none());
 
     server.abort();
 }
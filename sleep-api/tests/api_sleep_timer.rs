@@ -0,0 +1,200 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use chrono::TimeZone;
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+/// A clock the test moves forward by hand.
+#[derive(Debug)]
+struct StepClock(std::sync::Mutex<chrono::DateTime<chrono::Utc>>);
+
+impl sleep_api::time::Clock for StepClock {
+    fn now_utc(&self) -> chrono::DateTime<chrono::Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_sleep_timer_start_stop() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    // 23:10:30 on 2025-06-09 in Tokyo.
+    let clock = std::sync::Arc::new(StepClock(std::sync::Mutex::new(
+        chrono::Utc
+            .with_ymd_and_hms(2025, 6, 9, 14, 10, 30)
+            .unwrap(),
+    )));
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: sleep_api::events::EventBus::new(),
+        clock: clock.clone(),
+        features: sleep_api::features::Features::default(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let res = client
+        .post(format!("http://{addr}/api/settings/timezone"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"timezone": "Asia/Tokyo"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let active = || client.get(format!("http://{addr}/api/sleep/active")).send();
+    let post = |path: &str| {
+        client
+            .post(format!("http://{addr}/api/sleep/{path}"))
+            .header("X-CSRF-Token", &csrf)
+    };
+
+    let body: serde_json::Value = active().await.unwrap().json().await.unwrap();
+    assert!(body.is_null());
+    let res = post("stop").send().await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = post("start").send().await.unwrap();
+    assert_eq!(res.status(), 201);
+    let res = post("start").send().await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    *clock.0.lock().unwrap() = chrono::Utc.with_ymd_and_hms(2025, 6, 9, 21, 42, 0).unwrap();
+    let body: serde_json::Value = active().await.unwrap().json().await.unwrap();
+    assert_eq!(body["bed_time"], "23:10:30");
+    assert_eq!(body["elapsed_min"], 451);
+
+    let res = post("stop")
+        .json(&serde_json::json!({"quality": 4, "awakenings": 1}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let session: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(session["date"], "2025-06-10");
+    assert_eq!(session["bed_time"], "23:10:00");
+    assert_eq!(session["wake_time"], "06:42:00");
+    assert_eq!(session["quality"], 4);
+    assert_eq!(session["awakenings"], 1);
+    assert_eq!(session["latency_min"], 0);
+
+    let body: serde_json::Value = active().await.unwrap().json().await.unwrap();
+    assert!(body.is_null());
+
+    // A timer left running for a day cannot be stopped, only cancelled.
+    let res = post("start").send().await.unwrap();
+    assert_eq!(res.status(), 201);
+    *clock.0.lock().unwrap() = chrono::Utc.with_ymd_and_hms(2025, 6, 10, 22, 0, 0).unwrap();
+    let res = post("stop").send().await.unwrap();
+    assert_eq!(res.status(), 400);
+    let res = client
+        .delete(format!("http://{addr}/api/sleep/active"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = client
+        .delete(format!("http://{addr}/api/sleep/active"))
+        .header("X-CSRF-Token", &csrf)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    server.abort();
+}
@@ -0,0 +1,96 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::models::SleepInput;
+use sleep_api::{app, db, repository, smoke};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+#[tokio::test]
+async fn test_smoke_round_trip_against_running_server() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    // The first candidate day is taken, so the run must fall back to the day before.
+    let existing: SleepInput = serde_json::from_value(serde_json::json!({
+        "date": smoke::SMOKE_DATE,
+        "bed_time": "23:00:00",
+        "wake_time": "07:00:00",
+        "latency_min": 10,
+        "awakenings": 1,
+        "quality": 4,
+    }))
+    .unwrap();
+    repository::insert_sleep(&pool, &existing, 480)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = smoke::client().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let url = format!("http://{addr}/");
+
+    let report = smoke::run(&client, &url, "admin@example.com", "password123").await;
+    assert!(report.ok(), "smoke failed: {report:?}");
+    let names: Vec<_> = report.steps.iter().map(|s| s.name).collect();
+    assert_eq!(
+        names,
+        [
+            "health", "login", "create", "read", "trends", "delete", "logout"
+        ]
+    );
+    assert!(report.steps[2].detail.ends_with("on 1999-12-31"));
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sleep_sessions")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 1);
+
+    let report = smoke::run(&client, &url, "admin@example.com", "wrong").await;
+    assert!(!report.ok());
+    let last = report.steps.last().unwrap();
+    assert_eq!((last.name, last.ok), ("login", false));
+    assert_eq!(last.detail, "expected 200, got 401 Unauthorized");
+
+    server.abort();
+}
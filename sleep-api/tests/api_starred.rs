@@ -0,0 +1,225 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+async fn create_sleep(client: &Client, addr: &str, csrf: &str, wake_date: &str) -> i64 {
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .header("X-CSRF-Token", csrf)
+        .json(&serde_json::json!({
+            "date": wake_date,
+            "bed_time": "23:00:00",
+            "wake_time": "07:00:00",
+            "latency_min": 10,
+            "awakenings": 1,
+            "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_star_sleep_and_notes() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let addr = addr.to_string();
+
+    let older = create_sleep(&client, &addr, &csrf, "2025-06-10").await;
+    let newer = create_sleep(&client, &addr, &csrf, "2025-06-12").await;
+    let _plain = create_sleep(&client, &addr, &csrf, "2025-06-11").await;
+    let res = client
+        .post(format!("http://{addr}/api/note"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-10", "body": "baseline week"}))
+        .send()
+        .await
+        .unwrap();
+    let note: i64 = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    let star = |path: String| {
+        client
+            .post(format!("http://{addr}{path}"))
+            .header("X-CSRF-Token", &csrf)
+            .send()
+    };
+    for path in [
+        format!("/api/sleep/{older}/star"),
+        format!("/api/sleep/{newer}/star"),
+        // Starring twice is a no-op.
+        format!("/api/sleep/{newer}/star"),
+        format!("/api/note/{note}/star"),
+    ] {
+        assert_eq!(star(path).await.unwrap().status(), 204);
+    }
+    assert_eq!(
+        star("/api/sleep/999/star".into()).await.unwrap().status(),
+        404
+    );
+    assert_eq!(
+        star("/api/note/999/star".into()).await.unwrap().status(),
+        404
+    );
+    // CSRF is required.
+    let res = client
+        .post(format!("http://{addr}/api/sleep/{older}/star"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let starred: serde_json::Value = client
+        .get(format!("http://{addr}/api/starred"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<i64> = starred["sleep"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![newer, older]);
+    assert_eq!(starred["notes"][0]["body"], "baseline week");
+
+    let session: serde_json::Value = client
+        .get(format!("http://{addr}/api/sleep/{older}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(session["starred"], true);
+
+    for path in [
+        format!("/api/sleep/{older}/star"),
+        format!("/api/note/{note}/star"),
+    ] {
+        let res = client
+            .delete(format!("http://{addr}{path}"))
+            .header("X-CSRF-Token", &csrf)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 204);
+    }
+    let starred: serde_json::Value = client
+        .get(format!("http://{addr}/api/starred"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(starred["sleep"].as_array().unwrap().len(), 1);
+    assert_eq!(starred["sleep"][0]["id"], newer);
+    assert!(starred["notes"].as_array().unwrap().is_empty());
+
+    server.abort();
+}
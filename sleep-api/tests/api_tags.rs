@@ -0,0 +1,277 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_tags_on_sleep_and_notes_filter_and_trends() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let addr = addr.to_string();
+
+    // Blank and too many tags are rejected.
+    for tags in [
+        serde_json::json!(["  "]),
+        serde_json::json!((0..11).map(|i| format!("t{i}")).collect::<Vec<_>>()),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": "2025-06-01", "bed_time": "23:00:00", "wake_time": "07:00:00",
+                "latency_min": 10, "awakenings": 0, "quality": 3, "tags": tags
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+    }
+
+    // Nights 1..=3 tagged travel (quality 2), 4..=6 untagged (quality 4); caffeine on night 1.
+    let mut first_id = 0;
+    for day in 1..=6 {
+        let tags = match day {
+            1 => serde_json::json!([" Travel", "caffeine", "travel"]),
+            2 => serde_json::json!(["travel"]),
+            _ => serde_json::json!([]),
+        };
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": format!("2025-06-0{day}"), "bed_time": "23:00:00",
+                "wake_time": "07:00:00", "latency_min": 10, "awakenings": 0,
+                "quality": if day <= 3 { 2 } else { 4 }, "tags": tags
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+        let created: serde_json::Value = res.json().await.unwrap();
+        if day == 1 {
+            first_id = created["id"].as_i64().unwrap();
+        }
+    }
+
+    // Night 3 is tagged through its note.
+    let res = client
+        .post(format!("http://{addr}/api/note"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-03", "body": "hotel", "tags": ["TRAVEL"]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let note_id = res.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    let session: serde_json::Value = client
+        .get(format!("http://{addr}/api/sleep/{first_id}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(session["tags"], serde_json::json!(["caffeine", "travel"]));
+
+    let notes: serde_json::Value = client
+        .get(format!(
+            "http://{addr}/api/notes?from=2025-06-01&to=2025-06-06"
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(notes[0]["tags"], serde_json::json!(["travel"]));
+
+    // The range filter matches session tags only, case-insensitively.
+    let range = |tag: &str| {
+        format!("http://{addr}/api/sleep/range?from=2025-06-01&to=2025-06-06&tag={tag}")
+    };
+    let tagged: serde_json::Value = client
+        .get(range("Travel"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let dates: Vec<&str> = tagged
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["date"].as_str().unwrap())
+        .collect();
+    assert_eq!(dates, ["2025-06-01", "2025-06-02"]);
+    let none: serde_json::Value = client
+        .get(range("sick"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(none, serde_json::json!([]));
+
+    // A patch without tags keeps them.
+    let res = client
+        .patch(format!("http://{addr}/api/sleep/{first_id}"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"quality": 1}))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let session: serde_json::Value = client
+        .get(format!("http://{addr}/api/sleep/{first_id}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(session["tags"], serde_json::json!(["caffeine", "travel"]));
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/tags?from=2025-06-01&to=2025-06-06&min_samples=3"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let caffeine = &body["tags"][0];
+    assert_eq!(caffeine["tag"], "caffeine");
+    assert_eq!(caffeine["sufficient_sample"], false);
+    assert!(caffeine["quality_diff"].is_null());
+    let travel = &body["tags"][1];
+    assert_eq!(travel["tag"], "travel");
+    assert_eq!(travel["with_tag"]["nights"], 3);
+    assert_eq!(travel["without_tag"]["nights"], 3);
+    assert_eq!(travel["sufficient_sample"], true);
+    assert!(travel["quality_diff"].as_f64().unwrap() < 0.0);
+
+    // Clearing a note's tags removes the night from the tagged group.
+    let res = client
+        .put(format!("http://{addr}/api/note/{note_id}"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"date": "2025-06-03", "body": "hotel"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let body: serde_json::Value = client
+        .get(format!(
+            "http://{addr}/api/trends/tags?from=2025-06-01&to=2025-06-06&min_samples=3"
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["tags"][1]["with_tag"]["nights"], 2);
+    assert_eq!(body["tags"][1]["sufficient_sample"], false);
+
+    server.abort();
+}
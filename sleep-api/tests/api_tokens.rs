@@ -0,0 +1,309 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+async fn create_token(
+    client: &Client,
+    addr: &str,
+    csrf: &str,
+    name: &str,
+    scope: &str,
+) -> (i64, String) {
+    let res = client
+        .post(format!("http://{addr}/api/tokens"))
+        .header("X-CSRF-Token", csrf)
+        .json(&serde_json::json!({"name": name, "scope": scope}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["token"]["scope"], scope);
+    assert!(body["token"]["last_used_at"].is_null());
+    (
+        body["token"]["id"].as_i64().unwrap(),
+        body["secret"].as_str().unwrap().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_api_token_scopes_and_expiry() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, _) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let addr = addr.to_string();
+
+    let (read_id, read) = create_token(&client, &addr, &csrf, "dashboard", "read").await;
+    let (_, write) = create_token(&client, &addr, &csrf, "phone", "write:sleep").await;
+    let res = client
+        .post(format!("http://{addr}/api/tokens"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"name": "x", "scope": "root"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_client_error());
+    let res = client
+        .post(format!("http://{addr}/api/tokens"))
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({"name": "x", "scope": "read", "expires_in_days": 0}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Token clients hold no cookies and send no CSRF header.
+    let bearer = Client::new();
+    let sleep = serde_json::json!({
+        "date": "2025-06-10",
+        "bed_time": "23:00:00",
+        "wake_time": "07:00:00",
+        "latency_min": 10,
+        "awakenings": 1,
+        "quality": 4
+    });
+
+    let res = bearer
+        .get(format!("http://{addr}/api/sleep/recent"))
+        .bearer_auth(&read)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = bearer
+        .post(format!("http://{addr}/api/sleep"))
+        .bearer_auth(&read)
+        .json(&sleep)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+    assert_eq!(
+        res.json::<serde_json::Value>().await.unwrap()["error"],
+        "insufficient_scope"
+    );
+
+    let res = bearer
+        .post(format!("http://{addr}/api/sleep"))
+        .bearer_auth(&write)
+        .json(&sleep)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let res = bearer
+        .post(format!("http://{addr}/api/settings/timezone"))
+        .bearer_auth(&write)
+        .json(&serde_json::json!({"timezone": "Asia/Tokyo"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    // Unknown tokens never fall back to the session cookie.
+    let res = client
+        .get(format!("http://{addr}/api/sleep/recent"))
+        .bearer_auth("slt_nope")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    // Tokens cannot manage tokens, whatever their scope.
+    let res = bearer
+        .get(format!("http://{addr}/api/tokens"))
+        .bearer_auth(&write)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let tokens: serde_json::Value = client
+        .get(format!("http://{addr}/api/tokens"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let tokens = tokens.as_array().unwrap();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0]["name"], "phone");
+    assert!(tokens.iter().all(|t| !t["last_used_at"].is_null()));
+    assert!(tokens.iter().all(|t| t["expired"] == false));
+    assert!(tokens.iter().all(|t| t.get("secret").is_none()));
+
+    sqlx::query("UPDATE api_tokens SET expires_at = '2000-01-01 00:00:00' WHERE id = ?")
+        .bind(read_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let res = bearer
+        .get(format!("http://{addr}/api/sleep/recent"))
+        .bearer_auth(&read)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let tokens: serde_json::Value = client
+        .get(format!("http://{addr}/api/tokens"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tokens[1]["expired"], true);
+
+    for _ in 0..2 {
+        let res = client
+            .delete(format!("http://{addr}/api/tokens/{read_id}"))
+            .header("X-CSRF-Token", &csrf)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 204);
+    }
+    let tokens: serde_json::Value = client
+        .get(format!("http://{addr}/api/tokens"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tokens.as_array().unwrap().len(), 1);
+
+    // The export, account and settings routes are not data routes: admin tokens only.
+    let (_, read) = create_token(&client, &addr, &csrf, "dashboard", "read").await;
+    let (_, admin) = create_token(&client, &addr, &csrf, "backup", "admin").await;
+    for path in [
+        "/api/export",
+        "/api/account/devices",
+        "/api/settings/timezone",
+    ] {
+        let res = bearer
+            .get(format!("http://{addr}{path}"))
+            .bearer_auth(&read)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 403, "{path}");
+        assert_eq!(
+            res.json::<serde_json::Value>().await.unwrap()["error"],
+            "insufficient_scope"
+        );
+    }
+    let res = bearer
+        .get(format!("http://{addr}/api/export"))
+        .bearer_auth(&admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = bearer
+        .get(format!(
+            "http://{addr}/api/sleep/range?from=2025-06-01&to=2025-06-30"
+        ))
+        .bearer_auth(&read)
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success(), "{}", res.status());
+
+    server.abort();
+}
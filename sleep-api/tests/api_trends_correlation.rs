@@ -0,0 +1,230 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_exercise_intensity_correlation() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+
+    let (csrf, session_cookie) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+    let auth = format!("session={session_cookie}; csrf={csrf}");
+
+    // Days cycle none/light/hard; the night waking that day is better the harder the workout.
+    let levels = ["none", "light", "hard"];
+    for day in 1..=12 {
+        let date = format!("2025-06-{day:02}");
+        let rank = day % 3;
+        let res = client
+            .post(format!("http://{addr}/api/exercise"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({ "date": date, "intensity": levels[rank] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201, "exercise {date}");
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:00:00", "wake_time": "07:00:00",
+                "latency_min": 10, "awakenings": 0, "quality": 3 + rank,
+                "wake_feeling": 4 - rank
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201, "sleep {date}");
+    }
+
+    let url = format!("http://{addr}/api/trends/correlation?from=2025-06-01&to=2025-06-12");
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["mode"], "same_day");
+    assert_eq!(body["nights"], 12);
+    let groups = body["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 3);
+    for (i, level) in levels.iter().enumerate() {
+        assert_eq!(groups[i]["intensity"], *level);
+        assert_eq!(groups[i]["rank"], i);
+        assert_eq!(groups[i]["nights"], 4);
+        assert_eq!(groups[i]["avg_quality"], 3.0 + i as f64);
+        assert_eq!(groups[i]["avg_duration_min"], 480.0);
+        assert_eq!(groups[i]["avg_wake_feeling"], 4.0 - i as f64);
+        let inference = &groups[i]["inference"]["quality"];
+        assert_eq!(inference["n_a"], 4);
+        assert_eq!(inference["n_b"], 8);
+    }
+    // "hard" nights sleep better than the rest: a positive effect, and a clear one.
+    let hard = &groups[2]["inference"]["quality"];
+    assert!(hard["effect_size"].as_f64().unwrap() > 0.0);
+    assert!(hard["p_value"].as_f64().unwrap() < 0.05);
+    assert_eq!(body["quality"]["n"], 12);
+    assert_eq!(body["quality"]["r"], 1.0);
+    assert_eq!(body["quality"]["p_value"], 0.0);
+    assert_eq!(body["quality"]["strength"], "strong");
+    // Every night is as long, so duration cannot correlate.
+    assert!(body["duration_min"]["r"].is_null());
+    assert_eq!(body["wake_feeling"]["n"], 12);
+    assert_eq!(body["wake_feeling"]["r"], -1.0);
+
+    // The night after a workout: 2025-06-01 has no exercise the day before.
+    let res = client
+        .get(format!("{url}&mode=previous_day"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["mode"], "previous_day");
+    assert_eq!(body["nights"], 11);
+    assert!(body["quality"]["r"].as_f64().unwrap() < 0.0);
+
+    // Morning weigh-ins on six days, heavier on the better nights; none without the flag.
+    assert!(body.get("body_metrics").is_none());
+    for day in 1..=6 {
+        let res = client
+            .post(format!("http://{addr}/api/body-metrics"))
+            .header("Cookie", &auth)
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": format!("2025-06-{day:02}"), "weight_kg": 70.0 + (day % 3) as f64
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201, "body metric {day}");
+    }
+    let res = client
+        .get(format!("{url}&body_metrics=true"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let metrics = &body["body_metrics"];
+    assert_eq!(metrics["nights"], 6);
+    assert_eq!(metrics["avg_weight_kg"], 71.0);
+    assert_eq!(metrics["weight_quality"]["n"], 6);
+    assert_eq!(metrics["weight_quality"]["r"], 1.0);
+    assert!(metrics["weight_duration_min"]["r"].is_null());
+    assert_eq!(metrics["body_fat_quality"]["n"], 0);
+
+    let res = client
+        .get(format!("{url}&format=csv"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let csv = res.text().await.unwrap();
+    assert!(csv.starts_with("intensity,rank,nights,avg_quality,avg_duration_min,avg_wake_feeling"));
+    assert_eq!(csv.lines().count(), 4);
+
+    let res = client
+        .get(format!("{url}&mode=weekly"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    server.abort();
+}
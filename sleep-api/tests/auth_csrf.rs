@@ -159,6 +159,12 @@ async fn test_auth_and_csrf_flow() {
         latency_min: 15,
         awakenings: 0,
         quality: Quality(4),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let res = client
         .post(format!("http://{addr}/api/sleep"))
@@ -298,6 +304,12 @@ async fn test_csrf_percent_encoded_header() {
         latency_min: 12,
         awakenings: 0,
         quality: Quality(4),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let res = client
         .post(format!("http://{addr}/api/sleep"))
@@ -397,6 +409,12 @@ async fn test_dev_cookie_names_and_flags() {
         latency_min: 10,
         awakenings: 0,
         quality: Quality(5),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let res = client
         .post(format!("http://{addr}/api/sleep"))
@@ -0,0 +1,125 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, config, db, reload};
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+async fn login_status(client: &Client, addr: &str, email: &str) -> u16 {
+    client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": "password123" }))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+async fn version(client: &Client, addr: &str) -> serde_json::Value {
+    client
+        .get(format!("http://{addr}/api/version"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_reload_applies_config_file_changes() {
+    let hash = Argon2::default()
+        .hash_password(b"password123", &SaltString::generate(OsRng))
+        .unwrap()
+        .to_string();
+    let path = std::env::temp_dir().join(format!("sleep-reload-{}.env", std::process::id()));
+    std::fs::write(
+        &path,
+        "ADMIN_EMAIL=first@example.com\nADMIN_PASSWORD_HASH=ignored\n",
+    )
+    .unwrap();
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+        // Set in the process environment, so it wins over the file.
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+        std::env::set_var("CONFIG_FILE", &path);
+    };
+    assert_eq!(config::load_config_file(), Some(path.clone()));
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let app = app::router(pool);
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = Client::new();
+    wait_ready(&client, &addr).await;
+
+    assert_eq!(login_status(&client, &addr, "first@example.com").await, 200);
+    assert!(version(&client, &addr).await["config_reload"].is_null());
+
+    std::fs::write(
+        &path,
+        "ADMIN_EMAIL=second@example.com\nQUOTA_API_CALLS_PER_MIN=6\n",
+    )
+    .unwrap();
+    let status = reload::reload(chrono::Utc::now());
+    assert!(status.ok);
+    assert_eq!(status.generation, 1);
+
+    assert_eq!(login_status(&client, &addr, "first@example.com").await, 401);
+    assert_eq!(
+        login_status(&client, &addr, "second@example.com").await,
+        200
+    );
+    let info = version(&client, &addr).await;
+    assert_eq!(info["config_reload"]["ok"], true);
+    assert_eq!(info["config_reload"]["generation"], 1);
+
+    // A failed reload is reported and keeps the previous values.
+    std::fs::remove_file(&path).unwrap();
+    let status = reload::reload(chrono::Utc::now());
+    assert!(!status.ok);
+    assert_eq!(status.generation, 1);
+    assert!(status.error.is_some());
+    let info = version(&client, &addr).await;
+    assert_eq!(info["config_reload"]["ok"], false);
+
+    // The rebuilt quota now applies: 6 calls per minute in total.
+    let mut limited = false;
+    for _ in 0..6 {
+        let res = client
+            .get(format!("http://{addr}/api/version"))
+            .send()
+            .await
+            .unwrap();
+        if res.status() == 429 {
+            limited = true;
+            break;
+        }
+    }
+    assert!(limited);
+
+    server.abort();
+}
@@ -0,0 +1,231 @@
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use sleep_api::{
+    db::Db,
+    events::EventBus,
+    handlers::{self, EditLock, TimeContext},
+    models::{AuditReason, SleepInput, SleepPatch},
+    repository,
+};
+
+type DailyRow = (NaiveDate, i64, NaiveTime, NaiveTime, i32, i32, i64);
+
+async fn daily_rows(db: &Db) -> Vec<DailyRow> {
+    sqlx::query_as(
+        "SELECT wake_date, id, bed_time, wake_time, duration_min, longest_segment_min, \
+         session_count FROM v_daily_sleep ORDER BY wake_date",
+    )
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+async fn memory_db() -> Db {
+    sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
+
+fn night(date: &str, bed: &str, wake: &str) -> SleepInput {
+    serde_json::from_value(serde_json::json!({
+        "date": date, "bed_time": bed, "wake_time": wake,
+        "latency_min": 10, "awakenings": 1, "quality": 4
+    }))
+    .unwrap()
+}
+
+fn d(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+fn t(s: &str) -> NaiveTime {
+    s.parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_migration_backfills_existing_nights() {
+    let db = memory_db().await;
+    let mut files: Vec<_> = std::fs::read_dir("../migrations")
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    files.sort();
+    let (before, from_0034): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|p| p.file_name().unwrap().to_string_lossy().as_ref() < "0034");
+    for file in &before {
+        sqlx::raw_sql(&std::fs::read_to_string(file).unwrap())
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    // A split night on 2025-06-02 and a single one on 2025-06-03, written before the table.
+    for (id, date, bed, wake, duration) in [
+        (1, "2025-06-02", "23:00:00", "03:00:00", 240),
+        (2, "2025-06-02", "04:00:00", "06:30:00", 150),
+        (3, "2025-06-03", "22:30:00", "06:30:00", 480),
+    ] {
+        sqlx::query(
+            "INSERT INTO sleep_sessions(id, date, session_date, bed_time, wake_time) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(date)
+        .bind(date)
+        .bind(bed)
+        .bind(wake)
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO sleep_metrics(session_id, latency_min, awakenings, quality, duration_min) \
+             VALUES (?, 5, 0, 3, ?)",
+        )
+        .bind(id)
+        .bind(duration)
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+    let expected = daily_rows(&db).await;
+    assert_eq!(
+        expected,
+        [
+            (
+                d("2025-06-02"),
+                1,
+                t("23:00:00"),
+                t("06:30:00"),
+                390,
+                240,
+                2
+            ),
+            (
+                d("2025-06-03"),
+                3,
+                t("22:30:00"),
+                t("06:30:00"),
+                480,
+                480,
+                1
+            ),
+        ]
+    );
+
+    for file in &from_0034 {
+        sqlx::raw_sql(&std::fs::read_to_string(file).unwrap())
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM daily_sleep")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(stored, 2);
+    assert_eq!(daily_rows(&db).await, expected);
+}
+
+#[tokio::test]
+async fn test_writes_keep_daily_table_in_step() {
+    let db = memory_db().await;
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&db)
+        .await
+        .unwrap();
+    let events = EventBus::new();
+    let lock = EditLock::none();
+    let time = TimeContext::fixed(
+        Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap(),
+        chrono_tz::Asia::Tokyo,
+    );
+
+    let first = handlers::create_sleep(
+        &db,
+        &events,
+        &time,
+        &lock,
+        night("2025-06-02", "23:00:00", "03:00:00"),
+    )
+    .await
+    .unwrap();
+    let nap = handlers::create_sleep(
+        &db,
+        &events,
+        &time,
+        &lock,
+        night("2025-06-02", "04:00:00", "06:30:00"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        daily_rows(&db).await,
+        [(
+            d("2025-06-02"),
+            first,
+            t("23:00:00"),
+            t("06:30:00"),
+            390,
+            240,
+            2
+        )]
+    );
+
+    // Moving a session to another wake date updates both days.
+    handlers::update_sleep(
+        &db,
+        &events,
+        &time,
+        &lock,
+        nap,
+        night("2025-06-04", "04:00:00", "06:30:00"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        daily_rows(&db).await,
+        [
+            (
+                d("2025-06-02"),
+                first,
+                t("23:00:00"),
+                t("03:00:00"),
+                240,
+                240,
+                1
+            ),
+            (
+                d("2025-06-04"),
+                nap,
+                t("04:00:00"),
+                t("06:30:00"),
+                150,
+                150,
+                1
+            ),
+        ]
+    );
+
+    let patch: SleepPatch =
+        serde_json::from_value(serde_json::json!({ "wake_time": "07:00:00" })).unwrap();
+    handlers::patch_sleep(&db, &events, &time, &lock, nap, patch)
+        .await
+        .unwrap();
+    assert_eq!(daily_rows(&db).await[1].3, t("07:00:00"));
+    assert_eq!(daily_rows(&db).await[1].4, 180);
+
+    handlers::delete_sleep(&db, &events, &lock, first, &AuditReason::default())
+        .await
+        .unwrap();
+    let after_delete = daily_rows(&db).await;
+    assert_eq!(after_delete.len(), 1);
+    assert_eq!(after_delete[0].0, d("2025-06-04"));
+
+    // The triggers agree with a full recomputation.
+    assert_eq!(repository::rebuild_daily_sleep(&db).await.unwrap(), 1);
+    assert_eq!(daily_rows(&db).await, after_delete);
+}
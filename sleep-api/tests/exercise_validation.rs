@@ -8,6 +8,7 @@ fn exercise_duration_negative_is_invalid() {
         intensity: Intensity::Light,
         start_time: Some(NaiveTime::from_hms_opt(9, 0, 0).expect("valid time")),
         duration_min: Some(-5),
+        hr_zones: None,
     };
 
     let err = input
@@ -23,6 +24,7 @@ fn exercise_duration_excessive_is_invalid() {
         intensity: Intensity::Hard,
         start_time: Some(NaiveTime::from_hms_opt(18, 30, 0).expect("valid time")),
         duration_min: Some(24 * 60 + 1),
+        hr_zones: None,
     };
 
     let err = input
@@ -30,3 +32,35 @@ fn exercise_duration_excessive_is_invalid() {
         .expect_err("excessive duration should be rejected");
     assert!(matches!(err, DomainError::InvalidInput(_)));
 }
+
+#[test]
+fn exercise_hr_zones_are_validated() {
+    let zones = sleep_api::models::HrZoneMinutes {
+        z1: 10,
+        z2: 20,
+        z3: 10,
+        z4: 5,
+        z5: 0,
+    };
+    let mut input = sleep_api::models::ExerciseInput {
+        date: NaiveDate::from_ymd_opt(2025, 6, 17).expect("valid date"),
+        intensity: Intensity::Light,
+        start_time: Some(NaiveTime::from_hms_opt(7, 0, 0).expect("valid time")),
+        duration_min: Some(45),
+        hr_zones: Some(zones),
+    };
+    assert!(input.validate().is_ok());
+
+    input.duration_min = None;
+    assert!(matches!(
+        input.validate(),
+        Err(DomainError::InvalidInput(_))
+    ));
+
+    input.duration_min = Some(45);
+    input.hr_zones = Some(sleep_api::models::HrZoneMinutes { z2: -1, ..zones });
+    assert!(matches!(
+        input.validate(),
+        Err(DomainError::InvalidInput(_))
+    ));
+}
@@ -0,0 +1,118 @@
+use sleep_api::export::{self, SignatureStatus};
+use sleep_api::models::SleepInput;
+use sleep_api::{db, repository};
+
+const KEY: &[u8] = b"export-key";
+
+#[tokio::test]
+async fn test_export_manifest_detects_corruption_and_tampering() {
+    let dir = std::env::temp_dir().join(format!(
+        "sleep-export-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let export_dir = dir.join("export");
+    // A file database, as on a real instance (`VACUUM INTO` needs one).
+    std::fs::create_dir_all(&dir).unwrap();
+    let pool = db::connect_file(&dir.join("live.sqlite"))
+        .await
+        .expect("db connect");
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .expect("migrator")
+        .run(&pool)
+        .await
+        .expect("migrations run");
+
+    let input: SleepInput = serde_json::from_value(serde_json::json!({
+        "date": "2025-06-01",
+        "bed_time": "23:00:00",
+        "wake_time": "07:00:00",
+        "latency_min": 10,
+        "awakenings": 1,
+        "quality": 4,
+    }))
+    .unwrap();
+    repository::insert_sleep(&pool, &input, 480).await.unwrap();
+
+    let manifest = export::write_export(&pool, &export_dir, Some(KEY), chrono::Utc::now())
+        .await
+        .expect("export");
+    let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, [export::DATABASE_FILE, export::SESSIONS_FILE]);
+    assert!(
+        manifest
+            .signature
+            .as_deref()
+            .unwrap()
+            .starts_with("sha256=")
+    );
+
+    let csv = std::fs::read_to_string(export_dir.join(export::SESSIONS_FILE)).unwrap();
+    assert!(
+        csv.contains("2025-06-01,23:00:00,07:00:00,10,1,4,480"),
+        "{csv}"
+    );
+
+    // The snapshot is a usable database.
+    let snapshot = db::connect_file(&export_dir.join(export::DATABASE_FILE))
+        .await
+        .unwrap();
+    let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sleep_sessions")
+        .fetch_one(&snapshot)
+        .await
+        .unwrap();
+    assert_eq!(sessions, 1);
+    snapshot.close().await;
+
+    let report = export::verify_export(&export_dir, Some(KEY)).unwrap();
+    assert!(report.ok());
+    assert_eq!(report.signature, SignatureStatus::Valid);
+    assert_eq!(report.verified.len(), 2);
+    assert_eq!(
+        export::verify_export(&export_dir, None).unwrap().signature,
+        SignatureStatus::Unchecked
+    );
+    assert_eq!(
+        export::verify_export(&export_dir, Some(b"other-key"))
+            .unwrap()
+            .signature,
+        SignatureStatus::Invalid
+    );
+
+    // Exporting twice into the same directory is refused.
+    assert!(
+        export::write_export(&pool, &export_dir, Some(KEY), chrono::Utc::now())
+            .await
+            .is_err()
+    );
+
+    // A flipped byte in a file is reported as corruption.
+    let csv_path = export_dir.join(export::SESSIONS_FILE);
+    let mut bytes = std::fs::read(&csv_path).unwrap();
+    bytes[0] ^= 0x01;
+    std::fs::write(&csv_path, &bytes).unwrap();
+    std::fs::write(export_dir.join("notes.txt"), "added later").unwrap();
+    let report = export::verify_export(&export_dir, Some(KEY)).unwrap();
+    assert!(!report.ok());
+    assert_eq!(report.corrupted, [export::SESSIONS_FILE]);
+    assert_eq!(report.unlisted, ["notes.txt"]);
+    assert_eq!(report.signature, SignatureStatus::Valid);
+
+    // Updating the manifest to match the corrupted file breaks its signature.
+    let manifest_path = export_dir.join(export::MANIFEST_FILE);
+    let mut tampered: export::Manifest =
+        serde_json::from_slice(&std::fs::read(&manifest_path).unwrap()).unwrap();
+    let fake_hash = {
+        use sha2::Digest;
+        hex::encode(sha2::Sha256::digest(&bytes))
+    };
+    tampered.files[1].sha256 = fake_hash;
+    std::fs::write(&manifest_path, serde_json::to_vec(&tampered).unwrap()).unwrap();
+    let report = export::verify_export(&export_dir, Some(KEY)).unwrap();
+    assert!(report.corrupted.is_empty());
+    assert_eq!(report.signature, SignatureStatus::Invalid);
+    assert!(!report.ok());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -51,7 +51,7 @@ async fn test_insert_and_aggregate_friction_window() {
     ];
 
     for input in &inputs {
-        let id = repository::insert_friction_telemetry(&pool, input)
+        let id = repository::insert_friction_telemetry(&pool, input, 1)
             .await
             .expect("insert friction telemetry");
         assert!(id > 0);
@@ -114,3 +114,108 @@ async fn test_aggregate_friction_window_empty_range_returns_zeros() {
     approx_eq(agg.immediate_edit_rate, 0.0);
     approx_eq(agg.follow_up_failure_rate, 0.0);
 }
+
+#[tokio::test]
+async fn test_aggregate_friction_window_weights_sampled_events() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+    }
+    let pool = db::connect().await.expect("db connect");
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .expect("migrator")
+        .run(&pool)
+        .await
+        .expect("migrations run");
+
+    let success = FrictionTelemetryInput {
+        form_time_ms: 1000,
+        error_kind: None,
+        retry_count: 0,
+        immediate_edit: false,
+        follow_up_failure: false,
+    };
+    let error = FrictionTelemetryInput {
+        form_time_ms: 6000,
+        error_kind: Some("validation".to_string()),
+        retry_count: 2,
+        immediate_edit: true,
+        follow_up_failure: false,
+    };
+    // One stored success standing for four, plus two unsampled errors.
+    repository::insert_friction_telemetry(&pool, &success, 4)
+        .await
+        .expect("insert sampled success");
+    for _ in 0..2 {
+        repository::insert_friction_telemetry(&pool, &error, 1)
+            .await
+            .expect("insert error");
+    }
+
+    let agg = repository::aggregate_friction_window(&pool, dt(1970, 1, 1), dt(2100, 1, 1))
+        .await
+        .expect("aggregate friction window");
+    assert_eq!(agg.submit_count, 6);
+    assert_eq!(agg.recorded_count, 3);
+    approx_eq(agg.median_form_time_ms, 1000.0);
+    approx_eq(agg.avg_form_time_ms, 16000.0 / 6.0);
+    assert_eq!(agg.error_count, 2);
+    assert_eq!(agg.retries_total, 4);
+    approx_eq(agg.error_rate, 2.0 / 6.0);
+    approx_eq(agg.immediate_edit_rate, 2.0 / 6.0);
+
+    let kinds =
+        repository::aggregate_friction_error_kinds_window(&pool, dt(1970, 1, 1), dt(2100, 1, 1))
+            .await
+            .expect("aggregate error kinds");
+    assert_eq!(kinds.len(), 1);
+    assert_eq!(kinds[0].occurrences, 2);
+}
+
+#[tokio::test]
+async fn test_archive_adds_columns_missing_from_older_archives() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+    }
+    let pool = db::connect().await.expect("db connect");
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .expect("migrator")
+        .run(&pool)
+        .await
+        .expect("migrations run");
+
+    // An archive made before sample_weight existed.
+    sqlx::query(
+        "CREATE TABLE personalization_friction_events_archive_2020 (id INTEGER, \
+         recorded_at DATETIME, form_time_ms INTEGER, error_kind TEXT, retry_count INTEGER, \
+         immediate_edit INTEGER, follow_up_failure INTEGER)",
+    )
+    .execute(&pool)
+    .await
+    .expect("create old archive");
+    sqlx::query(
+        "INSERT INTO personalization_friction_events(recorded_at, form_time_ms, sample_weight) \
+         VALUES ('2020-03-01 00:00:00', 1500, 5)",
+    )
+    .execute(&pool)
+    .await
+    .expect("insert old event");
+
+    let moved = repository::archive_rows_before(
+        &pool,
+        "personalization_friction_events",
+        "recorded_at",
+        dt(2021, 1, 1),
+    )
+    .await
+    .expect("archive");
+    assert_eq!(moved, vec![(2020, 1)]);
+    let weight: i64 = sqlx::query_scalar(
+        "SELECT sample_weight FROM personalization_friction_events_archive_2020",
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("archived weight");
+    assert_eq!(weight, 5);
+}
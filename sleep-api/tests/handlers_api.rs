@@ -0,0 +1,216 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::{America::New_York, Asia::Tokyo};
+use sleep_api::{
+    Error,
+    db::Db,
+    domain::DomainError,
+    events::{DomainEvent, EventBus},
+    handlers::{self, EditLock, TimeContext},
+    models::SleepInput,
+    repository,
+};
+
+async fn setup() -> Db {
+    let db = sqlx::sqlite::SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&db)
+        .await
+        .unwrap();
+    db
+}
+
+fn sleep(date: &str, bed: &str, wake: &str, latency_min: i32) -> SleepInput {
+    serde_json::from_value(serde_json::json!({
+        "date": date, "bed_time": bed, "wake_time": wake,
+        "latency_min": latency_min, "awakenings": 0, "quality": 4
+    }))
+    .unwrap()
+}
+
+async fn stored_duration(db: &Db, date: &str) -> Option<i32> {
+    let date: NaiveDate = date.parse().unwrap();
+    repository::list_sleep_range(db, date, date).await.unwrap()[0].duration_min
+}
+
+#[tokio::test]
+async fn test_duration_recompute_across_dst() {
+    let db = setup().await;
+    let events = EventBus::new();
+    let mut rx = events.subscribe();
+    let now = Utc.with_ymd_and_hms(2025, 3, 9, 12, 0, 0).unwrap();
+    let new_york = TimeContext::fixed(now, New_York);
+
+    // Spring forward: 23:00 → 07:00 loses an hour.
+    let id = handlers::create_sleep(
+        &db,
+        &events,
+        &new_york,
+        &EditLock::none(),
+        sleep("2025-03-09", "23:00", "07:00", 10),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stored_duration(&db, "2025-03-09").await, Some(7 * 60));
+    assert!(matches!(
+        rx.try_recv().unwrap(),
+        DomainEvent::SleepCreated {
+            duration_min: 420,
+            ..
+        }
+    ));
+
+    // Fall back: the same night gains an hour.
+    handlers::update_sleep(
+        &db,
+        &events,
+        &new_york,
+        &EditLock::none(),
+        id,
+        sleep("2025-11-02", "23:00", "07:00", 10),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stored_duration(&db, "2025-11-02").await, Some(9 * 60));
+
+    // No DST in Tokyo.
+    let tokyo = TimeContext::fixed(now, Tokyo);
+    handlers::update_sleep(
+        &db,
+        &events,
+        &tokyo,
+        &EditLock::none(),
+        id,
+        sleep("2025-11-02", "23:00", "07:00", 10),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stored_duration(&db, "2025-11-02").await, Some(8 * 60));
+}
+
+#[tokio::test]
+async fn test_session_timezone_overrides_user_timezone() {
+    let db = setup().await;
+    let events = EventBus::new();
+    let tokyo = TimeContext::fixed(Utc.with_ymd_and_hms(2025, 3, 9, 12, 0, 0).unwrap(), Tokyo);
+
+    // A night in New York logged from a Tokyo account: spring forward still applies.
+    let mut trip = sleep("2025-03-09", "23:00", "07:00", 10);
+    trip.timezone = Some("America/New_York".into());
+    let id = handlers::create_sleep(&db, &events, &tokyo, &EditLock::none(), trip)
+        .await
+        .unwrap();
+    assert_eq!(stored_duration(&db, "2025-03-09").await, Some(7 * 60));
+    let stored = repository::find_sleep_by_id(&db, id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.timezone.as_deref(), Some("America/New_York"));
+
+    // Patching only the timezone recomputes the duration.
+    let patch = serde_json::from_value(serde_json::json!({ "timezone": "Asia/Tokyo" })).unwrap();
+    let patched = handlers::patch_sleep(&db, &events, &tokyo, &EditLock::none(), id, patch)
+        .await
+        .unwrap();
+    assert_eq!(patched.timezone.as_deref(), Some("Asia/Tokyo"));
+    assert_eq!(stored_duration(&db, "2025-03-09").await, Some(8 * 60));
+
+    let mut bad = sleep("2025-03-10", "23:00", "07:00", 10);
+    bad.timezone = Some("Mars/Olympus".into());
+    let err = handlers::create_sleep(&db, &events, &tokyo, &EditLock::none(), bad)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::Domain(DomainError::InvalidInput(m)) if m.contains("timezone")),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_validation_runs_before_overlap_check() {
+    let db = setup().await;
+    let events = EventBus::new();
+    let time = TimeContext::fixed(Utc.with_ymd_and_hms(2025, 6, 2, 0, 0, 0).unwrap(), Tokyo);
+    handlers::create_sleep(
+        &db,
+        &events,
+        &time,
+        &EditLock::none(),
+        sleep("2025-06-02", "23:00", "07:00", 10),
+    )
+    .await
+    .unwrap();
+
+    let err = handlers::create_sleep(
+        &db,
+        &events,
+        &time,
+        &EditLock::none(),
+        sleep("2025-06-02", "23:30", "06:30", 500),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(&err, Error::Domain(DomainError::InvalidInput(m)) if m.contains("latency_min")),
+        "{err:?}"
+    );
+    let err = handlers::create_sleep(
+        &db,
+        &events,
+        &time,
+        &EditLock::none(),
+        sleep("2025-06-02", "23:30", "06:30", 10),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(&err, Error::Domain(DomainError::InvalidInput(m)) if m.contains("overlaps")),
+        "{err:?}"
+    );
+    let err = handlers::update_sleep(
+        &db,
+        &events,
+        &time,
+        &EditLock::none(),
+        999,
+        sleep("2025-06-10", "23:00", "07:00", 10),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::NotFound), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_errors_chain_their_sources() {
+    let db = setup().await;
+    let err = handlers::create_sleep(
+        &db,
+        &EventBus::new(),
+        &TimeContext::fixed(Utc::now(), Tokyo),
+        &EditLock::none(),
+        sleep("2025-06-02", "23:30", "06:30", 500),
+    )
+    .await
+    .unwrap_err();
+    let source = std::error::Error::source(&err).expect("domain source");
+    assert!(source.downcast_ref::<DomainError>().is_some());
+
+    db.close().await;
+    let err = repository::list_sleep_range(
+        &db,
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2025, 6, 30).unwrap(),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(err, Error::Database(sqlx::Error::PoolClosed)),
+        "{err:?}"
+    );
+    let source = std::error::Error::source(&err).expect("sqlx source");
+    assert!(source.downcast_ref::<sqlx::Error>().is_some());
+}
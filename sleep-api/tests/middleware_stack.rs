@@ -0,0 +1,178 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db, middleware::stack::MiddlewareConfig};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+/// Serve `middleware` around a migrated in-memory database and log in; returns the address.
+async fn serve(middleware: Option<MiddlewareConfig>, client: &Client) -> String {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+    let state = app::AppState::new(pool, sleep_api::config::session_key());
+    let app = match middleware {
+        Some(middleware) => app::router_with_middleware(state, middleware),
+        None => app::router_with_state(state),
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    wait_ready(client, &addr).await;
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": "admin@example.com", "password": "password123" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    addr
+}
+
+fn night(date: &str) -> serde_json::Value {
+    serde_json::json!({
+        "date": date, "bed_time": "23:00:00", "wake_time": "07:00:00",
+        "latency_min": 10, "awakenings": 0, "quality": 4
+    })
+}
+
+#[tokio::test]
+async fn test_default_stack_is_unchanged() {
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    let addr = serve(None, &client).await;
+
+    let res = client
+        .get(format!("http://{addr}/api/schema/SummaryResponse"))
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["x-content-type-options"], "nosniff");
+    assert!(res.headers().get("content-encoding").is_none());
+
+    // Logged in but without the CSRF header.
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .json(&night("2025-06-01"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+}
+
+#[tokio::test]
+async fn test_custom_stack_drops_and_adds_layers() {
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    let middleware = MiddlewareConfig::from_config()
+        .security_headers(false)
+        .csrf(false)
+        .compression(true)
+        .tracing(true);
+    let addr = serve(Some(middleware), &client).await;
+    let url = format!("http://{addr}/api/schema/SummaryResponse");
+
+    let plain = client.get(&url).send().await.unwrap();
+    assert_eq!(plain.status(), 200);
+    assert!(plain.headers().get("x-content-type-options").is_none());
+    assert!(plain.headers().get("content-encoding").is_none());
+    let plain = plain.bytes().await.unwrap();
+    assert!(plain.len() > 1024, "schema too small to compress");
+
+    let res = client
+        .get(&url)
+        .header("Accept-Encoding", "br;q=1.0, gzip;q=0.8")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-encoding"], "gzip");
+    assert_eq!(res.headers()["vary"], "accept-encoding");
+    let compressed = res.bytes().await.unwrap();
+    assert!(compressed.len() < plain.len());
+    let mut decoded = Vec::new();
+    std::io::Read::read_to_end(
+        &mut flate2::read::GzDecoder::new(&compressed[..]),
+        &mut decoded,
+    )
+    .unwrap();
+    assert_eq!(decoded, plain);
+
+    let res = client
+        .get(&url)
+        .header("Accept-Encoding", "gzip;q=0")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.headers().get("content-encoding").is_none());
+
+    // CSRF is left to the embedder: the session cookie alone is enough.
+    let res = client
+        .post(format!("http://{addr}/api/sleep"))
+        .json(&night("2025-06-01"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    // Resumable downloads keep their byte offsets: never compressed.
+    for day in 2..=12 {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .json(&night(&format!("2025-06-{day:02}")))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+    let res = client
+        .get(format!("http://{addr}/api/export"))
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["accept-ranges"], "bytes");
+    assert!(res.headers().get("content-encoding").is_none());
+    assert!(
+        res.bytes().await.unwrap().len() as u64 > sleep_api::middleware::compression::MIN_BYTES
+    );
+}
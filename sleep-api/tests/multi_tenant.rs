@@ -0,0 +1,185 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::tenant::{TenantMode, TenantRegistry};
+
+fn set_tenant_admin_env(tenant: &str, email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var(format!("TENANT_{tenant}_ADMIN_EMAIL"), email);
+        std::env::set_var(format!("TENANT_{tenant}_ADMIN_PASSWORD_HASH"), hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/t/alpha/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Server did not become ready in time");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie in login response");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie in login response");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_tenants_are_isolated() {
+    unsafe {
+        std::env::set_var("COOKIE_SECURE", "0");
+        // Global credentials must not open tenant databases.
+        std::env::set_var("ADMIN_EMAIL", "global@example.com");
+    };
+    set_tenant_admin_env("ALPHA", "alpha@example.com", "alpha-pass");
+    set_tenant_admin_env("BETA", "beta@example.com", "beta-pass");
+
+    let data_dir = std::env::temp_dir().join(format!(
+        "sleep-tenants-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let registry = TenantRegistry::new(
+        TenantMode::PathPrefix,
+        data_dir.clone(),
+        ["alpha".to_string(), "beta".to_string()],
+        axum_extra::extract::cookie::Key::generate(),
+    );
+    let app = sleep_api::tenant::router(registry);
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::new();
+    wait_ready(&client, &addr.to_string()).await;
+
+    // Unknown tenants and unprefixed paths are rejected without creating files.
+    let res = client
+        .get(format!("http://{addr}/t/mallory/api/health"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["code"], "unknown_tenant");
+    assert!(!data_dir.join("mallory.sqlite").exists());
+
+    // Each tenant only accepts its own credentials.
+    let res = client
+        .post(format!("http://{addr}/t/beta/api/login.json"))
+        .json(&serde_json::json!({ "email": "alpha@example.com", "password": "alpha-pass" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let alpha = format!("{addr}/t/alpha");
+    let (csrf, session) =
+        login_and_get_auth(&client, &alpha, "alpha@example.com", "alpha-pass").await;
+    let auth = format!("session={session}; csrf={csrf}");
+    let res = client
+        .post(format!("http://{alpha}/api/sleep"))
+        .header("Cookie", &auth)
+        .header("X-CSRF-Token", &csrf)
+        .json(&serde_json::json!({
+            "date": "2025-06-01", "bed_time": "23:00:00", "wake_time": "07:00:00",
+            "latency_min": 10, "awakenings": 0, "quality": 4
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    // Alpha's session cookie does not authenticate against beta.
+    let beta = format!("{addr}/t/beta");
+    let res = client
+        .get(format!("http://{beta}/api/sleep/recent"))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let (beta_csrf, beta_session) =
+        login_and_get_auth(&client, &beta, "beta@example.com", "beta-pass").await;
+    let res = client
+        .get(format!(
+            "http://{beta}/api/sleep/range?from=2025-06-01&to=2025-06-01"
+        ))
+        .header(
+            "Cookie",
+            format!("session={beta_session}; csrf={beta_csrf}"),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let rows: Vec<serde_json::Value> = res.json().await.unwrap();
+    assert!(rows.is_empty());
+
+    let res = client
+        .get(format!(
+            "http://{alpha}/api/sleep/range?from=2025-06-01&to=2025-06-01"
+        ))
+        .header("Cookie", &auth)
+        .send()
+        .await
+        .unwrap();
+    let rows: Vec<serde_json::Value> = res.json().await.unwrap();
+    assert_eq!(rows.len(), 1);
+
+    assert!(data_dir.join("alpha.sqlite").exists());
+    assert!(data_dir.join("beta.sqlite").exists());
+
+    server.abort();
+    let _ = std::fs::remove_dir_all(&data_dir);
+}
@@ -0,0 +1,399 @@
+//! Large-history load test with p95 latency budgets.
+//!
+//! Seeds `PERF_YEARS` years of nightly sleep, exercise and notes into a temporary database,
+//! then drives a weighted mix of dashboard, list and trends requests against the in-process
+//! router from `PERF_CONCURRENCY` workers and checks each endpoint's p95 against its budget.
+//!
+//! Ignored by default; run it in release mode:
+//!
+//! ```text
+//! cargo test -p sleep-api --release --test perf -- --ignored --nocapture
+//! ```
+//!
+//! Knobs (environment): `PERF_YEARS` (default 5), `PERF_REQUESTS` (default 2000),
+//! `PERF_CONCURRENCY` (default 8), `PERF_BUDGET_SCALE` (default 1.0; raise it for debug builds
+//! or slow machines).
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use chrono::{Duration as ChronoDuration, NaiveDate, TimeZone, Utc};
+use sleep_api::{
+    app, db,
+    events::EventBus,
+    handlers::{self, EditLock, TimeContext},
+};
+use tower::ServiceExt;
+
+/// Last seeded wake date; the server clock is frozen the evening after.
+const LAST_DAY: (i32, u32, u32) = (2025, 6, 30);
+
+/// One request kind in the mix: label, relative weight, p95 budget, and request builder.
+///
+/// Budgets are roughly 3x the p95 measured with the defaults on a 4-core release build.
+struct Endpoint {
+    label: &'static str,
+    weight: u32,
+    budget: Duration,
+    path: fn(NaiveDate, u64) -> String,
+    write: bool,
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+fn endpoints() -> Vec<Endpoint> {
+    let ms = Duration::from_millis;
+    vec![
+        Endpoint {
+            label: "GET /api/dashboard",
+            weight: 20,
+            budget: ms(300),
+            path: |_, _| "/api/dashboard?days=30".into(),
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/sleep/recent",
+            weight: 15,
+            budget: ms(300),
+            path: |_, _| "/api/sleep/recent?days=7".into(),
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/sleep/range",
+            weight: 15,
+            budget: ms(200),
+            path: |last, n| {
+                let to = last - ChronoDuration::days((n % 700) as i64);
+                format!(
+                    "/api/sleep/range?from={}&to={to}",
+                    to - ChronoDuration::days(61)
+                )
+            },
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/trends/sleep-bars",
+            weight: 15,
+            budget: ms(200),
+            path: |last, _| {
+                format!(
+                    "/api/trends/sleep-bars?from={}&to={last}",
+                    last - ChronoDuration::days(89)
+                )
+            },
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/trends/summary",
+            weight: 10,
+            budget: ms(300),
+            path: |last, n| {
+                let bucket = if n % 2 == 0 { "day" } else { "week" };
+                format!(
+                    "/api/trends/summary?from={}&to={last}&bucket={bucket}",
+                    last - ChronoDuration::days(364)
+                )
+            },
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/trends/compare",
+            weight: 5,
+            budget: ms(300),
+            path: |last, _| {
+                format!(
+                    "/api/trends/compare?period=month&anchor={}",
+                    last.format("%Y-%m")
+                )
+            },
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/trends/decompose",
+            weight: 5,
+            budget: ms(300),
+            path: |last, _| {
+                format!(
+                    "/api/trends/decompose?from={}&to={last}&metric=duration",
+                    last - ChronoDuration::days(729)
+                )
+            },
+            write: false,
+        },
+        Endpoint {
+            label: "GET /api/stats/completeness",
+            weight: 5,
+            budget: ms(250),
+            path: |last, _| {
+                format!(
+                    "/api/stats/completeness?from={}&to={last}",
+                    last - ChronoDuration::days(364)
+                )
+            },
+            write: false,
+        },
+        Endpoint {
+            label: "POST /api/note",
+            weight: 10,
+            budget: ms(200),
+            path: |_, _| "/api/note".into(),
+            write: true,
+        },
+    ]
+}
+
+/// Small deterministic generator so seeded histories are identical between runs.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+async fn seed(db: &db::Db, last: NaiveDate, years: i64) {
+    let time = TimeContext::fixed(Utc::now(), chrono_tz::Asia::Tokyo);
+    let events = EventBus::new();
+    let lock = EditLock::none();
+    let mut rng = XorShift(0x5EED_5EED);
+    for offset in (0..years * 365).rev() {
+        let date = last - ChronoDuration::days(offset);
+        let bed_min = 22 * 60 + rng.below(150) as i64; // 22:00..00:29
+        let bed = format!("{:02}:{:02}", (bed_min / 60) % 24, bed_min % 60);
+        let wake = format!("{:02}:{:02}", 6 + rng.below(2), rng.below(60));
+        let input = serde_json::from_value(serde_json::json!({
+            "date": date, "bed_time": bed, "wake_time": wake,
+            "latency_min": rng.below(40), "awakenings": rng.below(4),
+            "quality": 1 + rng.below(5)
+        }))
+        .unwrap();
+        handlers::create_sleep(db, &events, &time, &lock, input)
+            .await
+            .unwrap();
+        if rng.below(3) == 0 {
+            let intensity = if rng.below(2) == 0 { "light" } else { "hard" };
+            let input = serde_json::from_value(serde_json::json!({
+                "date": date, "intensity": intensity,
+                "start_time": "18:00:00", "duration_min": 20 + rng.below(60)
+            }))
+            .unwrap();
+            handlers::create_exercise(db, &events, &lock, input)
+                .await
+                .unwrap();
+        }
+        if rng.below(7) == 0 {
+            let input = serde_json::from_value(serde_json::json!({
+                "date": date, "body": "seeded note"
+            }))
+            .unwrap();
+            handlers::create_note(db, &events, &lock, input)
+                .await
+                .unwrap();
+        }
+    }
+}
+
+/// Log in through the router and return `(cookie header, csrf token)`.
+async fn login(app: &Router) -> (String, String) {
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/login.json")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    r#"{"email":"admin@example.com","password":"password123"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut pairs = Vec::new();
+    let mut csrf = String::new();
+    for value in res.headers().get_all(header::SET_COOKIE) {
+        let pair = value
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+        if let Some(token) = pair.strip_prefix("csrf=") {
+            csrf = token.to_string();
+        }
+        pairs.push(pair);
+    }
+    (pairs.join("; "), csrf)
+}
+
+fn p(sorted: &[Duration], q: f64) -> Duration {
+    let idx = ((sorted.len() as f64 * q).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[idx]
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[ignore = "load test; run with --release -- --ignored"]
+async fn perf_budgets_hold_on_large_history() {
+    let years: i64 = env_or("PERF_YEARS", 5);
+    let requests: u64 = env_or("PERF_REQUESTS", 2000);
+    let concurrency: u64 = env_or("PERF_CONCURRENCY", 8);
+    let scale: f64 = env_or("PERF_BUDGET_SCALE", 1.0);
+
+    let hash = Argon2::default()
+        .hash_password(b"password123", &SaltString::generate(OsRng))
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("COOKIE_SECURE", "0");
+        std::env::set_var("ADMIN_EMAIL", "admin@example.com");
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+
+    let path = std::env::temp_dir().join(format!("sleep-perf-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let pool = db::connect_file(&path).await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let (y, m, d) = LAST_DAY;
+    let last = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let started = Instant::now();
+    seed(&pool, last, years).await;
+    println!(
+        "seeded {years} years ({} nights) in {:.1?}",
+        years * 365,
+        started.elapsed()
+    );
+
+    let frozen = Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap();
+    let app = app::router_with_state(app::AppState {
+        db: pool.clone(),
+        key: sleep_api::config::session_key().into(),
+        events: EventBus::new(),
+        clock: Arc::new(sleep_api::time::FixedClock(frozen)),
+        features: sleep_api::config::features(),
+        integrity: Default::default(),
+        deprecations: Default::default(),
+    });
+    let (cookie, csrf) = login(&app).await;
+
+    let endpoints = Arc::new(endpoints());
+    let total_weight: u32 = endpoints.iter().map(|e| e.weight).sum();
+    let started = Instant::now();
+    let mut workers = Vec::new();
+    for worker in 0..concurrency {
+        let (app, endpoints) = (app.clone(), endpoints.clone());
+        let (cookie, csrf) = (cookie.clone(), csrf.clone());
+        workers.push(tokio::spawn(async move {
+            let mut rng = XorShift(0xC0FFEE + worker);
+            let mut samples: Vec<(usize, Duration)> = Vec::new();
+            for n in (worker..requests).step_by(concurrency as usize) {
+                let mut pick = rng.below(u64::from(total_weight)) as u32;
+                let idx = endpoints
+                    .iter()
+                    .position(|e| {
+                        if pick < e.weight {
+                            true
+                        } else {
+                            pick -= e.weight;
+                            false
+                        }
+                    })
+                    .unwrap();
+                let endpoint = &endpoints[idx];
+                let uri = (endpoint.path)(last, n);
+                let req = if endpoint.write {
+                    Request::post(uri)
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .header("X-CSRF-Token", &csrf)
+                        .header(header::COOKIE, &cookie)
+                        .body(Body::from(format!(
+                            r#"{{"date":"{last}","body":"load test {n}"}}"#
+                        )))
+                } else {
+                    Request::get(uri)
+                        .header(header::COOKIE, &cookie)
+                        .body(Body::empty())
+                }
+                .unwrap();
+                let t0 = Instant::now();
+                let res = app.clone().oneshot(req).await.unwrap();
+                let status = res.status();
+                axum::body::to_bytes(res.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                assert!(status.is_success(), "{} returned {status}", endpoint.label);
+                samples.push((idx, t0.elapsed()));
+            }
+            samples
+        }));
+    }
+    let mut by_endpoint: BTreeMap<usize, Vec<Duration>> = BTreeMap::new();
+    for worker in workers {
+        for (idx, elapsed) in worker.await.unwrap() {
+            by_endpoint.entry(idx).or_default().push(elapsed);
+        }
+    }
+    let wall = started.elapsed();
+    println!(
+        "{requests} requests, {concurrency} workers, {:.1?} ({:.0} req/s)",
+        wall,
+        requests as f64 / wall.as_secs_f64()
+    );
+
+    let mut over = Vec::new();
+    println!(
+        "{:<30} {:>6} {:>10} {:>10} {:>10} {:>10}",
+        "endpoint", "n", "p50", "p95", "max", "budget"
+    );
+    for (idx, mut samples) in by_endpoint {
+        samples.sort();
+        let endpoint = &endpoints[idx];
+        let budget = endpoint.budget.mul_f64(scale);
+        let p95 = p(&samples, 0.95);
+        println!(
+            "{:<30} {:>6} {:>10.1?} {:>10.1?} {:>10.1?} {:>10.1?}",
+            endpoint.label,
+            samples.len(),
+            p(&samples, 0.50),
+            p95,
+            samples[samples.len() - 1],
+            budget
+        );
+        if p95 > budget {
+            over.push(format!("{}: p95 {p95:.1?} > {budget:.1?}", endpoint.label));
+        }
+    }
+    pool.close().await;
+    let _ = std::fs::remove_file(&path);
+    assert!(
+        over.is_empty(),
+        "p95 budgets exceeded:\n{}",
+        over.join("\n")
+    );
+}
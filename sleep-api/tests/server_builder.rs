@@ -0,0 +1,65 @@
+use reqwest::Client;
+use sleep_api::server::{Server, ServerConfig};
+
+#[tokio::test]
+async fn test_builder_serves_and_shuts_down() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    let client = Client::new();
+
+    // Auth on: protected routes need a session.
+    let server = Server::builder()
+        .config(ServerConfig::default().bind_addr("127.0.0.2:0"))
+        .build()
+        .await
+        .expect("build server");
+    let base = format!("http://{}", server.local_addr());
+    let res = client
+        .get(format!("{base}/api/mood?from=2025-06-01&to=2025-06-07"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    server.shutdown().await.expect("clean shutdown");
+    assert!(
+        client
+            .get(format!("{base}/api/health"))
+            .send()
+            .await
+            .is_err(),
+        "server still answering after shutdown"
+    );
+
+    // Auth off: reads and writes succeed without login or CSRF token.
+    let server = Server::builder()
+        .config(ServerConfig::default().bind_addr("127.0.0.2:0"))
+        .db(sleep_api::db::connect().await.unwrap())
+        .disable_auth_for_tests()
+        .build()
+        .await
+        .expect("build server");
+    let base = format!("http://{}", server.local_addr());
+    let res = client
+        .post(format!("{base}/api/mood"))
+        .json(&serde_json::json!({ "date": "2025-06-02", "mood": 4 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204, "write without CSRF: {}", res.status());
+    let res = client
+        .get(format!("{base}/api/mood?from=2025-06-01&to=2025-06-07"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM mood_entries")
+        .fetch_one(server.db())
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    server.shutdown().await.expect("clean shutdown");
+}
@@ -37,14 +37,13 @@ fn parse_cookie<'a>(
     name: &str,
 ) -> Option<String> {
     for hv in headers {
-        if let Ok(s) = hv.to_str() {
-            if s.starts_with(name) {
-                if let Some(eq_idx) = s.find('=') {
-                    let rest = &s[eq_idx + 1..];
-                    let end = rest.find(';').unwrap_or(rest.len());
-                    return Some(rest[..end].to_string());
-                }
-            }
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
         }
     }
     None
@@ -0,0 +1,92 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use sleep_api::{
+    completeness::completeness_range,
+    db::Db,
+    events::EventBus,
+    handlers::{self, EditLock, TimeContext},
+};
+
+async fn setup() -> Db {
+    let db = sqlx::sqlite::SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&db)
+        .await
+        .unwrap();
+    db
+}
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_completeness_reads_every_kind() {
+    let db = setup().await;
+    let events = EventBus::new();
+    let time = TimeContext::fixed(
+        Utc.with_ymd_and_hms(2025, 6, 3, 12, 0, 0).unwrap(),
+        chrono_tz::Asia::Tokyo,
+    );
+
+    // 2025-06-02: everything, with factors from the previous evening's routine.
+    handlers::create_sleep(
+        &db,
+        &events,
+        &time,
+        &EditLock::none(),
+        serde_json::from_value(serde_json::json!({
+            "date": "2025-06-02", "bed_time": "23:00", "wake_time": "07:00",
+            "latency_min": 10, "awakenings": 0, "quality": 4, "wake_feeling": 3
+        }))
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO routine_entries(date, item_id, done) VALUES ('2025-06-01', 'no_screens', 1)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+    for (table, sql) in [
+        (
+            "exercise",
+            "INSERT INTO exercise_events(date, intensity) VALUES ('2025-06-02', 'none')",
+        ),
+        (
+            "note",
+            "INSERT INTO notes(date, body) VALUES ('2025-06-02', 'slept well')",
+        ),
+        (
+            "blank note",
+            "INSERT INTO notes(date, body) VALUES ('2025-06-03', '  ')",
+        ),
+        (
+            "disturbance",
+            "INSERT INTO disturbances(date, time, kind, duration_min) VALUES ('2025-06-03', '02:00', 'noise', 5)",
+        ),
+    ] {
+        sqlx::query(sql)
+            .execute(&db)
+            .await
+            .unwrap_or_else(|e| panic!("{table}: {e}"));
+    }
+
+    let out = completeness_range(&db, date("2025-06-01"), date("2025-06-03"))
+        .await
+        .unwrap();
+    assert_eq!(out.days.len(), 3);
+    assert_eq!(out.days[0].score_pct, 0.0);
+    assert!(out.days[1].complete, "{:?}", out.days[1]);
+    let third = &out.days[2];
+    assert!(third.factors && !third.note && !third.sleep);
+    assert_eq!(third.score_pct, 20.0);
+    assert_eq!(out.complete_days, 1);
+    assert_eq!(out.completeness_pct, 40.0);
+    assert_eq!((out.current_streak, out.longest_streak), (0, 1));
+}
@@ -36,14 +36,13 @@ fn parse_cookie<'a>(
     name_with_eq: &str,
 ) -> Option<String> {
     for hv in headers {
-        if let Ok(s) = hv.to_str() {
-            if s.starts_with(name_with_eq) {
-                if let Some(eq_idx) = s.find('=') {
-                    let rest = &s[eq_idx + 1..];
-                    let end = rest.find(';').unwrap_or(rest.len());
-                    return Some(rest[..end].to_string());
-                }
-            }
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
         }
     }
     None
@@ -120,6 +119,12 @@ async fn test_trends_sleep_bars_basic() {
         latency_min: 15,
         awakenings: 0,
         quality: Quality(4),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
     let s2 = SleepInput {
         date: chrono::NaiveDate::from_ymd_opt(2025, 6, 18).unwrap(),
@@ -128,6 +133,12 @@ async fn test_trends_sleep_bars_basic() {
         latency_min: 20,
         awakenings: 1,
         quality: Quality(3),
+        wake_feeling: None,
+        sleep_inertia_min: None,
+        waso_min: None,
+        aids: Vec::new(),
+        tags: Vec::new(),
+        timezone: None,
     };
 
     let res = client
@@ -164,6 +175,50 @@ async fn test_trends_sleep_bars_basic() {
     assert!(first.get("date").is_some(), "missing date");
     assert!(first.get("bed_time").is_some(), "missing bed_time");
     assert!(first.get("wake_time").is_some(), "missing wake_time");
+
+    // Same data as CSV, via Accept header or ?format=csv
+    let res = client
+        .get(&bars_url)
+        .header("Accept", "text/csv")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.headers()["content-type"].to_str().unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let csv = res.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "date,bed_time,wake_time,quality,duration_min,duration_hours",
+            "2025-06-17,23:05:00,06:15:00,4,430,7.17",
+            "2025-06-18,00:30:00,07:00:00,3,390,6.5",
+        ]
+    );
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?from=2025-06-16&to=2025-06-19&bucket=week&format=csv"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let csv = res.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("bucket,avg_duration_min,min_duration_min,max_duration_min,"));
+    assert!(lines[1].starts_with("2025-W25,410,390,430,3.5,17.5,,,0,1,410,410,0"));
+
+    let res = client
+        .get(format!("{bars_url}&format=xml"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
 }
 
 #[tokio::test]
@@ -207,6 +262,12 @@ async fn test_personalization_response_shape_and_guardrails() {
             latency_min: 15,
             awakenings: 0,
             quality: Quality(4),
+            wake_feeling: None,
+            sleep_inertia_min: None,
+            waso_min: None,
+            aids: Vec::new(),
+            tags: Vec::new(),
+            timezone: None,
         },
         SleepInput {
             date: chrono::NaiveDate::from_ymd_opt(2025, 6, 24).unwrap(),
@@ -215,6 +276,12 @@ async fn test_personalization_response_shape_and_guardrails() {
             latency_min: 12,
             awakenings: 1,
             quality: Quality(3),
+            wake_feeling: None,
+            sleep_inertia_min: None,
+            waso_min: None,
+            aids: Vec::new(),
+            tags: Vec::new(),
+            timezone: None,
         },
         SleepInput {
             date: chrono::NaiveDate::from_ymd_opt(2025, 6, 25).unwrap(),
@@ -223,6 +290,12 @@ async fn test_personalization_response_shape_and_guardrails() {
             latency_min: 11,
             awakenings: 0,
             quality: Quality(5),
+            wake_feeling: None,
+            sleep_inertia_min: None,
+            waso_min: None,
+            aids: Vec::new(),
+            tags: Vec::new(),
+            timezone: None,
         },
         SleepInput {
             date: chrono::NaiveDate::from_ymd_opt(2025, 6, 26).unwrap(),
@@ -231,6 +304,12 @@ async fn test_personalization_response_shape_and_guardrails() {
             latency_min: 13,
             awakenings: 1,
             quality: Quality(4),
+            wake_feeling: None,
+            sleep_inertia_min: None,
+            waso_min: None,
+            aids: Vec::new(),
+            tags: Vec::new(),
+            timezone: None,
         },
     ];
 
@@ -287,3 +366,495 @@ async fn test_personalization_response_shape_and_guardrails() {
             .any(|r| { r.as_str() == Some("needs at least 60 baseline sessions in prior window") })
     );
 }
+
+#[tokio::test]
+async fn test_split_sleep_per_segment_and_day() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // Biphasic day: 4h core sleep plus a 90 minute afternoon segment, same wake date.
+    let post = |bed: &str, wake: &str, quality: i32| {
+        client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": "2025-06-18", "bed_time": bed, "wake_time": wake,
+                "latency_min": 10, "awakenings": 0, "quality": quality
+            }))
+            .send()
+    };
+    assert_eq!(post("13:00:00", "14:30:00", 2).await.unwrap().status(), 201);
+    assert_eq!(post("23:00:00", "03:00:00", 4).await.unwrap().status(), 201);
+    // Segments must not overlap.
+    assert_eq!(post("02:00:00", "05:00:00", 3).await.unwrap().status(), 400);
+
+    let range = "from=2025-06-18&to=2025-06-18";
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/sleep-bars?{range}&per=segment"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let bars: serde_json::Value = res.json().await.unwrap();
+    let bars = bars.as_array().unwrap();
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0]["bed_time"], "23:00:00");
+    assert_eq!(bars[1]["duration_min"], 90);
+
+    let res = client
+        .get(format!("http://{addr}/api/trends/summary?{range}"))
+        .send()
+        .await
+        .unwrap();
+    let day: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(day["per"], "day");
+    assert_eq!(day["duration_by_bucket"][0]["avg_min"], 330.0);
+    let segments = &day["segments_by_bucket"][0];
+    assert_eq!(segments["avg_segments"], 2.0);
+    assert_eq!(segments["avg_total_min"], 330.0);
+    assert_eq!(segments["avg_longest_min"], 240.0);
+    assert_eq!(segments["split_days"], 1);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?{range}&per=segment"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let seg: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(seg["per"], "segment");
+    assert_eq!(seg["duration_by_bucket"][0]["avg_min"], 165.0);
+    assert_eq!(seg["duration_by_bucket"][0]["max_min"], 240);
+    assert_eq!(seg["quality_by_bucket"][0]["avg"], 3.0);
+    assert_eq!(
+        seg["quality_by_bucket"][0]["counts"],
+        serde_json::json!([0, 1, 0, 1, 0])
+    );
+    assert_eq!(seg["duration_by_bucket"][0]["p25_min"], 127.5);
+    assert_eq!(seg["duration_by_bucket"][0]["p75_min"], 202.5);
+    assert_eq!(seg["duration_by_bucket"][0]["sd_min"], 75.0);
+    assert!(day["duration_by_bucket"][0]["sd_min"].is_null());
+    assert_eq!(seg["segments_by_bucket"], day["segments_by_bucket"]);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/summary?{range}&per=night"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_trends_compare_month() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // One night each: same month last year (420), previous month (400), current month (460).
+    for (date, wake) in [
+        ("2024-06-10", "06:00:00"),
+        ("2025-05-10", "05:40:00"),
+        ("2025-06-10", "06:40:00"),
+    ] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:00:00", "wake_time": wake,
+                "latency_min": 10, "awakenings": 0, "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/compare?period=month&anchor=2025-06"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["current"]["label"], "2025-06");
+    assert_eq!(body["previous"]["to"], "2025-05-31");
+    assert_eq!(body["year_ago"]["nights"], 1);
+    let duration = &body["deltas"][0];
+    assert_eq!(duration["metric"], "duration_min");
+    assert_eq!(duration["current"], 460.0);
+    assert_eq!(duration["vs_previous"], 60.0);
+    assert_eq!(duration["vs_year_ago"], 40.0);
+    assert_eq!(body["warnings"].as_array().unwrap().len(), 3);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/compare?period=year&anchor=2025"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["current"]["nights"], 2);
+    assert!(body["year_ago"].is_null());
+    assert_eq!(body["deltas"][0]["vs_previous"], 10.0);
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/compare?period=month&anchor=2025"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_trends_period_compare() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // Ten nights before (420..=429 min) and ten after (480..=489 min); quality never changes.
+    for day in 1..=10 {
+        for (month, hour) in [(3, 6), (4, 7)] {
+            let res = client
+                .post(format!("http://{addr}/api/sleep"))
+                .header("Cookie", format!("session={session}; csrf={csrf}"))
+                .header("X-CSRF-Token", &csrf)
+                .json(&serde_json::json!({
+                    "date": format!("2025-{month:02}-{day:02}"),
+                    "bed_time": "23:00:00",
+                    "wake_time": format!("{hour:02}:{:02}:00", day - 1),
+                    "latency_min": 10, "awakenings": 0, "quality": 3
+                }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 201);
+        }
+    }
+
+    let url = |query: &str| format!("http://{addr}/api/trends/period-compare?{query}");
+    let res = client
+        .get(url(
+            "a_from=2025-03-01&a_to=2025-03-31&b_from=2025-04-01&b_to=2025-04-10",
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["a"]["label"], "2025-03-01..2025-03-31");
+    assert_eq!(body["a"]["nights"], 10);
+    assert_eq!(body["b"]["nights"], 10);
+    let duration = &body["metrics"][0];
+    assert_eq!(duration["metric"], "duration_min");
+    assert_eq!(duration["a"], 424.5);
+    assert_eq!(duration["b"], 484.5);
+    assert_eq!(duration["diff"], 60.0);
+    assert_eq!(duration["hint"], "likely");
+    assert_eq!(duration["inference"]["effect_magnitude"], "large");
+    let quality = &body["metrics"][1];
+    assert_eq!(quality["diff"], 0.0);
+    assert_eq!(quality["hint"], "unlikely");
+    assert!(body["warnings"].as_array().unwrap().is_empty());
+
+    // Overlapping periods and a thin period are flagged.
+    let res = client
+        .get(url(
+            "a_from=2025-03-01&a_to=2025-04-02&b_from=2025-04-01&b_to=2025-04-03",
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["b"]["nights"], 3);
+    assert_eq!(body["metrics"][0]["hint"], "possible");
+    assert_eq!(body["warnings"].as_array().unwrap().len(), 2);
+
+    let res = client
+        .get(url("a_from=2025-03-01&a_to=2025-03-31&b_from=2025-04-01"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let problem: serde_json::Value = res.json().await.unwrap();
+    assert!(
+        problem.to_string().contains("period b"),
+        "unexpected problem: {problem}"
+    );
+
+    let res = client
+        .get(url(
+            "a_from=2025-03-01&a_to=2025-03-31&b_from=2025-04-01&b_to=2025-04-10&format=csv",
+        ))
+        .send()
+        .await
+        .unwrap();
+    let csv = res.text().await.unwrap();
+    assert!(csv.starts_with("metric,a,b,diff,effect_size,p_value,hint\n"));
+    assert!(csv.contains("duration_min,424.5,484.5,60,"));
+}
+
+#[tokio::test]
+async fn test_trends_decompose() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // Three weeks starting Monday 2025-06-02: 7h on weekdays, 8h on weekends.
+    for i in 0..21 {
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 6, 2).unwrap() + chrono::Duration::days(i);
+        let wake = if i % 7 >= 5 { "07:00:00" } else { "06:00:00" };
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:00:00", "wake_time": wake,
+                "latency_min": 10, "awakenings": 0, "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/decompose?metric=duration&from=2025-06-02&to=2025-06-22"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["metric"], "duration");
+    assert_eq!(body["points"].as_array().unwrap().len(), 21);
+    assert!(body["points"][0]["trend"].is_null());
+    assert_eq!(body["points"][3]["value"], 420.0);
+    let slope = body["trend_slope_per_week"].as_f64().unwrap();
+    assert!(slope.abs() < 1e-6, "slope {slope}");
+    let sat = body["weekday_effects"][5]["effect"].as_f64().unwrap();
+    let mon = body["weekday_effects"][0]["effect"].as_f64().unwrap();
+    assert!((sat - mon - 60.0).abs() < 1e-6);
+
+    for query in [
+        "metric=mood&from=2025-06-02&to=2025-06-22",
+        "metric=duration&from=2025-06-02&to=2025-06-10",
+    ] {
+        let res = client
+            .get(format!("http://{addr}/api/trends/decompose?{query}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+    }
+}
+
+#[tokio::test]
+async fn test_trends_population_context() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // Two 6.4 h nights with 15 minute latency.
+    for date in ["2025-06-01", "2025-06-02"] {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": "23:36:00", "wake_time": "06:00:00",
+                "latency_min": 15, "awakenings": 0, "quality": 3
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/context?from=2025-06-01&to=2025-06-30&age=70"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["age_bracket"], "65+");
+    assert_eq!(body["nights"], 2);
+    assert_eq!(body["duration"]["your_avg"], 384.0);
+    assert_eq!(body["duration"]["position"], "below");
+    assert_eq!(
+        body["duration"]["message"],
+        "Your 6.4 h average is below the typical 7\u{2013}8 h range for ages 65+."
+    );
+    assert_eq!(body["latency"]["position"], "within");
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/context?from=2025-07-01&to=2025-07-31"
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["age_bracket"], "26-64");
+    assert!(body["duration"].is_null());
+
+    let res = client
+        .get(format!(
+            "http://{addr}/api/trends/context?from=2025-06-01&to=2025-06-30&age=10"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+}
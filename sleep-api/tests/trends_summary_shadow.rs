@@ -0,0 +1,173 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString},
+};
+use reqwest::Client;
+use sleep_api::{app, db};
+
+fn set_admin_env(email: &str, password: &str) {
+    let salt = SaltString::generate(OsRng);
+    let argon2 = Argon2::default();
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+    unsafe {
+        std::env::set_var("ADMIN_EMAIL", email);
+        std::env::set_var("ADMIN_PASSWORD_HASH", hash);
+    }
+}
+
+async fn wait_ready(client: &Client, addr: &str) {
+    let health_url = format!("http://{addr}/api/health");
+    for _ in 0..20 {
+        if client.get(&health_url).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("server did not become ready");
+}
+
+fn parse_cookie<'a>(
+    headers: impl Iterator<Item = &'a reqwest::header::HeaderValue>,
+    name_with_eq: &str,
+) -> Option<String> {
+    for hv in headers {
+        if let Ok(s) = hv.to_str()
+            && s.starts_with(name_with_eq)
+            && let Some(eq_idx) = s.find('=')
+        {
+            let rest = &s[eq_idx + 1..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            return Some(rest[..end].to_string());
+        }
+    }
+    None
+}
+
+async fn login_and_get_auth(
+    client: &Client,
+    addr: &str,
+    email: &str,
+    password: &str,
+) -> (String, String) {
+    let res = client
+        .post(format!("http://{addr}/api/login.json"))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("login request failed");
+    assert_eq!(res.status(), 200, "login failed: {}", res.status());
+    let headers = res.headers().get_all(reqwest::header::SET_COOKIE);
+    // Accept both secure (__Host-*) and dev-mode (no prefix) cookie names
+    let csrf = parse_cookie(headers.iter(), "__Host-csrf=")
+        .or_else(|| parse_cookie(headers.iter(), "csrf="))
+        .expect("missing CSRF cookie");
+    let session = parse_cookie(headers.iter(), "__Host-session=")
+        .or_else(|| parse_cookie(headers.iter(), "session="))
+        .expect("missing session cookie");
+    (csrf, session)
+}
+
+#[tokio::test]
+async fn test_summary_sql_aggregation_matches_rust() {
+    unsafe {
+        std::env::set_var("DATABASE_URL", "sqlite::memory:");
+        std::env::set_var("COOKIE_SECURE", "0");
+    };
+    set_admin_env("admin@example.com", "password123");
+
+    let pool = db::connect().await.unwrap();
+    sqlx::migrate::Migrator::new(std::path::Path::new("../migrations"))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let app = app::router(pool.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _server = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    wait_ready(&client, &addr.to_string()).await;
+    let (csrf, session) = login_and_get_auth(
+        &client,
+        &addr.to_string(),
+        "admin@example.com",
+        "password123",
+    )
+    .await;
+
+    // Two ISO weeks (2025-W26, 2025-W27), an even-sized bucket, a split day, and sparse
+    // wake feelings so every aggregate (median, AVG over NULLs, split days) is exercised.
+    let sessions = [
+        ("2025-06-27", "23:00:00", "07:00:00", 10, 3, Some(4)),
+        ("2025-06-28", "22:30:00", "06:15:00", 25, 4, None),
+        ("2025-06-29", "00:10:00", "07:40:00", 5, 2, Some(2)),
+        ("2025-06-29", "14:00:00", "14:45:00", 15, 3, None),
+        ("2025-06-30", "23:40:00", "06:00:00", 40, 1, Some(3)),
+        ("2025-07-01", "22:50:00", "07:05:00", 12, 5, Some(5)),
+    ];
+    for (date, bed, wake, latency, quality, feeling) in sessions {
+        let res = client
+            .post(format!("http://{addr}/api/sleep"))
+            .header("Cookie", format!("session={session}; csrf={csrf}"))
+            .header("X-CSRF-Token", &csrf)
+            .json(&serde_json::json!({
+                "date": date, "bed_time": bed, "wake_time": wake,
+                "latency_min": latency, "awakenings": 1, "quality": quality,
+                "wake_feeling": feeling
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 201);
+    }
+
+    let summary = |mode: &'static str, query: &'static str| {
+        unsafe { std::env::set_var("TRENDS_SUMMARY_AGGREGATION", mode) };
+        let url = format!("http://{addr}/api/trends/summary?from=2025-06-26&to=2025-07-02{query}");
+        let client = client.clone();
+        async move {
+            let res = client.get(url).send().await.unwrap();
+            assert_eq!(res.status(), 200);
+            res.json::<serde_json::Value>().await.unwrap()
+        }
+    };
+
+    for query in [
+        "",
+        "&bucket=week",
+        "&per=segment",
+        "&bucket=week&per=segment",
+    ] {
+        let rust = summary("rust", query).await;
+        let sql = summary("sql", query).await;
+        assert_eq!(sql, rust, "sql aggregation differs for {query:?}");
+        assert!(!rust["duration_by_bucket"].as_array().unwrap().is_empty());
+        // Shadow mode always serves the Rust result.
+        let shadow = summary("shadow", query).await;
+        assert_eq!(shadow, rust);
+    }
+
+    let weekly = summary("sql", "&bucket=week").await;
+    assert_eq!(weekly["latency_by_bucket"][0]["bucket"], "2025-W26");
+    assert_eq!(weekly["latency_by_bucket"][0]["median"], 10.0);
+    assert_eq!(weekly["wake_feeling_by_bucket"][1]["days_reported"], 2);
+    assert_eq!(weekly["segments_by_bucket"][0]["split_days"], 1);
+    // W26 days: 480, 465 and 495 minutes.
+    assert_eq!(weekly["duration_by_bucket"][0]["p25_min"], 472.5);
+    assert_eq!(weekly["duration_by_bucket"][0]["p75_min"], 487.5);
+    assert_eq!(
+        weekly["quality_by_bucket"][1]["counts"],
+        serde_json::json!([1, 0, 0, 0, 1])
+    );
+
+    unsafe { std::env::remove_var("TRENDS_SUMMARY_AGGREGATION") };
+}
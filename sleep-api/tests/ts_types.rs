@@ -0,0 +1,12 @@
+#[test]
+fn test_generated_ts_types_are_current() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../sleep-ui/src/lib/api-types.gen.ts"
+    );
+    let checked_in = std::fs::read_to_string(path).expect("api-types.gen.ts is missing");
+    assert!(
+        checked_in == sleep_api::typegen::typescript(),
+        "sleep-ui/src/lib/api-types.gen.ts is stale; run `cargo run -p sleep-api --bin sleepctl -- gen-types`"
+    );
+}
@@ -22,11 +22,11 @@ Variants:
 ```rust
 # use chrono::{NaiveDate, NaiveTime};
 # use chrono_tz::Asia::Tokyo;
-# fn main() -> Result<(), sleep_api::domain::DomainError> {
-let mins = sleep_api::time::compute_duration_min(
-    NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| sleep_api::domain::DomainError::InvalidInput("invalid date".into()))?,
-    NaiveTime::from_hms_opt(22, 30, 0).ok_or_else(|| sleep_api::domain::DomainError::InvalidInput("invalid time".into()))?,
-    NaiveTime::from_hms_opt(6, 30, 0).ok_or_else(|| sleep_api::domain::DomainError::InvalidInput("invalid time".into()))?,
+# fn main() -> Result<(), sleep_core::domain::DomainError> {
+let mins = sleep_core::time::compute_duration_min(
+    NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| sleep_core::domain::DomainError::InvalidInput("invalid date".into()))?,
+    NaiveTime::from_hms_opt(22, 30, 0).ok_or_else(|| sleep_core::domain::DomainError::InvalidInput("invalid time".into()))?,
+    NaiveTime::from_hms_opt(6, 30, 0).ok_or_else(|| sleep_core::domain::DomainError::InvalidInput("invalid time".into()))?,
     Tokyo,
 )?;
 assert!(mins > 0);
@@ -0,0 +1,60 @@
+#![doc = r#"
+SleepTracker core types
+
+The parts of SleepTracker that do not need a server: request/response models with their
+validation, the domain error type, and the DST-aware time helpers that compute sleep
+durations. The crate depends only on serde, chrono, chrono-tz, thiserror and tracing and does
+no I/O, so the CLI and WASM frontends can validate input and compute durations exactly as the
+API does.
+
+`sleep-api` re-exports these modules under their original paths (`sleep_api::models`,
+`sleep_api::domain`, `sleep_api::time`).
+
+Cargo features (both off by default):
+- `schemars` — `JsonSchema` derives, used for the OpenAPI components and TypeScript types.
+- `sqlx` — `sqlx::FromRow` derives for the types read from the database.
+
+Modules:
+- [`domain`] — the validation error type.
+- [`models`] — input/output types with validation.
+- [`time`] — time and duration helpers including DST‑aware computations.
+
+# Example
+
+```rust
+use chrono::{NaiveDate, NaiveTime};
+use sleep_core::models::{Quality, SleepInput};
+
+let input = SleepInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(),
+    bed_time: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+    wake_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+    latency_min: 10,
+    awakenings: 1,
+    quality: Quality(4),
+    wake_feeling: None,
+    sleep_inertia_min: None,
+    waso_min: None,
+    aids: Vec::new(),
+    tags: Vec::new(),
+    timezone: None,
+};
+input.validate().unwrap();
+let minutes = sleep_core::time::compute_duration_min(
+    input.date,
+    input.bed_time,
+    input.wake_time,
+    chrono_tz::Asia::Tokyo,
+)
+.unwrap();
+assert_eq!(minutes, 480);
+```
+
+[`domain`]: crate::domain
+[`models`]: crate::models
+[`time`]: crate::time
+"#]
+
+pub mod domain;
+pub mod models;
+pub mod time;
@@ -0,0 +1,340 @@
+use crate::domain::DomainError;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const MAX_RULES: usize = 20;
+const MAX_RULE_ID_LEN: usize = 40;
+const MAX_NIGHTS: u32 = 90;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"Nightly value an [`AlertRule`] watches, as aggregated per wake date by `v_daily_sleep`."#]
+pub enum AlertMetric {
+    /// Total sleep in minutes.
+    DurationMin,
+    /// Mean sleep latency in minutes.
+    LatencyMin,
+    /// Total awakenings.
+    Awakenings,
+    /// Mean quality (1..=5).
+    Quality,
+    /// Mean wake feeling (1..=5).
+    WakeFeeling,
+}
+
+impl AlertMetric {
+    #[doc = r#"Return the `v_daily_sleep` column holding the metric."#]
+    pub fn column(self) -> &'static str {
+        match self {
+            AlertMetric::DurationMin => "duration_min",
+            AlertMetric::LatencyMin => "latency_min",
+            AlertMetric::Awakenings => "awakenings",
+            AlertMetric::Quality => "quality",
+            AlertMetric::WakeFeeling => "wake_feeling",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"Which side of the threshold triggers an [`AlertRule`] (strictly below or above)."#]
+pub enum AlertComparison {
+    Below,
+    Above,
+}
+
+impl AlertComparison {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertComparison::Below => value < threshold,
+            AlertComparison::Above => value > threshold,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            AlertComparison::Below => "below",
+            AlertComparison::Above => "above",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"How an [`AlertRule`] combines the last `nights` wake dates."#]
+pub enum AlertCondition {
+    /// The mean over the logged nights crosses the threshold. Needs values on at least half
+    /// of the nights, so one logged night does not stand in for a week.
+    Average,
+    /// Every one of the nights is logged and crosses the threshold.
+    Consecutive,
+}
+
+#[doc = r#"A user-defined alert, e.g. "7-day average duration below 6h" or "latency above 45
+minutes for 3 consecutive nights".
+
+- `id`: unique name of the rule, 1..=40 characters of `[a-z0-9_-]`; alert history refers to it.
+- `nights`: 1..=90 wake dates ending at the evaluated day.
+- `enabled`: defaults to `true`; disabled rules are kept but never evaluated.
+
+# Example
+
+```rust
+# use sleep_core::models::{AlertComparison, AlertCondition, AlertMetric, AlertRule};
+# use chrono::NaiveDate;
+let rule = AlertRule {
+    id: "short_week".into(),
+    metric: AlertMetric::DurationMin,
+    comparison: AlertComparison::Below,
+    threshold: 360.0,
+    condition: AlertCondition::Average,
+    nights: 7,
+    enabled: true,
+};
+let day = |d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap();
+let nights: Vec<_> = (1..=7).map(|d| (day(d), Some(330.0))).collect();
+assert_eq!(rule.evaluate(&nights, day(7)), Some(330.0));
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AlertRule {
+    pub id: String,
+    pub metric: AlertMetric,
+    pub comparison: AlertComparison,
+    pub threshold: f64,
+    pub condition: AlertCondition,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = MAX_NIGHTS)))]
+    pub nights: u32,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+impl AlertRule {
+    #[doc = r#"First wake date of the rule's window ending at `as_of`."#]
+    pub fn window_start(&self, as_of: NaiveDate) -> NaiveDate {
+        as_of - Duration::days(i64::from(self.nights) - 1)
+    }
+
+    #[doc = r#"Evaluate the rule over `(wake_date, value)` pairs ending at `as_of`.
+
+Nights outside the rule's window are ignored; unlogged nights may be missing or `None`.
+Returns the observed mean over the window when the rule fires.
+"#]
+    pub fn evaluate(&self, nights: &[(NaiveDate, Option<f64>)], as_of: NaiveDate) -> Option<f64> {
+        let from = self.window_start(as_of);
+        let values: Vec<f64> = nights
+            .iter()
+            .filter(|(date, _)| *date >= from && *date <= as_of)
+            .filter_map(|(_, value)| *value)
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let fires = match self.condition {
+            AlertCondition::Average => {
+                values.len() * 2 >= self.nights as usize
+                    && self.comparison.holds(mean, self.threshold)
+            }
+            AlertCondition::Consecutive => {
+                values.len() == self.nights as usize
+                    && values
+                        .iter()
+                        .all(|v| self.comparison.holds(*v, self.threshold))
+            }
+        };
+        fires.then_some((mean * 10.0).round() / 10.0)
+    }
+
+    #[doc = r#"Human-readable description of the rule firing with observed mean `value`."#]
+    pub fn message(&self, value: f64) -> String {
+        let metric = self.metric.column();
+        let cmp = self.comparison.as_str();
+        match self.condition {
+            AlertCondition::Average => format!(
+                "{}: average {metric} over {} nights is {value} ({cmp} {})",
+                self.id, self.nights, self.threshold
+            ),
+            AlertCondition::Consecutive => format!(
+                "{}: {metric} {cmp} {} for {} consecutive nights (average {value})",
+                self.id, self.threshold, self.nights
+            ),
+        }
+    }
+}
+
+#[doc = r#"Configured alert rules (`GET/PUT /api/settings/alerts`). Empty by default.
+
+Rules are evaluated once per night by the `alert_evaluation` background job (see
+[`crate::jobs`]); each rule fires at most once per evaluated day.
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AlertRules {
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_RULES)))]
+    pub rules: Vec<AlertRule>,
+}
+
+impl AlertRules {
+    #[doc = r#"Validate the rule list.
+
+- at most 20 rules with unique ids of 1..=40 characters of `[a-z0-9_-]`
+- `nights` in 1..=90
+- finite `threshold`
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.rules.len() > MAX_RULES {
+            return Err(DomainError::InvalidInput(format!(
+                "at most {MAX_RULES} alert rules"
+            )));
+        }
+        let mut seen = HashSet::new();
+        for rule in &self.rules {
+            let valid_id = !rule.id.is_empty()
+                && rule.id.len() <= MAX_RULE_ID_LEN
+                && rule
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+            if !valid_id {
+                return Err(DomainError::InvalidInput(format!(
+                    "invalid alert rule id {:?}",
+                    rule.id
+                )));
+            }
+            if !seen.insert(rule.id.as_str()) {
+                return Err(DomainError::InvalidInput(format!(
+                    "duplicate alert rule id: {}",
+                    rule.id
+                )));
+            }
+            if !(1..=MAX_NIGHTS).contains(&rule.nights) {
+                return Err(DomainError::InvalidInput(format!(
+                    "alert rule {}: nights must be between 1 and {MAX_NIGHTS}",
+                    rule.id
+                )));
+            }
+            if !rule.threshold.is_finite() {
+                return Err(DomainError::InvalidInput(format!(
+                    "alert rule {}: threshold must be a number",
+                    rule.id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"A fired alert, as listed by `GET /api/alerts/history`.
+
+- `as_of`: the evaluated wake date (last night of the rule's window).
+- `value`: observed mean over the window.
+- `delivered_via`: comma-separated notification channels that accepted the alert, or `None`
+  while delivery has not finished.
+- `fired_at`: UTC timestamp.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AlertEvent {
+    pub id: i64,
+    pub rule_id: String,
+    pub as_of: NaiveDate,
+    pub value: f64,
+    pub message: String,
+    pub delivered_via: Option<String>,
+    pub fired_at: NaiveDateTime,
+}
+
+#[doc = r#"Query parameters for `GET /api/alerts/history`.
+
+- `limit`: number of alerts, 1..=500 (default 50).
+"#]
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AlertHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn rule(condition: AlertCondition, nights: u32) -> AlertRule {
+        AlertRule {
+            id: "latency".into(),
+            metric: AlertMetric::LatencyMin,
+            comparison: AlertComparison::Above,
+            threshold: 45.0,
+            condition,
+            nights,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn consecutive_needs_every_night() {
+        let r = rule(AlertCondition::Consecutive, 3);
+        let nights = [
+            (day(1), Some(10.0)),
+            (day(2), Some(50.0)),
+            (day(3), Some(60.0)),
+            (day(4), Some(70.0)),
+        ];
+        assert_eq!(r.evaluate(&nights, day(4)), Some(60.0));
+        assert_eq!(r.evaluate(&nights, day(3)), None);
+        // A missing night breaks the run.
+        assert_eq!(r.evaluate(&nights[1..3], day(4)), None);
+    }
+
+    #[test]
+    fn average_needs_half_the_nights() {
+        let r = rule(AlertCondition::Average, 4);
+        let nights = [(day(3), Some(50.0)), (day(4), Some(60.0))];
+        assert_eq!(r.evaluate(&nights, day(4)), Some(55.0));
+        assert_eq!(r.evaluate(&nights[1..], day(4)), None);
+        assert_eq!(
+            r.evaluate(&[(day(4), Some(40.0)), (day(3), None)], day(4)),
+            None
+        );
+    }
+
+    #[test]
+    fn validate_rejects_bad_rules() {
+        let ok = AlertRules {
+            rules: vec![rule(AlertCondition::Average, 7)],
+        };
+        assert!(ok.validate().is_ok());
+
+        let mut dup = ok.clone();
+        dup.rules.push(rule(AlertCondition::Consecutive, 3));
+        assert!(dup.validate().is_err());
+
+        let mut bad = ok.clone();
+        bad.rules[0].id = "Bad Id".into();
+        assert!(bad.validate().is_err());
+
+        let mut bad = ok;
+        bad.rules[0].nights = 0;
+        assert!(bad.validate().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::domain::DomainError;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+const MAX_NAME_LEN: usize = 60;
+const DEFAULT_EXPIRES_IN_DAYS: u32 = 90;
+const MAX_EXPIRES_IN_DAYS: u32 = 365;
+
+/// Routes only `admin` tokens may call, reads included.
+const ADMIN_ONLY_PATHS: &[&str] = &["/api/admin", "/api/export", "/api/account", "/api/settings"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[doc = r#"What an API token may do. Serializes as `"read" | "write:sleep" | "admin"`.
+
+- `read`: `GET`/`HEAD` requests to data routes only: not administration (`/api/admin`), the
+  full data export (`/api/export`), or the account and its settings (`/api/account`,
+  `/api/settings`), which only `admin` may read.
+- `write:sleep`: `read`, plus creating, editing, starring, and deleting sleep sessions
+  (`/api/sleep/...`).
+- `admin`: every request a session may make.
+
+Token management (`/api/tokens`) always needs a browser session, so a leaked token cannot mint
+or extend tokens.
+
+# Example
+
+```rust
+# use sleep_core::models::ApiScope;
+assert!(ApiScope::Read.allows("GET", "/api/sleep/recent"));
+assert!(!ApiScope::Read.allows("DELETE", "/api/sleep/4"));
+assert!(!ApiScope::Read.allows("GET", "/api/export"));
+assert!(ApiScope::Admin.allows("GET", "/api/export"));
+assert!(ApiScope::WriteSleep.allows("DELETE", "/api/sleep/4"));
+assert!(!ApiScope::WriteSleep.allows("POST", "/api/settings/units"));
+```
+"#]
+pub enum ApiScope {
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "write:sleep")]
+    WriteSleep,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiScope {
+    #[doc = r#"Return the stored and serialized name of the scope."#]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::WriteSleep => "write:sleep",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    #[doc = r#"Whether a request with `method` (e.g. `"GET"`) to `path` (router-relative) is
+within the scope."#]
+    pub fn allows(self, method: &str, path: &str) -> bool {
+        let under = |prefix: &str| {
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        let read_only = matches!(method, "GET" | "HEAD" | "OPTIONS");
+        let admin_path = ADMIN_ONLY_PATHS.iter().any(|p| under(p));
+        let sleep_path = under("/api/sleep");
+        match self {
+            ApiScope::Admin => true,
+            ApiScope::WriteSleep => !admin_path && (read_only || sleep_path),
+            ApiScope::Read => !admin_path && read_only,
+        }
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(ApiScope::Read),
+            "write:sleep" => Ok(ApiScope::WriteSleep),
+            "admin" => Ok(ApiScope::Admin),
+            other => Err(DomainError::InvalidInput(format!(
+                "unknown token scope: {other}"
+            ))),
+        }
+    }
+}
+
+#[doc = r#"Request body of `POST /api/tokens`.
+
+- `name`: label shown in the token list, 1..=60 characters.
+- `scope`: see [`ApiScope`].
+- `expires_in_days`: lifetime, 1..=365 days (default 90). Tokens cannot be created without
+  an expiry.
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ApiTokenInput {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_NAME_LEN)))]
+    pub name: String,
+    pub scope: ApiScope,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = MAX_EXPIRES_IN_DAYS)))]
+    pub expires_in_days: Option<u32>,
+}
+
+impl ApiTokenInput {
+    #[doc = r#"Validate the name and lifetime.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let len = self.name.trim().chars().count();
+        if len == 0 || len > MAX_NAME_LEN {
+            return Err(DomainError::InvalidInput(format!(
+                "name must be 1-{MAX_NAME_LEN} characters"
+            )));
+        }
+        if !(1..=MAX_EXPIRES_IN_DAYS).contains(&self.expires_in_days()) {
+            return Err(DomainError::InvalidInput(format!(
+                "expires_in_days must be between 1 and {MAX_EXPIRES_IN_DAYS}"
+            )));
+        }
+        Ok(())
+    }
+
+    #[doc = r#"Requested lifetime in days, defaulting to 90."#]
+    pub fn expires_in_days(&self) -> u32 {
+        self.expires_in_days.unwrap_or(DEFAULT_EXPIRES_IN_DAYS)
+    }
+}
+
+#[doc = r#"An API token as listed by `GET /api/tokens` (the secret is never listed).
+
+- `scope`: `"read" | "write:sleep" | "admin"` (see [`ApiScope`]).
+- `created_at` / `expires_at` / `last_used_at`: UTC timestamps; `last_used_at` is `None` until
+  the token authenticates a request.
+- `expired`: whether `expires_at` has passed; expired tokens are rejected but kept listed
+  until revoked.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub scope: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub expired: bool,
+}
+
+#[doc = r#"Response of `POST /api/tokens`.
+
+`secret` is the bearer value (`Authorization: Bearer <secret>`). It is shown only here; the
+server keeps just its hash.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreatedApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
@@ -0,0 +1,73 @@
+use crate::domain::DomainError;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+const MAX_FILENAME_LEN: usize = 200;
+
+#[doc = r#"Query of `POST /api/attachments`; the request body is the file itself and its
+`Content-Type` header is stored as the attachment's type.
+
+- `date`: day the file belongs to.
+- `filename`: original file name, 1..=200 characters without path separators or control
+  characters. Offered back in `Content-Disposition` on download.
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AttachmentUpload {
+    pub date: NaiveDate,
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_FILENAME_LEN)))]
+    pub filename: String,
+}
+
+impl AttachmentUpload {
+    #[doc = r#"Validate the file name.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] if `filename` is empty, longer than 200 characters,
+or contains `/`, `\` or control characters.
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let name = self.filename.trim();
+        if name.is_empty() || name.chars().count() > MAX_FILENAME_LEN {
+            return Err(DomainError::InvalidInput(format!(
+                "filename must be 1..={MAX_FILENAME_LEN} characters"
+            )));
+        }
+        if name
+            .chars()
+            .any(|c| c == '/' || c == '\\' || c.is_control())
+        {
+            return Err(DomainError::InvalidInput(
+                "filename must not contain path separators or control characters".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"An uploaded attachment, as returned by `POST /api/attachments` and
+`GET /api/attachments?date=`.
+
+- `size_bytes` / `sha256`: of the original file; `sha256` is also its download `ETag`.
+- `width` / `height`: pixel size of images the server could decode.
+- `duration_ms`: length of audio memos the server could decode.
+- `has_thumb`: whether `GET /api/attachment/{id}/thumb` serves a preview. Thumbnails are made
+  for images when the server is built with the `image` feature.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Attachment {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub has_thumb: bool,
+    pub created_at: NaiveDateTime,
+}
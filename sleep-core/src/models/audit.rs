@@ -0,0 +1,115 @@
+use crate::domain::DomainError;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+const MAX_REASON_LEN: usize = 40;
+const MAX_NOTE_LEN: usize = 500;
+
+#[doc = r#"Why a destructive operation was performed, recorded in the audit log.
+
+Sent as the optional JSON body of destructive requests (e.g. `DELETE /api/sleep/{id}`).
+Both fields are optional unless `AUDIT_REASON_REQUIRED` is set, in which case `reason` must be
+present.
+
+- `reason`: short reason code, lowercase ASCII letters, digits, and `_` (max 40 chars),
+  e.g. `duplicate`, `bad_import`, `test_data`.
+- `note`: free text explaining the change (max 500 characters).
+
+# Example
+
+```rust
+# use sleep_core::models::AuditReason;
+let reason = AuditReason { reason: Some("duplicate".into()), note: None };
+assert!(reason.validate().is_ok());
+assert!(AuditReason { reason: Some("Bad Import".into()), note: None }.validate().is_err());
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AuditReason {
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_REASON_LEN), pattern(r"^[a-z0-9_]+$")))]
+    pub reason: Option<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_NOTE_LEN)))]
+    pub note: Option<String>,
+}
+
+impl AuditReason {
+    #[doc = r#"Validate the reason code format and note length.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] for a malformed reason code or an overlong note.
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if let Some(reason) = &self.reason {
+            let valid = !reason.is_empty()
+                && reason.len() <= MAX_REASON_LEN
+                && reason
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(DomainError::InvalidInput(format!(
+                    "invalid reason code: {reason:?}"
+                )));
+            }
+        }
+        if let Some(note) = &self.note
+            && note.chars().count() > MAX_NOTE_LEN
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "note must be at most {MAX_NOTE_LEN} characters"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"One row of the append-only audit log.
+
+- `action`: what happened (e.g. `delete`).
+- `entity` / `entity_id`: the affected record (e.g. `sleep_session`, 12).
+- `reason` / `note`: as supplied in the request's [`AuditReason`].
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AuditEntry {
+    pub id: i64,
+    pub recorded_at: NaiveDateTime,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: Option<i64>,
+    pub reason: Option<String>,
+    pub note: Option<String>,
+}
+
+#[doc = r#"Filters and cursor for listing the audit log (`GET /api/admin/audit`).
+
+- `entity`, `action`: exact matches (e.g. `sleep_session`, `delete`).
+- `from`, `to`: inclusive UTC dates bounding `recorded_at`.
+- `cursor`: the `next_cursor` of the previous page; only older entries are returned.
+- `limit`: page size, 1..=1000 (default 100).
+"#]
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AuditQuery {
+    pub entity: Option<String>,
+    pub action: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[doc = r#"One page of audit log entries, newest first.
+
+`next_cursor` is `None` on the last page; pass it back as `cursor` to fetch the next one.
+"#]
+#[derive(Serialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    pub next_cursor: Option<i64>,
+}
@@ -0,0 +1,91 @@
+use crate::domain::DomainError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"User-provided body metrics reading for a date.
+
+- `date`: calendar date of the reading (one reading per date; later writes replace earlier ones).
+- `weight_kg`: optional body weight in kilograms, must be in (0, 500].
+- `body_fat_pct`: optional body fat percentage, must be in (0, 100).
+
+At least one of `weight_kg` or `body_fat_pct` must be present.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::BodyMetricInput;
+# use chrono::NaiveDate;
+# fn main() -> Result<(), DomainError> {
+let reading = BodyMetricInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    weight_kg: Some(72.4),
+    body_fat_pct: Some(18.5),
+};
+reading.validate()?;
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BodyMetricInput {
+    pub date: NaiveDate,
+    #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0, "maximum" = 500)))]
+    pub weight_kg: Option<f64>,
+    #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0, "exclusiveMaximum" = 100)))]
+    pub body_fat_pct: Option<f64>,
+}
+
+const MAX_WEIGHT_KG: f64 = 500.0;
+
+impl BodyMetricInput {
+    #[doc = r#"Validate the reading.
+
+- At least one of `weight_kg` / `body_fat_pct` must be present
+- `weight_kg` must be in (0, 500]
+- `body_fat_pct` must be in (0, 100)
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.weight_kg.is_none() && self.body_fat_pct.is_none() {
+            return Err(DomainError::InvalidInput(
+                "weight_kg or body_fat_pct is required".into(),
+            ));
+        }
+        if let Some(w) = self.weight_kg
+            && !(w > 0.0 && w <= MAX_WEIGHT_KG)
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "weight_kg must be between 0 and {MAX_WEIGHT_KG}"
+            )));
+        }
+        if let Some(f) = self.body_fat_pct
+            && !(f > 0.0 && f < 100.0)
+        {
+            return Err(DomainError::InvalidInput(
+                "body_fat_pct must be between 0 and 100".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"Stored body metrics reading.
+
+`source` records where the reading came from: `manual`, `withings`, or `fitbit`.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BodyMetric {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub weight_kg: Option<f64>,
+    pub body_fat_pct: Option<f64>,
+    pub source: String,
+}
@@ -0,0 +1,149 @@
+use crate::domain::DomainError;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"When a logical day starts, for users whose day does not end at midnight.
+
+Local instants are assigned to days by [`DayBoundary::day_of`]:
+- `day_start` at or before noon (e.g. `04:00`): the day runs from `day_start` to `day_start`
+  the next morning, so a 01:00 workout still counts for the previous evening's day.
+- `day_start` after noon (e.g. `18:00`): the day starts the evening before, so activity from
+  18:00 on counts for the next calendar day.
+
+Used for webhook-pushed workouts and for the "today" that quick-log forms default to
+(`GET /api/now/today`). Defaults to midnight (calendar days).
+
+# Example
+
+```rust
+# use sleep_core::models::DayBoundary;
+# use chrono::{NaiveDate, NaiveTime};
+let night_owl = DayBoundary { day_start: NaiveTime::from_hms_opt(4, 0, 0).unwrap() };
+let late_workout = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap().and_hms_opt(1, 30, 0).unwrap();
+assert_eq!(night_owl.day_of(late_workout), NaiveDate::from_ymd_opt(2025, 6, 1).unwrap());
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DayBoundary {
+    pub day_start: NaiveTime,
+}
+
+impl Default for DayBoundary {
+    fn default() -> Self {
+        DayBoundary {
+            day_start: NaiveTime::MIN,
+        }
+    }
+}
+
+impl DayBoundary {
+    #[doc = r#"Validate the boundary.
+
+- `day_start` must be a whole minute
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.day_start.second() != 0 || self.day_start.nanosecond() != 0 {
+            return Err(DomainError::InvalidInput(
+                "day_start must be a whole minute".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[doc = r#"The logical day a local instant belongs to."#]
+    pub fn day_of(&self, local: NaiveDateTime) -> NaiveDate {
+        (local + self.shift()).date()
+    }
+
+    #[doc = r#"The logical day a session waking at `wake_time` belongs to, logged at local `now`.
+
+[`crate::time::infer_wake_date`] on the logical clock: with a `04:00` boundary, a `01:50`
+wake logged at `02:00` belongs to the previous day, the same one [`DayBoundary::day_of`]
+reports as "today".
+"#]
+    pub fn infer_wake_date(&self, now: NaiveDateTime, wake_time: NaiveTime) -> NaiveDate {
+        let shift = self.shift();
+        crate::time::infer_wake_date(now + shift, wake_time + shift)
+    }
+
+    /// Offset moving a local instant onto the logical clock, where days start at midnight.
+    fn shift(&self) -> Duration {
+        let offset = self.day_start - NaiveTime::MIN;
+        if offset <= Duration::hours(12) {
+            -offset
+        } else {
+            Duration::days(1) - offset
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    fn boundary(h: u32) -> DayBoundary {
+        DayBoundary {
+            day_start: NaiveTime::from_hms_opt(h, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn day_of_morning_and_evening_boundaries() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap();
+        assert_eq!(DayBoundary::default().day_of(at(2, 0, 30)), day(2));
+
+        assert_eq!(boundary(4).day_of(at(2, 3, 59)), day(1));
+        assert_eq!(boundary(4).day_of(at(2, 4, 0)), day(2));
+
+        assert_eq!(boundary(18).day_of(at(1, 17, 59)), day(1));
+        assert_eq!(boundary(18).day_of(at(1, 18, 0)), day(2));
+        assert_eq!(boundary(18).day_of(at(2, 7, 0)), day(2));
+    }
+
+    #[test]
+    fn infer_wake_date_follows_the_boundary() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap();
+        let wake = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(
+            DayBoundary::default().infer_wake_date(at(10, 2, 0), wake(1, 50)),
+            day(10)
+        );
+
+        assert_eq!(
+            boundary(4).infer_wake_date(at(10, 2, 0), wake(1, 50)),
+            day(9)
+        );
+        assert_eq!(boundary(4).day_of(at(10, 2, 0)), day(9));
+        assert_eq!(
+            boundary(4).infer_wake_date(at(10, 9, 0), wake(7, 0)),
+            day(10)
+        );
+        assert_eq!(
+            boundary(4).infer_wake_date(at(10, 2, 0), wake(7, 0)),
+            day(9)
+        );
+
+        assert_eq!(
+            boundary(18).infer_wake_date(at(10, 19, 0), wake(7, 0)),
+            day(10)
+        );
+        assert_eq!(
+            boundary(18).infer_wake_date(at(10, 9, 0), wake(7, 0)),
+            day(10)
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"A device that has logged in, as listed by `GET /api/account/devices`.
+
+- `label`: coarse browser and OS, e.g. `"Firefox on Linux"`.
+- `first_seen_at` / `last_seen_at`: UTC timestamps of the first and latest login.
+- `current`: whether the requesting device has this fingerprint.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct KnownDevice {
+    pub id: i64,
+    pub label: String,
+    pub first_seen_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub login_count: i64,
+    pub current: bool,
+}
@@ -0,0 +1,112 @@
+use crate::domain::DomainError;
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+const MAX_DISTURBANCE_DURATION_MIN: i32 = 12 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+#[doc = r#"Source of an external sleep disturbance.
+
+Serializes as `"noise" | "partner" | "pet" | "child"`; `Display` prints the same string.
+"#]
+pub enum DisturbanceKind {
+    Noise,
+    Partner,
+    Pet,
+    Child,
+}
+
+impl DisturbanceKind {
+    /// All kinds, in the order reports list them.
+    pub const ALL: [DisturbanceKind; 4] = [
+        DisturbanceKind::Noise,
+        DisturbanceKind::Partner,
+        DisturbanceKind::Pet,
+        DisturbanceKind::Child,
+    ];
+}
+
+impl std::fmt::Display for DisturbanceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            DisturbanceKind::Noise => "noise",
+            DisturbanceKind::Partner => "partner",
+            DisturbanceKind::Pet => "pet",
+            DisturbanceKind::Child => "child",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[doc = r#"User-provided disturbance event.
+
+- `date`: wake date of the affected night (same convention as [`SleepInput::date`]).
+- `time`: local clock time the disturbance started.
+- `type`: [`DisturbanceKind`].
+- `duration_min`: how long it lasted, 0..=720 minutes.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::{DisturbanceInput, DisturbanceKind};
+# use chrono::{NaiveDate, NaiveTime};
+# fn main() -> Result<(), DomainError> {
+let event = DisturbanceInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    time: NaiveTime::from_hms_opt(2, 30, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
+    kind: DisturbanceKind::Pet,
+    duration_min: 10,
+};
+event.validate()?;
+# Ok(()) }
+```
+
+[`SleepInput::date`]: crate::models::SleepInput::date
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DisturbanceInput {
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    #[serde(rename = "type")]
+    pub kind: DisturbanceKind,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = MAX_DISTURBANCE_DURATION_MIN)))]
+    pub duration_min: i32,
+}
+
+impl DisturbanceInput {
+    #[doc = r#"Validate the event.
+
+- `duration_min` must be in 0..=720
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !(0..=MAX_DISTURBANCE_DURATION_MIN).contains(&self.duration_min) {
+            return Err(DomainError::InvalidInput(format!(
+                "duration_min must be between 0 and {MAX_DISTURBANCE_DURATION_MIN}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"Stored disturbance event."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Disturbance {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    #[serde(rename = "type")]
+    pub kind: String, // "noise" | "partner" | "pet" | "child"
+    pub duration_min: i32,
+}
@@ -0,0 +1,76 @@
+use crate::domain::DomainError;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+const MAX_EMAIL_LEN: usize = 254;
+
+#[doc = r#"Request body of `POST /api/account/email`.
+
+- `current_password`: the account password, re-checked before anything is sent.
+- `new_email`: the login email to switch to once confirmed; surrounding whitespace is ignored.
+
+# Example
+
+```rust
+# use sleep_core::models::EmailChangeInput;
+let input = EmailChangeInput {
+    current_password: "hunter2".into(),
+    new_email: " me@example.org ".into(),
+};
+assert!(input.validate().is_ok());
+assert_eq!(input.new_email(), "me@example.org");
+```
+"#]
+#[derive(Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EmailChangeInput {
+    pub current_password: String,
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_EMAIL_LEN)))]
+    pub new_email: String,
+}
+
+impl EmailChangeInput {
+    #[doc = r#"Validate the new address: one `@` with text on both sides, no whitespace, at most
+254 characters. The password must not be empty.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.current_password.is_empty() {
+            return Err(DomainError::InvalidInput(
+                "current_password is required".into(),
+            ));
+        }
+        let email = self.new_email();
+        let well_formed = email.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty() && !domain.is_empty() && !domain.contains('@')
+        });
+        if !well_formed || email.chars().any(char::is_whitespace) || email.len() > MAX_EMAIL_LEN {
+            return Err(DomainError::InvalidInput(
+                "new_email must be a valid email address".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[doc = r#"The new email without surrounding whitespace."#]
+    pub fn new_email(&self) -> &str {
+        self.new_email.trim()
+    }
+}
+
+#[doc = r#"A pending login email change, as returned by `POST /api/account/email`.
+
+- `new_email`: the address that becomes the login email once the link is opened.
+- `expires_at`: UTC time after which the confirmation link no longer works.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PendingEmailChange {
+    pub new_email: String,
+    pub expires_at: NaiveDateTime,
+}
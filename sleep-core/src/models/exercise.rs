@@ -0,0 +1,189 @@
+use super::intensity::Intensity;
+use crate::domain::DomainError;
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"User-provided input representing an exercise event.
+
+Fields:
+- `date`: calendar date of the exercise.
+- `intensity`: qualitative intensity level, see [`Intensity`].
+- `start_time`: optional local start time.
+- `duration_min`: optional duration in minutes.
+- `hr_zones`: optional minutes per heart-rate zone, see [`HrZoneMinutes`]; needs
+  `duration_min`.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::{ExerciseInput, Intensity};
+# use chrono::{NaiveDate, NaiveTime};
+# fn main() -> Result<(), DomainError> {
+let ex = ExerciseInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    intensity: Intensity::Light,
+    start_time: Some(NaiveTime::from_hms_opt(18, 30, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?),
+    duration_min: Some(45),
+    hr_zones: None,
+};
+ex.validate()?;
+# Ok(()) }
+```
+
+[`Intensity`]: crate::models::Intensity
+"#]
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExerciseInput {
+    pub date: NaiveDate,
+    pub intensity: Intensity,
+    pub start_time: Option<NaiveTime>,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = MAX_EXERCISE_DURATION_MIN)))]
+    pub duration_min: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hr_zones: Option<HrZoneMinutes>,
+}
+
+#[doc = r#"Minutes spent in each heart-rate zone, Z1 (easiest) to Z5 (maximal).
+
+Zone boundaries are whatever the recording service used (Strava and Garmin both derive them
+from the athlete's max or reserve heart rate), so totals are comparable across sessions of
+one source. Every zone must be in 0..=1440 and their sum at most 1440.
+
+# Example
+
+```rust
+# use sleep_core::models::HrZoneMinutes;
+let zones = HrZoneMinutes { z1: 5, z2: 20, z3: 15, z4: 8, z5: 2 };
+assert_eq!(zones.total(), 50);
+assert_eq!(zones.high(), 10);
+assert!(zones.validate().is_ok());
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HrZoneMinutes {
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = MAX_EXERCISE_DURATION_MIN)))]
+    pub z1: i32,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = MAX_EXERCISE_DURATION_MIN)))]
+    pub z2: i32,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = MAX_EXERCISE_DURATION_MIN)))]
+    pub z3: i32,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = MAX_EXERCISE_DURATION_MIN)))]
+    pub z4: i32,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = MAX_EXERCISE_DURATION_MIN)))]
+    pub z5: i32,
+}
+
+impl HrZoneMinutes {
+    /// Zones in order, Z1 first.
+    pub fn as_array(&self) -> [i32; 5] {
+        [self.z1, self.z2, self.z3, self.z4, self.z5]
+    }
+
+    /// Minutes in any zone.
+    pub fn total(&self) -> i32 {
+        self.as_array().iter().sum()
+    }
+
+    /// Minutes in Z4 and Z5.
+    pub fn high(&self) -> i32 {
+        self.z4 + self.z5
+    }
+
+    #[doc = r#"Validate the zone minutes.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] if a zone is negative or the zones add up to more
+than 1440 minutes.
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.as_array().iter().any(|m| *m < 0) {
+            return Err(DomainError::InvalidInput(
+                "hr_zones minutes must not be negative".into(),
+            ));
+        }
+        if self.total() > MAX_EXERCISE_DURATION_MIN {
+            return Err(DomainError::InvalidInput(format!(
+                "hr_zones must add up to at most {MAX_EXERCISE_DURATION_MIN} minutes"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"Heart-rate zone minutes of one day's exercise, in `GET /api/exercise/zones`.
+
+`sessions` counts the day's exercise events that recorded zones; `zones` sums them."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExerciseZoneDay {
+    pub date: NaiveDate,
+    pub sessions: i64,
+    pub zones: HrZoneMinutes,
+}
+
+#[doc = r#"One day's exercise, in `GET /api/exercise/intensity`.
+
+`intensity` is the day's highest level; `sessions` counts the day's exercise events and
+`total_min` sums their `duration_min` (events without a duration add nothing)."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DateIntensity {
+    pub date: NaiveDate,
+    pub intensity: String, // "none" | "light" | "hard"
+    pub sessions: i64,
+    pub total_min: i64,
+}
+
+#[doc = r#"A stored exercise event, as exported by `GET /api/export`.
+
+`intensity` is the stored level name (`none`, `light`, `hard`, or a custom level).
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExerciseEvent {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub intensity: String,
+    pub start_time: Option<NaiveTime>,
+    pub duration_min: Option<i32>,
+}
+
+const MAX_EXERCISE_DURATION_MIN: i32 = 24 * 60;
+
+impl ExerciseInput {
+    #[doc = r#"Validate the exercise input.
+
+Currently, this ensures that `intensity` has been deserialized into a valid value.
+Duration (when provided) must be in 1..=1440 minutes; heart-rate zones (when provided) must
+be valid (see [`HrZoneMinutes::validate`]) and come with a duration.
+
+# Errors
+
+Returns [`DomainError`] if a validation rule is violated.
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if let Some(duration_min) = self.duration_min
+            && !(1..=MAX_EXERCISE_DURATION_MIN).contains(&duration_min)
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "duration_min must be between 1 and {MAX_EXERCISE_DURATION_MIN}"
+            )));
+        }
+        if let Some(zones) = &self.hr_zones {
+            zones.validate()?;
+            if self.duration_min.is_none() {
+                return Err(DomainError::InvalidInput(
+                    "hr_zones requires duration_min".into(),
+                ));
+            }
+        }
+        // intensity is validated by deserialization
+        Ok(())
+    }
+}
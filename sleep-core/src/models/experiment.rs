@@ -0,0 +1,137 @@
+use super::inference::Inference;
+use crate::domain::DomainError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+const MAX_NAME_LEN: usize = 80;
+const MAX_DESCRIPTION_LEN: usize = 1000;
+
+#[doc = r#"User-provided self-experiment period.
+
+- `name`: 1..=80 characters after trimming.
+- `start_date`, `end_date`: inclusive wake-date range; `end_date` omitted while running.
+- `description`: optional free text, at most 1000 characters.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::ExperimentInput;
+# use chrono::NaiveDate;
+# fn main() -> Result<(), DomainError> {
+let experiment = ExperimentInput {
+    name: "No caffeine month".into(),
+    start_date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    end_date: NaiveDate::from_ymd_opt(2025, 6, 30),
+    description: None,
+};
+experiment.validate()?;
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExperimentInput {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_NAME_LEN)))]
+    pub name: String,
+    pub start_date: NaiveDate,
+    #[serde(default)]
+    pub end_date: Option<NaiveDate>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_DESCRIPTION_LEN)))]
+    pub description: Option<String>,
+}
+
+impl ExperimentInput {
+    #[doc = r#"Validate the experiment.
+
+- `name` must be 1..=80 characters after trimming
+- `end_date`, when present, must not precede `start_date`
+- `description` must be at most 1000 characters
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let name_len = self.name.trim().chars().count();
+        if name_len == 0 || name_len > MAX_NAME_LEN {
+            return Err(DomainError::InvalidInput(format!(
+                "name must be 1-{MAX_NAME_LEN} characters"
+            )));
+        }
+        if let Some(end) = self.end_date
+            && end < self.start_date
+        {
+            return Err(DomainError::InvalidInput(
+                "end_date must be on or after start_date".into(),
+            ));
+        }
+        if let Some(d) = &self.description
+            && d.chars().count() > MAX_DESCRIPTION_LEN
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "description must be at most {MAX_DESCRIPTION_LEN} characters"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"Stored experiment."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Experiment {
+    pub id: i64,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub description: Option<String>,
+}
+
+#[doc = r#"Sample size, mean, and standard deviation of one group of nights."#]
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GroupSummary {
+    pub n: usize,
+    pub mean: Option<f64>,
+    pub sd: Option<f64>,
+}
+
+#[doc = r#"One metric compared between the experiment and its baseline.
+
+`diff` is experiment minus baseline; `ci95_low`/`ci95_high` bound it with a Welch t
+interval and are `None` unless both groups have at least two nights. The flattened
+[`Inference`] fields add effect size, p-value, a bootstrap interval, and small-sample caveats.
+"#]
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExperimentMetricResult {
+    pub metric: &'static str,
+    pub during: GroupSummary,
+    pub baseline: GroupSummary,
+    pub diff: Option<f64>,
+    pub ci95_low: Option<f64>,
+    pub ci95_high: Option<f64>,
+    #[serde(flatten)]
+    pub inference: Inference,
+}
+
+#[doc = r#"Response for `GET /api/experiments/{id}/results`.
+
+- `baseline`: `before` (the same number of days right before `start_date`) or `outside`
+  (every logged night outside the experiment).
+- `period_from`/`period_to`: the evaluated experiment range (running experiments end today).
+"#]
+#[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExperimentResults {
+    pub experiment: Experiment,
+    pub baseline: String,
+    pub period_from: NaiveDate,
+    pub period_to: NaiveDate,
+    pub metrics: Vec<ExperimentMetricResult>,
+}
@@ -0,0 +1,83 @@
+use crate::domain::DomainError;
+use serde::{Deserialize, Serialize};
+
+const MAX_SOURCE_LEN: usize = 40;
+const MAX_EXTERNAL_ID_LEN: usize = 200;
+const MAX_URL_LEN: usize = 500;
+
+#[doc = r#"A link from a recorded entry to its id in an external service.
+
+Set by `POST /api/ingest/{source}` and listed on sleep sessions as `external_refs`.
+`(source, external_id)` identifies one entry: a pushed entry whose reference is already
+recorded is skipped, so re-syncs are idempotent even when the entry itself was edited since.
+
+- `source`: service name, 1..=40 characters of `[a-z0-9_-]` (e.g. `fitbit`, `strava`, `oura`,
+  `health-auto-export`).
+- `external_id`: the id in that service, 1..=200 characters.
+- `url`: optional `http(s)` deep link back to the entry, up to 500 characters.
+
+# Example
+
+```rust
+# use sleep_core::models::ExternalRef;
+let r = ExternalRef {
+    source: "strava".into(),
+    external_id: "12345678".into(),
+    url: Some("https://www.strava.com/activities/12345678".into()),
+};
+assert!(r.validate().is_ok());
+assert!(ExternalRef { source: "Strava".into(), ..r }.validate().is_err());
+```
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ExternalRef {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_SOURCE_LEN)))]
+    pub source: String,
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_EXTERNAL_ID_LEN)))]
+    pub external_id: String,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_URL_LEN)))]
+    pub url: Option<String>,
+}
+
+impl ExternalRef {
+    #[doc = r#"Validate the source name, id length, and deep link.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a field is malformed.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let valid_source = !self.source.is_empty()
+            && self.source.len() <= MAX_SOURCE_LEN
+            && self
+                .source
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+        if !valid_source {
+            return Err(DomainError::InvalidInput(format!(
+                "invalid external source {:?}",
+                self.source
+            )));
+        }
+        let id_len = self.external_id.trim().chars().count();
+        if id_len == 0 || id_len > MAX_EXTERNAL_ID_LEN {
+            return Err(DomainError::InvalidInput(format!(
+                "external_id must be 1-{MAX_EXTERNAL_ID_LEN} characters"
+            )));
+        }
+        if let Some(url) = &self.url
+            && (url.len() > MAX_URL_LEN
+                || !(url.starts_with("https://") || url.starts_with("http://")))
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "external url must be an http(s) URL of at most {MAX_URL_LEN} characters"
+            )));
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
 
 #[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FrictionTelemetryInput {
     pub form_time_ms: i32,
     pub error_kind: Option<String>,
@@ -11,7 +11,9 @@ pub struct FrictionTelemetryInput {
     pub follow_up_failure: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FrictionTelemetryEvent {
     pub id: i64,
     pub recorded_at: NaiveDateTime,
@@ -20,11 +22,21 @@ pub struct FrictionTelemetryEvent {
     pub retry_count: i32,
     pub immediate_edit: bool,
     pub follow_up_failure: bool,
+    /// Submissions this stored event stands for (N when 1 in N successes is sampled).
+    pub sample_weight: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow, Clone)]
+#[doc = r#"Friction metrics over a window.
+
+Counts, averages, the median and rates are weighted by each event's `sample_weight`, so they
+estimate all submissions even when successes are sampled; `recorded_count` is the number of
+stored events behind them."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FrictionWindowAggregate {
     pub submit_count: i64,
+    pub recorded_count: i64,
     pub median_form_time_ms: f64,
     pub avg_form_time_ms: f64,
     pub error_count: i64,
@@ -37,7 +49,9 @@ pub struct FrictionWindowAggregate {
     pub follow_up_failure_rate: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, FromRow, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FrictionErrorKindAggregate {
     pub error_kind: String,
     pub occurrences: i64,
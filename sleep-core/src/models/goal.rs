@@ -0,0 +1,64 @@
+use crate::domain::DomainError;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+const MIN_TARGET_DURATION_MIN: i32 = 3 * 60;
+const MAX_TARGET_DURATION_MIN: i32 = 12 * 60;
+
+#[doc = r#"Personal sleep goal used for bedtime nudges and sleep debt.
+
+- `target_bedtime`: local clock time the user aims to be in bed.
+- `target_duration_min`: nightly sleep target in minutes, 180..=720.
+
+Defaults to 23:00 and 8 hours.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::SleepGoal;
+# fn main() -> Result<(), DomainError> {
+let goal = SleepGoal::default();
+goal.validate()?;
+assert_eq!(goal.target_duration_min, 480);
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SleepGoal {
+    pub target_bedtime: NaiveTime,
+    #[cfg_attr(feature = "schemars", schemars(range(min = MIN_TARGET_DURATION_MIN, max = MAX_TARGET_DURATION_MIN)))]
+    pub target_duration_min: i32,
+}
+
+impl Default for SleepGoal {
+    fn default() -> Self {
+        SleepGoal {
+            target_bedtime: NaiveTime::from_hms_opt(23, 0, 0).expect("valid time"),
+            target_duration_min: 8 * 60,
+        }
+    }
+}
+
+impl SleepGoal {
+    #[doc = r#"Validate the goal.
+
+- `target_duration_min` must be in 180..=720
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !(MIN_TARGET_DURATION_MIN..=MAX_TARGET_DURATION_MIN).contains(&self.target_duration_min)
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "target_duration_min must be between {MIN_TARGET_DURATION_MIN} and {MAX_TARGET_DURATION_MIN}"
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+#![doc = r#"Two-group inference results
+
+[`Inference`] holds the effect size, p-value, and bootstrap interval for the difference of
+means between two groups of nights; `sleep_api::stats::inference::compare_groups` computes it.
+[`Correlation`] holds a Pearson coefficient between two paired series;
+`sleep_api::stats::inference::pearson` computes it.
+"#]
+
+use serde::Serialize;
+
+/// Groups smaller than this get a small-sample caveat.
+pub const MIN_RELIABLE_N: usize = 10;
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[doc = r#"Inference for the difference of means `a - b`.
+
+- `effect_size`: Hedges' g (bias-corrected Cohen's d); `effect_magnitude` labels it
+  `negligible` (< 0.2), `small` (< 0.5), `medium` (< 0.8), or `large`.
+- `p_value`: two-sided Welch t-test.
+- `bootstrap_ci95_low`/`bootstrap_ci95_high`: 95% percentile bootstrap interval.
+- `caveats`: human-readable warnings; empty when both groups are reasonably sized.
+
+Numeric fields are `None` when a group has fewer than two values.
+"#]
+pub struct Inference {
+    pub n_a: usize,
+    pub n_b: usize,
+    pub effect_size: Option<f64>,
+    pub effect_magnitude: Option<&'static str>,
+    pub p_value: Option<f64>,
+    pub bootstrap_ci95_low: Option<f64>,
+    pub bootstrap_ci95_high: Option<f64>,
+    pub caveats: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"Plain-language reading of an [`Inference`], for badges next to a difference."#]
+pub enum SignificanceHint {
+    /// p < 0.05, at least [`MIN_RELIABLE_N`] values per group, and a non-negligible effect.
+    Likely,
+    /// p < 0.05 with small groups or a negligible effect, or 0.05 ≤ p < 0.10.
+    Possible,
+    /// p ≥ 0.10: the difference is indistinguishable from noise.
+    Unlikely,
+    /// Fewer than two values in a group.
+    Insufficient,
+}
+
+impl SignificanceHint {
+    /// The serialized name, e.g. `likely`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SignificanceHint::Likely => "likely",
+            SignificanceHint::Possible => "possible",
+            SignificanceHint::Unlikely => "unlikely",
+            SignificanceHint::Insufficient => "insufficient",
+        }
+    }
+}
+
+impl Inference {
+    #[doc = r#"Summarize the p-value, sample sizes, and effect size as a [`SignificanceHint`]."#]
+    pub fn hint(&self) -> SignificanceHint {
+        let Some(p) = self.p_value else {
+            return SignificanceHint::Insufficient;
+        };
+        let reliable = self.n_a >= MIN_RELIABLE_N && self.n_b >= MIN_RELIABLE_N;
+        let meaningful = self.effect_magnitude.is_some_and(|m| m != "negligible");
+        match p {
+            p if p < 0.05 && reliable && meaningful => SignificanceHint::Likely,
+            p if p < 0.10 => SignificanceHint::Possible,
+            _ => SignificanceHint::Unlikely,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[doc = r#"Pearson correlation between two paired series.
+
+- `n`: number of pairs.
+- `r`: Pearson coefficient in -1..=1; `strength` labels `|r|` as `negligible` (< 0.1),
+  `weak` (< 0.3), `moderate` (< 0.5), or `strong`.
+- `p_value`: two-sided t-test of `r = 0` with `n - 2` degrees of freedom.
+- `caveats`: human-readable warnings; empty with at least [`MIN_RELIABLE_N`] pairs.
+
+`r` and `p_value` are `None` with fewer than three pairs or when a series is constant.
+"#]
+pub struct Correlation {
+    pub n: usize,
+    pub r: Option<f64>,
+    pub strength: Option<&'static str>,
+    pub p_value: Option<f64>,
+    pub caveats: Vec<String>,
+}
@@ -0,0 +1,224 @@
+#![doc = r#"Exercise intensity levels
+
+Represents qualitative intensity used by the exercise model. Values serialize as lowercase
+strings and implement both `Display` and `FromStr` for ergonomic use.
+
+- Serde representation: `"none" | "light" | "hard"`, or a custom level such as `"moderate"`.
+- `Display`: prints the lowercase string.
+- `FromStr`: parses the lowercase string and returns a [`DomainError::InvalidIntensity`] on failure.
+
+Which levels are accepted, and how they order for the per-day maximum, is configured per
+instance by [`IntensityLevels`] (`/api/settings/intensity-levels`). The built-in levels are
+always present, in the order none < light < hard; custom levels may be placed anywhere around
+them.
+
+[`DomainError::InvalidIntensity`]: crate::domain::DomainError::InvalidIntensity
+"#]
+
+use crate::domain::DomainError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+
+const MAX_LEVELS: usize = 10;
+const MAX_LEVEL_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[doc = r#"Exercise intensity level.
+
+Custom levels are syntactically valid when they are 1..=32 characters of `[a-z0-9_]`; whether
+an instance accepts one is decided by its [`IntensityLevels`]. Build values with `parse` so the
+built-in names map to their variants.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# fn main() -> Result<(), DomainError> {
+use sleep_core::models::Intensity;
+
+let level: Intensity = "light".parse()?;
+assert_eq!(level, Intensity::Light);
+assert_eq!(level.to_string(), "light");
+
+let custom: Intensity = "moderate".parse()?;
+assert_eq!(custom, Intensity::Custom("moderate".into()));
+# Ok(()) }
+```
+
+# Errors
+
+Parsing with `FromStr` returns [`DomainError::InvalidIntensity`] when the input is not a valid
+level name.
+
+[`DomainError::InvalidIntensity`]: crate::domain::DomainError::InvalidIntensity
+"#]
+pub enum Intensity {
+    None,
+    Light,
+    Hard,
+    Custom(String),
+}
+
+impl Intensity {
+    #[doc = r#"Return the lowercase level name."#]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Intensity::None => "none",
+            Intensity::Light => "light",
+            Intensity::Hard => "hard",
+            Intensity::Custom(s) => s,
+        }
+    }
+}
+
+impl std::fmt::Display for Intensity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Intensity {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Intensity::None),
+            "light" => Ok(Intensity::Light),
+            "hard" => Ok(Intensity::Hard),
+            other
+                if !other.is_empty()
+                    && other.len() <= MAX_LEVEL_LEN
+                    && other
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') =>
+            {
+                Ok(Intensity::Custom(other.to_string()))
+            }
+            other => Err(DomainError::InvalidIntensity(other.to_string())),
+        }
+    }
+}
+
+impl Serialize for Intensity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Intensity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Intensity {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Intensity".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "Exercise intensity level: \"none\", \"light\", \"hard\", or a custom level configured in the intensity level settings.",
+            "type": "string",
+            "pattern": "^[a-z0-9_]{1,32}$"
+        })
+    }
+}
+
+#[doc = r#"Accepted intensity levels, lowest first.
+
+The order defines the per-day maximum reported by `GET /api/exercise/intensity`.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# fn main() -> Result<(), DomainError> {
+use sleep_core::models::{Intensity, IntensityLevels};
+
+let levels = IntensityLevels {
+    levels: vec![
+        Intensity::None,
+        Intensity::Light,
+        "moderate".parse()?,
+        Intensity::Hard,
+    ],
+};
+levels.validate()?;
+assert!(levels.rank(&"moderate".parse()?) > levels.rank(&Intensity::Light));
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IntensityLevels {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 3, max = MAX_LEVELS)))]
+    pub levels: Vec<Intensity>,
+}
+
+impl Default for IntensityLevels {
+    fn default() -> Self {
+        IntensityLevels {
+            levels: vec![Intensity::None, Intensity::Light, Intensity::Hard],
+        }
+    }
+}
+
+impl IntensityLevels {
+    #[doc = r#"Validate the level list.
+
+- 3..=10 unique levels
+- contains `none`, `light`, and `hard`, in that relative order
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.levels.len() < 3 || self.levels.len() > MAX_LEVELS {
+            return Err(DomainError::InvalidInput(format!(
+                "intensity levels must have between 3 and {MAX_LEVELS} entries"
+            )));
+        }
+        let mut seen = HashSet::new();
+        for level in &self.levels {
+            if !seen.insert(level) {
+                return Err(DomainError::InvalidInput(format!(
+                    "duplicate intensity level: {level}"
+                )));
+            }
+        }
+        let builtins = [Intensity::None, Intensity::Light, Intensity::Hard];
+        let ranks: Vec<Option<usize>> = builtins.iter().map(|b| self.rank(b)).collect();
+        match ranks[..] {
+            [Some(none), Some(light), Some(hard)] if none < light && light < hard => Ok(()),
+            _ => Err(DomainError::InvalidInput(
+                "intensity levels must include none, light, and hard in that order".into(),
+            )),
+        }
+    }
+
+    #[doc = r#"Position of `level` (0 = lowest), or `None` when it is not accepted."#]
+    pub fn rank(&self, level: &Intensity) -> Option<usize> {
+        self.levels.iter().position(|l| l == level)
+    }
+
+    #[doc = r#"Check that `level` is accepted.
+
+# Errors
+
+Returns [`DomainError::InvalidIntensity`] for a level missing from the list.
+"#]
+    pub fn check(&self, level: &Intensity) -> Result<(), DomainError> {
+        match self.rank(level) {
+            Some(_) => Ok(()),
+            None => Err(DomainError::InvalidIntensity(level.to_string())),
+        }
+    }
+}
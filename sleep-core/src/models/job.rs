@@ -0,0 +1,21 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"Recorded execution of a background job.
+
+- `job`: job name (e.g., `sqlite_maintenance`).
+- `started_at` / `finished_at`: UTC timestamps; `finished_at` is `None` while running.
+- `status`: `running`, `ok`, or `error`.
+- `detail`: job-specific summary on success, or the error message on failure.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct JobRun {
+    pub id: i64,
+    pub job: String,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub status: String,
+    pub detail: Option<String>,
+}
@@ -0,0 +1,152 @@
+use crate::domain::DomainError;
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+const MAX_NAME_LEN: usize = 80;
+const MAX_DOSE_LEN: usize = 32;
+
+#[doc = r#"User-provided medication (melatonin, a prescription sleep aid, ...).
+
+- `name`: 1..=80 characters after trimming; unique.
+- `dose`: optional usual dose as free text (e.g. `3 mg`), at most 32 characters.
+- `prescription`: whether it is prescribed; defaults to `false`.
+
+Intake is logged per night with [`MedicationEventInput`].
+
+# Example
+
+```rust
+# use sleep_core::models::MedicationInput;
+let melatonin = MedicationInput {
+    name: "Melatonin".into(),
+    dose: Some("3 mg".into()),
+    prescription: false,
+};
+assert!(melatonin.validate().is_ok());
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MedicationInput {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_NAME_LEN)))]
+    pub name: String,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_DOSE_LEN)))]
+    pub dose: Option<String>,
+    #[serde(default)]
+    pub prescription: bool,
+}
+
+impl MedicationInput {
+    #[doc = r#"Validate the medication.
+
+- `name` must be 1..=80 characters after trimming
+- `dose`, when present, must be at most 32 characters
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let name_len = self.name.trim().chars().count();
+        if name_len == 0 || name_len > MAX_NAME_LEN {
+            return Err(DomainError::InvalidInput(format!(
+                "name must be 1-{MAX_NAME_LEN} characters"
+            )));
+        }
+        validate_dose(self.dose.as_deref())
+    }
+}
+
+#[doc = r#"Stored medication."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Medication {
+    pub id: i64,
+    pub name: String,
+    pub dose: Option<String>,
+    pub prescription: bool,
+}
+
+#[doc = r#"User-provided intake of a medication.
+
+- `medication_id`: a medication from `GET /api/medications`.
+- `date`: wake date of the night it was taken for (same convention as [`SleepInput::date`]).
+- `time`: optional local clock time it was taken; accepts the same formats as sleep times.
+- `dose`: optional dose when it differs from the medication's usual one, at most 32 characters.
+
+# Example
+
+```rust
+# use sleep_core::models::MedicationEventInput;
+# use chrono::{NaiveDate, NaiveTime};
+let event = MedicationEventInput {
+    medication_id: 1,
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+    time: NaiveTime::from_hms_opt(22, 30, 0),
+    dose: None,
+};
+assert!(event.validate().is_ok());
+```
+
+[`SleepInput::date`]: crate::models::SleepInput::date
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MedicationEventInput {
+    pub medication_id: i64,
+    pub date: NaiveDate,
+    #[serde(default, deserialize_with = "crate::time::flexible_time_opt")]
+    pub time: Option<NaiveTime>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_DOSE_LEN)))]
+    pub dose: Option<String>,
+}
+
+impl MedicationEventInput {
+    #[doc = r#"Validate the event.
+
+- `dose`, when present, must be at most 32 characters
+
+Whether `medication_id` exists is checked when the event is stored.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        validate_dose(self.dose.as_deref())
+    }
+}
+
+#[doc = r#"Stored medication intake, with the medication's `name`.
+
+`dose` is the event's own dose, or the medication's usual dose when none was given.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MedicationEvent {
+    pub id: i64,
+    pub medication_id: i64,
+    pub name: String,
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    pub dose: Option<String>,
+}
+
+fn validate_dose(dose: Option<&str>) -> Result<(), DomainError> {
+    if let Some(d) = dose
+        && d.chars().count() > MAX_DOSE_LEN
+    {
+        return Err(DomainError::InvalidInput(format!(
+            "dose must be at most {MAX_DOSE_LEN} characters"
+        )));
+    }
+    Ok(())
+}
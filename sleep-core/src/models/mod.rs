@@ -0,0 +1,87 @@
+#![doc = r#"Data models
+
+Structures and enums used as request/response payloads and DB projections.
+
+Key types: [`SleepInput`], [`SleepPatch`], [`SleepSession`], [`ActiveSleep`], [`ExerciseInput`], [`HrZoneMinutes`], [`NoteInput`], [`BodyMetricInput`], [`DisturbanceInput`], [`MedicationInput`], [`MedicationEventInput`], [`MoodInput`], [`ExperimentInput`], [`AuditReason`], [`JobRun`], [`RoutineChecklist`], [`SleepGoal`], [`DayBoundary`], [`TimeRounding`], [`KnownDevice`], [`EmailChangeInput`], [`ApiToken`], [`Attachment`], [`Starred`], [`PublicSummarySettings`], [`RedactionSettings`], [`ShareLink`], [`AlertRules`], [`Quality`], [`Intensity`], [`IntensityLevels`].
+
+See also: [`time::compute_duration_min`] for DST-aware duration computation. Persistence lives
+in `sleep_api::repository`.
+
+[`time::compute_duration_min`]: crate::time::compute_duration_min
+"#]
+
+pub mod alert;
+pub mod api_token;
+pub mod attachment;
+pub mod audit;
+pub mod body;
+pub mod day_boundary;
+pub mod device;
+pub mod disturbance;
+pub mod email_change;
+pub mod exercise;
+pub mod experiment;
+pub mod external_ref;
+pub mod friction;
+pub mod goal;
+pub mod inference;
+pub mod intensity;
+pub mod job;
+pub mod medication;
+pub mod mood;
+pub mod note;
+pub mod public_summary;
+pub mod quality;
+pub mod redaction;
+pub mod routine;
+pub mod schema;
+pub mod share_link;
+pub mod sleep;
+pub mod sleep_timer;
+pub mod starred;
+pub mod tag;
+pub mod time_rounding;
+
+#[allow(unused_imports)]
+pub use alert::{
+    AlertComparison, AlertCondition, AlertEvent, AlertHistoryQuery, AlertMetric, AlertRule,
+    AlertRules,
+};
+#[allow(unused_imports)]
+pub use api_token::{ApiScope, ApiToken, ApiTokenInput, CreatedApiToken};
+pub use attachment::{Attachment, AttachmentUpload};
+pub use audit::{AuditEntry, AuditPage, AuditQuery, AuditReason};
+pub use body::{BodyMetric, BodyMetricInput};
+pub use day_boundary::DayBoundary;
+pub use device::KnownDevice;
+pub use disturbance::{Disturbance, DisturbanceInput, DisturbanceKind};
+pub use email_change::{EmailChangeInput, PendingEmailChange};
+pub use exercise::{DateIntensity, ExerciseEvent, ExerciseInput, ExerciseZoneDay, HrZoneMinutes};
+pub use experiment::{
+    Experiment, ExperimentInput, ExperimentMetricResult, ExperimentResults, GroupSummary,
+};
+pub use external_ref::ExternalRef;
+pub use friction::{
+    FrictionErrorKindAggregate, FrictionTelemetryEvent, FrictionTelemetryInput,
+    FrictionWindowAggregate,
+};
+pub use goal::SleepGoal;
+#[allow(unused_imports)]
+pub use intensity::{Intensity, IntensityLevels};
+pub use job::JobRun;
+pub use medication::{Medication, MedicationEvent, MedicationEventInput, MedicationInput};
+pub use mood::{MoodEntry, MoodInput};
+pub use note::{Note, NoteInput};
+pub use public_summary::{PublicField, PublicSummarySettings};
+#[allow(unused_imports)]
+pub use quality::Quality;
+pub use redaction::{Audience, RedactionPolicy, RedactionSettings};
+pub use routine::{RoutineChecklist, RoutineEntry, RoutineInput, RoutineItem};
+pub use schema::{SchemaColumn, SchemaDescription, SchemaObject};
+pub use share_link::{
+    CreatedShareLink, ShareLink, ShareLinkInput, ShareLinkStats, ShareLinkVisitor, SharedView,
+};
+pub use sleep::{SleepInput, SleepListItem, SleepPatch, SleepSession};
+pub use sleep_timer::{ActiveSleep, SleepTimerStop};
+pub use starred::Starred;
+pub use time_rounding::TimeRounding;
@@ -0,0 +1,60 @@
+use crate::domain::DomainError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"Daytime mood / energy rating for a day, posted to `POST /api/mood`.
+
+- `date`: the day rated. It is the wake date of the preceding night, so a rating sits next to
+  the sleep it followed.
+- `mood`: 1 (low) ..= 5 (great).
+
+Posting a date again replaces its rating.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::MoodInput;
+# use chrono::NaiveDate;
+# fn main() -> Result<(), DomainError> {
+let mood = MoodInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    mood: 4,
+};
+mood.validate()?;
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MoodInput {
+    pub date: NaiveDate,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = 5)))]
+    pub mood: i32,
+}
+
+impl MoodInput {
+    #[doc = r#"Validate the rating.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] unless `mood` is in 1..=5.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !(1..=5).contains(&self.mood) {
+            return Err(DomainError::InvalidInput("mood must be 1-5".into()));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"A stored mood rating, as listed by `GET /api/mood`."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MoodEntry {
+    pub date: NaiveDate,
+    pub mood: i32,
+}
@@ -0,0 +1,90 @@
+use super::tag::{MAX_TAG_LEN, MAX_TAGS, normalize_tags, validate_tags};
+use crate::domain::DomainError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"User-provided note associated with a date.
+
+Notes can be used to capture free-form observations that may help interpret sleep data.
+
+- `date`: calendar date the note applies to.
+- `body`: optional free text. Limited to 1000 characters.
+- `tags`: free-form labels (see [`tag`](crate::models::tag)); at most 10 of up to 32 characters.
+- `session_id`: optional sleep session the note is about. `date` must then be that session's
+  wake date; the note follows the session when its date is edited.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::NoteInput;
+# use chrono::NaiveDate;
+# fn main() -> Result<(), DomainError> {
+let note = NoteInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    body: Some("Felt refreshed".to_string()),
+    tags: vec!["caffeine".into()],
+    session_id: None,
+};
+note.validate()?;
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NoteInput {
+    pub date: NaiveDate,
+    #[cfg_attr(feature = "schemars", schemars(length(max = 1000)))]
+    pub body: Option<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_TAGS), inner(length(min = 1, max = MAX_TAG_LEN))))]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub session_id: Option<i64>,
+}
+
+impl NoteInput {
+    #[doc = r#"Validate the note length (<= 1000 characters) and its tags.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] if `body` is longer than 1000 characters or a tag rule
+is violated (see [`validate_tags`]).
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if let Some(ref b) = self.body
+            && (b.len() > 1000)
+        {
+            return Err(DomainError::InvalidInput("body too long".into()));
+        }
+        validate_tags(&self.tags)
+    }
+
+    #[doc = r#"Return `tags` normalized as stored (see [`normalize_tags`])."#]
+    pub fn normalized_tags(&self) -> Vec<String> {
+        normalize_tags(&self.tags)
+    }
+}
+
+#[doc = r#"A stored note, as listed by `GET /api/notes` and `GET /api/starred`.
+
+`session_id` is the linked sleep session, if any; linked notes are also returned with the
+session by `GET /api/sleep/{id}`.
+
+`tags` comes from the `note_tags` join table and is loaded separately by the repository.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Note {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub body: Option<String>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub session_id: Option<i64>,
+}
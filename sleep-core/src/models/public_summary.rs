@@ -0,0 +1,75 @@
+use crate::domain::DomainError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"A coarse aggregate that may be exposed by `GET /api/public/summary`."#]
+pub enum PublicField {
+    /// Mean nightly sleep this month, rounded per the `public` redaction policy (5 minutes by
+    /// default).
+    AvgDurationMonth,
+    /// Mean quality this month, rounded to one decimal.
+    AvgQualityMonth,
+    /// Nights logged this month.
+    NightsLoggedMonth,
+    /// Consecutive logged nights up to today.
+    LoggingStreak,
+}
+
+#[doc = r#"What the unauthenticated public summary exposes.
+
+- `enabled`: off by default; while off, `GET /api/public/summary` returns 404.
+- `fields`: the aggregates included in the response; anything not listed is omitted.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::{PublicField, PublicSummarySettings};
+# fn main() -> Result<(), DomainError> {
+let settings = PublicSummarySettings {
+    enabled: true,
+    fields: vec![PublicField::AvgDurationMonth, PublicField::LoggingStreak],
+};
+settings.validate()?;
+assert!(settings.exposes(PublicField::LoggingStreak));
+assert!(!PublicSummarySettings::default().enabled);
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PublicSummarySettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub fields: Vec<PublicField>,
+}
+
+impl PublicSummarySettings {
+    #[doc = r#"Validate the settings.
+
+- `fields` must not repeat a field
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let mut seen = HashSet::new();
+        if self.fields.iter().any(|f| !seen.insert(f)) {
+            return Err(DomainError::InvalidInput(
+                "public summary fields must be unique".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[doc = r#"Whether `field` is included in the public summary."#]
+    pub fn exposes(&self, field: PublicField) -> bool {
+        self.enabled && self.fields.contains(&field)
+    }
+}
@@ -9,8 +9,8 @@ Higher values indicate better perceived sleep quality.
 # Example
 
 ```rust
-# use sleep_api::domain::DomainError;
-use sleep_api::models::Quality;
+# use sleep_core::domain::DomainError;
+use sleep_core::models::Quality;
 
 // Construct directly
 let q = Quality(4);
@@ -23,7 +23,8 @@ assert_eq!(q2.value(), 5);
 ```
 "#]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
-pub struct Quality(pub u8);
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Quality(#[cfg_attr(feature = "schemars", schemars(range(min = 1, max = 5)))] pub u8);
 
 impl<'de> Deserialize<'de> for Quality {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -0,0 +1,142 @@
+use crate::domain::DomainError;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+/// Rounding steps a policy may use, in minutes.
+const ALLOWED_ROUNDING_MIN: [u32; 6] = [1, 5, 10, 15, 30, 60];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[doc = r#"Who sees data leaving the authenticated API, each with its own [`RedactionPolicy`]."#]
+pub enum Audience {
+    /// Holders of a share link (`GET /api/shared/{secret}`).
+    Share,
+    /// Anyone, through `GET /api/public/summary`.
+    Public,
+    /// Recipients of an anonymized export (`GET /api/export?anonymize=true`).
+    Export,
+}
+
+#[doc = r#"How records are coarsened for one [`Audience`].
+
+- `note_bodies`: keep the text and tags of notes; when false, notes keep their date but lose
+  `body` and `tags`.
+- `time_rounding_min`: clock times and durations are rounded to this many minutes (one of 1,
+  5, 10, 15, 30, 60; 1 keeps them exact to the minute).
+- `check_in`: keep the morning check-in (`wake_feeling`, `sleep_inertia_min`).
+
+# Example
+
+```rust
+# use chrono::NaiveTime;
+# use sleep_core::models::RedactionPolicy;
+let policy = RedactionPolicy { note_bodies: false, time_rounding_min: 15, check_in: false };
+let t = NaiveTime::from_hms_opt(23, 7, 0).unwrap();
+assert_eq!(policy.round_time(t), NaiveTime::from_hms_opt(23, 0, 0).unwrap());
+assert_eq!(policy.round_minutes(458), 465);
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RedactionPolicy {
+    pub note_bodies: bool,
+    pub time_rounding_min: u32,
+    pub check_in: bool,
+}
+
+impl RedactionPolicy {
+    /// Strip note bodies and the check-in, round times to 15 minutes.
+    pub const STRICT: RedactionPolicy = RedactionPolicy {
+        note_bodies: false,
+        time_rounding_min: 15,
+        check_in: false,
+    };
+
+    #[doc = r#"Round `t` to the nearest step; times rounding up past 23:59 wrap to midnight."#]
+    pub fn round_time(&self, t: NaiveTime) -> NaiveTime {
+        super::time_rounding::round_time(t, self.step())
+    }
+
+    #[doc = r#"Round a duration in minutes to the nearest step."#]
+    pub fn round_minutes(&self, minutes: i32) -> i32 {
+        let step = self.step() as i32;
+        (f64::from(minutes) / f64::from(step)).round() as i32 * step
+    }
+
+    fn step(&self) -> u32 {
+        self.time_rounding_min.max(1)
+    }
+
+    fn validate(&self, audience: &str) -> Result<(), DomainError> {
+        if !ALLOWED_ROUNDING_MIN.contains(&self.time_rounding_min) {
+            return Err(DomainError::InvalidInput(format!(
+                "{audience}.time_rounding_min must be one of 1, 5, 10, 15, 30, 60"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"Redaction policy per [`Audience`], saved with `POST /api/settings/redaction`.
+
+Defaults: share links and anonymized exports drop note bodies and the check-in and round
+times to 15 minutes; the public summary rounds its average duration to 5 minutes.
+
+# Example
+
+```rust
+# use sleep_core::models::{Audience, RedactionSettings};
+let settings = RedactionSettings::default();
+assert!(settings.validate().is_ok());
+assert!(!settings.policy(Audience::Share).note_bodies);
+assert_eq!(settings.policy(Audience::Public).time_rounding_min, 5);
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RedactionSettings {
+    pub share: RedactionPolicy,
+    pub public: RedactionPolicy,
+    pub export: RedactionPolicy,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        RedactionSettings {
+            share: RedactionPolicy::STRICT,
+            public: RedactionPolicy {
+                time_rounding_min: 5,
+                ..RedactionPolicy::STRICT
+            },
+            export: RedactionPolicy::STRICT,
+        }
+    }
+}
+
+impl RedactionSettings {
+    #[doc = r#"Validate the settings.
+
+- every `time_rounding_min` must be 1, 5, 10, 15, 30 or 60
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        self.share.validate("share")?;
+        self.public.validate("public")?;
+        self.export.validate("export")
+    }
+
+    #[doc = r#"The policy applied for `audience`."#]
+    pub fn policy(&self, audience: Audience) -> RedactionPolicy {
+        match audience {
+            Audience::Share => self.share,
+            Audience::Public => self.public,
+            Audience::Export => self.export,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::domain::DomainError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const MAX_ITEMS: usize = 20;
+const MAX_LABEL_LEN: usize = 80;
+const MAX_ID_LEN: usize = 40;
+
+#[doc = r#"One item of the pre-sleep routine checklist.
+
+- `id`: stable identifier, lowercase ASCII letters, digits, and `_` (max 40 chars). Recorded
+  nights reference items by id, so renaming a label keeps history intact.
+- `label`: display text, 1..=80 characters.
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RoutineItem {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_ID_LEN), pattern(r"^[a-z0-9_]+$")))]
+    pub id: String,
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_LABEL_LEN)))]
+    pub label: String,
+}
+
+#[doc = r#"The configured pre-sleep routine checklist.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::RoutineChecklist;
+# fn main() -> Result<(), DomainError> {
+let checklist = RoutineChecklist::default();
+checklist.validate()?;
+assert_eq!(checklist.items[0].id, "no_screens_after_22");
+# Ok(()) }
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RoutineChecklist {
+    #[cfg_attr(feature = "schemars", schemars(length(min = 1, max = MAX_ITEMS)))]
+    pub items: Vec<RoutineItem>,
+}
+
+impl Default for RoutineChecklist {
+    fn default() -> Self {
+        let item = |id: &str, label: &str| RoutineItem {
+            id: id.into(),
+            label: label.into(),
+        };
+        RoutineChecklist {
+            items: vec![
+                item("no_screens_after_22", "No screens after 22:00"),
+                item("no_caffeine_after_14", "No caffeine after 14:00"),
+                item("read_20_min", "Read for 20 minutes"),
+            ],
+        }
+    }
+}
+
+impl RoutineChecklist {
+    #[doc = r#"Validate the checklist.
+
+- 1..=20 items
+- ids are unique, non-empty, at most 40 chars of `[a-z0-9_]`
+- labels are 1..=80 characters after trimming
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.items.is_empty() || self.items.len() > MAX_ITEMS {
+            return Err(DomainError::InvalidInput(format!(
+                "checklist must have between 1 and {MAX_ITEMS} items"
+            )));
+        }
+        let mut seen = HashSet::new();
+        for item in &self.items {
+            let valid_id = !item.id.is_empty()
+                && item.id.len() <= MAX_ID_LEN
+                && item
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid_id {
+                return Err(DomainError::InvalidInput(format!(
+                    "invalid item id: {:?}",
+                    item.id
+                )));
+            }
+            if !seen.insert(item.id.as_str()) {
+                return Err(DomainError::InvalidInput(format!(
+                    "duplicate item id: {}",
+                    item.id
+                )));
+            }
+            let label_len = item.label.trim().chars().count();
+            if label_len == 0 || label_len > MAX_LABEL_LEN {
+                return Err(DomainError::InvalidInput(format!(
+                    "label for {} must be 1-{MAX_LABEL_LEN} characters",
+                    item.id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[doc = r#"Body for `POST /api/routine/{date}`: ids of the checklist items completed that evening.
+
+Items of the current checklist that are not listed are recorded as not done.
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RoutineInput {
+    pub done: Vec<String>,
+}
+
+#[doc = r#"Recorded routine item for one evening."#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RoutineEntry {
+    pub item_id: String,
+    pub done: bool,
+}
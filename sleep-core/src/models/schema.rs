@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"Logical description of the live database schema.
+
+Returned by `GET /api/admin/schema`. Generated from `sqlite_master` and
+`pragma_table_info`, so it reflects the migrated database rather than the
+migration files.
+
+- `schema_version`: highest applied migration version (from `_sqlx_migrations`).
+- `objects`: tables and views ordered by kind then name; SQLite internals and the
+  migration bookkeeping table are excluded.
+"#]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SchemaDescription {
+    pub schema_version: Option<i64>,
+    pub objects: Vec<SchemaObject>,
+}
+
+#[doc = r#"One table or view.
+
+- `kind`: `"table"` or `"view"`.
+- `sql`: the `CREATE` statement as stored by SQLite (the view definition for views).
+"#]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SchemaObject {
+    pub name: String,
+    pub kind: String,
+    pub sql: Option<String>,
+    pub columns: Vec<SchemaColumn>,
+}
+
+#[doc = r#"Column metadata as reported by `pragma_table_info`.
+
+- `data_type`: declared type (may be empty for view columns computed by expressions).
+- `primary_key`: 1-based position in the primary key, `0` when not part of it.
+"#]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SchemaColumn {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub default_value: Option<String>,
+    pub primary_key: i64,
+}
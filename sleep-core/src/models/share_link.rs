@@ -0,0 +1,170 @@
+use crate::domain::DomainError;
+use crate::models::{ExerciseEvent, Note, SleepListItem};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_EXPIRES_IN_DAYS: u32 = 7;
+const MAX_EXPIRES_IN_DAYS: u32 = 90;
+const MAX_RANGE_DAYS: i64 = 366;
+const MAX_USES: u32 = 10_000;
+
+#[doc = r#"Request body of `POST /api/share-links`.
+
+- `from` / `to`: the inclusive date range the link exposes, at most 366 days.
+- `expires_in_days`: lifetime, 1..=90 days (default 7). Links cannot be created without an
+  expiry.
+- `max_uses`: optional number of views, 1..=10000, after which the link stops working.
+
+# Example
+
+```rust
+# use chrono::NaiveDate;
+# use sleep_core::models::ShareLinkInput;
+let input = ShareLinkInput {
+    from: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+    to: NaiveDate::from_ymd_opt(2025, 6, 30).unwrap(),
+    expires_in_days: None,
+    max_uses: Some(5),
+};
+assert!(input.validate().is_ok());
+assert_eq!(input.expires_in_days(), 7);
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShareLinkInput {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = MAX_EXPIRES_IN_DAYS)))]
+    pub expires_in_days: Option<u32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = MAX_USES)))]
+    pub max_uses: Option<u32>,
+}
+
+impl ShareLinkInput {
+    #[doc = r#"Validate the range, lifetime and use limit.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.from > self.to {
+            return Err(DomainError::InvalidInput(
+                "from must not be after to".into(),
+            ));
+        }
+        if (self.to - self.from).num_days() >= MAX_RANGE_DAYS {
+            return Err(DomainError::InvalidInput(format!(
+                "a share link covers at most {MAX_RANGE_DAYS} days"
+            )));
+        }
+        if !(1..=MAX_EXPIRES_IN_DAYS).contains(&self.expires_in_days()) {
+            return Err(DomainError::InvalidInput(format!(
+                "expires_in_days must be between 1 and {MAX_EXPIRES_IN_DAYS}"
+            )));
+        }
+        if let Some(max_uses) = self.max_uses
+            && !(1..=MAX_USES).contains(&max_uses)
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "max_uses must be between 1 and {MAX_USES}"
+            )));
+        }
+        Ok(())
+    }
+
+    #[doc = r#"Requested lifetime in days, defaulting to 7."#]
+    pub fn expires_in_days(&self) -> u32 {
+        self.expires_in_days.unwrap_or(DEFAULT_EXPIRES_IN_DAYS)
+    }
+}
+
+#[doc = r#"A share link as listed by `GET /api/share-links` (the secret is never listed).
+
+- `from_date` / `to_date`: the shared date range.
+- `created_at` / `expires_at` / `last_used_at`: UTC timestamps; `last_used_at` is `None` until
+  the link is opened.
+- `use_count` / `max_uses`: views so far, and the optional limit after which the link answers
+  404.
+- `expired`: whether `expires_at` has passed or the use limit is reached; such links answer 404
+  but stay listed until revoked.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShareLink {
+    pub id: i64,
+    pub from_date: NaiveDate,
+    pub to_date: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub use_count: i64,
+    pub max_uses: Option<i64>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub expired: bool,
+}
+
+#[doc = r#"One client address that opened a share link, in [`ShareLinkStats::visitors`].
+
+`ip` is `"unknown"` when the server could not determine the client address."#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShareLinkVisitor {
+    pub ip: String,
+    pub use_count: i64,
+    pub first_used_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+}
+
+#[doc = r#"Response of `GET /api/shares/{id}/stats`: how often and from where a link was
+opened, to spot a leaked link.
+
+- `use_count`, `max_uses`, `last_used_at`: as on [`ShareLink`].
+- `distinct_ips`: number of client addresses that opened the link.
+- `visitors`: per-address counts, most recently seen first.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShareLinkStats {
+    pub id: i64,
+    pub use_count: i64,
+    pub max_uses: Option<i64>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub distinct_ips: usize,
+    pub visitors: Vec<ShareLinkVisitor>,
+}
+
+#[doc = r#"Response of `POST /api/share-links`.
+
+`secret` is the last path segment of the share URL (`/api/shared/{secret}`). It is shown only
+here; the server keeps just its hash.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreatedShareLink {
+    pub link: ShareLink,
+    pub secret: String,
+}
+
+#[doc = r#"Response of `GET /api/shared/{secret}`: the records of the shared range, redacted
+with the `share` policy of the redaction settings (see [`RedactionSettings`]).
+
+[`RedactionSettings`]: crate::models::RedactionSettings
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SharedView {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub sleep: Vec<SleepListItem>,
+    pub exercise: Vec<ExerciseEvent>,
+    pub notes: Vec<Note>,
+}
@@ -0,0 +1,379 @@
+use super::external_ref::ExternalRef;
+use super::note::Note;
+use super::quality::Quality;
+use super::tag::{MAX_TAG_LEN, MAX_TAGS, normalize_tags, validate_tags};
+use crate::domain::DomainError;
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"User-provided input for creating or updating a sleep session.
+
+Field semantics (wake-date model):
+- `date`: the wake date of the sleep (the morning date).
+- `bed_time` / `wake_time`: local times. If `bed_time > wake_time`, the bed datetime
+  is considered to be on the previous calendar day. Input accepts `HH:MM`, `HH:MM:SS`, or
+  `h:mm AM/PM` (see [`parse_flexible_time`]); output is always `HH:MM:SS`.
+- `latency_min`: minutes to fall asleep, must be in 0..=180.
+- `awakenings`: number of awakenings, must be in 0..=10.
+- `quality`: discrete quality score enforced by [`Quality`] (1..=5).
+- `wake_feeling`: optional rating of how alert the user felt on waking, 1 (groggy) ..= 5 (refreshed).
+- `sleep_inertia_min`: optional minutes until the user felt fully awake, must be in 0..=240.
+- `waso_min`: optional minutes awake after first falling asleep (wake after sleep onset), must
+  be in 0..=600. It lowers the session's sleep efficiency (see [`sleep_efficiency`]).
+- `aids`: sleep aids used for the session (e.g. `earplugs`, `mask`, `white_noise`, `melatonin`).
+  Names are normalized to lowercase; at most 10 distinct aids of up to 32 characters each.
+- `tags`: free-form labels such as `caffeine` or `travel` (see [`tag`](crate::models::tag)),
+  normalized the same way; at most 10 of up to 32 characters each.
+- `timezone`: optional IANA timezone the night was spent in (e.g. `America/New_York`). It is
+  stored with the session and used instead of the user timezone when computing the duration,
+  so nights logged while travelling get their local DST rules (see [`SleepInput::tz`]).
+
+For duration computations across DST, see [`compute_duration_min`].
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use sleep_core::models::{SleepInput, Quality};
+# use chrono::{NaiveDate, NaiveTime};
+# fn main() -> Result<(), DomainError> {
+let input = SleepInput {
+    date: NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    bed_time: NaiveTime::from_hms_opt(23, 0, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
+    wake_time: NaiveTime::from_hms_opt(7, 0, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
+    latency_min: 10,
+    awakenings: 1,
+    quality: Quality(4),
+    wake_feeling: Some(3),
+    sleep_inertia_min: Some(20),
+    waso_min: Some(15),
+    aids: vec!["earplugs".into()],
+    tags: vec!["travel".into()],
+    timezone: Some("Europe/Paris".into()),
+};
+input.validate()?;
+# Ok(()) }
+```
+
+[`compute_duration_min`]: crate::time::compute_duration_min
+[`sleep_efficiency`]: crate::time::sleep_efficiency
+[`parse_flexible_time`]: crate::time::parse_flexible_time
+[`Quality`]: crate::models::Quality
+"#]
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SleepInput {
+    pub date: NaiveDate,
+    #[serde(deserialize_with = "crate::time::flexible_time")]
+    pub bed_time: NaiveTime,
+    #[serde(deserialize_with = "crate::time::flexible_time")]
+    pub wake_time: NaiveTime,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 180)))]
+    pub latency_min: i32,
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 10)))]
+    pub awakenings: i32,
+    pub quality: Quality,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = 5)))]
+    pub wake_feeling: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 240)))]
+    pub sleep_inertia_min: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 600)))]
+    pub waso_min: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_AIDS), inner(length(min = 1, max = MAX_AID_LEN))))]
+    pub aids: Vec<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_TAGS), inner(length(min = 1, max = MAX_TAG_LEN))))]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+const MAX_AIDS: usize = 10;
+const MAX_AID_LEN: usize = 32;
+
+impl SleepInput {
+    #[doc = r#"Validate input ranges for latency and awakenings.
+
+- `latency_min` must be in 0..=180
+- `awakenings` must be in 0..=10
+- `quality` is validated by the [`Quality`] type
+- `wake_feeling`, when present, must be in 1..=5
+- `sleep_inertia_min`, when present, must be in 0..=240
+- `waso_min`, when present, must be in 0..=600
+- at most 10 `aids`, each non-blank and at most 32 characters
+- at most 10 `tags`, each non-blank and at most 32 characters
+- `timezone`, when present, must be an IANA timezone name
+- Time relationships are validated at duration computation time (see [`compute_duration_min`]).
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a field is out of range.
+
+[`Quality`]: crate::models::Quality
+[`compute_duration_min`]: crate::time::compute_duration_min
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !(0..=180).contains(&self.latency_min) {
+            return Err(DomainError::InvalidInput(
+                "latency_min must be between 0 and 180".into(),
+            ));
+        }
+        if !(0..=10).contains(&self.awakenings) {
+            return Err(DomainError::InvalidInput(
+                "awakenings must be between 0 and 10".into(),
+            ));
+        }
+        if let Some(f) = self.wake_feeling
+            && !(1..=5).contains(&f)
+        {
+            return Err(DomainError::InvalidInput(
+                "wake_feeling must be between 1 and 5".into(),
+            ));
+        }
+        if let Some(m) = self.sleep_inertia_min
+            && !(0..=240).contains(&m)
+        {
+            return Err(DomainError::InvalidInput(
+                "sleep_inertia_min must be between 0 and 240".into(),
+            ));
+        }
+        if let Some(m) = self.waso_min
+            && !(0..=600).contains(&m)
+        {
+            return Err(DomainError::InvalidInput(
+                "waso_min must be between 0 and 600".into(),
+            ));
+        }
+        if self.aids.len() > MAX_AIDS {
+            return Err(DomainError::InvalidInput(format!(
+                "at most {MAX_AIDS} aids are allowed"
+            )));
+        }
+        if self
+            .aids
+            .iter()
+            .any(|a| a.trim().is_empty() || a.trim().chars().count() > MAX_AID_LEN)
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "aids must be 1-{MAX_AID_LEN} characters"
+            )));
+        }
+        validate_tags(&self.tags)?;
+        if self.timezone.is_some() && self.tz().is_none() {
+            return Err(DomainError::InvalidInput(
+                "timezone must be an IANA timezone name such as Europe/Paris".into(),
+            ));
+        }
+        // quality validated by type; time relationship validated via duration computation in handlers
+        Ok(())
+    }
+
+    #[doc = r#"The session's own timezone, when `timezone` is set and valid.
+
+Callers fall back to the user timezone when this is `None`."#]
+    pub fn tz(&self) -> Option<Tz> {
+        self.timezone.as_deref().and_then(|name| name.parse().ok())
+    }
+
+    #[doc = r#"Return `aids` trimmed, lowercased, deduplicated, and sorted, as stored."#]
+    pub fn normalized_aids(&self) -> Vec<String> {
+        let mut aids: Vec<String> = self.aids.iter().map(|a| a.trim().to_lowercase()).collect();
+        aids.sort();
+        aids.dedup();
+        aids
+    }
+
+    #[doc = r#"Return `tags` normalized as stored (see [`normalize_tags`])."#]
+    pub fn normalized_tags(&self) -> Vec<String> {
+        normalize_tags(&self.tags)
+    }
+}
+
+#[doc = r#"Partial update for `PATCH /api/sleep/{id}`; every field is optional.
+
+Absent (or `null`) fields keep their stored value, so `{"quality": 4}` changes only the quality.
+`aids` and `tags`, when present, replace the whole list. `wake_feeling`, `sleep_inertia_min`,
+`waso_min` and `timezone` cannot be cleared by a patch; use `PUT` with a full [`SleepInput`] for
+that. Changing `timezone` recomputes the duration like a change of date or times.
+
+[`SleepPatch::apply`] merges the patch into a stored session and validates the result like a
+[`SleepInput`].
+"#]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SleepPatch {
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "crate::time::flexible_time_opt")]
+    pub bed_time: Option<NaiveTime>,
+    #[serde(default, deserialize_with = "crate::time::flexible_time_opt")]
+    pub wake_time: Option<NaiveTime>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 180)))]
+    pub latency_min: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 10)))]
+    pub awakenings: Option<i32>,
+    #[serde(default)]
+    pub quality: Option<Quality>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 1, max = 5)))]
+    pub wake_feeling: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 240)))]
+    pub sleep_inertia_min: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 600)))]
+    pub waso_min: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_AIDS), inner(length(min = 1, max = MAX_AID_LEN))))]
+    pub aids: Option<Vec<String>>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(length(max = MAX_TAGS), inner(length(min = 1, max = MAX_TAG_LEN))))]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+impl SleepPatch {
+    #[doc = r#"Merge this patch into `existing` and return the full, validated record.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a merged field is out of range (see
+[`SleepInput::validate`]), or [`DomainError::InvalidQuality`] when the stored quality is.
+"#]
+    pub fn apply(&self, existing: &SleepSession) -> Result<SleepInput, DomainError> {
+        let quality = match self.quality {
+            Some(q) => q,
+            None => Quality::try_from(
+                u8::try_from(existing.quality).map_err(|_| DomainError::InvalidQuality)?,
+            )?,
+        };
+        let merged = SleepInput {
+            date: self.date.unwrap_or(existing.date),
+            bed_time: self.bed_time.unwrap_or(existing.bed_time),
+            wake_time: self.wake_time.unwrap_or(existing.wake_time),
+            latency_min: self.latency_min.unwrap_or(existing.latency_min),
+            awakenings: self.awakenings.unwrap_or(existing.awakenings),
+            quality,
+            wake_feeling: self.wake_feeling.or(existing.wake_feeling),
+            sleep_inertia_min: self.sleep_inertia_min.or(existing.sleep_inertia_min),
+            waso_min: self.waso_min.or(existing.waso_min),
+            aids: self.aids.clone().unwrap_or_else(|| existing.aids.clone()),
+            tags: self.tags.clone().unwrap_or_else(|| existing.tags.clone()),
+            timezone: self.timezone.clone().or_else(|| existing.timezone.clone()),
+        };
+        merged.validate()?;
+        Ok(merged)
+    }
+}
+
+#[doc = r#"Database projection of a stored sleep session.
+
+This type aggregates fields from `sleep_sessions` and `sleep_metrics` for a given session id.
+
+Note: `quality` is stored as `i32` in the DB layer; use [`Quality::try_from`] to convert into the strong type if needed.
+`aids` and `tags` come from the `sleep_aids` and `sleep_session_tags` join tables and are
+loaded separately by the repository.
+`efficiency` is derived on every write from the stored duration, `latency_min` and `waso_min`
+(see [`sleep_efficiency`]); it is `None` for rows without a duration.
+`timezone` is the session's own IANA timezone, `None` when its duration uses the user timezone.
+`starred` is set with `POST /api/sleep/{id}/star` (see `GET /api/starred`).
+`external_refs` links the session to its ids in external services (see [`ExternalRef`]); it is
+empty for manually logged sessions.
+`notes` are the notes linked to the session (see [`Note::session_id`]), ordered by id; only
+`GET /api/sleep/{id}` loads them.
+
+[`Quality::try_from`]: crate::models::Quality::try_from
+[`sleep_efficiency`]: crate::time::sleep_efficiency
+[`ExternalRef`]: crate::models::ExternalRef
+[`Note::session_id`]: crate::models::Note::session_id
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SleepSession {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub bed_time: NaiveTime,
+    pub wake_time: NaiveTime,
+    pub latency_min: i32,
+    pub awakenings: i32,
+    pub quality: i32,
+    pub wake_feeling: Option<i32>,
+    pub sleep_inertia_min: Option<i32>,
+    #[serde(default)]
+    pub waso_min: Option<i32>,
+    #[serde(default)]
+    pub efficiency: Option<f64>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub starred: bool,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub aids: Vec<String>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub external_refs: Vec<ExternalRef>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
+
+#[doc = r#"List item projection for sleep summaries and sessions.
+
+Used by GET /api/sleep/recent and GET /api/sleep/range. The recent endpoint
+queries `v_daily_sleep`, while the range endpoint returns per-session rows from
+`sleep_sessions` and `sleep_metrics`. Both map the wake date to `date` via
+`AS date` to align with the existing field name.
+`duration_min` is nullable (computed on insert/update; may be NULL for legacy rows).
+
+Fields mirror the selected columns:
+- id
+- date (wake date)
+- bed_time
+- wake_time
+- latency_min
+- awakenings
+- quality
+- duration_min (nullable)
+- wake_feeling (nullable)
+- sleep_inertia_min (nullable)
+- waso_min (nullable; summed per day in `v_daily_sleep`)
+- efficiency (nullable; weighted by time in bed per day in `v_daily_sleep`)
+
+`duration_hours` is not a column: handlers fill it from `duration_min` when the units
+preference is hours (see `sleep_api::i18n::duration_hours`); it is omitted otherwise.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SleepListItem {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub bed_time: NaiveTime,
+    pub wake_time: NaiveTime,
+    pub latency_min: i32,
+    pub awakenings: i32,
+    pub quality: i32,
+    pub duration_min: Option<i32>,
+    pub wake_feeling: Option<i32>,
+    pub sleep_inertia_min: Option<i32>,
+    #[serde(default)]
+    pub waso_min: Option<i32>,
+    #[serde(default)]
+    pub efficiency: Option<f64>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_hours: Option<f64>,
+}
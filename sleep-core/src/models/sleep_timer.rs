@@ -0,0 +1,56 @@
+use crate::models::Quality;
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"The running sleep timer, as returned by `GET /api/sleep/active` and
+`POST /api/sleep/start`.
+
+- `started_at`: UTC instant `POST /api/sleep/start` was called.
+- `bed_time`: `started_at` as a local clock time in the configured timezone; the session's
+  `bed_time` once stopped.
+- `elapsed_min`: whole minutes since `started_at`.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ActiveSleep {
+    pub started_at: NaiveDateTime,
+    pub bed_time: NaiveTime,
+    pub elapsed_min: i64,
+}
+
+#[doc = r#"Optional request body of `POST /api/sleep/stop`: the fields of [`SleepInput`] that
+cannot be measured by the timer.
+
+Omitted fields default to `latency_min` 0, `awakenings` 0 and `quality` 3; they can be
+corrected later with `PATCH /api/sleep/{id}`.
+
+# Example
+
+```rust
+# use sleep_core::models::{Quality, SleepTimerStop};
+let stop = SleepTimerStop { quality: Some(Quality(4)), ..Default::default() };
+assert_eq!(stop.quality(), Quality(4));
+assert_eq!(SleepTimerStop::default().quality(), Quality(3));
+```
+
+[`SleepInput`]: crate::models::SleepInput
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SleepTimerStop {
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 180)))]
+    pub latency_min: Option<i32>,
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0, max = 10)))]
+    pub awakenings: Option<i32>,
+    #[serde(default)]
+    pub quality: Option<Quality>,
+}
+
+impl SleepTimerStop {
+    #[doc = r#"The quality to record, defaulting to 3."#]
+    pub fn quality(&self) -> Quality {
+        self.quality.unwrap_or(Quality(3))
+    }
+}
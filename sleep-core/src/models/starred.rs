@@ -0,0 +1,17 @@
+use crate::models::{Note, SleepListItem};
+use serde::{Deserialize, Serialize};
+
+#[doc = r#"Starred records, as returned by `GET /api/starred`.
+
+Stars mark remarkable nights (best or worst, experiment baselines) so comparison views can
+pull them up without searching. Both lists are newest first.
+
+- `sleep`: starred sleep sessions (one row per session, `date` is the wake date).
+- `notes`: starred notes.
+"#]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Starred {
+    pub sleep: Vec<SleepListItem>,
+    pub notes: Vec<Note>,
+}
@@ -0,0 +1,62 @@
+#![doc = r#"Free-form tags on sleep sessions and notes.
+
+Tags (`caffeine`, `travel`, `sick`, ...) label the circumstances of a night so lists can be
+filtered by them (`GET /api/sleep/range?tag=travel`) and trends can compare nights with and
+without a tag (`GET /api/trends/tags`). Unlike sleep aids they are not a fixed vocabulary and
+apply to notes as well as sessions.
+
+Names are trimmed and lowercased; an entry holds at most 10 distinct tags of up to 32
+characters each.
+"#]
+
+use crate::domain::DomainError;
+
+/// Most tags one session or note may carry.
+pub(crate) const MAX_TAGS: usize = 10;
+/// Longest tag name, in characters.
+pub(crate) const MAX_TAG_LEN: usize = 32;
+
+#[doc = r#"Validate a tag list as given by the client.
+
+- at most 10 tags
+- each non-blank and at most 32 characters once trimmed
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+pub fn validate_tags(tags: &[String]) -> Result<(), DomainError> {
+    if tags.len() > MAX_TAGS {
+        return Err(DomainError::InvalidInput(format!(
+            "at most {MAX_TAGS} tags are allowed"
+        )));
+    }
+    if tags
+        .iter()
+        .any(|t| t.trim().is_empty() || t.trim().chars().count() > MAX_TAG_LEN)
+    {
+        return Err(DomainError::InvalidInput(format!(
+            "tags must be 1-{MAX_TAG_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+#[doc = r#"Return `tags` trimmed, lowercased, deduplicated, and sorted, as stored.
+
+# Example
+
+```rust
+# use sleep_core::models::tag::normalize_tags;
+let tags = normalize_tags(&[" Travel".into(), "caffeine".into(), "travel".into()]);
+assert_eq!(tags, vec!["caffeine".to_string(), "travel".to_string()]);
+```
+"#]
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_lowercase()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
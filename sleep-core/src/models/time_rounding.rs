@@ -0,0 +1,111 @@
+use crate::domain::DomainError;
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+/// Steps the preference may use, in minutes.
+const ALLOWED_STEP_MIN: [u32; 4] = [1, 5, 10, 15];
+
+#[doc = r#"Granularity of bed and wake times in trends and exports, saved with
+`POST /api/settings/time-rounding`.
+
+Stored sessions keep their exact times (device imports carry seconds); only what trends
+and `GET /api/export` show and aggregate is rounded to the nearest `step_min` minutes
+(one of 1, 5, 10, 15). The default of 1 keeps times as stored.
+
+# Example
+
+```rust
+# use chrono::NaiveTime;
+# use sleep_core::models::TimeRounding;
+let rounding = TimeRounding { step_min: 10 };
+let t = NaiveTime::from_hms_opt(23, 54, 31).unwrap();
+assert_eq!(rounding.round(t), NaiveTime::from_hms_opt(23, 50, 0).unwrap());
+assert_eq!(TimeRounding::default().round(t), t);
+```
+"#]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TimeRounding {
+    pub step_min: u32,
+}
+
+impl Default for TimeRounding {
+    fn default() -> Self {
+        TimeRounding { step_min: 1 }
+    }
+}
+
+impl TimeRounding {
+    #[doc = r#"Validate the preference.
+
+- `step_min` must be 1, 5, 10 or 15
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] when a rule is violated.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !ALLOWED_STEP_MIN.contains(&self.step_min) {
+            return Err(DomainError::InvalidInput(
+                "step_min must be one of 1, 5, 10, 15".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether times are kept as stored.
+    pub fn is_exact(&self) -> bool {
+        self.step_min <= 1
+    }
+
+    #[doc = r#"Round `t` to the nearest step; times rounding up past 23:59 wrap to midnight.
+
+With the default step of 1, `t` is returned unchanged (seconds included)."#]
+    pub fn round(&self, t: NaiveTime) -> NaiveTime {
+        if self.is_exact() {
+            return t;
+        }
+        round_time(t, self.step_min)
+    }
+}
+
+/// Round `t` to the nearest multiple of `step_min` minutes, wrapping at midnight.
+pub(crate) fn round_time(t: NaiveTime, step_min: u32) -> NaiveTime {
+    let step = step_min.max(1) * 60;
+    let secs = t.num_seconds_from_midnight();
+    let rounded = (secs + step / 2) / step * step % 86_400;
+    NaiveTime::from_num_seconds_from_midnight_opt(rounded, 0).unwrap_or(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32, s: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, s).unwrap()
+    }
+
+    #[test]
+    fn rounds_to_nearest_step_and_wraps_at_midnight() {
+        let five = TimeRounding { step_min: 5 };
+        assert_eq!(five.round(time(6, 42, 29)), time(6, 40, 0));
+        assert_eq!(five.round(time(6, 42, 30)), time(6, 45, 0));
+        assert_eq!(five.round(time(23, 58, 0)), time(0, 0, 0));
+
+        let quarter = TimeRounding { step_min: 15 };
+        assert_eq!(quarter.round(time(7, 7, 29)), time(7, 0, 0));
+        assert_eq!(quarter.round(time(7, 7, 30)), time(7, 15, 0));
+    }
+
+    #[test]
+    fn validate_accepts_only_listed_steps() {
+        for step_min in ALLOWED_STEP_MIN {
+            assert!(TimeRounding { step_min }.validate().is_ok());
+        }
+        for step_min in [0, 2, 30, 60] {
+            assert!(TimeRounding { step_min }.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,350 @@
+#![doc = r#"Time handling utilities
+
+Provides DST-aware resolution and helpers for computing sleep durations
+using "wake-date" semantics. See [`compute_duration_min`].
+
+Current time is read through a [`Clock`] (the server holds one in its `AppState`), never
+`Utc::now()` directly, so tests and the demo instance can freeze it (`FROZEN_TIME`).
+
+[`compute_duration_min`]: crate::time::compute_duration_min
+"#]
+
+use crate::domain::DomainError;
+use chrono::{
+    DateTime, Duration as ChronoDuration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use std::sync::Arc;
+
+#[doc = r#"Source of the current time.
+
+Implementations only provide [`now_utc`](Clock::now_utc); [`today_in_tz`](Clock::today_in_tz)
+derives the local date from it.
+
+# Example
+
+```rust
+# use sleep_core::time::{Clock, FixedClock};
+# use chrono::{NaiveDate, TimeZone, Utc};
+let clock = FixedClock(Utc.with_ymd_and_hms(2025, 6, 1, 20, 0, 0).unwrap());
+// 05:00 the next morning in Tokyo
+assert_eq!(
+    clock.today_in_tz(chrono_tz::Asia::Tokyo),
+    NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()
+);
+```
+"#]
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn now_utc(&self) -> DateTime<Utc>;
+
+    fn today_in_tz(&self, tz: Tz) -> NaiveDate {
+        self.now_utc().with_timezone(&tz).date_naive()
+    }
+}
+
+/// Shared clock handle stored in application state.
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Clone, Copy, Default)]
+#[doc = r#"The system clock."#]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[doc = r#"A clock stopped at a fixed instant (tests and the frozen-time demo mode)."#]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Maximum minutes to scan forward to bridge DST "spring forward" gaps
+const MAX_DST_GAP_MINUTES: usize = 3 * 60;
+
+#[derive(Copy, Clone, Debug)]
+enum AmbiguousTimeChoice {
+    Earliest,
+    Latest,
+}
+
+/// Resolve a local naive datetime in a timezone to a concrete instant.
+///
+/// Handling of DST gaps/overlaps:
+/// - Ambiguous: pick the earliest or latest instant based on `choice`.
+/// - Non-existent (spring-forward gap): advance in 1-minute steps until valid (up to MAX_DST_GAP_MINUTES).
+///
+/// Fallback: If no valid local time can be resolved, falls back to interpreting the naive datetime as UTC with a warning. This may affect duration calculations across DST transitions.
+fn resolve_local(tz: Tz, ndt: NaiveDateTime, choice: AmbiguousTimeChoice) -> DateTime<Tz> {
+    match tz.from_local_datetime(&ndt) {
+        LocalResult::Single(dt) => dt,
+        LocalResult::Ambiguous(earliest, latest) => match choice {
+            AmbiguousTimeChoice::Earliest => earliest,
+            AmbiguousTimeChoice::Latest => latest,
+        },
+        LocalResult::None => {
+            // advance forward until it becomes valid
+            let mut cur = ndt;
+            for _ in 0..MAX_DST_GAP_MINUTES {
+                match tz.from_local_datetime(&cur) {
+                    LocalResult::Single(dt) => return dt,
+                    LocalResult::Ambiguous(earliest, latest) => {
+                        return match choice {
+                            AmbiguousTimeChoice::Earliest => earliest,
+                            AmbiguousTimeChoice::Latest => latest,
+                        };
+                    }
+                    LocalResult::None => {
+                        cur += ChronoDuration::minutes(1);
+                    }
+                }
+            }
+            // Fallback: interpret as UTC wall time then project to tz (best-effort)
+            tracing::warn!(
+                "resolve_local fallback: projecting naive datetime as UTC; tz={:?}, ndt={}",
+                tz,
+                ndt
+            );
+            tz.from_utc_datetime(&ndt)
+        }
+    }
+}
+
+#[doc = r#"Compute duration minutes using wake-date semantics in the provided timezone.
+
+Given a target `wake_date`, `bed_time` and `wake_time`, the bed datetime may belong to the
+previous calendar day when `bed_time > wake_time` (crossing midnight).
+
+DST handling:
+- Ambiguous local times (fall back) choose the earliest instant for bed times and the latest instant for wake times.
+- Non-existent times (spring forward gap) advance minute-by-minute until valid.
+
+# Example
+
+```rust
+# use sleep_core::domain::DomainError;
+# use chrono::{NaiveDate, NaiveTime};
+# use chrono_tz::Asia::Tokyo;
+# fn main() -> Result<(), DomainError> {
+// Cross-midnight: bed 23:00, wake 07:00 next day
+let mins = sleep_core::time::compute_duration_min(
+    NaiveDate::from_ymd_opt(2025, 6, 1).ok_or_else(|| DomainError::InvalidInput("invalid date".into()))?,
+    NaiveTime::from_hms_opt(23, 0, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
+    NaiveTime::from_hms_opt(7, 0, 0).ok_or_else(|| DomainError::InvalidInput("invalid time".into()))?,
+    Tokyo,
+)?;
+assert_eq!(mins, 8 * 60);
+# Ok(()) }
+```
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] if the computed duration is non-positive,
+exceeds `i32::MAX`, or if the computed bed date would underflow.
+
+[`DomainError::InvalidInput`]: crate::domain::DomainError::InvalidInput
+"#]
+pub fn compute_duration_min(
+    wake_date: NaiveDate,
+    bed_time: NaiveTime,
+    wake_time: NaiveTime,
+    tz: Tz,
+) -> Result<i32, DomainError> {
+    let (bed_ndt, wake_ndt) = sleep_window_bounds(wake_date, bed_time, wake_time)?;
+
+    let bed_local = resolve_local(tz, bed_ndt, AmbiguousTimeChoice::Earliest);
+    let wake_local = resolve_local(tz, wake_ndt, AmbiguousTimeChoice::Latest);
+
+    let bed_utc = bed_local.with_timezone(&Utc);
+    let wake_utc = wake_local.with_timezone(&Utc);
+
+    let mins = (wake_utc - bed_utc).num_minutes();
+    if mins <= 0 {
+        return Err(DomainError::InvalidInput(
+            "Duration must be positive".into(),
+        ));
+    }
+    if mins > i32::MAX as i64 {
+        return Err(DomainError::InvalidInput("Duration too large".into()));
+    }
+    Ok(mins as i32)
+}
+
+#[doc = r#"Sleep efficiency: the share of time in bed spent asleep, rounded to 3 decimals.
+
+`in_bed_min` is the session's [`compute_duration_min`]; the time asleep is what remains after
+`latency_min` and `waso_min` (wake after sleep onset, `None` counts as 0), floored at 0.
+Returns `None` when `in_bed_min` is not positive.
+
+# Example
+
+```rust
+assert_eq!(sleep_core::time::sleep_efficiency(480, 20, Some(40)), Some(0.875));
+assert_eq!(sleep_core::time::sleep_efficiency(480, 600, None), Some(0.0));
+```
+"#]
+pub fn sleep_efficiency(in_bed_min: i32, latency_min: i32, waso_min: Option<i32>) -> Option<f64> {
+    if in_bed_min <= 0 {
+        return None;
+    }
+    let asleep =
+        (i64::from(in_bed_min) - i64::from(latency_min) - i64::from(waso_min.unwrap_or(0))).max(0);
+    Some((asleep as f64 / f64::from(in_bed_min) * 1000.0).round() / 1000.0)
+}
+
+#[doc = r#"Return the local bed/wake datetime bounds for a sleep session.
+
+Uses wake-date semantics: if `bed_time > wake_time`, the bed datetime is
+considered to be on the previous calendar day relative to `wake_date`.
+
+# Errors
+
+Returns [`DomainError::InvalidInput`] if the computed bed date would underflow.
+"#]
+pub fn sleep_window_bounds(
+    wake_date: NaiveDate,
+    bed_time: NaiveTime,
+    wake_time: NaiveTime,
+) -> Result<(NaiveDateTime, NaiveDateTime), DomainError> {
+    let bed_date = if bed_time > wake_time {
+        wake_date
+            .pred_opt()
+            .ok_or_else(|| DomainError::InvalidInput("invalid date (underflow)".into()))?
+    } else {
+        wake_date
+    };
+
+    Ok((
+        NaiveDateTime::new(bed_date, bed_time),
+        NaiveDateTime::new(wake_date, wake_time),
+    ))
+}
+
+/// Minutes a submitted wake time may lie ahead of the local clock and still count as today
+/// (clients whose clocks run slightly fast).
+pub const WAKE_INFERENCE_SKEW_MIN: i64 = 15;
+
+#[doc = r#"Infer the wake date of a session submitted at local time `now` with `wake_time`.
+
+The wake date is the most recent day on which `wake_time` has already occurred: today when
+`wake_time` is not later than `now` (allowing [`WAKE_INFERENCE_SKEW_MIN`] of clock skew),
+otherwise yesterday. A late-morning log of a 07:00 wake is today's; a log of the same wake
+shortly after midnight belongs to the morning that just ended.
+
+# Example
+
+```rust
+# use sleep_core::time::infer_wake_date;
+# use chrono::{NaiveDate, NaiveTime};
+let day = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+let wake = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+let late_morning = day.and_hms_opt(11, 30, 0).unwrap();
+assert_eq!(infer_wake_date(late_morning, wake), day);
+let after_midnight = day.and_hms_opt(0, 20, 0).unwrap();
+assert_eq!(infer_wake_date(after_midnight, wake), day.pred_opt().unwrap());
+let slightly_early = day.and_hms_opt(6, 50, 0).unwrap();
+assert_eq!(infer_wake_date(slightly_early, wake), day);
+```
+"#]
+pub fn infer_wake_date(now: NaiveDateTime, wake_time: NaiveTime) -> NaiveDate {
+    let today = now.date();
+    if NaiveDateTime::new(today, wake_time)
+        <= now + ChronoDuration::minutes(WAKE_INFERENCE_SKEW_MIN)
+    {
+        today
+    } else {
+        today.pred_opt().unwrap_or(today)
+    }
+}
+
+#[doc = r#"Parse a wall-clock time in any of the accepted input formats.
+
+Accepted (surrounding whitespace ignored):
+- `HH:MM` / `H:MM` (24-hour)
+- `HH:MM:SS` (24-hour)
+- `h:mm AM/PM` or `h:mm:ss AM/PM` (12-hour; case-insensitive, space optional)
+
+# Example
+
+```rust
+# use sleep_core::time::parse_flexible_time;
+# use chrono::NaiveTime;
+let t = NaiveTime::from_hms_opt(23, 5, 0).unwrap();
+assert_eq!(parse_flexible_time("23:05").unwrap(), t);
+assert_eq!(parse_flexible_time("23:05:00").unwrap(), t);
+assert_eq!(parse_flexible_time("11:05 PM").unwrap(), t);
+assert_eq!(parse_flexible_time("12:30am").unwrap(), NaiveTime::from_hms_opt(0, 30, 0).unwrap());
+assert!(parse_flexible_time("24:00").is_err());
+```
+
+# Errors
+
+Returns a message naming the offending value and the accepted formats.
+"#]
+pub fn parse_flexible_time(input: &str) -> Result<NaiveTime, String> {
+    let invalid = || format!("invalid time {input:?}: expected HH:MM, HH:MM:SS, or h:mm AM/PM");
+    let s = input.trim();
+    let lower = s.to_ascii_lowercase();
+    let (clock, meridiem) = match lower.strip_suffix("am") {
+        Some(rest) => (rest.trim_end(), Some(false)),
+        None => match lower.strip_suffix("pm") {
+            Some(rest) => (rest.trim_end(), Some(true)),
+            None => (lower.as_str(), None),
+        },
+    };
+
+    let parts: Vec<&str> = clock.split(':').collect();
+    let field = |p: &str, max_len: usize| -> Option<u32> {
+        (!p.is_empty() && p.len() <= max_len && p.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| p.parse().ok())
+            .flatten()
+    };
+    let (hour, minute, second) = match parts.as_slice() {
+        [h, m] if m.len() == 2 => (field(h, 2), field(m, 2), Some(0)),
+        [h, m, sec] if m.len() == 2 && sec.len() == 2 => (field(h, 2), field(m, 2), field(sec, 2)),
+        _ => return Err(invalid()),
+    };
+    let (Some(hour), Some(minute), Some(second)) = (hour, minute, second) else {
+        return Err(invalid());
+    };
+    let hour = match meridiem {
+        None => hour,
+        Some(_) if !(1..=12).contains(&hour) => return Err(invalid()),
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second).ok_or_else(invalid)
+}
+
+#[doc = r#"Serde adapter accepting [`parse_flexible_time`] formats for a `NaiveTime` field.
+
+Use with `#[serde(deserialize_with = "crate::time::flexible_time")]`. Serialization is
+unchanged (`HH:MM:SS`). JSON extractors prefix the error with the field path, e.g.
+`bed_time: invalid time "7pm": expected HH:MM, HH:MM:SS, or h:mm AM/PM`.
+"#]
+pub fn flexible_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_flexible_time(&raw).map_err(serde::de::Error::custom)
+}
+
+#[doc = r#"Optional variant of [`flexible_time`]; `null` deserializes to `None`.
+
+Use with `#[serde(default, deserialize_with = "crate::time::flexible_time_opt")]`."#]
+pub fn flexible_time_opt<'de, D>(deserializer: D) -> Result<Option<NaiveTime>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    <Option<String> as serde::Deserialize>::deserialize(deserializer)?
+        .map(|raw| parse_flexible_time(&raw).map_err(serde::de::Error::custom))
+        .transpose()
+}
@@ -0,0 +1,1742 @@
+// Generated by `sleepctl gen-types` from the sleep-api Rust models. Do not edit.
+
+export interface ActionRecommendation {
+  action_key: string;
+  confidence: Confidence;
+  rationale: string;
+  status: RecommendationStatus;
+  suppression_reasons: string[];
+}
+
+/** The running sleep timer, as returned by `GET /api/sleep/active` and */
+export interface ActiveSleep {
+  bed_time: string;
+  elapsed_min: number;
+  started_at: string;
+}
+
+/** With-vs-without comparison for one sleep aid. */
+export interface AidEffect {
+  aid: string;
+  duration_diff_min?: number | null;
+  inference: Record<string, Inference>;
+  latency_diff_min?: number | null;
+  quality_diff?: number | null;
+  sufficient_sample: boolean;
+  wake_feeling_diff?: number | null;
+  with_aid: NightGroupStats;
+  without_aid: NightGroupStats;
+}
+
+/** Sleep aid effectiveness response. */
+export interface AidsResponse {
+  aids: AidEffect[];
+  from: string;
+  min_samples: number;
+  to: string;
+}
+
+/** Which side of the threshold triggers an [`AlertRule`] (strictly below or above). */
+export type AlertComparison = "below" | "above";
+
+/** How an [`AlertRule`] combines the last `nights` wake dates. */
+export type AlertCondition = "average" | "consecutive";
+
+/** A fired alert, as listed by `GET /api/alerts/history`. */
+export interface AlertEvent {
+  as_of: string;
+  delivered_via?: string | null;
+  fired_at: string;
+  id: number;
+  message: string;
+  rule_id: string;
+  value: number;
+}
+
+/** Query parameters for `GET /api/alerts/history`. */
+export interface AlertHistoryQuery {
+  limit?: number | null;
+}
+
+/** Nightly value an [`AlertRule`] watches, as aggregated per wake date by `v_daily_sleep`. */
+export type AlertMetric = "duration_min" | "latency_min" | "awakenings" | "quality" | "wake_feeling";
+
+/** A user-defined alert, e.g. "7-day average duration below 6h" or "latency above 45 */
+export interface AlertRule {
+  comparison: AlertComparison;
+  condition: AlertCondition;
+  enabled?: boolean;
+  id: string;
+  metric: AlertMetric;
+  nights: number;
+  threshold: number;
+}
+
+/** Configured alert rules (`GET/PUT /api/settings/alerts`). Empty by default. */
+export interface AlertRules {
+  rules: AlertRule[];
+}
+
+/** What an API token may do. Serializes as `"read" | "write:sleep" | "admin"`. */
+export type ApiScope = "read" | "write:sleep" | "admin";
+
+/** An API token as listed by `GET /api/tokens` (the secret is never listed). */
+export interface ApiToken {
+  created_at: string;
+  expired?: boolean;
+  expires_at: string;
+  id: number;
+  last_used_at?: string | null;
+  name: string;
+  scope: string;
+}
+
+/** Request body of `POST /api/tokens`. */
+export interface ApiTokenInput {
+  expires_in_days?: number | null;
+  name: string;
+  scope: ApiScope;
+}
+
+/** An uploaded attachment, as returned by `POST /api/attachments` and */
+export interface Attachment {
+  content_type: string;
+  created_at: string;
+  date: string;
+  duration_ms?: number | null;
+  filename: string;
+  has_thumb: boolean;
+  height?: number | null;
+  id: number;
+  sha256: string;
+  size_bytes: number;
+  width?: number | null;
+}
+
+/** Query of `POST /api/attachments`; the request body is the file itself and its */
+export interface AttachmentUpload {
+  date: string;
+  filename: string;
+}
+
+/** Who sees data leaving the authenticated API, each with its own [`RedactionPolicy`]. */
+export type Audience = "share" | "public" | "export";
+
+/** One row of the append-only audit log. */
+export interface AuditEntry {
+  action: string;
+  entity: string;
+  entity_id?: number | null;
+  id: number;
+  note?: string | null;
+  reason?: string | null;
+  recorded_at: string;
+}
+
+/** One page of audit log entries, newest first. */
+export interface AuditPage {
+  entries: AuditEntry[];
+  next_cursor?: number | null;
+}
+
+/** Filters and cursor for listing the audit log (`GET /api/admin/audit`). */
+export interface AuditQuery {
+  action?: string | null;
+  cursor?: number | null;
+  entity?: string | null;
+  from?: string | null;
+  limit?: number | null;
+  to?: string | null;
+}
+
+/** Why a destructive operation was performed, recorded in the audit log. */
+export interface AuditReason {
+  note?: string | null;
+  reason?: string | null;
+}
+
+/** Averages over the logged nights among the last `days` wake dates. */
+export interface AverageWindow {
+  avg_duration_min?: number | null;
+  avg_quality?: number | null;
+  days: number;
+  nights_logged: number;
+}
+
+/** Awakenings and quality averaged over a group of nights. */
+export interface AwakeningsGroup {
+  avg_awakenings?: number | null;
+  avg_quality?: number | null;
+  nights: number;
+}
+
+/** Awakenings analysis split by external disturbances. */
+export interface AwakeningsResponse {
+  by_type: DisturbanceTypeAwakenings[];
+  disturbed: AwakeningsGroup;
+  from: string;
+  to: string;
+  undisturbed: AwakeningsGroup;
+}
+
+/** Countdown to the target bedtime with sleep debt and a short recommendation. */
+export interface BedtimeStatus {
+  bedtime_at: string;
+  debt_window_days: number;
+  local_time: string;
+  minutes_until_bedtime: number;
+  nights_logged: number;
+  recommendation: string;
+  sleep_debt_min: number;
+  target_bedtime: string;
+  target_duration_min: number;
+  timezone: string;
+}
+
+/** Stored body metrics reading. */
+export interface BodyMetric {
+  body_fat_pct?: number | null;
+  date: string;
+  id: number;
+  source: string;
+  weight_kg?: number | null;
+}
+
+/** User-provided body metrics reading for a date. */
+export interface BodyMetricInput {
+  body_fat_pct?: number | null;
+  date: string;
+  weight_kg?: number | null;
+}
+
+/** Body metrics versus sleep, over nights with a body reading dated their wake date. */
+export interface BodyMetricsCorrelation {
+  avg_weight_kg?: number | null;
+  body_fat_quality: Correlation;
+  nights: number;
+  weight_duration_min: Correlation;
+  weight_quality: Correlation;
+}
+
+export interface BodyMetricsImportSummary {
+  imported: number;
+  source: string;
+}
+
+/** Which spreadsheet column feeds each sleep field, for `POST /api/import/*`. */
+export interface ColumnMapping {
+  awakenings?: string | null;
+  bed_time: string;
+  date: string;
+  date_format?: string | null;
+  delimiter?: string | null;
+  latency_min?: string | null;
+  quality?: string | null;
+  wake_feeling?: string | null;
+  wake_time: string;
+}
+
+/** Query parameters for `GET /api/trends/compare`. */
+export interface CompareQuery {
+  anchor: string;
+  period: string;
+}
+
+/** Result of [`compare_export`]: how the live sessions differ from an export's records. */
+export interface CompareReport {
+  added: number;
+  changed: ManifestRecord[];
+  created_at: string;
+  missing: ManifestRecord[];
+  signature: SignatureStatus;
+  unchanged: number;
+}
+
+/** Period comparison response. */
+export interface CompareResponse {
+  current: PeriodStats;
+  deltas: MetricDelta[];
+  period: string;
+  previous: PeriodStats;
+  warnings: string[];
+  year_ago?: PeriodStats | null;
+}
+
+/** Per-day completeness with range totals. */
+export interface CompletenessResponse {
+  complete_days: number;
+  completeness_pct: number;
+  current_streak: number;
+  days: DayCompleteness[];
+  from: string;
+  longest_streak: number;
+  to: string;
+}
+
+export type Confidence = "high" | "medium" | "low";
+
+/** A metric placed in population context with a human-readable summary. */
+export interface ContextMetric {
+  message: string;
+  population_percentile: number;
+  position: RangePosition;
+  typical_high: number;
+  typical_low: number;
+  your_avg: number;
+}
+
+/** Population context for the average duration and latency over a range. */
+export interface ContextResponse {
+  age_bracket: string;
+  duration?: ContextMetric | null;
+  from: string;
+  latency?: ContextMetric | null;
+  nights: number;
+  to: string;
+}
+
+/** Pearson correlation between two paired series. */
+export interface Correlation {
+  caveats: string[];
+  n: number;
+  p_value?: number | null;
+  r?: number | null;
+  strength?: string | null;
+}
+
+/** Exercise intensity versus sleep. */
+export interface CorrelationResponse {
+  body_metrics?: BodyMetricsCorrelation | null;
+  duration_min: Correlation;
+  from: string;
+  groups: IntensityGroup[];
+  mode: string;
+  nights: number;
+  quality: Correlation;
+  to: string;
+  wake_feeling: Correlation;
+}
+
+/** Response of `POST /api/tokens`. */
+export interface CreatedApiToken {
+  secret: string;
+  token: ApiToken;
+}
+
+/** Response of `POST /api/share-links`. */
+export interface CreatedShareLink {
+  link: ShareLink;
+  secret: string;
+}
+
+/** Response of `GET /api/admin/csp-reports`: reports received since `since` (UTC), */
+export interface CspReportSummary {
+  groups: CspViolationGroup[];
+  since: string;
+  total: number;
+}
+
+/** Violations of one directive by one blocked URI (and disposition). */
+export interface CspViolationGroup {
+  blocked_uri: string;
+  count: number;
+  disposition: string;
+  document_uri: string;
+  first_seen: string;
+  last_seen: string;
+  source_file?: string | null;
+  violated_directive: string;
+}
+
+/** One wake date where the stored row differs from the computed one. */
+export interface DailySleepDiscrepancy {
+  computed?: DailySleepRow | null;
+  fields: string[];
+  kind: DiscrepancyKind;
+  stored?: DailySleepRow | null;
+  wake_date: string;
+}
+
+/** One wake date of `daily_sleep`, as stored or as computed from the sessions. */
+export interface DailySleepRow {
+  awakenings?: number | null;
+  bed_time: string;
+  duration_min?: number | null;
+  efficiency?: number | null;
+  id: number;
+  latency_min?: number | null;
+  longest_segment_min?: number | null;
+  quality?: number | null;
+  session_count: number;
+  sleep_inertia_min?: number | null;
+  wake_date: string;
+  wake_feeling?: number | null;
+  wake_time: string;
+  waso_min?: number | null;
+}
+
+/** Outcome of [`verify_daily_sleep`] over the wake dates `from..=to`. */
+export interface DailySleepVerification {
+  checked_at: string;
+  discrepancies: DailySleepDiscrepancy[];
+  from: string;
+  nights: number;
+  ok: boolean;
+  to: string;
+}
+
+/** Response of `GET /api/dashboard`. */
+export interface Dashboard {
+  as_of: string;
+  days: number;
+  debt_window_days: number;
+  last_night?: LastNight | null;
+  local_time: string;
+  period: AverageWindow;
+  reminders: Reminder[];
+  sleep_debt_min: number;
+  streaks: Streaks;
+  top_insight?: ActionRecommendation | null;
+  week: AverageWindow;
+}
+
+/** One day's exercise, in `GET /api/exercise/intensity`. */
+export interface DateIntensity {
+  date: string;
+  intensity: string;
+  sessions: number;
+  total_min: number;
+}
+
+/** When a logical day starts, for users whose day does not end at midnight. */
+export interface DayBoundary {
+  day_start: string;
+}
+
+/** Which data kinds are present on one day. */
+export interface DayCompleteness {
+  check_in: boolean;
+  complete: boolean;
+  date: string;
+  exercise: boolean;
+  factors: boolean;
+  note: boolean;
+  score_pct: number;
+  sleep: boolean;
+}
+
+export interface DayTypeTimingBaselineMetric {
+  eligible: boolean;
+  midpoint_stable_across_windows: boolean;
+  recent_14_day_diverges_from_baseline: boolean;
+  weekday_bed_median_min?: number | null;
+  weekday_sample_days: number;
+  weekday_wake_median_min?: number | null;
+  weekend_bed_median_min?: number | null;
+  weekend_sample_days: number;
+  weekend_wake_median_min?: number | null;
+}
+
+/** Decomposition of one daily metric into trend, weekly seasonality, and residual. */
+export interface DecomposeResponse {
+  from: string;
+  metric: string;
+  points: DecomposedPoint[];
+  to: string;
+  trend_slope_per_week?: number | null;
+  weekday_effects: WeekdayEffect[];
+}
+
+/** One day of a decomposed series. Components are `None` where they cannot be computed. */
+export interface DecomposedPoint {
+  date: string;
+  residual?: number | null;
+  seasonal?: number | null;
+  trend?: number | null;
+  value?: number | null;
+}
+
+/** Usage of one legacy endpoint; `calls` is 0 and the dates `None` when never called. */
+export interface DeprecatedEndpointUsage {
+  calls: number;
+  endpoint: string;
+  first_seen?: string | null;
+  last_seen?: string | null;
+  replacement?: string | null;
+}
+
+/** Response of `GET /api/admin/deprecations`: every tracked endpoint, in */
+export interface DeprecationReport {
+  endpoints: DeprecatedEndpointUsage[];
+}
+
+/** How a wake date of `daily_sleep` disagrees with the sessions. */
+export type DiscrepancyKind = "missing" | "orphaned" | "mismatch";
+
+/** Stored disturbance event. */
+export interface Disturbance {
+  date: string;
+  duration_min: number;
+  id: number;
+  time: string;
+  type: string;
+}
+
+/** User-provided disturbance event. */
+export interface DisturbanceInput {
+  date: string;
+  duration_min: number;
+  time: string;
+  type: DisturbanceKind;
+}
+
+/** Source of an external sleep disturbance. */
+export type DisturbanceKind = "noise" | "partner" | "pet" | "child";
+
+/** Awakenings on nights with at least one disturbance of a given type. */
+export interface DisturbanceTypeAwakenings {
+  avg_awakenings?: number | null;
+  events: number;
+  nights: number;
+  total_minutes: number;
+  type: string;
+}
+
+/** A committed change to user data or settings. */
+export type DomainEvent = {
+  date: string;
+  duration_min: number;
+  id: number;
+  type: "sleep_created";
+} | {
+  date: string;
+  duration_min: number;
+  id: number;
+  type: "sleep_updated";
+} | {
+  id: number;
+  type: "sleep_deleted";
+} | {
+  date: string;
+  id: number;
+  type: "exercise_created";
+} | {
+  date: string;
+  id: number;
+  type: "exercise_updated";
+} | {
+  id: number;
+  type: "exercise_deleted";
+} | {
+  date: string;
+  id: number;
+  type: "note_created";
+} | {
+  date: string;
+  id: number;
+  type: "note_updated";
+} | {
+  id: number;
+  type: "note_deleted";
+} | {
+  date: string;
+  id: number;
+  type: "body_metric_saved";
+} | {
+  id: number;
+  type: "body_metric_deleted";
+} | {
+  imported: number;
+  source: string;
+  type: "body_metrics_imported";
+} | {
+  date: string;
+  id: number;
+  type: "disturbance_saved";
+} | {
+  id: number;
+  type: "disturbance_deleted";
+} | {
+  id: number;
+  type: "experiment_saved";
+} | {
+  id: number;
+  type: "experiment_deleted";
+} | {
+  id: number;
+  type: "medication_saved";
+} | {
+  id: number;
+  type: "medication_deleted";
+} | {
+  date: string;
+  id: number;
+  type: "medication_event_saved";
+} | {
+  id: number;
+  type: "medication_event_deleted";
+} | {
+  date: string;
+  type: "routine_recorded";
+} | {
+  date: string;
+  type: "mood_recorded";
+} | {
+  entity: string;
+  id: number;
+  starred: boolean;
+  type: "star_changed";
+} | {
+  date: string;
+  id: number;
+  type: "attachment_created";
+} | {
+  id: number;
+  type: "api_token_created";
+} | {
+  id: number;
+  type: "api_token_revoked";
+} | {
+  id: number;
+  type: "share_link_created";
+} | {
+  id: number;
+  type: "share_link_revoked";
+} | {
+  id: number;
+  type: "device_forgotten";
+} | {
+  key: string;
+  type: "setting_changed";
+};
+
+export interface DurationBaselineMetric {
+  eligible: boolean;
+  iqr_min?: number | null;
+  p10_min?: number | null;
+  p50_min?: number | null;
+  p90_min?: number | null;
+  recent_out_of_range_incidence_pct?: number | null;
+  sample_days: number;
+}
+
+/** Aggregated duration statistics per bucket (`bucket` is a date or ISO week). */
+export interface DurationBucket {
+  avg_min: number;
+  bucket: string;
+  max_min: number;
+  min_min: number;
+  p25_min: number;
+  p75_min: number;
+  sd_min?: number | null;
+}
+
+/** Preferred unit for durations in report text and responses. */
+export type DurationUnit = "hours" | "minutes";
+
+/** Sleep efficiency and wake after sleep onset per bucket, always per wake date. */
+export interface EfficiencyBucket {
+  avg_efficiency: number;
+  avg_waso_min?: number | null;
+  bucket: string;
+  days: number;
+}
+
+/** Request body of `POST /api/account/email`. */
+export interface EmailChangeInput {
+  current_password: string;
+  new_email: string;
+}
+
+/** A stored exercise event, as exported by `GET /api/export`. */
+export interface ExerciseEvent {
+  date: string;
+  duration_min?: number | null;
+  id: number;
+  intensity: string;
+  start_time?: string | null;
+}
+
+/** User-provided input representing an exercise event. */
+export interface ExerciseInput {
+  date: string;
+  duration_min?: number | null;
+  hr_zones?: HrZoneMinutes | null;
+  intensity: Intensity;
+  start_time?: string | null;
+}
+
+/** Heart-rate zone minutes of one day's exercise, in `GET /api/exercise/zones`. */
+export interface ExerciseZoneDay {
+  date: string;
+  sessions: number;
+  zones: HrZoneMinutes;
+}
+
+/** Response of `GET /api/exercise/zones`: heart-rate zone minutes in [from, to]. */
+export interface ExerciseZones {
+  days: ExerciseZoneDay[];
+  from: string;
+  sessions: number;
+  to: string;
+  totals: HrZoneMinutes;
+}
+
+/** Stored experiment. */
+export interface Experiment {
+  description?: string | null;
+  end_date?: string | null;
+  id: number;
+  name: string;
+  start_date: string;
+}
+
+/** User-provided self-experiment period. */
+export interface ExperimentInput {
+  description?: string | null;
+  end_date?: string | null;
+  name: string;
+  start_date: string;
+}
+
+/** One metric compared between the experiment and its baseline. */
+export interface ExperimentMetricResult {
+  baseline: GroupSummary;
+  bootstrap_ci95_high?: number | null;
+  bootstrap_ci95_low?: number | null;
+  caveats: string[];
+  ci95_high?: number | null;
+  ci95_low?: number | null;
+  diff?: number | null;
+  during: GroupSummary;
+  effect_magnitude?: string | null;
+  effect_size?: number | null;
+  metric: string;
+  n_a: number;
+  n_b: number;
+  p_value?: number | null;
+}
+
+/** Response for `GET /api/experiments/{id}/results`. */
+export interface ExperimentResults {
+  baseline: string;
+  experiment: Experiment;
+  metrics: ExperimentMetricResult[];
+  period_from: string;
+  period_to: string;
+}
+
+/** An export directory found by [`list_exports`]: its name, manifest files, and whether */
+export interface ExportSummary {
+  created_at: string;
+  files: ManifestFile[];
+  name: string;
+  signed: boolean;
+}
+
+/** A link from a recorded entry to its id in an external service. */
+export interface ExternalRef {
+  external_id: string;
+  source: string;
+  url?: string | null;
+}
+
+/** On/off switches for optional subsystems. [`Default`] enables everything except */
+export interface Features {
+  integrations: Integrations;
+  telemetry: boolean;
+  trends_cache: boolean;
+  webhooks: boolean;
+}
+
+export interface FrictionBacklogProposal {
+  action_key: string;
+  auto_promoted: boolean;
+  confidence: FrictionProposalConfidence;
+  estimated_minutes_saved_per_week: number;
+  expected_benefit: string;
+  observed_evidence: FrictionProposalEvidence;
+  persistence_two_windows: boolean;
+  rank: number;
+  rollback_condition: string;
+}
+
+export interface FrictionBacklogResponse {
+  as_of: string;
+  current_window: FrictionBacklogWindow;
+  minimum_sample_met: boolean;
+  prior_window: FrictionBacklogWindow;
+  proposals: FrictionBacklogProposal[];
+  window_days: number;
+}
+
+export interface FrictionBacklogWindow {
+  from: string;
+  submit_count: number;
+  to: string;
+}
+
+export type FrictionProposalConfidence = "high" | "medium" | "low";
+
+export interface FrictionProposalEvidence {
+  current_avg_form_time_ms: number;
+  current_follow_up_failure_rate: number;
+  current_occurrences: number;
+  current_retry_avg: number;
+  current_submit_count: number;
+  prior_avg_form_time_ms: number;
+  prior_follow_up_failure_rate: number;
+  prior_occurrences: number;
+  prior_retry_avg: number;
+  prior_submit_count: number;
+}
+
+export interface FrictionTelemetryEvent {
+  error_kind?: string | null;
+  follow_up_failure: boolean;
+  form_time_ms: number;
+  id: number;
+  immediate_edit: boolean;
+  recorded_at: string;
+  retry_count: number;
+  /** Submissions this stored event stands for (N when 1 in N successes is sampled). */
+  sample_weight: number;
+}
+
+export interface FrictionTelemetryInput {
+  error_kind?: string | null;
+  follow_up_failure: boolean;
+  form_time_ms: number;
+  immediate_edit: boolean;
+  retry_count: number;
+}
+
+/** Friction metrics over a window. */
+export interface FrictionWindowAggregate {
+  avg_form_time_ms: number;
+  error_count: number;
+  error_rate: number;
+  follow_up_failure_count: number;
+  follow_up_failure_rate: number;
+  immediate_edit_count: number;
+  immediate_edit_rate: number;
+  median_form_time_ms: number;
+  recorded_count: number;
+  retries_avg: number;
+  retries_total: number;
+  submit_count: number;
+}
+
+/** Sample size, mean, and standard deviation of one group of nights. */
+export interface GroupSummary {
+  mean?: number | null;
+  n: number;
+  sd?: number | null;
+}
+
+/** One histogram bin covering `[start_min, end_min)`. */
+export interface HistogramBin {
+  count: number;
+  end_min: number;
+  label: string;
+  start_min: number;
+}
+
+/** Distribution of bed times, wake times, or durations over a range. */
+export interface HistogramResponse {
+  bin_min: number;
+  bins: HistogramBin[];
+  field: string;
+  from: string;
+  nights_logged: number;
+  to: string;
+}
+
+/** Minutes spent in each heart-rate zone, Z1 (easiest) to Z5 (maximal). */
+export interface HrZoneMinutes {
+  z1: number;
+  z2: number;
+  z3: number;
+  z4: number;
+  z5: number;
+}
+
+/** A problem with one CSV row (`row` counts the header as row 1) or, with `row: 0`, */
+export interface ImportIssue {
+  column?: string | null;
+  message: string;
+  row: number;
+}
+
+/** Inference for the difference of means `a - b`. */
+export interface Inference {
+  bootstrap_ci95_high?: number | null;
+  bootstrap_ci95_low?: number | null;
+  caveats: string[];
+  effect_magnitude?: string | null;
+  effect_size?: number | null;
+  n_a: number;
+  n_b: number;
+  p_value?: number | null;
+}
+
+/** Outcome of one webhook push; entries already recorded are counted as skipped. */
+export interface IngestSummary {
+  exercise_imported: number;
+  exercise_skipped: number;
+  sleep_imported: number;
+  sleep_skipped: number;
+  source: string;
+}
+
+/** Per-source switches for third-party imports and pushes. */
+export interface Integrations {
+  fitbit: boolean;
+  garmin: boolean;
+  health_auto_export: boolean;
+  strava: boolean;
+  tasker: boolean;
+  withings: boolean;
+}
+
+/** Which SQLite check to run. */
+export type IntegrityCheck = "quick" | "full";
+
+/** Outcome of an integrity check. */
+export interface IntegrityReport {
+  check: IntegrityCheck;
+  checked_at: string;
+  ok: boolean;
+  problems: string[];
+}
+
+/** Exercise intensity level: "none", "light", "hard", or a custom level configured in the intensity level settings. */
+export type Intensity = string;
+
+/** Averages over the nights paired with one exercise intensity. */
+export interface IntensityGroup {
+  avg_duration_min?: number | null;
+  avg_quality?: number | null;
+  avg_wake_feeling?: number | null;
+  inference: Record<string, Inference>;
+  intensity: string;
+  nights: number;
+  rank?: number | null;
+}
+
+/** Accepted intensity levels, lowest first. */
+export interface IntensityLevels {
+  levels: Intensity[];
+}
+
+/** Recorded execution of a background job. */
+export interface JobRun {
+  detail?: string | null;
+  finished_at?: string | null;
+  id: number;
+  job: string;
+  started_at: string;
+  status: string;
+}
+
+export interface JobsOverview {
+  jobs: string[];
+  runs: JobRun[];
+}
+
+/** A device that has logged in, as listed by `GET /api/account/devices`. */
+export interface KnownDevice {
+  current: boolean;
+  first_seen_at: string;
+  id: number;
+  label: string;
+  last_seen_at: string;
+  login_count: number;
+}
+
+/** The most recent logged night up to `as_of` (all sessions waking that day). */
+export interface LastNight {
+  bed_time: string;
+  duration_min: number;
+  quality?: number | null;
+  session_count: number;
+  wake_date: string;
+  wake_feeling?: number | null;
+  wake_time: string;
+}
+
+/** Median latency per bucket (computed via selection). */
+export interface LatencyBucket {
+  bucket: string;
+  median: number;
+}
+
+/** Supported response locale. */
+export type Locale = "en" | "ja";
+
+/** Contents of `manifest.json`. */
+export interface Manifest {
+  created_at: string;
+  files: ManifestFile[];
+  records?: ManifestRecord[];
+  signature?: string | null;
+  version: number;
+}
+
+/** One exported file: path relative to the export directory, size, and SHA-256 (hex). */
+export interface ManifestFile {
+  path: string;
+  sha256: string;
+  size: number;
+}
+
+/** One exported sleep session: its id, wake date, and the SHA-256 (hex) of the session */
+export interface ManifestRecord {
+  date: string;
+  id: number;
+  sha256: string;
+}
+
+/** A CSV row parsed into a sleep entry. */
+export interface MappedRow {
+  input: SleepInput;
+  row: number;
+}
+
+/** Body of `POST /api/import/mapping-preview` and `POST /api/import/with-mapping`. */
+export interface MappingImportRequest {
+  csv: string;
+  mapping: ColumnMapping;
+}
+
+/** Outcome of `POST /api/import/with-mapping`; rows overlapping recorded sleep are skipped. */
+export interface MappingImportSummary {
+  imported: number;
+  skipped: number;
+}
+
+/** Result of `POST /api/import/mapping-preview`. */
+export interface MappingPreview {
+  issues: ImportIssue[];
+  rows: MappedRow[];
+  total_rows: number;
+  valid_rows: number;
+}
+
+/** Stored medication. */
+export interface Medication {
+  dose?: string | null;
+  id: number;
+  name: string;
+  prescription: boolean;
+}
+
+/** Nights in a bucket on which a medication was logged. */
+export interface MedicationBucket {
+  bucket: string;
+  medication: string;
+  nights: number;
+}
+
+/** Stored medication intake, with the medication's `name`. */
+export interface MedicationEvent {
+  date: string;
+  dose?: string | null;
+  id: number;
+  medication_id: number;
+  name: string;
+  time?: string | null;
+}
+
+/** User-provided intake of a medication. */
+export interface MedicationEventInput {
+  date: string;
+  dose?: string | null;
+  medication_id: number;
+  time?: string | null;
+}
+
+/** User-provided medication (melatonin, a prescription sleep aid, ...). */
+export interface MedicationInput {
+  dose?: string | null;
+  name: string;
+  prescription?: boolean;
+}
+
+/** Change of one metric: current minus previous period, and current minus a year earlier. */
+export interface MetricDelta {
+  current?: number | null;
+  metric: string;
+  vs_previous?: number | null;
+  vs_year_ago?: number | null;
+}
+
+/** Average daytime mood per bucket; `days` counts the rated days it covers. */
+export interface MoodBucket {
+  avg_mood: number;
+  bucket: string;
+  days: number;
+}
+
+/** A stored mood rating, as listed by `GET /api/mood`. */
+export interface MoodEntry {
+  date: string;
+  mood: number;
+}
+
+/** Daytime mood / energy rating for a day, posted to `POST /api/mood`. */
+export interface MoodInput {
+  date: string;
+  mood: number;
+}
+
+/** Averages over a group of nights; fields are `None` when no night reported them. */
+export interface NightGroupStats {
+  avg_duration_min?: number | null;
+  avg_latency_min?: number | null;
+  avg_quality?: number | null;
+  avg_wake_feeling?: number | null;
+  nights: number;
+}
+
+/** A stored note, as listed by `GET /api/notes` and `GET /api/starred`. */
+export interface Note {
+  body?: string | null;
+  date: string;
+  id: number;
+  session_id?: number | null;
+  tags?: string[];
+}
+
+/** A note of the week; `excerpt` is the body cut to 200 characters. */
+export interface NoteHighlight {
+  date: string;
+  excerpt: string;
+  starred: boolean;
+}
+
+/** User-provided note associated with a date. */
+export interface NoteInput {
+  body?: string | null;
+  date: string;
+  session_id?: number | null;
+  tags?: string[];
+}
+
+/** The server's view of "now", so clients need not do date math around midnight and DST. */
+export interface NowStatus {
+  local_time: string;
+  now: string;
+  sleep_logged: boolean;
+  timezone: string;
+  utc_offset_min: number;
+  wake_date: string;
+}
+
+/** A pending login email change, as returned by `POST /api/account/email`. */
+export interface PendingEmailChange {
+  expires_at: string;
+  new_email: string;
+}
+
+/** Query parameters for `GET /api/trends/period-compare`. */
+export interface PeriodCompareQuery {
+  a_from?: string | null;
+  a_to?: string | null;
+  b_from?: string | null;
+  b_to?: string | null;
+}
+
+/** Response of `GET /api/trends/period-compare`. */
+export interface PeriodCompareResponse {
+  a: PeriodStats;
+  b: PeriodStats;
+  metrics: PeriodMetricComparison[];
+  warnings: string[];
+}
+
+/** One metric compared between two periods. */
+export interface PeriodMetricComparison {
+  a?: number | null;
+  b?: number | null;
+  diff?: number | null;
+  hint: SignificanceHint;
+  inference: Inference;
+  metric: string;
+}
+
+/** Averages over the logged nights of one period (`None` when nothing was reported). */
+export interface PeriodStats {
+  avg_awakenings?: number | null;
+  avg_duration_min?: number | null;
+  avg_latency_min?: number | null;
+  avg_quality?: number | null;
+  avg_wake_feeling?: number | null;
+  from: string;
+  label: string;
+  nights: number;
+  to: string;
+}
+
+export interface PersonalizationMetrics {
+  day_type_timing_baseline: DayTypeTimingBaselineMetric;
+  duration_baseline: DurationBaselineMetric;
+  quality_factor_ranking: QualityFactorRankingMetric;
+  schedule_variability: ScheduleVariabilityMetric;
+  social_jetlag: SocialJetlagMetric;
+}
+
+/** Query parameters for personalization trends endpoint. */
+export interface PersonalizationQuery {
+  to?: string | null;
+  window_days?: number | null;
+}
+
+export interface PersonalizationResponse {
+  as_of: string;
+  current_window: PersonalizationWindow;
+  metrics: PersonalizationMetrics;
+  prior_window: PersonalizationWindow;
+  recommendations: ActionRecommendation[];
+  window_days: number;
+}
+
+export interface PersonalizationWindow {
+  from: string;
+  logged_days: number;
+  missing_days: number;
+  missing_days_pct: number;
+  to: string;
+}
+
+/** Proposed sleep for the night ending on `wake_date`. */
+export interface PlannedNight {
+  attainable: boolean;
+  bed_at?: string | null;
+  conflicts: number;
+  planned_duration_min: number;
+  shift_min: number;
+  wake_at?: string | null;
+  wake_date: string;
+}
+
+/** A coarse aggregate that may be exposed by `GET /api/public/summary`. */
+export type PublicField = "avg_duration_month" | "avg_quality_month" | "nights_logged_month" | "logging_streak";
+
+/** Response of `GET /api/public/summary`. */
+export interface PublicSummary {
+  avg_duration_min_month?: number | null;
+  avg_quality_month?: number | null;
+  logging_streak?: number | null;
+  month: string;
+  nights_logged_month?: number | null;
+}
+
+/** What the unauthenticated public summary exposes. */
+export interface PublicSummarySettings {
+  enabled: boolean;
+  fields?: PublicField[];
+}
+
+/** Sleep quality score (1..=5). */
+export type Quality = number;
+
+/** Average quality per bucket; `counts[i]` is the number of samples with quality `i + 1`. */
+export interface QualityBucket {
+  avg: number;
+  bucket: string;
+  counts: number[];
+}
+
+export interface QualityFactorRankingMetric {
+  distinct_quality_values: number;
+  eligible: boolean;
+  ranked_factors: RankedQualityFactor[];
+  sessions_with_quality: number;
+  stable_across_adjacent_windows: boolean;
+}
+
+/** Request body for `POST /api/admin/query`. */
+export interface QueryRequest {
+  sql: string;
+}
+
+/** Result of a read-only query. */
+export interface QueryResult {
+  columns: string[];
+  rows: unknown[][];
+  truncated: boolean;
+}
+
+/** Where a value falls relative to the typical range. */
+export type RangePosition = "below" | "within" | "above";
+
+export interface RankedQualityFactor {
+  effect: number;
+  factor: string;
+}
+
+export type RecommendationStatus = "recommended" | "suppressed";
+
+/** How records are coarsened for one [`Audience`]. */
+export interface RedactionPolicy {
+  check_in: boolean;
+  note_bodies: boolean;
+  time_rounding_min: number;
+}
+
+/** Redaction policy per [`Audience`], saved with `POST /api/settings/redaction`. */
+export interface RedactionSettings {
+  export: RedactionPolicy;
+  public: RedactionPolicy;
+  share: RedactionPolicy;
+}
+
+/** How regular bedtimes and wake times are over a range and per ISO week. */
+export interface RegularityResponse {
+  bedtime_mean?: string | null;
+  bedtime_sd_min?: number | null;
+  from: string;
+  nights_logged: number;
+  to: string;
+  waketime_mean?: string | null;
+  waketime_sd_min?: number | null;
+  weeks: RegularityWeek[];
+}
+
+/** Bedtime and wake time regularity of one ISO week (`YYYY-Www`). */
+export interface RegularityWeek {
+  bedtime_mean?: string | null;
+  bedtime_sd_min?: number | null;
+  nights_logged: number;
+  waketime_mean?: string | null;
+  waketime_sd_min?: number | null;
+  week: string;
+}
+
+/** Outcome of the most recent config reload. */
+export interface ReloadStatus {
+  at: string;
+  /** Why the reload failed; previous values stay in effect. */
+  error?: string | null;
+  /** Number of successful reloads since startup. */
+  generation: number;
+  ok: boolean;
+}
+
+/** A pending action. `due_at` (local) and `minutes_until` are set for timed reminders; */
+export interface Reminder {
+  due_at?: string | null;
+  kind: ReminderKind;
+  minutes_until?: number | null;
+}
+
+/** What a [`Reminder`] asks for. */
+export type ReminderKind = "log_sleep" | "check_in" | "bedtime";
+
+/** The configured pre-sleep routine checklist. */
+export interface RoutineChecklist {
+  items: RoutineItem[];
+}
+
+/** Recorded routine item for one evening. */
+export interface RoutineEntry {
+  done: boolean;
+  item_id: string;
+}
+
+/** Body for `POST /api/routine/{date}`: ids of the checklist items completed that evening. */
+export interface RoutineInput {
+  done: string[];
+}
+
+/** One item of the pre-sleep routine checklist. */
+export interface RoutineItem {
+  id: string;
+  label: string;
+}
+
+/** Adherence and outcome comparison for one checklist item. */
+export interface RoutineItemAdherence {
+  adherence_pct?: number | null;
+  done: RoutineOutcome;
+  id: string;
+  label: string;
+  nights_done: number;
+  nights_recorded: number;
+  not_done: RoutineOutcome;
+}
+
+/** Sleep outcomes for the nights following evenings in one group (item done / not done). */
+export interface RoutineOutcome {
+  avg_duration_min?: number | null;
+  avg_quality?: number | null;
+  avg_wake_feeling?: number | null;
+  nights: number;
+}
+
+/** Routine adherence response for an inclusive range of evenings. */
+export interface RoutineTrendsResponse {
+  from: string;
+  items: RoutineItemAdherence[];
+  to: string;
+}
+
+export interface ScheduleVariabilityMetric {
+  current_variability_min?: number | null;
+  eligible: boolean;
+  high_data_gap: boolean;
+  prior_variability_min?: number | null;
+  sustained_two_windows: boolean;
+}
+
+/** Status of a registered schema change, as listed by `GET /api/admin/schema-changes`. */
+export interface SchemaChangeStatus {
+  name: string;
+  new_column: string;
+  old_column: string;
+  pending_rows: number;
+  phase: SchemaPhase;
+  table: string;
+}
+
+/** Column metadata as reported by `pragma_table_info`. */
+export interface SchemaColumn {
+  data_type: string;
+  default_value?: string | null;
+  name: string;
+  not_null: boolean;
+  primary_key: number;
+}
+
+/** Logical description of the live database schema. */
+export interface SchemaDescription {
+  objects: SchemaObject[];
+  schema_version?: number | null;
+}
+
+/** One table or view. */
+export interface SchemaObject {
+  columns: SchemaColumn[];
+  kind: string;
+  name: string;
+  sql?: string | null;
+}
+
+/** Rollout phase of a registered [`ColumnMove`]. Parses from its `snake_case` name. */
+export type SchemaPhase = "dual_write" | "backfilled" | "read_new";
+
+/** Split-sleep statistics per bucket, always computed per wake date. */
+export interface SegmentBucket {
+  avg_longest_min: number;
+  avg_segments: number;
+  avg_total_min: number;
+  bucket: string;
+  split_days: number;
+}
+
+/** A share link as listed by `GET /api/share-links` (the secret is never listed). */
+export interface ShareLink {
+  created_at: string;
+  expired?: boolean;
+  expires_at: string;
+  from_date: string;
+  id: number;
+  last_used_at?: string | null;
+  max_uses?: number | null;
+  to_date: string;
+  use_count: number;
+}
+
+/** Request body of `POST /api/share-links`. */
+export interface ShareLinkInput {
+  expires_in_days?: number | null;
+  from: string;
+  max_uses?: number | null;
+  to: string;
+}
+
+/** Response of `GET /api/shares/{id}/stats`: how often and from where a link was */
+export interface ShareLinkStats {
+  distinct_ips: number;
+  id: number;
+  last_used_at?: string | null;
+  max_uses?: number | null;
+  use_count: number;
+  visitors: ShareLinkVisitor[];
+}
+
+/** One client address that opened a share link, in [`ShareLinkStats::visitors`]. */
+export interface ShareLinkVisitor {
+  first_used_at: string;
+  ip: string;
+  last_used_at: string;
+  use_count: number;
+}
+
+/** Response of `GET /api/shared/{secret}`: the records of the shared range, redacted */
+export interface SharedView {
+  exercise: ExerciseEvent[];
+  from: string;
+  notes: Note[];
+  sleep: SleepListItem[];
+  to: string;
+}
+
+/** State of the manifest signature found by [`verify_export`] and [`compare_export`]. */
+export type SignatureStatus = "valid" | "invalid" | "unsigned" | "unchecked";
+
+/** Plain-language reading of an [`Inference`], for badges next to a difference. */
+export type SignificanceHint = "likely" | "possible" | "unlikely" | "insufficient";
+
+/** Bar data point for per-day sleep: local bed/wake times, optional quality/duration. */
+export interface SleepBar {
+  bed_time: string;
+  date: string;
+  duration_hours?: number | null;
+  duration_min?: number | null;
+  quality?: number | null;
+  wake_time: string;
+}
+
+/** One day of `GET /api/trends/sleep-debt`. */
+export interface SleepDebtDay {
+  balance_min: number;
+  date: string;
+  delta_min?: number | null;
+  duration_min?: number | null;
+}
+
+/** Cumulative sleep deficit and surplus against the sleep goal over a range. */
+export interface SleepDebtResponse {
+  balance_min: number;
+  days: SleepDebtDay[];
+  deficit_min: number;
+  from: string;
+  nights_logged: number;
+  surplus_min: number;
+  target_duration_min: number;
+  to: string;
+}
+
+/** Personal sleep goal used for bedtime nudges and sleep debt. */
+export interface SleepGoal {
+  target_bedtime: string;
+  target_duration_min: number;
+}
+
+/** Per-row report of `POST /api/import/sleep`. */
+export interface SleepImportReport {
+  dry_run: boolean;
+  errors: ImportIssue[];
+  imported: number;
+  skipped: number;
+  total_rows: number;
+}
+
+/** User-provided input for creating or updating a sleep session. */
+export interface SleepInput {
+  aids?: string[];
+  awakenings: number;
+  bed_time: string;
+  date: string;
+  latency_min: number;
+  quality: Quality;
+  sleep_inertia_min?: number | null;
+  tags?: string[];
+  timezone?: string | null;
+  wake_feeling?: number | null;
+  wake_time: string;
+  waso_min?: number | null;
+}
+
+/** List item projection for sleep summaries and sessions. */
+export interface SleepListItem {
+  awakenings: number;
+  bed_time: string;
+  date: string;
+  duration_hours?: number | null;
+  duration_min?: number | null;
+  efficiency?: number | null;
+  id: number;
+  latency_min: number;
+  quality: number;
+  sleep_inertia_min?: number | null;
+  wake_feeling?: number | null;
+  wake_time: string;
+  waso_min?: number | null;
+}
+
+/** Partial update for `PATCH /api/sleep/{id}`; every field is optional. */
+export interface SleepPatch {
+  aids?: string[] | null;
+  awakenings?: number | null;
+  bed_time?: string | null;
+  date?: string | null;
+  latency_min?: number | null;
+  quality?: Quality | null;
+  sleep_inertia_min?: number | null;
+  tags?: string[] | null;
+  timezone?: string | null;
+  wake_feeling?: number | null;
+  wake_time?: string | null;
+  waso_min?: number | null;
+}
+
+/** Database projection of a stored sleep session. */
+export interface SleepSession {
+  aids?: string[];
+  awakenings: number;
+  bed_time: string;
+  date: string;
+  efficiency?: number | null;
+  external_refs?: ExternalRef[];
+  id: number;
+  latency_min: number;
+  notes?: Note[];
+  quality: number;
+  sleep_inertia_min?: number | null;
+  starred?: boolean;
+  tags?: string[];
+  timezone?: string | null;
+  wake_feeling?: number | null;
+  wake_time: string;
+  waso_min?: number | null;
+}
+
+/** Optional request body of `POST /api/sleep/stop`: the fields of [`SleepInput`] that */
+export interface SleepTimerStop {
+  awakenings?: number | null;
+  latency_min?: number | null;
+  quality?: Quality | null;
+}
+
+export interface SocialJetlagMetric {
+  current_delta_min?: number | null;
+  eligible: boolean;
+  prior_delta_min?: number | null;
+  sustained_two_windows: boolean;
+  weekend_sample_days: number;
+}
+
+/** Starred records, as returned by `GET /api/starred`. */
+export interface Starred {
+  notes: Note[];
+  sleep: SleepListItem[];
+}
+
+/** Current streaks ending at `as_of`. */
+export interface Streaks {
+  complete: number;
+  logging: number;
+  longest_complete: number;
+}
+
+/** Aggregated trends response combining duration, quality, latency, wake feeling, and segment buckets. */
+export interface SummaryResponse {
+  duration_by_bucket: DurationBucket[];
+  efficiency_by_bucket: EfficiencyBucket[];
+  goal_adherence_pct?: number | null;
+  latency_by_bucket: LatencyBucket[];
+  medication_by_bucket: MedicationBucket[];
+  mood_by_bucket: MoodBucket[];
+  per: string;
+  quality_by_bucket: QualityBucket[];
+  segments_by_bucket: SegmentBucket[];
+  wake_feeling_by_bucket: WakeFeelingBucket[];
+}
+
+/** Tagged-vs-untagged comparison for one tag; fields as in [`AidEffect`]. */
+export interface TagEffect {
+  duration_diff_min?: number | null;
+  inference: Record<string, Inference>;
+  latency_diff_min?: number | null;
+  quality_diff?: number | null;
+  sufficient_sample: boolean;
+  tag: string;
+  wake_feeling_diff?: number | null;
+  with_tag: NightGroupStats;
+  without_tag: NightGroupStats;
+}
+
+/** Tag comparison response. */
+export interface TagsResponse {
+  from: string;
+  min_samples: number;
+  tags: TagEffect[];
+  to: string;
+}
+
+/** Granularity of bed and wake times in trends and exports, saved with */
+export interface TimeRounding {
+  step_min: number;
+}
+
+/** The logical day for the current time. */
+export interface TodayStatus {
+  date: string;
+  day_start: string;
+  local_time: string;
+  timezone: string;
+}
+
+/** Response of `GET /api/version`: the crate version, the active feature flags, and the */
+export interface VersionInfo {
+  config_reload?: ReloadStatus | null;
+  features: Features;
+  version: string;
+}
+
+/** Average wake feeling and sleep inertia per bucket. */
+export interface WakeFeelingBucket {
+  avg_feeling?: number | null;
+  avg_inertia_min?: number | null;
+  bucket: string;
+  days_reported: number;
+}
+
+/** `current` minus `prior` for each metric; `None` when either side has no value. */
+export interface WeekChange {
+  avg_duration_min?: number | null;
+  avg_latency_min?: number | null;
+  avg_quality?: number | null;
+  bedtime_sd_min?: number | null;
+  exercise_days: number;
+  nights_logged: number;
+}
+
+/** Response of `GET /api/plan/week`: the goal and one proposal per upcoming night, */
+export interface WeekPlan {
+  as_of: string;
+  nights: PlannedNight[];
+  target_bedtime: string;
+  target_duration_min: number;
+}
+
+/** Aggregates of one ISO week; averages are `None` without logged nights, and */
+export interface WeekStats {
+  avg_duration_min?: number | null;
+  avg_latency_min?: number | null;
+  avg_quality?: number | null;
+  bedtime_sd_min?: number | null;
+  exercise_days: number;
+  from: string;
+  nights_logged: number;
+  to: string;
+  week: string;
+}
+
+/** Average deviation from trend on one weekday (`Mon`..`Sun`). */
+export interface WeekdayEffect {
+  effect?: number | null;
+  samples: number;
+  weekday: string;
+}
+
+/** Response of `GET /api/reports/weekly`. */
+export interface WeeklyReportResponse {
+  change: WeekChange;
+  current: WeekStats;
+  highlights: NoteHighlight[];
+  prior: WeekStats;
+}
@@ -4,6 +4,8 @@
  * - Attach X-CSRF-Token for mutating requests by mirroring CSRF cookie
  */
 
+import type { Attachment, Dashboard, SleepInput, SleepPatch } from './api-types.gen';
+
 export type Json = Record<string, unknown> | unknown[];
 
 function isBrowser(): boolean {
@@ -171,17 +173,16 @@ export interface SleepListItem {
   awakenings: number;
   quality: number;
   duration_min: number | null;
+  wake_feeling?: number | null;
+  sleep_inertia_min?: number | null;
+  waso_min?: number | null;
+  efficiency?: number | null;
   session_count?: number | null;
 }
 
-export interface SleepInput {
-  date: IsoDate;
-  bed_time: IsoTime;
-  wake_time: IsoTime;
-  latency_min: number;
-  awakenings: number;
-  quality: number;
-}
+// Request bodies come from the Rust models; regenerate with `sleepctl gen-types`.
+// See api-types.gen.ts for the full set.
+export type { Attachment, Dashboard, SleepInput, SleepPatch };
 
 export interface SleepSession extends SleepInput {
   id: number;
@@ -189,6 +190,19 @@ export interface SleepSession extends SleepInput {
   session_date?: IsoDate | null;
 }
 
+export type DisturbanceType = 'noise' | 'partner' | 'pet' | 'child';
+
+export interface DisturbanceInput {
+  date: IsoDate;
+  time: IsoTime;
+  type: DisturbanceType;
+  duration_min: number;
+}
+
+export interface Disturbance extends DisturbanceInput {
+  id: number;
+}
+
 export interface ExerciseUpsert {
   date: IsoDate;
   intensity: 'none' | 'light' | 'hard';
@@ -350,16 +364,29 @@ export interface TrendsLatencyBucket {
   median: number;
 }
 
+export interface TrendsSegmentBucket {
+  bucket: string;
+  avg_segments: number;
+  avg_total_min: number;
+  avg_longest_min: number;
+  split_days: number;
+}
+
+export type TrendsSummaryPer = 'day' | 'segment';
+
 export interface TrendsSummaryResponse {
+  per?: TrendsSummaryPer;
   duration_by_bucket: TrendsDurationBucket[];
   quality_by_bucket: TrendsQualityBucket[];
   latency_by_bucket: TrendsLatencyBucket[];
+  segments_by_bucket?: TrendsSegmentBucket[];
 }
 
 export interface TrendsSummaryQuery {
   from: IsoDate;
   to: IsoDate;
   bucket?: TrendsSummaryBucket;
+  per?: TrendsSummaryPer;
 }
 
 export interface DurationWarningBounds {
@@ -475,6 +502,50 @@ export async function updateSleep(id: number, input: SleepInput): Promise<void>
   await apiPut<void>(`/api/sleep/${id}`, input as unknown as Json);
 }
 
+/** Change only the given fields (e.g. `{ quality: 4 }`); returns the updated session. */
+export async function patchSleep(id: number, patch: SleepPatch): Promise<SleepSession> {
+  const res = await apiFetch(`/api/sleep/${id}`, {
+    method: 'PATCH',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify(patch)
+  });
+  if (!res.ok) {
+    throw new Error(`PATCH /api/sleep/${id} failed: ${res.status}`);
+  }
+  return (await res.json()) as SleepSession;
+}
+
+/** Download URL of the full data export (`from` / `to` are optional bounds). */
+export function exportUrl(format: 'json' | 'csv', from?: IsoDate, to?: IsoDate): string {
+  const params = new URLSearchParams({ format });
+  if (from) params.set('from', from);
+  if (to) params.set('to', to);
+  return `/api/export?${params.toString()}`;
+}
+
+export async function listAttachments(date: IsoDate): Promise<Attachment[]> {
+  return apiGet<Attachment[]>(`/api/attachments?date=${encodeURIComponent(date)}`);
+}
+
+/** Upload a photo or audio memo for `date`; the server extracts its thumbnail or duration. */
+export async function uploadAttachment(date: IsoDate, file: File): Promise<Attachment> {
+  const query = `date=${encodeURIComponent(date)}&filename=${encodeURIComponent(file.name)}`;
+  const res = await apiFetch(`/api/attachments?${query}`, {
+    method: 'POST',
+    headers: { 'Content-Type': file.type || 'application/octet-stream' },
+    body: file
+  });
+  if (!res.ok) {
+    throw new Error(`POST /api/attachments failed: ${res.status}`);
+  }
+  return (await res.json()) as Attachment;
+}
+
+/** Preview image URL, or null when the server made no thumbnail for this attachment. */
+export function attachmentThumbUrl(attachment: Attachment): string | null {
+  return attachment.has_thumb ? `/api/attachment/${attachment.id}/thumb` : null;
+}
+
 export async function deleteSleep(id: number): Promise<void> {
   await apiDelete(`/api/sleep/${id}`);
 }
@@ -503,8 +574,8 @@ export async function upsertExercise(payload: ExerciseUpsert): Promise<{ id: num
   return apiPost<{ id: number }>('/api/exercise', payload as unknown as Json);
 }
 
-export async function postFrictionTelemetry(payload: FrictionTelemetryInput): Promise<{ id: number }> {
-  return apiPost<{ id: number }>('/api/personalization/friction-telemetry', payload as unknown as Json);
+export async function postFrictionTelemetry(payload: FrictionTelemetryInput): Promise<{ id: number | null }> {
+  return apiPost<{ id: number | null }>('/api/personalization/friction-telemetry', payload as unknown as Json);
 }
 
 export interface FrictionBacklogQuery {
@@ -533,10 +604,50 @@ export async function getPersonalization(query: PersonalizationQuery = {}): Prom
   return apiGet<PersonalizationResponse>(`/api/trends/personalization${qs ? `?${qs}` : ''}`);
 }
 
+export interface SleepGoal {
+  target_bedtime: IsoTime;
+  target_duration_min: number;
+}
+
+export interface BedtimeStatus {
+  timezone: string;
+  local_time: string;
+  target_bedtime: IsoTime;
+  target_duration_min: number;
+  bedtime_at: string;
+  minutes_until_bedtime: number;
+  sleep_debt_min: number;
+  debt_window_days: number;
+  nights_logged: number;
+  recommendation: string;
+}
+
+export async function getBedtimeStatus(now?: string): Promise<BedtimeStatus> {
+  const qs = now ? `?now=${encodeURIComponent(now)}` : '';
+  return apiGet<BedtimeStatus>(`/api/now/bedtime-status${qs}`);
+}
+
+export interface TodayStatus {
+  timezone: string;
+  local_time: string;
+  day_start: string;
+  date: IsoDate;
+}
+
+export async function getToday(now?: string): Promise<TodayStatus> {
+  const qs = now ? `?now=${encodeURIComponent(now)}` : '';
+  return apiGet<TodayStatus>(`/api/now/today${qs}`);
+}
+
+export async function getDashboard(days = 30): Promise<Dashboard> {
+  return apiGet<Dashboard>(`/api/dashboard?days=${days}`);
+}
+
 export async function getTrendsSummary(query: TrendsSummaryQuery): Promise<TrendsSummaryResponse> {
   const search = new URLSearchParams();
   search.set('from', query.from);
   search.set('to', query.to);
   if (query.bucket) search.set('bucket', query.bucket);
+  if (query.per) search.set('per', query.per);
   return apiGet<TrendsSummaryResponse>(`/api/trends/summary?${search.toString()}`);
 }
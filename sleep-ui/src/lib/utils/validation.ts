@@ -0,0 +1,71 @@
+import { browser } from '$app/environment';
+import type { SleepInput } from '$lib/api';
+import { computeDurationMin } from '$lib/utils/sleep';
+
+/**
+ * Bindings of the `sleep-wasm` crate (built with `just wasm` into `static/wasm`), which runs the
+ * server's own validation and DST-aware duration code in the browser.
+ */
+export interface SleepWasm {
+  default: (init?: unknown) => Promise<unknown>;
+  validate_sleep_input(inputJson: string, tz: string): string | undefined;
+  compute_duration_min(
+    date: string,
+    bedTime: string,
+    wakeTime: string,
+    tz: string,
+    sessionTz?: string | null
+  ): number;
+}
+
+const WASM_URL = '/wasm/sleep_wasm.js';
+
+let loading: Promise<SleepWasm | null> | null = null;
+
+/** Load the WASM module once; resolves to null when it was not built or cannot be loaded. */
+export function loadSleepWasm(): Promise<SleepWasm | null> {
+  if (!browser) return Promise.resolve(null);
+  loading ??= import(/* @vite-ignore */ WASM_URL)
+    .then(async (mod: SleepWasm) => {
+      await mod.default();
+      return mod;
+    })
+    .catch(() => null);
+  return loading;
+}
+
+/** Test hook: replace (or with null, reset) the loaded module. */
+export function setSleepWasm(mod: SleepWasm | null): void {
+  loading = mod ? Promise.resolve(mod) : null;
+}
+
+export function browserTimezone(): string {
+  return Intl.DateTimeFormat().resolvedOptions().timeZone || 'UTC';
+}
+
+/**
+ * The error the server would answer `input` with, or null when it would be accepted.
+ * Without the WASM module nothing is checked client-side and the server's response is shown.
+ */
+export async function validateSleepInput(input: SleepInput, tz = browserTimezone()): Promise<string | null> {
+  const wasm = await loadSleepWasm();
+  if (!wasm) return null;
+  return wasm.validate_sleep_input(JSON.stringify(input), tz) ?? null;
+}
+
+/**
+ * Duration the server will store for `input`, including DST shifts in its own `timezone` or else
+ * `tz`. Falls back to the wall-clock difference when the WASM module is unavailable or rejects
+ * the input.
+ */
+export async function sleepDurationMin(input: SleepInput, tz = browserTimezone()): Promise<number> {
+  const wasm = await loadSleepWasm();
+  if (wasm) {
+    try {
+      return wasm.compute_duration_min(input.date, input.bed_time, input.wake_time, tz, input.timezone);
+    } catch {
+      // Invalid input: the server will report it; show the naive duration meanwhile.
+    }
+  }
+  return computeDurationMin(input.bed_time, input.wake_time);
+}
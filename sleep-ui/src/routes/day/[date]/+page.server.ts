@@ -9,6 +9,14 @@ type SleepSession = {
   duration_min: number | null;
 };
 
+type Disturbance = {
+  id: number;
+  date: string; // YYYY-MM-DD (wake date)
+  time: string;
+  type: 'noise' | 'partner' | 'pet' | 'child';
+  duration_min: number;
+};
+
 export const load = async ({ fetch, params }: any) => {
   const date = params.date as string;
   let items: SleepSession[] = [];
@@ -23,5 +31,17 @@ export const load = async ({ fetch, params }: any) => {
   } catch {
     // ignore
   }
-  return { date, items };
+  let disturbances: Disturbance[] = [];
+  try {
+    const res = await fetch(`/api/disturbances?from=${date}&to=${date}`);
+    if (res.ok) {
+      const data = await res.json();
+      if (Array.isArray(data)) {
+        disturbances = data as Disturbance[];
+      }
+    }
+  } catch {
+    // ignore
+  }
+  return { date, items, disturbances };
 };
@@ -0,0 +1,60 @@
+import { afterEach, describe, expect, it, vi } from 'vitest';
+import type { SleepInput } from '../../src/lib/api';
+import { setSleepWasm, sleepDurationMin, validateSleepInput, type SleepWasm } from '../../src/lib/utils/validation';
+
+const input: SleepInput = {
+  date: '2025-03-09',
+  bed_time: '23:00:00',
+  wake_time: '07:00:00',
+  latency_min: 10,
+  awakenings: 1,
+  quality: 4
+};
+
+function fakeWasm(overrides: Partial<SleepWasm> = {}): SleepWasm {
+  return {
+    default: async () => undefined,
+    validate_sleep_input: () => undefined,
+    compute_duration_min: () => 420,
+    ...overrides
+  };
+}
+
+describe('sleep validation', () => {
+  afterEach(() => setSleepWasm(null));
+
+  it('reports the module message and passes the JSON and timezone through', async () => {
+    const validate = vi.fn(() => 'quality must be between 1 and 5');
+    setSleepWasm(fakeWasm({ validate_sleep_input: validate }));
+    expect(await validateSleepInput(input, 'Asia/Tokyo')).toBe('quality must be between 1 and 5');
+    expect(validate).toHaveBeenCalledWith(JSON.stringify(input), 'Asia/Tokyo');
+  });
+
+  it('treats an accepted input as valid', async () => {
+    setSleepWasm(fakeWasm());
+    expect(await validateSleepInput(input, 'UTC')).toBeNull();
+  });
+
+  it('uses the DST-aware duration from the module', async () => {
+    setSleepWasm(fakeWasm());
+    expect(await sleepDurationMin(input, 'America/New_York')).toBe(420);
+  });
+
+  it("passes the session's own timezone through", async () => {
+    const compute = vi.fn(() => 420);
+    setSleepWasm(fakeWasm({ compute_duration_min: compute }));
+    await sleepDurationMin({ ...input, timezone: 'America/New_York' }, 'Asia/Tokyo');
+    expect(compute).toHaveBeenCalledWith('2025-03-09', '23:00:00', '07:00:00', 'Asia/Tokyo', 'America/New_York');
+  });
+
+  it('falls back to the wall-clock duration when the module rejects the input', async () => {
+    setSleepWasm(
+      fakeWasm({
+        compute_duration_min: () => {
+          throw new Error('Duration must be positive');
+        }
+      })
+    );
+    expect(await sleepDurationMin(input, 'UTC')).toBe(480);
+  });
+});
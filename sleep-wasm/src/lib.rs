@@ -0,0 +1,236 @@
+#![doc = r#"
+SleepTracker validation for the browser
+
+A `wasm-bindgen` build of the [`sleep_core`] checks the API runs when a sleep session is
+created or updated, so the SvelteKit form shows exactly the errors and duration the server
+will produce. Build it with `just wasm` (`wasm-pack build sleep-wasm --target web`); the UI
+loads the package from `/wasm/sleep_wasm.js` and falls back to its TypeScript helpers when
+the package is missing.
+
+Exports:
+- [`validate_sleep_input`] — `SleepInput` JSON in, the server's error message (or
+  `undefined`) out.
+- [`compute_duration_min`] — the DST-aware duration the server stores.
+
+Both functions take the IANA timezone name the server uses for the account (see
+`GET /api/settings/timezone`); a session's own `timezone` takes precedence over it, as on the
+server.
+
+The functions are plain Rust as well, so they are tested natively with `cargo test`.
+"#]
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use sleep_core::models::SleepInput;
+use sleep_core::time;
+use wasm_bindgen::prelude::*;
+
+#[doc = r##"Validate a `SleepInput` JSON document as `POST /api/sleep` does.
+
+Runs the same steps as the server before it touches the database: field validation, the
+bed/wake window and the duration in the input's `timezone`, or `tz` when it has none. Returns `None` when the input would be accepted,
+otherwise the message the API puts in its `400` response. Values the API already rejects while
+parsing the body (e.g. a quality of 9, answered with `422`) yield the parser's message without
+its line/column suffix. Overlaps with other sessions need
+the database and are still only reported by the server.
+
+# Example
+
+```rust
+use sleep_wasm::validate_sleep_input;
+
+let ok = r#"{"date":"2025-06-02","bed_time":"23:00","wake_time":"07:00",
+             "latency_min":10,"awakenings":1,"quality":4}"#;
+assert_eq!(validate_sleep_input(ok, "Asia/Tokyo"), None);
+
+let bad = ok.replace(r#""quality":4"#, r#""quality":9"#);
+assert_eq!(
+    validate_sleep_input(&bad, "Asia/Tokyo").as_deref(),
+    Some("quality must be between 1 and 5")
+);
+```
+"##]
+#[wasm_bindgen]
+pub fn validate_sleep_input(input_json: &str, tz: &str) -> Option<String> {
+    let tz = match parse_tz(tz) {
+        Ok(tz) => tz,
+        Err(e) => return Some(e),
+    };
+    let input: SleepInput = match serde_json::from_str(input_json) {
+        Ok(input) => input,
+        Err(e) => return Some(without_position(e)),
+    };
+    let tz = input.tz().unwrap_or(tz);
+    input
+        .validate()
+        .and_then(|_| time::sleep_window_bounds(input.date, input.bed_time, input.wake_time))
+        .and_then(|_| time::compute_duration_min(input.date, input.bed_time, input.wake_time, tz))
+        .err()
+        .map(|e| e.to_string())
+}
+
+#[doc = r#"Duration in minutes of a night waking on `date` (`YYYY-MM-DD`) in `session_tz`, or in
+`tz` when the session has no timezone of its own (`undefined`, `null` or empty).
+
+Times accept the same formats as the API (`HH:MM`, `HH:MM:SS`, `h:mm AM/PM`); a bed time
+after the wake time is on the previous day. DST transitions are accounted for exactly as on
+the server. In JavaScript an error is thrown with the server's message.
+
+# Example
+
+```rust
+use sleep_wasm::compute_duration_min;
+
+assert_eq!(compute_duration_min("2025-06-02", "23:00", "07:00", "Asia/Tokyo", None), Ok(480));
+// The night the clocks go forward in New York is an hour shorter.
+assert_eq!(
+    compute_duration_min("2025-03-09", "23:00", "07:00", "America/New_York", None),
+    Ok(420)
+);
+// A session recorded in New York by a Tokyo account.
+assert_eq!(
+    compute_duration_min("2025-03-09", "23:00", "07:00", "Asia/Tokyo", Some("America/New_York".into())),
+    Ok(420)
+);
+```
+"#]
+#[wasm_bindgen]
+pub fn compute_duration_min(
+    date: &str,
+    bed_time: &str,
+    wake_time: &str,
+    tz: &str,
+    session_tz: Option<String>,
+) -> Result<i32, String> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("invalid date {date:?}: expected YYYY-MM-DD"))?;
+    let bed = time::parse_flexible_time(bed_time)?;
+    let wake = time::parse_flexible_time(wake_time)?;
+    let tz = match session_tz
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        Some(session_tz) => parse_tz(session_tz)?,
+        None => parse_tz(tz)?,
+    };
+    time::compute_duration_min(date, bed, wake, tz).map_err(|e| e.to_string())
+}
+
+/// A serde_json error without the ` at line L column C` suffix, which means nothing to a form.
+fn without_position(e: serde_json::Error) -> String {
+    let msg = e.to_string();
+    match msg.rsplit_once(" at line ") {
+        Some((head, _)) if e.line() > 0 => head.to_string(),
+        _ => msg,
+    }
+}
+
+fn parse_tz(tz: &str) -> Result<Tz, String> {
+    tz.parse::<Tz>()
+        .map_err(|_| format!("unknown timezone {tz:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(bed: &str, wake: &str, quality: i32) -> String {
+        format!(
+            r#"{{"date":"2025-06-02","bed_time":"{bed}","wake_time":"{wake}","latency_min":10,"awakenings":1,"quality":{quality}}}"#
+        )
+    }
+
+    #[test]
+    fn accepts_what_the_server_accepts() {
+        assert_eq!(
+            validate_sleep_input(&input("23:00", "07:00", 4), "UTC"),
+            None
+        );
+        assert_eq!(
+            validate_sleep_input(&input("11:00 PM", "7:00 AM", 4), "UTC"),
+            None
+        );
+    }
+
+    #[test]
+    fn reports_the_server_messages() {
+        assert_eq!(
+            validate_sleep_input(&input("23:00", "07:00", 0), "UTC").as_deref(),
+            Some("quality must be between 1 and 5")
+        );
+        assert_eq!(
+            validate_sleep_input(&input("07:00", "07:00", 3), "UTC").as_deref(),
+            Some("Duration must be positive")
+        );
+        assert_eq!(
+            validate_sleep_input(&input("23:00", "07:00", 3), "Mars/Olympus").as_deref(),
+            Some("unknown timezone \"Mars/Olympus\"")
+        );
+        assert!(validate_sleep_input("{}", "UTC").is_some());
+    }
+
+    #[test]
+    fn duration_matches_core() {
+        assert_eq!(
+            compute_duration_min("2025-06-02", "22:30", "06:15:00", "UTC", None),
+            Ok(465)
+        );
+        // Clocks go back in Berlin on 2025-10-26: the night is an hour longer.
+        assert_eq!(
+            compute_duration_min("2025-10-26", "23:00", "07:00", "Europe/Berlin", None),
+            Ok(540)
+        );
+        assert!(compute_duration_min("2025-13-01", "23:00", "07:00", "UTC", None).is_err());
+        assert!(compute_duration_min("2025-06-02", "25:00", "07:00", "UTC", None).is_err());
+    }
+
+    #[test]
+    fn session_timezone_overrides_user_timezone() {
+        // Spring forward in New York: 420 minutes there, 480 in Tokyo.
+        let night = |timezone: &str| {
+            format!(
+                r#"{{"date":"2025-03-09","bed_time":"23:00","wake_time":"07:00","latency_min":10,"awakenings":1,"quality":4,"timezone":"{timezone}"}}"#
+            )
+        };
+        assert_eq!(
+            validate_sleep_input(&night("America/New_York"), "Asia/Tokyo"),
+            None
+        );
+        assert_eq!(
+            compute_duration_min(
+                "2025-03-09",
+                "23:00",
+                "07:00",
+                "Asia/Tokyo",
+                Some("America/New_York".into())
+            ),
+            Ok(420)
+        );
+        assert_eq!(
+            compute_duration_min(
+                "2025-03-09",
+                "23:00",
+                "07:00",
+                "America/New_York",
+                Some("Asia/Tokyo".into())
+            ),
+            Ok(480)
+        );
+        assert_eq!(
+            compute_duration_min(
+                "2025-03-09",
+                "23:00",
+                "07:00",
+                "Asia/Tokyo",
+                Some(String::new())
+            ),
+            Ok(480)
+        );
+        assert!(
+            validate_sleep_input(&night("Mars/Olympus"), "UTC")
+                .unwrap()
+                .contains("timezone")
+        );
+    }
+}